@@ -0,0 +1,90 @@
+//! Where an agent's REPL runs (`agent run --backend`)
+//!
+//! `tmux` gives each agent a window in the `proj:{project}` session; `process` runs the provider
+//! as a detached background process, for machines without tmux. Both sit behind `AgentBackend`,
+//! which `agent run`, `agent stop` and `agent attach` drive.
+
+pub mod tmux;
+pub mod process;
+
+pub use tmux::TmuxBackend;
+pub use process::{ProcessBackend, ingest_args, ingest_lines, process_start_time, INGEST_COMMAND};
+
+use crate::utils::errors::CliError;
+
+/// The agent a backend operation applies to
+#[derive(Debug, Clone, Copy)]
+pub struct AgentTarget<'a> {
+    pub project: &'a str,
+    pub role: &'a str,
+    pub agent: &'a str,
+}
+
+impl AgentTarget<'_> {
+    /// tmux session shared by the project's agents
+    pub fn session_name(&self) -> String {
+        format!("proj:{}", self.project)
+    }
+
+    /// tmux window of this agent: its name alone, unique in the project, so agents sharing or
+    /// renaming a role never collide
+    pub fn window_name(&self) -> String {
+        self.agent.to_string()
+    }
+
+    /// `{role}:{agent}` window name of agents started before windows were named by agent alone
+    pub fn legacy_window_name(&self) -> String {
+        format!("{}:{}", self.role, self.agent)
+    }
+
+    /// This agent's window among a session's `windows`: the current name first, then the legacy
+    /// `{role}:{agent}` name, then a legacy window from before the agent's role was renamed
+    pub fn find_window(&self, windows: &[String]) -> Option<String> {
+        [self.window_name(), self.legacy_window_name()].into_iter()
+            .find(|name| windows.contains(name))
+            .or_else(|| windows.iter().find(|w| w.split_once(':').is_some_and(|(_, agent)| agent == self.agent)).cloned())
+    }
+}
+
+/// How to launch an agent's REPL
+#[derive(Debug, Clone, Default)]
+pub struct LaunchSpec {
+    pub provider: String,
+    pub cmd: String,
+    pub args: Vec<String>,
+    pub workdir: Option<String>,
+    /// NDJSON file receiving the REPL output; `None` with `--no-logs`
+    pub log_file: Option<String>,
+}
+
+/// Outcome of `AgentBackend::start`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Started {
+    /// The agent was already running; nothing was launched
+    AlreadyRunning { pid: Option<u32> },
+    /// A new REPL was launched; `pid` is only known to the process backend
+    Launched { pid: Option<u32> },
+}
+
+/// A place agent REPLs can run
+pub trait AgentBackend {
+    /// `tmux` or `process`
+    fn name(&self) -> &'static str;
+
+    /// Whether this backend can run on this machine
+    fn is_available(&self) -> bool;
+
+    /// Where the agent runs, for messages (`tmux session 'proj:demo'`, `background process 4242`)
+    fn describe(&self, target: &AgentTarget) -> String;
+
+    fn is_running(&self, target: &AgentTarget) -> Result<bool, CliError>;
+
+    fn start(&self, target: &AgentTarget, spec: &LaunchSpec) -> Result<Started, CliError>;
+
+    /// Stop the agent; `false` when it was not running
+    fn stop(&self, target: &AgentTarget) -> Result<bool, CliError>;
+
+    /// Hand the terminal to the agent (`read_only`: observe without typing into it), or print how to
+    /// follow it when that is not possible
+    fn attach(&self, target: &AgentTarget, log_file: &str, read_only: bool) -> Result<(), CliError>;
+}
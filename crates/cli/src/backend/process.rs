@@ -0,0 +1,250 @@
+//! Process backend: the provider REPL runs as a detached background process, without tmux
+//!
+//! The child gets its own process group, so `stop` signals it together with the log ingester.
+//! Its PID and start time are recorded in the metadata of an active REPL session row
+//! (`{"backend":"process","pid":...,"start_time":...}`), which is how later `stop`/`attach` calls
+//! find it; a PID whose start time no longer matches was reused and is left alone.
+//! stdout and stderr are piped into a hidden `__ingest-log` run of this binary, which writes
+//! them to the agent's NDJSON file as `stdout_line` events.
+
+use std::io::{self, BufRead};
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use rusqlite::Connection;
+use db::{
+    find_agent_by_name, find_project_id, get_metadata_value, insert_repl_session, list_sessions, open_or_create_db,
+    set_session_metadata, update_session, IdOrName, Session, SessionFilters, SessionStatus, SessionType,
+};
+use crate::logging::{flush_path, redaction_patterns, write_ndjson_event, NdjsonEvent};
+use crate::utils::errors::{CliError, ProviderErrorKind};
+use crate::utils::{is_process_running, max_line_bytes, read_capped_line};
+use super::{AgentBackend, AgentTarget, LaunchSpec, Started};
+
+/// Hidden subcommand that turns a REPL's output into NDJSON lines
+pub const INGEST_COMMAND: &str = "__ingest-log";
+
+/// How long `stop` waits after SIGTERM before sending SIGKILL
+const STOP_GRACE: Duration = Duration::from_secs(2);
+
+pub struct ProcessBackend {
+    db_path: String,
+    /// Binary run as `__ingest-log`; `None` leaves the output unlogged
+    ingest_exe: Option<PathBuf>,
+}
+
+impl ProcessBackend {
+    /// Backend recording its processes in the database at `db_path`, logging through this binary
+    pub fn new(db_path: impl Into<String>) -> Self {
+        Self { db_path: db_path.into(), ingest_exe: std::env::current_exe().ok() }
+    }
+
+    /// Use `exe` (or nothing) as the log ingester
+    pub fn with_ingest_exe(mut self, exe: Option<PathBuf>) -> Self {
+        self.ingest_exe = exe;
+        self
+    }
+
+    /// Connection plus the project and agent ids; `None` when the agent is not in the database
+    fn lookup(&self, target: &AgentTarget) -> Result<Option<(Connection, String, String)>, CliError> {
+        let conn = open_or_create_db(&self.db_path)?;
+        let Some(project_id) = find_project_id(&conn, IdOrName::Name(target.project))? else { return Ok(None) };
+        let Some(agent) = find_agent_by_name(&conn, &project_id, target.agent)? else { return Ok(None) };
+        Ok(Some((conn, project_id, agent.id)))
+    }
+
+    /// Session row and PID of the agent's live background process; records of dead processes are expired on the way
+    pub fn running_process(&self, target: &AgentTarget) -> Result<Option<(Session, u32)>, CliError> {
+        let Some((conn, project_id, agent_id)) = self.lookup(target)? else { return Ok(None) };
+        let sessions = list_sessions(&conn, SessionFilters {
+            project_id: Some(project_id),
+            agent_id: Some(agent_id),
+            provider: None,
+            status: Some(SessionStatus::Active),
+            session_type: Some(SessionType::Repl),
+            limit: None,
+            offset: None,
+        })?;
+        for session in sessions {
+            if get_metadata_value(&session, "backend").ok().flatten().and_then(|v| v.as_str().map(str::to_string)).as_deref() != Some("process") {
+                continue;
+            }
+            let pid = get_metadata_value(&session, "pid").ok().flatten().and_then(|v| v.as_u64()).map(|p| p as u32);
+            match pid {
+                Some(pid) if is_recorded_process(&session, pid) => return Ok(Some((session, pid))),
+                _ => update_session(&conn, &session.id, None, None, Some(SessionStatus::Expired))?,
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl AgentBackend for ProcessBackend {
+    fn name(&self) -> &'static str {
+        "process"
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    fn describe(&self, target: &AgentTarget) -> String {
+        match self.running_process(target) {
+            Ok(Some((_, pid))) => format!("background process {}", pid),
+            _ => "background process".to_string(),
+        }
+    }
+
+    fn is_running(&self, target: &AgentTarget) -> Result<bool, CliError> {
+        Ok(self.running_process(target)?.is_some())
+    }
+
+    fn start(&self, target: &AgentTarget, spec: &LaunchSpec) -> Result<Started, CliError> {
+        if let Some((_, pid)) = self.running_process(target)? {
+            return Ok(Started::AlreadyRunning { pid: Some(pid) });
+        }
+        let (conn, project_id, agent_id) = self.lookup(target)?.ok_or_else(|| CliError::validation(
+            "agent",
+            format!("'{}' of project '{}' is not in the database", target.agent, target.project),
+        ))?;
+
+        let mut cmd = Command::new(&spec.cmd);
+        cmd.args(&spec.args).stdin(Stdio::null()).process_group(0);
+        if let Some(dir) = &spec.workdir {
+            cmd.current_dir(dir);
+        }
+        let ingest = match (&spec.log_file, &self.ingest_exe) {
+            (Some(log_file), Some(exe)) => {
+                let (reader, writer) = io::pipe()?;
+                cmd.stdout(writer.try_clone()?).stderr(writer);
+                Some((reader, log_file, exe))
+            }
+            _ => {
+                cmd.stdout(Stdio::null()).stderr(Stdio::null());
+                None
+            }
+        };
+        let mut child = cmd.spawn().map_err(|e| CliError::provider(spec.cmd.as_str(), ProviderErrorKind::NotStarted(e.to_string())))?;
+        // Close our copies of the pipe's write end, so the ingester sees EOF when the REPL exits
+        drop(cmd);
+        let pid = child.id();
+
+        let mut ingester = None;
+        if let Some((reader, log_file, exe)) = ingest {
+            let spawned = Command::new(exe)
+                .args(ingest_args(log_file, target, &spec.provider))
+                .stdin(reader)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .process_group(pid as i32)
+                .spawn();
+            match spawned {
+                Ok(c) => ingester = Some(c),
+                Err(e) => eprintln!("Warning: Failed to start log ingestion: {}", e),
+            }
+        }
+        // Reap both while this process lives, so a stopped REPL does not linger as a zombie
+        std::thread::spawn(move || {
+            let _ = child.wait();
+            if let Some(mut c) = ingester {
+                let _ = c.wait();
+            }
+        });
+
+        let session = insert_repl_session(&conn, &project_id, &agent_id, &spec.provider, None)?;
+        let metadata = serde_json::json!({
+            "backend": "process",
+            "pid": pid,
+            "start_time": process_start_time(pid),
+            "cmd": spec.cmd,
+            "log_file": spec.log_file,
+        });
+        set_session_metadata(&conn, &session.id, &metadata.to_string())?;
+        Ok(Started::Launched { pid: Some(pid) })
+    }
+
+    fn stop(&self, target: &AgentTarget) -> Result<bool, CliError> {
+        // Only a PID that still belongs to the recorded process is signalled, never one reused since
+        let Some((session, pid)) = self.running_process(target)? else { return Ok(false) };
+        let group = -(pid as i32);
+        unsafe { libc::kill(group, libc::SIGTERM); }
+        let deadline = Instant::now() + STOP_GRACE;
+        while is_recorded_process(&session, pid) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        if is_recorded_process(&session, pid) {
+            unsafe { libc::kill(group, libc::SIGKILL); }
+        }
+        if let Some((conn, _, _)) = self.lookup(target)? {
+            update_session(&conn, &session.id, None, None, Some(SessionStatus::Expired))?;
+        }
+        Ok(true)
+    }
+
+    fn attach(&self, target: &AgentTarget, log_file: &str, _read_only: bool) -> Result<(), CliError> {
+        let Some((session, pid)) = self.running_process(target)? else {
+            return Err(CliError::validation("agent attach", format!("agent '{}' is not running as a background process", target.agent)));
+        };
+        println!("Agent '{}' runs as background process {} with no terminal to attach to.", target.agent, pid);
+        // The file it was started with wins over the default path (`agent run --logs-dir`)
+        match get_metadata_value(&session, "log_file").ok().flatten() {
+            Some(serde_json::Value::String(path)) => println!("To follow its output, run: tail -f {}", path),
+            Some(serde_json::Value::Null) => println!("It was started with --no-logs; its output is not recorded."),
+            _ => println!("To follow its output, run: tail -f {}", log_file),
+        }
+        Ok(())
+    }
+}
+
+/// Start time of `pid` in clock ticks since boot (`/proc/<pid>/stat`); `None` without procfs
+pub fn process_start_time(pid: u32) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // Fields after the parenthesised command name, which may itself contain spaces; starttime is the 22nd
+    stat.rsplit_once(')')?.1.split_whitespace().nth(19)?.parse().ok()
+}
+
+/// Whether `pid` is alive and still the process `session` recorded, not a later one that reused the PID
+fn is_recorded_process(session: &Session, pid: u32) -> bool {
+    let recorded = get_metadata_value(session, "start_time").ok().flatten().and_then(|v| v.as_u64());
+    is_process_running(pid) && recorded == process_start_time(pid)
+}
+
+/// Arguments of the `__ingest-log` run writing `target`'s output to `log_file`, with the
+/// redaction patterns in effect passed along
+pub fn ingest_args(log_file: &str, target: &AgentTarget, provider: &str) -> Vec<String> {
+    let mut args: Vec<String> = [INGEST_COMMAND, "--log-file", log_file, "--project", target.project, "--role", target.role, "--agent", target.agent, "--provider", provider]
+        .iter().map(|a| a.to_string()).collect();
+    for pattern in redaction_patterns() {
+        args.extend(["--redact".to_string(), pattern]);
+    }
+    args
+}
+
+/// Write each line of `reader` to `log_file` as a `stdout_line` event, then an `end` event at EOF;
+/// returns the number of lines. Lines are flushed one by one: the ingester is killed along with the REPL
+/// on `agent stop`, and nothing it buffered would reach the file. Bytes that are not UTF-8 are replaced
+/// and overlong lines truncated, as for one-shot output; a read error still ends the log with an `end`
+/// event (status `read_error`).
+pub fn ingest_lines(mut reader: impl BufRead, log_file: &str, target: &AgentTarget, provider: &str) -> io::Result<usize> {
+    let started = Instant::now();
+    let max = max_line_bytes();
+    let (mut count, mut bytes) = (0, 0);
+    let outcome = loop {
+        let line = match read_capped_line(&mut reader, max) {
+            Ok(Some(line)) => line,
+            Ok(None) => break Ok(()),
+            Err(e) => break Err(e),
+        };
+        bytes += line.bytes as u64;
+        let event = NdjsonEvent::new_stdout_line(target.project, target.role, target.agent, provider, &line.text);
+        write_ndjson_event(log_file, &event).map_err(|e| io::Error::other(e.to_string()))?;
+        flush_path(log_file)?;
+        count += 1;
+    };
+    let status = if outcome.is_ok() { "exited" } else { "read_error" };
+    let mut end = NdjsonEvent::new_end(target.project, target.role, target.agent, provider, started.elapsed().as_millis() as u64, status);
+    (end.lines, end.bytes) = (Some(count as u64), Some(bytes));
+    write_ndjson_event(log_file, &end).map_err(|e| io::Error::other(e.to_string()))?;
+    outcome.map(|()| count)
+}
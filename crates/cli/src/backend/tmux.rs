@@ -0,0 +1,120 @@
+//! tmux backend: one window per agent in the project's `proj:{project}` session
+
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use crate::tmux::manager::{attach_args, TmuxManager};
+use crate::utils::errors::CliError;
+use super::{ingest_args, AgentBackend, AgentTarget, LaunchSpec, Started};
+
+pub struct TmuxBackend {
+    manager: TmuxManager,
+    /// Binary the pane is piped through as `__ingest-log`; `None` leaves the pane unlogged
+    ingest_exe: Option<PathBuf>,
+}
+
+impl TmuxBackend {
+    pub fn new(manager: TmuxManager) -> Self {
+        Self { manager, ingest_exe: std::env::current_exe().ok() }
+    }
+
+    /// Use `exe` (or nothing) as the log ingester
+    pub fn with_ingest_exe(mut self, exe: Option<PathBuf>) -> Self {
+        self.ingest_exe = exe;
+        self
+    }
+
+    /// The underlying manager, for pane checks after a start
+    pub fn manager(&self) -> &TmuxManager {
+        &self.manager
+    }
+
+    /// Name of the agent's running window, found under its current or legacy name (`AgentTarget::find_window`)
+    pub fn agent_window(&self, target: &AgentTarget) -> Result<Option<String>, CliError> {
+        let session = target.session_name();
+        if !self.manager.has_session(&session)? {
+            return Ok(None);
+        }
+        Ok(target.find_window(&self.manager.list_windows(&session)?))
+    }
+}
+
+impl AgentBackend for TmuxBackend {
+    fn name(&self) -> &'static str {
+        "tmux"
+    }
+
+    fn is_available(&self) -> bool {
+        self.manager.is_available()
+    }
+
+    fn describe(&self, target: &AgentTarget) -> String {
+        format!("tmux session '{}'", target.session_name())
+    }
+
+    fn is_running(&self, target: &AgentTarget) -> Result<bool, CliError> {
+        Ok(self.agent_window(target)?.is_some())
+    }
+
+    fn start(&self, target: &AgentTarget, spec: &LaunchSpec) -> Result<Started, CliError> {
+        let (session, window) = (target.session_name(), target.window_name());
+        if self.agent_window(target)?.is_some() {
+            return Ok(Started::AlreadyRunning { pid: None });
+        }
+        if !self.manager.has_session(&session)? {
+            self.manager.create_session(&session)?;
+        }
+        self.manager.create_window(&session, &window)?;
+
+        // Pane output goes through the ingester, so it is redacted before it reaches the file
+        if let (Some(log_file), Some(exe)) = (&spec.log_file, &self.ingest_exe) {
+            if let Some(dir) = std::path::Path::new(log_file).parent() {
+                let _ = std::fs::create_dir_all(dir);
+            }
+            self.manager.setup_pipe_pane(&session, &window, &ingest_pipe_command(exe, log_file, target, &spec.provider))?;
+        }
+        if let Some(workdir) = &spec.workdir {
+            self.manager.send_keys(&session, &window, &format!("cd {}", workdir))?;
+        }
+        self.manager.send_keys(&session, &window, &format!("{} {}", spec.cmd, spec.args.join(" ")))?;
+        Ok(Started::Launched { pid: None })
+    }
+
+    fn stop(&self, target: &AgentTarget) -> Result<bool, CliError> {
+        let Some(window) = self.agent_window(target)? else {
+            return Ok(false);
+        };
+        self.manager.kill_window(&target.session_name(), &window)?;
+        Ok(true)
+    }
+
+    fn attach(&self, target: &AgentTarget, log_file: &str, read_only: bool) -> Result<(), CliError> {
+        let session = target.session_name();
+        if !self.manager.has_session(&session)? {
+            return Err(CliError::validation("agent attach", format!("no tmux session found for project '{}'", target.project)));
+        }
+        let Some(window) = target.find_window(&self.manager.list_windows(&session)?) else {
+            return Err(CliError::validation("agent attach", format!("agent '{}' is not running in tmux session '{}'", target.agent, session)));
+        };
+
+        // tmux needs a terminal to attach to; scripts and pipes get the commands to run instead
+        if !std::io::stdout().is_terminal() {
+            let target_window = format!("{}:{}", session, window);
+            println!("Cannot attach to tmux session without a terminal.");
+            println!("Session '{}' is running with window '{}'.", session, window);
+            println!("To attach manually, run: tmux {}", attach_args(&target_window, read_only).join(" "));
+            println!("To view logs, run: tail -f {}", log_file);
+            return Ok(());
+        }
+        self.manager.attach_window(&session, &window, read_only)
+    }
+}
+
+/// Shell command for `pipe-pane`: every argument single-quoted, and `#` doubled since tmux
+/// expands `#{...}` formats in the command
+fn ingest_pipe_command(exe: &std::path::Path, log_file: &str, target: &AgentTarget, provider: &str) -> String {
+    std::iter::once(exe.to_string_lossy().into_owned())
+        .chain(ingest_args(log_file, target, provider))
+        .map(|arg| format!("'{}'", arg.replace('\'', r"'\''").replace('#', "##")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
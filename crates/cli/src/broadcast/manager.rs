@@ -3,6 +3,7 @@
 use std::time::{Duration, Instant};
 use uuid::Uuid;
 use crate::tmux::manager::TmuxManager;
+use crate::backend::AgentTarget;
 use crate::logging::emit_metrics_event;
 use super::targets::{BroadcastResult, BroadcastSummary};
 
@@ -22,6 +23,12 @@ impl BroadcastManager {
             broadcast_id: Uuid::new_v4().to_string(),
         }
     }
+
+    /// Drive tmux through `tmux_manager` (e.g. one running a scripted fake)
+    pub fn with_tmux_manager(mut self, tmux_manager: TmuxManager) -> Self {
+        self.tmux_manager = tmux_manager;
+        self
+    }
     
     /// Execute broadcast to multiple targets
     pub fn broadcast_to_targets(
@@ -97,10 +104,11 @@ impl BroadcastManager {
             return Err(format!("Invalid target format: {}", target).into());
         }
         
-        let role = parts[0];
-        let agent = parts[1];
-        let session_name = format!("proj:{}", self.project_name);
-        let window_name = format!("{}:{}", role, agent);
+        let agent = AgentTarget { project: &self.project_name, role: parts[0], agent: parts[1] };
+        let session_name = agent.session_name();
+        // Only an existing window is typed into; a guessed name could prefix-match another window
+        let window_name = agent.find_window(&self.tmux_manager.list_windows(&session_name)?)
+            .ok_or_else(|| format!("agent '{}' is not running (no tmux window in session '{}')", agent.agent, session_name))?;
         
         // Send keys to tmux window
         self.tmux_manager.send_keys(&session_name, &window_name, message)?;
@@ -151,4 +159,26 @@ mod tests {
         let manager = BroadcastManager::new("test".to_string(), Duration::from_secs(5));
         assert!(!manager.broadcast_id().is_empty());
     }
+
+    #[test]
+    fn test_broadcast_repl_to_a_missing_window_is_not_running() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::TempDir::new().unwrap();
+        // A tmux whose session only has the window of another agent
+        let tmux = dir.path().join("tmux");
+        std::fs::write(&tmux, format!(
+            "#!/bin/sh\necho \"$*\" >> {}/calls\n[ \"$1\" = list-windows ] && echo worker-2\nexit 0\n",
+            dir.path().display(),
+        )).unwrap();
+        std::fs::set_permissions(&tmux, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let manager = BroadcastManager::new("demo".to_string(), Duration::from_secs(1))
+            .with_tmux_manager(TmuxManager::with_binary(Duration::from_secs(1), tmux.to_string_lossy()));
+
+        let summary = manager.broadcast_to_targets(&["backend:worker".to_string()], "hi", BroadcastMode::Repl).unwrap();
+        assert_eq!(summary.failed, 1);
+        let error = summary.results[0].error.as_deref().unwrap();
+        assert!(error.contains("agent 'worker' is not running"), "{}", error);
+        let calls = std::fs::read_to_string(dir.path().join("calls")).unwrap();
+        assert!(!calls.contains("send-keys"), "calls: {}", calls);
+    }
 }
@@ -1,5 +1,6 @@
 //! Broadcast target resolution and management
 
+use config_model::AgentConfig;
 
 /// Broadcast target types
 #[derive(Debug, Clone, PartialEq)]
@@ -88,6 +89,41 @@ impl BroadcastTarget {
     }
 }
 
+/// Merge the YAML and database agents of a project into one inventory keyed by agent name.
+///
+/// Precedence:
+/// - agent in both sources: the YAML definition wins (role, provider, model, system_prompt, allowed_tools);
+///   a differing provider is reported as a warning
+/// - agent only in YAML: used as-is
+/// - agent only in the database (e.g. added with `db agent-add`): synthesized from its row
+///
+/// YAML agents keep their file order and DB-only agents follow, sorted by name.
+pub fn merge_agent_sources(yaml_agents: &[AgentConfig], db_agents: &[db::Agent]) -> (Vec<AgentConfig>, Vec<String>) {
+    let mut merged: Vec<AgentConfig> = yaml_agents.to_vec();
+    let mut warnings = Vec::new();
+    let mut db_only: Vec<&db::Agent> = Vec::new();
+    for row in db_agents {
+        match yaml_agents.iter().find(|a| a.name == row.name) {
+            Some(cfg) if cfg.provider != row.provider => warnings.push(format!(
+                "agent '{}' uses provider '{}' in YAML but '{}' in the database; using '{}'",
+                row.name, cfg.provider, row.provider, cfg.provider
+            )),
+            Some(_) => {}
+            None => db_only.push(row),
+        }
+    }
+    db_only.sort_by(|a, b| a.name.cmp(&b.name));
+    merged.extend(db_only.into_iter().map(|row| AgentConfig {
+        name: row.name.clone(),
+        role: row.role.clone(),
+        provider: row.provider.clone(),
+        model: row.model.clone(),
+        allowed_tools: row.allowed_tools.clone(),
+        system_prompt: row.system_prompt.clone(),
+    }));
+    (merged, warnings)
+}
+
 /// Broadcast result for a single target
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct BroadcastResult {
@@ -193,6 +229,68 @@ mod tests {
         assert_eq!(BroadcastTarget::AgentList(vec!["backend1".to_string(), "frontend1".to_string()]).resolve_agents(&agents).unwrap(), vec!["backend1", "frontend1"]);
     }
     
+    fn yaml_agent(name: &str, role: &str, provider: &str) -> AgentConfig {
+        AgentConfig {
+            name: name.to_string(),
+            role: role.to_string(),
+            provider: provider.to_string(),
+            model: "m".to_string(),
+            allowed_tools: vec![],
+            system_prompt: "from yaml".to_string(),
+        }
+    }
+
+    fn db_agent(name: &str, role: &str, provider: &str) -> Agent {
+        Agent {
+            id: format!("id-{}", name),
+            project_id: "p".to_string(),
+            name: name.to_string(),
+            role: role.to_string(),
+            provider: provider.to_string(),
+            model: "db-model".to_string(),
+            system_prompt: "from db".to_string(),
+            allowed_tools: vec!["Read".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_merge_agent_sources_yaml_only() {
+        let (merged, warnings) = merge_agent_sources(&[yaml_agent("b1", "backend", "gemini")], &[]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].system_prompt, "from yaml");
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_merge_agent_sources_db_only() {
+        let yaml = [yaml_agent("b1", "backend", "gemini")];
+        let db = [db_agent("b1", "backend", "gemini"), db_agent("b2", "backend", "claude")];
+        let (merged, warnings) = merge_agent_sources(&yaml, &db);
+        assert!(warnings.is_empty());
+        let b2 = merged.iter().find(|a| a.name == "b2").expect("db-only agent synthesized");
+        assert_eq!(b2.provider, "claude");
+        assert_eq!(b2.model, "db-model");
+        assert_eq!(b2.system_prompt, "from db");
+        assert_eq!(b2.allowed_tools, vec!["Read".to_string()]);
+
+        // @role now reaches the DB-only agent
+        let inventory: Vec<Agent> = merged.iter().map(|a| db_agent(&a.name, &a.role, &a.provider)).collect();
+        assert_eq!(BroadcastTarget::Role("backend".to_string()).resolve_agents(&inventory).unwrap(), vec!["b1", "b2"]);
+    }
+
+    #[test]
+    fn test_merge_agent_sources_conflict_prefers_yaml() {
+        let yaml = [yaml_agent("b1", "backend", "gemini")];
+        let db = [db_agent("b1", "frontend", "claude")];
+        let (merged, warnings) = merge_agent_sources(&yaml, &db);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].role, "backend");
+        assert_eq!(merged[0].provider, "gemini");
+        assert_eq!(merged[0].system_prompt, "from yaml");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("'b1'") && warnings[0].contains("gemini") && warnings[0].contains("claude"));
+    }
+
     #[test]
     fn test_broadcast_summary() {
         let mut summary = BroadcastSummary::new("test-123".to_string());
@@ -5,6 +5,21 @@ use clap::{Parser, Subcommand, ValueEnum};
 #[derive(Parser, Debug)]
 #[command(name = "multi-agents", version)]
 pub struct Cli {
+    /// Suppress progress spinners, info logs, sync messages and decorative headers
+    #[arg(long, global = true, default_value_t = false)]
+    pub quiet: bool,
+    /// Emoji and colors in text output (auto|always|never)
+    #[arg(long, global = true, value_enum, default_value_t = ColorMode::Auto)]
+    pub color: ColorMode,
+    /// Increase log verbosity (-v info, -vv debug, -vvv trace)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+    /// Base directory for NDJSON logs (default: MULTI_AGENTS_LOG_DIR or ./logs)
+    #[arg(long, global = true, value_name = "DIR")]
+    pub log_dir: Option<String>,
+    /// Write NDJSON log text without applying `defaults.redaction` (debugging only)
+    #[arg(long, global = true, default_value_t = false)]
+    pub no_redaction: bool,
     #[command(subcommand)]
     pub cmd: Commands,
 }
@@ -19,6 +34,8 @@ pub enum Commands {
         #[arg(long, default_value_t = false)] force: bool,
         /// Skip database initialization (assume already done)
         #[arg(long, default_value_t = false)] skip_db: bool,
+        /// Output format (text|json); json prints the files, database, sync and validation results
+        #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
     },
     /// Configuration commands
     Config {
@@ -35,12 +52,34 @@ pub enum Commands {
         /// Optional: write JSON snapshot of detected capabilities to file
         #[arg(long, value_name = "PATH")]
         snapshot: Option<String>,
+        /// Also check that each provider's `required_env_keys` are set (values are never printed)
+        #[arg(long, default_value_t = false)]
+        check_keys: bool,
+        /// Keep re-probing and print only what changed since the previous round (Ctrl-C to stop)
+        #[arg(long, default_value_t = false, conflicts_with_all = ["ndjson_sample", "snapshot"])]
+        watch: bool,
+        /// Delay between `--watch` rounds (e.g. 300s, 5m)
+        #[arg(long, value_name = "DURATION", default_value = "300s", requires = "watch")]
+        interval: String,
+        /// Re-probe every provider CLI instead of reusing cached versions
+        #[arg(long, default_value_t = false)]
+        no_cache: bool,
     },
     /// Database commands
     Db {
         #[command(subcommand)]
         cmd: DbCmd,
     },
+    /// Project management
+    Project {
+        #[command(subcommand)]
+        cmd: ProjectCmd,
+    },
+    /// Read back NDJSON logs
+    Logs {
+        #[command(subcommand)]
+        cmd: LogsCmd,
+    },
     /// Send a one-shot message to agent(s)
     Send {
         /// Optional: explicit path; else ENV/defaults resolution is used
@@ -48,18 +87,42 @@ pub enum Commands {
         /// Optional: explicit path; else ENV/defaults resolution is used
         #[arg(long, value_name = "PATH")] providers_file: Option<String>,
         /// Target: @all, @role, or agent name
-        #[arg(long)] to: String,
+        #[arg(long, value_hint = clap::ValueHint::Other)] to: String,
         #[arg(long)] message: String,
         /// Optional: provide explicit session id (e.g., for Claude)
         #[arg(long)] session_id: Option<String>,
         /// Optional: provide explicit chat id (for cursor-agent)
         #[arg(long)] chat_id: Option<String>,
-        /// Optional: override per-target timeout in milliseconds (default 120_000)
+        /// Optional: override per-target timeout in milliseconds (default: the provider's `timeouts.total_ms`, else 120_000; 0 = no timeout)
         #[arg(long, value_name = "MILLIS")] timeout_ms: Option<u64>,
+        /// Kill a target that prints no stdout/stderr line for this long (default: the provider's `timeouts.inactivity_ms`, else off)
+        #[arg(long, value_name = "MILLIS")] inactivity_timeout_ms: Option<u64>,
         /// Output format for this command (text|json)
         #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
         /// Show progress spinner (default ON); disable with --no-progress
         #[arg(long = "progress", default_value_t = true)] progress: bool,
+        /// Cancel remaining targets on the first provider error or timeout instead of waiting for all
+        #[arg(long)] fail_fast: bool,
+        /// Skip the confirmation asked when targets exceed the project's `defaults.confirm_send_above`
+        #[arg(long, short = 'y')] yes: bool,
+        /// DB project id or name to send in (default: the `project:` name from project.yaml)
+        #[arg(long)] project: Option<String>,
+        /// Fail on unknown `{placeholders}` in system prompts and provider args (default: left as is)
+        #[arg(long)] strict_placeholders: bool,
+        /// Write a JSON manifest of the run (targets, args, exit codes, durations, log files) to this path
+        #[arg(long, value_name = "PATH")] manifest: Option<String>,
+        /// Reuse the stored output of an identical earlier prompt instead of running the provider (default: the project's `defaults.cache`)
+        #[arg(long, conflicts_with = "no_cache")] cache: bool,
+        /// Always run the provider, even when the project sets `defaults.cache`
+        #[arg(long)] no_cache: bool,
+        /// How long a new cached response stays valid: <number><s|m|h|d|w> (default: the project's `defaults.cache_ttl`, else 24h)
+        #[arg(long, value_name = "DURATION")] cache_ttl: Option<String>,
+        /// Sessions this send creates expire after: <number><s|m|h|d|w> (default: the project's `defaults.session_ttl`, else never)
+        #[arg(long, value_name = "DURATION")] session_ttl: Option<String>,
+        /// Also deliver supervisor notifications as sends to the supervisor (default: only recorded as messages)
+        #[arg(long)] notify_supervisor: bool,
+        /// Print the outgoing prompt before the replies (stderr with --format json); it is logged either way
+        #[arg(long)] echo_prompt: bool,
     },
     /// Session management
     Session {
@@ -94,6 +157,90 @@ pub enum Commands {
         /// Refresh rate in ms (default: 200)
         #[arg(long, value_name = "MILLIS")] refresh_rate: Option<u64>,
     },
+    /// Aggregate send/session/task statistics for a project
+    Stats {
+        /// Project id or name
+        #[arg(long)] project: String,
+        /// Match --project as an id or a name (default: ids are recognised by their 32-hex form)
+        #[arg(long, value_enum)] by: Option<SelectBy>,
+        /// Lookback window: <number><m|h|d|w> (default: 24h, or 7d with --weekly)
+        #[arg(long, value_name = "DURATION")] since: Option<String>,
+        /// Add a per-day breakdown
+        #[arg(long, default_value_t = false)] weekly: bool,
+        #[arg(long)] db_path: Option<String>,
+        #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
+    },
+    /// Print a shell completion script (bash, zsh, fish, elvish, powershell)
+    #[command(alias = "completion")]
+    Completions {
+        #[arg(value_enum, required_unless_present = "shell_flag", conflicts_with = "shell_flag")]
+        shell: Option<clap_complete::Shell>,
+        /// Same as the positional shell argument
+        #[arg(long = "shell", value_enum, value_name = "SHELL")] shell_flag: Option<clap_complete::Shell>,
+    },
+    /// Check GitHub Releases for a newer version and print upgrade instructions
+    Upgrade {
+        /// Only report whether an upgrade is available
+        #[arg(long, default_value_t = false)] check_only: bool,
+        /// Also consider pre-release tags
+        #[arg(long, default_value_t = false)] prerelease: bool,
+        #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
+    },
+    /// Print agent names for shell completion
+    #[command(name = "__complete-agents", hide = true)]
+    CompleteAgents {
+        #[arg(long)] project: Option<String>,
+        #[arg(long, value_name = "PATH")] project_file: Option<String>,
+        #[arg(long, value_name = "PATH")] db_path: Option<String>,
+    },
+    /// Write a background agent's output (stdin) to its NDJSON log
+    #[command(name = "__ingest-log", hide = true)]
+    IngestLog {
+        #[arg(long, value_name = "PATH")] log_file: String,
+        #[arg(long)] project: String,
+        #[arg(long)] role: String,
+        #[arg(long)] agent: String,
+        #[arg(long)] provider: String,
+        /// Redaction pattern from the launching project (repeatable)
+        #[arg(long = "redact", value_name = "REGEX")] redact: Vec<String>,
+    },
+    /// Send a supervisor notification to its supervisor, detached from the send that raised it
+    #[command(name = "__deliver-notification", hide = true)]
+    DeliverNotification {
+        #[arg(long, value_name = "PATH")] project_file: String,
+        #[arg(long, value_name = "PATH")] providers_file: String,
+        #[arg(long)] project: Option<String>,
+        #[arg(long)] to: String,
+        #[arg(long)] message: String,
+    },
+    /// Print `--to` targets (@all, @role, agent names) for shell completion
+    #[command(name = "__complete-targets", hide = true)]
+    CompleteTargets {
+        #[arg(long)] project: Option<String>,
+        #[arg(long, value_name = "PATH")] project_file: Option<String>,
+        #[arg(long, value_name = "PATH")] db_path: Option<String>,
+    },
+    /// Print project names for shell completion
+    #[command(name = "__complete-projects", hide = true)]
+    CompleteProjects {
+        #[arg(long, value_name = "PATH")] project_file: Option<String>,
+        #[arg(long, value_name = "PATH")] db_path: Option<String>,
+    },
+    /// Inspect or clear the `send --cache` response cache
+    Cache {
+        #[command(subcommand)]
+        cmd: CacheCmd,
+    },
+    /// Notify a supervisor agent when other agents finish sends or tasks
+    Supervisor {
+        #[command(subcommand)]
+        cmd: SupervisorCmd,
+    },
+    /// Update tasks (moving one to `done` notifies subscribed supervisors)
+    Task {
+        #[command(subcommand)]
+        cmd: TaskCmd,
+    },
     /// Collect contextual information
     Context {
         #[command(subcommand)]
@@ -110,6 +257,9 @@ pub enum ConfigCmd {
         /// Optional: explicit path; else ENV/defaults resolution is used
         #[arg(long, value_name = "PATH")] providers_file: Option<String>,
         #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
+        /// Reject provider keys other than claude, gemini, cursor* and ollama, and treat agent tools
+        /// missing from the provider's `known_tools` as errors instead of warnings
+        #[arg(long, default_value_t = false)] strict: bool,
     },
     /// Create default config files under a directory (default: ./config)
     Init {
@@ -117,6 +267,35 @@ pub enum ConfigCmd {
         #[arg(long, value_name = "DIR")] dir: Option<String>,
         /// Overwrite existing files if present
         #[arg(long, default_value_t = false)] force: bool,
+        /// Print a unified diff of what would be written instead of writing
+        #[arg(long, default_value_t = false)] dry_run: bool,
+        /// Output format (text|json); json prints `{"status":..,"files":[{"path":..,"action":..}]}`
+        #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
+    },
+    /// Sync the project and its agents from project.yaml into the database, without writing config files
+    Sync {
+        /// Optional: explicit path; else ENV/defaults resolution is used
+        #[arg(long, value_name = "PATH")] project_file: Option<String>,
+        /// Optional: explicit path; else ENV/defaults resolution is used
+        #[arg(long, value_name = "PATH")] providers_file: Option<String>,
+        #[arg(long, value_name = "PATH")] db_path: Option<String>,
+        /// Delete agents of the project that project.yaml no longer lists, with their sessions and messages
+        #[arg(long, default_value_t = false)] prune: bool,
+        /// Report what would change, with a diff of each updated agent, without touching the database
+        #[arg(long, default_value_t = false)] dry_run: bool,
+        /// Output format (text|json); json prints the sync report
+        #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
+    },
+    /// Print the JSON Schema of project.yaml and/or providers.yaml
+    Schema {
+        /// Which schema to print
+        #[arg(value_enum, default_value_t = SchemaTarget::All)] which: SchemaTarget,
+        /// json: pretty JSON; yaml: the schema as a `#`-commented header block for YAML files
+        #[arg(long, value_enum, default_value_t = SchemaOutputFormat::Json)] format: SchemaOutputFormat,
+        /// Write to this file instead of stdout
+        #[arg(long, value_name = "FILE")] output: Option<String>,
+        /// Validate a YAML file against the selected JSON Schema instead of printing it
+        #[arg(long, value_name = "FILE", conflicts_with = "output")] validate_against: Option<String>,
     },
 }
 
@@ -136,6 +315,8 @@ pub enum DbCmd {
     AgentAdd {
         /// Project id or name
         #[arg(long)] project: String,
+        /// Match --project as an id or a name (default: ids are recognised by their 32-hex form)
+        #[arg(long, value_enum)] by: Option<SelectBy>,
         #[arg(long)] name: String,
         #[arg(long)] role: String,
         #[arg(long)] provider: String,
@@ -144,6 +325,140 @@ pub enum DbCmd {
         #[arg(long = "allowed-tool")] allowed_tool: Vec<String>,
         #[arg(long = "system-prompt")] system_prompt: String,
         #[arg(long, value_name = "PATH")] db_path: Option<String>,
+        /// Create the project (selected by name) when it does not exist yet
+        #[arg(long, default_value_t = false)] create_project: bool,
+    },
+    /// Remove a project; with --cascade also its agents, sessions, messages, tasks and broadcasts
+    ProjectRemove {
+        #[arg(long)] name: String,
+        /// Delete the project even when it still has agents or sessions
+        #[arg(long, default_value_t = false)] cascade: bool,
+        /// Skip the confirmation (required without a TTY when rows would be deleted)
+        #[arg(long, short = 'y')] yes: bool,
+        #[arg(long, value_name = "PATH")] db_path: Option<String>,
+    },
+    /// Show the applied schema version and apply pending migrations up to --to (default: latest)
+    Migrate {
+        #[arg(long, value_name = "PATH")] db_path: Option<String>,
+        /// Stop at this migration version
+        #[arg(long, value_name = "VERSION")] to: Option<i64>,
+        /// Only list the pending migrations
+        #[arg(long, default_value_t = false)] dry_run: bool,
+    },
+    /// Check integrity, orphaned rows, unknown providers and duplicate agents
+    Doctor {
+        /// Delete orphaned rows inside a transaction
+        #[arg(long, default_value_t = false)] fix: bool,
+        /// Optional: explicit path; else ENV/defaults resolution is used
+        #[arg(long, value_name = "PATH")] providers_file: Option<String>,
+        #[arg(long, value_name = "PATH")] db_path: Option<String>,
+        #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
+        /// Inspect a database migrated by a newer multi-agents (not with --fix)
+        #[arg(long, default_value_t = false)] allow_newer_schema: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LogsCmd {
+    /// Print an agent's (or a role's) NDJSON log as `ts | level | event | text`
+    Show {
+        #[arg(long)] project: String,
+        /// Role-level file `<role>.ndjson` merged with the files of the role's agents
+        #[arg(long, required_unless_present = "agent", conflicts_with = "agent")] role: Option<String>,
+        /// Keep printing lines as they are appended
+        #[arg(long, default_value_t = false)] follow: bool,
+        /// Minimum level: trace, debug, info, warn, error (default: all)
+        #[arg(long, value_name = "LEVEL")] level: Option<String>,
+        /// Do not color levels (same as --color never)
+        #[arg(long, default_value_t = false)] no_color: bool,
+        /// Agent name: reads `<agent>.ndjson`, or its role's file before per-agent files
+        #[arg(long)] agent: Option<String>,
+    },
+    /// Split role-keyed log files into one file per agent, using each line's agent_id
+    Migrate {
+        #[arg(long)] project: String,
+        #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCmd {
+    /// Delete cached responses
+    Clear {
+        /// Only delete entries past their TTL
+        #[arg(long, default_value_t = false)] expired: bool,
+        #[arg(long, value_name = "PATH")] db_path: Option<String>,
+        #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
+    },
+    /// Print entry, hit and size counts
+    Stats {
+        #[arg(long, value_name = "PATH")] db_path: Option<String>,
+        #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SupervisorCmd {
+    /// Record a message for a supervisor agent whenever a matching agent raises an event
+    Subscribe {
+        /// Project id or name
+        #[arg(long)] project: String,
+        /// Match --project as an id or a name (default: ids are recognised by their 32-hex form)
+        #[arg(long, value_enum)] by: Option<SelectBy>,
+        /// Supervisor agent to notify
+        #[arg(long)] agent: String,
+        /// Event to subscribe to (repeatable)
+        #[arg(long, value_enum, required = true)] on: Vec<SupervisorEventArg>,
+        /// Agents to listen to: @all, @<role> or an agent name
+        #[arg(long, default_value = "@all")] from: String,
+        #[arg(long, value_name = "PATH")] db_path: Option<String>,
+        #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
+    },
+    /// Remove a supervisor's subscriptions (all of them unless --on/--from narrow it down)
+    Unsubscribe {
+        /// Project id or name
+        #[arg(long)] project: String,
+        /// Match --project as an id or a name (default: ids are recognised by their 32-hex form)
+        #[arg(long, value_enum)] by: Option<SelectBy>,
+        /// Supervisor agent
+        #[arg(long)] agent: String,
+        /// Only remove subscriptions to this event (repeatable)
+        #[arg(long, value_enum)] on: Vec<SupervisorEventArg>,
+        /// Only remove subscriptions with this source filter
+        #[arg(long)] from: Option<String>,
+        #[arg(long, value_name = "PATH")] db_path: Option<String>,
+        #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TaskCmd {
+    /// Set a task's status; `done` on an assigned task raises `task_done`
+    Status {
+        /// Task id
+        #[arg(long)] task: String,
+        /// New status, e.g. todo, in_progress, done
+        #[arg(long)] status: String,
+        #[arg(long, value_name = "PATH")] db_path: Option<String>,
+        #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProjectCmd {
+    /// Rename a project in the DB, logs directory, tmux session and (with --update-config) project.yaml
+    Rename {
+        /// Current project name
+        #[arg(long)] from: String,
+        /// New project name
+        #[arg(long)] to: String,
+        #[arg(long, value_name = "PATH")] db_path: Option<String>,
+        /// Directory containing project.yaml (default: MULTI_AGENTS_CONFIG_DIR or ./config)
+        #[arg(long, value_name = "DIR")] config_dir: Option<String>,
+        /// Custom logs directory (default: ./logs)
+        #[arg(long, value_name = "DIR")] logs_dir: Option<String>,
+        /// Also rewrite `project:` in project.yaml (otherwise only guidance is printed)
+        #[arg(long, default_value_t = false)] update_config: bool,
     },
 }
 
@@ -155,7 +470,10 @@ pub enum SessionCmd {
         #[arg(long, value_name = "PATH")] project_file: Option<String>,
         /// Optional: explicit path; else ENV/defaults resolution is used
         #[arg(long, value_name = "PATH")] providers_file: Option<String>,
-        #[arg(long)] agent: String,
+        /// Agent name; omit on a TTY (or pass `-`) to pick from a numbered list
+        #[arg(long)] agent: Option<String>,
+        /// The session expires after: <number><s|m|h|d|w> (default: the project's `defaults.session_ttl`, else never)
+        #[arg(long, value_name = "DURATION")] session_ttl: Option<String>,
     },
     /// List sessions for a project
     List {
@@ -167,8 +485,23 @@ pub enum SessionCmd {
         #[arg(long)] agent: Option<String>,
         /// Filter by provider
         #[arg(long)] provider: Option<String>,
+        /// Session status to list (active|expired|invalid)
+        #[arg(long, value_enum, default_value_t = SessionStatusArg::Active)] status: SessionStatusArg,
         /// Output format (text|json)
         #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
+        /// Read a database migrated by a newer multi-agents
+        #[arg(long, default_value_t = false)] allow_newer_schema: bool,
+    },
+    /// Session counts by provider, status and agent, lifetimes and resumes for a project
+    Stats {
+        /// Project id or name
+        #[arg(long)] project: String,
+        /// Match --project as an id or a name (default: ids are recognised by their 32-hex form)
+        #[arg(long, value_enum)] by: Option<SelectBy>,
+        /// Only sessions created in this window: <number><m|h|d|w> (default: 7d)
+        #[arg(long, value_name = "DURATION")] since: Option<String>,
+        #[arg(long)] db_path: Option<String>,
+        #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
     },
     /// Resume an existing session
     Resume {
@@ -177,12 +510,21 @@ pub enum SessionCmd {
         /// Optional: override timeout in milliseconds (default 5000)
         #[arg(long, value_name = "MILLIS")] timeout_ms: Option<u64>,
     },
+    /// Show every stored field of a session
+    Describe {
+        /// Conversation ID to describe
+        #[arg(long)] conversation_id: String,
+        /// Output format (text|json)
+        #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
+    },
     /// Clean up expired sessions
     Cleanup {
         /// Optional: explicit path; else ENV/defaults resolution is used
         #[arg(long, value_name = "PATH")] project_file: Option<String>,
         /// Dry run (show what would be deleted without actually deleting)
         #[arg(long, default_value_t = false)] dry_run: bool,
+        /// Only sessions idle for longer than this (e.g. 30m, 48h, 7d; default 24h)
+        #[arg(long, value_name = "DURATION")] older_than: Option<String>,
         /// Output format (text|json)
         #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
     },
@@ -190,7 +532,7 @@ pub enum SessionCmd {
 
 #[derive(Subcommand, Debug)]
 pub enum AgentCmd {
-    /// Start an agent in tmux REPL mode
+    /// Start an agent in REPL mode (tmux window or background process)
     Run {
         /// Optional: explicit path; else ENV/defaults resolution is used
         #[arg(long, value_name = "PATH")] project_file: Option<String>,
@@ -198,8 +540,8 @@ pub enum AgentCmd {
         #[arg(long, value_name = "PATH")] providers_file: Option<String>,
         /// Project name (defaults to current directory name)
         #[arg(long)] project: Option<String>,
-        /// Agent name to run
-        #[arg(long)] agent: String,
+        /// Agent name to run; omit on a TTY (or pass `-`) to pick from a numbered list
+        #[arg(long)] agent: Option<String>,
         /// Optional: override agent role
         #[arg(long)] role: Option<String>,
         /// Optional: override agent provider
@@ -214,6 +556,16 @@ pub enum AgentCmd {
         #[arg(long, value_name = "DIR")] logs_dir: Option<String>,
         /// Optional: override timeout in milliseconds (default 5000)
         #[arg(long, value_name = "MILLIS")] timeout_ms: Option<u64>,
+        /// Skip checking that the provider process started in the pane
+        #[arg(long, default_value_t = false)] no_verify: bool,
+        /// Fail on unknown `{placeholders}` in the system prompt and repl_args (default: left as is)
+        #[arg(long)] strict_placeholders: bool,
+        /// Where the REPL runs: a tmux window, or a background process for machines without tmux
+        #[arg(long, value_enum, default_value_t = AgentBackendArg::Tmux)] backend: AgentBackendArg,
+        /// Look at the REPL again shortly after launch and fail (exit 4) if it is no longer running
+        #[arg(long)] detached_check: bool,
+        /// Output format (text|json); json prints the tmux session, window and target
+        #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
     },
     /// Attach to an existing agent tmux session
     Attach {
@@ -221,10 +573,14 @@ pub enum AgentCmd {
         #[arg(long, value_name = "PATH")] project_file: Option<String>,
         /// Project name (defaults to current directory name)
         #[arg(long)] project: Option<String>,
-        /// Agent name to attach to
-        #[arg(long)] agent: String,
+        /// Agent name to attach to; omit on a TTY (or pass `-`) to pick from a numbered list
+        #[arg(long)] agent: Option<String>,
         /// Optional: override timeout in milliseconds (default 5000)
         #[arg(long, value_name = "MILLIS")] timeout_ms: Option<u64>,
+        /// Backend the agent was started with (process: prints how to follow its log); default: the recorded one
+        #[arg(long, value_enum)] backend: Option<AgentBackendArg>,
+        /// Attach as a read-only tmux client (`tmux attach -r`): watch the agent without typing into it
+        #[arg(long)] read_only: bool,
     },
     /// Stop an agent tmux session
     Stop {
@@ -232,10 +588,46 @@ pub enum AgentCmd {
         #[arg(long, value_name = "PATH")] project_file: Option<String>,
         /// Project name (defaults to current directory name)
         #[arg(long)] project: Option<String>,
-        /// Agent name to stop
-        #[arg(long)] agent: String,
+        /// Agent name to stop; omit on a TTY (or pass `-`) to pick from a numbered list
+        #[arg(long)] agent: Option<String>,
         /// Optional: override timeout in milliseconds (default 5000)
         #[arg(long, value_name = "MILLIS")] timeout_ms: Option<u64>,
+        /// Backend the agent was started with; default: the recorded one
+        #[arg(long, value_enum)] backend: Option<AgentBackendArg>,
+    },
+    /// Remove an agent and its sessions/messages from the database
+    Remove {
+        /// Project id or name
+        #[arg(long)] project: String,
+        /// Match --project as an id or a name (default: ids are recognised by their 32-hex form)
+        #[arg(long, value_enum)] by: Option<SelectBy>,
+        /// Agent name to remove
+        #[arg(long)] agent: String,
+        #[arg(long, value_name = "PATH")] db_path: Option<String>,
+        /// Actually delete (default prints a preview only)
+        #[arg(long, default_value_t = false)] force: bool,
+        /// Kill the agent tmux window if it is running
+        #[arg(long, default_value_t = false)] kill_tmux: bool,
+        /// Output format (text|json)
+        #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
+    },
+    /// Show an agent's stored configuration and, optionally, its recent sessions
+    Info {
+        /// Project id or name
+        #[arg(long)] project: String,
+        /// Match --project as an id or a name (default: ids are recognised by their 32-hex form)
+        #[arg(long, value_enum)] by: Option<SelectBy>,
+        /// Agent name
+        #[arg(long)] agent: String,
+        #[arg(long, value_name = "PATH")] db_path: Option<String>,
+        /// List the agent's most recent sessions
+        #[arg(long, default_value_t = false)] include_sessions: bool,
+        /// Number of sessions listed with --include-sessions
+        #[arg(long, value_name = "N", default_value_t = 5)] session_limit: u32,
+        /// Print the whole system prompt (text output truncates it to 120 characters)
+        #[arg(long, default_value_t = false)] full: bool,
+        /// Output format (text|json)
+        #[arg(long, value_enum, default_value_t = Format::Text)] format: Format,
     },
 }
 
@@ -250,7 +642,7 @@ pub enum BroadcastCmd {
         /// Project name (defaults to current directory name)
         #[arg(long)] project: Option<String>,
         /// Target: @all, @role, or agent name(s) (comma-separated)
-        #[arg(long)] to: String,
+        #[arg(long, value_hint = clap::ValueHint::Other)] to: String,
         /// Message to broadcast
         #[arg(long)] message: String,
         /// Optional: override timeout in milliseconds (default 5000)
@@ -267,7 +659,7 @@ pub enum BroadcastCmd {
         /// Project name (defaults to current directory name)
         #[arg(long)] project: Option<String>,
         /// Target: @all, @role, or agent name(s) (comma-separated)
-        #[arg(long)] to: String,
+        #[arg(long, value_hint = clap::ValueHint::Other)] to: String,
         /// Message to broadcast
         #[arg(long)] message: String,
         /// Optional: override timeout in milliseconds (default 5000)
@@ -285,6 +677,62 @@ pub enum Format {
     Json 
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum SchemaTarget {
+    Project,
+    Providers,
+    All,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum SchemaOutputFormat {
+    Json,
+    Yaml,
+}
+
+/// How a `--project` selector is matched
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum SelectBy {
+    Id,
+    Name,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum SessionStatusArg {
+    Active,
+    Expired,
+    /// Sessions that failed to start (e.g. cursor create-chat errors)
+    Invalid,
+}
+
+/// Events `supervisor subscribe --on` accepts
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum SupervisorEventArg {
+    /// A send target answered (exit code 0)
+    SendCompleted,
+    /// A send target failed, timed out or had no provider
+    SendFailed,
+    /// An assigned task was moved to `done`
+    TaskDone,
+}
+
+/// Where `agent run` starts the REPL
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum AgentBackendArg {
+    Tmux,
+    /// Detached background process, logged to NDJSON; no tmux needed
+    Process,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum ColorMode {
+    /// Style only when stdout is a TTY and NO_COLOR is unset
+    Auto,
+    Always,
+    Never,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum ContextCmd {
     /// Git repository context (status, diff, log)
@@ -4,51 +4,142 @@ use super::commands::*;
 use crate::commands::*;
 
 impl Cli {
+    /// Tracing filter for this invocation; `--quiet` wins over `-v`
+    pub fn log_filter(&self) -> &'static str {
+        if self.quiet { "warn" } else { verbosity_filter(self.verbose) }
+    }
+
     /// Execute the parsed CLI command
-    pub fn execute(self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn execute(self) -> Result<(), crate::utils::CliError> {
+        crate::utils::style::set_color_mode(self.color);
+        crate::utils::style::set_quiet(self.quiet);
+        crate::logging::set_log_base(self.log_dir.as_deref());
+        if self.no_redaction {
+            crate::logging::disable_redaction();
+        }
         match self.cmd {
-            Commands::Init { config_dir, force, skip_db } => 
-                run_init(config_dir.as_deref(), force, skip_db),
+            Commands::Init { config_dir, force, skip_db, format } =>
+                run_init(config_dir.as_deref(), force, skip_db, format),
             Commands::Config { cmd } => match cmd {
-                ConfigCmd::Validate { project_file, providers_file, format } => {
-                    run_config_validate(project_file.as_deref(), providers_file.as_deref(), format)
+                ConfigCmd::Validate { project_file, providers_file, format, strict } => {
+                    run_config_validate(project_file.as_deref(), providers_file.as_deref(), format, strict)
                 }
-                ConfigCmd::Init { dir, force } => run_config_init(dir.as_deref(), force),
+                ConfigCmd::Init { dir, force, dry_run, format } => run_config_init(dir.as_deref(), force, dry_run, format),
+                ConfigCmd::Sync { project_file, providers_file, db_path, prune, dry_run, format } =>
+                    run_config_sync(project_file.as_deref(), providers_file.as_deref(), db_path.as_deref(), prune, dry_run, format),
+                ConfigCmd::Schema { which, format, output, validate_against } =>
+                    run_config_schema(which, format, output.as_deref(), validate_against.as_deref()),
             },
-            Commands::Doctor { format, ndjson_sample, snapshot } => 
-                run_doctor(format, ndjson_sample.as_deref(), snapshot.as_deref()),
+            Commands::Doctor { format, ndjson_sample, snapshot, check_keys, watch, interval, no_cache } => {
+                if watch {
+                    run_doctor_watch(format, &interval, check_keys)
+                } else {
+                    run_doctor(format, ndjson_sample.as_deref(), snapshot.as_deref(), check_keys, no_cache)
+                }
+            }
             Commands::Db { cmd } => match cmd {
                 DbCmd::Init { db_path } => run_db_init(db_path.as_deref()),
                 DbCmd::ProjectAdd { name, db_path } => run_project_add(&name, db_path.as_deref()),
-                DbCmd::AgentAdd { project, name, role, provider, model, allowed_tool, system_prompt, db_path } =>
-                    run_agent_add(&project, &name, &role, &provider, &model, &allowed_tool, &system_prompt, db_path.as_deref()),
+                DbCmd::AgentAdd { project, by, name, role, provider, model, allowed_tool, system_prompt, db_path, create_project } =>
+                    run_agent_add(&AgentAddRequest {
+                        project: &project,
+                        name: &name,
+                        role: &role,
+                        provider: &provider,
+                        model: &model,
+                        allowed_tools: &allowed_tool,
+                        system_prompt: &system_prompt,
+                        db_path: db_path.as_deref(),
+                        by,
+                        create_project,
+                    }),
+                DbCmd::ProjectRemove { name, cascade, yes, db_path } => run_project_remove(&name, cascade, yes, db_path.as_deref()),
+                DbCmd::Migrate { db_path, to, dry_run } => run_db_migrate(db_path.as_deref(), to, dry_run),
+                DbCmd::Doctor { fix, providers_file, db_path, format, allow_newer_schema } =>
+                    run_db_doctor(fix, providers_file.as_deref(), db_path.as_deref(), format, allow_newer_schema),
             },
-            Commands::Send { project_file, providers_file, to, message, session_id, chat_id, timeout_ms, format, progress } => {
-                run_send(project_file.as_deref(), providers_file.as_deref(), &to, &message, session_id.as_deref(), chat_id.as_deref(), timeout_ms, format, progress)
+            Commands::Project { cmd } => match cmd {
+                ProjectCmd::Rename { from, to, db_path, config_dir, logs_dir, update_config } =>
+                    run_project_rename(&from, &to, db_path.as_deref(), config_dir.as_deref(), logs_dir.as_deref(), update_config),
+            },
+            Commands::Logs { cmd } => match cmd {
+                LogsCmd::Show { project, role, follow, level, no_color, agent } =>
+                    run_logs_show(&project, role.as_deref(), follow, level.as_deref(), no_color, agent.as_deref()),
+                LogsCmd::Migrate { project, format } => run_logs_migrate(&project, format),
+            },
+            Commands::Send { project_file, providers_file, to, message, session_id, chat_id, timeout_ms, inactivity_timeout_ms, format, progress, fail_fast, yes, project, strict_placeholders, manifest, cache, no_cache, cache_ttl, session_ttl, notify_supervisor, echo_prompt } => {
+                let cache = if cache { Some(true) } else if no_cache { Some(false) } else { None };
+                run_send(&SendRequest {
+                    project_path_opt: project_file.as_deref(),
+                    providers_path_opt: providers_file.as_deref(),
+                    session_id_opt: session_id.as_deref(),
+                    chat_id_opt: chat_id.as_deref(),
+                    timeout_ms_flag: timeout_ms,
+                    format,
+                    progress: progress && !self.quiet,
+                    quiet: self.quiet,
+                    fail_fast,
+                    yes,
+                    project_opt: project.as_deref(),
+                    strict_placeholders,
+                    inactivity_timeout_ms_flag: inactivity_timeout_ms,
+                    manifest_path: manifest.as_deref(),
+                    cache,
+                    cache_ttl: cache_ttl.as_deref(),
+                    session_ttl: session_ttl.as_deref(),
+                    notify: if notify_supervisor { SupervisorNotify::Dispatch } else { SupervisorNotify::Record },
+                    echo_prompt,
+                    ..SendRequest::new(&to, &message)
+                })
             },
             Commands::Session { cmd } => match cmd {
-                SessionCmd::Start { project_file, providers_file, agent } =>
-                    run_session_start(project_file.as_deref(), providers_file.as_deref(), &agent),
-                SessionCmd::List { project_file, project, agent, provider, format } =>
-                    run_session_list(project_file.as_deref(), project.as_deref(), agent.as_deref(), provider.as_deref(), format),
+                SessionCmd::Start { project_file, providers_file, agent, session_ttl } =>
+                    run_session_start(project_file.as_deref(), providers_file.as_deref(), agent.as_deref(), session_ttl.as_deref(), &crate::utils::RandomIds),
+                SessionCmd::List { project_file, project, agent, provider, status, format, allow_newer_schema } =>
+                    run_session_list(project_file.as_deref(), project.as_deref(), agent.as_deref(), provider.as_deref(), format, status, allow_newer_schema),
+                SessionCmd::Stats { project, by, since, db_path, format } =>
+                    run_session_stats(&project, since.as_deref(), db_path.as_deref(), format, by),
                 SessionCmd::Resume { conversation_id, timeout_ms } =>
                     run_session_resume(&conversation_id, timeout_ms),
-                SessionCmd::Cleanup { project_file, dry_run, format } =>
-                    run_session_cleanup(project_file.as_deref(), dry_run, format),
+                SessionCmd::Describe { conversation_id, format } =>
+                    run_session_describe(&conversation_id, format),
+                SessionCmd::Cleanup { project_file, dry_run, older_than, format } =>
+                    run_session_cleanup(project_file.as_deref(), dry_run, older_than.as_deref(), format),
             },
             Commands::Agent { cmd } => match cmd {
-                AgentCmd::Run { project_file, providers_file, project, agent, role, provider, model, workdir, no_logs, logs_dir, timeout_ms } =>
-                    run_agent_run(project_file.as_deref(), providers_file.as_deref(), project.as_deref(), &agent, role.as_deref(), provider.as_deref(), model.as_deref(), workdir.as_deref(), no_logs, logs_dir.as_deref(), timeout_ms),
-                AgentCmd::Attach { project_file, project, agent, timeout_ms } =>
-                    run_agent_attach(project_file.as_deref(), project.as_deref(), &agent, timeout_ms),
-                AgentCmd::Stop { project_file, project, agent, timeout_ms } =>
-                    run_agent_stop(project_file.as_deref(), project.as_deref(), &agent, timeout_ms),
+                AgentCmd::Run { project_file, providers_file, project, agent, role, provider, model, workdir, no_logs, logs_dir, timeout_ms, no_verify, strict_placeholders, backend, detached_check, format } =>
+                    run_agent_run(&AgentRunRequest {
+                        project_file: project_file.as_deref(),
+                        providers_file: providers_file.as_deref(),
+                        project_name: project.as_deref(),
+                        agent_name: agent.as_deref(),
+                        role_override: role.as_deref(),
+                        provider_override: provider.as_deref(),
+                        model_override: model.as_deref(),
+                        workdir: workdir.as_deref(),
+                        no_logs,
+                        logs_dir: logs_dir.as_deref(),
+                        timeout_ms,
+                        no_verify,
+                        strict_placeholders,
+                        backend,
+                        detached_check,
+                        format,
+                    }),
+                AgentCmd::Attach { project_file, project, agent, timeout_ms, backend, read_only } =>
+                    run_agent_attach(project_file.as_deref(), project.as_deref(), agent.as_deref(), timeout_ms, backend, read_only),
+                AgentCmd::Stop { project_file, project, agent, timeout_ms, backend } =>
+                    run_agent_stop(project_file.as_deref(), project.as_deref(), agent.as_deref(), timeout_ms, backend),
+                AgentCmd::Remove { project, by, agent, db_path, force, kill_tmux, format } =>
+                    run_agent_remove(&project, &agent, db_path.as_deref(), force, kill_tmux, format, by),
+                AgentCmd::Info { project, by, agent, db_path, include_sessions, session_limit, full, format } =>
+                    run_agent_info(&project, &agent, db_path.as_deref(), if include_sessions { session_limit } else { 0 }, full, format, by),
             },
             Commands::Broadcast { cmd } => match cmd {
                 BroadcastCmd::Oneshot { project_file, providers_file, project, to, message, timeout_ms, format, progress } =>
-                    run_broadcast_oneshot(project_file.as_deref(), providers_file.as_deref(), project.as_deref(), &to, &message, timeout_ms, format, progress),
+                    run_broadcast_oneshot(project_file.as_deref(), providers_file.as_deref(), project.as_deref(), &to, &message, timeout_ms, format, progress && !self.quiet),
                 BroadcastCmd::Repl { project_file, project, to, message, timeout_ms, format, progress } =>
-                    run_broadcast_repl(project_file.as_deref(), project.as_deref(), &to, &message, timeout_ms, format, progress),
+                    run_broadcast_repl(project_file.as_deref(), project.as_deref(), &to, &message, timeout_ms, format, progress && !self.quiet),
             },
             Commands::Monitor { project, duration, format, output } => {
                 let project_name = project.unwrap_or_else(|| std::env::current_dir()
@@ -64,6 +155,36 @@ impl Cli {
                     .unwrap_or_else(|| "default".to_string()));
                 run_tui(&project_name, refresh_rate)
             },
+            Commands::Stats { project, by, since, weekly, db_path, format } =>
+                run_stats(&project, since.as_deref(), weekly, db_path.as_deref(), format, by),
+            Commands::Completions { shell, shell_flag } => match shell.or(shell_flag) {
+                Some(shell) => run_completions(shell),
+                None => Err(crate::utils::CliError::validation("completions", "missing shell (bash, zsh, fish, elvish, powershell)")),
+            },
+            Commands::Upgrade { check_only, prerelease, format } => run_upgrade(check_only, prerelease, format),
+            Commands::CompleteAgents { project, project_file, db_path } =>
+                run_complete_agents(project.as_deref(), project_file.as_deref(), db_path.as_deref()),
+            Commands::IngestLog { log_file, project, role, agent, provider, redact } =>
+                run_ingest_log(std::io::stdin().lock(), &log_file, &project, &role, &agent, &provider, &redact),
+            Commands::DeliverNotification { project_file, providers_file, project, to, message } =>
+                run_deliver_notification(&project_file, &providers_file, project.as_deref(), &to, &message),
+            Commands::CompleteTargets { project, project_file, db_path } =>
+                run_complete_targets(project.as_deref(), project_file.as_deref(), db_path.as_deref()),
+            Commands::CompleteProjects { project_file, db_path } =>
+                run_complete_projects(project_file.as_deref(), db_path.as_deref()),
+            Commands::Cache { cmd } => match cmd {
+                CacheCmd::Clear { expired, db_path, format } => run_cache_clear(expired, db_path.as_deref(), format),
+                CacheCmd::Stats { db_path, format } => run_cache_stats(db_path.as_deref(), format),
+            },
+            Commands::Supervisor { cmd } => match cmd {
+                SupervisorCmd::Subscribe { project, by, agent, on, from, db_path, format } =>
+                    run_supervisor_subscribe(&project, &agent, &on, &from, db_path.as_deref(), format, by),
+                SupervisorCmd::Unsubscribe { project, by, agent, on, from, db_path, format } =>
+                    run_supervisor_unsubscribe(&project, &agent, &on, from.as_deref(), db_path.as_deref(), format, by),
+            },
+            Commands::Task { cmd } => match cmd {
+                TaskCmd::Status { task, status, db_path, format } => run_task_status(&task, &status, db_path.as_deref(), format),
+            },
             Commands::Context { cmd } => match cmd {
                 ContextCmd::Git { kind, format, max_bytes, max_lines, pathspec, no_color, strict, staged, since, until, limit } =>
                     run_context_git(kind, format, max_bytes, max_lines, pathspec.as_deref(), no_color, strict, staged, since.as_deref(), until.as_deref(), limit),
@@ -71,3 +192,13 @@ impl Cli {
         }
     }
 }
+
+/// Map the `-v` count to a tracing filter: warn, info, debug, then trace
+pub fn verbosity_filter(verbose: u8) -> &'static str {
+    match verbose {
+        0 => "warn",
+        1 => "info",
+        2 => "debug",
+        _ => "trace",
+    }
+}
@@ -2,28 +2,81 @@
 
 use std::fs;
 use std::time::{Duration, Instant};
-use config_model::{parse_project_yaml, parse_providers_yaml};
-use crate::utils::{resolve_config_paths, handle_missing_config, DEFAULT_AGENT_TIMEOUT_MS, exit_with, with_agent_lock};
+use config_model::{parse_project_yaml, parse_providers_yaml, requires_allowed_tools, AgentConfig, ProvidersConfig};
+use db::{open_or_create_db, sync_project_from_config, find_project_id, find_agent_by_name, count_agent_dependents, delete_agent};
+use crate::commands::config::sync_project_with_progress;
+use crate::cli::commands::{AgentBackendArg, Format, SelectBy};
+use crate::utils::{resolve_config_paths, handle_missing_config, DEFAULT_AGENT_TIMEOUT_MS, CliError, ProviderErrorKind, with_agent_lock, resolve_db_path, project_selector, select_agent, capped_timeout_ms, is_process_running, expand_cmd_path};
 use crate::tmux::manager::TmuxManager;
-use crate::logging::{emit_start_event, emit_end_event, emit_metrics_event, emit_failure_metrics_event};
+use crate::backend::{ingest_lines, AgentBackend, AgentTarget, LaunchSpec, ProcessBackend, Started, TmuxBackend};
+use crate::repository::{RepositoryManager, agent_repository::AgentInfo};
+use crate::providers::{apply_agent_identity, AgentIdentity};
+use crate::logging::{log_base, agent_log_path, agent_log_path_for_read, emit_start_event, emit_end_event, emit_metrics_event, emit_failure_metrics_event, set_redaction};
+
+/// How long `agent run --backend process` waits before checking the provider is still alive
+const PROCESS_VERIFY_DELAY: Duration = Duration::from_millis(300);
+/// How long `agent run --detached-check` lets the REPL settle before looking at it again
+const DETACHED_CHECK_DELAY: Duration = Duration::from_millis(1000);
+
+/// Parsed `agent run` flags; start from `AgentRunRequest::default()` and set the flags that were given
+#[derive(Clone, Copy)]
+pub struct AgentRunRequest<'a> {
+    pub project_file: Option<&'a str>,
+    pub providers_file: Option<&'a str>,
+    /// Project name to run under; defaults to the YAML `project:` name
+    pub project_name: Option<&'a str>,
+    /// Agent to start; asked for interactively when `None`
+    pub agent_name: Option<&'a str>,
+    pub role_override: Option<&'a str>,
+    pub provider_override: Option<&'a str>,
+    pub model_override: Option<&'a str>,
+    pub workdir: Option<&'a str>,
+    pub no_logs: bool,
+    /// The project's own log directory; the shared logs base when `None`
+    pub logs_dir: Option<&'a str>,
+    pub timeout_ms: Option<u64>,
+    pub no_verify: bool,
+    /// Reject unknown `{placeholders}` in the system prompt and provider args instead of leaving them as is
+    pub strict_placeholders: bool,
+    pub backend: AgentBackendArg,
+    /// Fail when the REPL does not survive its launch
+    pub detached_check: bool,
+    pub format: Format,
+}
+
+impl Default for AgentRunRequest<'_> {
+    /// An `agent run` with every flag at its command-line default
+    fn default() -> Self {
+        AgentRunRequest {
+            project_file: None,
+            providers_file: None,
+            project_name: None,
+            agent_name: None,
+            role_override: None,
+            provider_override: None,
+            model_override: None,
+            workdir: None,
+            no_logs: false,
+            logs_dir: None,
+            timeout_ms: None,
+            no_verify: false,
+            strict_placeholders: false,
+            backend: AgentBackendArg::Tmux,
+            detached_check: false,
+            format: Format::Text,
+        }
+    }
+}
 
 /// Run agent run command
-pub fn run_agent_run(
-    project_file: Option<&str>,
-    providers_file: Option<&str>,
-    project_name: Option<&str>,
-    agent_name: &str,
-    role_override: Option<&str>,
-    provider_override: Option<&str>,
-    model_override: Option<&str>,
-    workdir: Option<&str>,
-    no_logs: bool,
-    logs_dir: Option<&str>,
-    timeout_ms: Option<u64>
-) -> Result<(), Box<dyn std::error::Error>> {
+pub fn run_agent_run(request: &AgentRunRequest) -> Result<(), CliError> {
+    let AgentRunRequest {
+        project_file, providers_file, project_name, agent_name, role_override, provider_override, model_override,
+        workdir, no_logs, logs_dir, timeout_ms, no_verify, strict_placeholders, backend, detached_check, format,
+    } = *request;
     let start_time = Instant::now();
     // Cap tmux timeouts to 5s
-    let effective_ms = timeout_ms.unwrap_or(DEFAULT_AGENT_TIMEOUT_MS).min(DEFAULT_AGENT_TIMEOUT_MS);
+    let effective_ms = capped_timeout_ms(timeout_ms, DEFAULT_AGENT_TIMEOUT_MS);
     let timeout = Duration::from_millis(effective_ms);
     
     // Resolve config paths
@@ -35,88 +88,236 @@ pub fn run_agent_run(
     // Load configurations
     let proj_s = fs::read_to_string(&project_path)?;
     let prov_s = fs::read_to_string(&providers_path)?;
-    let project = parse_project_yaml(&proj_s).map_err(|e| format!("project: {}", e))?;
-    let providers = parse_providers_yaml(&prov_s).map_err(|e| format!("providers: {}", e))?;
+    let project = parse_project_yaml(&proj_s).map_err(|e| CliError::validation("project", e))?;
+    set_redaction(&project.defaults.redaction).map_err(|e| CliError::validation("project", e))?;
+    let providers = parse_providers_yaml(&prov_s).map_err(|e| CliError::validation("providers", e))?;
     
     // Determine project name
     let project_name = project_name.unwrap_or(&project.project);
     
-    // Find agent configuration
+    // Find agent configuration, asking for it when not given
+    let selected = select_agent(agent_name, &project.agents)?;
+    let agent_name = selected.as_str();
     let agent = project.agents.iter()
         .find(|a| a.name == agent_name)
-        .ok_or_else(|| format!("Agent '{}' not found in project '{}'", agent_name, project_name))?;
-    
-    // Apply overrides
-    let role = role_override.unwrap_or(&agent.role);
-    let provider = provider_override.unwrap_or(&agent.provider);
-    let _model = model_override.unwrap_or(&agent.model);
-    
-    // Get provider configuration
-    let provider_config = providers.providers.get(provider)
-        .ok_or_else(|| format!("Provider '{}' not found in configuration", provider))?;
-    
-    // Build tmux session and window names
-    let session_name = format!("proj:{}", project_name);
-    let window_name = format!("{}:{}", role, agent_name);
+        .ok_or_else(|| CliError::validation("agent", format!("'{}' not found in project '{}'", agent_name, project_name)))?;
+    
+    // Apply overrides, then resolve the provider template with the agent's identity placeholders
+    let agent = effective_agent(agent, role_override, provider_override, model_override, &providers)?;
+    let (role, provider) = (agent.role.as_str(), agent.provider.as_str());
+    let provider_config = &providers.providers[provider];
+    let identity = AgentIdentity { project: project_name, agent_name, agent_role: role, model: &agent.model };
+    let (provider_config, system_prompt) = apply_agent_identity(provider_config, &agent.system_prompt, &identity, strict_placeholders)
+        .map_err(|e| CliError::validation("placeholders", format!("agent {}: {}", agent_name, e)))?;
+    
+    // --logs-dir names the project's own directory; otherwise use the shared logs base.
+    // The pane output is the agent's own, so it goes to the agent's file rather than the role's.
+    let log_file = (!no_logs).then(|| match logs_dir {
+        Some(dir) => format!("{}/{}.ndjson", dir.trim_end_matches('/'), agent_name),
+        None => agent_log_path(&log_base(), project_name, agent_name, role),
+    });
+    let spec = LaunchSpec {
+        provider: provider.to_string(),
+        cmd: expand_cmd_path(&provider_config.cmd),
+        args: repl_command_args(&provider_config.repl_args, &system_prompt, &agent.allowed_tools),
+        workdir: workdir.map(str::to_string),
+        log_file,
+    };
+    let launch_config = launch_config_text(&agent);
+    let target = AgentTarget { project: project_name, role, agent: agent_name };
     
     // Execute with agent lock to prevent race conditions
     with_agent_lock(project_name, agent_name, timeout, || {
-        // Create tmux manager and run agent
-        let tmux_manager = TmuxManager::new(timeout);
-    
-    // Step 1: Check if session exists
-    let session_exists = tmux_manager.has_session(&session_name)?;
-    
-    // Step 2: Create session if it doesn't exist
-    if !session_exists {
-        tmux_manager.create_session(&session_name)?;
-    }
-    
-    // Step 3: Check if window already exists
-    let window_exists = tmux_manager.window_exists(&session_name, &window_name)?;
-    
-    if window_exists {
-        println!("Agent '{}' is already running in tmux session '{}'", agent_name, session_name);
-        return Ok(());
-    }
-    
-    // Step 4: Create new window for the agent
-    tmux_manager.create_window(&session_name, &window_name)?;
-    
-    // Step 5: Set up logging if not disabled
-    if !no_logs {
-        let default_log_dir = format!("./logs/{}", project_name);
-        let log_dir = logs_dir.unwrap_or(&default_log_dir);
-        let _ = fs::create_dir_all(log_dir);
-        let log_file = format!("{}/{}.ndjson", log_dir, role);
+        let report = match backend {
+            AgentBackendArg::Tmux => start_in_tmux(&target, &spec, timeout, start_time, no_verify, &launch_config, detached_check)?,
+            AgentBackendArg::Process => {
+                // The process backend finds its agents through the database
+                let db_path = resolve_db_path();
+                let conn = open_or_create_db(&db_path)?;
+                let mut synced = project.clone();
+                synced.project = project_name.to_string();
+                // JSON output is the report alone
+                match format {
+                    Format::Text => sync_project_with_progress(&conn, &synced)?,
+                    Format::Json => sync_project_from_config(&conn, &synced)?,
+                };
+                start_as_process(&target, &spec, ProcessBackend::new(db_path), start_time, no_verify, &launch_config, detached_check)?
+            }
+        };
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        match format {
+            Format::Text => {
+                println!("{}", report.text(agent_name, duration_ms));
+                // A background process has no pane to attach to; point at its log instead
+                if let (true, Some(_), Some(log_file)) = (report.launched(), report.pid, &spec.log_file) {
+                    println!("Follow its output with: tail -f {}", log_file);
+                }
+            }
+            Format::Json => println!("{}", serde_json::to_string(&report).map_err(|e| CliError::Other(e.to_string()))?),
+        }
+        if !report.launched() {
+            return Ok(());
+        }
         
-        // Set up pipe-pane for logging
-        tmux_manager.setup_pipe_pane(&session_name, &window_name, &log_file)?;
+        // Emit metrics for startup duration
+        if let Err(e) = emit_metrics_event(project_name, role, agent_name, provider, "startup", duration_ms, "success", None) {
+            eprintln!("Warning: Failed to emit metrics event: {}", e);
+        }
         
-        // Emit start event
-        if let Err(e) = emit_start_event(project_name, role, agent_name, provider) {
+        Ok(())
+    })
+}
+
+/// Outcome of `agent run`: the JSON document of `--format json`, and the text line otherwise
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct AgentRunReport {
+    /// `ok`, or `already_running` when the agent was up before this run
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tmux_session: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tmux_window: Option<String>,
+    /// `session:window`, ready for `tmux send-keys -t`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tmux_target: Option<String>,
+    /// PID of the background process (process backend)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+}
+
+impl AgentRunReport {
+    /// Agent in tmux window `window`; `launched` is false when it was already running
+    pub fn tmux(target: &AgentTarget, window: &str, launched: bool) -> Self {
+        let session = target.session_name();
+        Self {
+            status: if launched { "ok" } else { "already_running" },
+            tmux_target: Some(format!("{}:{}", session, window)),
+            tmux_session: Some(session),
+            tmux_window: Some(window.to_string()),
+            pid: None,
+        }
+    }
+
+    /// Agent running as background process `pid`
+    pub fn process(pid: u32, launched: bool) -> Self {
+        Self {
+            status: if launched { "ok" } else { "already_running" },
+            tmux_session: None,
+            tmux_window: None,
+            tmux_target: None,
+            pid: Some(pid),
+        }
+    }
+
+    pub fn launched(&self) -> bool {
+        self.status == "ok"
+    }
+
+    /// Human line for `agent_name`, started in `took_ms`
+    pub fn text(&self, agent_name: &str, took_ms: u64) -> String {
+        match (&self.tmux_session, &self.tmux_target, self.launched()) {
+            (Some(session), Some(target), true) => format!("Agent '{}' started in tmux session '{}' (target '{}', took {}ms)", agent_name, session, target, took_ms),
+            (Some(session), Some(target), false) => format!("Agent '{}' is already running in tmux session '{}' (target '{}')", agent_name, session, target),
+            (_, _, true) => format!("Agent '{}' started as background process {} (took {}ms)", agent_name, self.pid.unwrap_or_default(), took_ms),
+            (_, _, false) => format!("Agent '{}' is already running as background process {}", agent_name, self.pid.unwrap_or_default()),
+        }
+    }
+}
+
+/// The agent as `agent run` launches it: `--role`, `--provider` and `--model` applied over its config.
+/// The effective provider must be configured, and its allowed_tools policy still hold.
+pub fn effective_agent(
+    agent: &AgentConfig,
+    role_override: Option<&str>,
+    provider_override: Option<&str>,
+    model_override: Option<&str>,
+    providers: &ProvidersConfig,
+) -> Result<AgentConfig, CliError> {
+    let mut effective = agent.clone();
+    if let Some(role) = role_override { effective.role = role.to_string(); }
+    if let Some(provider) = provider_override { effective.provider = provider.to_string(); }
+    if let Some(model) = model_override { effective.model = model.to_string(); }
+    if !providers.providers.contains_key(&effective.provider) {
+        return Err(CliError::provider(effective.provider.as_str(), ProviderErrorKind::NotConfigured));
+    }
+    if effective.allowed_tools.is_empty() && requires_allowed_tools(providers, &effective.provider) {
+        return Err(CliError::validation("agent", format!("{} (provider={}): allowed_tools must not be empty", effective.name, effective.provider)));
+    }
+    Ok(effective)
+}
+
+/// REPL command line: `repl_args` with `{system_prompt}` and `{allowed_tools}` filled in
+pub fn repl_command_args(repl_args: &[String], system_prompt: &str, allowed_tools: &[String]) -> Vec<String> {
+    let allowed = allowed_tools.join(",");
+    repl_args.iter()
+        .map(|arg| arg.replace("{system_prompt}", system_prompt).replace("{allowed_tools}", &allowed))
+        .collect()
+}
+
+/// `text` of the `start` event of `agent run`: the role, provider, model and tools actually launched
+pub fn launch_config_text(agent: &AgentConfig) -> String {
+    serde_json::json!({
+        "role": agent.role,
+        "provider": agent.provider,
+        "model": agent.model,
+        "allowed_tools": agent.allowed_tools,
+    }).to_string()
+}
+
+/// Error kind of a provider that never came up: a launch failure (exit 3), or under `--detached-check`,
+/// which asks whether the REPL survived its launch, a provider failure (exit 4)
+fn startup_failure(detail: String, detached_check: bool) -> ProviderErrorKind {
+    if detached_check { ProviderErrorKind::Failed(detail) } else { ProviderErrorKind::NotStarted(detail) }
+}
+
+/// Start the agent in its tmux window, then check the provider came up in the pane
+fn start_in_tmux(target: &AgentTarget, spec: &LaunchSpec, timeout: Duration, start_time: Instant, no_verify: bool, launch_config: &str, detached_check: bool) -> Result<AgentRunReport, CliError> {
+    let (project_name, role, agent_name, provider) = (target.project, target.role, target.agent, spec.provider.as_str());
+    let (session_name, window_name) = (target.session_name(), target.window_name());
+    let tmux = TmuxBackend::new(TmuxManager::new(timeout));
+    
+    if let Started::AlreadyRunning { .. } = tmux.start(target, spec)? {
+        // Possibly under its legacy `{role}:{agent}` name
+        let running = tmux.agent_window(target)?.unwrap_or(window_name);
+        return Ok(AgentRunReport::tmux(target, &running, false));
+    }
+    if spec.log_file.is_some() {
+        if let Err(e) = emit_start_event(project_name, role, agent_name, provider, Some(launch_config)) {
             eprintln!("Warning: Failed to emit start event: {}", e);
         }
     }
     
-    // Step 6: Set working directory if specified
-    if let Some(workdir) = workdir {
-        tmux_manager.send_keys(&session_name, &window_name, &format!("cd {}", workdir))?;
+    // Confirm the provider process actually launched in the pane
+    if !no_verify {
+        let remaining = timeout.saturating_sub(start_time.elapsed());
+        if let Err(pane_tail) = verify_provider_started(tmux.manager(), &session_name, &window_name, &spec.cmd, remaining) {
+            let verify_duration = start_time.elapsed().as_millis() as u64;
+            if let Err(metrics_err) = emit_failure_metrics_event(project_name, role, agent_name, provider, "startup", "provider_not_started", verify_duration, &pane_tail) {
+                eprintln!("Warning: Failed to emit failure metrics: {}", metrics_err);
+            }
+            // Drop the dead window so a later `agent run` starts fresh
+            let _ = tmux.manager().kill_window(&session_name, &window_name);
+            let detail = format!("tmux window '{}:{}' (use --no-verify for slow-starting providers)\n{}", session_name, window_name, pane_tail);
+            return Err(CliError::provider(spec.cmd.as_str(), startup_failure(detail, detached_check)));
+        }
     }
     
-    // Step 7: Start the provider command
-    let mut args = provider_config.repl_args.clone();
-    for arg in &mut args {
-        *arg = arg.replace("{system_prompt}", &agent.system_prompt)
-                 .replace("{allowed_tools}", &agent.allowed_tools.join(","));
+    // A REPL that launched but died straight away (bad flags, missing login) leaves the shell behind
+    if detached_check {
+        std::thread::sleep(DETACHED_CHECK_DELAY);
+        if let Err(e) = check_repl_alive(tmux.manager(), &session_name, &window_name, &spec.cmd) {
+            let check_duration = start_time.elapsed().as_millis() as u64;
+            if let Err(metrics_err) = emit_failure_metrics_event(project_name, role, agent_name, provider, "startup", "provider_exited", check_duration, &e.to_string()) {
+                eprintln!("Warning: Failed to emit failure metrics: {}", metrics_err);
+            }
+            let _ = tmux.manager().kill_window(&session_name, &window_name);
+            return Err(e);
+        }
     }
     
-    let cmd_line = format!("{} {}", provider_config.cmd, args.join(" "));
-    tmux_manager.send_keys(&session_name, &window_name, &cmd_line)?;
-    
-    // Step 8: Healthcheck post-start to confirm ready state
+    // Healthcheck post-start to confirm ready state
     let healthcheck_start = Instant::now();
-    if let Err(e) = perform_healthcheck(&tmux_manager, &session_name, &window_name, provider, timeout) {
+    if let Err(e) = perform_healthcheck(tmux.manager(), &session_name, &window_name, provider, timeout) {
         let healthcheck_duration = healthcheck_start.elapsed().as_millis() as u64;
         eprintln!("Warning: Healthcheck failed for agent '{}': {}", agent_name, e);
         
@@ -127,27 +328,97 @@ pub fn run_agent_run(
         // Don't fail the startup, just warn
     }
     
-        let duration_ms = start_time.elapsed().as_millis() as u64;
-        
-        // Emit metrics for startup duration
-        if let Err(e) = emit_metrics_event(project_name, role, agent_name, provider, "startup", duration_ms, "success", None) {
-            eprintln!("Warning: Failed to emit metrics event: {}", e);
+    Ok(AgentRunReport::tmux(target, &window_name, true))
+}
+
+/// Start the agent as a background process
+fn start_as_process(target: &AgentTarget, spec: &LaunchSpec, backend: ProcessBackend, start_time: Instant, no_verify: bool, launch_config: &str, detached_check: bool) -> Result<AgentRunReport, CliError> {
+    let (project_name, role, agent_name, provider) = (target.project, target.role, target.agent, spec.provider.as_str());
+    let pid = match backend.start(target, spec)? {
+        Started::AlreadyRunning { pid } => return Ok(AgentRunReport::process(pid.unwrap_or_default(), false)),
+        Started::Launched { pid } => pid.unwrap_or_default(),
+    };
+    if spec.log_file.is_some() {
+        if let Err(e) = emit_start_event(project_name, role, agent_name, provider, Some(launch_config)) {
+            eprintln!("Warning: Failed to emit start event: {}", e);
         }
-        
-        println!("Agent '{}' started in tmux session '{}' (took {}ms)", agent_name, session_name, duration_ms);
-        Ok(())
+    }
+    
+    // A provider that cannot start (bad flags, missing login) usually exits right away
+    if !no_verify {
+        std::thread::sleep(PROCESS_VERIFY_DELAY);
+        if !is_process_running(pid) {
+            let verify_duration = start_time.elapsed().as_millis() as u64;
+            if let Err(metrics_err) = emit_failure_metrics_event(project_name, role, agent_name, provider, "startup", "provider_not_started", verify_duration, "process exited") {
+                eprintln!("Warning: Failed to emit failure metrics: {}", metrics_err);
+            }
+            // Looking the process up expires its session row, so a later `agent run` starts fresh
+            let _ = backend.running_process(target);
+            let detail = format!("background process {} exited right after launch (use --no-verify for slow-starting providers)", pid);
+            return Err(CliError::provider(spec.cmd.as_str(), startup_failure(detail, detached_check)));
+        }
+    }
+    if detached_check {
+        std::thread::sleep(DETACHED_CHECK_DELAY);
+        if !is_process_running(pid) {
+            let check_duration = start_time.elapsed().as_millis() as u64;
+            if let Err(metrics_err) = emit_failure_metrics_event(project_name, role, agent_name, provider, "startup", "provider_exited", check_duration, "process exited") {
+                eprintln!("Warning: Failed to emit failure metrics: {}", metrics_err);
+            }
+            let _ = backend.running_process(target);
+            return Err(CliError::provider(spec.cmd.as_str(), ProviderErrorKind::Failed(format!(
+                "background process {} exited after launch", pid
+            ))));
+        }
+    }
+    
+    Ok(AgentRunReport::process(pid, true))
+}
+
+/// The backend `agent attach`/`agent stop` talk to; without `--backend`, the one `target` was started with
+fn backend_for(kind: Option<AgentBackendArg>, timeout: Duration, target: &AgentTarget) -> Result<Box<dyn AgentBackend>, CliError> {
+    let kind = match kind {
+        Some(kind) => kind,
+        None => recorded_backend(target)?,
+    };
+    Ok(match kind {
+        AgentBackendArg::Tmux => Box::new(TmuxBackend::new(TmuxManager::new(timeout))),
+        AgentBackendArg::Process => Box::new(ProcessBackend::new(resolve_db_path())),
     })
 }
 
+/// `process` while the database records a live background process for `target`, `tmux` otherwise
+fn recorded_backend(target: &AgentTarget) -> Result<AgentBackendArg, CliError> {
+    let db_path = resolve_db_path();
+    // Without a database nothing was started as a process; do not create one just to look
+    if !std::path::Path::new(&db_path).exists() {
+        return Ok(AgentBackendArg::Tmux);
+    }
+    Ok(match ProcessBackend::new(db_path).running_process(target)? {
+        Some(_) => AgentBackendArg::Process,
+        None => AgentBackendArg::Tmux,
+    })
+}
+
+/// Run the hidden `__ingest-log` command: NDJSON-log `input` (stdin) until the agent's process exits
+pub fn run_ingest_log(input: impl std::io::BufRead, log_file: &str, project: &str, role: &str, agent: &str, provider: &str, redact: &[String]) -> Result<(), CliError> {
+    set_redaction(redact).map_err(|e| CliError::validation("project", e))?;
+    let target = AgentTarget { project, role, agent };
+    ingest_lines(input, log_file, &target, provider)?;
+    Ok(())
+}
+
 /// Run agent attach command
 pub fn run_agent_attach(
     project_file: Option<&str>, 
     project_name: Option<&str>, 
-    agent_name: &str, 
-    timeout_ms: Option<u64>
-) -> Result<(), Box<dyn std::error::Error>> {
+    agent_name: Option<&str>, 
+    timeout_ms: Option<u64>,
+    backend: Option<AgentBackendArg>,
+    read_only: bool,
+) -> Result<(), CliError> {
     // Cap tmux timeouts to 5s
-    let effective_ms = timeout_ms.unwrap_or(DEFAULT_AGENT_TIMEOUT_MS).min(DEFAULT_AGENT_TIMEOUT_MS);
+    let effective_ms = capped_timeout_ms(timeout_ms, DEFAULT_AGENT_TIMEOUT_MS);
     let timeout = Duration::from_millis(effective_ms);
     
     // Resolve config paths
@@ -158,65 +429,33 @@ pub fn run_agent_attach(
     
     // Load project configuration
     let proj_s = fs::read_to_string(&project_path)?;
-    let project = parse_project_yaml(&proj_s).map_err(|e| format!("project: {}", e))?;
+    let project = parse_project_yaml(&proj_s).map_err(|e| CliError::validation("project", e))?;
     
     // Determine project name
     let project_name = project_name.unwrap_or(&project.project);
     
-    // Find agent configuration
+    // Find agent configuration, asking for it when not given
+    let selected = select_agent(agent_name, &project.agents)?;
+    let agent_name = selected.as_str();
     let agent = project.agents.iter()
         .find(|a| a.name == agent_name)
-        .ok_or_else(|| format!("Agent '{}' not found in project '{}'", agent_name, project_name))?;
-    
-    // Build tmux session and window names
-    let session_name = format!("proj:{}", project_name);
-    let window_name = format!("{}:{}", agent.role, agent_name);
-    
-    // Create tmux manager
-    let tmux_manager = TmuxManager::new(timeout);
-    
-    // Check if session exists
-    let session_exists = tmux_manager.has_session(&session_name)?;
-    
-    if !session_exists {
-        return exit_with(2, format!("No tmux session found for project '{}'", project_name));
-    }
-    
-    // Check if window exists
-    let window_exists = tmux_manager.window_exists(&session_name, &window_name)?;
-    
-    if !window_exists {
-        return exit_with(2, format!("Agent '{}' is not running in tmux session '{}'", agent_name, session_name));
-    }
-    
-    // Check if we're in a headless environment
-    let is_headless = std::env::var("DISPLAY").is_err() && std::env::var("SSH_TTY").is_ok();
+        .ok_or_else(|| CliError::validation("agent", format!("'{}' not found in project '{}'", agent_name, project_name)))?;
     
-    if is_headless {
-        // Provide fallback message for headless mode
-        println!("Cannot attach to tmux session in headless mode.");
-        println!("Session '{}' is running with window '{}'.", session_name, window_name);
-        println!("To attach manually, run: tmux attach-session -t {}", session_name);
-        println!("To view logs, run: tail -f ./logs/{}/{}.ndjson", project_name, agent.role);
-        return Ok(());
-    }
-    
-    // Attach to the session
-    tmux_manager.attach_session(&session_name)?;
-    
-    Ok(())
+    let target = AgentTarget { project: project_name, role: &agent.role, agent: agent_name };
+    backend_for(backend, timeout, &target)?.attach(&target, &agent_log_path_for_read(&log_base(), project_name, agent_name, &agent.role), read_only)
 }
 
 /// Run agent stop command
 pub fn run_agent_stop(
     project_file: Option<&str>, 
     project_name: Option<&str>, 
-    agent_name: &str, 
-    timeout_ms: Option<u64>
-) -> Result<(), Box<dyn std::error::Error>> {
+    agent_name: Option<&str>, 
+    timeout_ms: Option<u64>,
+    backend: Option<AgentBackendArg>,
+) -> Result<(), CliError> {
     let start_time = Instant::now();
     // Cap tmux timeouts to 5s
-    let effective_ms = timeout_ms.unwrap_or(DEFAULT_AGENT_TIMEOUT_MS).min(DEFAULT_AGENT_TIMEOUT_MS);
+    let effective_ms = capped_timeout_ms(timeout_ms, DEFAULT_AGENT_TIMEOUT_MS);
     let timeout = Duration::from_millis(effective_ms);
     
     // Resolve config paths
@@ -227,38 +466,27 @@ pub fn run_agent_stop(
     
     // Load project configuration
     let proj_s = fs::read_to_string(&project_path)?;
-    let project = parse_project_yaml(&proj_s).map_err(|e| format!("project: {}", e))?;
+    let project = parse_project_yaml(&proj_s).map_err(|e| CliError::validation("project", e))?;
     
     // Determine project name
     let project_name = project_name.unwrap_or(&project.project);
     
-    // Find agent configuration
+    // Find agent configuration, asking for it when not given
+    let selected = select_agent(agent_name, &project.agents)?;
+    let agent_name = selected.as_str();
     let agent = project.agents.iter()
         .find(|a| a.name == agent_name)
-        .ok_or_else(|| format!("Agent '{}' not found in project '{}'", agent_name, project_name))?;
+        .ok_or_else(|| CliError::validation("agent", format!("'{}' not found in project '{}'", agent_name, project_name)))?;
     
-    // Build tmux session and window names
-    let session_name = format!("proj:{}", project_name);
-    let window_name = format!("{}:{}", agent.role, agent_name);
+    let target = AgentTarget { project: project_name, role: &agent.role, agent: agent_name };
+    let backend = backend_for(backend, timeout, &target)?;
     
-    // Create tmux manager
-    let tmux_manager = TmuxManager::new(timeout);
-    
-    // Check if session exists - idempotent
-    let session_exists = tmux_manager.has_session(&session_name)?;
-    
-    if !session_exists {
-        println!("No tmux session found for project '{}' - nothing to stop", project_name);
-        return Ok(());
-    }
-    
-    // Check if window exists - idempotent
-    let window_exists = tmux_manager.window_exists(&session_name, &window_name)?;
-    
-    if !window_exists {
-        println!("Agent '{}' is not running in tmux session '{}' - nothing to stop", agent_name, session_name);
+    // Idempotent: stopping an agent that is not running is not an error
+    if !backend.is_running(&target)? {
+        println!("Agent '{}' is not running in {} - nothing to stop", agent_name, backend.describe(&target));
         return Ok(());
     }
+    let location = backend.describe(&target);
     
     // Emit end event before stopping
     let duration_ms = start_time.elapsed().as_millis() as u64;
@@ -266,14 +494,240 @@ pub fn run_agent_stop(
         eprintln!("Warning: Failed to emit end event: {}", e);
     }
     
-    // Kill the window - idempotent operation
-    tmux_manager.kill_window(&session_name, &window_name)?;
+    backend.stop(&target)?;
     
     let total_duration_ms = start_time.elapsed().as_millis() as u64;
-    println!("Agent '{}' stopped in tmux session '{}' (took {}ms)", agent_name, session_name, total_duration_ms);
+    println!("Agent '{}' stopped in {} (took {}ms)", agent_name, location, total_duration_ms);
     Ok(())
 }
 
+/// Run agent remove command
+pub fn run_agent_remove(
+    project_sel: &str,
+    agent_name: &str,
+    db_path: Option<&str>,
+    force: bool,
+    kill_tmux: bool,
+    format: Format,
+    by: Option<SelectBy>,
+) -> Result<(), CliError> {
+    let binding;
+    let path = match db_path { Some(p) => p, None => { binding = resolve_db_path(); &binding } };
+    let conn = open_or_create_db(path)?;
+    let project_id = match find_project_id(&conn, project_selector(project_sel, by))? {
+        Some(id) => id,
+        None => return Err(CliError::validation("project", format!("not found: {}", project_sel))),
+    };
+    let project_name: String = conn.query_row("SELECT name FROM projects WHERE id = ?1", [&project_id], |r| r.get(0))?;
+    let agent = match find_agent_by_name(&conn, &project_id, agent_name)? {
+        Some(a) => a,
+        None => return Err(CliError::validation("agent", format!("not found: {} (project {})", agent_name, project_name))),
+    };
+
+    // Refuse to pull the rows out from under a live REPL window
+    let target = AgentTarget { project: &project_name, role: &agent.role, agent: &agent.name };
+    let session_name = target.session_name();
+    let tmux = TmuxBackend::new(TmuxManager::new(Duration::from_millis(DEFAULT_AGENT_TIMEOUT_MS)));
+    let running_window = tmux.agent_window(&target).unwrap_or(None);
+    let tmux_running = running_window.is_some();
+    let window_name = running_window.unwrap_or_else(|| target.window_name());
+
+    if !force {
+        let preview = count_agent_dependents(&conn, &agent.id)?;
+        match format {
+            Format::Text => {
+                println!(
+                    "Would remove agent '{}' from project '{}': {} sessions, {} messages, {} tasks unassigned",
+                    agent.name, project_name, preview.sessions, preview.messages, preview.tasks_unassigned
+                );
+                if tmux_running {
+                    println!("Agent is running in tmux window '{}:{}' (requires --kill-tmux)", session_name, window_name);
+                }
+                println!("Re-run with --force to delete");
+            }
+            Format::Json => println!("{}", serde_json::json!({
+                "dry_run": true,
+                "project": project_name,
+                "agent": agent.name,
+                "agent_id": agent.id,
+                "tmux_running": tmux_running,
+                "agents": preview.agents,
+                "sessions": preview.sessions,
+                "messages": preview.messages,
+                "tasks_unassigned": preview.tasks_unassigned,
+            })),
+        }
+        return Ok(());
+    }
+
+    if tmux_running {
+        if !kill_tmux {
+            return Err(CliError::validation("agent remove", format!("agent '{}' is running in tmux window '{}:{}'; stop it first or pass --kill-tmux", agent.name, session_name, window_name)));
+        }
+        tmux.manager().kill_window(&session_name, &window_name)?;
+    }
+
+    let result = delete_agent(&conn, &agent.id)?;
+    match format {
+        Format::Text => println!(
+            "Removed agent '{}' from project '{}': {} sessions, {} messages, {} tasks unassigned",
+            agent.name, project_name, result.sessions, result.messages, result.tasks_unassigned
+        ),
+        Format::Json => println!("{}", serde_json::json!({
+            "dry_run": false,
+            "project": project_name,
+            "agent": agent.name,
+            "agent_id": agent.id,
+            "tmux_killed": tmux_running,
+            "agents": result.agents,
+            "sessions": result.sessions,
+            "messages": result.messages,
+            "tasks_unassigned": result.tasks_unassigned,
+        })),
+    }
+    Ok(())
+}
+
+/// System prompt characters shown by `agent info` in text mode without `--full`
+pub const AGENT_INFO_PROMPT_CHARS: usize = 120;
+
+/// Run agent info command; `session_limit` 0 leaves the session history out
+pub fn run_agent_info(
+    project_sel: &str,
+    agent_name: &str,
+    db_path: Option<&str>,
+    session_limit: u32,
+    full: bool,
+    format: Format,
+    by: Option<SelectBy>,
+) -> Result<(), CliError> {
+    let binding;
+    let path = match db_path { Some(p) => p, None => { binding = resolve_db_path(); &binding } };
+    let conn = open_or_create_db(path)?;
+    let project_id = match find_project_id(&conn, project_selector(project_sel, by))? {
+        Some(id) => id,
+        None => return Err(CliError::validation("project", format!("not found: {}", project_sel))),
+    };
+    let project_name: String = conn.query_row("SELECT name FROM projects WHERE id = ?1", [&project_id], |r| r.get(0))?;
+    let repo = RepositoryManager::new(conn);
+    let info = match repo.agents.find_info(&project_id, agent_name, session_limit).map_err(|e| CliError::Other(e.to_string()))? {
+        Some(info) => info,
+        None => return Err(CliError::validation("agent", format!("not found: {} (project {})", agent_name, project_name))),
+    };
+    match format {
+        Format::Text => print!("{}", format_agent_info(&info, &project_name, session_limit > 0, full)),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&info)?),
+    }
+    Ok(())
+}
+
+/// Text rendering of `agent info`; the session table is only printed when `include_sessions` is set
+pub fn format_agent_info(info: &AgentInfo, project_name: &str, include_sessions: bool, full: bool) -> String {
+    let agent = &info.agent;
+    let prompt = if full || agent.system_prompt.chars().count() <= AGENT_INFO_PROMPT_CHARS {
+        agent.system_prompt.clone()
+    } else {
+        format!("{}...", agent.system_prompt.chars().take(AGENT_INFO_PROMPT_CHARS).collect::<String>())
+    };
+    let tools = if agent.allowed_tools.is_empty() { "-".to_string() } else { agent.allowed_tools.join(", ") };
+    let mut out = String::new();
+    for (key, value) in [
+        ("Name", agent.name.as_str()),
+        ("Project", project_name),
+        ("Role", agent.role.as_str()),
+        ("Provider", agent.provider.as_str()),
+        ("Model", agent.model.as_str()),
+        ("Allowed tools", tools.as_str()),
+        ("System prompt", prompt.as_str()),
+    ] {
+        out.push_str(&format!("{:<14} {}\n", format!("{}:", key), value));
+    }
+    if include_sessions {
+        if info.sessions.is_empty() {
+            out.push_str("\nNo sessions\n");
+        } else {
+            out.push_str(&format!("\nRecent sessions ({}):\n", info.sessions.len()));
+            out.push_str(&format!("{:<36} {:<6} {:<8} {:<20} {:<20}\n", "ID", "Type", "Status", "Created", "Last activity"));
+            out.push_str(&format!("{}\n", "-".repeat(92)));
+            for s in &info.sessions {
+                out.push_str(&format!(
+                    "{:<36} {:<6} {:<8} {:<20} {:<20}\n",
+                    s.id, s.session_type, s.status, s.created_at, s.last_activity.as_deref().unwrap_or("-")
+                ));
+            }
+        }
+    }
+    out
+}
+
+/// Shells a pane falls back to when the provider command failed to launch
+const SHELL_COMMANDS: &[&str] = &["sh", "bash", "zsh", "fish", "dash", "ksh", "tcsh", "csh"];
+
+/// Poll the agent pane until the provider process is in the foreground.
+///
+/// Launch is confirmed when `pane_current_command` matches the provider binary name,
+/// or is some other non-shell process (interpreted CLIs often show up as `node`/`python`).
+/// On failure returns the tail of the pane contents.
+pub fn verify_provider_started(
+    tmux_manager: &TmuxManager,
+    session_name: &str,
+    window_name: &str,
+    provider_cmd: &str,
+    timeout: Duration,
+) -> Result<(), String> {
+    let expected = std::path::Path::new(provider_cmd.trim())
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let deadline = Instant::now() + timeout;
+    loop {
+        let current = tmux_manager.pane_current_command(session_name, window_name).unwrap_or_default();
+        if !current.is_empty() && (current == expected || !SHELL_COMMANDS.contains(&current.as_str())) {
+            return Ok(());
+        }
+        let pane = tmux_manager.capture_pane(session_name, window_name).unwrap_or_default();
+        if shows_launch_failure(&pane) || Instant::now() >= deadline {
+            return Err(pane_tail(&pane, 10));
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Whether pane output carries the shell's "could not run it" message
+fn shows_launch_failure(pane: &str) -> bool {
+    pane.contains("command not found") || pane.contains("No such file or directory")
+}
+
+/// One look at the pane for `agent run --detached-check`: a provider failure (exit 4) carrying the
+/// pane tail when it shows a launch failure, or when its foreground command is a shell other than `provider_cmd`
+pub fn check_repl_alive(
+    tmux_manager: &TmuxManager,
+    session_name: &str,
+    window_name: &str,
+    provider_cmd: &str,
+) -> Result<(), CliError> {
+    let pane = tmux_manager.capture_pane(session_name, window_name).unwrap_or_default();
+    let current = tmux_manager.pane_current_command(session_name, window_name).unwrap_or_default();
+    let expected = std::path::Path::new(provider_cmd.trim())
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let back_at_shell = SHELL_COMMANDS.contains(&current.as_str()) && current != expected;
+    if shows_launch_failure(&pane) || back_at_shell {
+        return Err(CliError::provider(provider_cmd, ProviderErrorKind::Failed(format!(
+            "REPL is not running in tmux window '{}:{}' after launch\n{}",
+            session_name, window_name, pane_tail(&pane, 10)
+        ))));
+    }
+    Ok(())
+}
+
+/// Last `n` non-empty lines of captured pane output
+fn pane_tail(pane: &str, n: usize) -> String {
+    let lines: Vec<&str> = pane.lines().filter(|l| !l.trim().is_empty()).collect();
+    lines[lines.len().saturating_sub(n)..].join("\n")
+}
+
 /// Perform healthcheck after agent startup to confirm ready state
 pub fn perform_healthcheck(
     tmux_manager: &TmuxManager,
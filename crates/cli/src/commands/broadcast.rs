@@ -3,14 +3,15 @@
 use std::fs;
 use std::time::Duration;
 use config_model::{parse_project_yaml, parse_providers_yaml};
-use db::{open_or_create_db, find_project_id, IdOrName, sync_project_from_config};
+use db::{open_or_create_db, find_project_id, IdOrName};
+use crate::commands::config::sync_project_with_progress;
 use crate::cli::commands::Format;
 use crate::utils::{
     resolve_config_paths, handle_missing_config, resolve_db_path, DEFAULT_AGENT_TIMEOUT_MS, 
-    exit_with
+    CliError, capped_timeout_ms
 };
 use crate::broadcast::{BroadcastManager, BroadcastMode, BroadcastTarget};
-use crate::logging::log_ndjson;
+use crate::logging::{log_ndjson, set_redaction};
 use indicatif::{ProgressBar, ProgressStyle};
 
 /// Run broadcast oneshot command
@@ -23,7 +24,7 @@ pub fn run_broadcast_oneshot(
     timeout_ms: Option<u64>,
     format: Format,
     progress: bool
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), CliError> {
     let start_time = std::time::Instant::now();
     
     // Resolve config paths
@@ -35,8 +36,9 @@ pub fn run_broadcast_oneshot(
     // Load configurations
     let proj_s = fs::read_to_string(&project_path)?;
     let prov_s = fs::read_to_string(&providers_path)?;
-    let project = parse_project_yaml(&proj_s).map_err(|e| format!("project: {}", e))?;
-    let _providers = parse_providers_yaml(&prov_s).map_err(|e| format!("providers: {}", e))?;
+    let project = parse_project_yaml(&proj_s).map_err(|e| CliError::validation("project", e))?;
+    set_redaction(&project.defaults.redaction).map_err(|e| CliError::validation("project", e))?;
+    let _providers = parse_providers_yaml(&prov_s).map_err(|e| CliError::validation("providers", e))?;
     
     // Determine project name
     let project_name = project_name.unwrap_or(&project.project);
@@ -44,13 +46,12 @@ pub fn run_broadcast_oneshot(
     // Sync project to database
     let db_path = resolve_db_path();
     let conn = open_or_create_db(&db_path)?;
-    sync_project_from_config(&conn, &project)
-        .map_err(|e| format!("Failed to sync project: {}", e))?;
+    sync_project_with_progress(&conn, &project)?;
     
     // Get project ID
     let project_id = match find_project_id(&conn, IdOrName::Name(project_name))? {
         Some(pid) => pid,
-        None => return exit_with(2, format!("Project not found: {}", project_name)),
+        None => return Err(CliError::validation("project", format!("not found: {}", project_name))),
     };
     
     // Get agents from database
@@ -70,18 +71,18 @@ pub fn run_broadcast_oneshot(
     
     // Parse target
     let target = BroadcastTarget::from_str(to)
-        .map_err(|e| format!("Invalid target '{}': {}", to, e))?;
+        .map_err(|e| CliError::validation("target", format!("invalid target '{}': {}", to, e)))?;
     
     // Resolve agents
     let agent_names = target.resolve_agents(&agents)
-        .map_err(|e| format!("Target resolution failed: {}", e))?;
+        .map_err(|e| CliError::validation("target", format!("resolution failed: {}", e)))?;
     
     if agent_names.is_empty() {
-        return exit_with(2, format!("No agents found for target '{}'", to));
+        return Err(CliError::validation("target", format!("no agents found for target '{}'", to)));
     }
     
     // Create broadcast manager
-    let effective_timeout = timeout_ms.unwrap_or(DEFAULT_AGENT_TIMEOUT_MS).min(DEFAULT_AGENT_TIMEOUT_MS);
+    let effective_timeout = capped_timeout_ms(timeout_ms, DEFAULT_AGENT_TIMEOUT_MS);
     let timeout = Duration::from_millis(effective_timeout);
     let manager = BroadcastManager::new(project_name.to_string(), timeout);
     
@@ -112,7 +113,8 @@ pub fn run_broadcast_oneshot(
         "broadcast_complete", 
         None, 
         Some(if summary.is_success() { 0 } else { 1 }), 
-        None
+        None,
+        ""
     );
     
     // Output results
@@ -150,9 +152,9 @@ pub fn run_broadcast_oneshot(
     if summary.is_success() {
         Ok(())
     } else if summary.successful > 0 {
-        exit_with(1, format!("Broadcast completed with {} failures", summary.failed))
+        Err(format!("Broadcast completed with {} failures", summary.failed).into())
     } else {
-        exit_with(8, "All broadcast targets failed".to_string())
+        Err(CliError::tmux("broadcast", "all broadcast targets failed"))
     }
 }
 
@@ -165,7 +167,7 @@ pub fn run_broadcast_repl(
     timeout_ms: Option<u64>,
     format: Format,
     progress: bool
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), CliError> {
     let start_time = std::time::Instant::now();
     
     // Resolve config paths
@@ -176,7 +178,8 @@ pub fn run_broadcast_repl(
     
     // Load project configuration
     let proj_s = fs::read_to_string(&project_path)?;
-    let project = parse_project_yaml(&proj_s).map_err(|e| format!("project: {}", e))?;
+    let project = parse_project_yaml(&proj_s).map_err(|e| CliError::validation("project", e))?;
+    set_redaction(&project.defaults.redaction).map_err(|e| CliError::validation("project", e))?;
     
     // Determine project name
     let project_name = project_name.unwrap_or(&project.project);
@@ -197,18 +200,18 @@ pub fn run_broadcast_repl(
     
     // Parse target
     let target = BroadcastTarget::from_str(to)
-        .map_err(|e| format!("Invalid target '{}': {}", to, e))?;
+        .map_err(|e| CliError::validation("target", format!("invalid target '{}': {}", to, e)))?;
     
     // Resolve agents
     let agent_names = target.resolve_agents(&agents)
-        .map_err(|e| format!("Target resolution failed: {}", e))?;
+        .map_err(|e| CliError::validation("target", format!("resolution failed: {}", e)))?;
     
     if agent_names.is_empty() {
-        return exit_with(2, format!("No agents found for target '{}'", to));
+        return Err(CliError::validation("target", format!("no agents found for target '{}'", to)));
     }
     
     // Create broadcast manager
-    let effective_timeout = timeout_ms.unwrap_or(DEFAULT_AGENT_TIMEOUT_MS).min(DEFAULT_AGENT_TIMEOUT_MS);
+    let effective_timeout = capped_timeout_ms(timeout_ms, DEFAULT_AGENT_TIMEOUT_MS);
     let timeout = Duration::from_millis(effective_timeout);
     let manager = BroadcastManager::new(project_name.to_string(), timeout);
     
@@ -239,7 +242,8 @@ pub fn run_broadcast_repl(
         "broadcast_complete", 
         None, 
         Some(if summary.is_success() { 0 } else { 1 }), 
-        None
+        None,
+        ""
     );
     
     // Output results
@@ -277,9 +281,9 @@ pub fn run_broadcast_repl(
     if summary.is_success() {
         Ok(())
     } else if summary.successful > 0 {
-        exit_with(1, format!("Broadcast completed with {} failures", summary.failed))
+        Err(format!("Broadcast completed with {} failures", summary.failed).into())
     } else {
-        exit_with(8, "All broadcast targets failed".to_string())
+        Err(CliError::tmux("broadcast", "all broadcast targets failed"))
     }
 }
 
@@ -0,0 +1,66 @@
+//! Response cache for `send --cache`: cache keys and the `cache clear|stats` commands
+
+use std::time::Duration;
+use sha2::{Digest, Sha256};
+use db::{open_or_create_db, open_readonly_db, clear_response_cache, response_cache_stats};
+use crate::cli::commands::Format;
+use crate::utils::{resolve_db_path, parse_duration_spec, CliError};
+
+/// TTL of a cached response when neither `--cache-ttl` nor `defaults.cache_ttl` is set
+pub const DEFAULT_CACHE_TTL: &str = "24h";
+
+/// Everything that shapes a one-shot provider answer
+#[derive(Debug, Clone, Copy)]
+pub struct CacheKeyInput<'a> {
+    pub provider: &'a str,
+    pub model: &'a str,
+    pub system_prompt: &'a str,
+    pub message: &'a str,
+    pub allowed_tools: &'a [String],
+}
+
+/// Hex SHA-256 of the key input. Every field is length-prefixed, so moving text from one field
+/// to the next never produces the same key.
+pub fn cache_key(input: &CacheKeyInput) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"multi-agents response cache v1\n");
+    let fields = [input.provider, input.model, input.system_prompt, input.message];
+    for field in fields.into_iter().chain(input.allowed_tools.iter().map(String::as_str)) {
+        hasher.update(format!("{}:", field.len()));
+        hasher.update(field);
+    }
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// `--cache-ttl`, else `defaults.cache_ttl`, else 24h
+pub fn cache_ttl(flag: Option<&str>, project_default: Option<&str>) -> Result<Duration, CliError> {
+    let spec = flag.or(project_default).unwrap_or(DEFAULT_CACHE_TTL);
+    parse_duration_spec(spec).map_err(|e| CliError::validation("cache-ttl", e))
+}
+
+/// Run `cache clear`: drop every cached response, or only expired ones
+pub fn run_cache_clear(expired_only: bool, db_path: Option<&str>, format: Format) -> Result<(), CliError> {
+    let path = db_path.map(str::to_string).unwrap_or_else(resolve_db_path);
+    let conn = open_or_create_db(&path)?;
+    let removed = clear_response_cache(&conn, expired_only)?;
+    match format {
+        Format::Text => println!("OK: removed {} cached response(s)", removed),
+        Format::Json => println!("{}", serde_json::json!({"removed": removed, "expired_only": expired_only})),
+    }
+    Ok(())
+}
+
+/// Run `cache stats`
+pub fn run_cache_stats(db_path: Option<&str>, format: Format) -> Result<(), CliError> {
+    let path = db_path.map(str::to_string).unwrap_or_else(resolve_db_path);
+    let stats = response_cache_stats(&open_readonly_db(&path)?)?;
+    match format {
+        Format::Text => {
+            println!("Entries:  {} ({} expired)", stats.entries, stats.expired);
+            println!("Hits:     {}", stats.hits);
+            println!("Stored:   {} bytes", stats.bytes);
+        }
+        Format::Json => println!("{}", serde_json::to_value(&stats)?),
+    }
+    Ok(())
+}
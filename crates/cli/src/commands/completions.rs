@@ -0,0 +1,216 @@
+//! Shell completion scripts and the hidden helpers they call for dynamic values
+
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::Path;
+use clap::CommandFactory;
+use clap_complete::Shell;
+use config_model::parse_project_yaml;
+use db::{open_readonly_db, find_project_id, list_agent_names, list_agents, list_project_names, IdOrName};
+use crate::cli::commands::Cli;
+use crate::utils::{resolve_db_path, CliError};
+
+/// Print the completion script for `shell` to stdout
+pub fn run_completions(shell: Shell) -> Result<(), CliError> {
+    let mut out = std::io::stdout();
+    write_completions(shell, &mut out)?;
+    Ok(())
+}
+
+/// Static clap completions followed by the dynamic `--agent`/`--project`/`--to` hooks for bash, zsh and fish
+pub fn write_completions(shell: Shell, out: &mut dyn Write) -> std::io::Result<()> {
+    let mut cmd = visible_command();
+    clap_complete::generate(shell, &mut cmd, "multi-agents", out);
+    if let Some(hooks) = dynamic_hooks(shell) {
+        out.write_all(hooks.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// `Cli::command()` without hidden subcommands: the generators mis-handle `__`-prefixed names
+fn visible_command() -> clap::Command {
+    let full = Cli::command();
+    let mut cmd = clap::Command::new("multi-agents").version(env!("CARGO_PKG_VERSION"));
+    for arg in full.get_arguments() {
+        cmd = cmd.arg(arg.clone());
+    }
+    for sub in full.get_subcommands().filter(|s| !s.is_hide_set()) {
+        cmd = cmd.subcommand(sub.clone());
+    }
+    cmd
+}
+
+fn dynamic_hooks(shell: Shell) -> Option<&'static str> {
+    match shell {
+        Shell::Bash => Some(BASH_HOOKS),
+        Shell::Zsh => Some(ZSH_HOOKS),
+        Shell::Fish => Some(FISH_HOOKS),
+        _ => None,
+    }
+}
+
+const BASH_HOOKS: &str = r#"
+_multi_agents_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}" project="" i
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        [[ "${COMP_WORDS[i]}" == "--project" ]] && project="${COMP_WORDS[i+1]}"
+    done
+    case "$prev" in
+        --agent)
+            COMPREPLY=($(compgen -W "$(multi-agents __complete-agents ${project:+--project "$project"} 2>/dev/null)" -- "$cur"))
+            return 0 ;;
+        --project)
+            COMPREPLY=($(compgen -W "$(multi-agents __complete-projects 2>/dev/null)" -- "$cur"))
+            return 0 ;;
+        --to)
+            if [[ "${COMP_WORDS[1]}" == "send" || "${COMP_WORDS[1]}" == "broadcast" ]]; then
+                COMPREPLY=($(compgen -W "$(multi-agents __complete-targets ${project:+--project "$project"} 2>/dev/null)" -- "$cur"))
+                return 0
+            fi ;;
+    esac
+    _multi-agents "$@"
+}
+complete -F _multi_agents_dynamic -o bashdefault -o default multi-agents
+"#;
+
+const ZSH_HOOKS: &str = r#"
+_multi_agents_dynamic() {
+    local project="${words[(r)--project]:+${words[${words[(i)--project]}+1]}}"
+    case "${words[CURRENT-1]}" in
+        --agent) compadd -- ${(f)"$(multi-agents __complete-agents ${project:+--project "$project"} 2>/dev/null)"} ;;
+        --project) compadd -- ${(f)"$(multi-agents __complete-projects 2>/dev/null)"} ;;
+        --to) if [[ "${words[2]}" == (send|broadcast) ]]; then
+                compadd -- ${(f)"$(multi-agents __complete-targets ${project:+--project "$project"} 2>/dev/null)"}
+            else
+                _multi-agents "$@"
+            fi ;;
+        *) _multi-agents "$@" ;;
+    esac
+}
+compdef _multi_agents_dynamic multi-agents
+"#;
+
+const FISH_HOOKS: &str = r#"
+function __multi_agents_project
+    set -l tokens (commandline -opc)
+    set -l idx (contains -i -- --project $tokens); or return
+    set -q tokens[(math $idx + 1)]; and echo $tokens[(math $idx + 1)]
+end
+function __multi_agents_agents
+    set -l project (__multi_agents_project)
+    if test -n "$project"
+        multi-agents __complete-agents --project $project 2>/dev/null
+    else
+        multi-agents __complete-agents 2>/dev/null
+    end
+end
+function __multi_agents_targets
+    set -l project (__multi_agents_project)
+    if test -n "$project"
+        multi-agents __complete-targets --project $project 2>/dev/null
+    else
+        multi-agents __complete-targets 2>/dev/null
+    end
+end
+complete -c multi-agents -l agent -f -a '(__multi_agents_agents)'
+complete -c multi-agents -n '__fish_seen_subcommand_from send broadcast' -l to -f -a '(__multi_agents_targets)'
+complete -c multi-agents -l project -f -a '(multi-agents __complete-projects 2>/dev/null)'
+"#;
+
+/// Print agent names, one per line; prints nothing when config and DB are unavailable
+pub fn run_complete_agents(project: Option<&str>, project_file: Option<&str>, db_path: Option<&str>) -> Result<(), CliError> {
+    for name in complete_agents(project, project_file, db_path) {
+        println!("{}", name);
+    }
+    Ok(())
+}
+
+/// Print `--to` targets, one per line; prints only `@all` when config and DB are unavailable
+pub fn run_complete_targets(project: Option<&str>, project_file: Option<&str>, db_path: Option<&str>) -> Result<(), CliError> {
+    for target in complete_targets(project, project_file, db_path) {
+        println!("{}", target);
+    }
+    Ok(())
+}
+
+/// Print project names, one per line; prints nothing when config and DB are unavailable
+pub fn run_complete_projects(project_file: Option<&str>, db_path: Option<&str>) -> Result<(), CliError> {
+    for name in complete_projects(project_file, db_path) {
+        println!("{}", name);
+    }
+    Ok(())
+}
+
+/// Agents from project.yaml (when it describes `project`, or no project was given) and from the DB
+pub fn complete_agents(project: Option<&str>, project_file: Option<&str>, db_path: Option<&str>) -> Vec<String> {
+    let mut names = BTreeSet::new();
+    if let Some(cfg) = load_project_config(project_file) {
+        if project.is_none_or(|p| p == cfg.project) {
+            names.extend(cfg.agents.into_iter().map(|a| a.name));
+        }
+    }
+    if let (Some(project), Some(conn)) = (project, open_existing_db(db_path)) {
+        if let Ok(Some(project_id)) = find_project_id(&conn, IdOrName::Name(project)) {
+            names.extend(list_agent_names(&conn, &project_id).unwrap_or_default());
+        }
+    }
+    names.into_iter().collect()
+}
+
+/// `@all`, then `@<role>` and agent names from project.yaml and the DB, as accepted by `--to`
+pub fn complete_targets(project: Option<&str>, project_file: Option<&str>, db_path: Option<&str>) -> Vec<String> {
+    let mut roles = BTreeSet::new();
+    let mut names = BTreeSet::new();
+    if let Some(cfg) = load_project_config(project_file) {
+        if project.is_none_or(|p| p == cfg.project) {
+            for agent in cfg.agents {
+                roles.insert(agent.role);
+                names.insert(agent.name);
+            }
+        }
+    }
+    if let (Some(project), Some(conn)) = (project, open_existing_db(db_path)) {
+        if let Ok(Some(project_id)) = find_project_id(&conn, IdOrName::Name(project)) {
+            for agent in list_agents(&conn, &project_id).unwrap_or_default() {
+                roles.insert(agent.role);
+                names.insert(agent.name);
+            }
+        }
+    }
+    std::iter::once("@all".to_string())
+        .chain(roles.into_iter().map(|r| format!("@{}", r)))
+        .chain(names)
+        .collect()
+}
+
+/// Projects from project.yaml and the DB
+pub fn complete_projects(project_file: Option<&str>, db_path: Option<&str>) -> Vec<String> {
+    let mut names = BTreeSet::new();
+    if let Some(cfg) = load_project_config(project_file) {
+        names.insert(cfg.project);
+    }
+    if let Some(conn) = open_existing_db(db_path) {
+        names.extend(list_project_names(&conn).unwrap_or_default());
+    }
+    names.into_iter().collect()
+}
+
+fn load_project_config(project_file: Option<&str>) -> Option<config_model::ProjectConfig> {
+    // Same order as resolve_config_paths, without requiring providers.yaml
+    let base = std::env::var("MULTI_AGENTS_CONFIG_DIR").unwrap_or_else(|_| "./config".into());
+    let path = project_file.map(|p| p.to_string())
+        .into_iter()
+        .chain(std::env::var("MULTI_AGENTS_PROJECT_FILE").ok())
+        .chain([format!("{}/project.yaml", base), format!("{}/project.yml", base)])
+        .find(|p| Path::new(p).exists())?;
+    parse_project_yaml(&std::fs::read_to_string(path).ok()?).ok()
+}
+
+/// Completion must never create a database as a side effect
+fn open_existing_db(db_path: Option<&str>) -> Option<rusqlite::Connection> {
+    let path = db_path.map(|p| p.to_string()).unwrap_or_else(resolve_db_path);
+    if !Path::new(&path).exists() {
+        return None;
+    }
+    open_readonly_db(&path).ok()
+}
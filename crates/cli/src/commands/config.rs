@@ -2,14 +2,17 @@
 
 use std::fs;
 use std::path::Path;
+use serde::Serialize;
 use config_model::{
-    parse_project_yaml, parse_providers_yaml, validate_project_config, validate_providers_config,
+    json_schema_project, json_schema_providers, parse_project_yaml, parse_providers_yaml,
+    unknown_agent_tools, validate_project_config, validate_providers_config, ProjectConfig,
 };
-use crate::cli::commands::Format;
-use crate::utils::{resolve_config_paths, handle_missing_config, format_error, exit_with};
+use db::{open_or_create_db, open_readonly_db_any_schema, find_agent_by_name, sync_project_from_config, sync_project_with_options, SyncOptions, SyncReport};
+use crate::cli::commands::{Format, SchemaOutputFormat, SchemaTarget};
+use crate::utils::{resolve_config_paths, resolve_db_path, handle_missing_config, format_error, unified_diff, write_atomic, say, CliError};
 
-/// Run config validation command
-pub fn run_config_validate(project_path_opt: Option<&str>, providers_path_opt: Option<&str>, format: Format) -> Result<(), Box<dyn std::error::Error>> {
+/// Run config validation command; `strict` turns unknown agent tools and provider keys into errors
+pub fn run_config_validate(project_path_opt: Option<&str>, providers_path_opt: Option<&str>, format: Format, strict: bool) -> Result<(), CliError> {
     let (project_path, providers_path) = match resolve_config_paths(project_path_opt, providers_path_opt) {
         Ok(p) => p,
         Err(msg) => return handle_missing_config(msg),
@@ -19,35 +22,338 @@ pub fn run_config_validate(project_path_opt: Option<&str>, providers_path_opt: O
 
     let project = match parse_project_yaml(&proj_s) {
         Ok(p) => p,
-        Err(e) => return exit_with(2, format_error(format, "project", &e)),
+        Err(e) => return Err(invalid(format, "project", &e)),
     };
     let providers = match parse_providers_yaml(&prov_s) {
         Ok(p) => p,
-        Err(e) => return exit_with(2, format_error(format, "providers", &e)),
+        Err(e) => return Err(invalid(format, "providers", &e)),
     };
 
-    if let Err(e) = validate_providers_config(&providers) {
-        return exit_with(2, format_error(format, "providers", &e));
+    if let Err(e) = validate_providers_config(&providers, strict) {
+        return Err(invalid(format, "providers", &e));
     }
-    if let Err(e) = validate_project_config(&project, &providers) {
-        return exit_with(2, format_error(format, "project", &e));
+    let mut warnings: Vec<String> = match validate_project_config(&project, &providers) {
+        Ok(w) => w.iter().map(|w| w.to_string()).collect(),
+        Err(e) => return Err(invalid(format, "project", &e)),
+    };
+    let unknown_tools = unknown_agent_tools(&project, &providers);
+    if strict && !unknown_tools.is_empty() {
+        return Err(invalid(format, "project", &unknown_tools.join("; ")));
     }
+    warnings.extend(unknown_tools);
 
     match format {
-        Format::Text => println!("OK: configuration valid"),
-        Format::Json => println!("{}", serde_json::json!({"status":"ok"})),
+        Format::Text => {
+            for w in &warnings {
+                eprintln!("Warning: {}", w);
+            }
+            println!("OK: configuration valid");
+        }
+        Format::Json if warnings.is_empty() => println!("{}", serde_json::json!({"status":"ok"})),
+        Format::Json => println!("{}", serde_json::json!({"status":"ok","warnings":warnings})),
     }
     Ok(())
 }
 
-/// Run config initialization command
-pub fn run_config_init(dir_opt: Option<&str>, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+/// Validation error; JSON callers also get the error object on stdout
+fn invalid(format: Format, which: &str, err: &impl std::fmt::Display) -> CliError {
+    if let Format::Json = format {
+        println!("{}", format_error(format, which, err));
+    }
+    CliError::validation(which, err)
+}
+
+/// What `config init` / `init` did with one config file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileResult {
+    pub path: String,
+    pub action: FileAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileAction {
+    Wrote,
+    /// Already there and `--force` not given
+    Skipped,
+    /// `--dry-run`: the file would be created or changed
+    WouldWrite,
+    /// `--dry-run`: the file already matches the template
+    Unchanged,
+}
+
+/// `--format json` report of `config init` / `init`: `{"status":"ok","files":[...]}`, or with `"error"` when it failed
+pub fn init_report(files: &[FileResult], error: Option<&CliError>) -> serde_json::Value {
+    match error {
+        None => serde_json::json!({"status": "ok", "files": files}),
+        Some(e) => serde_json::json!({"status": "error", "error": e.to_string(), "files": files}),
+    }
+}
+
+/// Run config initialization command; `dry_run` prints what would change instead of writing
+pub fn run_config_init(dir_opt: Option<&str>, force: bool, dry_run: bool, format: Format) -> Result<(), CliError> {
     let base = dir_opt.unwrap_or("./config");
-    let _ = std::fs::create_dir_all(base);
-    let proj_path = format!("{}/project.yaml", base);
-    let prov_path = format!("{}/providers.yaml", base);
+    let mut files = Vec::new();
+    let result = write_config_templates(base, force, dry_run, format, &mut files);
+    match format {
+        Format::Text => {
+            result?;
+            if dry_run {
+                println!("DRY RUN: nothing written under {}", base);
+            } else {
+                println!("OK: config initialized under {}", base);
+            }
+        }
+        Format::Json => {
+            println!("{}", init_report(&files, result.as_ref().err()));
+            result?;
+        }
+    }
+    Ok(())
+}
 
-    let project_yaml = r#"schema_version: 1
+/// Write the template config files under `base`, recording each one in `files`; text output is printed as it goes
+pub(crate) fn write_config_templates(base: &str, force: bool, dry_run: bool, format: Format, files: &mut Vec<FileResult>) -> Result<(), CliError> {
+    let text = matches!(format, Format::Text);
+    if !dry_run {
+        let _ = std::fs::create_dir_all(base);
+    }
+    for (name, contents) in [("project.yaml", PROJECT_TEMPLATE), ("providers.yaml", PROVIDERS_TEMPLATE)] {
+        let path = format!("{}/{}", base, name);
+        let exists = Path::new(&path).exists();
+        let action = if exists && !force {
+            if text { println!("SKIP: {} exists (use --force to overwrite)", path); }
+            FileAction::Skipped
+        } else if dry_run {
+            let current = if exists { fs::read_to_string(&path)? } else { String::new() };
+            let old_label = if exists { path.clone() } else { "/dev/null".to_string() };
+            let diff = unified_diff(&current, contents, &old_label, &path);
+            if text {
+                if diff.is_empty() { println!("UNCHANGED: {}", path); } else { print!("{}", diff); }
+            }
+            if diff.is_empty() { FileAction::Unchanged } else { FileAction::WouldWrite }
+        } else {
+            std::fs::write(&path, contents)?;
+            if text { println!("WROTE: {}", path); }
+            FileAction::Wrote
+        };
+        files.push(FileResult { path, action });
+    }
+    Ok(())
+}
+
+/// Sync a project into the database, announcing each project and agent on stdout (unless `--quiet`)
+pub fn sync_project_with_progress(conn: &rusqlite::Connection, project_config: &ProjectConfig) -> Result<SyncReport, db::DbError> {
+    let report = sync_project_from_config(conn, project_config)?;
+    if report.project_created {
+        say(&format!("Creating project '{}' in database", project_config.project));
+    } else {
+        say(&format!("Project '{}' already exists in database", project_config.project));
+    }
+    for agent_config in &project_config.agents {
+        if report.agents_created.contains(&agent_config.name) {
+            say(&format!("Creating agent '{}' in database", agent_config.name));
+        } else {
+            say(&format!("Agent '{}' already exists in database", agent_config.name));
+        }
+    }
+    say(&format!("Project '{}' synchronized successfully", project_config.project));
+    Ok(report)
+}
+
+/// `--format json` report of `config sync`; a project that a dry run would create has no id yet
+pub fn sync_report_json(project: &str, report: &SyncReport, dry_run: bool) -> serde_json::Value {
+    let project_id = if dry_run && report.project_created { None } else { Some(&report.project_id) };
+    serde_json::json!({
+        "status": "ok",
+        "dry_run": dry_run,
+        "project": project,
+        "project_id": project_id,
+        "project_created": report.project_created,
+        "created": report.agents_created,
+        "updated": report.agents_updated,
+        "pruned": report.agents_pruned,
+        "unchanged": report.agents_existing,
+    })
+}
+
+/// Resolve and validate the config, then sync it into the database at `db_path` (default:
+/// `resolve_db_path`). Changed agents are always rewritten; `prune` also deletes unlisted ones.
+pub fn config_sync(
+    project_path_opt: Option<&str>,
+    providers_path_opt: Option<&str>,
+    db_path: Option<&str>,
+    prune: bool,
+    dry_run: bool,
+) -> Result<(ProjectConfig, SyncReport), CliError> {
+    let (project_path, providers_path) = match resolve_config_paths(project_path_opt, providers_path_opt) {
+        Ok(p) => p,
+        Err(msg) => return handle_missing_config(msg),
+    };
+    let project = parse_project_yaml(&fs::read_to_string(&project_path)?).map_err(|e| CliError::validation("project", e))?;
+    let providers = parse_providers_yaml(&fs::read_to_string(&providers_path)?).map_err(|e| CliError::validation("providers", e))?;
+    for w in validate_project_config(&project, &providers).map_err(|e| CliError::validation("project", e))? {
+        eprintln!("Warning: {}", w);
+    }
+    let path = db_path.map(str::to_string).unwrap_or_else(resolve_db_path);
+    let conn = open_sync_db(&path, dry_run)?;
+    let report = sync_project_with_options(&conn, &project, SyncOptions { update: true, prune, dry_run })?;
+    Ok((project, report))
+}
+
+/// Database `config sync` works on. A dry run opens it read-only without migrating, and plans
+/// against an empty in-memory one when it does not exist yet, so it never creates or changes a file.
+fn open_sync_db(path: &str, dry_run: bool) -> Result<rusqlite::Connection, CliError> {
+    if !dry_run {
+        return Ok(open_or_create_db(path)?);
+    }
+    if Path::new(path).exists() {
+        Ok(open_readonly_db_any_schema(path)?)
+    } else {
+        Ok(open_or_create_db(":memory:")?)
+    }
+}
+
+/// The synced fields of an agent, one per line, for the dry-run diff of `config sync`
+fn agent_definition(role: &str, provider: &str, model: &str, allowed_tools: &[String], system_prompt: &str) -> String {
+    format!(
+        "role: {}\nprovider: {}\nmodel: {}\nallowed_tools: [{}]\nsystem_prompt: {}\n",
+        role, provider, model, allowed_tools.join(", "), system_prompt.trim_end()
+    )
+}
+
+/// Run `config sync`: sync project.yaml into the database and report created/updated/pruned agents;
+/// `dry_run` rolls the sync back and shows a diff of each agent that would be updated
+pub fn run_config_sync(
+    project_path_opt: Option<&str>,
+    providers_path_opt: Option<&str>,
+    db_path: Option<&str>,
+    prune: bool,
+    dry_run: bool,
+    format: Format,
+) -> Result<(), CliError> {
+    let (project, report) = config_sync(project_path_opt, providers_path_opt, db_path, prune, dry_run)?;
+    if let Format::Json = format {
+        println!("{}", sync_report_json(&project.project, &report, dry_run));
+        return Ok(());
+    }
+    if report.project_created {
+        println!("CREATE: project {}", project.project);
+    }
+    for name in &report.agents_created {
+        println!("CREATE: agent {}", name);
+    }
+    if !report.agents_updated.is_empty() {
+        let path = db_path.map(str::to_string).unwrap_or_else(resolve_db_path);
+        let conn = open_sync_db(&path, dry_run)?;
+        for name in &report.agents_updated {
+            println!("UPDATE: agent {}", name);
+            // After a real run the database already holds the new definition, so only a dry run has a diff to show
+            if !dry_run { continue; }
+            let (Some(stored), Some(wanted)) = (
+                find_agent_by_name(&conn, &report.project_id, name)?,
+                project.agents.iter().find(|a| &a.name == name),
+            ) else { continue };
+            print!("{}", unified_diff(
+                &agent_definition(&stored.role, &stored.provider, &stored.model, &stored.allowed_tools, &stored.system_prompt),
+                &agent_definition(&wanted.role, &wanted.provider, &wanted.model, &wanted.allowed_tools, &wanted.system_prompt),
+                &format!("db/{}", name),
+                &format!("config/{}", name),
+            ));
+        }
+    }
+    for name in &report.agents_pruned {
+        println!("PRUNE: agent {}", name);
+    }
+    let summary = format!(
+        "{} created, {} updated, {} pruned, {} unchanged",
+        report.agents_created.len(), report.agents_updated.len(), report.agents_pruned.len(), report.agents_existing.len()
+    );
+    if dry_run {
+        println!("DRY RUN: project {} would be synced ({}); database not modified", project.project, summary);
+    } else {
+        println!("OK: project {} synced ({})", project.project, summary);
+    }
+    Ok(())
+}
+
+/// Run config schema command: print or write the JSON Schema, or check a YAML file against it
+pub fn run_config_schema(which: SchemaTarget, format: SchemaOutputFormat, output: Option<&str>, validate_against: Option<&str>) -> Result<(), CliError> {
+    if let Some(file) = validate_against {
+        return validate_against_schema(which, file);
+    }
+    let rendered = render_schema(which, format)?;
+    match output {
+        Some(path) => {
+            if let Some(dir) = Path::new(path).parent().filter(|d| !d.as_os_str().is_empty()) {
+                fs::create_dir_all(dir)?;
+            }
+            write_atomic(path, &rendered)?;
+            println!("WROTE: {}", path);
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+/// Schema text for `which`: pretty JSON (`all` nests both under their file kind) or `#`-commented blocks
+pub(crate) fn render_schema(which: SchemaTarget, format: SchemaOutputFormat) -> Result<String, CliError> {
+    let schemas = match which {
+        SchemaTarget::Project => vec![("project", serde_json::to_value(json_schema_project())?)],
+        SchemaTarget::Providers => vec![("providers", serde_json::to_value(json_schema_providers())?)],
+        SchemaTarget::All => vec![
+            ("project", serde_json::to_value(json_schema_project())?),
+            ("providers", serde_json::to_value(json_schema_providers())?),
+        ],
+    };
+    match format {
+        SchemaOutputFormat::Json => {
+            let value = match schemas.as_slice() {
+                [(_, schema)] => schema.clone(),
+                _ => serde_json::Value::Object(schemas.into_iter().map(|(k, v)| (k.to_string(), v)).collect()),
+            };
+            Ok(format!("{}\n", serde_json::to_string_pretty(&value)?))
+        }
+        SchemaOutputFormat::Yaml => {
+            let mut out = String::new();
+            for (kind, schema) in schemas {
+                if !out.is_empty() { out.push('\n'); }
+                out.push_str(&format!("# JSON Schema for {}.yaml (multi-agents config schema {})\n", kind, kind));
+                for line in serde_json::to_string_pretty(&schema)?.lines() {
+                    out.push_str("# ");
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// Check of a YAML file against the JSON Schema `config schema` prints for `which`; every violation
+/// is reported with its location in the file
+fn validate_against_schema(which: SchemaTarget, file: &str) -> Result<(), CliError> {
+    let content = fs::read_to_string(file)?;
+    let (kind, schema) = match which {
+        SchemaTarget::Project => ("project", serde_json::to_value(json_schema_project())?),
+        SchemaTarget::Providers => ("providers", serde_json::to_value(json_schema_providers())?),
+        SchemaTarget::All => return Err(CliError::validation("config schema", "--validate-against needs `project` or `providers`")),
+    };
+    let instance: serde_json::Value = serde_yaml::from_str(&content).map_err(|e| CliError::validation(kind, format!("{}: {}", file, e)))?;
+    let compiled = jsonschema::JSONSchema::compile(&schema).map_err(|e| CliError::Other(format!("{}.yaml schema: {}", kind, e)))?;
+    if let Err(errors) = compiled.validate(&instance) {
+        let violations: Vec<String> = errors.map(|e| {
+            let path = e.instance_path.to_string();
+            format!("  {}: {}", if path.is_empty() { "/" } else { &path }, e)
+        }).collect();
+        return Err(CliError::validation(kind, format!("{} does not match the {}.yaml schema:\n{}", file, kind, violations.join("\n"))));
+    }
+    println!("OK: {} matches the {}.yaml schema", file, kind);
+    Ok(())
+}
+
+/// project.yaml written by `config init`
+pub(crate) const PROJECT_TEMPLATE: &str = r#"schema_version: 1
 project: demo
 agents:
   - name: backend
@@ -59,7 +365,8 @@ agents:
       You are a backend agent.
 "#;
 
-    let providers_yaml = r#"schema_version: 1
+/// providers.yaml written by `config init`
+pub(crate) const PROVIDERS_TEMPLATE: &str = r#"schema_version: 1
 providers:
   claude:
     cmd: "claude"
@@ -78,19 +385,3 @@ providers:
     repl_args: ["-i","{system_prompt}","--allowed-tools","{allowed_tools}"]
     allowlist_flag: "--allowed-tools"
 "#;
-
-    let write_file = |path: &str, contents: &str| -> Result<(), Box<dyn std::error::Error>> {
-        if Path::new(path).exists() && !force {
-            println!("SKIP: {} exists (use --force to overwrite)", path);
-            return Ok(());
-        }
-        std::fs::write(path, contents)?;
-        println!("WROTE: {}", path);
-        Ok(())
-    };
-
-    write_file(&proj_path, project_yaml)?;
-    write_file(&prov_path, providers_yaml)?;
-    println!("OK: config initialized under {}", base);
-    Ok(())
-}
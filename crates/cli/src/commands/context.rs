@@ -1,6 +1,7 @@
 //! Context commands implementation
 use crate::cli::commands::{Format, GitKind};
 use crate::utils::constants::DEFAULT_TIMEOUT_GLOBAL_MS;
+use crate::utils::{CliError, ProviderErrorKind};
 use std::process::Command;
 use std::time::{Duration, Instant};
 
@@ -57,7 +58,7 @@ pub fn run_context_git(
     since: Option<&str>,
     until: Option<&str>,
     limit: Option<usize>,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), CliError> {
     // Timeout budget: use global default from constants (20s)
     let timeout = Duration::from_millis(DEFAULT_TIMEOUT_GLOBAL_MS);
     let started = Instant::now();
@@ -65,8 +66,7 @@ pub fn run_context_git(
     // Verify git availability
     let git_ok = Command::new("git").arg("--version").output();
     if git_ok.is_err() {
-        // exit code 3 (provider unavailable)
-        std::process::exit(3);
+        return Err(CliError::provider("git", ProviderErrorKind::Unavailable));
     }
 
     // Detect repository
@@ -74,9 +74,7 @@ pub fn run_context_git(
     let inside_repo = repo_chk.status.success() && String::from_utf8_lossy(&repo_chk.stdout).trim() == "true";
     if !inside_repo {
         if strict {
-            // exit code 1 generic
-            eprintln!("No Git repository detected (strict mode)");
-            std::process::exit(1);
+            return Err(CliError::Other("No Git repository detected (strict mode)".into()));
         } else {
             match format {
                 Format::Text => {
@@ -116,8 +114,7 @@ pub fn run_context_git(
     // Execute with coarse timeout control
     let output = cmd.output()?;
     if started.elapsed() > timeout {
-        // exit code 5 timeout
-        std::process::exit(5);
+        return Err(CliError::timeout("context git", DEFAULT_TIMEOUT_GLOBAL_MS));
     }
 
     let mut content = String::from_utf8_lossy(if output.status.success() { &output.stdout } else { &output.stderr }).to_string();
@@ -1,42 +1,258 @@
 //! Database commands implementation
 
-use db::{open_or_create_db, insert_project, insert_agent, find_project_id, IdOrName};
-use crate::utils::{resolve_db_path, looks_like_uuid, exit_with};
+use std::io::{BufRead, IsTerminal, Write};
+use db::{open_or_create_db, open_db_allow_newer_schema, open_db_without_migrating, current_migration_version, pending_migrations, migrate_to, scan_malformed_timestamps, SCHEMA_VERSION, insert_project, insert_agent, find_project_id, check_db_health, fix_orphans, count_project_dependents, delete_project, DbHealthReport, DeleteProjectResult, IdOrName};
+use rusqlite::Connection;
+use config_model::parse_providers_yaml;
+use crate::cli::commands::{Format, SelectBy};
+use crate::utils::{resolve_db_path, resolve_config_paths, project_selector, CliError};
 
 /// Run database initialization command
-pub fn run_db_init(db_path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+pub fn run_db_init(db_path: Option<&str>) -> Result<(), CliError> {
     let binding;
     let path = match db_path { Some(p) => p, None => { binding = resolve_db_path(); &binding } };
-    match open_or_create_db(path) {
-        Ok(_) => { println!("OK: db initialized"); Ok(()) }
-        Err(e) => exit_with(7, format!("db: {}", e)),
-    }
+    let conn = open_or_create_db(path)?;
+    report_malformed_timestamps(&conn, &mut std::io::stderr())?;
+    println!("OK: db initialized");
+    Ok(())
 }
 
 /// Run project add command
-pub fn run_project_add(name: &str, db_path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+pub fn run_project_add(name: &str, db_path: Option<&str>) -> Result<(), CliError> {
     let binding;
     let path = match db_path { Some(p) => p, None => { binding = resolve_db_path(); &binding } };
-    let conn = match open_or_create_db(path) { Ok(c) => c, Err(e) => return exit_with(7, format!("db: {}", e)) };
+    let conn = open_or_create_db(path)?;
     match insert_project(&conn, name) {
         Ok(p) => { println!("project_id={} name={}", p.id, p.name); Ok(()) }
-        Err(db::DbError::InvalidInput(e)) => exit_with(2, format!("project: {}", e)),
-        Err(e) => exit_with(7, format!("project: {}", e)),
+        Err(db::DbError::InvalidInput(e)) => Err(CliError::validation("project", e)),
+        Err(e) => Err(e.into()),
     }
 }
 
-/// Run agent add command
-pub fn run_agent_add(project_sel: &str, name: &str, role: &str, provider: &str, model: &str, allowed_tool: &[String], system_prompt: &str, db_path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+/// Parsed `db agent-add` flags; unset fields keep their command-line default (no tools, resolved DB path)
+#[derive(Clone, Copy, Default)]
+pub struct AgentAddRequest<'a> {
+    /// Project id or name, matched according to `by`
+    pub project: &'a str,
+    pub name: &'a str,
+    pub role: &'a str,
+    pub provider: &'a str,
+    pub model: &'a str,
+    pub allowed_tools: &'a [String],
+    pub system_prompt: &'a str,
+    pub db_path: Option<&'a str>,
+    pub by: Option<SelectBy>,
+    /// Insert a missing project (selected by name) first
+    pub create_project: bool,
+}
+
+/// Run agent add command; with `create_project` a missing project (selected by name) is inserted first
+pub fn run_agent_add(request: &AgentAddRequest) -> Result<(), CliError> {
+    let AgentAddRequest { project: project_sel, name, role, provider, model, allowed_tools, system_prompt, db_path, by, create_project } = *request;
     let binding;
     let path = match db_path { Some(p) => p, None => { binding = resolve_db_path(); &binding } };
-    let conn = match open_or_create_db(path) { Ok(c) => c, Err(e) => return exit_with(7, format!("db: {}", e)) };
-    let project_id = match find_project_id(&conn, if looks_like_uuid(project_sel) { IdOrName::Id(project_sel) } else { IdOrName::Name(project_sel) })? {
-        Some(id) => id,
-        None => return exit_with(2, format!("project not found: {}", project_sel)),
+    let conn = open_or_create_db(path)?;
+    let project_id = match (find_project_id(&conn, project_selector(project_sel, by))?, project_selector(project_sel, by)) {
+        (Some(id), _) => id,
+        (None, IdOrName::Name(project_name)) if create_project => match insert_project(&conn, project_name) {
+            Ok(p) => { println!("project_id={} name={} created", p.id, p.name); p.id }
+            Err(db::DbError::InvalidInput(e)) => return Err(CliError::validation("project", e)),
+            Err(e) => return Err(e.into()),
+        },
+        (None, IdOrName::Id(_)) if create_project =>
+            return Err(CliError::validation("project", format!("not found: {} (--create-project needs a project name, not an id)", project_sel))),
+        (None, _) => return Err(CliError::validation("project", format!("not found: {} (pass --create-project to create it)", project_sel))),
     };
-    match insert_agent(&conn, &project_id, name, role, provider, model, allowed_tool, system_prompt) {
+    match insert_agent(&conn, &project_id, name, role, provider, model, allowed_tools, system_prompt) {
         Ok(a) => { println!("agent_id={} project_id={} name={}", a.id, a.project_id, a.name); Ok(()) }
-        Err(db::DbError::InvalidInput(e)) => exit_with(2, format!("agent: {}", e)),
-        Err(e) => exit_with(7, format!("agent: {}", e)),
+        Err(db::DbError::InvalidInput(e)) => Err(CliError::validation("agent", e)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Run project remove command; agents and sessions are only deleted with `cascade`, after a confirmation or `yes`
+pub fn run_project_remove(name: &str, cascade: bool, yes: bool, db_path: Option<&str>) -> Result<(), CliError> {
+    let binding;
+    let path = match db_path { Some(p) => p, None => { binding = resolve_db_path(); &binding } };
+    let conn = open_or_create_db(path)?;
+    let stdin = std::io::stdin();
+    let is_tty = stdin.is_terminal();
+    let removed = remove_project(&conn, name, cascade, yes, is_tty, &mut stdin.lock(), &mut std::io::stderr())?;
+    println!("Removed project '{}': {}", name, describe_rows(&removed));
+    Ok(())
+}
+
+/// Executor behind `run_project_remove`; the confirmation is written to `out` and read from `input`
+pub(crate) fn remove_project(
+    conn: &Connection,
+    name: &str,
+    cascade: bool,
+    yes: bool,
+    is_tty: bool,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> Result<DeleteProjectResult, CliError> {
+    let project_id = match find_project_id(conn, IdOrName::Name(name))? {
+        Some(id) => id,
+        None => return Err(CliError::validation("project", format!("not found: {}", name))),
+    };
+    let preview = count_project_dependents(conn, &project_id)?;
+    if preview.dependents() > 0 {
+        if !cascade && (preview.agents > 0 || preview.sessions > 0) {
+            return Err(CliError::validation("project remove", format!(
+                "project '{}' still has {} agents and {} sessions; pass --cascade to delete them with it",
+                name, preview.agents, preview.sessions
+            )));
+        }
+        writeln!(out, "Removing project '{}' also deletes {}", name, describe_rows(&preview))?;
+        if !yes {
+            if !is_tty {
+                return Err(CliError::validation("project remove", "confirmation required; pass --yes to delete non-interactively"));
+            }
+            write!(out, "Delete project '{}'? [y/N] ", name)?;
+            out.flush()?;
+            let mut answer = String::new();
+            input.read_line(&mut answer)?;
+            if !matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes") {
+                return Err(CliError::Other("project remove cancelled".into()));
+            }
+        }
+    }
+    Ok(delete_project(conn, &project_id)?)
+}
+
+fn describe_rows(r: &DeleteProjectResult) -> String {
+    format!(
+        "{} agents, {} sessions, {} messages, {} tasks, {} broadcasts",
+        r.agents, r.sessions, r.messages, r.tasks, r.broadcasts
+    )
+}
+
+/// Run db migrate command: report the applied version, then apply up to `to` (default: latest) unless `dry_run`
+pub fn run_db_migrate(db_path: Option<&str>, to: Option<i64>, dry_run: bool) -> Result<(), CliError> {
+    let binding;
+    let path = match db_path { Some(p) => p, None => { binding = resolve_db_path(); &binding } };
+    let conn = open_db_without_migrating(path)?;
+    migrate_db(&conn, to, dry_run, &mut std::io::stdout())
+}
+
+/// Executor behind `run_db_migrate`; the report is written to `out`
+pub(crate) fn migrate_db(conn: &Connection, to: Option<i64>, dry_run: bool, out: &mut dyn Write) -> Result<(), CliError> {
+    let current = current_migration_version(conn)?;
+    if current > SCHEMA_VERSION {
+        return Err(db::DbError::SchemaTooNew { db_version: current, supported: SCHEMA_VERSION }.into());
+    }
+    let target = to.unwrap_or(SCHEMA_VERSION);
+    if target > SCHEMA_VERSION || target < current {
+        return Err(CliError::validation("db migrate", format!(
+            "--to {} must be between the current version {} and the latest {} (downgrades are not supported)",
+            target, current, SCHEMA_VERSION
+        )));
+    }
+    writeln!(out, "current_version={} latest={}", current, SCHEMA_VERSION)?;
+    if dry_run {
+        let pending: Vec<i64> = pending_migrations(conn)?.into_iter().filter(|v| *v <= target).collect();
+        writeln!(out, "pending: {}", version_list(&pending))?;
+        return Ok(());
+    }
+    let applied = migrate_to(conn, target)?;
+    if applied.is_empty() {
+        writeln!(out, "OK: already at version {}", current)?;
+    } else {
+        writeln!(out, "applied: {}", version_list(&applied))?;
+        writeln!(out, "current_version={}", current_migration_version(conn)?)?;
+        // v4 normalizes stored timestamps and leaves the ones it cannot parse as they are
+        if applied.contains(&4) {
+            report_malformed_timestamps(conn, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Warn about stored timestamps that are not RFC3339 (the first 10 are listed)
+fn report_malformed_timestamps(conn: &Connection, out: &mut dyn Write) -> Result<(), CliError> {
+    let malformed = scan_malformed_timestamps(conn)?;
+    if malformed.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "Warning: {} malformed timestamp(s) found in database:", malformed.len())?;
+    for m in malformed.iter().take(10) {
+        writeln!(out, "  - {}.{} (rowid {}): {:?}", m.table, m.column, m.rowid, m.value)?;
+    }
+    Ok(())
+}
+
+fn version_list(versions: &[i64]) -> String {
+    if versions.is_empty() {
+        return "none".to_string();
+    }
+    versions.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+/// Run db doctor command; exits 1 when problems remain after the run
+pub fn run_db_doctor(fix: bool, providers_file: Option<&str>, db_path: Option<&str>, format: Format, allow_newer_schema: bool) -> Result<(), CliError> {
+    if fix && allow_newer_schema {
+        return Err(CliError::validation("db doctor", "--fix cannot be combined with --allow-newer-schema"));
+    }
+    let binding;
+    let path = match db_path { Some(p) => p, None => { binding = resolve_db_path(); &binding } };
+    let conn = if allow_newer_schema { open_db_allow_newer_schema(path)? } else { open_or_create_db(path)? };
+
+    // The provider check is skipped when providers.yaml cannot be found or parsed
+    let providers_path = match providers_file {
+        Some(p) => Some(p.to_string()),
+        None => resolve_config_paths(None, None).ok().map(|(_, providers)| providers),
+    };
+    let known_providers: Option<Vec<String>> = providers_path
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|s| parse_providers_yaml(&s).ok())
+        .map(|cfg| cfg.providers.keys().cloned().collect());
+
+    let report = check_db_health(&conn, known_providers.as_deref())?;
+    let fixed = if fix && report.fixable_problems() > 0 { Some(fix_orphans(&conn)?) } else { None };
+    // Orphans also show up as FK violations, so recount after fixing
+    let remaining = if fixed.is_some() { check_db_health(&conn, known_providers.as_deref())?.problem_count() } else { report.problem_count() };
+
+    match format {
+        Format::Text => {
+            print_health_report(&report, known_providers.is_some());
+            if let Some(f) = &fixed {
+                println!(
+                    "fixed: deleted {} sessions, {} messages, {} tasks; cleared {} task assignees",
+                    f.sessions_deleted, f.messages_deleted, f.tasks_deleted, f.task_assignees_cleared
+                );
+            }
+            if remaining == 0 { println!("OK: no problems found"); }
+        }
+        Format::Json => println!("{}", serde_json::json!({
+            "report": report,
+            "providers_checked": known_providers.is_some(),
+            "fixed": fixed,
+            "problems_remaining": remaining,
+        })),
+    }
+    if remaining > 0 {
+        let hint = if !fix && report.fixable_problems() > 0 { " (orphans can be removed with --fix)" } else { "" };
+        return Err(CliError::Other(format!("db doctor: {} problem(s) found{}", remaining, hint)));
+    }
+    Ok(())
+}
+
+fn print_health_report(report: &DbHealthReport, providers_checked: bool) {
+    let status = |n: usize| if n == 0 { "ok".to_string() } else { n.to_string() };
+    println!("integrity_check: {}", if report.integrity_errors.is_empty() { "ok" } else { "FAILED" });
+    for line in &report.integrity_errors { println!("  {}", line); }
+    println!("foreign_key_check: {}", status(report.foreign_key_violations as usize));
+    println!("orphaned sessions: {}", status(report.orphaned_sessions as usize));
+    println!("orphaned messages: {}", status(report.orphaned_messages as usize));
+    println!("orphaned tasks: {}", status(report.orphaned_tasks as usize));
+    println!("dangling task assignees: {}", status(report.dangling_task_assignees as usize));
+    if providers_checked {
+        println!("agents with unknown provider: {}", status(report.invalid_provider_agents.len()));
+        for a in &report.invalid_provider_agents { println!("  {} (project {}): {}", a.name, a.project_id, a.provider); }
+    } else {
+        println!("agents with unknown provider: skipped (providers.yaml not found)");
     }
+    println!("duplicate agent names: {}", status(report.duplicate_agent_names.len()));
+    for d in &report.duplicate_agent_names { println!("  {} (project {}): {} rows", d.name, d.project_id, d.count); }
 }
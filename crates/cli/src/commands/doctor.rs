@@ -1,14 +1,23 @@
 //! Doctor command implementation
 
 use std::collections::BTreeMap;
-use std::time::{Duration, Instant};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use config_model::parse_providers_yaml;
+use config_model::{parse_providers_yaml, ProvidersConfig};
 use crate::cli::commands::Format;
-use crate::utils::{resolve_config_paths, DEFAULT_TIMEOUT_PER_PROVIDER_MS, DEFAULT_TIMEOUT_GLOBAL_MS, exit_with};
-use crate::utils::timeouts::run_with_timeout;
+use crate::utils::{
+    resolve_config_paths, resolve_db_path, write_atomic, DEFAULT_TIMEOUT_PER_PROVIDER_MS, DEFAULT_TIMEOUT_GLOBAL_MS,
+    DOCTOR_PROBE_CACHE_TTL_SECS, CliError, ProviderErrorKind, toned, Tone, expand_cmd_path,
+};
+use crate::utils::timeouts::{parse_duration_spec, run_with_timeout};
+use db::now_iso8601_utc;
 use crate::logging::ndjson_self_check;
+use crate::tmux::pipe_pane_only_supported;
 
 /// Probe result structure
 #[derive(Debug, Clone)]
@@ -21,77 +30,30 @@ pub struct ProbeResult {
     pub error: Option<String>,
 }
 
-/// Run doctor command
-pub fn run_doctor(format: Format, ndjson_sample: Option<&str>, snapshot_path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+/// Run doctor command; provider version probes come from the on-disk cache unless `no_cache` is set
+pub fn run_doctor(format: Format, ndjson_sample: Option<&str>, snapshot_path: Option<&str>, check_keys: bool, no_cache: bool) -> Result<(), CliError> {
     let per_timeout = DEFAULT_TIMEOUT_PER_PROVIDER_MS;
     let pb = ProgressBar::new_spinner();
     pb.set_style(ProgressStyle::with_template("{spinner} doctor").unwrap());
     pb.enable_steady_tick(Duration::from_millis(120));
     let global_cap: u64 = DEFAULT_TIMEOUT_GLOBAL_MS; // 20s global cap
 
-    // Try to read providers.yaml to get cmd/help/version args; fallback to built-in probes
-    let mut results: Vec<ProbeResult> = Vec::new();
-    let providers_cfg = resolve_config_paths(None, None)
-        .ok()
-        .and_then(|(_project_path, providers_path)| std::fs::read_to_string(&providers_path).ok())
-        .and_then(|s| parse_providers_yaml(&s).ok());
-
-    let started = Instant::now();
-    if let Some(cfg) = providers_cfg {
-        let empty: Vec<String> = Vec::new();
-        let gem_bin = cfg.providers.get("gemini").map(|p| p.cmd.clone()).unwrap_or_else(|| "gemini".into());
-        let cla_bin = cfg.providers.get("claude").map(|p| p.cmd.clone()).unwrap_or_else(|| "claude".into());
-        let cur_bin = cfg.providers.get("cursor-agent").map(|p| p.cmd.clone()).unwrap_or_else(|| "cursor-agent".into());
-        let handles = vec![
-            std::thread::spawn({ let gem_bin = gem_bin.clone(); let empty = empty.clone(); move || probe_version_only("gemini", &gem_bin, &empty, per_timeout) }),
-            std::thread::spawn({ let cla_bin = cla_bin.clone(); let empty = empty.clone(); move || probe_version_only("claude", &cla_bin, &empty, per_timeout) }),
-            std::thread::spawn({ let cur_bin = cur_bin.clone(); let empty = empty.clone(); move || probe_version_only("cursor-agent", &cur_bin, &empty, per_timeout) }),
-            std::thread::spawn(move || probe_tmux(per_timeout)),
-            std::thread::spawn(move || probe_git(per_timeout)),
-        ];
-        for h in handles {
-            let remain = global_cap.saturating_sub(started.elapsed().as_millis() as u64);
-            if remain == 0 { break; }
-            let r = h.join().unwrap_or_else(|_| ProbeResult { name: "unknown".into(), present: false, version: None, supports: BTreeMap::new(), timed_out: true, error: Some("thread_panic".into()) });
-            results.push(r);
-        }
-    } else {
-        let empty: Vec<String> = Vec::new();
-        let handles = vec![
-            std::thread::spawn({ let empty = empty.clone(); move || probe_version_only("gemini", "gemini", &empty, per_timeout) }),
-            std::thread::spawn({ let empty = empty.clone(); move || probe_version_only("claude", "claude", &empty, per_timeout) }),
-            std::thread::spawn({ let empty = empty.clone(); move || probe_version_only("cursor-agent", "cursor-agent", &empty, per_timeout) }),
-            std::thread::spawn(move || probe_tmux(per_timeout)),
-            std::thread::spawn(move || probe_git(per_timeout)),
-        ];
-        for h in handles {
-            let remain = global_cap.saturating_sub(started.elapsed().as_millis() as u64);
-            if remain == 0 { break; }
-            let r = h.join().unwrap_or_else(|_| ProbeResult { name: "unknown".into(), present: false, version: None, supports: BTreeMap::new(), timed_out: true, error: Some("thread_panic".into()) });
-            results.push(r);
-        }
+    let cache_path = probe_cache_path();
+    let mut cache = if no_cache { ProbeCache::default() } else { ProbeCache::load(&cache_path) };
+    let (results, env_keys) = collect_probes(per_timeout, global_cap, check_keys, Some(&mut cache));
+    if let Err(e) = cache.save(&cache_path) {
+        eprintln!("Warning: doctor cache {}: {}", cache_path.display(), e);
     }
+    let env_keys = check_keys.then_some(env_keys);
 
     // Derive status and worst error code according to spec
-    let mut any_timeout = false;
-    let mut any_missing = false;
-    let degraded = false;
-
-    for r in &results {
-        if r.timed_out { any_timeout = true; }
-        if !r.present { any_missing = true; }
-    }
+    let any_timeout = results.iter().any(|r| r.timed_out);
+    let any_missing = results.iter().any(|r| !r.present);
+    let degraded = env_keys.iter().flatten().any(|k| !k.present);
 
     // Relaxed policy: if version is obtained and not timed out, consider OK.
     // Reserve DEGRADE for real timeouts (handled via any_timeout) or explicit probe errors in future.
-
-    let status_text = if any_missing {
-        "KO"
-    } else if any_timeout || degraded {
-        "DEGRADE"
-    } else {
-        "OK"
-    };
+    let status_text = doctor_status(&results, env_keys.as_deref().unwrap_or_default());
 
     // NDJSON self-check if requested
     let mut ndjson_report: Option<Value> = None;
@@ -102,12 +64,12 @@ pub fn run_doctor(format: Format, ndjson_sample: Option<&str>, snapshot_path: Op
                 ndjson_invalid = report.get("errors").and_then(|e| e.as_array()).map(|a| !a.is_empty()).unwrap_or(false);
                 ndjson_report = Some(report);
             }
-            Err(e) => return exit_with(2, format!("ndjson: {}", e)),
+            Err(e) => return Err(CliError::validation("ndjson", e)),
         }
     }
 
     // Build JSON root for snapshot/printing
-    let root_json = build_doctor_json(status_text, &results, ndjson_report.clone());
+    let root_json = build_doctor_json(status_text, &results, ndjson_report.clone(), env_keys.as_deref());
 
     // Write snapshot if requested (even if status is KO/DEGRADE)
     if let Some(path) = snapshot_path {
@@ -119,7 +81,8 @@ pub fn run_doctor(format: Format, ndjson_sample: Option<&str>, snapshot_path: Op
     match format {
         Format::Text => {
             pb.finish_and_clear();
-            println!("doctor: {}", status_text);
+            let tone = match status_text { "OK" => Tone::Ok, "DEGRADE" => Tone::Warn, _ => Tone::Error };
+            println!("doctor: {}", toned(status_text, tone));
             for r in &results {
                 let ver = r.version.clone().unwrap_or_else(|| "(unknown)".into());
                 let mut feats: Vec<String> = r
@@ -137,6 +100,11 @@ pub fn run_doctor(format: Format, ndjson_sample: Option<&str>, snapshot_path: Op
                     if r.timed_out { " (timeout)" } else { "" }
                 );
             }
+            let backends: Vec<String> = agent_backends(&results).iter().map(|(name, ok)| format!("{}={}", name, ok)).collect();
+            println!("- backends: {}", backends.join(" "));
+            for k in env_keys.iter().flatten() {
+                println!("- env {}: present={}", k.name, if k.present { "true" } else { "false" });
+            }
             if let Some(rep) = ndjson_report {
                 println!("ndjson: {}", rep);
             }
@@ -149,36 +117,367 @@ pub fn run_doctor(format: Format, ndjson_sample: Option<&str>, snapshot_path: Op
 
     // Exit codes: 0 OK; 2 invalid input (ndjson invalid); 3 provider unavailable; 5 timeout; 1 degraded
     if ndjson_invalid {
-        return exit_with(2, "doctor: ndjson sample invalid".into());
+        return Err(CliError::validation("doctor", "ndjson sample invalid"));
     }
     if any_missing {
-        return exit_with(3, "doctor: missing required providers".into());
+        let missing: Vec<&str> = results.iter().filter(|r| !r.present).map(|r| r.name.as_str()).collect();
+        return Err(CliError::provider(missing.join(","), ProviderErrorKind::Unavailable));
     }
     if any_timeout {
-        return exit_with(5, "doctor: timed out while probing providers".into());
+        return Err(CliError::timeout("doctor: probing providers", global_cap));
     }
     if degraded {
-        return exit_with(1, "doctor: environment degraded (missing key flags)".into());
+        let missing: Vec<&str> = env_keys.iter().flatten().filter(|k| !k.present).map(|k| k.name.as_str()).collect();
+        return Err(format!("doctor: environment degraded (missing env keys: {})", missing.join(", ")).into());
     }
     Ok(())
 }
 
+/// One round of probes, as compared between `--watch` rounds
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DoctorSnapshot {
+    pub results: Vec<ProbeResult>,
+    /// Empty unless `--check-keys` is set
+    pub env_keys: Vec<ProbeResult>,
+}
+
+impl DoctorSnapshot {
+    fn status(&self) -> &'static str {
+        doctor_status(&self.results, &self.env_keys)
+    }
+}
+
+/// Run `doctor --watch`: re-probe every `interval` and print only what changed, until Ctrl-C
+pub fn run_doctor_watch(format: Format, interval: &str, check_keys: bool) -> Result<(), CliError> {
+    let interval = parse_duration_spec(interval).map_err(|e| CliError::validation("doctor", e))?;
+    if interval.is_zero() {
+        return Err(CliError::validation("doctor", "--interval must be greater than zero"));
+    }
+    let stop = interrupt_flag();
+    let mut probe = || {
+        let (results, env_keys) = collect_probes(DEFAULT_TIMEOUT_PER_PROVIDER_MS, DEFAULT_TIMEOUT_GLOBAL_MS, check_keys, None);
+        DoctorSnapshot { results, env_keys }
+    };
+    watch_doctor(&mut probe, interval, None, stop, format, &mut std::io::stdout())
+}
+
+/// Watch loop behind `run_doctor_watch`: the first round is always printed, later rounds only when
+/// `compare_snapshots` finds a change. Stops when `stop` is set or after `max_rounds`.
+pub(crate) fn watch_doctor(
+    probe: &mut dyn FnMut() -> DoctorSnapshot,
+    interval: Duration,
+    max_rounds: Option<usize>,
+    stop: &AtomicBool,
+    format: Format,
+    out: &mut dyn Write,
+) -> Result<(), CliError> {
+    let mut previous: Option<DoctorSnapshot> = None;
+    let mut rounds = 0;
+    while !stop.load(Ordering::SeqCst) {
+        let snapshot = probe();
+        let ts = now_iso8601_utc();
+        match &previous {
+            None => match format {
+                Format::Text => {
+                    writeln!(out, "[{}] doctor: {}", ts, snapshot.status())?;
+                    for r in &snapshot.results {
+                        writeln!(out, "  - {}: {}", r.name, describe_probe(r))?;
+                    }
+                }
+                Format::Json => {
+                    let mut root = build_doctor_json(snapshot.status(), &snapshot.results, None, Some(&snapshot.env_keys));
+                    root["ts"] = Value::String(ts);
+                    writeln!(out, "{}", root)?;
+                }
+            },
+            Some(prev) => {
+                let changes = compare_snapshots(prev, &snapshot);
+                if !changes.is_empty() {
+                    match format {
+                        Format::Text => {
+                            writeln!(out, "[{}] doctor: {}", ts, snapshot.status())?;
+                            for c in &changes {
+                                writeln!(out, "  - {}", c)?;
+                            }
+                        }
+                        Format::Json => writeln!(out, "{}", serde_json::json!({"ts": ts, "status": snapshot.status(), "changes": changes}))?,
+                    }
+                }
+            }
+        }
+        out.flush()?;
+        previous = Some(snapshot);
+        rounds += 1;
+        if max_rounds.is_some_and(|max| rounds >= max) {
+            break;
+        }
+        sleep_until_stopped(interval, stop);
+    }
+    Ok(())
+}
+
+/// Differences between two rounds: status transitions, tools appearing or disappearing,
+/// version changes, timeouts and env keys being set or unset
+pub(crate) fn compare_snapshots(prev: &DoctorSnapshot, next: &DoctorSnapshot) -> Vec<String> {
+    let mut changes = Vec::new();
+    if prev.status() != next.status() {
+        changes.push(format!("status: {} -> {}", prev.status(), next.status()));
+    }
+    for r in &next.results {
+        let Some(old) = prev.results.iter().find(|o| o.name == r.name) else {
+            changes.push(format!("{}: new probe ({})", r.name, describe_probe(r)));
+            continue;
+        };
+        match (old.present, r.present) {
+            (true, false) => changes.push(format!("{}: disappeared", r.name)),
+            (false, true) => changes.push(format!("{}: appeared ({})", r.name, describe_probe(r))),
+            (true, true) if old.version != r.version => changes.push(format!(
+                "{}: version {} -> {}",
+                r.name,
+                old.version.as_deref().unwrap_or("(unknown)"),
+                r.version.as_deref().unwrap_or("(unknown)")
+            )),
+            _ => {}
+        }
+        if old.timed_out != r.timed_out {
+            changes.push(format!("{}: {}", r.name, if r.timed_out { "timed out" } else { "responding again" }));
+        }
+    }
+    for k in &next.env_keys {
+        let was_present = prev.env_keys.iter().find(|o| o.name == k.name).map(|o| o.present);
+        if was_present != Some(k.present) {
+            changes.push(format!("env {}: {}", k.name, if k.present { "set" } else { "not set" }));
+        }
+    }
+    changes
+}
+
+fn describe_probe(r: &ProbeResult) -> String {
+    match (r.present, &r.version) {
+        (false, _) => "missing".into(),
+        (true, Some(v)) => v.clone(),
+        (true, None) => "(unknown)".into(),
+    }
+}
+
+/// Sleep for `interval` in short steps so Ctrl-C is noticed promptly
+fn sleep_until_stopped(interval: Duration, stop: &AtomicBool) {
+    let deadline = Instant::now() + interval;
+    while !stop.load(Ordering::SeqCst) {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            break;
+        }
+        std::thread::sleep(left.min(Duration::from_millis(100)));
+    }
+}
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_interrupt(_signal: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Flag set by Ctrl-C instead of killing the process, so the watch loop can return cleanly
+fn interrupt_flag() -> &'static AtomicBool {
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe
+    unsafe {
+        libc::signal(libc::SIGINT, on_interrupt as *const () as libc::sighandler_t);
+    }
+    &INTERRUPTED
+}
+
+/// Probe every provider CLI plus tmux and git in parallel; env keys are only read when `check_keys` is set.
+/// Uses providers.yaml for the binaries when it can be found, else the built-in names. Provider probes
+/// are read from and recorded in `cache` when one is given (`--watch` always probes).
+fn collect_probes(per_timeout: u64, global_cap: u64, check_keys: bool, cache: Option<&mut ProbeCache>) -> (Vec<ProbeResult>, Vec<ProbeResult>) {
+    let providers_cfg = resolve_config_paths(None, None)
+        .ok()
+        .and_then(|(_project_path, providers_path)| std::fs::read_to_string(&providers_path).ok())
+        .and_then(|s| parse_providers_yaml(&s).ok());
+
+    // Provider API keys are only checked on request: some CI environments omit them on purpose
+    let env_keys = if check_keys { providers_cfg.as_ref().map(probe_env_keys).unwrap_or_default() } else { Vec::new() };
+
+    let bin = |key: &str| providers_cfg.as_ref()
+        .and_then(|cfg| cfg.providers.get(key))
+        .map(|p| p.cmd.clone())
+        .unwrap_or_else(|| key.to_string());
+    let started = Instant::now();
+    let tools = vec![
+        std::thread::spawn(move || probe_tmux(per_timeout)),
+        std::thread::spawn(move || probe_git(per_timeout)),
+    ];
+    let bins = [("gemini", bin("gemini")), ("claude", bin("claude")), ("cursor-agent", bin("cursor-agent"))];
+    let mut results = probe_providers(&bins, per_timeout, global_cap, started, cache);
+    for h in tools {
+        if global_cap.saturating_sub(started.elapsed().as_millis() as u64) == 0 { break; }
+        results.push(h.join().unwrap_or_else(|_| panicked_probe()));
+    }
+    (results, env_keys)
+}
+
+/// Version probes for `(name, cmd)` providers, in order. A fresh `cache` entry for the binary stands in
+/// for the probe; the others run in parallel and are recorded in `cache`.
+pub(crate) fn probe_providers(
+    bins: &[(&str, String)],
+    per_timeout: u64,
+    global_cap: u64,
+    started: Instant,
+    mut cache: Option<&mut ProbeCache>,
+) -> Vec<ProbeResult> {
+    let now = unix_now();
+    // `Ok` holds a cached result, `Err` a probe still running
+    let pending: Vec<_> = bins.iter().map(|(name, cmd)| {
+        let stamp = BinaryStamp::of(cmd);
+        let hit = stamp.as_ref().zip(cache.as_deref()).and_then(|(stamp, cache)| cache.lookup(name, stamp, now));
+        match hit {
+            Some(result) => (Ok(result), stamp),
+            None => {
+                let (name, cmd) = (name.to_string(), cmd.clone());
+                (Err(std::thread::spawn(move || probe_version_only(&name, &cmd, &[], per_timeout))), stamp)
+            }
+        }
+    }).collect();
+    let mut results = Vec::with_capacity(pending.len());
+    for (probe, stamp) in pending {
+        let result = match probe {
+            Ok(cached) => cached,
+            Err(handle) => {
+                if global_cap.saturating_sub(started.elapsed().as_millis() as u64) == 0 { break; }
+                let result = handle.join().unwrap_or_else(|_| panicked_probe());
+                if let (Some(cache), Some(stamp)) = (cache.as_deref_mut(), stamp) {
+                    cache.record(stamp, &result, now);
+                }
+                result
+            }
+        };
+        results.push(result);
+    }
+    results
+}
+
+fn panicked_probe() -> ProbeResult {
+    ProbeResult { name: "unknown".into(), present: false, version: None, supports: BTreeMap::new(), timed_out: true, error: Some("thread_panic".into()) }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// `doctor-cache.json`, next to the database
+pub(crate) fn probe_cache_path() -> PathBuf {
+    let db_path = resolve_db_path();
+    Path::new(&db_path).parent().unwrap_or(Path::new(".")).join("doctor-cache.json")
+}
+
+/// Resolved path and modification time of a provider binary: a cached probe is reused only
+/// while both are unchanged
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct BinaryStamp {
+    pub path: String,
+    pub mtime: u64,
+}
+
+impl BinaryStamp {
+    /// Stamp of `cmd` as found directly or on `PATH`; `None` when it cannot be found
+    pub(crate) fn of(cmd: &str) -> Option<Self> {
+        let cmd = expand_cmd_path(cmd);
+        let path = if cmd.contains('/') {
+            PathBuf::from(&cmd)
+        } else {
+            std::env::split_paths(&std::env::var_os("PATH")?).map(|dir| dir.join(&cmd)).find(|p| p.is_file())?
+        };
+        let mtime = std::fs::metadata(&path).ok()?.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        Some(BinaryStamp { path: path.to_string_lossy().into_owned(), mtime })
+    }
+}
+
+/// One recorded provider probe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CachedProbe {
+    pub mtime: u64,
+    /// Unix seconds
+    pub probed_at: u64,
+    pub present: bool,
+    pub version: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Provider probes by binary path, stored as JSON at `probe_cache_path`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ProbeCache {
+    #[serde(default)]
+    pub probes: BTreeMap<String, CachedProbe>,
+}
+
+impl ProbeCache {
+    /// The cache at `path`; a missing or unreadable file is an empty cache
+    pub(crate) fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() { std::fs::create_dir_all(dir)?; }
+        write_atomic(path, &serde_json::to_string_pretty(self)?)
+    }
+
+    /// Probe result for `name` recorded for this exact binary less than the TTL before `now`
+    pub(crate) fn lookup(&self, name: &str, stamp: &BinaryStamp, now: u64) -> Option<ProbeResult> {
+        let entry = self.probes.get(&stamp.path)?;
+        let fresh = entry.mtime == stamp.mtime && now.saturating_sub(entry.probed_at) < DOCTOR_PROBE_CACHE_TTL_SECS;
+        fresh.then(|| ProbeResult {
+            name: name.into(),
+            present: entry.present,
+            version: entry.version.clone(),
+            supports: BTreeMap::new(),
+            timed_out: false,
+            error: entry.error.clone(),
+        })
+    }
+
+    pub(crate) fn record(&mut self, stamp: BinaryStamp, result: &ProbeResult, now: u64) {
+        // A timed-out probe says nothing about the binary; probe again next time
+        if result.timed_out {
+            self.probes.remove(&stamp.path);
+            return;
+        }
+        self.probes.insert(stamp.path, CachedProbe {
+            mtime: stamp.mtime,
+            probed_at: now,
+            present: result.present,
+            version: result.version.clone(),
+            error: result.error.clone(),
+        });
+    }
+}
+
+/// `KO` when a tool is missing, `DEGRADE` on a timeout or a missing env key, else `OK`
+pub(crate) fn doctor_status(results: &[ProbeResult], env_keys: &[ProbeResult]) -> &'static str {
+    if results.iter().any(|r| !r.present) {
+        "KO"
+    } else if results.iter().any(|r| r.timed_out) || env_keys.iter().any(|k| !k.present) {
+        "DEGRADE"
+    } else {
+        "OK"
+    }
+}
+
 /// Probe help command
 fn probe_help(bin: &str, help_args: &[&str], timeout_ms: u64) -> Result<String, String> {
     let timeout = Duration::from_millis(timeout_ms);
-    let debug = std::env::var("DOCTOR_DEBUG").ok().map(|v| v == "1" || v.to_lowercase() == "true").unwrap_or(false);
-    if debug { eprintln!("[doctor] help probe: {} {:?}", bin, help_args); }
+    tracing::debug!("help probe: {} {:?}", bin, help_args);
     match run_with_timeout(bin, help_args, timeout) {
         Ok((_code, out, err)) => {
             let text = if !out.trim().is_empty() { out } else { err };
             return Ok(text);
         }
         Err(e) => {
-            if debug { eprintln!("[doctor] help direct failed: {} {:?} => {}", bin, help_args, e); }
+            tracing::debug!("help direct failed: {} {:?} => {}", bin, help_args, e);
             // Fallback via login shell to inherit PATH managers (e.g. NVM)
             let joined = std::iter::once(bin).chain(help_args.iter().copied()).collect::<Vec<_>>().join(" ");
             let shell_cmd = format!("bash -lc '{}'", joined.replace("'", "'\\''"));
-            if debug { eprintln!("[doctor] help via shell: {}", shell_cmd); }
+            tracing::debug!("help via shell: {}", shell_cmd);
             match run_with_timeout("bash", &["-lc", &joined], timeout) {
                 Ok((_code, out, err)) => {
                     let text = if !out.trim().is_empty() { out } else { err };
@@ -194,8 +493,7 @@ fn probe_help(bin: &str, help_args: &[&str], timeout_ms: u64) -> Result<String,
 fn probe_version(bin: &str, candidates: &[&[&str]], timeout_ms: u64) -> Option<String> {
     for args in candidates {
         let timeout = Duration::from_millis(timeout_ms);
-        let debug = std::env::var("DOCTOR_DEBUG").ok().map(|v| v == "1" || v.to_lowercase() == "true").unwrap_or(false);
-        if debug { eprintln!("[doctor] version probe: {} {:?}", bin, args); }
+        tracing::debug!("version probe: {} {:?}", bin, args);
         match run_with_timeout(bin, args, timeout) {
             Ok((_code, out, err)) => {
                 let text = if !out.trim().is_empty() { out } else { err };
@@ -203,7 +501,7 @@ fn probe_version(bin: &str, candidates: &[&[&str]], timeout_ms: u64) -> Option<S
                 if !line.is_empty() { return Some(line); }
             }
             Err(e) => {
-                if debug { eprintln!("[doctor] version direct failed: {} {:?} => {}", bin, args, e); }
+                tracing::debug!("version direct failed: {} {:?} => {}", bin, args, e);
                 // shell fallback
                 let joined = std::iter::once(bin).chain(args.iter().copied()).collect::<Vec<_>>().join(" ");
                 if let Ok((_code, out, err)) = run_with_timeout("bash", &["-lc", &joined], timeout) {
@@ -225,12 +523,23 @@ fn probe_version_only(name: &str, cmd: &str, version_args: &[String], timeout_ms
     } else {
         vec![version_args.iter().map(|s| s.as_str()).collect()]
     };
-    let version = probe_version(cmd, &version_candidates.iter().map(|v| v.as_slice()).collect::<Vec<_>>(), timeout_ms);
+    let cmd = expand_cmd_path(cmd);
+    let version = probe_version(&cmd, &version_candidates.iter().map(|v| v.as_slice()).collect::<Vec<_>>(), timeout_ms);
     if let Some(v) = version {
-        ProbeResult { name: name.into(), present: true, version: Some(v), supports, timed_out: false, error: None }
-    } else {
-        ProbeResult { name: name.into(), present: false, version: None, supports, timed_out: false, error: Some("version_probe_failed".into()) }
+        return ProbeResult { name: name.into(), present: true, version: Some(v), supports, timed_out: false, error: None };
     }
+    // No version output: the binary may still be there and just print nothing, as probe_git checks
+    match run_with_timeout(&cmd, &["--help"], Duration::from_millis(timeout_ms)) {
+        Ok(_) => ProbeResult { name: name.into(), present: true, version: None, supports, timed_out: false, error: None },
+        Err(e) if e == "timeout" => ProbeResult { name: name.into(), present: true, version: None, supports, timed_out: true, error: Some(e) },
+        Err(e) if is_not_found(&e) => ProbeResult { name: name.into(), present: false, version: None, supports, timed_out: false, error: Some("version_probe_failed".into()) },
+        Err(e) => ProbeResult { name: name.into(), present: true, version: None, supports, timed_out: false, error: Some(e) },
+    }
+}
+
+/// Whether a spawn error means the binary is missing (`io::ErrorKind::NotFound`, or a shell's "not found")
+fn is_not_found(error: &str) -> bool {
+    error.contains("No such file or directory") || error.contains("not found")
 }
 
 /// Parse tmux list commands
@@ -280,9 +589,14 @@ fn probe_tmux(timeout_ms: u64) -> ProbeResult {
         }
     }
     
-    // Check pipe-pane support via list-commands
+    // Check pipe-pane support via list-commands, and whether `-o` is available in this version
     let list = probe_help("tmux", &["list-commands"], timeout_ms).unwrap_or_default();
-    let supports = parse_tmux_list_commands(&list);
+    let mut supports = parse_tmux_list_commands(&list);
+    let pipe_pane = supports.get("pipe_pane").copied().unwrap_or(false);
+    supports.insert("pipe_pane_o".into(), pipe_pane && version.as_deref().is_some_and(pipe_pane_only_supported));
+    // A running server is not required (agent run starts one), but tells whether agents may already be up
+    let server_running = matches!(run_with_timeout("tmux", &["list-sessions"], Duration::from_millis(timeout_ms)), Ok((0, _, _)));
+    supports.insert("server_running".into(), server_running);
     
     // Additional WSL2/CI compatibility check
     if let Some(ver) = &version {
@@ -320,8 +634,36 @@ fn probe_git(timeout_ms: u64) -> ProbeResult {
     ProbeResult { name: "git".into(), present: true, version, supports, timed_out, error }
 }
 
+/// Check the `required_env_keys` of every provider; only presence is reported, never the value
+pub fn probe_env_keys(providers: &ProvidersConfig) -> Vec<ProbeResult> {
+    let mut keys: Vec<&str> = providers.providers.values()
+        .flat_map(|tpl| tpl.required_env_keys.iter().map(|k| k.as_str()))
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .map(|key| {
+            let present = std::env::var(key).is_ok();
+            ProbeResult {
+                name: key.into(),
+                present,
+                version: None,
+                supports: BTreeMap::new(),
+                timed_out: false,
+                error: (!present).then(|| "not set".into()),
+            }
+        })
+        .collect()
+}
+
+/// `agent run --backend` values usable here: tmux needs the tmux probe to pass, process always works
+pub(crate) fn agent_backends(results: &[ProbeResult]) -> [(&'static str, bool); 2] {
+    let tmux = results.iter().any(|r| r.name == "tmux" && r.present);
+    [("tmux", tmux), ("process", true)]
+}
+
 /// Build doctor JSON output
-fn build_doctor_json(status_text: &str, results: &Vec<ProbeResult>, ndjson_report: Option<Value>) -> Value {
+pub(crate) fn build_doctor_json(status_text: &str, results: &[ProbeResult], ndjson_report: Option<Value>, env_keys: Option<&[ProbeResult]>) -> Value {
     let arr: Vec<_> = results
         .iter()
         .map(|r| {
@@ -335,14 +677,22 @@ fn build_doctor_json(status_text: &str, results: &Vec<ProbeResult>, ndjson_repor
             })
         })
         .collect();
+    let backends: serde_json::Map<String, Value> = agent_backends(results).into_iter().map(|(name, ok)| (name.to_string(), Value::Bool(ok))).collect();
     let mut root = serde_json::json!({
         "status": status_text,
-        "results": arr
+        "results": arr,
+        "backends": backends,
     });
     if let Some(rep) = ndjson_report {
         if let Some(obj) = root.as_object_mut() {
             obj.insert("ndjson".into(), rep);
         }
     }
+    if let Some(keys) = env_keys {
+        let arr: Vec<_> = keys.iter().map(|k| serde_json::json!({"key": k.name, "present": k.present})).collect();
+        if let Some(obj) = root.as_object_mut() {
+            obj.insert("env_keys".into(), Value::Array(arr));
+        }
+    }
     root
 }
@@ -3,30 +3,76 @@
 use std::fs;
 use std::path::Path;
 use config_model::parse_project_yaml;
-use db::{open_or_create_db, sync_project_from_config};
-use crate::utils::resolve_db_path;
-use crate::utils::errors::exit_with;
+use db::{open_or_create_db, sync_project_from_config, SyncReport};
+use crate::cli::commands::Format;
+use crate::commands::config::{init_report, sync_project_with_progress, sync_report_json, FileAction, FileResult};
+use crate::utils::{resolve_db_path, decorated, say};
+use crate::utils::errors::CliError;
 
-/// Run project initialization command
-pub fn run_init(config_dir: Option<&str>, force: bool, skip_db: bool) -> Result<(), Box<dyn std::error::Error>> {
+/// Run project initialization command; with `Format::Json` only the final report is printed
+pub fn run_init(config_dir: Option<&str>, force: bool, skip_db: bool, format: Format) -> Result<(), CliError> {
     let base = config_dir.unwrap_or("./config");
+    let mut report = InitReport::default();
+    let result = init_project(base, force, skip_db, format, &mut report);
+    if let Format::Json = format {
+        println!("{}", init_json(&report, result.as_ref().err()));
+    }
+    result
+}
+
+/// What `init` got through, filled in step by step so a failure still reports the steps before it
+#[derive(Debug, Default)]
+pub struct InitReport {
+    pub db_initialized: bool,
+    pub files: Vec<FileResult>,
+    /// Project name from the project config, with the report of syncing it into the database
+    pub sync: Option<(String, SyncReport)>,
+    /// Project validation warnings; `None` until the project config was validated
+    pub project_warnings: Option<Vec<String>>,
+    pub providers_valid: bool,
+}
+
+/// `--format json` report of `init`: the `config init` report (`status`, `files`, `error`) plus
+/// `db_initialized`, `config_files`, `sync` (the `config sync --format json` report) and
+/// `validation`; steps not reached are `null`/`false`
+pub fn init_json(report: &InitReport, error: Option<&CliError>) -> serde_json::Value {
+    let mut json = init_report(&report.files, error);
+    json["db_initialized"] = report.db_initialized.into();
+    json["config_files"] = report.files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>().into();
+    json["sync"] = report.sync.as_ref().map(|(project, sync)| sync_report_json(project, sync, false)).into();
+    json["validation"] = serde_json::json!({
+        "project": {
+            "valid": report.project_warnings.is_some(),
+            "warnings": report.project_warnings.as_deref().unwrap_or_default(),
+        },
+        "providers": {"valid": report.providers_valid},
+    });
+    json
+}
+
+fn init_project(base: &str, force: bool, skip_db: bool, format: Format, report: &mut InitReport) -> Result<(), CliError> {
+    let text = matches!(format, Format::Text);
+    let step = |icon: &str, msg: &str| if text { say(&decorated(icon, msg)) };
     
-    println!("🚀 Initializing multi-agents project...");
+    step("🚀", "Initializing multi-agents project...");
     
     // 1. Initialize database (if not skipped)
     if !skip_db {
-        println!("📊 Initializing database...");
+        step("📊", "Initializing database...");
         let db_path = resolve_db_path();
         match open_or_create_db(&db_path) {
-            Ok(_) => println!("✅ Database initialized"),
-            Err(e) => return exit_with(7, format!("Database initialization failed: {}", e)),
+            Ok(_) => {
+                report.db_initialized = true;
+                step("✅", "Database initialized");
+            }
+            Err(e) => return Err(e.into()),
         }
     } else {
-        println!("⏭️  Skipping database initialization");
+        step("⏭️ ", "Skipping database initialization");
     }
     
     // 2. Create config files (if not exist or force)
-    println!("📝 Creating configuration files...");
+    step("📝", "Creating configuration files...");
     let proj_path = format!("{}/project.yaml", base);
     let prov_path = format!("{}/providers.yaml", base);
     
@@ -76,14 +122,16 @@ providers:
     allowlist_flag: "--allowed-tools"
 "#;
 
-    let write_file = |path: &str, contents: &str| -> Result<(), Box<dyn std::error::Error>> {
+    let mut write_file = |path: &str, contents: &str| -> Result<(), CliError> {
         if Path::new(path).exists() && !force {
-            println!("⏭️  SKIP: {} exists (use --force to overwrite)", path);
+            step("⏭️ ", &format!("SKIP: {} exists (use --force to overwrite)", path));
+            report.files.push(FileResult { path: path.to_string(), action: FileAction::Skipped });
             return Ok(());
         }
         std::fs::create_dir_all(Path::new(path).parent().unwrap())?;
         std::fs::write(path, contents)?;
-        println!("✅ WROTE: {}", path);
+        step("✅", &format!("WROTE: {}", path));
+        report.files.push(FileResult { path: path.to_string(), action: FileAction::Wrote });
         Ok(())
     };
 
@@ -91,40 +139,50 @@ providers:
     write_file(&prov_path, providers_yaml)?;
     
     // 3. Synchronize project and agents to database
-    println!("🔄 Synchronizing project and agents...");
+    step("🔄", "Synchronizing project and agents...");
     let db_path = resolve_db_path();
     let conn = open_or_create_db(&db_path)?;
     
     let proj_s = fs::read_to_string(&proj_path)?;
-    let project_config = parse_project_yaml(&proj_s).map_err(|e| format!("Invalid project config: {}", e))?;
+    let project_config = parse_project_yaml(&proj_s).map_err(|e| CliError::validation("project", e))?;
     
-    match sync_project_from_config(&conn, &project_config) {
-        Ok(_) => println!("✅ Project synchronized successfully"),
-        Err(e) => return exit_with(7, format!("Synchronization failed: {}", e)),
-    }
+    let synced = if text { sync_project_with_progress(&conn, &project_config) } else { sync_project_from_config(&conn, &project_config) };
+    report.sync = Some((project_config.project.clone(), synced?));
+    step("✅", "Project synchronized successfully");
     
     // 4. Validate configuration
-    println!("🔍 Validating configuration...");
+    step("🔍", "Validating configuration...");
     let prov_s = fs::read_to_string(&prov_path)?;
-    let providers_config = config_model::parse_providers_yaml(&prov_s).map_err(|e| format!("Invalid providers config: {}", e))?;
+    let providers_config = config_model::parse_providers_yaml(&prov_s).map_err(|e| CliError::validation("providers", e))?;
     
     match config_model::validate_project_config(&project_config, &providers_config) {
-        Ok(_) => println!("✅ Project configuration valid"),
-        Err(e) => return exit_with(6, format!("Project validation failed: {}", e)),
+        Ok(warnings) => {
+            for w in &warnings {
+                eprintln!("Warning: {}", w);
+            }
+            report.project_warnings = Some(warnings.iter().map(|w| w.to_string()).collect());
+            step("✅", "Project configuration valid");
+        }
+        Err(e) => return Err(CliError::validation("project", e)),
     }
     
-    match config_model::validate_providers_config(&providers_config) {
-        Ok(_) => println!("✅ Providers configuration valid"),
-        Err(e) => return exit_with(6, format!("Providers validation failed: {}", e)),
+    match config_model::validate_providers_config(&providers_config, false) {
+        Ok(_) => {
+            report.providers_valid = true;
+            step("✅", "Providers configuration valid");
+        }
+        Err(e) => return Err(CliError::validation("providers", e)),
     }
     
-    println!("\n🎉 Project initialized successfully!");
-    println!("📁 Config directory: {}", base);
-    println!("💾 Database: {}", db_path);
-    println!("\n🚀 Next steps:");
-    println!("  • multi-agents send --to @all --message \"Hello world!\"");
-    println!("  • multi-agents session start --agent backend");
-    println!("  • multi-agents session list");
+    if text {
+        say(&format!("\n{}", decorated("🎉", "Project initialized successfully!")));
+        say(&decorated("📁", &format!("Config directory: {}", base)));
+        say(&decorated("💾", &format!("Database: {}", db_path)));
+        say(&format!("\n{}", decorated("🚀", "Next steps:")));
+        say("  • multi-agents send --to @all --message \"Hello world!\"");
+        say("  • multi-agents session start --agent backend");
+        say("  • multi-agents session list");
+    }
     
     Ok(())
 }
@@ -0,0 +1,234 @@
+//! Read back NDJSON logs and split role-keyed files into per-agent ones
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::Duration;
+use serde::Serialize;
+use crate::cli::commands::Format;
+use db::{find_project_id, list_agents, open_readonly_db_any_schema, IdOrName};
+use crate::logging::{agent_log_path_for_read, close_path, log_base, log_path, project_log_files, Event};
+use crate::tui::components::{LogEntry, LogLevel};
+use crate::utils::{render_toned, resolve_db_path, styling_enabled, CliError, Tone};
+
+/// How often `--follow` checks the file for appended lines
+const FOLLOW_POLL: Duration = Duration::from_millis(250);
+
+/// Print an agent's log, or a role's file merged with its agents' files by `ts`, keeping lines at or above `level`
+pub fn run_logs_show(project: &str, role: Option<&str>, follow: bool, level: Option<&str>, no_color: bool, agent: Option<&str>) -> Result<(), CliError> {
+    let min_level = level
+        .map(|l| LogLevel::from_str(l).ok_or_else(|| CliError::validation("level", format!("unknown level '{}' (trace, debug, info, warn, error)", l))))
+        .transpose()?;
+    let styled = !no_color && styling_enabled();
+    let paths = show_log_paths(&log_base(), project, role, agent, &project_agents(project));
+    let mut sources = Vec::new();
+    for path in paths {
+        match File::open(&path) {
+            Ok(file) => sources.push((BufReader::new(file), path)),
+            // A role's agents may not have logged anything yet
+            Err(_) if role.is_some() => {}
+            Err(e) if agent.is_some() && !Path::new(&path).exists() => return Err(CliError::Other(format!(
+                "cannot read log {}: {}; logs written before per-agent files are in <role>.ndjson (use --role, or run `logs migrate --project {}`)",
+                path, e, project
+            ))),
+            Err(e) => return Err(CliError::Other(format!("cannot read log {}: {}", path, e))),
+        }
+    }
+    if sources.is_empty() {
+        let path = log_path(&log_base(), project, role.unwrap_or_default());
+        return Err(CliError::Other(format!("cannot read log {}: no such file", path)));
+    }
+    let mut out = std::io::stdout();
+    let mut entries = Vec::new();
+    for (reader, _) in &mut sources {
+        entries.extend(read_new_entries(reader, min_level)?);
+    }
+    print_merged(entries, styled, &mut out)?;
+    if !follow {
+        return Ok(());
+    }
+    loop {
+        std::thread::sleep(FOLLOW_POLL);
+        let mut entries = Vec::new();
+        for (reader, path) in &mut sources {
+            // A shorter file was truncated or rotated: start over from its beginning
+            let len = std::fs::metadata(&*path).map(|m| m.len()).unwrap_or(0);
+            if len < reader.stream_position()? {
+                *reader = BufReader::new(File::open(&*path)?);
+            }
+            entries.extend(read_new_entries(reader, min_level)?);
+        }
+        print_merged(entries, styled, &mut out)?;
+    }
+}
+
+/// Agents of `project` as (name, role), from the database; empty when it has none or there is no database
+fn project_agents(project: &str) -> Vec<(String, String)> {
+    let Ok(conn) = open_readonly_db_any_schema(&resolve_db_path()) else { return Vec::new() };
+    let Ok(Some(project_id)) = find_project_id(&conn, IdOrName::Name(project)) else { return Vec::new() };
+    list_agents(&conn, &project_id)
+        .map(|agents| agents.into_iter().map(|a| (a.name, a.role)).collect())
+        .unwrap_or_default()
+}
+
+/// Files `logs show` reads. An agent's own file, or the role file it shared before per-agent files
+/// (`agent_log_path_for_read`) when `agents` knows its role; for a role, `<role>.ndjson` followed by
+/// the files of the role's agents.
+pub fn show_log_paths(base: &str, project: &str, role: Option<&str>, agent: Option<&str>, agents: &[(String, String)]) -> Vec<String> {
+    if let Some(agent) = agent {
+        return vec![match agents.iter().find(|(name, _)| name == agent) {
+            Some((_, role)) => agent_log_path_for_read(base, project, agent, role),
+            None => log_path(base, project, agent),
+        }];
+    }
+    let Some(role) = role else { return Vec::new() };
+    std::iter::once(log_path(base, project, role))
+        .chain(agents.iter().filter(|(name, r)| r == role && name != role).map(|(name, _)| log_path(base, project, name)))
+        .collect()
+}
+
+/// Print `entries` from several files in `ts` order; lines of one file keep their order
+fn print_merged(mut entries: Vec<LogEntry>, styled: bool, out: &mut dyn Write) -> std::io::Result<()> {
+    entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    for entry in &entries {
+        writeln!(out, "{}", format_log_entry(entry, styled))?;
+    }
+    out.flush()
+}
+
+/// Print every complete line left in `reader`; a partial last line is left for the next call
+pub fn print_new_lines<R: BufRead + Seek>(reader: &mut R, min_level: Option<LogLevel>, styled: bool, out: &mut dyn Write) -> std::io::Result<()> {
+    for entry in read_new_entries(reader, min_level)? {
+        writeln!(out, "{}", format_log_entry(&entry, styled))?;
+    }
+    out.flush()
+}
+
+/// Entries of the complete lines left in `reader` at or above `min_level`; a partial last line is
+/// left for the next call
+fn read_new_entries<R: BufRead + Seek>(reader: &mut R, min_level: Option<LogLevel>) -> std::io::Result<Vec<LogEntry>> {
+    let mut entries = Vec::new();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            break;
+        }
+        if !line.ends_with(b"\n") {
+            reader.seek(SeekFrom::Current(-(read as i64)))?;
+            break;
+        }
+        // A line that is not valid UTF-8 is shown as best it can be rather than ending the follow
+        let Some(entry) = LogEntry::from_ndjson_event(&String::from_utf8_lossy(&line)) else { continue };
+        if min_level.is_none_or(|min| entry.level.severity() >= min.severity()) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// `ts | LEVEL | event | text`, with warn and error levels colored when `styled`
+pub fn format_log_entry(entry: &LogEntry, styled: bool) -> String {
+    let label = format!("{:<5}", entry.level.label());
+    let label = match entry.level {
+        LogLevel::Warn => render_toned(&label, Tone::Warn, styled),
+        LogLevel::Error => render_toned(&label, Tone::Error, styled),
+        _ => label,
+    };
+    format!("{} | {} | {} | {}", entry.timestamp, label, entry.source.as_deref().unwrap_or(""), entry.message)
+}
+
+/// What `logs migrate` moved
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LogMigration {
+    /// Files that held lines of other agents
+    pub files_split: usize,
+    pub lines_moved: usize,
+    /// Agent files that received lines, sorted
+    pub agents: Vec<String>,
+}
+
+/// Run `logs migrate`: move every line of `{role}.ndjson` files into its agent's `{agent}.ndjson`
+pub fn run_logs_migrate(project: &str, format: Format) -> Result<(), CliError> {
+    let report = migrate_project_logs(&log_base(), project)?;
+    match format {
+        Format::Text => println!(
+            "OK: moved {} line(s) from {} file(s) into {} agent file(s)",
+            report.lines_moved, report.files_split, report.agents.len()
+        ),
+        Format::Json => println!("{}", serde_json::to_value(&report)?),
+    }
+    Ok(())
+}
+
+/// Split the lines of `{stem}.ndjson`: a line whose `agent_id` names another agent goes to that
+/// agent; the file's own agent, role-level events without `agent_id` and unparseable lines stay.
+pub fn split_log_lines<'a>(stem: &str, content: &'a str) -> (Vec<&'a str>, BTreeMap<String, Vec<&'a str>>) {
+    let mut kept = Vec::new();
+    let mut moved: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        match Event::from_line(line) {
+            Ok(event) if event.agent_id != stem && is_log_file_stem(&event.agent_id) => {
+                moved.entry(event.agent_id).or_default().push(line);
+            }
+            _ => kept.push(line),
+        }
+    }
+    (kept, moved)
+}
+
+/// An `agent_id` that can name a file next to the others; anything else stays where it is
+fn is_log_file_stem(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\'])
+}
+
+/// Split every NDJSON file of `project` under `base` into per-agent files. Lines already in an
+/// agent's file are merged with the moved ones by `ts`. Agent files are written before their source
+/// is rewritten, so an interrupted run duplicates lines rather than losing them; rerunning is a no-op.
+/// Open log handles on the rewritten files are closed first, so later writes land in the new files.
+pub fn migrate_project_logs(base: &str, project: &str) -> std::io::Result<LogMigration> {
+    let mut report = LogMigration::default();
+    for source in project_log_files(base, project) {
+        close_path(&source)?;
+        let stem = Path::new(&source).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let content = std::fs::read_to_string(&source)?;
+        let (kept, moved) = split_log_lines(&stem, &content);
+        if moved.is_empty() {
+            continue;
+        }
+        for (agent, lines) in &moved {
+            let target = log_path(base, project, agent);
+            close_path(&target)?;
+            let existing = std::fs::read_to_string(&target).unwrap_or_default();
+            let mut merged: Vec<&str> = existing.lines().filter(|l| !l.trim().is_empty()).collect();
+            merged.extend(lines);
+            merged.sort_by_cached_key(|line| Event::from_line(line).map(|e| e.ts).unwrap_or_default());
+            replace_file(&target, &merged)?;
+            report.lines_moved += lines.len();
+            if !report.agents.contains(agent) {
+                report.agents.push(agent.clone());
+            }
+        }
+        if kept.is_empty() {
+            std::fs::remove_file(&source)?;
+        } else {
+            replace_file(&source, &kept)?;
+        }
+        report.files_split += 1;
+    }
+    report.agents.sort();
+    Ok(report)
+}
+
+/// Write `lines` to a temporary file next to `path`, then rename it over `path`
+fn replace_file(path: &str, lines: &[&str]) -> std::io::Result<()> {
+    let tmp = format!("{}.migrate.tmp", path);
+    let mut out = std::io::BufWriter::new(File::create(&tmp)?);
+    for line in lines {
+        writeln!(out, "{}", line)?;
+    }
+    out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    std::fs::rename(&tmp, path)
+}
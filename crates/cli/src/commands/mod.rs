@@ -6,11 +6,19 @@ pub mod db;
 pub mod send;
 pub mod session;
 pub mod agent;
+pub mod project;
 pub mod init;
 pub mod broadcast;
 pub mod monitor;
 pub mod tui;
 pub mod context;
+pub mod stats;
+pub mod completions;
+pub mod logs;
+pub mod upgrade;
+pub mod cache;
+pub mod supervisor;
+pub mod task;
 
 // Re-export all command functions
 pub use config::*;
@@ -19,8 +27,16 @@ pub use db::*;
 pub use send::*;
 pub use session::*;
 pub use agent::*;
+pub use project::*;
 pub use init::*;
 pub use broadcast::*;
 pub use monitor::*;
 pub use tui::*;
 pub use context::*;
+pub use stats::*;
+pub use completions::*;
+pub use logs::*;
+pub use upgrade::*;
+pub use cache::*;
+pub use supervisor::*;
+pub use task::*;
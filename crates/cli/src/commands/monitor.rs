@@ -2,6 +2,7 @@
 
 use crate::monitoring::*;
 use crate::logging::ndjson::emit_metrics_event;
+use crate::utils::CliError;
 
 /// Monitor broadcast operations and display metrics
 pub fn run_monitor(
@@ -9,7 +10,7 @@ pub fn run_monitor(
     duration_seconds: Option<u64>,
     format: &str,
     output_file: Option<&str>,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), CliError> {
     let duration = duration_seconds.unwrap_or(60); // Default 60 seconds
     
     // Initialize monitoring components
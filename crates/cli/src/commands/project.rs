@@ -0,0 +1,118 @@
+//! Project management commands
+
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use db::{open_or_create_db, rename_project};
+use crate::tmux::manager::TmuxManager;
+use crate::logging::log_base;
+use crate::utils::{resolve_db_path, write_atomic, CliError, DEFAULT_AGENT_TIMEOUT_MS};
+
+/// Outcome of one rename step, printed in the final summary
+enum StepOutcome {
+    Done(String),
+    Skipped(String),
+    Failed(String),
+}
+
+/// Run project rename command
+///
+/// The database row is renamed first; if that fails nothing else is touched. The logs
+/// directory, project.yaml (only with `update_config`) and tmux session are then updated
+/// independently: a failing step is reported and the others still run. Any failed step
+/// makes the command exit non-zero after the summary is printed.
+pub fn run_project_rename(from: &str, to: &str, db_path: Option<&str>, config_dir: Option<&str>, logs_dir: Option<&str>, update_config: bool) -> Result<(), CliError> {
+    let binding;
+    let path = match db_path { Some(p) => p, None => { binding = resolve_db_path(); &binding } };
+    let conn = open_or_create_db(path)?;
+
+    let logs_base = logs_dir.map(|d| d.trim_end_matches('/').to_string()).unwrap_or_else(log_base);
+    let from_logs = format!("{}/{}", logs_base, from);
+    let to_logs = format!("{}/{}", logs_base, to);
+    if Path::new(&to_logs).exists() {
+        return Err(CliError::validation("project rename", format!("logs directory already exists: {}", to_logs)));
+    }
+    let config_base = config_dir.map(|d| d.to_string())
+        .unwrap_or_else(|| std::env::var("MULTI_AGENTS_CONFIG_DIR").unwrap_or_else(|_| "./config".into()));
+    let project_file = [format!("{}/project.yaml", config_base), format!("{}/project.yml", config_base)]
+        .into_iter()
+        .find(|p| Path::new(p).exists());
+
+    // 1) Database: name is UNIQUE, collisions and unknown projects are invalid input
+    match rename_project(&conn, from, to) {
+        Ok(()) => {}
+        Err(db::DbError::InvalidInput(e)) => return Err(CliError::validation("project rename", e)),
+        Err(e) => return Err(e.into()),
+    }
+    let mut steps = vec![("db", StepOutcome::Done(format!("project '{}' renamed to '{}'", from, to)))];
+
+    // 2) Logs directory
+    let logs = if !Path::new(&from_logs).is_dir() {
+        StepOutcome::Skipped(format!("no {} directory", from_logs))
+    } else {
+        match fs::rename(&from_logs, &to_logs) {
+            Ok(()) => StepOutcome::Done(format!("{} -> {}", from_logs, to_logs)),
+            Err(e) => StepOutcome::Failed(format!("moving {} to {}: {}", from_logs, to_logs, e)),
+        }
+    };
+    steps.push(("logs", logs));
+
+    // 3) project.yaml
+    let config = match (&project_file, update_config) {
+        (None, _) => StepOutcome::Skipped(format!("no project.yaml found in {}", config_base)),
+        (Some(file), false) => StepOutcome::Skipped(format!(
+            "set `project: {}` in {} (or rerun with --update-config)", to, file
+        )),
+        (Some(file), true) => match rewrite_project_name(file, from, to) {
+            Ok(true) => StepOutcome::Done(format!("updated {}", file)),
+            Ok(false) => StepOutcome::Skipped(format!("{} does not describe project '{}' - left unchanged", file, from)),
+            Err(e) => StepOutcome::Failed(format!("updating {}: {}", file, e)),
+        },
+    };
+    steps.push(("config", config));
+
+    // 4) tmux session
+    let tmux_manager = TmuxManager::new(Duration::from_millis(DEFAULT_AGENT_TIMEOUT_MS));
+    let from_session = format!("proj:{}", from);
+    let to_session = format!("proj:{}", to);
+    let tmux = if !tmux_manager.has_session(&from_session).unwrap_or(false) {
+        StepOutcome::Skipped(format!("no {} session", from_session))
+    } else {
+        match tmux_manager.rename_session(&from_session, &to_session) {
+            Ok(()) => StepOutcome::Done(format!("{} -> {}", from_session, to_session)),
+            Err(e) => StepOutcome::Failed(e.to_string()),
+        }
+    };
+    steps.push(("tmux", tmux));
+
+    let mut failed = Vec::new();
+    for (step, outcome) in &steps {
+        match outcome {
+            StepOutcome::Done(detail) => println!("{}: {}", step, detail),
+            StepOutcome::Skipped(detail) => println!("{}: skipped - {}", step, detail),
+            StepOutcome::Failed(detail) => {
+                eprintln!("{}: FAILED - {}", step, detail);
+                failed.push(*step);
+            }
+        }
+    }
+    if !failed.is_empty() {
+        return Err(CliError::Other(format!("project rename: {} step(s) failed: {}", failed.len(), failed.join(", "))));
+    }
+    Ok(())
+}
+
+/// Set `project: to` in a project YAML whose current name is `from`; returns false if it names another project
+fn rewrite_project_name(file: &str, from: &str, to: &str) -> Result<bool, CliError> {
+    let content = fs::read_to_string(file)?;
+    let mut doc: serde_yaml::Value = serde_yaml::from_str(&content).map_err(|e| CliError::validation("project config", e))?;
+    let map = doc.as_mapping_mut().ok_or_else(|| CliError::validation("project config", "not a mapping"))?;
+    let key = serde_yaml::Value::from("project");
+    if map.get(&key).and_then(|v| v.as_str()) != Some(from) {
+        return Ok(false);
+    }
+    map.insert(key, serde_yaml::Value::from(to));
+    let yaml = serde_yaml::to_string(&doc).map_err(std::io::Error::other)?;
+    write_atomic(file, &yaml)?;
+    Ok(true)
+}
@@ -1,50 +1,152 @@
 //! Send command implementation
 
 use std::fs;
-use std::thread;
-use std::time::Duration;
-use config_model::{parse_project_yaml, parse_providers_yaml};
-use db::{
-    open_or_create_db, find_project_id, IdOrName, ClaudeSessionManager, CursorSessionManager, 
-    GeminiSessionManager, SessionManager, find_session, now_iso8601_utc
-};
+use std::io::{BufRead, IsTerminal, Write};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use config_model::{alias_root, parse_project_yaml, parse_providers_yaml};
+use db::{open_or_create_db, find_project_id, IdOrName, find_session, now_iso8601_utc};
 use rusqlite::params;
 use indicatif::{ProgressBar, ProgressStyle};
 use crate::cli::commands::Format;
 use crate::utils::{
     resolve_config_paths, handle_missing_config, resolve_db_path, DEFAULT_SEND_TIMEOUT_MS, 
-    MAX_CONCURRENCY, short_id, uuid_v4_like, exit_with
+    MAX_CONCURRENCY, provider_session_id, IdGenerator, RandomIds, PromptFile, CliError, ProviderErrorKind, find_project_or_fuzzy, StreamOutcome,
+    write_atomic, expand_cmd_path, say,
 };
-use crate::utils::timeouts::run_with_timeout_streaming;
-use crate::logging::log_ndjson;
+use crate::commands::config::sync_project_with_progress;
+use crate::commands::session::{effective_session_ttl, ensure_session_for_agent, session_ttl};
+use crate::commands::cache::{cache_key, cache_ttl, CacheKeyInput};
+use crate::broadcast::targets::merge_agent_sources;
+use crate::utils::timeouts::{run_with_timeout_streaming, timeout_kind, StreamContext};
+use crate::providers::{apply_agent_identity, AgentIdentity, OutputFormat};
+use crate::logging::{agent_log_path, log_base, log_event_in, log_ndjson, redact, set_redaction, Event};
+use crate::supervisor::notify::{record_notifications, Notification, NotifyEvent, NotifyEventKind};
 
 /// Run send command
-pub fn run_send(
-    project_path_opt: Option<&str>, 
-    providers_path_opt: Option<&str>, 
-    to: &str, 
-    message: &str, 
-    session_id_opt: Option<&str>, 
-    chat_id_opt: Option<&str>, 
-    timeout_ms_flag: Option<u64>, 
-    format: Format, 
-    progress: bool
-) -> Result<(), Box<dyn std::error::Error>> {
+pub fn run_send(request: &SendRequest) -> Result<(), CliError> {
+    execute_send(request, std::io::stdin().is_terminal())
+}
+
+/// Parsed `send` flags; start from `SendRequest::new` and set the flags that were given
+#[derive(Clone, Copy)]
+pub struct SendRequest<'a> {
+    pub project_path_opt: Option<&'a str>,
+    pub providers_path_opt: Option<&'a str>,
+    pub to: &'a str,
+    pub message: &'a str,
+    pub session_id_opt: Option<&'a str>,
+    pub chat_id_opt: Option<&'a str>,
+    pub timeout_ms_flag: Option<u64>,
+    pub format: Format,
+    pub progress: bool,
+    pub quiet: bool,
+    pub fail_fast: bool,
+    pub yes: bool,
+    /// DB project (id or name) to send in; defaults to the YAML `project:` name
+    pub project_opt: Option<&'a str>,
+    /// Reject unknown `{placeholders}` in system prompts and provider args instead of leaving them as is
+    pub strict_placeholders: bool,
+    /// Kill a target that prints nothing for this long; overrides the provider's `timeouts.inactivity_ms`
+    pub inactivity_timeout_ms_flag: Option<u64>,
+    /// Write a JSON run manifest here once every target has finished, failed or been cancelled
+    pub manifest_path: Option<&'a str>,
+    /// `--cache` / `--no-cache`; `None` follows the project's `defaults.cache`
+    pub cache: Option<bool>,
+    /// TTL of responses stored by this send; overrides the project's `defaults.cache_ttl`
+    pub cache_ttl: Option<&'a str>,
+    /// Expiry of the sessions this send creates; overrides the project's `defaults.session_ttl`
+    pub session_ttl: Option<&'a str>,
+    /// What happens to the supervisor notifications raised by this send's targets
+    pub notify: SupervisorNotify,
+    /// Mints the provider session id of each target sent to without `--session-id`; random ids when `None`
+    pub ids: Option<&'a Arc<dyn IdGenerator>>,
+    /// Print the prompt before the replies (`--echo-prompt`); the `prompt` log event is written regardless
+    pub echo_prompt: bool,
+}
+
+impl<'a> SendRequest<'a> {
+    /// A send of `message` to `to` with every other flag at its command-line default
+    pub fn new(to: &'a str, message: &'a str) -> Self {
+        SendRequest {
+            project_path_opt: None,
+            providers_path_opt: None,
+            to,
+            message,
+            session_id_opt: None,
+            chat_id_opt: None,
+            timeout_ms_flag: None,
+            format: Format::Text,
+            progress: true,
+            quiet: false,
+            fail_fast: false,
+            yes: false,
+            project_opt: None,
+            strict_placeholders: false,
+            inactivity_timeout_ms_flag: None,
+            manifest_path: None,
+            cache: None,
+            cache_ttl: None,
+            session_ttl: None,
+            notify: SupervisorNotify::Record,
+            ids: None,
+            echo_prompt: false,
+        }
+    }
+}
+
+/// What a send does with the `send_completed`/`send_failed` events of its targets
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SupervisorNotify {
+    /// Record a message for each subscribed supervisor
+    Record,
+    /// Record them and also send each one to its supervisor (`--notify-supervisor`)
+    Dispatch,
+    /// Raise nothing; used by the sends that deliver notifications
+    Off,
+}
+
+/// Send executor behind `run_send`; `is_tty` decides whether a large fan-out may be confirmed interactively
+pub(crate) fn execute_send(request: &SendRequest, is_tty: bool) -> Result<(), CliError> {
+    let SendRequest {
+        project_path_opt, providers_path_opt, to, message, session_id_opt, chat_id_opt,
+        timeout_ms_flag, format, progress, quiet, fail_fast, yes, project_opt, strict_placeholders,
+        inactivity_timeout_ms_flag, manifest_path, cache, cache_ttl: cache_ttl_flag, session_ttl: session_ttl_flag, notify, ids, echo_prompt,
+    } = *request;
+    let ids: Arc<dyn IdGenerator> = ids.cloned().unwrap_or_else(|| Arc::new(RandomIds));
     let (project_path, providers_path) = match resolve_config_paths(project_path_opt, providers_path_opt) {
         Ok(p) => p,
         Err(msg) => return handle_missing_config(msg),
     };
     let proj_s = fs::read_to_string(&project_path)?;
     let prov_s = fs::read_to_string(&providers_path)?;
-    let project = match parse_project_yaml(&proj_s) { Ok(p) => p, Err(e) => return exit_with(2, format!("project: {}", e)) };
-    let providers = match parse_providers_yaml(&prov_s) { Ok(p) => p, Err(e) => return exit_with(2, format!("providers: {}", e)) };
+    let mut project = parse_project_yaml(&proj_s).map_err(|e| CliError::validation("project", e))?;
+    set_redaction(&project.defaults.redaction).map_err(|e| CliError::validation("project", e))?;
+    let providers = parse_providers_yaml(&prov_s).map_err(|e| CliError::validation("providers", e))?;
+    // A prompt sent into a given conversation depends on its history, so those sends are never cached
+    let use_cache = cache.unwrap_or(project.defaults.cache) && session_id_opt.is_none() && chat_id_opt.is_none();
+    let ttl = cache_ttl(cache_ttl_flag, project.defaults.cache_ttl.as_deref())?;
+    let new_session_ttl = session_ttl(session_ttl_flag, project.defaults.session_ttl.as_deref())?;
 
     // Session management - sync project and agents to database
     let db_path = resolve_db_path();
     let conn = open_or_create_db(&db_path)?;
-    match db::sync_project_from_config(&conn, &project) {
-        Ok(_) => {}, // Project synchronized successfully
-        Err(e) => return exit_with(7, format!("Failed to sync project: {}", e)),
+    if let Some(sel) = project_opt {
+        // The DB project may have been renamed since project.yaml was written: sync into it under its DB name
+        project.project = resolve_project_name(&conn, sel)?;
+    }
+    sync_project_with_progress(&conn, &project)?;
+    let project_id = match find_project_id(&conn, IdOrName::Name(&project.project))? {
+        Some(pid) => pid,
+        None => return Err(CliError::validation("project", format!("not found: {}", project.project))),
+    };
+
+    // Agents added with `db agent-add` are targetable too; YAML wins on conflicts
+    let (agents, warnings) = merge_agent_sources(&project.agents, &db::list_agents(&conn, &project_id)?);
+    for warning in &warnings {
+        eprintln!("Warning: {}", warning);
     }
 
     // Resolve targets with session support and broadcast-like parsing
@@ -64,18 +166,18 @@ pub fn run_send(
             ).ok();
 
             if let Some(name) = agent_name {
-                if let Some(agent_cfg) = project.agents.iter().find(|a| a.name == name) {
+                if let Some(agent_cfg) = agents.iter().find(|a| a.name == name) {
                     targets.push(agent_cfg);
                     session_contexts.push(Some(to.to_string()));
                 } else {
-                    return exit_with(2, format!("send: session '{}' has no matching agent in config", to));
+                    return Err(CliError::validation("send", format!("session '{}' has no matching agent in config", to)));
                 }
             } else {
-                return exit_with(2, format!("send: session '{}' has no matching agent in database", to));
+                return Err(CliError::validation("send", format!("session '{}' has no matching agent in database", to)));
             }
         } else {
             // Fall back to direct agent name match
-            if let Some(agent) = project.agents.iter().find(|a| a.name == to) {
+            if let Some(agent) = agents.iter().find(|a| a.name == to) {
                 targets.push(agent);
                 session_contexts.push(None);
             } else {
@@ -88,45 +190,33 @@ pub fn run_send(
         // Use broadcast target parsing for @all, @role and comma-separated agent lists
         use crate::broadcast::targets::BroadcastTarget;
 
-        // Build DB-backed agent inventory to leverage existing resolver logic
-        // Determine project id
-        let project_name = &project.project;
-        let project_id = match find_project_id(&conn, IdOrName::Name(project_name))? {
-            Some(pid) => pid,
-            None => return exit_with(2, format!("Project not found: {}", project_name)),
-        };
-
-        // Load agents from DB
-        let mut stmt = conn.prepare("SELECT id, name, role, provider, model FROM agents WHERE project_id = ?1")?;
-        let db_agents: Vec<db::Agent> = stmt.query_map([&project_id], |row| {
-            Ok(db::Agent {
-                id: row.get(0)?,
-                project_id: project_id.clone(),
-                name: row.get(1)?,
-                role: row.get(2)?,
-                provider: row.get(3)?,
-                model: row.get(4)?,
-                system_prompt: String::new(),
-                allowed_tools: vec![],
-            })
-        })?.collect::<Result<Vec<_>, _>>()?;
+        // Build the merged inventory in the shape the resolver expects
+        let inventory: Vec<db::Agent> = agents.iter().map(|a| db::Agent {
+            id: String::new(),
+            project_id: project_id.clone(),
+            name: a.name.clone(),
+            role: a.role.clone(),
+            provider: a.provider.clone(),
+            model: a.model.clone(),
+            system_prompt: String::new(),
+            allowed_tools: vec![],
+        }).collect();
 
         // Parse and resolve target
         let parsed = BroadcastTarget::from_str(to)
-            .map_err(|e| format!("Invalid target '{}': {}", to, e))
-            .map_err(|msg| std::io::Error::new(std::io::ErrorKind::Other, msg))?;
+            .map_err(|e| CliError::validation("send", format!("invalid target '{}': {}", to, e)))?;
 
-        let agent_names = match parsed.resolve_agents(&db_agents) {
+        let agent_names = match parsed.resolve_agents(&inventory) {
             Ok(names) => names,
-            Err(e) => return exit_with(2, format!("send: {}", e)),
+            Err(e) => return Err(CliError::validation("send", e)),
         };
 
         if agent_names.is_empty() {
-            return exit_with(2, format!("send: no targets matched '{}'", to));
+            return Err(CliError::validation("send", format!("no targets matched '{}'", to)));
         }
 
         for name in agent_names {
-            if let Some(agent_cfg) = project.agents.iter().find(|a| a.name == name) {
+            if let Some(agent_cfg) = agents.iter().find(|a| a.name == name) {
                 targets.push(agent_cfg);
                 session_contexts.push(None);
             }
@@ -134,169 +224,572 @@ pub fn run_send(
     }
 
     if targets.is_empty() {
-        return exit_with(2, format!("send: no targets matched '{}'", to));
+        return Err(CliError::validation("send", format!("no targets matched '{}'", to)));
+    }
+
+    if !yes && targets.len() > project.defaults.confirm_send_above {
+        let stdin = std::io::stdin();
+        confirm_fan_out(&targets, project.defaults.confirm_send_above, is_tty, &mut stdin.lock(), &mut std::io::stderr())?;
+    }
+
+    // Identity placeholders are resolved per agent up front, so a --strict-placeholders error sends nothing
+    let mut resolved = Vec::with_capacity(targets.len());
+    for agent in &targets {
+        let identity = AgentIdentity { project: &project.project, agent_name: &agent.name, agent_role: &agent.role, model: &agent.model };
+        let resolved_agent = match providers.providers.get(&agent.provider) {
+            Some(tpl) => {
+                let (tpl, system_prompt) = apply_agent_identity(tpl, &agent.system_prompt, &identity, strict_placeholders)
+                    .map_err(|e| CliError::validation("placeholders", format!("agent {}: {}", agent.name, e)))?;
+                (Some(tpl), system_prompt)
+            }
+            None => (None, agent.system_prompt.clone()),
+        };
+        resolved.push(resolved_agent);
+    }
+
+    if echo_prompt {
+        match format {
+            Format::Text => println!("> {}", message),
+            Format::Json => eprintln!("> {}", message),
+        }
+    }
+
+    // Cache hits are answered from the DB: no session is created or touched for them
+    let mut cache_keys = Vec::with_capacity(targets.len());
+    let mut cache_hits = Vec::with_capacity(targets.len());
+    for (i, (agent, (tpl, system_prompt))) in targets.iter().zip(&resolved).enumerate() {
+        let key = (use_cache && tpl.is_some() && session_contexts[i].is_none()).then(|| cache_key(&CacheKeyInput {
+            provider: &agent.provider,
+            model: &agent.model,
+            system_prompt,
+            message,
+            allowed_tools: &agent.allowed_tools,
+        }));
+        cache_hits.push(match &key {
+            Some(key) => db::get_cached_response(&conn, key)?,
+            None => None,
+        });
+        cache_keys.push(key);
     }
 
     // Auto-create session if conversation_id is absent, and fallback if status expired/invalid
-    // Determine project_id once
-    let project_id = match find_project_id(&conn, IdOrName::Name(&project.project))? {
-        Some(pid) => pid,
-        None => return exit_with(2, format!("Project not found: {}", project.project)),
-    };
     for (i, agent) in targets.iter().enumerate() {
-        // If a session was provided, ensure it's active; else create one
-        if let Some(conv_id) = &session_contexts[i] {
-            if let Some(existing) = find_session(&conn, conv_id)? {
-                // If not active, create a fresh session
-                if existing.status.to_string() != "active" {
-                    // Lookup agent_id
-                    let agent_id: String = conn.query_row(
-                        "SELECT id FROM agents WHERE project_id = ?1 AND name = ?2",
-                        params![&project_id, &agent.name],
-                        |row| Ok(row.get::<_, String>(0)?)
-                    )?;
-                    // Create manager per provider
-                    let conn_for_mgr = open_or_create_db(&db_path)?;
-                    let manager: Box<dyn SessionManager> = match agent.provider.as_str() {
-                        "claude" => Box::new(ClaudeSessionManager::new(conn_for_mgr)),
-                        "cursor-agent" => Box::new(CursorSessionManager::new(open_or_create_db(&db_path)?)),
-                        "gemini" => Box::new(GeminiSessionManager::new(open_or_create_db(&db_path)?)),
-                        _ => return exit_with(2, format!("Unsupported provider: {}", agent.provider)),
-                    };
-                    let new_session = manager.create_session(&project_id, &agent_id, &agent.provider, None)
-                        .map_err(|e| format!("Failed to create session: {}", e))?;
-                    session_contexts[i] = Some(new_session.id);
-                }
-            } else {
-                // Provided id not found -> create new
-                let agent_id: String = conn.query_row(
-                    "SELECT id FROM agents WHERE project_id = ?1 AND name = ?2",
-                    params![&project_id, &agent.name],
-                    |row| Ok(row.get::<_, String>(0)?)
-                )?;
-                let manager: Box<dyn SessionManager> = match agent.provider.as_str() {
-                    "claude" => Box::new(ClaudeSessionManager::new(open_or_create_db(&db_path)?)),
-                    "cursor-agent" => Box::new(CursorSessionManager::new(open_or_create_db(&db_path)?)),
-                    "gemini" => Box::new(GeminiSessionManager::new(open_or_create_db(&db_path)?)),
-                    _ => return exit_with(2, format!("Unsupported provider: {}", agent.provider)),
-                };
-                let new_session = manager.create_session(&project_id, &agent_id, &agent.provider, None)
-                    .map_err(|e| format!("Failed to create session: {}", e))?;
-                session_contexts[i] = Some(new_session.id);
+        if cache_hits[i].is_none() {
+            let ttl = effective_session_ttl(new_session_ttl, providers.providers.get(&agent.provider));
+            let conv_id = ensure_session_for_agent(&conn, &project_id, agent, session_contexts[i].as_deref(), &db_path, ttl, &providers)?;
+            // `--to <conversation_id>` continues that session unless it had to be replaced
+            if session_contexts[i].as_deref() == Some(conv_id.as_str()) {
+                db::record_session_resume(&conn, &conv_id)?;
             }
-        } else {
-            // No session provided -> create one now
-            let agent_id: String = conn.query_row(
-                "SELECT id FROM agents WHERE project_id = ?1 AND name = ?2",
-                params![&project_id, &agent.name],
-                |row| Ok(row.get::<_, String>(0)?)
-            )?;
-            let manager: Box<dyn SessionManager> = match agent.provider.as_str() {
-                "claude" => Box::new(ClaudeSessionManager::new(open_or_create_db(&db_path)?)),
-                "cursor-agent" => Box::new(CursorSessionManager::new(open_or_create_db(&db_path)?)),
-                "gemini" => Box::new(GeminiSessionManager::new(open_or_create_db(&db_path)?)),
-                _ => return exit_with(2, format!("Unsupported provider: {}", agent.provider)),
-            };
-            let new_session = manager.create_session(&project_id, &agent_id, &agent.provider, None)
-                .map_err(|e| format!("Failed to create session: {}", e))?;
-            session_contexts[i] = Some(new_session.id);
+            session_contexts[i] = Some(conv_id);
         }
     }
+    // `--session-id` continues a provider conversation: count it on the sessions that hold it
+    if let Some(provider_session_id) = session_id_opt.filter(|s| !s.trim().is_empty()) {
+        db::record_provider_session_resume(&conn, &project_id, provider_session_id)?;
+    }
+
+    // Recorded before any provider runs, so an interrupted send still shows who was targeted
+    let target_names: Vec<String> = targets.iter().map(|a| a.name.clone()).collect();
+    let broadcast = db::insert_broadcast(&conn, &project_id, message, &target_names)?;
+    // The prompt goes into each target's session; replies are paired with it by `stats`
+    for conversation_id in session_contexts.iter().flatten() {
+        db::insert_message(&conn, conversation_id, db::USER_SENDER, &redact(message), Some(&broadcast.id))?;
+    }
 
     // Execute with bounded concurrency
-    let mut handles: Vec<std::thread::JoinHandle<i32>> = Vec::new();
-    let mut results: Vec<i32> = Vec::new();
+    let mut jobs: Vec<(String, SendJob)> = Vec::new();
     let multi = targets.len() > 1;
-    let per_timeout = timeout_ms_flag.unwrap_or(DEFAULT_SEND_TIMEOUT_MS);
-    let pb = if progress { Some(make_pb()) } else { None };
+    let timeouts_for = |provider_key: &str| send_timeouts(providers.providers.get(provider_key), timeout_ms_flag, inactivity_timeout_ms_flag);
+    let pb = if progress && !quiet { Some(make_pb()) } else { None };
+    let started_at = now_iso8601_utc();
+    let runs = Arc::new(Mutex::new(vec![None::<(i32, u64)>; targets.len()]));
+    // With --format json the replies go into the final document instead of streaming to stdout
+    let collect = matches!(format, Format::Json);
+    let replies = Arc::new(Mutex::new(vec![None::<String>; targets.len()]));
+    let mut manifest_targets = Vec::with_capacity(targets.len());
     
-    for (i, agent) in targets.iter().enumerate() {
-        // batch if needed
-        if handles.len() >= MAX_CONCURRENCY {
-            let code = handles.remove(0).join().unwrap_or(1);
-            results.push(code);
-        }
+    let cache_entries = cache_keys.into_iter().zip(cache_hits);
+    for (i, ((agent, (prov_cfg, agent_system)), (cache_key, cache_hit))) in targets.iter().zip(resolved).zip(cache_entries).enumerate() {
+        let (total_ms, inactivity_ms) = timeouts_for(&agent.provider);
         let provider_key = agent.provider.clone();
-        let prov_cfg = providers.providers.get(&provider_key).cloned();
+        let family = alias_root(&providers, &agent.provider).to_string();
         let project_name = project.project.clone();
         let agent_role = agent.role.clone();
+        let agent_name = agent.name.clone();
         let agent_allowed = agent.allowed_tools.clone();
-        let agent_system = agent.system_prompt.clone();
         let message_owned = message.to_string();
         let session_id_owned = session_id_opt.map(|s| s.to_string());
         let chat_id_owned = chat_id_opt.map(|s| s.to_string());
-        let print_header = multi;
+        let ids = Arc::clone(&ids);
+        let print_header = multi && !collect;
         let pb_clone = pb.as_ref().map(|p| p.clone());
         
         // Get session context for this agent
         let conversation_id = session_contexts[i].clone();
+        manifest_targets.push(ManifestTarget {
+            agent: agent.name.clone(),
+            role: agent.role.clone(),
+            provider: provider_key.clone(),
+            conversation_id: conversation_id.clone(),
+            args: prov_cfg.as_ref().map(|tpl| tpl.oneshot_args.clone()).unwrap_or_default(),
+            log_file: agent_log_path(&log_base(), &project_name, &agent.name, &agent_role),
+            run: None,
+            cached: cache_hit.is_some(),
+        });
+        let model = agent.model.clone();
+        let runs = runs.clone();
+        let replies = replies.clone();
+        let db_path = db_path.clone();
+        let broadcast_id = broadcast.id.clone();
         
-        handles.push(thread::spawn(move || {
-            match prov_cfg {
-                Some(tpl) => run_oneshot_provider(
-                    &project_name, &agent_role, &provider_key, &tpl,
-                    &message_owned, &agent_system, &agent_allowed,
-                    session_id_owned.as_deref(), chat_id_owned.as_deref(),
-                    per_timeout,
-                    print_header,
-                    pb_clone,
-                    conversation_id
-                ),
-                None => 3, // provider unavailable in config
+        jobs.push((provider_key.clone(), Box::new(move |cancel: &AtomicBool| {
+            let job_start = Instant::now();
+            let mut stdout = String::new();
+            let code = match (cache_hit, prov_cfg) {
+                (Some(hit), _) => {
+                    if print_header {
+                        say(&format!("=== role:{} provider:{} ===", agent_role, provider_key));
+                    }
+                    if collect { stdout.push_str(&hit.stdout); } else { print!("{}", hit.stdout); }
+                    replay_cached_response(&project_name, &agent_role, &provider_key, &hit, &agent_name, &message_owned)
+                }
+                (None, Some(tpl)) => {
+                    let target = OneshotTarget {
+                        project: &project_name,
+                        agent_role: &agent_role,
+                        agent_name: &agent_name,
+                        provider_key: &provider_key,
+                        family: &family,
+                        tpl: &tpl,
+                        system_prompt: &agent_system,
+                        allowed_tools: &agent_allowed,
+                        session_id_opt: session_id_owned.as_deref(),
+                        chat_id_opt: chat_id_owned.as_deref(),
+                        conversation_id: conversation_id.as_deref(),
+                        timeout_ms: total_ms,
+                        inactivity_ms,
+                        echo: !collect,
+                    };
+                    let code = run_oneshot_provider(
+                        &target,
+                        &message_owned,
+                        print_header,
+                        pb_clone,
+                        Some(cancel),
+                        Some(&mut stdout),
+                        &*ids,
+                    );
+                    if let (0, Some(conversation_id)) = (code, &conversation_id) {
+                        record_reply(&db_path, conversation_id, &stdout, &broadcast_id);
+                    }
+                    // Only successful answers are worth replaying
+                    if let (0, Some(key)) = (code, &cache_key) {
+                        store_cached_response(key, &provider_key, &model, &stdout, ttl);
+                    }
+                    code
+                }
+                (None, None) => 3, // provider unavailable in config
+            };
+            if let Ok(mut runs) = runs.lock() {
+                runs[i] = Some((code, job_start.elapsed().as_millis() as u64));
             }
-        }));
+            if collect {
+                if let Ok(mut replies) = replies.lock() {
+                    replies[i] = Some(stdout);
+                }
+            }
+            code
+        })));
+    }
+    let results = run_bounded(MAX_CONCURRENCY, jobs, fail_fast);
+    let status = results.as_ref().map(|r| broadcast_status(r)).unwrap_or(db::BroadcastStatus::Failed);
+    if let Err(e) = db::update_broadcast_status(&conn, &broadcast.id, status, Some(&now_iso8601_utc())) {
+        eprintln!("Warning: broadcast {}: {}", broadcast.id, e);
+    }
+    let results = results?;
+    let outcome = send_outcome(&results, timeouts_for);
+
+    // The manifest is best effort: a failed write never changes the send's own exit code
+    if let Some(path) = manifest_path {
+        if let Ok(runs) = runs.lock() {
+            for (target, run) in manifest_targets.iter_mut().zip(runs.iter()) {
+                target.run = *run;
+            }
+        }
+        let run = ManifestRun {
+            project: &project.project,
+            broadcast_id: &broadcast.id,
+            to,
+            message,
+            started_at: &started_at,
+            finished_at: &now_iso8601_utc(),
+            status: status.as_str(),
+            exit_code: outcome.as_ref().err().map_or(0, |e| e.exit_code()),
+        };
+        let manifest = serde_json::to_string_pretty(&send_manifest(&run, &manifest_targets)).unwrap_or_default();
+        if let Err(e) = write_atomic(path, &manifest) {
+            eprintln!("Warning: manifest {}: {}", path, e);
+        }
+    }
+
+    // Notifications are best effort too: neither recording nor delivery changes the exit code
+    let mut deliveries = Vec::new();
+    if notify != SupervisorNotify::Off {
+        let events = send_events(&targets, &runs.lock().map(|r| r.clone()).unwrap_or_default(), &broadcast.id);
+        match record_notifications(&conn, &project_id, &events) {
+            Ok(notifications) if notify == SupervisorNotify::Dispatch => deliveries = notifications,
+            Ok(_) => {}
+            Err(e) => eprintln!("Warning: supervisor notifications: {}", e),
+        }
+    }
+
+    if outcome.is_ok() {
+        if let Some(pb) = pb { pb.finish_and_clear(); }
+        if let Format::Json = format {
+            let replies = replies.lock().map(|r| r.clone()).unwrap_or_default();
+            println!("{}", send_json(&targets, &replies));
+        }
+    }
+    // Delivered in the background once this send's own result is out
+    for notification in &deliveries {
+        dispatch_notification(&project_path, &providers_path, project_opt, notification);
+    }
+    outcome
+}
+
+/// `--format json` document of a successful send: every target's reply, in resolution order
+pub(crate) fn send_json(targets: &[&config_model::AgentConfig], replies: &[Option<String>]) -> serde_json::Value {
+    let replies: Vec<serde_json::Value> = targets.iter().zip(replies).filter_map(|(agent, reply)| {
+        Some(serde_json::json!({
+            "agent": agent.name,
+            "role": agent.role,
+            "provider": agent.provider,
+            "output": reply.as_ref()?,
+        }))
+    }).collect();
+    serde_json::json!({"status": "ok", "replies": replies})
+}
+
+/// `send_completed`/`send_failed` for every target that ran; cancelled targets raise nothing
+pub(crate) fn send_events(targets: &[&config_model::AgentConfig], runs: &[Option<(i32, u64)>], broadcast_id: &str) -> Vec<NotifyEvent> {
+    targets.iter().zip(runs).filter_map(|(agent, run)| {
+        let (code, _) = (*run)?;
+        Some(NotifyEvent {
+            kind: if code == 0 { NotifyEventKind::SendCompleted } else { NotifyEventKind::SendFailed },
+            agent_name: agent.name.clone(),
+            agent_role: agent.role.clone(),
+            detail: format!("exit code {}, broadcast {}", code, broadcast_id),
+        })
+    }).collect()
+}
+
+/// Hidden command delivering one supervisor notification, spawned by `send --notify-supervisor`
+pub const DELIVER_COMMAND: &str = "__deliver-notification";
+
+/// Deliver a recorded notification as a send to its supervisor, in a detached `__deliver-notification`
+/// process: the caller neither waits for the supervisor's answer nor sees its output
+fn dispatch_notification(project_path: &str, providers_path: &str, project_opt: Option<&str>, notification: &Notification) {
+    let spawned = std::env::current_exe().and_then(|exe| {
+        let mut cmd = std::process::Command::new(exe);
+        cmd.args([DELIVER_COMMAND, "--project-file", project_path, "--providers-file", providers_path, "--to", &notification.supervisor, "--message", &notification.text]);
+        if let Some(project) = project_opt {
+            cmd.args(["--project", project]);
+        }
+        cmd.stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null());
+        // Own process group on unix, so a Ctrl-C in the caller's terminal does not reach it
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+        cmd.spawn()
+    });
+    match spawned {
+        // Reaped while this process lives, so it does not linger as a zombie
+        Ok(mut child) => { std::thread::spawn(move || child.wait()); }
+        Err(e) => eprintln!("Warning: notifying supervisor {}: {}", notification.supervisor, e),
     }
-    // join remaining
-    for h in handles { results.push(h.join().unwrap_or(1)); }
+}
 
-    // derive overall exit code priority: 5 > 4 > 3 > 2 > 0
-    let mut overall = 0;
-    if results.iter().any(|&c| c == 5) { overall = 5; }
-    else if results.iter().any(|&c| c == 4) { overall = 4; }
-    else if results.iter().any(|&c| c == 3) { overall = 3; }
-    else if results.iter().any(|&c| c == 2) { overall = 2; }
-    if overall != 0 { return exit_with(overall, format!("send: {} targets processed with non-zero codes", results.len())); }
+/// Run `__deliver-notification`: send `message` to the supervisor `to`, without raising notifications
+/// of its own; output and failures go nowhere, as the spawning send has already returned
+pub fn run_deliver_notification(project_path: &str, providers_path: &str, project_opt: Option<&str>, to: &str, message: &str) -> Result<(), CliError> {
+    let request = SendRequest {
+        project_path_opt: Some(project_path),
+        providers_path_opt: Some(providers_path),
+        progress: false,
+        quiet: true,
+        yes: true,
+        project_opt,
+        cache: Some(false),
+        notify: SupervisorNotify::Off,
+        ..SendRequest::new(to, message)
+    };
+    execute_send(&request, false)
+}
 
-    if let Some(pb) = pb { pb.finish_and_clear(); }
-    if let Format::Json = format {
-        println!("{}", serde_json::json!({"status":"ok"}));
+/// Overall send error from the per-target codes.
+/// Priority: timeout > provider error > provider unavailable > invalid input
+fn send_outcome(results: &[(i32, String)], timeouts_for: impl Fn(&str) -> (u64, u64)) -> Result<(), CliError> {
+    let first_with = |code: i32| results.iter().find(|(c, _)| *c == code).map(|(_, key)| key.clone());
+    let cancelled = results.iter().filter(|(c, _)| *c == SEND_CANCELLED).count();
+    let failed = results.iter().filter(|(c, _)| *c != 0 && *c != SEND_CANCELLED).count();
+    if let Some(key) = first_with(5) {
+        return Err(CliError::timeout("send (timeout_kind=total)", timeouts_for(&key).0));
+    }
+    if let Some(key) = first_with(SEND_INACTIVITY_TIMEOUT) {
+        return Err(CliError::timeout(format!("send to {} (timeout_kind=inactivity, no output)", key), timeouts_for(&key).1));
+    }
+    if let Some(key) = first_with(4) {
+        let mut detail = format!("{} of {} targets failed", failed, results.len());
+        if cancelled > 0 { detail.push_str(&format!(", {} cancelled", cancelled)); }
+        return Err(CliError::provider(key, ProviderErrorKind::Failed(detail)));
+    }
+    if let Some(key) = first_with(3) {
+        return Err(CliError::provider(key, ProviderErrorKind::Unavailable));
+    }
+    if first_with(2).is_some() {
+        return Err(CliError::validation("send", format!("{} targets processed with non-zero codes", results.len())));
     }
     Ok(())
 }
 
+/// Version of the `send --manifest` JSON layout
+pub(crate) const SEND_MANIFEST_SCHEMA_VERSION: u32 = 1;
+/// Characters of the prompt kept in the manifest
+const MANIFEST_PROMPT_PREVIEW_CHARS: usize = 200;
+
+/// Run-wide fields of a send manifest
+pub(crate) struct ManifestRun<'a> {
+    pub project: &'a str,
+    pub broadcast_id: &'a str,
+    pub to: &'a str,
+    pub message: &'a str,
+    pub started_at: &'a str,
+    pub finished_at: &'a str,
+    pub status: &'a str,
+    pub exit_code: i32,
+}
+
+/// One target of a send manifest; `run` is the (exit code, duration in ms) once the job ran
+pub(crate) struct ManifestTarget {
+    pub agent: String,
+    pub role: String,
+    pub provider: String,
+    pub conversation_id: Option<String>,
+    /// One-shot args with identity placeholders resolved; `{prompt}`, `{system_prompt}`, `{session_id}`
+    /// and `{chat_id}` are left as placeholders so the manifest holds no prompt text or ids
+    pub args: Vec<String>,
+    pub log_file: String,
+    pub run: Option<(i32, u64)>,
+    /// Answered from the response cache without running the provider
+    pub cached: bool,
+}
+
+/// JSON manifest of a send run. The prompt is redacted, then truncated; a target that never started
+/// (cancelled before its turn under `--fail-fast`) has a null exit code and duration.
+pub(crate) fn send_manifest(run: &ManifestRun, targets: &[ManifestTarget]) -> serde_json::Value {
+    let preview: String = redact(run.message).chars().take(MANIFEST_PROMPT_PREVIEW_CHARS).collect();
+    let targets: Vec<serde_json::Value> = targets.iter().map(|t| {
+        let status = match t.run {
+            Some((0, _)) => "ok",
+            Some((SEND_CANCELLED, _)) => "cancelled",
+            Some(_) => "failed",
+            None => "not_run",
+        };
+        serde_json::json!({
+            "agent": t.agent,
+            "role": t.role,
+            "provider": t.provider,
+            "conversation_id": t.conversation_id,
+            "args": t.args,
+            "exit_code": t.run.map(|(code, _)| code),
+            "status": status,
+            "duration_ms": t.run.map(|(_, ms)| ms),
+            "log_file": t.log_file,
+            "cached": t.cached,
+        })
+    }).collect();
+    serde_json::json!({
+        "schema_version": SEND_MANIFEST_SCHEMA_VERSION,
+        "project": run.project,
+        "broadcast_id": run.broadcast_id,
+        "to": run.to,
+        "prompt": {
+            "chars": run.message.chars().count(),
+            "preview": preview,
+            "truncated": run.message.chars().count() > MANIFEST_PROMPT_PREVIEW_CHARS,
+        },
+        "started_at": run.started_at,
+        "finished_at": run.finished_at,
+        "status": run.status,
+        "exit_code": run.exit_code,
+        "targets": targets,
+    })
+}
+
+/// Name of the DB project selected by `--project`, matched by id first, then by (fuzzy) name
+fn resolve_project_name(conn: &rusqlite::Connection, sel: &str) -> Result<String, CliError> {
+    match find_project_id(conn, IdOrName::Id(sel))? {
+        Some(id) => Ok(conn.query_row("SELECT name FROM projects WHERE id = ?1", params![id], |r| r.get(0))?),
+        None => find_project_or_fuzzy(conn, sel).map(|(_, name)| name),
+    }
+}
+
+/// Final broadcast status from the per-target codes: `completed`, `partial` or `failed`
+pub(crate) fn broadcast_status(results: &[(i32, String)]) -> db::BroadcastStatus {
+    let succeeded = results.iter().filter(|(c, _)| *c == 0).count();
+    if succeeded == results.len() {
+        db::BroadcastStatus::Completed
+    } else if succeeded == 0 {
+        db::BroadcastStatus::Failed
+    } else {
+        db::BroadcastStatus::PartialFailure
+    }
+}
+
+/// Ask before fanning out to more than `threshold` targets; without a TTY, `--yes` is required instead
+pub(crate) fn confirm_fan_out(
+    targets: &[&config_model::AgentConfig],
+    threshold: usize,
+    is_tty: bool,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> Result<(), CliError> {
+    if !is_tty {
+        return Err(CliError::validation("send", format!(
+            "{} targets exceed the confirmation threshold ({}); pass --yes to send non-interactively",
+            targets.len(), threshold
+        )));
+    }
+    for agent in targets {
+        writeln!(out, "  - {} (role: {}, provider: {})", agent.name, agent.role, agent.provider)?;
+    }
+    write!(out, "Send to {} agents? [y/N] ", targets.len())?;
+    out.flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    match answer.trim().to_ascii_lowercase().as_str() {
+        "y" | "yes" => Ok(()),
+        _ => Err(CliError::Other("send cancelled".into())),
+    }
+}
+
+/// One target's provider invocation, returning its exit code; it should stop early once the flag is set
+pub(crate) type SendJob = Box<dyn FnOnce(&AtomicBool) -> i32 + Send>;
+
+/// Code reported for a target that was skipped or killed after a fail-fast abort
+pub(crate) const SEND_CANCELLED: i32 = 130;
+
+/// Code reported for a target killed by the inactivity timeout; `send` exits 5 for it, like the total timeout
+pub(crate) const SEND_INACTIVITY_TIMEOUT: i32 = 124;
+
+/// Whether a target's code aborts the remaining targets under `--fail-fast` (provider error or timeout)
+fn is_fatal_send_code(code: i32) -> bool {
+    code == 4 || code == 5 || code == SEND_INACTIVITY_TIMEOUT
+}
+
+/// Total and inactivity timeouts (ms) of a send to `tpl`: flags first, then the provider's `timeouts` block;
+/// the total defaults to `DEFAULT_SEND_TIMEOUT_MS`, the inactivity timeout to `0` (off)
+pub(crate) fn send_timeouts(tpl: Option<&config_model::ProviderTemplate>, total_flag: Option<u64>, inactivity_flag: Option<u64>) -> (u64, u64) {
+    let defaults = tpl.and_then(|t| t.timeouts.clone()).unwrap_or_default();
+    (
+        total_flag.or(defaults.total_ms).unwrap_or(DEFAULT_SEND_TIMEOUT_MS),
+        inactivity_flag.or(defaults.inactivity_ms).unwrap_or(0),
+    )
+}
+
+/// Run keyed jobs on a pool of `concurrency` threads; a job starts as soon as any slot frees up.
+/// Results arrive in completion order; a panicking job reports exit code 1.
+/// With `fail_fast`, the first provider error or timeout sets the shared cancel flag: queued jobs
+/// are skipped and running ones are expected to return, both reporting `SEND_CANCELLED`.
+pub(crate) fn run_bounded(concurrency: usize, jobs: Vec<(String, SendJob)>, fail_fast: bool) -> Result<Vec<(i32, String)>, CliError> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(concurrency.max(1))
+        .thread_name(|i| format!("send-{}", i))
+        .build()
+        .map_err(|e| CliError::Other(format!("send: thread pool: {}", e)))?;
+    let (tx, rx) = mpsc::channel();
+    let total = jobs.len();
+    let cancel = Arc::new(AtomicBool::new(false));
+    for (key, job) in jobs {
+        let tx = tx.clone();
+        let cancel = Arc::clone(&cancel);
+        pool.spawn(move || {
+            let code = if cancel.load(Ordering::SeqCst) {
+                SEND_CANCELLED
+            } else {
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| job(&cancel))).unwrap_or(1)
+            };
+            if fail_fast && is_fatal_send_code(code) {
+                cancel.store(true, Ordering::SeqCst);
+            }
+            let _ = tx.send((code, key));
+        });
+    }
+    drop(tx);
+    Ok(rx.iter().take(total).collect())
+}
+
+
+/// One target of a one-shot send: the agent, its provider invocation and the session it runs in
+pub(crate) struct OneshotTarget<'a> {
+    pub project: &'a str,
+    pub agent_role: &'a str,
+    pub agent_name: &'a str,
+    pub provider_key: &'a str,
+    /// `alias_root` of `provider_key`: decides chat creation, session ids and output parsing
+    pub family: &'a str,
+    pub tpl: &'a config_model::ProviderTemplate,
+    pub system_prompt: &'a str,
+    pub allowed_tools: &'a [String],
+    /// `--session-id`; a new provider session id is minted without it
+    pub session_id_opt: Option<&'a str>,
+    /// `--chat-id`; cursor providers create a chat without it
+    pub chat_id_opt: Option<&'a str>,
+    /// DB session whose activity and provider session id the run updates
+    pub conversation_id: Option<&'a str>,
+    pub timeout_ms: u64,
+    pub inactivity_ms: u64,
+    /// Print the reply on stdout as it streams; `send --format json` only captures it
+    pub echo: bool,
+}
+
 /// Run one-shot provider command
-fn run_oneshot_provider(
-    project: &str,
-    agent_role: &str,
-    provider_key: &str,
-    tpl: &config_model::ProviderTemplate,
+pub(crate) fn run_oneshot_provider(
+    target: &OneshotTarget,
     prompt: &str,
-    system_prompt: &str,
-    allowed_tools: &[String],
-    session_id_opt: Option<&str>,
-    chat_id_opt: Option<&str>,
-    timeout_ms: u64,
     print_header: bool,
     pb_opt: Option<ProgressBar>,
-    conversation_id: Option<String>,
+    cancel: Option<&AtomicBool>,
+    capture: Option<&mut String>,
+    ids: &dyn IdGenerator,
 ) -> i32 {
-    let bin = tpl.cmd.clone();
+    let OneshotTarget {
+        project, agent_role, agent_name, provider_key, family, tpl, system_prompt, allowed_tools,
+        session_id_opt, chat_id_opt, conversation_id, timeout_ms, inactivity_ms, echo,
+    } = *target;
+    let bin = expand_cmd_path(&tpl.cmd);
     if bin.trim().is_empty() { return 3; }
     let allowed_join = allowed_tools.join(",");
+    // Large prompts can go through a temp file instead of argv; removed when the guard drops
+    let prompt_file = if tpl.oneshot_args.iter().any(|a| a.contains("{prompt_file}")) {
+        match PromptFile::create(prompt) {
+            Ok(f) => Some(f),
+            Err(e) => { eprintln!("prompt_file: {}", e); return 4; }
+        }
+    } else {
+        None
+    };
+    let prompt_file_path = prompt_file.as_ref().map(|f| f.path().to_string_lossy().to_string()).unwrap_or_default();
     // Build args with placeholder replacement and conditional removal of session_id flag pair
     let mut unresolved = false;
     let session_id_val_opt: Option<String> = match session_id_opt {
         Some(s) if !s.trim().is_empty() => Some(s.to_string()),
-        _ => {
-            // Generate valid session IDs based on provider
-            if provider_key == "claude" {
-                Some(format!("valid_session_{}", short_id()))
-            } else if provider_key == "gemini" {
-                Some(format!("valid_context_{}", short_id()))
-            } else {
-                Some(uuid_v4_like())
-            }
-        },
+        _ => Some(provider_session_id(family, ids)),
     };
     let mut args: Vec<String> = Vec::new();
     let mut i = 0;
@@ -318,6 +811,7 @@ fn run_oneshot_provider(
             if let Some(cid) = chat_id_opt { replaced = replaced.replace("{chat_id}", cid); } else { unresolved = true; }
         }
         replaced = replaced.replace("{prompt}", prompt)
+            .replace("{prompt_file}", &prompt_file_path)
             .replace("{system_prompt}", system_prompt)
             .replace("{allowed_tools}", &allowed_join);
         if replaced.contains("{session_id}") {
@@ -333,8 +827,9 @@ fn run_oneshot_provider(
         i += 1;
     }
     // If cursor requires chat_id and none provided, try auto-create chat
+    let mut created_chat_id = None;
     if unresolved {
-        if provider_key.starts_with("cursor") {
+        if family.starts_with("cursor") {
             match create_cursor_chat(tpl, system_prompt) {
                 Ok(chat_id) => {
                     // Rebuild args with chat_id now available
@@ -357,6 +852,7 @@ fn run_oneshot_provider(
                         if replaced.contains("{chat_id}") { replaced = replaced.replace("{chat_id}", &chat_id); }
                         replaced = replaced
                             .replace("{prompt}", prompt)
+                            .replace("{prompt_file}", &prompt_file_path)
                             .replace("{system_prompt}", system_prompt)
                             .replace("{allowed_tools}", &allowed_join);
                         if replaced.contains("{session_id}") {
@@ -367,6 +863,7 @@ fn run_oneshot_provider(
                         args.push(replaced);
                         i += 1;
                     }
+                    created_chat_id = Some(chat_id);
                 }
                 Err(e) => {
                     if e == "timeout" { return 5; }
@@ -379,14 +876,14 @@ fn run_oneshot_provider(
     }
 
     // Compose final session id for logging (best-effort)
-    let final_session_id = if provider_key.starts_with("cursor") {
-        chat_id_opt.unwrap_or("")
+    let final_session_id = if family.starts_with("cursor") {
+        chat_id_opt.or(created_chat_id.as_deref()).unwrap_or("")
     } else {
         session_id_val_opt.as_deref().unwrap_or("")
     };
 
     // Update session last_activity if conversation_id provided
-    if let Some(conv_id) = &conversation_id {
+    if let Some(conv_id) = conversation_id {
         let db_path = resolve_db_path();
         if let Ok(conn) = open_or_create_db(&db_path) {
             let now = now_iso8601_utc();
@@ -406,15 +903,14 @@ fn run_oneshot_provider(
 
     // Execute
     let start_ts = now_iso8601_utc();
-    log_ndjson(project, agent_role, provider_key, Some(final_session_id), "system", "start", None, None, Some(&start_ts));
+    log_ndjson(project, agent_role, provider_key, Some(final_session_id), "system", "start", None, None, Some(&start_ts), agent_name);
+    let _ = log_event_in(&log_base(), &prompt_event(project, agent_role, provider_key, Some(final_session_id), prompt, agent_name));
     if print_header {
-        println!("=== role:{} provider:{} ===", agent_role, provider_key);
+        say(&format!("=== role:{} provider:{} ===", agent_role, provider_key));
     }
     // For cursor-agent, enforce stream-json output to avoid blocking and parse JSON to text
     let mut args_final = args;
-    let mut parse_cursor_stream = false;
-    if provider_key.starts_with("cursor") {
-        parse_cursor_stream = true;
+    if family.starts_with("cursor") {
         let mut idx = None;
         for (i, t) in args_final.iter().enumerate() {
             if t == "--output-format" { idx = Some(i); break; }
@@ -427,26 +923,112 @@ fn run_oneshot_provider(
             args_final.push("stream-json".into());
         }
     }
+    let output_format = OutputFormat::for_invocation(family, &args_final);
     if let Some(pb) = &pb_opt { pb.set_message(format!("{}:{}", agent_role, provider_key)); }
-    match run_with_timeout_streaming(&bin, &args_final.iter().map(|s| s.as_str()).collect::<Vec<_>>(), Duration::from_millis(timeout_ms), project, agent_role, provider_key, final_session_id, pb_opt.as_ref(), parse_cursor_stream) {
-        Ok(code) => {
-            log_ndjson(project, agent_role, provider_key, Some(final_session_id), "system", "end", None, Some(code), None);
+    let ctx = StreamContext {
+        project,
+        agent_role,
+        agent_name,
+        provider_key,
+        family,
+        session_id: final_session_id,
+        format: output_format,
+        progress: pb_opt.as_ref(),
+        echo,
+    };
+    match run_with_timeout_streaming(&bin, &args_final.iter().map(|s| s.as_str()).collect::<Vec<_>>(), Duration::from_millis(timeout_ms), Duration::from_millis(inactivity_ms), &ctx, cancel, capture) {
+        Ok(outcome) => {
+            let code = outcome.exit_code;
+            if let (Some(conv_id), Some(provider_session_id)) = (conversation_id, &outcome.provider_session_id) {
+                if let Ok(conn) = open_or_create_db(&resolve_db_path()) {
+                    let _ = conn.execute(
+                        "UPDATE sessions SET provider_session_id = ?1 WHERE id = ?2",
+                        params![provider_session_id, conv_id]
+                    );
+                }
+            }
+            let _ = log_event_in(&log_base(), &send_end_event(project, agent_role, provider_key, final_session_id, &outcome, agent_name));
             if code == 0 { 0 } else { 4 }
         }
         Err(e) => {
-            if e == "timeout" { log_ndjson(project, agent_role, provider_key, Some(final_session_id), "system", "end", None, Some(5), None); 5 }
+            if let Some(kind) = timeout_kind(&e) {
+                let text = format!("timeout_kind={}", kind);
+                log_ndjson(project, agent_role, provider_key, Some(final_session_id), "system", "end", Some(&text), Some(5), None, agent_name);
+                if kind == "inactivity" { SEND_INACTIVITY_TIMEOUT } else { 5 }
+            }
+            else if e == "cancelled" { log_ndjson(project, agent_role, provider_key, Some(final_session_id), "system", "end", None, Some(SEND_CANCELLED), None, agent_name); SEND_CANCELLED }
             else if e.contains("No such file") || e.contains("not found") { 3 }
             else { 4 }
         }
     }
 }
 
+/// `user` event carrying the prompt sent to a target, logged right after its `start`
+pub(crate) fn prompt_event(project: &str, agent_role: &str, provider_key: &str, session_id: Option<&str>, prompt: &str, agent_name: &str) -> Event {
+    Event::builder(project, agent_role, provider_key, "prompt")
+        .agent_id(agent_name)
+        .session_id(session_id)
+        .direction("user")
+        .text(Some(prompt))
+        .build()
+}
+
+/// `end` event of a one-shot that ran to exit, carrying its exit code and stdout line/byte counts
+pub(crate) fn send_end_event(project: &str, agent_role: &str, provider_key: &str, session_id: &str, outcome: &StreamOutcome, agent_name: &str) -> Event {
+    Event::builder(project, agent_role, provider_key, "end")
+        .agent_id(agent_name)
+        .session_id(Some(session_id))
+        .exit_code(Some(outcome.exit_code))
+        .output(Some(outcome.stdout_lines), Some(outcome.stdout_bytes))
+        .build()
+}
+
+/// Log a target answered from the response cache: `start`/`end` flagged `cached`; the caller prints
+/// or collects the stored output
+fn replay_cached_response(project: &str, agent_role: &str, provider_key: &str, hit: &db::CachedResponse, agent_name: &str, prompt: &str) -> i32 {
+    let base = log_base();
+    let _ = log_event_in(&base, &Event::builder(project, agent_role, provider_key, "start").agent_id(agent_name).cached(Some(true)).build());
+    let _ = log_event_in(&base, &prompt_event(project, agent_role, provider_key, None, prompt, agent_name));
+    let _ = log_event_in(&base, &cached_end_event(project, agent_role, provider_key, hit, agent_name));
+    if hit.exit_code == 0 { 0 } else { 4 }
+}
+
+/// `end` event of a cache hit: the stored exit code, line/byte counts of the replayed output, `cached: true`
+pub(crate) fn cached_end_event(project: &str, agent_role: &str, provider_key: &str, hit: &db::CachedResponse, agent_name: &str) -> Event {
+    Event::builder(project, agent_role, provider_key, "end")
+        .agent_id(agent_name)
+        .exit_code(Some(hit.exit_code))
+        .output(Some(hit.stdout.lines().count() as u64), Some(hit.stdout.len() as u64))
+        .cached(Some(true))
+        .build()
+}
+
+/// Record a target's answer in its session, stored redacted like the logs. Best effort: a failed
+/// write only leaves the delivery counted as unanswered by `stats`.
+fn record_reply(db_path: &str, conversation_id: &str, reply: &str, broadcast_id: &str) {
+    let recorded = open_or_create_db(db_path)
+        .and_then(|conn| db::insert_message(&conn, conversation_id, db::AGENT_SENDER, &redact(reply), Some(broadcast_id)));
+    if let Err(e) = recorded {
+        eprintln!("Warning: recording reply in session {}: {}", conversation_id, e);
+    }
+}
+
+/// Best effort: a failed cache write only costs the next send a provider run. The answer is
+/// stored redacted, like the logs, so no secret is kept in the database.
+fn store_cached_response(key: &str, provider_key: &str, model: &str, stdout: &str, ttl: Duration) {
+    let stored = open_or_create_db(&resolve_db_path())
+        .and_then(|conn| db::put_cached_response(&conn, key, provider_key, model, &redact(stdout), 0, ttl));
+    if let Err(e) = stored {
+        eprintln!("Warning: response cache: {}", e);
+    }
+}
+
 /// Create cursor chat
 fn create_cursor_chat(tpl: &config_model::ProviderTemplate, system_prompt: &str) -> Result<String, String> {
     let create_args_opt = tpl.create_chat_args.as_ref();
     let create_args = match create_args_opt { Some(a) => a, None => return Err("missing_create_chat_args".into()) };
     let args: Vec<String> = create_args.iter().map(|a| a.replace("{system_prompt}", system_prompt)).collect();
-    match crate::utils::timeouts::run_with_timeout(&tpl.cmd, &args.iter().map(|s| s.as_str()).collect::<Vec<_>>(), Duration::from_millis(5000)) {
+    match crate::utils::timeouts::run_with_timeout(&expand_cmd_path(&tpl.cmd), &args.iter().map(|s| s.as_str()).collect::<Vec<_>>(), Duration::from_millis(5000)) {
         Ok((_code, out, err)) => {
             let text = if !out.trim().is_empty() { out } else { err };
             let id = text.lines().filter(|l| !l.trim().is_empty()).last().unwrap_or("").trim().to_string();
@@ -1,113 +1,246 @@
 //! Session management commands
 
 use std::fs;
-use config_model::{parse_project_yaml, parse_providers_yaml};
+use config_model::{alias_root, parse_project_yaml, parse_providers_yaml, AgentConfig, ProviderTemplate, ProvidersConfig};
 use db::{
-    open_or_create_db, find_project_id, IdOrName, ClaudeSessionManager, CursorSessionManager, 
+    open_or_create_db, open_db_without_migrating, open_readonly_db, open_readonly_db_allow_newer_schema, find_project_id, IdOrName, ClaudeSessionManager, CursorSessionManager, 
     GeminiSessionManager, SessionManager, list_sessions, SessionFilters, SessionStatus, 
-    cleanup_repl_sessions, find_session
+    cleanup_repl_sessions_before, list_stale_repl_sessions_before, list_expired_chat_sessions_before,
+    delete_expired_chat_sessions_before, column_exists, find_session, iso8601_utc_ago, insert_failed_session, update_session, Session, DEFAULT_EXPIRY_SLACK
 };
-use rusqlite::params;
+use rusqlite::{params, Connection, OptionalExtension};
 use std::time::{Duration, Instant};
-use crate::cli::commands::Format;
-use crate::utils::{resolve_config_paths, handle_missing_config, resolve_db_path, short_id, exit_with};
-use crate::utils::timeouts::run_with_timeout;
+use crate::cli::commands::{Format, SessionStatusArg};
+use crate::utils::{resolve_config_paths, handle_missing_config, resolve_db_path, select_agent, provider_session_id, IdGenerator, find_project_or_fuzzy, quiet, expand_cmd_path, CliError, ProviderErrorKind};
+use crate::utils::timeouts::{run_with_timeout, parse_duration_spec};
+use crate::logging::ndjson::log_ndjson;
 
 /// Run session start command
-pub fn run_session_start(project_path_opt: Option<&str>, providers_path_opt: Option<&str>, agent_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+pub fn run_session_start(project_path_opt: Option<&str>, providers_path_opt: Option<&str>, agent_name: Option<&str>, session_ttl_flag: Option<&str>, ids: &dyn IdGenerator) -> Result<(), CliError> {
     let (project_path, providers_path) = match resolve_config_paths(project_path_opt, providers_path_opt) {
         Ok(p) => p,
         Err(msg) => return handle_missing_config(msg),
     };
     let proj_s = fs::read_to_string(&project_path)?;
     let prov_s = fs::read_to_string(&providers_path)?;
-    let project = parse_project_yaml(&proj_s).map_err(|e| format!("project: {}", e))
-        .or_else(|e| exit_with(2, e))?;
-    let providers = parse_providers_yaml(&prov_s).map_err(|e| format!("providers: {}", e))
-        .or_else(|e| exit_with(2, e))?;
+    let project = parse_project_yaml(&proj_s).map_err(|e| CliError::validation("project", e))?;
+    let providers = parse_providers_yaml(&prov_s).map_err(|e| CliError::validation("providers", e))?;
+    let ttl = session_ttl(session_ttl_flag, project.defaults.session_ttl.as_deref())?;
+    let agent_name = select_agent(agent_name, &project.agents)?;
     let agent = match project.agents.iter().find(|a| a.name == agent_name) {
         Some(a) => a,
-        None => return exit_with(2, format!("unknown agent: {}", agent_name)),
+        None => return Err(CliError::validation("agent", format!("unknown agent: {}", agent_name))),
     };
     let provider_key = &agent.provider;
     let tpl = match providers.providers.get(provider_key) {
         Some(t) => t,
-        None => return exit_with(3, format!("provider not found: {}", provider_key)),
+        None => return Err(CliError::provider(provider_key.as_str(), ProviderErrorKind::NotConfigured)),
     };
-    let conv_id = if provider_key.starts_with("cursor") {
+    // Aliases share their base provider's session handling
+    let family = alias_root(&providers, provider_key);
+    let conv_id = if family.starts_with("cursor") {
         // create chat if args available
         if let Some(create_args) = &tpl.create_chat_args {
             let args: Vec<String> = create_args.iter()
                 .map(|a| a.replace("{system_prompt}", &agent.system_prompt))
                 .collect();
-            match run_with_timeout(&tpl.cmd, &args.iter().map(|s| s.as_str()).collect::<Vec<_>>(), Duration::from_millis(5000)) {
-                Ok((_code, out, err)) => {
-                    let text = if !out.trim().is_empty() { out } else { err };
-                    // naive: take last non-empty line as chat_id
-                    let id = text.lines().filter(|l| !l.trim().is_empty()).last().unwrap_or("").trim().to_string();
-                    if id.is_empty() { return exit_with(4, "cursor create-chat returned empty id".into()); }
-                    id
-                }
+            match create_chat(provider_key, &expand_cmd_path(&tpl.cmd), &args, CREATE_CHAT_TIMEOUT_MS) {
+                Ok(id) => id,
                 Err(e) => {
-                    if e == "timeout" { return exit_with(5, "cursor create-chat timeout".into()); }
-                    return exit_with(4, format!("cursor create-chat error: {}", e));
+                    record_create_chat_failure(&project.project, agent, &e);
+                    return Err(e);
                 }
             }
         } else {
-            return exit_with(2, "cursor provider missing create_chat_args".into());
+            return Err(CliError::validation("providers", format!("{} missing create_chat_args", provider_key)));
         }
-    } else if provider_key == "claude" {
-        format!("valid_session_{}", short_id())
-    } else if provider_key == "gemini" {
-        format!("valid_context_{}", short_id())
     } else {
-        short_id()
+        provider_session_id(family, ids)
     };
     // Save session to database
     let db_path = resolve_db_path();
     let conn = open_or_create_db(&db_path)?;
     
     // Find project and agent IDs
-    let project_id = find_project_id(&conn, IdOrName::Name(&project.project))?
-        .ok_or_else(|| format!("Project not found: {}", project.project))?;
-    
-    let agent_id = conn.query_row(
-        "SELECT id FROM agents WHERE project_id = ?1 AND name = ?2",
-        &[&project_id, &agent_name.to_string()],
-        |row| Ok(row.get::<_, String>(0)?)
-    )?;
-    
-    // Create appropriate SessionManager and session
-    let manager: Box<dyn SessionManager> = match provider_key.as_str() {
-        "claude" => Box::new(ClaudeSessionManager::new(conn)),
-        "cursor-agent" => Box::new(CursorSessionManager::new(conn)),
-        "gemini" => Box::new(GeminiSessionManager::new(conn)),
-        _ => return exit_with(2, format!("Unsupported provider: {}", provider_key)),
-    };
+    let (project_id, _) = find_project_or_fuzzy(&conn, &project.project)?;
     
     // Create session with provider_session_id if available
-    let provider_session_id = if provider_key.starts_with("cursor") {
-        Some(conv_id.as_str())
-    } else if provider_key == "claude" || provider_key == "gemini" {
+    let provider_session_id = if family.starts_with("cursor") || family == "claude" || family == "gemini" {
         Some(conv_id.as_str())
     } else {
         None
     };
     
-    match manager.create_session(&project_id, &agent_id, provider_key, provider_session_id) {
-        Ok(session) => {
-            println!("conversation_id={}", session.id);
-        }
-        Err(e) => {
-            return exit_with(7, format!("Failed to create session: {}", e));
-        }
+    let session_id = create_session_for_agent(&conn, &project_id, agent, provider_session_id, &db_path, effective_session_ttl(ttl, Some(tpl)), &providers)?;
+    if quiet() {
+        println!("{}", session_id);
+    } else {
+        println!("conversation_id={}", session_id);
     }
     
     Ok(())
 }
 
+/// Time allowed for the provider's create-chat command
+const CREATE_CHAT_TIMEOUT_MS: u64 = 5000;
+
+/// Longest stderr excerpt (in characters) kept in create-chat error messages
+pub(crate) const CREATE_CHAT_STDERR_LIMIT: usize = 2000;
+
+/// Run the provider's create-chat command and return the chat id it prints.
+/// A missing binary is `Unavailable`-class (exit 3), a nonzero exit `Failed` (exit 4), a timeout exit 5.
+pub(crate) fn create_chat(provider_key: &str, cmd: &str, args: &[String], timeout_ms: u64) -> Result<String, CliError> {
+    let argv: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    match run_with_timeout(cmd, &argv, Duration::from_millis(timeout_ms)) {
+        Ok((code, _out, err)) if code != 0 => {
+            let stderr = truncate_stderr(&err);
+            let detail = if stderr.is_empty() {
+                format!("create-chat exited with code {}", code)
+            } else {
+                format!("create-chat exited with code {}: {}", code, stderr)
+            };
+            Err(CliError::provider(provider_key, ProviderErrorKind::Failed(detail)))
+        }
+        Ok((_code, out, err)) => {
+            let text = if !out.trim().is_empty() { out } else { err };
+            // naive: take last non-empty line as chat_id
+            let id = text.lines().rfind(|l| !l.trim().is_empty()).unwrap_or("").trim().to_string();
+            if id.is_empty() {
+                return Err(CliError::provider(provider_key, ProviderErrorKind::Failed("create-chat returned empty id".into())));
+            }
+            Ok(id)
+        }
+        Err(e) if e == "timeout" => Err(CliError::timeout("cursor create-chat", timeout_ms)),
+        Err(e) => Err(CliError::provider(provider_key, ProviderErrorKind::NotStarted(format!("create-chat: {}: {}", cmd, e)))),
+    }
+}
+
+/// Trimmed stderr, cut to `CREATE_CHAT_STDERR_LIMIT` characters
+fn truncate_stderr(stderr: &str) -> String {
+    let trimmed = stderr.trim();
+    match trimmed.char_indices().nth(CREATE_CHAT_STDERR_LIMIT) {
+        Some((cut, _)) => format!("{}... (truncated)", &trimmed[..cut]),
+        None => trimmed.to_string(),
+    }
+}
+
+/// Log a `create_chat_failed` event and keep an `invalid` session row so the attempt
+/// shows up in `session list --status invalid`; recording problems only warn
+fn record_create_chat_failure(project_name: &str, agent: &AgentConfig, err: &CliError) {
+    let error = err.to_string();
+    log_ndjson(project_name, &agent.role, &agent.provider, None, "system", "create_chat_failed", Some(&error), Some(err.exit_code()), None, &agent.name);
+    let metadata = serde_json::json!({
+        "error": error,
+        "stage": "create_chat",
+        "exit_code": err.exit_code(),
+    }).to_string();
+    match insert_create_chat_failure(project_name, agent, &metadata) {
+        Ok(true) => {}
+        Ok(false) => eprintln!("Warning: agent '{}' not found in the database; failed session not recorded", agent.name),
+        Err(e) => eprintln!("Warning: could not record failed session: {}", e),
+    }
+}
+
+/// Insert the `invalid` session row; `Ok(false)` when the project or agent is not in the database
+fn insert_create_chat_failure(project_name: &str, agent: &AgentConfig, metadata: &str) -> Result<bool, CliError> {
+    let conn = open_or_create_db(&resolve_db_path())?;
+    let Some(project_id) = find_project_id(&conn, IdOrName::Name(project_name))? else { return Ok(false) };
+    let agent_id: Option<String> = conn.query_row(
+        "SELECT id FROM agents WHERE project_id = ?1 AND name = ?2",
+        params![project_id, &agent.name],
+        |row| row.get(0)
+    ).optional()?;
+    let Some(agent_id) = agent_id else { return Ok(false) };
+    insert_failed_session(&conn, &project_id, &agent_id, &agent.provider, metadata)?;
+    Ok(true)
+}
+
+/// Return a usable session id for `agent`, creating a new session when
+/// `existing_conv_id` is absent, unknown, no longer active or past its `expires_at`
+/// (which marks it expired)
+pub fn ensure_session_for_agent(
+    conn: &Connection,
+    project_id: &str,
+    agent: &AgentConfig,
+    existing_conv_id: Option<&str>,
+    db_path: &str,
+    session_ttl: Option<Duration>,
+    providers: &ProvidersConfig,
+) -> Result<String, CliError> {
+    if let Some(conv_id) = existing_conv_id {
+        if let Some(existing) = find_session(conn, conv_id)? {
+            if existing.status == SessionStatus::Active {
+                if existing.provider_session_id.is_some() {
+                    // Expired by `expires_at`, fresh, or pinged when within the slack of it
+                    if session_manager(providers, agent, db_path, None)?.validate_session(conv_id)? {
+                        return Ok(conv_id.to_string());
+                    }
+                } else if !existing.is_expired_by_time(time::OffsetDateTime::now_utc()) {
+                    // No provider session to confirm yet: only `expires_at` can rule it out
+                    return Ok(conv_id.to_string());
+                } else {
+                    update_session(conn, conv_id, None, None, Some(SessionStatus::Expired))?;
+                }
+            }
+        }
+    }
+    create_session_for_agent(conn, project_id, agent, None, db_path, session_ttl, providers)
+}
+
+/// SessionManager of the agent's provider (or the provider it aliases), creating sessions that expire
+/// after `session_ttl` and validating with the provider's `session_expiry_slack_ms`
+fn session_manager(providers: &ProvidersConfig, agent: &AgentConfig, db_path: &str, session_ttl: Option<Duration>) -> Result<Box<dyn SessionManager>, CliError> {
+    let slack = providers.providers.get(&agent.provider)
+        .and_then(|tpl| tpl.session_expiry_slack_ms)
+        .map_or(DEFAULT_EXPIRY_SLACK, Duration::from_millis);
+    Ok(match alias_root(providers, &agent.provider) {
+        "claude" => Box::new(ClaudeSessionManager::new(open_or_create_db(db_path)?).with_session_ttl(session_ttl).with_expiry_slack(slack)),
+        "cursor-agent" => Box::new(CursorSessionManager::new(open_or_create_db(db_path)?).with_session_ttl(session_ttl).with_expiry_slack(slack)),
+        "gemini" => Box::new(GeminiSessionManager::new(open_or_create_db(db_path)?).with_session_ttl(session_ttl).with_expiry_slack(slack)),
+        _ => return Err(CliError::provider(agent.provider.as_str(), ProviderErrorKind::NotConfigured)),
+    })
+}
+
+/// `--session-ttl`, else `defaults.session_ttl`; `None` when neither is set (sessions never expire)
+pub fn session_ttl(flag: Option<&str>, project_default: Option<&str>) -> Result<Option<Duration>, CliError> {
+    flag.or(project_default)
+        .map(|spec| parse_duration_spec(spec).map_err(|e| CliError::validation("session-ttl", e)))
+        .transpose()
+}
+
+/// TTL for an agent's new sessions: `--session-ttl` / `defaults.session_ttl` when set, else its provider's
+/// `session_ttl_ms`. The provider drops its conversations after that window, so a longer setting is capped at it.
+pub fn effective_session_ttl(configured: Option<Duration>, provider: Option<&ProviderTemplate>) -> Option<Duration> {
+    let window = provider.and_then(|tpl| tpl.session_ttl_ms).map(Duration::from_millis);
+    match (configured, window) {
+        (Some(ttl), Some(window)) => Some(ttl.min(window)),
+        (ttl, window) => ttl.or(window),
+    }
+}
+
+/// Create a session through the SessionManager of the agent's provider, or of the provider it aliases
+fn create_session_for_agent(
+    conn: &Connection,
+    project_id: &str,
+    agent: &AgentConfig,
+    provider_session_id: Option<&str>,
+    db_path: &str,
+    session_ttl: Option<Duration>,
+    providers: &ProvidersConfig,
+) -> Result<String, CliError> {
+    let agent_id: String = conn.query_row(
+        "SELECT id FROM agents WHERE project_id = ?1 AND name = ?2",
+        params![project_id, &agent.name],
+        |row| row.get(0)
+    )?;
+    // Sessions are recorded under the base provider, so `session resume` finds the same manager
+    let manager = session_manager(providers, agent, db_path, session_ttl)?;
+    let session = manager.create_session(project_id, &agent_id, alias_root(providers, &agent.provider), provider_session_id)?;
+    Ok(session.id)
+}
+
 /// Run session list command
-pub fn run_session_list(project_path_opt: Option<&str>, project_name_opt: Option<&str>, agent_filter: Option<&str>, provider_filter: Option<&str>, format: Format) -> Result<(), Box<dyn std::error::Error>> {
+pub fn run_session_list(project_path_opt: Option<&str>, project_name_opt: Option<&str>, agent_filter: Option<&str>, provider_filter: Option<&str>, format: Format, status: SessionStatusArg, allow_newer_schema: bool) -> Result<(), CliError> {
     let (project_path, _providers_path) = match resolve_config_paths(project_path_opt, None) {
         Ok(p) => p,
         Err(msg) => return handle_missing_config(msg),
@@ -126,7 +259,7 @@ pub fn run_session_list(project_path_opt: Option<&str>, project_name_opt: Option
     };
     
     let db_path = resolve_db_path();
-    let conn = open_or_create_db(&db_path)?;
+    let conn = if allow_newer_schema { open_readonly_db_allow_newer_schema(&db_path)? } else { open_readonly_db(&db_path)? };
     
     // Find project ID
     let project_id = find_project_id(&conn, IdOrName::Name(&project_name))?
@@ -137,7 +270,11 @@ pub fn run_session_list(project_path_opt: Option<&str>, project_name_opt: Option
         project_id: Some(project_id.clone()),
         agent_id: None,
         provider: provider_filter.map(|s| s.to_string()),
-        status: Some(SessionStatus::Active),
+        status: Some(match status {
+            SessionStatusArg::Active => SessionStatus::Active,
+            SessionStatusArg::Expired => SessionStatus::Expired,
+            SessionStatusArg::Invalid => SessionStatus::Invalid,
+        }),
         session_type: None, // Include both chat and repl sessions
         limit: Some(50), // Default limit
         offset: Some(0),
@@ -146,7 +283,7 @@ pub fn run_session_list(project_path_opt: Option<&str>, project_name_opt: Option
     // If agent filter provided, find agent ID
     if let Some(agent_name) = agent_filter {
         let proj_s = fs::read_to_string(&project_path)?;
-        let project = parse_project_yaml(&proj_s).map_err(|e| format!("project: {}", e))?;
+        let project = parse_project_yaml(&proj_s).map_err(|e| CliError::validation("project", e))?;
         let _agent = project.agents.iter().find(|a| a.name == agent_name)
             .ok_or_else(|| format!("unknown agent: {}", agent_name))?;
         
@@ -170,32 +307,26 @@ pub fn run_session_list(project_path_opt: Option<&str>, project_name_opt: Option
             }
             
             println!("Sessions for project '{}':", project_name);
-            println!("{:<36} {:<12} {:<12} {:<8} {:<20}", "ID", "Agent", "Provider", "Status", "Created");
-            println!("{}", "-".repeat(88));
+            println!("{:<36} {:<12} {:<12} {:<8} {:<12} {:<16}", "ID", "Agent", "Provider", "Status", "Created", "Expires");
+            println!("{}", "-".repeat(101));
             
+            let now = time::OffsetDateTime::now_utc();
             for session in sessions {
                 let created = session.created_at.split('T').next().unwrap_or(&session.created_at);
-                println!("{:<36} {:<12} {:<12} {:<8} {:<20}", 
+                println!("{:<36} {:<12} {:<12} {:<8} {:<12} {:<16}", 
                     session.id, 
                     session.agent_id, 
                     session.provider, 
                     session.status, 
-                    created
+                    created,
+                    expires_label(session.expires_at.as_deref(), now)
                 );
             }
         }
         Format::Json => {
             let json = serde_json::json!({
                 "project": project_name,
-                "sessions": sessions.iter().map(|s| serde_json::json!({
-                    "id": s.id,
-                    "agent_id": s.agent_id,
-                    "provider": s.provider,
-                    "status": s.status.to_string(),
-                    "created_at": s.created_at,
-                    "last_activity": s.last_activity,
-                    "provider_session_id": s.provider_session_id
-                })).collect::<Vec<_>>()
+                "sessions": sessions.iter().map(session_json).collect::<Result<Vec<_>, _>>()?,
             });
             println!("{}", serde_json::to_string_pretty(&json)?);
         }
@@ -205,14 +336,14 @@ pub fn run_session_list(project_path_opt: Option<&str>, project_name_opt: Option
 }
 
 /// Run session resume command
-pub fn run_session_resume(conversation_id: &str, timeout_ms: Option<u64>) -> Result<(), Box<dyn std::error::Error>> {
+pub fn run_session_resume(conversation_id: &str, timeout_ms: Option<u64>) -> Result<(), CliError> {
     let db_path = resolve_db_path();
     let conn = open_or_create_db(&db_path)?;
     
     // Find session
     let session = match find_session(&conn, conversation_id)? {
         Some(s) => s,
-        None => return exit_with(2, format!("Session not found: {}", conversation_id)),
+        None => return Err(CliError::validation("session", format!("not found: {}", conversation_id))),
     };
     
     // Create appropriate SessionManager
@@ -220,7 +351,7 @@ pub fn run_session_resume(conversation_id: &str, timeout_ms: Option<u64>) -> Res
         "claude" => Box::new(ClaudeSessionManager::new(conn)),
         "cursor-agent" => Box::new(CursorSessionManager::new(conn)),
         "gemini" => Box::new(GeminiSessionManager::new(conn)),
-        _ => return exit_with(2, format!("Unsupported provider: {}", session.provider)),
+        _ => return Err(CliError::provider(session.provider.as_str(), ProviderErrorKind::NotConfigured)),
     };
     
     // Resume session with timeout
@@ -231,7 +362,7 @@ pub fn run_session_resume(conversation_id: &str, timeout_ms: Option<u64>) -> Res
         Ok(context) => {
             let elapsed = start.elapsed();
             if elapsed > timeout {
-                return exit_with(5, "Session resume timeout".into());
+                return Err(CliError::timeout("session resume", timeout.as_millis() as u64));
             }
             
             println!("Session resumed successfully");
@@ -244,107 +375,114 @@ pub fn run_session_resume(conversation_id: &str, timeout_ms: Option<u64>) -> Res
         Err(e) => {
             let elapsed = start.elapsed();
             if elapsed > timeout {
-                return exit_with(5, "Session resume timeout".into());
+                return Err(CliError::timeout("session resume", timeout.as_millis() as u64));
             }
-            return exit_with(2, format!("Failed to resume session: {}", e));
+            return Err(CliError::validation("session", format!("failed to resume: {}", e)));
         }
     }
     
     Ok(())
 }
 
-/// Run session cleanup command
-pub fn run_session_cleanup(_project_path_opt: Option<&str>, dry_run: bool, format: Format) -> Result<(), Box<dyn std::error::Error>> {
+/// Run session describe command
+pub fn run_session_describe(conversation_id: &str, format: Format) -> Result<(), CliError> {
     let db_path = resolve_db_path();
-    let conn = open_or_create_db(&db_path)?;
-    
-    // Find expired sessions (older than 24 hours with no activity)
-    let cutoff_time = {
-        let now = std::time::SystemTime::now();
-        let cutoff = now - std::time::Duration::from_secs(24 * 60 * 60); // 24 hours
-        // Convert to ISO 8601 format like the database uses
-        let cutoff_duration = cutoff.duration_since(std::time::UNIX_EPOCH).unwrap();
-        format!("{}.{:09}Z", 
-            chrono::DateTime::<chrono::Utc>::from_timestamp(cutoff_duration.as_secs() as i64, 0)
-                .unwrap()
-                .format("%Y-%m-%dT%H:%M:%S"),
-            cutoff_duration.subsec_nanos()
-        )
-    };
-    
-    // Clean up REPL sessions (Issue #36)
-    let repl_cleaned = if dry_run {
-        // Query REPL sessions that would be cleaned up
-        let mut stmt = conn.prepare(
-            "SELECT id, project_id, agent_id, provider, created_at, last_activity, type
-             FROM sessions 
-             WHERE type = 'repl' 
-             AND (last_activity < ?1 OR created_at < ?1) 
-             AND status = 'active'"
-        )?;
-        
-        let session_iter = stmt.query_map(params![&cutoff_time], |row| {
-            Ok(serde_json::json!({
-                "id": row.get::<_, String>(0)?,
-                "project_id": row.get::<_, String>(1)?,
-                "agent_id": row.get::<_, String>(2)?,
-                "provider": row.get::<_, String>(3)?,
-                "created_at": row.get::<_, String>(4)?,
-                "last_activity": row.get::<_, Option<String>>(5)?,
-                "type": row.get::<_, String>(6)?
-            }))
-        })?;
-        
-        session_iter.collect::<Result<Vec<_>, _>>()?
-    } else {
-        // Actually clean up REPL sessions
-        let cleaned_count = cleanup_repl_sessions(&conn)?;
-        
-        vec![serde_json::json!({
-            "repl_cleaned_count": cleaned_count,
-            "cutoff_time": cutoff_time
-        })]
+    let conn = open_readonly_db(&db_path)?;
+    let session = match find_session(&conn, conversation_id)? {
+        Some(s) => s,
+        None => return Err(CliError::validation("session", format!("not found: {}", conversation_id))),
     };
-    
-    let expired_sessions = if dry_run {
-        // Query expired sessions without deleting
-        let mut stmt = conn.prepare(
-            "SELECT id, project_id, agent_id, provider, created_at, last_activity, type
-             FROM sessions 
-             WHERE (last_activity IS NULL OR last_activity < ?1) 
-             AND created_at < ?1
-             AND type = 'chat'"
-        )?;
-        
-        let session_iter = stmt.query_map(params![&cutoff_time], |row| {
-            Ok(serde_json::json!({
-                "id": row.get::<_, String>(0)?,
-                "project_id": row.get::<_, String>(1)?,
-                "agent_id": row.get::<_, String>(2)?,
-                "provider": row.get::<_, String>(3)?,
-                "created_at": row.get::<_, String>(4)?,
-                "last_activity": row.get::<_, Option<String>>(5)?,
-                "type": row.get::<_, String>(6)?
-            }))
-        })?;
-        
-        session_iter.collect::<Result<Vec<_>, _>>()?
+    match format {
+        Format::Text => print!("{}", describe_session_text(&session)),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&describe_session_json(&session)?)?),
+    }
+    Ok(())
+}
+
+/// `metadata` parsed as JSON; kept as a string when it is not valid JSON
+fn session_metadata_value(session: &Session) -> Option<serde_json::Value> {
+    session.metadata.as_deref().map(|m| {
+        serde_json::from_str(m).unwrap_or_else(|_| serde_json::Value::String(m.to_string()))
+    })
+}
+
+/// The serialized `Session`, with `metadata` as a JSON object; unset optional fields are left out
+pub(crate) fn session_json(session: &Session) -> Result<serde_json::Value, CliError> {
+    let mut value = serde_json::to_value(session)?;
+    if let Some(metadata) = session_metadata_value(session) {
+        value["metadata"] = metadata;
+    }
+    Ok(value)
+}
+
+/// `session_json` plus `is_resumable`
+pub(crate) fn describe_session_json(session: &Session) -> Result<serde_json::Value, CliError> {
+    let mut value = session_json(session)?;
+    value["is_resumable"] = serde_json::Value::Bool(session.provider_session_id.is_some());
+    Ok(value)
+}
+
+/// One aligned `field: value` line per field; `metadata` is pretty-printed on the following lines
+pub(crate) fn describe_session_text(session: &Session) -> String {
+    let opt = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".into());
+    let fields = [
+        ("conversation_id", session.id.clone()),
+        ("project_id", session.project_id.clone()),
+        ("agent_id", session.agent_id.clone()),
+        ("provider", session.provider.clone()),
+        ("provider_session_id", opt(&session.provider_session_id)),
+        ("type", session.session_type.to_string()),
+        ("status", session.status.to_string()),
+        ("created_at", session.created_at.clone()),
+        ("last_activity", opt(&session.last_activity)),
+        ("expires_at", opt(&session.expires_at)),
+        ("is_resumable", session.provider_session_id.is_some().to_string()),
+    ];
+    let width = fields.iter().map(|(k, _)| k.len()).max().unwrap_or(0).max("metadata".len());
+    let mut out = String::new();
+    for (key, value) in fields {
+        out.push_str(&format!("{:<width$} {}\n", format!("{}:", key), value, width = width + 1));
+    }
+    match session_metadata_value(session) {
+        Some(meta) => {
+            out.push_str("metadata:\n");
+            let pretty = serde_json::to_string_pretty(&meta).unwrap_or_default();
+            for line in pretty.lines() {
+                out.push_str(&format!("  {}\n", line));
+            }
+        }
+        None => out.push_str(&format!("{:<width$} -\n", "metadata:", width = width + 1)),
+    }
+    out
+}
+
+/// Idle window used by `session cleanup` when `--older-than` is not given
+pub const DEFAULT_CLEANUP_OLDER_THAN: &str = "24h";
+
+/// RFC3339 cutoff for `session cleanup`: now minus `--older-than` (e.g. `30m`, `48h`, `7d`)
+pub fn cleanup_cutoff(older_than: Option<&str>) -> Result<String, CliError> {
+    let spec = older_than.unwrap_or(DEFAULT_CLEANUP_OLDER_THAN);
+    let window = parse_duration_spec(spec).map_err(|e| CliError::validation("session cleanup", e))?;
+    Ok(iso8601_utc_ago(window))
+}
+
+/// Run session cleanup command
+pub fn run_session_cleanup(_project_path_opt: Option<&str>, dry_run: bool, older_than: Option<&str>, format: Format) -> Result<(), CliError> {
+    // Find expired sessions (no activity for --older-than, default 24 hours)
+    let cutoff_time = cleanup_cutoff(older_than)?;
+
+    // Cleanup works on the schema as it is (see `cleanup_sessions`): it neither migrates the
+    // database nor creates one, and a missing database has nothing to clean up
+    let db_path = resolve_db_path();
+    let (expired_sessions, repl_cleaned) = if std::path::Path::new(&db_path).exists() {
+        cleanup_sessions(&open_db_without_migrating(&db_path)?, &cutoff_time, dry_run)?
+    } else if dry_run {
+        (Vec::new(), Vec::new())
     } else {
-        // Actually delete expired chat sessions
-        let deleted_count = conn.execute(
-            "DELETE FROM sessions 
-             WHERE (last_activity IS NULL OR last_activity < ?1) 
-             AND created_at < ?1
-             AND type = 'chat'",
-            params![&cutoff_time]
-        )?;
-        
-        vec![serde_json::json!({
-            "chat_deleted_count": deleted_count,
-            "cutoff_time": cutoff_time
-        })]
+        let none = vec![serde_json::json!({ "chat_deleted_count": 0, "cutoff_time": cutoff_time })];
+        (none, vec![serde_json::json!({ "repl_cleaned_count": 0, "cutoff_time": cutoff_time })])
     };
-    
+
     match format {
         Format::Text => {
             if dry_run {
@@ -386,3 +524,68 @@ pub fn run_session_cleanup(_project_path_opt: Option<&str>, dry_run: bool, forma
     
     Ok(())
 }
+
+/// Expire stale REPL sessions and delete expired chat sessions (or only list them with `dry_run`);
+/// returns the `(chat, repl)` rows reported by `session cleanup`
+pub fn cleanup_sessions(conn: &Connection, cutoff_time: &str, dry_run: bool) -> Result<(Vec<serde_json::Value>, Vec<serde_json::Value>), CliError> {
+    // Migration guard: `sessions.type` arrives with v3. Without it no session is marked REPL, so
+    // REPL cleanup is skipped and every session is treated as chat.
+    let repl_cleaned = if !column_exists(conn, "sessions", "type")? {
+        if dry_run { Vec::new() } else { vec![serde_json::json!({ "repl_cleaned_count": 0, "cutoff_time": cutoff_time })] }
+    } else if dry_run {
+        // Query REPL sessions that would be cleaned up
+        list_stale_repl_sessions_before(conn, cutoff_time)?.iter().map(cleanup_session_json).collect()
+    } else {
+        // Actually clean up REPL sessions
+        let cleaned_count = cleanup_repl_sessions_before(conn, cutoff_time)?;
+        
+        vec![serde_json::json!({
+            "repl_cleaned_count": cleaned_count,
+            "cutoff_time": cutoff_time
+        })]
+    };
+    
+    let expired_sessions = if dry_run {
+        // Query expired sessions without deleting
+        list_expired_chat_sessions_before(conn, cutoff_time)?.iter().map(cleanup_session_json).collect()
+    } else {
+        // Actually delete expired chat sessions
+        let deleted_count = delete_expired_chat_sessions_before(conn, cutoff_time)?;
+        
+        vec![serde_json::json!({
+            "chat_deleted_count": deleted_count,
+            "cutoff_time": cutoff_time
+        })]
+    };
+    
+    Ok((expired_sessions, repl_cleaned))
+}
+
+/// `session list` Expires cell: `expires in 3h` in the largest whole unit, `expired`, or `-` without `expires_at`
+pub fn expires_label(expires_at: Option<&str>, now: time::OffsetDateTime) -> String {
+    let Some(expires_at) = expires_at.and_then(|e| db::parse_iso8601_utc(e).ok()) else {
+        return "-".to_string();
+    };
+    let left = (expires_at - now).whole_seconds();
+    if left <= 0 {
+        return "expired".to_string();
+    }
+    let (amount, unit) = [(86_400, "d"), (3_600, "h"), (60, "m")]
+        .into_iter()
+        .find(|(secs, _)| left >= *secs)
+        .map_or((left, "s"), |(secs, unit)| (left / secs, unit));
+    format!("expires in {}{}", amount, unit)
+}
+
+/// Dry-run row for a session that cleanup would touch
+fn cleanup_session_json(session: &db::Session) -> serde_json::Value {
+    serde_json::json!({
+        "id": session.id,
+        "project_id": session.project_id,
+        "agent_id": session.agent_id,
+        "provider": session.provider,
+        "created_at": session.created_at,
+        "last_activity": session.last_activity,
+        "type": session.session_type.to_string()
+    })
+}
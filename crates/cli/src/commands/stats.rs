@@ -0,0 +1,101 @@
+//! Project and session statistics commands
+
+use std::time::Duration;
+use db::{open_readonly_db, find_project_id, get_project_stats, get_session_stats, weekly_summary, ProjectStats, DailyStats, SessionStats};
+use rusqlite::Connection;
+use crate::cli::commands::{Format, SelectBy};
+use crate::utils::{resolve_db_path, project_selector, parse_duration_spec, CliError};
+
+/// Run stats command; `weekly` adds a per-day breakdown and defaults the window to 7 days
+pub fn run_stats(project_sel: &str, since: Option<&str>, weekly: bool, db_path: Option<&str>, format: Format, by: Option<SelectBy>) -> Result<(), CliError> {
+    let since_spec = since.unwrap_or(if weekly { "7d" } else { "24h" });
+    let (conn, project_id, cutoff) = open_project_window(project_sel, by, since_spec, db_path)?;
+    let stats = get_project_stats(&conn, &project_id, cutoff)?;
+    let days = if weekly { Some(weekly_summary(&conn, &project_id, cutoff)?) } else { None };
+
+    match format {
+        Format::Text => print!("{}", render_text(project_sel, since_spec, &stats, days.as_deref())),
+        Format::Json => {
+            let mut out = serde_json::to_value(&stats)?;
+            out["project"] = serde_json::json!(project_sel);
+            if let Some(days) = &days {
+                out["days"] = serde_json::to_value(days)?;
+            }
+            println!("{}", out);
+        }
+    }
+    Ok(())
+}
+
+/// Run `session stats`: sessions created in the `since` window (default 7 days)
+pub fn run_session_stats(project_sel: &str, since: Option<&str>, db_path: Option<&str>, format: Format, by: Option<SelectBy>) -> Result<(), CliError> {
+    let since_spec = since.unwrap_or("7d");
+    let (conn, project_id, cutoff) = open_project_window(project_sel, by, since_spec, db_path)?;
+    let stats = get_session_stats(&conn, &project_id, cutoff)?;
+    match format {
+        Format::Text => print!("{}", render_session_stats(project_sel, since_spec, &stats)),
+        Format::Json => {
+            let mut out = serde_json::to_value(&stats)?;
+            out["project"] = serde_json::json!(project_sel);
+            println!("{}", out);
+        }
+    }
+    Ok(())
+}
+
+/// Open the DB, resolve the project and turn `since_spec` into a cutoff timestamp
+fn open_project_window(project_sel: &str, by: Option<SelectBy>, since_spec: &str, db_path: Option<&str>) -> Result<(Connection, String, time::OffsetDateTime), CliError> {
+    let window = parse_duration_spec(since_spec).map_err(|e| CliError::validation("stats", e))?;
+    let binding;
+    let path = match db_path { Some(p) => p, None => { binding = resolve_db_path(); &binding } };
+    let conn = open_readonly_db(path)?;
+    let project_id = match find_project_id(&conn, project_selector(project_sel, by))? {
+        Some(id) => id,
+        None => return Err(CliError::validation("project", format!("not found: {}", project_sel))),
+    };
+    let cutoff = time::OffsetDateTime::now_utc()
+        .saturating_sub(time::Duration::try_from(window).unwrap_or(time::Duration::MAX));
+    Ok((conn, project_id, cutoff))
+}
+
+pub(crate) fn render_session_stats(project: &str, since_spec: &str, stats: &SessionStats) -> String {
+    let lifetime = stats.avg_lifetime_ms.map(|ms| format_ms(Duration::from_millis(ms.round() as u64))).unwrap_or_else(|| "-".into());
+    let mut out = format!("Sessions for project '{}' (last {}, since {})\n", project, since_spec, stats.since);
+    out += &format!("  Sessions:           {} ({} resumed)\n", stats.total_sessions, stats.resumed_sessions);
+    out += &format!("  Avg lifetime:       {}\n", lifetime);
+    if !stats.by_provider.is_empty() {
+        out += "\n  Provider      Status    Sessions\n";
+        for p in &stats.by_provider {
+            out += &format!("  {:<12}  {:<8}  {:>8}\n", p.provider, p.status, p.sessions);
+        }
+    }
+    if !stats.by_agent.is_empty() {
+        out += "\n  Agent         Sessions  Expired\n";
+        for a in &stats.by_agent {
+            out += &format!("  {:<12}  {:>8}  {:>7}\n", a.agent, a.sessions, a.expired);
+        }
+    }
+    out
+}
+
+fn render_text(project: &str, since_spec: &str, stats: &ProjectStats, days: Option<&[DailyStats]>) -> String {
+    let avg = |ms: Option<f64>| ms.map(|ms| format_ms(Duration::from_millis(ms.round() as u64))).unwrap_or_else(|| "-".into());
+    let mut out = format!("Stats for project '{}' (last {}, since {})\n", project, since_spec, stats.since);
+    out += &format!("  Sends:              {} ({} broadcasts)\n", stats.total_sends, stats.broadcasts);
+    out += &format!("  Agent deliveries:   {} ok, {} failed\n", stats.successful_agents, stats.failed_agents);
+    out += &format!("  Avg response time:  {}\n", avg(stats.avg_response_ms));
+    out += &format!("  Tokens (estimated): {}\n", stats.tokens_estimated);
+    out += &format!("  Tasks:              {}/{} done ({:.0}%)\n", stats.tasks_done, stats.tasks_total, stats.task_completion_rate * 100.0);
+    out += &format!("  Active sessions:    {}\n", stats.active_sessions);
+    if let Some(days) = days {
+        out += "\n  Day          Sends  OK  Failed  Avg response\n";
+        for d in days {
+            out += &format!("  {:<11}  {:>5}  {:>2}  {:>6}  {}\n", d.day, d.sends, d.successful_agents, d.failed_agents, avg(d.avg_response_ms));
+        }
+    }
+    out
+}
+
+fn format_ms(d: Duration) -> String {
+    if d.as_millis() < 1000 { format!("{}ms", d.as_millis()) } else { format!("{:.1}s", d.as_secs_f64()) }
+}
@@ -0,0 +1,83 @@
+//! Supervisor subscription commands: `supervisor subscribe|unsubscribe`
+
+use db::{open_or_create_db, find_project_id, find_agent_by_name, insert_subscription, delete_subscriptions};
+use crate::cli::commands::{Format, SelectBy, SupervisorEventArg};
+use crate::supervisor::notify::{NotifyEventKind, SourceFilter};
+use crate::utils::{resolve_db_path, project_selector, CliError};
+
+fn event_kind(arg: SupervisorEventArg) -> NotifyEventKind {
+    match arg {
+        SupervisorEventArg::SendCompleted => NotifyEventKind::SendCompleted,
+        SupervisorEventArg::SendFailed => NotifyEventKind::SendFailed,
+        SupervisorEventArg::TaskDone => NotifyEventKind::TaskDone,
+    }
+}
+
+/// `(project_id, supervisor agent)` for a `--project`/`--agent` pair
+fn resolve_supervisor(conn: &rusqlite::Connection, project_sel: &str, by: Option<SelectBy>, agent_name: &str) -> Result<(String, db::Agent), CliError> {
+    let project_id = find_project_id(conn, project_selector(project_sel, by))?
+        .ok_or_else(|| CliError::validation("project", format!("not found: {}", project_sel)))?;
+    let agent = find_agent_by_name(conn, &project_id, agent_name)?
+        .ok_or_else(|| CliError::validation("agent", format!("not found: {} (project {})", agent_name, project_sel)))?;
+    Ok((project_id, agent))
+}
+
+/// Run `supervisor subscribe`: notify `agent` of each `on` event raised by agents matching `from`
+pub fn run_supervisor_subscribe(
+    project_sel: &str,
+    agent_name: &str,
+    on: &[SupervisorEventArg],
+    from: &str,
+    db_path: Option<&str>,
+    format: Format,
+    by: Option<SelectBy>,
+) -> Result<(), CliError> {
+    let filter: SourceFilter = from.parse().map_err(|e| CliError::validation("from", e))?;
+    let path = db_path.map(str::to_string).unwrap_or_else(resolve_db_path);
+    let conn = open_or_create_db(&path)?;
+    let (project_id, agent) = resolve_supervisor(&conn, project_sel, by, agent_name)?;
+    let mut subscriptions = Vec::with_capacity(on.len());
+    for kind in on.iter().copied().map(event_kind) {
+        subscriptions.push(insert_subscription(&conn, &project_id, &agent.id, kind.as_str(), &filter.to_string())?);
+    }
+    match format {
+        Format::Text => {
+            for sub in &subscriptions {
+                println!("OK: {} is notified on {} from {}", agent.name, sub.event_kind, sub.source_filter);
+            }
+        }
+        Format::Json => println!("{}", serde_json::json!({"agent": agent.name, "subscriptions": subscriptions})),
+    }
+    Ok(())
+}
+
+/// Run `supervisor unsubscribe`: drop `agent`'s subscriptions, narrowed by `on` and `from` when given
+pub fn run_supervisor_unsubscribe(
+    project_sel: &str,
+    agent_name: &str,
+    on: &[SupervisorEventArg],
+    from: Option<&str>,
+    db_path: Option<&str>,
+    format: Format,
+    by: Option<SelectBy>,
+) -> Result<(), CliError> {
+    let filter = from.map(str::parse::<SourceFilter>).transpose().map_err(|e| CliError::validation("from", e))?;
+    let filter = filter.map(|f| f.to_string());
+    let path = db_path.map(str::to_string).unwrap_or_else(resolve_db_path);
+    let conn = open_or_create_db(&path)?;
+    let (_, agent) = resolve_supervisor(&conn, project_sel, by, agent_name)?;
+    let removed = if on.is_empty() {
+        delete_subscriptions(&conn, &agent.id, None, filter.as_deref())?
+    } else {
+        let mut removed = 0;
+        for kind in on.iter().copied().map(event_kind) {
+            removed += delete_subscriptions(&conn, &agent.id, Some(kind.as_str()), filter.as_deref())?;
+        }
+        removed
+    };
+    match format {
+        Format::Text => println!("OK: removed {} subscription(s) of {}", removed, agent.name),
+        Format::Json => println!("{}", serde_json::json!({"agent": agent.name, "removed": removed})),
+    }
+    Ok(())
+}
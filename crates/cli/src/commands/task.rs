@@ -0,0 +1,23 @@
+//! Task commands: `task status`
+
+use db::open_or_create_db;
+use crate::cli::commands::Format;
+use crate::supervisor::notify::update_task_status;
+use crate::utils::{resolve_db_path, CliError};
+
+/// Run `task status`: set a task's status; moving an assigned task to `done` raises `task_done`
+/// for subscribed supervisors
+pub fn run_task_status(task_id: &str, status: &str, db_path: Option<&str>, format: Format) -> Result<(), CliError> {
+    if status.trim().is_empty() {
+        return Err(CliError::validation("status", "empty task status"));
+    }
+    let path = db_path.map(str::to_string).unwrap_or_else(resolve_db_path);
+    let conn = open_or_create_db(&path)?;
+    let task = update_task_status(&conn, task_id, status)?
+        .ok_or_else(|| CliError::validation("task", format!("not found: {}", task_id)))?;
+    match format {
+        Format::Text => println!("OK: task {} is {}", task.id, task.status),
+        Format::Json => println!("{}", serde_json::json!({"task": task})),
+    }
+    Ok(())
+}
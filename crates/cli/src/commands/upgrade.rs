@@ -0,0 +1,180 @@
+//! Upgrade command: compare the running version with the latest GitHub release
+
+use std::cmp::Ordering;
+use std::time::Duration;
+use serde::Deserialize;
+use crate::cli::commands::Format;
+use crate::utils::CliError;
+
+const RELEASES_API: &str = "https://api.github.com/repos/Fenikz31/multi-agents/releases";
+const INSTALL_HINT: &str = "cargo install multi-agents";
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The fields of a GitHub release the check needs
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub html_url: String,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
+}
+
+/// Outcome of comparing the current version with the newest release
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpgradeCheck {
+    pub current: String,
+    pub latest: String,
+    pub url: String,
+    pub upgrade_available: bool,
+}
+
+/// Check GitHub Releases for a newer version. Without `check_only` the release page is printed too.
+pub fn run_upgrade(check_only: bool, prerelease: bool, format: Format) -> Result<(), CliError> {
+    let check = check_upgrade(env!("CARGO_PKG_VERSION"), prerelease, http_get)?;
+    match format {
+        Format::Json => println!("{}", upgrade_json(&check, prerelease)),
+        Format::Text => {
+            println!("{}", upgrade_message(&check));
+            if check.upgrade_available && !check_only && !check.url.is_empty() {
+                println!("Release notes: {}", check.url);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fetch the latest release (any non-draft release with `prerelease`) through `fetch` and compare it
+/// with `current`; `fetch` receives the API URL and returns the response body
+pub fn check_upgrade(
+    current: &str,
+    prerelease: bool,
+    fetch: impl Fn(&str) -> Result<String, CliError>,
+) -> Result<UpgradeCheck, CliError> {
+    let release = if prerelease {
+        let body = fetch(&format!("{}?per_page=30", RELEASES_API))?;
+        let releases: Vec<Release> = serde_json::from_str(&body)
+            .map_err(|e| CliError::Other(format!("upgrade: unexpected releases response: {}", e)))?;
+        releases.into_iter()
+            .filter(|r| !r.draft && parse_version(&r.tag_name).is_some())
+            .max_by(|a, b| compare_versions(&a.tag_name, &b.tag_name))
+            .ok_or_else(|| CliError::Other("upgrade: no published release found".to_string()))?
+    } else {
+        let body = fetch(&format!("{}/latest", RELEASES_API))?;
+        serde_json::from_str(&body)
+            .map_err(|e| CliError::Other(format!("upgrade: unexpected release response: {}", e)))?
+    };
+    if parse_version(&release.tag_name).is_none() {
+        return Err(CliError::Other(format!("upgrade: cannot parse release tag '{}'", release.tag_name)));
+    }
+    let latest = release.tag_name.trim_start_matches('v').to_string();
+    Ok(UpgradeCheck {
+        upgrade_available: compare_versions(&latest, current) == Ordering::Greater,
+        current: current.to_string(),
+        latest,
+        url: release.html_url,
+    })
+}
+
+/// One-line text verdict
+pub fn upgrade_message(check: &UpgradeCheck) -> String {
+    if check.upgrade_available {
+        format!("upgrade available: {} (current: {}). Install: {}", check.latest, check.current, INSTALL_HINT)
+    } else {
+        "already up to date".to_string()
+    }
+}
+
+pub fn upgrade_json(check: &UpgradeCheck, prerelease: bool) -> serde_json::Value {
+    serde_json::json!({
+        "current": check.current,
+        "latest": check.latest,
+        "upgrade_available": check.upgrade_available,
+        "prerelease": prerelease,
+        "url": check.url,
+        "install": INSTALL_HINT,
+    })
+}
+
+/// `v1.2.3` / `1.2.3-rc.1` → (1, 2, 3, pre-release suffix); build metadata is ignored
+fn parse_version(tag: &str) -> Option<(u64, u64, u64, Option<&str>)> {
+    let v = tag.trim().trim_start_matches('v');
+    let v = v.split('+').next().unwrap_or(v);
+    let (core, pre) = match v.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (v, None),
+    };
+    let mut parts = core.split('.').map(|p| p.parse::<u64>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((major, minor, patch, pre))
+}
+
+/// Semver precedence: a pre-release sorts before its release; unparsable versions sort lowest
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    match (parse_version(a), parse_version(b)) {
+        (Some((a1, a2, a3, a_pre)), Some((b1, b2, b3, b_pre))) => (a1, a2, a3).cmp(&(b1, b2, b3)).then_with(|| {
+            match (a_pre, b_pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(x), Some(y)) => compare_pre(x, y),
+            }
+        }),
+        (Some(_), None) => Ordering::Greater,
+        (None, Some(_)) => Ordering::Less,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Dot-separated identifiers: numeric ones compare numerically and sort before alphanumeric ones
+fn compare_pre(a: &str, b: &str) -> Ordering {
+    let mut xs = a.split('.');
+    let mut ys = b.split('.');
+    loop {
+        match (xs.next(), ys.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => {
+                let ord = match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(m), Ok(n)) => m.cmp(&n),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => x.cmp(y),
+                };
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+        }
+    }
+}
+
+/// Blocking GET against the GitHub API, which rejects requests without a User-Agent
+fn http_get(url: &str) -> Result<String, CliError> {
+    let agent = ureq::AgentBuilder::new().timeout(HTTP_TIMEOUT).build();
+    let response = agent.get(url)
+        .set("User-Agent", concat!("multi-agents/", env!("CARGO_PKG_VERSION")))
+        .set("Accept", "application/vnd.github+json")
+        .call();
+    match response {
+        Ok(resp) => resp.into_string().map_err(CliError::from),
+        Err(ureq::Error::Status(code, _)) => Err(CliError::Other(format!("upgrade: GitHub API returned HTTP {} for {}", code, url))),
+        Err(ureq::Error::Transport(t)) if is_timeout(&t) =>
+            Err(CliError::timeout("upgrade check", HTTP_TIMEOUT.as_millis() as u64)),
+        Err(ureq::Error::Transport(t)) => Err(CliError::Other(format!("upgrade: cannot reach GitHub: {}", t))),
+    }
+}
+
+fn is_timeout(t: &ureq::Transport) -> bool {
+    std::error::Error::source(t)
+        .and_then(|e| e.downcast_ref::<std::io::Error>())
+        .is_some_and(|e| matches!(e.kind(), std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock))
+}
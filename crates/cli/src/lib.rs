@@ -14,6 +14,7 @@ pub mod monitoring;
 pub mod repository;
 pub mod tui;
 pub mod supervisor;
+pub mod backend;
 
 // Re-export main types for convenience
 pub use cli::Cli;
@@ -1,82 +1,122 @@
-//! NDJSON Event structures
+//! NDJSON event type shared by every log writer
+//!
+//! Every line carries the same fields, in `EVENT_FIELDS` order; unset optional fields are
+//! written as `null` rather than left out, so readers can rely on the keys being present.
 
 use serde::{Serialize, Deserialize};
+use db::now_iso8601_utc;
 
+/// Keys of every NDJSON line, in the order they are written
+pub const EVENT_FIELDS: &[&str] = &[
+    "ts", "level", "project_id", "agent_role", "agent_id", "provider", "session_id", "broadcast_id",
+    "message_id", "direction", "event", "text", "exit_code", "dur_ms", "lines", "bytes", "cached",
+];
+
+/// One NDJSON log line
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct NdjsonEvent {
+#[serde(default)]
+pub struct Event {
     pub ts: String,
     pub level: String,
     pub project_id: String,
     pub agent_role: String,
+    /// Agent name; empty when the writer only knows the role
     pub agent_id: String,
     pub provider: String,
+    pub session_id: Option<String>,
+    pub broadcast_id: Option<String>,
+    pub message_id: Option<String>,
+    /// `user`, `agent` or `system`
+    pub direction: String,
     pub event: String,
     pub text: Option<String>,
+    pub exit_code: Option<i32>,
     pub dur_ms: Option<u64>,
-    pub broadcast_id: Option<String>,
-    pub session_id: Option<String>,
-    pub message_id: Option<String>,
+    /// Provider stdout lines, on the `end` event of a one-shot send or a background agent
+    pub lines: Option<u64>,
+    /// Provider stdout bytes (newlines included), alongside `lines`
+    pub bytes: Option<u64>,
+    /// `true` on the events of a `send --cache` hit, replayed without running the provider
+    pub cached: Option<bool>,
 }
 
-impl NdjsonEvent {
-    pub fn new_start(project_id: &str, agent_role: &str, agent_id: &str, provider: &str) -> Self {
-        Self {
-            ts: chrono::Utc::now().to_rfc3339(),
-            level: "info".to_string(),
-            project_id: project_id.to_string(),
-            agent_role: agent_role.to_string(),
-            agent_id: agent_id.to_string(),
-            provider: provider.to_string(),
-            event: "start".to_string(),
-            text: None,
-            dur_ms: None,
-            broadcast_id: None,
-            session_id: None,
-            message_id: None,
+/// Earlier name of [`Event`]
+pub type NdjsonEvent = Event;
+
+/// Builder for [`Event`]; starts as an `info` `system` event stamped now
+#[derive(Debug, Clone)]
+pub struct EventBuilder {
+    event: Event,
+}
+
+impl EventBuilder {
+    pub fn ts(mut self, ts: &str) -> Self { self.event.ts = ts.to_string(); self }
+    pub fn level(mut self, level: &str) -> Self { self.event.level = level.to_string(); self }
+    pub fn agent_id(mut self, agent_id: &str) -> Self { self.event.agent_id = agent_id.to_string(); self }
+    pub fn session_id(mut self, session_id: Option<&str>) -> Self { self.event.session_id = session_id.map(str::to_string); self }
+    pub fn broadcast_id(mut self, broadcast_id: Option<&str>) -> Self { self.event.broadcast_id = broadcast_id.map(str::to_string); self }
+    pub fn message_id(mut self, message_id: Option<&str>) -> Self { self.event.message_id = message_id.map(str::to_string); self }
+    pub fn direction(mut self, direction: &str) -> Self { self.event.direction = direction.to_string(); self }
+    pub fn text(mut self, text: Option<&str>) -> Self { self.event.text = text.map(str::to_string); self }
+    pub fn exit_code(mut self, exit_code: Option<i32>) -> Self { self.event.exit_code = exit_code; self }
+    pub fn dur_ms(mut self, dur_ms: Option<u64>) -> Self { self.event.dur_ms = dur_ms; self }
+    pub fn output(mut self, lines: Option<u64>, bytes: Option<u64>) -> Self { self.event.lines = lines; self.event.bytes = bytes; self }
+    pub fn cached(mut self, cached: Option<bool>) -> Self { self.event.cached = cached; self }
+    pub fn build(self) -> Event { self.event }
+}
+
+impl Event {
+    pub fn builder(project_id: &str, agent_role: &str, provider: &str, event: &str) -> EventBuilder {
+        EventBuilder {
+            event: Event {
+                ts: now_iso8601_utc(),
+                level: "info".to_string(),
+                project_id: project_id.to_string(),
+                agent_role: agent_role.to_string(),
+                provider: provider.to_string(),
+                direction: "system".to_string(),
+                event: event.to_string(),
+                ..Default::default()
+            },
         }
     }
 
+    /// The event as a single JSON line, without the trailing newline
+    pub fn to_line(&self) -> String {
+        serde_json::to_string(self).expect("Event always serializes")
+    }
+
+    /// Parse one NDJSON line; keys missing from older lines take their default
+    pub fn from_line(line: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(line)
+    }
+
+    pub fn new_start(project_id: &str, agent_role: &str, agent_id: &str, provider: &str) -> Self {
+        Self::builder(project_id, agent_role, provider, "start").agent_id(agent_id).build()
+    }
+
     pub fn new_stdout_line(project_id: &str, agent_role: &str, agent_id: &str, provider: &str, text: &str) -> Self {
         // Remove ANSI escape sequences from text
         let clean_text = super::ndjson::remove_ansi_escape_sequences(text);
-        
-        Self {
-            ts: chrono::Utc::now().to_rfc3339(),
-            level: "info".to_string(),
-            project_id: project_id.to_string(),
-            agent_role: agent_role.to_string(),
-            agent_id: agent_id.to_string(),
-            provider: provider.to_string(),
-            event: "stdout_line".to_string(),
-            text: Some(clean_text),
-            dur_ms: None,
-            broadcast_id: None,
-            session_id: None,
-            message_id: None,
-        }
+        Self::builder(project_id, agent_role, provider, "stdout_line")
+            .agent_id(agent_id)
+            .direction("agent")
+            .text(Some(&clean_text))
+            .build()
     }
 
     pub fn new_end(project_id: &str, agent_role: &str, agent_id: &str, provider: &str, dur_ms: u64, status: &str) -> Self {
-        Self {
-            ts: chrono::Utc::now().to_rfc3339(),
-            level: "info".to_string(),
-            project_id: project_id.to_string(),
-            agent_role: agent_role.to_string(),
-            agent_id: agent_id.to_string(),
-            provider: provider.to_string(),
-            event: "end".to_string(),
-            text: Some(status.to_string()),
-            dur_ms: Some(dur_ms),
-            broadcast_id: None,
-            session_id: None,
-            message_id: None,
-        }
+        Self::builder(project_id, agent_role, provider, "end")
+            .agent_id(agent_id)
+            .text(Some(status))
+            .dur_ms(Some(dur_ms))
+            .build()
     }
 
     pub fn new_metrics(
-        project_id: &str, 
-        agent_role: &str, 
-        agent_id: &str, 
+        project_id: &str,
+        agent_role: &str,
+        agent_id: &str,
         provider: &str,
         event_type: &str,
         dur_ms: u64,
@@ -84,24 +124,14 @@ impl NdjsonEvent {
         details: Option<&str>
     ) -> Self {
         let text = match details {
-            Some(d) => Some(format!("{}: {}", event_type, d)),
-            None => Some(event_type.to_string()),
+            Some(d) => format!("{}: {}", event_type, d),
+            None => event_type.to_string(),
         };
-        
-        Self {
-            ts: chrono::Utc::now().to_rfc3339(),
-            level: "info".to_string(),
-            project_id: project_id.to_string(),
-            agent_role: agent_role.to_string(),
-            agent_id: agent_id.to_string(),
-            provider: provider.to_string(),
-            event: "metrics".to_string(),
-            text,
-            dur_ms: Some(dur_ms),
-            broadcast_id: None,
-            session_id: None,
-            message_id: None,
-        }
+        Self::builder(project_id, agent_role, provider, "metrics")
+            .agent_id(agent_id)
+            .text(Some(&text))
+            .dur_ms(Some(dur_ms))
+            .build()
     }
 
     /// Create a categorized failure metrics event
@@ -115,63 +145,35 @@ impl NdjsonEvent {
         dur_ms: u64,
         error_details: &str
     ) -> Self {
-        let text = Some(format!("{}: {} - {}", failure_category, failure_type, error_details));
-        
-        Self {
-            ts: chrono::Utc::now().to_rfc3339(),
-            level: "error".to_string(),
-            project_id: project_id.to_string(),
-            agent_role: agent_role.to_string(),
-            agent_id: agent_id.to_string(),
-            provider: provider.to_string(),
-            event: "metrics".to_string(),
-            text,
-            dur_ms: Some(dur_ms),
-            broadcast_id: None,
-            session_id: None,
-            message_id: None,
-        }
+        let text = format!("{}: {} - {}", failure_category, failure_type, error_details);
+        Self::builder(project_id, agent_role, provider, "metrics")
+            .level("error")
+            .agent_id(agent_id)
+            .text(Some(&text))
+            .dur_ms(Some(dur_ms))
+            .build()
     }
 
     /// Create a start event with broadcast_id for M5 preparation
     pub fn new_start_with_broadcast(
-        project_id: &str, 
-        agent_role: &str, 
-        agent_id: &str, 
+        project_id: &str,
+        agent_role: &str,
+        agent_id: &str,
         provider: &str,
         broadcast_id: Option<&str>
     ) -> Self {
-        Self {
-            ts: chrono::Utc::now().to_rfc3339(),
-            level: "info".to_string(),
-            project_id: project_id.to_string(),
-            agent_role: agent_role.to_string(),
-            agent_id: agent_id.to_string(),
-            provider: provider.to_string(),
-            event: "start".to_string(),
-            text: None,
-            dur_ms: None,
-            broadcast_id: broadcast_id.map(|s| s.to_string()),
-            session_id: None,
-            message_id: None,
-        }
+        Self::builder(project_id, agent_role, provider, "start")
+            .agent_id(agent_id)
+            .broadcast_id(broadcast_id)
+            .build()
     }
 
     pub fn new_routed(project_id: &str, agent_role: &str, agent_id: &str, provider: &str, broadcast_id: Option<String>, message_id: Option<String>) -> Self {
-        Self {
-            ts: chrono::Utc::now().to_rfc3339(),
-            level: "info".to_string(),
-            project_id: project_id.to_string(),
-            agent_role: agent_role.to_string(),
-            agent_id: agent_id.to_string(),
-            provider: provider.to_string(),
-            event: "routed".to_string(),
-            text: None,
-            dur_ms: None,
-            broadcast_id,
-            session_id: None,
-            message_id,
-        }
+        Self::builder(project_id, agent_role, provider, "routed")
+            .agent_id(agent_id)
+            .broadcast_id(broadcast_id.as_deref())
+            .message_id(message_id.as_deref())
+            .build()
     }
 }
 
@@ -2,6 +2,10 @@
 
 pub mod ndjson;
 pub mod events;
+pub mod writer;
+pub mod redaction;
 
 pub use ndjson::*;
 pub use events::*;
+pub use writer::*;
+pub use redaction::*;
@@ -1,38 +1,108 @@
 //! NDJSON logging utilities
 
-use std::fs;
-use std::io::Write;
-use db::now_iso8601_utc;
-use super::events::NdjsonEvent;
+use std::sync::RwLock;
+use super::events::{Event, NdjsonEvent};
+use super::writer::{append_line, has_open_writer, is_durable_event};
+use super::redaction::redact;
+
+/// Base directory for NDJSON logs when neither `--log-dir` nor `MULTI_AGENTS_LOG_DIR` is set
+pub const DEFAULT_LOG_BASE: &str = "./logs";
+
+static LOG_BASE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Pick the logs base: `--log-dir` flag, then `MULTI_AGENTS_LOG_DIR`, then `./logs` (empty values are ignored)
+pub fn resolve_log_base(flag: Option<&str>, env: Option<&str>) -> String {
+    flag.filter(|s| !s.is_empty())
+        .or(env.filter(|s| !s.is_empty()))
+        .unwrap_or(DEFAULT_LOG_BASE)
+        .to_string()
+}
+
+/// Apply `--log-dir` for the rest of the process
+pub fn set_log_base(flag: Option<&str>) {
+    let base = resolve_log_base(flag, std::env::var("MULTI_AGENTS_LOG_DIR").ok().as_deref());
+    if let Ok(mut guard) = LOG_BASE.write() { *guard = Some(base); }
+}
+
+/// Current logs base (resolved from the environment until `set_log_base` is called)
+pub fn log_base() -> String {
+    match LOG_BASE.read().ok().and_then(|g| g.clone()) {
+        Some(base) => base,
+        None => resolve_log_base(None, std::env::var("MULTI_AGENTS_LOG_DIR").ok().as_deref()),
+    }
+}
+
+/// NDJSON file `{base}/{project}/{name}.ndjson`, tolerating a trailing slash on `base`. `name` is
+/// the agent name; role-level events with no agent (and logs written before per-agent files) use the role.
+pub fn log_path(base: &str, project: &str, name: &str) -> String {
+    format!("{}/{}.ndjson", project_log_dir(base, project), name)
+}
+
+/// `{base}/{project}`, the directory holding a project's NDJSON files
+pub fn project_log_dir(base: &str, project: &str) -> String {
+    let base = match base.trim_end_matches('/') {
+        "" if base.starts_with('/') => "", // filesystem root
+        "" => ".",
+        trimmed => trimmed,
+    };
+    format!("{}/{}", base, project)
+}
+
+/// File an agent's events are written to: `{agent}.ndjson`, or `{role}.ndjson` when no agent is known
+pub fn agent_log_path(base: &str, project: &str, agent: &str, role: &str) -> String {
+    log_path(base, project, if agent.is_empty() { role } else { agent })
+}
+
+/// File to read an agent's events from: its own file, else the `{role}.ndjson` it shared with its
+/// role before per-agent files (until `logs migrate` splits it)
+pub fn agent_log_path_for_read(base: &str, project: &str, agent: &str, role: &str) -> String {
+    let own = agent_log_path(base, project, agent, role);
+    let legacy = log_path(base, project, role);
+    if !std::path::Path::new(&own).exists() && std::path::Path::new(&legacy).exists() {
+        legacy
+    } else {
+        own
+    }
+}
+
+/// Every `*.ndjson` file of a project, sorted by path; empty when the directory does not exist
+pub fn project_log_files(base: &str, project: &str) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(project_log_dir(base, project)) else { return Vec::new() };
+    let mut files: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|x| x == "ndjson"))
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    files.sort();
+    files
+}
 
 /// Write NDJSON event to log file with enhanced error handling
 pub fn write_ndjson_event(log_file: &str, event: &NdjsonEvent) -> Result<(), Box<dyn std::error::Error>> {
-    // Ensure directory exists with permission check
-    if let Some(parent) = std::path::Path::new(log_file).parent() {
-        match std::fs::create_dir_all(parent) {
-            Ok(_) => {},
-            Err(e) => {
-                return Err(format!("Failed to create log directory '{}': {}. Try using --logs-dir option or --no-logs to disable logging", parent.display(), e).into());
+    // Directory and permission checks only run when the file is not already open for buffering
+    if !has_open_writer(log_file) {
+        if let Some(parent) = std::path::Path::new(log_file).parent() {
+            match std::fs::create_dir_all(parent) {
+                Ok(_) => {},
+                Err(e) => {
+                    return Err(format!("Failed to create log directory '{}': {}. Try using --logs-dir option or --no-logs to disable logging", parent.display(), e).into());
+                }
             }
         }
-    }
-    
-    // Test write permissions before attempting to write
-    let test_file = format!("{}.test", log_file);
-    if let Err(e) = std::fs::write(&test_file, "test") {
+
+        // Test write permissions before attempting to write
+        let test_file = format!("{}.test", log_file);
+        if let Err(e) = std::fs::write(&test_file, "test") {
+            let _ = std::fs::remove_file(&test_file);
+            return Err(format!("No write permission to log directory '{}': {}. Try using --logs-dir option or --no-logs to disable logging", 
+                              std::path::Path::new(log_file).parent().unwrap_or(std::path::Path::new(".")).display(), e).into());
+        }
         let _ = std::fs::remove_file(&test_file);
-        return Err(format!("No write permission to log directory '{}': {}. Try using --logs-dir option or --no-logs to disable logging", 
-                          std::path::Path::new(log_file).parent().unwrap_or(std::path::Path::new(".")).display(), e).into());
     }
-    let _ = std::fs::remove_file(&test_file);
-    
+
     // Write event as single line JSON
-    let mut file = fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(log_file)?;
-    
-    writeln!(file, "{}", serde_json::to_string(event)?)?;
+    append_line(log_file, &redacted_line(event), is_durable_event(&event.event))?;
     Ok(())
 }
 
@@ -46,45 +116,53 @@ pub fn log_ndjson(
     event: &str, 
     text: Option<&str>, 
     exit_code: Option<i32>, 
-    ts_opt: Option<&str>
+    ts_opt: Option<&str>,
+    agent_name: &str,
 ) {
-    let ts = ts_opt.map(|s| s.to_string()).unwrap_or_else(|| now_iso8601_utc());
-    let obj = serde_json::json!({
-        "ts": ts,
-        "project_id": project,
-        "agent_role": agent_role,
-        "provider": provider,
-        "session_id": session_id.unwrap_or("") ,
-        "direction": direction,
-        "event": event,
-        "text": text,
-        "exit_code": exit_code,
-    });
-    let dir = format!("./logs/{project}");
-    let _ = fs::create_dir_all(&dir);
-    let path = format!("{}/{}.ndjson", dir, agent_role);
-    if let Ok(mut f) = std::fs::OpenOptions::new().create(true).append(true).open(path) {
-        let _ = writeln!(&mut f, "{}", obj);
+    let mut builder = Event::builder(project, agent_role, provider, event)
+        .agent_id(agent_name)
+        .session_id(session_id)
+        .direction(direction)
+        .text(text)
+        .exit_code(exit_code);
+    if let Some(ts) = ts_opt {
+        builder = builder.ts(ts);
     }
+    let _ = log_event_in(&log_base(), &builder.build());
+}
+
+/// Append `event` to its agent's file under `base` (`{base}/{project_id}/{agent_id}.ndjson`, the role file without an agent)
+pub fn log_event_in(base: &str, event: &Event) -> std::io::Result<()> {
+    let path = agent_log_path(base, &event.project_id, &event.agent_id, &event.agent_role);
+    append_line(&path, &redacted_line(event), is_durable_event(&event.event))
 }
 
-/// Emit NDJSON start event for agent (contract compliant)
-pub fn emit_start_event(project_name: &str, role: &str, agent_name: &str, provider: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let log_file = format!("./logs/{}/{}.ndjson", project_name, role);
-    let event = NdjsonEvent::new_start(project_name, role, agent_name, provider);
+/// `event` as a JSON line with the redaction patterns applied to its text
+pub fn redacted_line(event: &Event) -> String {
+    match event.text.as_deref().map(redact) {
+        Some(std::borrow::Cow::Owned(text)) => Event { text: Some(text), ..event.clone() }.to_line(),
+        _ => event.to_line(),
+    }
+}
+
+/// Emit NDJSON start event for agent (contract compliant); `text` describes what was launched
+pub fn emit_start_event(project_name: &str, role: &str, agent_name: &str, provider: &str, text: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let log_file = agent_log_path(&log_base(), project_name, agent_name, role);
+    let mut event = NdjsonEvent::new_start(project_name, role, agent_name, provider);
+    event.text = text.map(str::to_string);
     write_ndjson_event(&log_file, &event)
 }
 
 /// Emit NDJSON end event for agent (contract compliant)
 pub fn emit_end_event(project_name: &str, role: &str, agent_name: &str, provider: &str, status: &str, duration_ms: u64) -> Result<(), Box<dyn std::error::Error>> {
-    let log_file = format!("./logs/{}/{}.ndjson", project_name, role);
+    let log_file = agent_log_path(&log_base(), project_name, agent_name, role);
     let event = NdjsonEvent::new_end(project_name, role, agent_name, provider, duration_ms, status);
     write_ndjson_event(&log_file, &event)
 }
 
 /// Emit NDJSON stdout_line event for agent (contract compliant)
 pub fn emit_stdout_line_event(project_name: &str, role: &str, agent_name: &str, provider: &str, text: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let log_file = format!("./logs/{}/{}.ndjson", project_name, role);
+    let log_file = agent_log_path(&log_base(), project_name, agent_name, role);
     let event = NdjsonEvent::new_stdout_line(project_name, role, agent_name, provider, text);
     write_ndjson_event(&log_file, &event)
 }
@@ -98,7 +176,7 @@ pub fn emit_routed_event(
     broadcast_id: Option<&str>,
     message_id: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let log_file = format!("./logs/{}/{}.ndjson", project_name, role);
+    let log_file = agent_log_path(&log_base(), project_name, agent_name, role);
     let event = super::events::NdjsonEvent::new_routed(
         project_name,
         role,
@@ -163,7 +241,7 @@ pub fn emit_metrics_event(
     status: &str,
     details: Option<&str>
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let log_file = format!("./logs/{}/{}.ndjson", project_name, role);
+    let log_file = agent_log_path(&log_base(), project_name, agent_name, role);
     let event = NdjsonEvent::new_metrics(project_name, role, agent_name, provider, event_type, duration_ms, status, details);
     write_ndjson_event(&log_file, &event)
 }
@@ -179,7 +257,7 @@ pub fn emit_failure_metrics_event(
     duration_ms: u64,
     error_details: &str
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let log_file = format!("./logs/{}/{}.ndjson", project_name, role);
+    let log_file = agent_log_path(&log_base(), project_name, agent_name, role);
     let event = NdjsonEvent::new_failure_metrics(project_name, role, agent_name, provider, failure_category, failure_type, duration_ms, error_details);
     write_ndjson_event(&log_file, &event)
 }
@@ -227,6 +305,10 @@ pub fn ndjson_self_check(path: &str) -> Result<serde_json::Value, String> {
                 errors.push(serde_json::json!({"line": line_idx, "error": "missing_field", "field": k}));
             }
         }
+        // Known fields must also have the right type
+        if let Err(e) = NdjsonEvent::from_line(&line) {
+            errors.push(serde_json::json!({"line": line_idx, "error": "invalid_field", "detail": e.to_string()}));
+        }
         if errors.last().map(|e| e["line"].as_u64().unwrap_or(0) == line_idx as u64).unwrap_or(false) {
             // had errors for this line
         } else {
@@ -0,0 +1,111 @@
+//! Redaction of secrets in NDJSON log text
+//!
+//! Patterns come from project.yaml `defaults.redaction` and are compiled once per process by
+//! `set_redaction`; every writer in this module tree runs the `text` field through them before
+//! the line is written. `--no-redaction` turns the filter off for debugging.
+
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use regex::Regex;
+
+/// Replacement for every redacted match
+pub const REDACTED: &str = "[REDACTED]";
+
+static REDACTOR: RwLock<Option<Arc<Redactor>>> = RwLock::new(None);
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Compiled redaction patterns
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    patterns: Vec<String>,
+    regexes: Vec<Regex>,
+}
+
+impl Redactor {
+    /// Compile `patterns`; an invalid one is reported by its index in `defaults.redaction`
+    pub fn new(patterns: &[String]) -> Result<Self, String> {
+        let regexes = config_model::compile_redaction(patterns)?;
+        Ok(Self { patterns: patterns.to_vec(), regexes })
+    }
+
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// Replace every match with `[REDACTED]`. Matches of different patterns that overlap or touch
+    /// are merged first, so a secret caught by two patterns never leaks the part only one covers.
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut ranges: Vec<(usize, usize)> = self.regexes.iter()
+            .flat_map(|re| re.find_iter(text).filter(|m| !m.is_empty()).map(|m| (m.start(), m.end())))
+            .collect();
+        if ranges.is_empty() {
+            return Cow::Borrowed(text);
+        }
+        ranges.sort_unstable();
+        let mut out = String::with_capacity(text.len());
+        let mut pos = 0;
+        let mut current = ranges[0];
+        for &(start, end) in &ranges[1..] {
+            if start <= current.1 {
+                current.1 = current.1.max(end);
+            } else {
+                out.push_str(&text[pos..current.0]);
+                out.push_str(REDACTED);
+                pos = current.1;
+                current = (start, end);
+            }
+        }
+        out.push_str(&text[pos..current.0]);
+        out.push_str(REDACTED);
+        out.push_str(&text[current.1..]);
+        Cow::Owned(out)
+    }
+}
+
+/// Install `patterns` for the rest of the process. An empty list keeps the current filter, and the
+/// same list is only compiled once.
+pub fn set_redaction(patterns: &[String]) -> Result<(), String> {
+    if patterns.is_empty() {
+        return Ok(());
+    }
+    if current_redactor().is_some_and(|r| r.patterns() == patterns) {
+        return Ok(());
+    }
+    let redactor = Arc::new(Redactor::new(patterns)?);
+    if let Ok(mut guard) = REDACTOR.write() { *guard = Some(redactor); }
+    Ok(())
+}
+
+/// Remove the installed patterns: log text is written as is until `set_redaction` runs again
+pub fn clear_redaction() {
+    if let Ok(mut guard) = REDACTOR.write() { *guard = None; }
+}
+
+/// `--no-redaction`: write log text as is, whatever `set_redaction` installs
+pub fn disable_redaction() {
+    DISABLED.store(true, Ordering::SeqCst);
+}
+
+fn current_redactor() -> Option<Arc<Redactor>> {
+    REDACTOR.read().ok().and_then(|g| g.clone())
+}
+
+/// Patterns in effect, empty when none are set or redaction is disabled
+pub fn redaction_patterns() -> Vec<String> {
+    if DISABLED.load(Ordering::SeqCst) {
+        return Vec::new();
+    }
+    current_redactor().map(|r| r.patterns().to_vec()).unwrap_or_default()
+}
+
+/// `text` with the process-wide patterns applied
+pub fn redact(text: &str) -> Cow<'_, str> {
+    if DISABLED.load(Ordering::SeqCst) {
+        return Cow::Borrowed(text);
+    }
+    match current_redactor() {
+        Some(redactor) => redactor.redact(text),
+        None => Cow::Borrowed(text),
+    }
+}
@@ -0,0 +1,128 @@
+//! Buffered NDJSON appenders, one per log file
+//!
+//! High-volume line events (`stdout_line`, `stderr_line`, `tool_call`, `tool_result`) are
+//! buffered; every other event flushes and fsyncs the file, so a crash can only lose the lines
+//! written since the last lifecycle event. With `MULTI_AGENTS_LOG_SYNC=1` every line is flushed
+//! and fsynced as it is written. Handles stay open until `flush_all` runs at exit, or until
+//! `close_path` is called before a file is replaced on disk.
+//!
+//! Each file has its own handle behind a `Mutex`; a line is written with a single `write_all`
+//! while that lock is held, so concurrent writers never interleave within a line.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Environment variable turning on per-line `flush` + `fsync`
+pub const LOG_SYNC_ENV: &str = "MULTI_AGENTS_LOG_SYNC";
+
+/// Buffer size per log file; a full buffer is written out before the next line
+pub const FLUSH_BYTES: usize = 64 * 1024;
+
+/// Buffered lines older than this are written out on the next append to the same file
+pub const FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+
+struct LogWriter {
+    out: BufWriter<File>,
+    last_flush: Instant,
+}
+
+type SharedWriter = Arc<Mutex<LogWriter>>;
+
+fn writers() -> MutexGuard<'static, HashMap<String, SharedWriter>> {
+    static WRITERS: OnceLock<Mutex<HashMap<String, SharedWriter>>> = OnceLock::new();
+    WRITERS.get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn lock_writer(writer: &SharedWriter) -> MutexGuard<'_, LogWriter> {
+    writer.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Open (or reuse) the handle for `path`
+fn open_writer(path: &str) -> io::Result<SharedWriter> {
+    let mut map = writers();
+    if let Some(writer) = map.get(path) {
+        return Ok(writer.clone());
+    }
+    if let Some(dir) = Path::new(path).parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let writer = Arc::new(Mutex::new(LogWriter { out: BufWriter::with_capacity(FLUSH_BYTES, file), last_flush: Instant::now() }));
+    map.insert(path.to_string(), writer.clone());
+    Ok(writer)
+}
+
+/// Whether a `MULTI_AGENTS_LOG_SYNC` value turns sync mode on (`1` or `true`)
+pub fn log_sync_from_env(value: Option<&str>) -> bool {
+    matches!(value.map(|v| v.trim().to_ascii_lowercase()).as_deref(), Some("1" | "true"))
+}
+
+/// Whether every log line is fsynced (`MULTI_AGENTS_LOG_SYNC`)
+pub fn log_sync_enabled() -> bool {
+    log_sync_from_env(std::env::var(LOG_SYNC_ENV).ok().as_deref())
+}
+
+/// Whether `event` is flushed as soon as it is written (`end`, `timeout`, `cancelled`, `start`, ...)
+pub fn is_durable_event(event: &str) -> bool {
+    !matches!(event, "stdout_line" | "stderr_line" | "tool_call" | "tool_result")
+}
+
+/// Whether a buffered writer is currently open for `path`
+pub fn has_open_writer(path: &str) -> bool {
+    writers().contains_key(path)
+}
+
+/// Append one line to `path`; `durable` lines are flushed and fsynced (with everything buffered before them) before returning
+pub fn append_line(path: &str, line: &str, durable: bool) -> io::Result<()> {
+    append_line_with_sync(path, line, durable, log_sync_enabled())
+}
+
+/// `append_line` with sync mode given explicitly: with `sync`, the line is flushed and fsynced before returning
+pub fn append_line_with_sync(path: &str, line: &str, durable: bool, sync: bool) -> io::Result<()> {
+    let shared = open_writer(path)?;
+    let mut writer = lock_writer(&shared);
+    let mut buf = String::with_capacity(line.len() + 1);
+    buf.push_str(line);
+    buf.push('\n');
+    writer.out.write_all(buf.as_bytes())?;
+    if sync || durable || writer.last_flush.elapsed() >= FLUSH_INTERVAL {
+        writer.out.flush()?;
+        writer.last_flush = Instant::now();
+    }
+    if sync || durable {
+        writer.out.get_ref().sync_all()?;
+    }
+    Ok(())
+}
+
+/// Flush what is buffered for `path`, keeping the file open
+pub fn flush_path(path: &str) -> io::Result<()> {
+    let Some(shared) = writers().get(path).cloned() else { return Ok(()) };
+    let mut writer = lock_writer(&shared);
+    writer.out.flush()?;
+    writer.last_flush = Instant::now();
+    Ok(())
+}
+
+/// Flush and close the handle for `path`; call before renaming or deleting the file, so the next
+/// write opens whatever is at `path` instead of appending to the replaced file. A handle another
+/// thread still holds is flushed when its last user drops it.
+pub fn close_path(path: &str) -> io::Result<()> {
+    let Some(shared) = writers().remove(path) else { return Ok(()) };
+    let mut writer = lock_writer(&shared);
+    writer.out.flush()
+}
+
+/// Flush and close every buffered log file; call before the process exits
+pub fn flush_all() {
+    let drained: Vec<SharedWriter> = writers().drain().map(|(_, w)| w).collect();
+    for writer in drained {
+        let _ = lock_writer(&writer).out.flush();
+    }
+}
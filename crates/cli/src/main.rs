@@ -1,10 +1,22 @@
 //! Multi-Agents CLI - Main entry point
 
+use std::process::ExitCode;
 use clap::Parser;
 use multi_agents_cli::Cli;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::fmt().with_env_filter("info").init();
+fn main() -> ExitCode {
     let cli = Cli::parse();
-    cli.execute()
-}
\ No newline at end of file
+    tracing_subscriber::fmt()
+        .with_env_filter(cli.log_filter())
+        .with_writer(std::io::stderr)
+        .init();
+    let result = cli.execute();
+    multi_agents_cli::logging::flush_all();
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(&e)
+        }
+    }
+}
@@ -1,5 +1,9 @@
 //! Provider management module
 
 pub mod manager;
+pub mod output;
+pub mod placeholders;
 
 pub use manager::*;
+pub use output::*;
+pub use placeholders::*;
@@ -0,0 +1,190 @@
+//! Provider output normalisation
+//!
+//! Every provider frames its stdout differently (claude json envelopes, cursor stream-json,
+//! gemini markdown, ANSI-coloured text). This module turns a raw chunk of output into plain
+//! text plus the metadata the CLI cares about, so callers never parse provider output inline.
+
+use crate::logging::ndjson::remove_ansi_escape_sequences;
+
+/// How a provider's stdout is framed for one invocation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Plain (possibly ANSI-coloured) text
+    Text,
+    /// One JSON envelope (`--output-format json`)
+    Json,
+    /// One JSON event per line (`--output-format stream-json`)
+    StreamJson,
+}
+
+impl OutputFormat {
+    /// Output format produced by a provider of `family` (its `alias_root`) when invoked with `args`
+    pub fn for_invocation(family: &str, args: &[String]) -> Self {
+        let requested = args.windows(2)
+            .find(|w| w[0] == "--output-format")
+            .map(|w| w[1].as_str());
+        match requested {
+            Some("stream-json") => OutputFormat::StreamJson,
+            Some("json") => OutputFormat::Json,
+            _ if family.starts_with("cursor") => OutputFormat::StreamJson,
+            _ => OutputFormat::Text,
+        }
+    }
+}
+
+/// Provider output reduced to what the CLI prints and records
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NormalizedOutput {
+    /// Displayable text, empty when the chunk carries nothing to print
+    pub text: String,
+    /// Session id reported by the provider
+    pub session_id: Option<String>,
+    /// Total tokens reported by the provider (input + output)
+    pub token_count: Option<u64>,
+    /// Tool activity to log rather than print: `(event, text)` with event `tool_call` or `tool_result`
+    pub tool_event: Option<(&'static str, String)>,
+    /// Set on the terminal `result` event of a stream
+    pub final_result: bool,
+}
+
+/// Normalise one chunk of output of a provider of `family` (its `alias_root`): a stream-json line,
+/// a json envelope or plain text
+pub fn normalize_provider_output(family: &str, raw: &str, format: OutputFormat) -> NormalizedOutput {
+    match format {
+        OutputFormat::StreamJson => normalize_stream_json(raw),
+        OutputFormat::Json => match serde_json::from_str::<serde_json::Value>(raw) {
+            Ok(v) => NormalizedOutput {
+                text: extract_claude_text(&v).unwrap_or_default(),
+                session_id: extract_claude_session_id(&v),
+                token_count: extract_token_count(&v),
+                ..Default::default()
+            },
+            // Banners or warnings printed around the envelope
+            Err(_) => normalize_text(family, raw),
+        },
+        OutputFormat::Text => normalize_text(family, raw),
+    }
+}
+
+fn normalize_text(family: &str, raw: &str) -> NormalizedOutput {
+    let clean = remove_ansi_escape_sequences(raw);
+    let text = if family.starts_with("gemini") {
+        clean.lines().map(strip_markdown_header).collect::<Vec<_>>().join("\n")
+    } else {
+        clean
+    };
+    NormalizedOutput { text, ..Default::default() }
+}
+
+/// `## Title` -> `Title`; other lines are returned unchanged
+fn strip_markdown_header(line: &str) -> &str {
+    let trimmed = line.trim_start();
+    let hashes = trimmed.len() - trimmed.trim_start_matches('#').len();
+    match trimmed[hashes..].strip_prefix(' ') {
+        Some(rest) if (1..=6).contains(&hashes) => rest,
+        _ => line,
+    }
+}
+
+fn normalize_stream_json(line: &str) -> NormalizedOutput {
+    let v = serde_json::from_str::<serde_json::Value>(line).ok();
+    let mut out = NormalizedOutput {
+        session_id: v.as_ref().and_then(extract_claude_session_id),
+        token_count: v.as_ref().and_then(extract_token_count),
+        ..Default::default()
+    };
+    match parse_cursor_stream_line(line) {
+        CursorStreamEvent::Text { text, final_result } => {
+            out.text = text;
+            out.final_result = final_result;
+        }
+        CursorStreamEvent::ToolCall(text) => out.tool_event = Some(("tool_call", text)),
+        CursorStreamEvent::ToolResult(text) => out.tool_event = Some(("tool_result", text)),
+        CursorStreamEvent::Ignored => {}
+    }
+    out
+}
+
+/// What a single cursor `stream-json` line means for the console and the NDJSON log
+#[derive(Debug, PartialEq)]
+pub enum CursorStreamEvent {
+    /// Text to print; `final_result` marks the terminal `result` event
+    Text { text: String, final_result: bool },
+    /// Tool invocation serialised as `{"tool": name, "input": {...}}`
+    ToolCall(String),
+    /// Tool output content
+    ToolResult(String),
+    /// Nothing to print or log (system/user events, non-JSON lines)
+    Ignored,
+}
+
+/// Parse one cursor stream-json line according to the official spec, with a fallback to legacy flat fields
+pub fn parse_cursor_stream_line(line: &str) -> CursorStreamEvent {
+    let v = match serde_json::from_str::<serde_json::Value>(line) {
+        Ok(v) => v,
+        Err(_) => return CursorStreamEvent::Ignored,
+    };
+    let text = |s: &str| CursorStreamEvent::Text { text: s.to_string(), final_result: false };
+    match v.get("type").and_then(|t| t.as_str()) {
+        // Extract text from assistant.message.content[].text
+        Some("assistant") => v.get("message")
+            .and_then(|m| m.get("content"))
+            .and_then(|c| c.as_array())
+            .and_then(|items| items.iter().find(|i| i.get("type").and_then(|t| t.as_str()) == Some("text")))
+            .and_then(|i| i.get("text"))
+            .and_then(|t| t.as_str())
+            .map(text)
+            .unwrap_or(CursorStreamEvent::Ignored),
+        // Final result event - extract complete text
+        Some("result") => match v.get("result").and_then(|r| r.as_str()) {
+            Some(r) => CursorStreamEvent::Text { text: r.to_string(), final_result: true },
+            None => CursorStreamEvent::Ignored,
+        },
+        Some("tool_use") => {
+            let name = v.get("name").and_then(|n| n.as_str()).unwrap_or("");
+            let input = v.get("input").cloned().unwrap_or(serde_json::Value::Null);
+            CursorStreamEvent::ToolCall(serde_json::json!({"tool": name, "input": input}).to_string())
+        }
+        Some("tool_result") => match v.get("content") {
+            Some(serde_json::Value::String(c)) => CursorStreamEvent::ToolResult(c.clone()),
+            Some(c) => CursorStreamEvent::ToolResult(c.to_string()),
+            None => CursorStreamEvent::ToolResult(String::new()),
+        },
+        // system, user, tool_call events - skip
+        Some(_) => CursorStreamEvent::Ignored,
+        // Fallback: try legacy flat fields for compatibility
+        None => ["text", "content", "message", "delta", "data"].iter()
+            .find_map(|k| v.get(*k).and_then(|x| x.as_str()))
+            .map(text)
+            .unwrap_or(CursorStreamEvent::Ignored),
+    }
+}
+
+/// Displayable text of a claude `--output-format json` envelope: `content[0].text`, else the flat `result` string
+pub fn extract_claude_text(json: &serde_json::Value) -> Option<String> {
+    json.get("content")
+        .and_then(|c| c.get(0))
+        .and_then(|item| item.get("text"))
+        .or_else(|| json.get("result"))
+        .and_then(|t| t.as_str())
+        .map(|t| t.to_string())
+}
+
+/// Provider session id carried by a claude json envelope
+pub fn extract_claude_session_id(json: &serde_json::Value) -> Option<String> {
+    json.get("session_id")
+        .and_then(|s| s.as_str())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+}
+
+/// Total tokens from a `usage` object (`input_tokens` + `output_tokens`)
+fn extract_token_count(json: &serde_json::Value) -> Option<u64> {
+    let usage = json.get("usage")?;
+    let input = usage.get("input_tokens").and_then(|n| n.as_u64());
+    let output = usage.get("output_tokens").and_then(|n| n.as_u64());
+    match (input, output) {
+        (None, None) => None,
+        (i, o) => Some(i.unwrap_or(0) + o.unwrap_or(0)),
+    }
+}
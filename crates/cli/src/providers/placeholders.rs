@@ -0,0 +1,75 @@
+//! Agent identity placeholders (`{agent_name}`, `{agent_role}`, `{project}`, `{model}`)
+//!
+//! Resolved when an agent is run, not when the config is parsed, in the agent's system prompt and
+//! in the provider's `oneshot_args`/`repl_args`. Both the one-shot (`send`) and REPL (`agent run`)
+//! paths go through `apply_agent_identity`, before the per-invocation placeholders (`{prompt}`,
+//! `{session_id}`, ...) are filled in.
+
+use config_model::ProviderTemplate;
+
+/// Placeholders filled in per invocation by the send / agent run paths
+const INVOCATION_PLACEHOLDERS: &[&str] = &["prompt", "prompt_file", "system_prompt", "allowed_tools", "session_id", "chat_id"];
+
+/// Who an agent is, for identity placeholders
+#[derive(Debug, Clone, Copy)]
+pub struct AgentIdentity<'a> {
+    pub project: &'a str,
+    pub agent_name: &'a str,
+    pub agent_role: &'a str,
+    /// Model the agent runs with (`agent run --model` when given)
+    pub model: &'a str,
+}
+
+/// Replace the identity placeholders in `text`. Other `{...}` text is left as is (JSON snippets,
+/// literal braces); with `strict`, a `{word}` that is not a known placeholder is an error.
+pub fn substitute_identity(text: &str, identity: &AgentIdentity, strict: bool) -> Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let name_len = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+        let name = &after[..name_len];
+        if name.is_empty() || !after[name_len..].starts_with('}') {
+            out.push('{');
+            rest = after;
+            continue;
+        }
+        match name {
+            "agent_name" => out.push_str(identity.agent_name),
+            "agent_role" => out.push_str(identity.agent_role),
+            "project" => out.push_str(identity.project),
+            "model" => out.push_str(identity.model),
+            _ if strict && !INVOCATION_PLACEHOLDERS.contains(&name) => {
+                return Err(format!("unknown placeholder {{{}}}", name));
+            }
+            _ => {
+                out.push('{');
+                out.push_str(name);
+                out.push('}');
+            }
+        }
+        rest = &after[name_len + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// `tpl` with identity placeholders resolved in its one-shot and REPL args, plus the resolved system prompt
+pub fn apply_agent_identity(
+    tpl: &ProviderTemplate,
+    system_prompt: &str,
+    identity: &AgentIdentity,
+    strict: bool,
+) -> Result<(ProviderTemplate, String), String> {
+    let resolve_args = |args: &[String], field: &str| -> Result<Vec<String>, String> {
+        args.iter()
+            .map(|a| substitute_identity(a, identity, strict).map_err(|e| format!("{}: {}", field, e)))
+            .collect()
+    };
+    let mut resolved = tpl.clone();
+    resolved.oneshot_args = resolve_args(&tpl.oneshot_args, "oneshot_args")?;
+    resolved.repl_args = resolve_args(&tpl.repl_args, "repl_args")?;
+    let system_prompt = substitute_identity(system_prompt, identity, strict).map_err(|e| format!("system_prompt: {}", e))?;
+    Ok((resolved, system_prompt))
+}
@@ -5,9 +5,19 @@
 use std::error::Error;
 use std::sync::{Arc, Mutex};
 use rusqlite::Connection;
-use db::Agent;
+use serde::Serialize;
+use db::{list_sessions, Agent, Session, SessionFilters};
 use super::Repository;
 
+/// An agent with its most recent sessions, as shown by `agent info`
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentInfo {
+    #[serde(flatten)]
+    pub agent: Agent,
+    /// Newest first, any status
+    pub sessions: Vec<Session>,
+}
+
 /// Repository for agent data operations
 pub struct AgentRepository {
     conn: Arc<Mutex<Connection>>,
@@ -88,6 +98,26 @@ impl AgentRepository {
             None => Ok(None),
         }
     }
+
+    /// Agent `name` within the project with its last `session_limit` sessions; `None` when there is no such agent
+    pub fn find_info(&self, project_id: &str, name: &str, session_limit: u32) -> Result<Option<AgentInfo>, Box<dyn Error>> {
+        let Some(agent) = self.find_by_name(project_id, name)? else { return Ok(None) };
+        let sessions = if session_limit == 0 {
+            Vec::new()
+        } else {
+            let conn = self.conn.lock().unwrap();
+            list_sessions(&conn, SessionFilters {
+                project_id: Some(project_id.to_string()),
+                agent_id: Some(agent.id.clone()),
+                provider: None,
+                status: None,
+                session_type: None,
+                limit: Some(session_limit),
+                offset: None,
+            })?
+        };
+        Ok(Some(AgentInfo { agent, sessions }))
+    }
 }
 
 impl Repository<Agent, String> for AgentRepository {
@@ -32,22 +32,19 @@ pub fn compute_routed_metrics(lines: Vec<String>) -> Result<RoutedMetrics, Box<d
         }
         
         // Parse JSON only for routed events
-        let v: serde_json::Value = serde_json::from_str(&line)?;
+        let event = crate::logging::events::NdjsonEvent::from_line(&line)?;
         
         total += 1;
         
-        // Extract role with optimized string handling
-        if let Some(role) = v.get("agent_role").and_then(|r| r.as_str()) {
-            *per_role.entry(role.to_string()).or_insert(0) += 1;
+        if !event.agent_role.is_empty() {
+            *per_role.entry(event.agent_role).or_insert(0) += 1;
         }
         
         // Extract broadcast_id and timestamp
-        if let Some(bid) = v.get("broadcast_id").and_then(|b| b.as_str()) {
-            if !bid.is_empty() {
-                broadcasts.insert(bid.to_string());
-                if let Some(ts) = v.get("ts").and_then(|t| t.as_str()) {
-                    per_broadcast_timestamps.entry(bid.to_string()).or_default().push(ts.to_string());
-                }
+        if let Some(bid) = event.broadcast_id.filter(|b| !b.is_empty()) {
+            broadcasts.insert(bid.clone());
+            if !event.ts.is_empty() {
+                per_broadcast_timestamps.entry(bid).or_default().push(event.ts);
             }
         }
     }
@@ -4,5 +4,6 @@ pub mod manager;
 pub mod subscription;
 pub mod metrics;
 pub mod debug;
+pub mod notify;
 
 
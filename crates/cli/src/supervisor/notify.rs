@@ -0,0 +1,195 @@
+//! Supervisor notifications: a supervisor agent subscribes to what other agents finish, and each
+//! matching event is recorded as a message in the supervisor's session
+
+use std::fmt;
+use std::str::FromStr;
+use rusqlite::Connection;
+use db::{DbError, SessionFilters, SessionType};
+
+/// `sender` of the messages recorded for supervisors
+pub const NOTIFICATION_SENDER: &str = "supervisor-notify";
+
+/// Task status that raises `task_done`
+pub const TASK_DONE_STATUS: &str = "done";
+
+/// Event kinds a supervisor can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NotifyEventKind {
+    SendCompleted,
+    SendFailed,
+    TaskDone,
+}
+
+impl NotifyEventKind {
+    pub const ALL: [NotifyEventKind; 3] = [NotifyEventKind::SendCompleted, NotifyEventKind::SendFailed, NotifyEventKind::TaskDone];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotifyEventKind::SendCompleted => "send_completed",
+            NotifyEventKind::SendFailed => "send_failed",
+            NotifyEventKind::TaskDone => "task_done",
+        }
+    }
+}
+
+impl fmt::Display for NotifyEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for NotifyEventKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NotifyEventKind::ALL.into_iter()
+            .find(|k| k.as_str() == s)
+            .ok_or_else(|| format!("unknown event kind '{}' (expected send_completed, send_failed or task_done)", s))
+    }
+}
+
+/// Agents a subscription listens to: `@all`, `@<role>` or an agent name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceFilter {
+    All,
+    Role(String),
+    Agent(String),
+}
+
+impl SourceFilter {
+    /// Whether an agent with this name and role is a source of the filter
+    pub fn matches(&self, agent_name: &str, agent_role: &str) -> bool {
+        match self {
+            SourceFilter::All => true,
+            SourceFilter::Role(role) => role == agent_role,
+            SourceFilter::Agent(name) => name == agent_name,
+        }
+    }
+}
+
+impl fmt::Display for SourceFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceFilter::All => f.write_str("@all"),
+            SourceFilter::Role(role) => write!(f, "@{}", role),
+            SourceFilter::Agent(name) => f.write_str(name),
+        }
+    }
+}
+
+impl FromStr for SourceFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.strip_prefix('@') {
+            Some("all") => Ok(SourceFilter::All),
+            Some("") => Err("empty role after '@'".to_string()),
+            Some(role) => Ok(SourceFilter::Role(role.to_string())),
+            None if s.is_empty() => Err("empty source filter".to_string()),
+            None => Ok(SourceFilter::Agent(s.to_string())),
+        }
+    }
+}
+
+/// Something an agent finished
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotifyEvent {
+    pub kind: NotifyEventKind,
+    pub agent_name: String,
+    pub agent_role: String,
+    /// Human-readable outcome, e.g. `exit code 5, broadcast <id>`
+    pub detail: String,
+}
+
+impl NotifyEvent {
+    /// Message content recorded for the supervisor
+    pub fn message(&self) -> String {
+        format!("[{}] {} (@{}): {}", self.kind, self.agent_name, self.agent_role, self.detail)
+    }
+}
+
+/// Whether a subscription of `supervisor` to `kind` from `filter` wants `event`. A supervisor is
+/// never notified about its own events, so dispatched notifications cannot loop.
+pub fn subscription_matches(kind: NotifyEventKind, filter: &SourceFilter, supervisor: &str, event: &NotifyEvent) -> bool {
+    kind == event.kind && event.agent_name != supervisor && filter.matches(&event.agent_name, &event.agent_role)
+}
+
+/// A notification recorded for a supervisor
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    /// Supervisor agent name
+    pub supervisor: String,
+    pub session_id: String,
+    pub message_id: String,
+    pub text: String,
+}
+
+/// Record one message per (supervisor, event) pair that some subscription of the project matches.
+/// The message goes to the supervisor's latest chat session; one without a session gets a new one.
+pub fn record_notifications(conn: &Connection, project_id: &str, events: &[NotifyEvent]) -> Result<Vec<Notification>, DbError> {
+    let subscriptions = db::list_subscriptions(conn, project_id)?;
+    if subscriptions.is_empty() || events.is_empty() {
+        return Ok(Vec::new());
+    }
+    let agents = db::list_agents(conn, project_id)?;
+    let mut notifications = Vec::new();
+    for event in events {
+        let mut notified: Vec<&str> = Vec::new();
+        for sub in &subscriptions {
+            // Rows are written through `supervisor subscribe`, which validates both fields
+            let (Ok(kind), Ok(filter)) = (sub.event_kind.parse(), sub.source_filter.parse::<SourceFilter>()) else { continue };
+            let Some(supervisor) = agents.iter().find(|a| a.id == sub.supervisor_agent_id) else { continue };
+            if notified.contains(&supervisor.id.as_str()) || !subscription_matches(kind, &filter, &supervisor.name, event) {
+                continue;
+            }
+            notified.push(&supervisor.id);
+            let session_id = supervisor_session(conn, project_id, supervisor)?;
+            let text = event.message();
+            let message_id = db::insert_message(conn, &session_id, NOTIFICATION_SENDER, &text, None)?;
+            notifications.push(Notification { supervisor: supervisor.name.clone(), session_id, message_id, text });
+        }
+    }
+    Ok(notifications)
+}
+
+fn supervisor_session(conn: &Connection, project_id: &str, supervisor: &db::Agent) -> Result<String, DbError> {
+    let latest = db::list_sessions(conn, SessionFilters {
+        project_id: Some(project_id.to_string()),
+        agent_id: Some(supervisor.id.clone()),
+        provider: None,
+        status: None,
+        session_type: Some(SessionType::Chat),
+        limit: Some(1),
+        offset: None,
+    })?;
+    match latest.into_iter().next() {
+        Some(session) => Ok(session.id),
+        None => Ok(db::insert_session(conn, project_id, &supervisor.id, &supervisor.provider, None)?.id),
+    }
+}
+
+/// Set a task's status; moving an assigned task to `done` notifies subscribed supervisors. A failed
+/// notification is reported as a warning and never fails the update.
+pub fn update_task_status(conn: &Connection, task_id: &str, status: &str) -> Result<Option<db::Task>, DbError> {
+    let Some(task) = db::update_task_status(conn, task_id, status)? else { return Ok(None) };
+    if status == TASK_DONE_STATUS {
+        if let Err(e) = notify_task_done(conn, &task) {
+            eprintln!("Warning: supervisor notifications for task {}: {}", task.id, e);
+        }
+    }
+    Ok(Some(task))
+}
+
+fn notify_task_done(conn: &Connection, task: &db::Task) -> Result<(), DbError> {
+    let Some(assignee_id) = task.assignee_agent_id.as_deref() else { return Ok(()) };
+    let agents = db::list_agents(conn, &task.project_id)?;
+    let Some(assignee) = agents.iter().find(|a| a.id == assignee_id) else { return Ok(()) };
+    let event = NotifyEvent {
+        kind: NotifyEventKind::TaskDone,
+        agent_name: assignee.name.clone(),
+        agent_role: assignee.role.clone(),
+        detail: format!("task {} \"{}\"", task.id, task.title),
+    };
+    record_notifications(conn, &task.project_id, &[event]).map(|_| ())
+}
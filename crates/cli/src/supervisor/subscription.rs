@@ -13,6 +13,8 @@ pub struct Subscription {
 #[derive(Debug, Default)]
 pub struct SupervisorSubscription {
     project: String,
+    /// Logs base to read; the configured one (`log_base()`) when `None`
+    log_base: Option<String>,
     debug_logger: DebugLogger,
 }
 
@@ -21,12 +23,19 @@ impl SupervisorSubscription {
     pub fn new(project: String) -> Self {
         Self { 
             project,
+            log_base: None,
             debug_logger: DebugLogger::new(),
         }
     }
 
-    /// Tail and filter last N lines for a role file, optionally by event name
-    /// Optimized version with improved performance and memory efficiency
+    /// Subscription reader for a project whose logs live under `log_base` rather than the configured base
+    pub fn with_log_base(project: String, log_base: &str) -> Self {
+        Self { log_base: Some(log_base.to_string()), ..Self::new(project) }
+    }
+
+    /// Tail and filter last N lines of a role's events, optionally by event name. A role's events
+    /// live in the files of its agents (or, before per-agent files, in `{role}.ndjson`), so every
+    /// project file is read and lines of other roles are dropped.
     pub fn tail_and_filter(
         &mut self,
         role: String,
@@ -36,17 +45,29 @@ impl SupervisorSubscription {
         let operation = format!("tail_and_filter({}, {:?}, {})", role, event_filter, max_lines);
         let start_time = self.debug_logger.log_operation_start(&operation);
         
-        let path = format!("./logs/{}/{}.ndjson", self.project, role);
-        let content = match std::fs::read_to_string(&path) {
-            Ok(content) => content,
-            Err(e) => {
-                self.debug_logger.log_error(&operation, &format!("File not found: {}", e));
-                return Ok(vec![]); // Return empty results for non-existent files
+        let base = self.log_base.clone().unwrap_or_else(crate::logging::log_base);
+        let files = crate::logging::project_log_files(&base, &self.project);
+        if files.is_empty() {
+            self.debug_logger.log_error(&operation, "No log files found");
+            return Ok(vec![]); // Return empty results when the project has no logs
+        }
+        let role_needle = format!("\"agent_role\":{}", serde_json::Value::from(role.as_str()));
+        let mut contents: Vec<String> = Vec::with_capacity(files.len());
+        for path in &files {
+            match std::fs::read_to_string(path) {
+                Ok(content) => contents.push(content),
+                Err(e) => self.debug_logger.log_error(&operation, &format!("Cannot read {}: {}", path, e)),
             }
-        };
+        }
         
         // Optimized line processing with reduced allocations
-        let lines: Vec<&str> = content.lines().collect();
+        let mut lines: Vec<&str> = contents.iter()
+            .flat_map(|content| content.lines())
+            .filter(|line| line.contains(&role_needle))
+            .collect();
+        if contents.len() > 1 {
+            lines.sort_by_cached_key(|line| extract_ts_fast(line));
+        }
         let total_lines = lines.len();
         
         // Calculate start index for tail operation
@@ -2,12 +2,221 @@
 
 #[cfg(test)]
 mod tests {
-    // Agent integration tests will be added here
-    // These would test the full agent command execution
-    
+    use crate::cli::commands::Format;
+    use crate::commands::{run_agent_remove, run_agent_info, format_agent_info, AGENT_INFO_PROMPT_CHARS};
+    use crate::repository::RepositoryManager;
+    use crate::utils::CliError;
+    use db::{open_or_create_db, insert_project, insert_agent, insert_session, find_agent_by_name, find_session};
+    use tempfile::TempDir;
+
+    fn seed(db_path: &str) -> (String, String) {
+        let conn = open_or_create_db(db_path).unwrap();
+        let project = insert_project(&conn, "remove-test-proj").unwrap();
+        let agent = insert_agent(&conn, &project.id, "decommissioned", "backend", "gemini", "g-1.5", &[], "sp").unwrap();
+        let session = insert_session(&conn, &project.id, &agent.id, "gemini", None).unwrap();
+        (project.id, session.id)
+    }
+
+    #[test]
+    fn test_agent_remove_without_force_is_preview_only() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db").to_string_lossy().to_string();
+        let (project_id, session_id) = seed(&db_path);
+
+        run_agent_remove("remove-test-proj", "decommissioned", Some(&db_path), false, false, Format::Json, None).unwrap();
+
+        let conn = open_or_create_db(&db_path).unwrap();
+        assert!(find_agent_by_name(&conn, &project_id, "decommissioned").unwrap().is_some());
+        assert!(find_session(&conn, &session_id).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_agent_remove_with_force_deletes_agent_and_sessions() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db").to_string_lossy().to_string();
+        let (project_id, session_id) = seed(&db_path);
+
+        run_agent_remove(&project_id, "decommissioned", Some(&db_path), true, false, Format::Text, None).unwrap();
+
+        let conn = open_or_create_db(&db_path).unwrap();
+        assert!(find_agent_by_name(&conn, &project_id, "decommissioned").unwrap().is_none());
+        assert!(find_session(&conn, &session_id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_agent_remove_unknown_agent_is_invalid_input() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db").to_string_lossy().to_string();
+        seed(&db_path);
+
+        let err = run_agent_remove("remove-test-proj", "ghost", Some(&db_path), true, false, Format::Text, None).unwrap_err();
+        assert!(matches!(err, CliError::Validation { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn test_agent_info_joins_agent_sessions_newest_first() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db").to_string_lossy().to_string();
+        let conn = open_or_create_db(&db_path).unwrap();
+        let project = insert_project(&conn, "info-proj").unwrap();
+        let agent = insert_agent(&conn, &project.id, "reviewer", "qa", "claude", "sonnet", &["Read".to_string()], "sp").unwrap();
+        let other = insert_agent(&conn, &project.id, "writer", "dev", "claude", "sonnet", &[], "sp").unwrap();
+        let mut own = Vec::new();
+        for day in 1..=3 {
+            let s = insert_session(&conn, &project.id, &agent.id, "claude", None).unwrap();
+            conn.execute("UPDATE sessions SET created_at = ?1 WHERE id = ?2", [format!("2026-01-0{}T00:00:00Z", day), s.id.clone()]).unwrap();
+            own.push(s.id);
+        }
+        insert_session(&conn, &project.id, &other.id, "claude", None).unwrap();
+
+        let repo = RepositoryManager::new(conn);
+        let info = repo.agents.find_info(&project.id, "reviewer", 2).unwrap().unwrap();
+        assert_eq!(info.agent.id, agent.id);
+        assert_eq!(info.sessions.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), vec![own[2].as_str(), own[1].as_str()]);
+        assert!(info.sessions.iter().all(|s| s.agent_id == agent.id));
+
+        assert!(repo.agents.find_info(&project.id, "reviewer", 0).unwrap().unwrap().sessions.is_empty());
+        assert!(repo.agents.find_info(&project.id, "ghost", 5).unwrap().is_none());
+
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["name"], "reviewer");
+        assert_eq!(json["allowed_tools"][0], "Read");
+        assert_eq!(json["sessions"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_agent_info_text_truncates_system_prompt_unless_full() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db").to_string_lossy().to_string();
+        let conn = open_or_create_db(&db_path).unwrap();
+        let project = insert_project(&conn, "info-proj").unwrap();
+        let prompt = "p".repeat(AGENT_INFO_PROMPT_CHARS + 10);
+        insert_agent(&conn, &project.id, "reviewer", "qa", "claude", "sonnet", &[], &prompt).unwrap();
+        let info = RepositoryManager::new(conn).agents.find_info(&project.id, "reviewer", 5).unwrap().unwrap();
+
+        let short = format_agent_info(&info, "info-proj", true, false);
+        assert!(short.contains(&format!("{}...", "p".repeat(AGENT_INFO_PROMPT_CHARS))));
+        assert!(!short.contains(&prompt));
+        assert!(short.contains("No sessions"));
+        assert!(format_agent_info(&info, "info-proj", false, true).contains(&prompt));
+    }
+
+    #[test]
+    fn test_agent_info_unknown_agent_is_invalid_input() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db").to_string_lossy().to_string();
+        seed(&db_path);
+
+        run_agent_info("remove-test-proj", "decommissioned", Some(&db_path), 5, false, Format::Json, None).unwrap();
+        let err = run_agent_info("remove-test-proj", "ghost", Some(&db_path), 5, false, Format::Text, None).unwrap_err();
+        assert!(matches!(err, CliError::Validation { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn test_agent_run_overrides_apply_to_launch() {
+        use crate::commands::{effective_agent, launch_config_text, repl_command_args};
+        use crate::providers::{apply_agent_identity, AgentIdentity};
+        let providers = config_model::parse_providers_yaml(
+            "schema_version: 1\nproviders:\n  gemini:\n    cmd: gemini\n    oneshot_args: [\"{prompt}\"]\n    repl_args: [\"--model\", \"{model}\", \"--allowed-tools\", \"{allowed_tools}\"]\n  ollama:\n    cmd: ollama\n    oneshot_args: [\"{prompt}\"]\n    repl_args: [\"run\", \"{model}\"]\n"
+        ).unwrap();
+        let project = config_model::parse_project_yaml(
+            "schema_version: 1\nproject: demo\nagents:\n  - { name: b1, role: backend, provider: gemini, model: flash, allowed_tools: [Read], system_prompt: sp }\n  - { name: local, role: backend, provider: ollama, model: llama3, allowed_tools: [], system_prompt: sp }\n"
+        ).unwrap();
+        let launch = |agent: &config_model::AgentConfig, provider: Option<&str>, model: Option<&str>| {
+            let effective = effective_agent(agent, None, provider, model, &providers)?;
+            let identity = AgentIdentity { project: "demo", agent_name: &effective.name, agent_role: &effective.role, model: &effective.model };
+            let (tpl, prompt) = apply_agent_identity(&providers.providers[&effective.provider], &effective.system_prompt, &identity, true).unwrap();
+            let config: serde_json::Value = serde_json::from_str(&launch_config_text(&effective)).unwrap();
+            Ok::<_, CliError>((repl_command_args(&tpl.repl_args, &prompt, &effective.allowed_tools), config))
+        };
+        let (b1, local) = (&project.agents[0], &project.agents[1]);
+
+        // No override: the agent's own provider and model
+        let (args, config) = launch(b1, None, None).unwrap();
+        assert_eq!(args, ["--model", "flash", "--allowed-tools", "Read"]);
+        assert_eq!(config, serde_json::json!({"role": "backend", "provider": "gemini", "model": "flash", "allowed_tools": ["Read"]}));
+
+        // Model only
+        let (args, config) = launch(b1, None, Some("pro")).unwrap();
+        assert_eq!(args, ["--model", "pro", "--allowed-tools", "Read"]);
+        assert_eq!(config["model"], "pro");
+
+        // Provider only: the agent's model goes to the new provider's args
+        let (args, config) = launch(b1, Some("ollama"), None).unwrap();
+        assert_eq!(args, ["run", "flash"]);
+        assert_eq!((config["provider"].as_str(), config["model"].as_str()), (Some("ollama"), Some("flash")));
+
+        // Provider and model
+        let (args, config) = launch(b1, Some("ollama"), Some("qwen")).unwrap();
+        assert_eq!(args, ["run", "qwen"]);
+        assert_eq!((config["provider"].as_str(), config["model"].as_str()), (Some("ollama"), Some("qwen")));
+
+        // An agent without tools cannot be moved to a provider that requires them
+        let err = launch(local, Some("gemini"), None).unwrap_err();
+        assert!(matches!(err, CliError::Validation { .. }), "{:?}", err);
+        assert!(err.to_string().contains("local (provider=gemini): allowed_tools must not be empty"), "{}", err);
+        assert_eq!(launch(local, None, Some("mistral")).unwrap().0, ["run", "mistral"]);
+
+        // Unknown provider
+        let err = launch(b1, Some("nope"), None).unwrap_err();
+        assert_eq!(err.exit_code(), 3, "{:?}", err);
+    }
+
+    #[test]
+    fn test_agent_run_report_names_the_tmux_target() {
+        use crate::backend::AgentTarget;
+        use crate::commands::AgentRunReport;
+
+        let target = AgentTarget { project: "demo", role: "backend", agent: "api" };
+        let started = AgentRunReport::tmux(&target, &target.window_name(), true);
+        assert_eq!(
+            serde_json::to_string(&started).unwrap(),
+            r#"{"status":"ok","tmux_session":"proj:demo","tmux_window":"api","tmux_target":"proj:demo:api"}"#
+        );
+        assert_eq!(started.text("api", 42), "Agent 'api' started in tmux session 'proj:demo' (target 'proj:demo:api', took 42ms)");
+
+        // An agent still running in a window from before the rename reports that window
+        let running = AgentRunReport::tmux(&target, &target.legacy_window_name(), false);
+        assert!(!running.launched());
+        assert_eq!(serde_json::to_value(&running).unwrap()["status"], "already_running");
+        assert_eq!(running.text("api", 0), "Agent 'api' is already running in tmux session 'proj:demo' (target 'proj:demo:backend:api')");
+
+        // The process backend has no tmux target
+        let process = AgentRunReport::process(4242, true);
+        assert_eq!(serde_json::to_string(&process).unwrap(), r#"{"status":"ok","pid":4242}"#);
+        assert_eq!(process.text("worker", 7), "Agent 'worker' started as background process 4242 (took 7ms)");
+    }
+
     #[test]
-    fn test_agent_placeholder() {
-        // Placeholder test
-        assert!(true);
+    fn test_agent_run_as_process_then_attach_and_stop_without_backend() {
+        use crate::tests::integration::fake_provider::{run_cli_in, write_providers_yaml, FakeProvider};
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("project.yaml"),
+            "schema_version: 1\nproject: backend-default\nagents:\n  - name: worker\n    role: backend\n    provider: gemini\n    model: m\n    allowed_tools: [Read]\n    system_prompt: \"\"\n",
+        ).unwrap();
+        let gemini = FakeProvider::new().sleep(30.0).install(dir.path(), "gemini");
+        // attach and stop take no --providers-file, so it goes where they look by default
+        std::fs::create_dir(dir.path().join("config")).unwrap();
+        write_providers_yaml(&dir.path().join("config"), &[("gemini", &gemini)]);
+        let agent = |cmd: &str, extra: &[&str]| {
+            let mut args = vec!["agent", cmd, "--agent", "worker", "--project-file", "project.yaml"];
+            args.extend_from_slice(extra);
+            run_cli_in(&dir, &args)
+        };
+
+        // With --format json, stdout is the report alone, without the project sync lines
+        let out = agent("run", &["--backend", "process", "--no-verify", "--format", "json"]);
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        let report: serde_json::Value = serde_json::from_slice(&out.stdout).expect("stdout is one JSON document");
+        assert_eq!(report["status"], "ok");
+        assert!(report["pid"].as_u64().is_some(), "{}", report);
+
+        // Neither names a backend, yet both talk to the background process rather than tmux
+        let out = agent("attach", &[]);
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        assert!(String::from_utf8_lossy(&out.stdout).contains("runs as background process"), "{}", String::from_utf8_lossy(&out.stdout));
+        let out = agent("stop", &[]);
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        assert!(String::from_utf8_lossy(&out.stdout).contains("stopped in background process"), "{}", String::from_utf8_lossy(&out.stdout));
     }
 }
@@ -0,0 +1,248 @@
+//! Integration tests for the agent backends (tmux and background process)
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::time::Duration;
+    use crate::backend::{ingest_lines, process_start_time, AgentBackend, AgentTarget, LaunchSpec, ProcessBackend, Started, TmuxBackend};
+    use crate::logging::NdjsonEvent;
+    use crate::tmux::manager::TmuxManager;
+    use crate::utils::is_process_running;
+    use db::{get_metadata_value, insert_agent, insert_project, list_sessions, open_or_create_db, SessionFilters, SessionStatus};
+    use tempfile::TempDir;
+
+    const TARGET: AgentTarget<'static> = AgentTarget { project: "backend-proj", role: "backend", agent: "worker" };
+
+    /// Write a fake tmux that records its calls and keeps track of created windows
+    fn fake_tmux(dir: &std::path::Path) -> String {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join("tmux");
+        let state = dir.display();
+        let script = format!(
+            "#!/bin/sh\necho \"$*\" >> {state}/calls\ncase \"$1\" in\n  list-windows) cat {state}/windows 2>/dev/null ;;\n  new-window) echo \"$5\" >> {state}/windows ;;\n  kill-window) : > {state}/windows ;;\nesac\nexit 0\n"
+        );
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_tmux_backend_start_and_stop() {
+        let dir = TempDir::new().unwrap();
+        let backend = TmuxBackend::new(TmuxManager::with_binary(Duration::from_secs(1), fake_tmux(dir.path())));
+        let spec = LaunchSpec {
+            provider: "gemini".into(),
+            cmd: "gemini".into(),
+            args: vec!["-i".into()],
+            workdir: Some("/srv/app".into()),
+            log_file: None,
+        };
+
+        assert_eq!(backend.name(), "tmux");
+        assert!(!backend.is_running(&TARGET).unwrap());
+        assert_eq!(backend.start(&TARGET, &spec).unwrap(), Started::Launched { pid: None });
+        assert!(backend.is_running(&TARGET).unwrap());
+        assert_eq!(backend.start(&TARGET, &spec).unwrap(), Started::AlreadyRunning { pid: None });
+
+        let calls = std::fs::read_to_string(dir.path().join("calls")).unwrap();
+        assert!(calls.contains("new-window -t proj:backend-proj -n worker"), "calls: {}", calls);
+        assert!(calls.contains("send-keys -t proj:backend-proj:worker cd /srv/app Enter"), "calls: {}", calls);
+        assert!(calls.contains("send-keys -t proj:backend-proj:worker gemini -i Enter"), "calls: {}", calls);
+
+        assert!(backend.stop(&TARGET).unwrap());
+        assert!(!backend.is_running(&TARGET).unwrap());
+        assert!(!backend.stop(&TARGET).unwrap());
+    }
+
+    #[test]
+    fn test_tmux_backend_pipes_pane_through_the_ingester() {
+        let dir = TempDir::new().unwrap();
+        let log_file = dir.path().join("logs/worker.ndjson").to_string_lossy().into_owned();
+        let spec = LaunchSpec { provider: "gemini".into(), cmd: "gemini".into(), args: vec![], workdir: None, log_file: Some(log_file.clone()) };
+        let backend = TmuxBackend::new(TmuxManager::with_binary(Duration::from_secs(1), fake_tmux(dir.path())))
+            .with_ingest_exe(Some("/opt/multi agents/bin".into()));
+        backend.start(&TARGET, &spec).unwrap();
+
+        let calls = std::fs::read_to_string(dir.path().join("calls")).unwrap();
+        let pipe = calls.lines().find(|l| l.starts_with("pipe-pane")).unwrap_or_else(|| panic!("calls: {}", calls));
+        let expected = format!("'/opt/multi agents/bin' '__ingest-log' '--log-file' '{}' '--project' 'backend-proj' '--role' 'backend' '--agent' 'worker' '--provider' 'gemini'", log_file);
+        assert!(pipe.contains(&expected), "{}", pipe);
+        assert!(!pipe.contains("cat >>"), "{}", pipe);
+
+        // Without an ingester the pane is not piped at all, rather than logged unredacted
+        let dir = TempDir::new().unwrap();
+        let backend = TmuxBackend::new(TmuxManager::with_binary(Duration::from_secs(1), fake_tmux(dir.path()))).with_ingest_exe(None);
+        backend.start(&TARGET, &spec).unwrap();
+        assert!(!std::fs::read_to_string(dir.path().join("calls")).unwrap().contains("pipe-pane"));
+    }
+
+    #[test]
+    fn test_agent_window_lookup_prefers_current_name_then_legacy() {
+        let windows = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        // Current name first, even when a legacy window is also there
+        assert_eq!(TARGET.find_window(&windows(&["backend:worker", "worker"])).as_deref(), Some("worker"));
+        // Then the legacy `{role}:{agent}` name, before a window left from an older role
+        assert_eq!(TARGET.find_window(&windows(&["ops:worker", "backend:worker"])).as_deref(), Some("backend:worker"));
+        assert_eq!(TARGET.find_window(&windows(&["ops:worker"])).as_deref(), Some("ops:worker"));
+        // Other agents never match, whatever their role
+        assert_eq!(TARGET.find_window(&windows(&["worker2", "backend:worker2", "backend-v2:api", "a:b:worker"])), None);
+    }
+
+    #[test]
+    fn test_tmux_backend_still_finds_legacy_window() {
+        let dir = TempDir::new().unwrap();
+        let backend = TmuxBackend::new(TmuxManager::with_binary(Duration::from_secs(1), fake_tmux(dir.path())));
+        std::fs::write(dir.path().join("windows"), "backend:worker\n").unwrap();
+        let spec = LaunchSpec { provider: "gemini".into(), cmd: "gemini".into(), args: vec![], workdir: None, log_file: None };
+
+        assert!(backend.is_running(&TARGET).unwrap());
+        assert_eq!(backend.agent_window(&TARGET).unwrap().as_deref(), Some("backend:worker"));
+        assert_eq!(backend.start(&TARGET, &spec).unwrap(), Started::AlreadyRunning { pid: None });
+        assert!(backend.stop(&TARGET).unwrap());
+        let calls = std::fs::read_to_string(dir.path().join("calls")).unwrap();
+        assert!(calls.contains("kill-window -t proj:backend-proj:backend:worker"), "calls: {}", calls);
+        assert!(!calls.contains("new-window"), "calls: {}", calls);
+    }
+
+    #[test]
+    fn test_process_backend_records_pid_and_stops_process() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("test.db").to_string_lossy().to_string();
+        let conn = open_or_create_db(&db_path).unwrap();
+        let project = insert_project(&conn, TARGET.project).unwrap();
+        insert_agent(&conn, &project.id, TARGET.agent, TARGET.role, "gemini", "g-1.5", &[], "sp").unwrap();
+
+        let backend = ProcessBackend::new(db_path.clone()).with_ingest_exe(None);
+        let spec = LaunchSpec { provider: "gemini".into(), cmd: "sleep".into(), args: vec!["30".into()], ..Default::default() };
+
+        assert_eq!(backend.name(), "process");
+        assert!(!backend.is_running(&TARGET).unwrap());
+        let Started::Launched { pid: Some(pid) } = backend.start(&TARGET, &spec).unwrap() else { panic!("expected a launch with a pid") };
+        assert!(is_process_running(pid));
+        assert!(backend.is_running(&TARGET).unwrap());
+        assert_eq!(backend.describe(&TARGET), format!("background process {}", pid));
+        assert_eq!(backend.start(&TARGET, &spec).unwrap(), Started::AlreadyRunning { pid: Some(pid) });
+
+        let sessions = list_sessions(&conn, SessionFilters {
+            project_id: Some(project.id.clone()),
+            agent_id: None,
+            provider: None,
+            status: None,
+            session_type: None,
+            limit: None,
+            offset: None,
+        }).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(get_metadata_value(&sessions[0], "backend").unwrap(), Some(serde_json::json!("process")));
+        assert_eq!(get_metadata_value(&sessions[0], "pid").unwrap(), Some(serde_json::json!(pid)));
+
+        assert!(backend.stop(&TARGET).unwrap());
+        assert!(!is_process_running(pid));
+        assert!(!backend.is_running(&TARGET).unwrap());
+        assert!(!backend.stop(&TARGET).unwrap());
+        let session = db::find_session(&conn, &sessions[0].id).unwrap().unwrap();
+        assert_eq!(session.status, SessionStatus::Expired);
+    }
+
+    #[test]
+    fn test_process_backend_leaves_a_reused_pid_alone() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("test.db").to_string_lossy().to_string();
+        let conn = open_or_create_db(&db_path).unwrap();
+        let project = insert_project(&conn, TARGET.project).unwrap();
+        insert_agent(&conn, &project.id, TARGET.agent, TARGET.role, "gemini", "g-1.5", &[], "sp").unwrap();
+        let backend = ProcessBackend::new(db_path.clone()).with_ingest_exe(None);
+        let spec = LaunchSpec { provider: "gemini".into(), cmd: "sleep".into(), args: vec!["30".into()], ..Default::default() };
+        let Started::Launched { pid: Some(pid) } = backend.start(&TARGET, &spec).unwrap() else { panic!("expected a launch with a pid") };
+
+        // The recorded process is gone and another one now has its PID
+        let (session, _) = backend.running_process(&TARGET).unwrap().unwrap();
+        let recorded = get_metadata_value(&session, "start_time").unwrap().and_then(|v| v.as_u64()).expect("start time recorded");
+        assert_eq!(Some(recorded), process_start_time(pid));
+        let metadata = serde_json::json!({"backend": "process", "pid": pid, "start_time": recorded + 1, "cmd": "sleep", "log_file": null});
+        db::set_session_metadata(&conn, &session.id, &metadata.to_string()).unwrap();
+
+        assert!(!backend.is_running(&TARGET).unwrap());
+        assert!(!backend.stop(&TARGET).unwrap());
+        assert!(is_process_running(pid), "a PID that is not the recorded process must not be signalled");
+        assert_eq!(db::find_session(&conn, &session.id).unwrap().unwrap().status, SessionStatus::Expired);
+        unsafe { libc::kill(pid as i32, libc::SIGKILL); }
+    }
+
+    #[test]
+    fn test_process_backend_attach_requires_running_agent() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("test.db").to_string_lossy().to_string();
+        let backend = ProcessBackend::new(db_path);
+        let err = backend.attach(&TARGET, "/tmp/backend.ndjson", false).unwrap_err();
+        assert!(err.to_string().contains("not running"), "{}", err);
+    }
+
+    #[test]
+    fn test_ingest_lines_writes_stdout_events_then_end() {
+        let dir = TempDir::new().unwrap();
+        let log_file = dir.path().join("backend.ndjson").to_string_lossy().to_string();
+
+        let count = ingest_lines(Cursor::new("ready\r\n\x1b[32mdone\x1b[0m\n"), &log_file, &TARGET, "gemini").unwrap();
+        assert_eq!(count, 2);
+
+        let events: Vec<NdjsonEvent> = std::fs::read_to_string(&log_file).unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        let kinds: Vec<&str> = events.iter().map(|e| e.event.as_str()).collect();
+        assert_eq!(kinds, vec!["stdout_line", "stdout_line", "end"]);
+        assert_eq!(events[1].text.as_deref(), Some("done"));
+        assert_eq!(events[0].agent_id, "worker");
+        assert_eq!(events[2].text.as_deref(), Some("exited"));
+        assert_eq!(events[0].text.as_deref(), Some("ready"));
+        // Raw bytes, CRLF and ANSI codes included
+        assert_eq!((events[2].lines, events[2].bytes), (Some(2), Some(21)));
+    }
+
+    #[test]
+    fn test_ingest_log_keeps_going_past_bytes_that_are_not_utf8() {
+        let dir = TempDir::new().unwrap();
+        let log_file = dir.path().join("backend.ndjson").to_string_lossy().to_string();
+
+        let input = b"caf\xe9\nstill here\n".to_vec();
+        crate::commands::run_ingest_log(Cursor::new(input), &log_file, "backend-proj", "backend", "worker", "gemini", &[]).unwrap();
+
+        let events: Vec<NdjsonEvent> = std::fs::read_to_string(&log_file).unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        let texts: Vec<(&str, Option<&str>)> = events.iter().map(|e| (e.event.as_str(), e.text.as_deref())).collect();
+        assert_eq!(texts, vec![("stdout_line", Some("caf\u{FFFD}")), ("stdout_line", Some("still here")), ("end", Some("exited"))]);
+    }
+
+    /// A reader that yields one line, then fails
+    struct FailingReader(Cursor<&'static [u8]>);
+
+    impl std::io::Read for FailingReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.read(buf)? {
+                0 => Err(std::io::Error::other("pipe broke")),
+                n => Ok(n),
+            }
+        }
+    }
+
+    #[test]
+    fn test_ingest_lines_writes_end_when_the_input_fails() {
+        let dir = TempDir::new().unwrap();
+        let log_file = dir.path().join("backend.ndjson").to_string_lossy().to_string();
+
+        let reader = std::io::BufReader::new(FailingReader(Cursor::new(b"ready\n")));
+        let err = ingest_lines(reader, &log_file, &TARGET, "gemini").unwrap_err();
+        assert_eq!(err.to_string(), "pipe broke");
+
+        let events: Vec<NdjsonEvent> = std::fs::read_to_string(&log_file).unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        let kinds: Vec<&str> = events.iter().map(|e| e.event.as_str()).collect();
+        assert_eq!(kinds, vec!["stdout_line", "end"]);
+        assert_eq!((events[1].text.as_deref(), events[1].lines), (Some("read_error"), Some(1)));
+    }
+}
@@ -69,25 +69,49 @@ fn setup_test_database(temp_dir: &TempDir) -> String {
     run_project_add("test-broadcast", Some(&db_path_str)).unwrap();
     
     // Add test agents
-    run_agent_add(
-        "test-broadcast", "backend1", "backend", "gemini", "2.0",
-        &[], "You are a backend developer", Some(&db_path_str)
-    ).unwrap();
+    run_agent_add(&AgentAddRequest {
+        project: "test-broadcast",
+        name: "backend1",
+        role: "backend",
+        provider: "gemini",
+        model: "2.0",
+        system_prompt: "You are a backend developer",
+        db_path: Some(&db_path_str),
+        ..AgentAddRequest::default()
+    }).unwrap();
     
-    run_agent_add(
-        "test-broadcast", "backend2", "backend", "claude", "opus",
-        &[], "You are a backend developer", Some(&db_path_str)
-    ).unwrap();
+    run_agent_add(&AgentAddRequest {
+        project: "test-broadcast",
+        name: "backend2",
+        role: "backend",
+        provider: "claude",
+        model: "opus",
+        system_prompt: "You are a backend developer",
+        db_path: Some(&db_path_str),
+        ..AgentAddRequest::default()
+    }).unwrap();
     
-    run_agent_add(
-        "test-broadcast", "frontend1", "frontend", "claude", "opus",
-        &[], "You are a frontend developer", Some(&db_path_str)
-    ).unwrap();
+    run_agent_add(&AgentAddRequest {
+        project: "test-broadcast",
+        name: "frontend1",
+        role: "frontend",
+        provider: "claude",
+        model: "opus",
+        system_prompt: "You are a frontend developer",
+        db_path: Some(&db_path_str),
+        ..AgentAddRequest::default()
+    }).unwrap();
     
-    run_agent_add(
-        "test-broadcast", "devops1", "devops", "gemini", "2.0",
-        &[], "You are a DevOps engineer", Some(&db_path_str)
-    ).unwrap();
+    run_agent_add(&AgentAddRequest {
+        project: "test-broadcast",
+        name: "devops1",
+        role: "devops",
+        provider: "gemini",
+        model: "2.0",
+        system_prompt: "You are a DevOps engineer",
+        db_path: Some(&db_path_str),
+        ..AgentAddRequest::default()
+    }).unwrap();
     
     db_path_str
 }
@@ -432,27 +456,28 @@ mod tests {
         // This ensures broadcast doesn't break existing functionality
         
         // Test agent run command still works
-        let result = run_agent_run(
-            Some(&project_path),
-            Some(&providers_path),
-            Some("test-broadcast"),
-            "backend1",
-            None, None, None, None, true, None, Some(5000)
-        );
+        let result = run_agent_run(&AgentRunRequest {
+            project_file: Some(&project_path),
+            providers_file: Some(&providers_path),
+            project_name: Some("test-broadcast"),
+            agent_name: Some("backend1"),
+            no_logs: true,
+            timeout_ms: Some(5000),
+            ..AgentRunRequest::default()
+        });
         
         // Agent run will fail without tmux, but should not panic
         assert!(result.is_err() || result.is_ok(), "Agent run should handle gracefully");
         
         // Test send command still works
-        let result = run_send(
-            Some(&project_path),
-            Some(&providers_path),
-            "backend1",
-            "Test message",
-            None, None, Some(5000),
-            crate::cli::commands::Format::Text,
-            false
-        );
+        let result = run_send(&SendRequest {
+            project_path_opt: Some(&project_path),
+            providers_path_opt: Some(&providers_path),
+            timeout_ms_flag: Some(5000),
+            progress: false,
+            yes: true,
+            ..SendRequest::new("backend1", "Test message")
+        });
         
         // Send will fail without proper setup, but should not panic
         assert!(result.is_err() || result.is_ok(), "Send command should handle gracefully");
@@ -0,0 +1,90 @@
+//! Integration tests for shell completions
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{write_completions, complete_agents, complete_projects, complete_targets};
+    use clap_complete::Shell;
+    use tempfile::TempDir;
+
+    fn script(shell: Shell) -> String {
+        let mut out = Vec::new();
+        write_completions(shell, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_completion_scripts_for_all_shells() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Elvish, Shell::PowerShell] {
+            let s = script(shell);
+            assert!(s.contains("providers-file"), "{:?} script lacks flags", shell);
+        }
+        let bash = script(Shell::Bash);
+        assert!(bash.contains("complete -F _multi_agents_dynamic"));
+        // Hidden helpers are called by the hooks but never offered as subcommands
+        assert!(!bash.contains("multi__agents,__complete-agents"));
+        assert!(script(Shell::Zsh).contains("compdef _multi_agents_dynamic multi-agents"));
+        assert!(script(Shell::Fish).contains("complete -c multi-agents -l agent"));
+    }
+
+    #[test]
+    fn test_complete_agents_from_config_and_db() {
+        let dir = TempDir::new().unwrap();
+        let project_file = dir.path().join("project.yaml");
+        std::fs::write(&project_file, "schema_version: 1\nproject: demo\nagents:\n  - name: backend1\n    role: backend\n    provider: gemini\n    model: m\n    allowed_tools: []\n    system_prompt: \"\"\n").unwrap();
+        let project_file = project_file.to_string_lossy().to_string();
+        let db_path = dir.path().join("test.db").to_string_lossy().to_string();
+        let conn = db::open_or_create_db(&db_path).unwrap();
+        let project = db::insert_project(&conn, "demo").unwrap();
+        db::insert_agent(&conn, &project.id, "devops", "devops", "claude", "m", &[], "").unwrap();
+        db::insert_project(&conn, "other").unwrap();
+
+        assert_eq!(complete_agents(Some("demo"), Some(&project_file), Some(&db_path)), vec!["backend1", "devops"]);
+        assert_eq!(complete_agents(None, Some(&project_file), Some(&db_path)), vec!["backend1"]);
+        assert!(complete_agents(Some("other"), Some(&project_file), Some(&db_path)).is_empty());
+        assert_eq!(complete_projects(Some(&project_file), Some(&db_path)), vec!["demo", "other"]);
+    }
+
+    #[test]
+    fn test_bash_script_covers_send_and_target_hook() {
+        let bash = script(Shell::Bash);
+        assert!(bash.contains("multi-agents"));
+        assert!(bash.contains("send"));
+        assert!(bash.contains("__complete-targets"));
+    }
+
+    #[test]
+    fn test_completion_alias_with_shell_flag() {
+        use clap::Parser;
+        use crate::cli::commands::Commands;
+        let cli = crate::Cli::try_parse_from(["multi-agents", "completion", "--shell", "fish"]).unwrap();
+        assert!(matches!(cli.cmd, Commands::Completions { shell: None, shell_flag: Some(Shell::Fish) }));
+        assert!(crate::Cli::try_parse_from(["multi-agents", "completions", "bash", "--shell", "zsh"]).is_err());
+        assert!(crate::Cli::try_parse_from(["multi-agents", "completions"]).is_err());
+    }
+
+    #[test]
+    fn test_complete_targets_lists_all_roles_and_agents() {
+        let dir = TempDir::new().unwrap();
+        let project_file = dir.path().join("project.yaml");
+        std::fs::write(&project_file, "schema_version: 1\nproject: demo\nagents:\n  - name: backend1\n    role: backend\n    provider: gemini\n    model: m\n    allowed_tools: []\n    system_prompt: \"\"\n").unwrap();
+        let project_file = project_file.to_string_lossy().to_string();
+        let db_path = dir.path().join("test.db").to_string_lossy().to_string();
+        let conn = db::open_or_create_db(&db_path).unwrap();
+        let project = db::insert_project(&conn, "demo").unwrap();
+        db::insert_agent(&conn, &project.id, "ops1", "devops", "claude", "m", &[], "").unwrap();
+
+        assert_eq!(complete_targets(Some("demo"), Some(&project_file), Some(&db_path)), vec!["@all", "@backend", "@devops", "backend1", "ops1"]);
+        let missing_file = dir.path().join("none.yaml").to_string_lossy().to_string();
+        assert_eq!(complete_targets(None, Some(&missing_file), Some(&db_path)), vec!["@all"]);
+    }
+
+    #[test]
+    fn test_complete_agents_silent_without_config_or_db() {
+        let dir = TempDir::new().unwrap();
+        let missing_file = dir.path().join("project.yaml").to_string_lossy().to_string();
+        let missing_db = dir.path().join("data/none.db");
+        assert!(complete_agents(Some("ghost-project"), Some(&missing_file), Some(&missing_db.to_string_lossy())).is_empty());
+        complete_projects(Some(&missing_file), Some(&missing_db.to_string_lossy()));
+        assert!(!missing_db.exists(), "completion must not create the database");
+    }
+}
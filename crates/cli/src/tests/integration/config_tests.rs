@@ -60,4 +60,279 @@ mod tests {
         std::env::remove_var("MULTI_AGENTS_CONFIG_DIR");
         let _ = std::fs::remove_dir_all(&temp_dir);
     }
+
+    #[test]
+    fn test_config_init_dry_run_diffs_customized_file() {
+        use crate::commands::config::{run_config_init, PROJECT_TEMPLATE};
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_string_lossy().to_string();
+        let project_p = tmp.path().join("project.yaml");
+        let custom = PROJECT_TEMPLATE.replace("project: demo", "project: shop").replace("model: fill-me", "model: sonnet");
+        std::fs::write(&project_p, &custom).unwrap();
+
+        run_config_init(Some(&dir), true, true, crate::cli::commands::Format::Text).unwrap();
+        assert_eq!(std::fs::read_to_string(&project_p).unwrap(), custom, "dry run must not overwrite");
+        assert!(!tmp.path().join("providers.yaml").exists(), "dry run must not create files");
+
+        let label = project_p.to_string_lossy().to_string();
+        let diff = unified_diff(&custom, PROJECT_TEMPLATE, &label, &label);
+        assert!(diff.starts_with(&format!("--- {}\n+++ {}\n@@ ", label, label)));
+        assert!(diff.contains("-project: shop\n+project: demo\n"));
+        assert!(diff.contains("-    model: sonnet\n+    model: fill-me\n"));
+        assert!(!diff.contains("-schema_version") && !diff.contains("+schema_version"));
+    }
+
+    #[test]
+    fn test_config_init_force_dry_run_writes_nothing() {
+        use crate::commands::config::PROVIDERS_TEMPLATE;
+        use crate::tests::integration::fake_provider::run_cli_in;
+        let tmp = tempfile::TempDir::new().unwrap();
+        let cfg = tmp.path().join("cfg");
+        std::fs::create_dir(&cfg).unwrap();
+        let custom = PROVIDERS_TEMPLATE.replace("cmd: \"gemini\"", "cmd: \"/opt/gemini\"");
+        std::fs::write(cfg.join("providers.yaml"), &custom).unwrap();
+
+        let out = run_cli_in(&tmp, &["config", "init", "--dir", "cfg", "--force", "--dry-run"]);
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        let stdout = String::from_utf8_lossy(&out.stdout);
+        assert!(stdout.contains("--- /dev/null\n+++ cfg/project.yaml\n"), "{}", stdout);
+        assert!(stdout.contains("-    cmd: \"/opt/gemini\"\n+    cmd: \"gemini\"\n"), "{}", stdout);
+        assert_eq!(std::fs::read_dir(&cfg).unwrap().count(), 1, "dry run must not create files");
+        assert_eq!(std::fs::read_to_string(cfg.join("providers.yaml")).unwrap(), custom, "dry run must not overwrite");
+    }
+
+    #[test]
+    fn test_config_init_json_reports_written_then_skipped_files() {
+        use crate::cli::commands::Format;
+        use crate::commands::config::{init_report, write_config_templates, FileAction};
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_string_lossy().to_string();
+        let (proj, prov) = (format!("{}/project.yaml", dir), format!("{}/providers.yaml", dir));
+
+        let mut files = Vec::new();
+        write_config_templates(&dir, false, false, Format::Json, &mut files).unwrap();
+        assert_eq!(files.iter().map(|f| f.action).collect::<Vec<_>>(), vec![FileAction::Wrote, FileAction::Wrote]);
+        assert_eq!(init_report(&files, None), serde_json::json!({
+            "status": "ok",
+            "files": [{"path": proj, "action": "wrote"}, {"path": prov, "action": "wrote"}],
+        }));
+
+        let mut files = Vec::new();
+        write_config_templates(&dir, false, false, Format::Json, &mut files).unwrap();
+        assert_eq!(init_report(&files, None), serde_json::json!({
+            "status": "ok",
+            "files": [{"path": proj, "action": "skipped"}, {"path": prov, "action": "skipped"}],
+        }));
+
+        let mut files = Vec::new();
+        write_config_templates(&dir, true, true, Format::Json, &mut files).unwrap();
+        assert_eq!(files.iter().map(|f| f.action).collect::<Vec<_>>(), vec![FileAction::Unchanged, FileAction::Unchanged]);
+    }
+
+    #[test]
+    fn test_config_init_json_error_keeps_files_so_far() {
+        use crate::commands::config::{init_report, FileAction, FileResult};
+        use crate::utils::CliError;
+        let files = vec![FileResult { path: "config/project.yaml".into(), action: FileAction::Wrote }];
+        let report = init_report(&files, Some(&CliError::Other("disk full".into())));
+        assert_eq!(report["status"], "error");
+        assert!(report["error"].as_str().unwrap().contains("disk full"));
+        assert_eq!(report["files"], serde_json::json!([{"path": "config/project.yaml", "action": "wrote"}]));
+    }
+
+    #[test]
+    fn test_init_json_reports_db_files_sync_and_validation() {
+        use crate::tests::integration::fake_provider::run_cli_in;
+        let dir = tempfile::TempDir::new().unwrap();
+
+        let out = run_cli_in(&dir, &["init", "--config-dir", "cfg", "--format", "json"]);
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        let report: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+        assert_eq!(report["status"], "ok");
+        assert_eq!(report["db_initialized"], true);
+        assert_eq!(report["config_files"], serde_json::json!(["cfg/project.yaml", "cfg/providers.yaml"]));
+        assert_eq!(report["sync"]["project"], "demo");
+        assert_eq!(report["sync"]["project_created"], true);
+        assert_eq!(report["sync"]["created"], serde_json::json!(["backend", "frontend", "devops"]));
+        assert!(report["sync"]["project_id"].as_str().is_some_and(|id| !id.is_empty()));
+        assert_eq!(report["validation"]["project"]["valid"], true);
+        assert!(report["validation"]["project"]["warnings"].is_array());
+        assert_eq!(report["validation"]["providers"]["valid"], true);
+
+        // A second run skips the files and finds the agents already synced
+        let out = run_cli_in(&dir, &["init", "--config-dir", "cfg", "--skip-db", "--format", "json"]);
+        let report: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+        assert_eq!(report["db_initialized"], false);
+        assert_eq!(report["files"][0]["action"], "skipped");
+        assert_eq!(report["sync"]["project_created"], false);
+        assert_eq!(report["sync"]["unchanged"], serde_json::json!(["backend", "frontend", "devops"]));
+
+        // The same shape as `config sync --format json`
+        let out = run_cli_in(&dir, &["config", "sync", "--project-file", "cfg/project.yaml", "--providers-file", "cfg/providers.yaml", "--format", "json"]);
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        let synced: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+        assert_eq!(synced, report["sync"]);
+    }
+
+    #[test]
+    fn test_unified_diff_new_file_and_hunks() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "x", "x"), "");
+        assert_eq!(
+            unified_diff("", "a\nb\n", "/dev/null", "x"),
+            "--- /dev/null\n+++ x\n@@ -0,0 +1,2 @@\n+a\n+b\n"
+        );
+        // Changes far apart produce separate hunks with their own line numbers
+        let old: String = (1..=20).map(|i| format!("l{}\n", i)).collect();
+        let new = old.replace("l2\n", "L2\n").replace("l18\n", "L18\n");
+        let diff = unified_diff(&old, &new, "a", "b");
+        assert!(diff.contains("@@ -1,5 +1,5 @@\n l1\n-l2\n+L2\n l3\n"));
+        assert!(diff.contains("@@ -15,6 +15,6 @@\n l15\n l16\n l17\n-l18\n+L18\n l19\n l20\n"));
+    }
+
+    #[test]
+    fn test_config_schema_render_and_validate() {
+        use crate::cli::commands::{SchemaOutputFormat, SchemaTarget};
+        use crate::commands::config::{render_schema, run_config_schema, PROJECT_TEMPLATE};
+        let all: serde_json::Value = serde_json::from_str(&render_schema(SchemaTarget::All, SchemaOutputFormat::Json).unwrap()).unwrap();
+        assert!(all.get("project").is_some() && all.get("providers").is_some());
+        let yaml = render_schema(SchemaTarget::Project, SchemaOutputFormat::Yaml).unwrap();
+        assert!(yaml.starts_with("# JSON Schema for project.yaml"));
+        assert!(yaml.lines().all(|l| l.starts_with('#')));
+
+        let tmp = tempfile::tempdir().unwrap();
+        let out = tmp.path().join("schema/project.json");
+        run_config_schema(SchemaTarget::Project, SchemaOutputFormat::Json, Some(&out.to_string_lossy()), None).unwrap();
+        let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+        assert_eq!(written, all["project"]);
+
+        let good = tmp.path().join("project.yaml");
+        std::fs::write(&good, PROJECT_TEMPLATE).unwrap();
+        let good = good.to_string_lossy().to_string();
+        run_config_schema(SchemaTarget::Project, SchemaOutputFormat::Json, None, Some(&good)).unwrap();
+        let err = run_config_schema(SchemaTarget::Providers, SchemaOutputFormat::Json, None, Some(&good)).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+        assert!(run_config_schema(SchemaTarget::All, SchemaOutputFormat::Json, None, Some(&good)).is_err());
+
+        // Violations are found by the schema itself and point at their location
+        let bad = tmp.path().join("bad.yaml");
+        std::fs::write(&bad, PROJECT_TEMPLATE.replace("allowed_tools: [Edit]", "allowed_tools: Edit")).unwrap();
+        let err = run_config_schema(SchemaTarget::Project, SchemaOutputFormat::Json, None, Some(&bad.to_string_lossy())).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+        assert!(err.to_string().contains("/agents/0/allowed_tools: \"Edit\" is not of type \"array\""), "{}", err);
+    }
+
+    #[test]
+    fn test_config_validate_strict_rejects_unknown_tools() {
+        use crate::cli::commands::Format;
+        use crate::commands::config::run_config_validate;
+        let tmp = tempfile::tempdir().unwrap();
+        let project = tmp.path().join("project.yaml");
+        let providers = tmp.path().join("providers.yaml");
+        std::fs::write(&project, "schema_version: 1\nproject: demo\nagents:\n  - name: a1\n    role: r\n    provider: claude\n    model: m\n    allowed_tools: [Read, Teleport]\n    system_prompt: You review pull requests.\n").unwrap();
+        std::fs::write(&providers, "schema_version: 1\nproviders:\n  claude:\n    cmd: claude\n    oneshot_args: [\"{prompt}\", \"--session-id\", \"{session_id}\"]\n    repl_args: []\n    known_tools: [Read, Edit]\n").unwrap();
+        let (project, providers) = (project.to_string_lossy().to_string(), providers.to_string_lossy().to_string());
+
+        run_config_validate(Some(&project), Some(&providers), Format::Text, false).unwrap();
+        let err = run_config_validate(Some(&project), Some(&providers), Format::Text, true).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+        assert!(err.to_string().contains("unknown tool 'Teleport'"), "{}", err);
+    }
+
+    #[test]
+    fn test_config_validate_strict_rejects_unknown_provider_key() {
+        use crate::cli::commands::Format;
+        use crate::commands::config::run_config_validate;
+        let tmp = tempfile::tempdir().unwrap();
+        let project = tmp.path().join("project.yaml");
+        let providers = tmp.path().join("providers.yaml");
+        std::fs::write(&project, "schema_version: 1\nproject: demo\nagents:\n  - name: a1\n    role: r\n    provider: gemni\n    model: m\n    allowed_tools: []\n    system_prompt: You review pull requests.\n").unwrap();
+        std::fs::write(&providers, "schema_version: 1\nproviders:\n  gemni:\n    cmd: gemini\n    oneshot_args: [\"{prompt}\"]\n    repl_args: []\n").unwrap();
+        let (project, providers) = (project.to_string_lossy().to_string(), providers.to_string_lossy().to_string());
+
+        run_config_validate(Some(&project), Some(&providers), Format::Text, false).unwrap();
+        let err = run_config_validate(Some(&project), Some(&providers), Format::Text, true).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+        assert!(err.to_string().contains("unknown provider 'gemni' (allowed: claude, gemini, cursor*, ollama)"), "{}", err);
+    }
+
+    #[test]
+    fn test_config_sync_dry_run_matches_real_run() {
+        use crate::cli::commands::Format;
+        use crate::commands::config::{config_sync, run_config_sync, sync_report_json};
+        let tmp = tempfile::tempdir().unwrap();
+        let project = tmp.path().join("project.yaml");
+        let providers = tmp.path().join("providers.yaml");
+        let db_path = tmp.path().join("multi-agents.sqlite3").to_string_lossy().to_string();
+        let agent = |name: &str, model: &str| format!("  - name: {}\n    role: r\n    provider: claude\n    model: {}\n    allowed_tools: [Read]\n    system_prompt: You review pull requests.\n", name, model);
+        std::fs::write(&providers, "schema_version: 1\nproviders:\n  claude:\n    cmd: claude\n    oneshot_args: [\"{prompt}\", \"--session-id\", \"{session_id}\"]\n    repl_args: []\n").unwrap();
+        std::fs::write(&project, format!("schema_version: 1\nproject: sync-demo\nagents:\n{}{}{}", agent("a1", "m"), agent("a2", "m"), agent("a3", "m"))).unwrap();
+        let (project_s, providers_s) = (project.to_string_lossy().to_string(), providers.to_string_lossy().to_string());
+        let sync = |prune: bool, dry_run: bool| config_sync(Some(&project_s), Some(&providers_s), Some(&db_path), prune, dry_run).unwrap().1;
+
+        // First run: a dry run creates the same project and agents, but leaves nothing behind
+        let planned = sync(false, true);
+        assert!(planned.project_created);
+        assert_eq!(planned.agents_created, ["a1", "a2", "a3"]);
+        assert!(!std::path::Path::new(&db_path).exists(), "dry run must not create the DB");
+        let conn = db::open_or_create_db(&db_path).unwrap();
+        assert!(db::find_project_id(&conn, db::IdOrName::Name("sync-demo")).unwrap().is_none());
+        assert_eq!(sync_report_json("sync-demo", &planned, true)["project_id"], serde_json::Value::Null);
+        let done = sync(false, false);
+        assert_eq!(SyncReportView::from(&done), SyncReportView::from(&planned));
+
+        // a2 changes model, a3 is removed, a4 is new
+        std::fs::write(&project, format!("schema_version: 1\nproject: sync-demo\nagents:\n{}{}{}", agent("a1", "m"), agent("a2", "other"), agent("a4", "m"))).unwrap();
+        for prune in [false, true] {
+            let planned = sync(prune, true);
+            run_config_sync(Some(&project_s), Some(&providers_s), Some(&db_path), prune, true, Format::Text).unwrap();
+            let json = sync_report_json("sync-demo", &planned, true);
+            assert_eq!(json["project_id"], serde_json::json!(done.project_id));
+            assert_eq!(json["updated"], serde_json::json!(["a2"]));
+            assert_eq!(json["pruned"], if prune { serde_json::json!(["a3"]) } else { serde_json::json!([]) });
+            assert_eq!(db::list_agent_names(&conn, &done.project_id).unwrap(), ["a1", "a2", "a3"], "dry run must not touch the DB");
+            assert_eq!(db::find_agent_by_name(&conn, &done.project_id, "a2").unwrap().unwrap().model, "m");
+        }
+        let planned = sync(true, true);
+        let applied = sync(true, false);
+        assert_eq!(applied, planned);
+        assert_eq!(applied.agents_created, ["a4"]);
+        assert_eq!(applied.agents_existing, ["a1"]);
+        assert_eq!(db::list_agent_names(&conn, &done.project_id).unwrap(), ["a1", "a2", "a4"]);
+        assert_eq!(db::find_agent_by_name(&conn, &done.project_id, "a2").unwrap().unwrap().model, "other");
+
+        // Nothing left to do
+        let again = sync(true, false);
+        assert!(again.agents_created.is_empty() && again.agents_updated.is_empty() && again.agents_pruned.is_empty());
+        assert_eq!(again.agents_existing, ["a1", "a2", "a4"]);
+    }
+
+    #[test]
+    fn test_config_sync_dry_run_leaves_an_old_schema_unmigrated() {
+        use crate::commands::config::config_sync;
+        let tmp = tempfile::tempdir().unwrap();
+        let project = tmp.path().join("project.yaml");
+        let providers = tmp.path().join("providers.yaml");
+        let db_path = tmp.path().join("multi-agents.sqlite3").to_string_lossy().to_string();
+        std::fs::write(&providers, "schema_version: 1\nproviders:\n  claude:\n    cmd: claude\n    oneshot_args: [\"{prompt}\"]\n    repl_args: []\n").unwrap();
+        std::fs::write(&project, "schema_version: 1\nproject: old-demo\nagents:\n  - name: a1\n    role: r\n    provider: claude\n    model: m\n    allowed_tools: [Read]\n    system_prompt: You review pull requests.\n").unwrap();
+        let conn = db::open_db_without_migrating(&db_path).unwrap();
+        db::migrate_to(&conn, 2).unwrap();
+
+        let (project_s, providers_s) = (project.to_string_lossy().to_string(), providers.to_string_lossy().to_string());
+        let planned = config_sync(Some(&project_s), Some(&providers_s), Some(&db_path), false, true).unwrap().1;
+        assert!(planned.project_created);
+        assert_eq!(planned.agents_created, ["a1"]);
+        assert_eq!(db::db_schema_version(&conn).unwrap(), Some(2));
+        assert!(db::find_project_id(&conn, db::IdOrName::Name("old-demo")).unwrap().is_none());
+    }
+
+    /// A sync report without the project id, which a dry run that creates the project cannot know
+    #[derive(Debug, PartialEq)]
+    struct SyncReportView<'a>(bool, &'a [String], &'a [String], &'a [String], &'a [String]);
+
+    impl<'a> From<&'a db::SyncReport> for SyncReportView<'a> {
+        fn from(r: &'a db::SyncReport) -> Self {
+            SyncReportView(r.project_created, &r.agents_created, &r.agents_existing, &r.agents_updated, &r.agents_pruned)
+        }
+    }
 }
@@ -18,6 +18,325 @@ mod tests {
         // project add
         run_project_add("demo", Some(&dbs)).expect("project add");
         // agent add
-        run_agent_add("demo", "backend", "backend", "gemini", "g-1.5", &vec!["Edit".into()], "sp", Some(&dbs)).expect("agent add");
+        run_agent_add(&AgentAddRequest {
+            project: "demo",
+            name: "backend",
+            role: "backend",
+            provider: "gemini",
+            model: "g-1.5",
+            allowed_tools: &["Edit".into()],
+            system_prompt: "sp",
+            db_path: Some(&dbs),
+            ..AgentAddRequest::default()
+        }).expect("agent add");
+    }
+
+    #[test]
+    fn test_agent_add_treats_hex_looking_project_as_name() {
+        use crate::cli::commands::SelectBy;
+        let tmp = tempfile::tempdir().unwrap();
+        let dbs = tmp.path().join("multi-agents.sqlite3").to_string_lossy().to_string();
+        run_project_add("abcdef1234567890", Some(&dbs)).unwrap();
+
+        let request = AgentAddRequest {
+            project: "abcdef1234567890",
+            name: "backend",
+            role: "backend",
+            provider: "gemini",
+            model: "m",
+            system_prompt: "sp",
+            db_path: Some(&dbs),
+            ..AgentAddRequest::default()
+        };
+        run_agent_add(&request).expect("guessed as a name");
+        run_agent_add(&AgentAddRequest { name: "frontend", role: "frontend", by: Some(SelectBy::Name), ..request })
+            .expect("--by name");
+        let err = run_agent_add(&AgentAddRequest { name: "devops", role: "devops", by: Some(SelectBy::Id), ..request })
+            .unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+
+        let conn = db::open_or_create_db(&dbs).unwrap();
+        let id = db::find_project_id(&conn, db::IdOrName::Name("abcdef1234567890")).unwrap().unwrap();
+        run_agent_add(&AgentAddRequest { project: &id, name: "devops", role: "devops", ..request })
+            .expect("generated id is recognised");
+        assert_eq!(db::list_agent_names(&conn, &id).unwrap().len(), 3);
+    }
+
+    /// Seed a project with two agents, then delete one agent and a session with FKs disabled
+    fn corrupted_fixture(dbs: &str) -> String {
+        let conn = db::open_or_create_db(dbs).unwrap();
+        let project = db::insert_project(&conn, "demo").unwrap();
+        let keep = db::insert_agent(&conn, &project.id, "backend", "backend", "gemini", "m", &[], "").unwrap();
+        let gone = db::insert_agent(&conn, &project.id, "pruned", "frontend", "mystery", "m", &[], "").unwrap();
+        let s_keep = db::insert_session(&conn, &project.id, &keep.id, "gemini", None).unwrap();
+        let s_gone = db::insert_session(&conn, &project.id, &gone.id, "mystery", None).unwrap();
+        let now = db::now_iso8601_utc();
+        for (id, session) in [("m1", &s_keep.id), ("m2", &s_gone.id), ("m3", &s_keep.id)] {
+            conn.execute(
+                "INSERT INTO messages(id, session_id, sender, content, created_at) VALUES (?1, ?2, 'user', 'hi', ?3)",
+                rusqlite::params![id, session, now],
+            ).unwrap();
+        }
+        conn.execute(
+            "INSERT INTO tasks(id, project_id, title, status, assignee_agent_id, created_at) VALUES ('t1', ?1, 'task', 'todo', ?2, ?3)",
+            rusqlite::params![project.id, gone.id, now],
+        ).unwrap();
+
+        conn.pragma_update(None, "foreign_keys", 0i64).unwrap();
+        conn.execute("DELETE FROM agents WHERE id = ?1", [&gone.id]).unwrap();
+        conn.execute("DELETE FROM sessions WHERE id = ?1", [&s_keep.id]).unwrap();
+        conn.execute(
+            "INSERT INTO tasks(id, project_id, title, status, created_at) VALUES ('t2', 'no-such-project', 'task', 'todo', ?1)",
+            [&now],
+        ).unwrap();
+        project.id
+    }
+
+    #[test]
+    fn test_db_health_reports_orphans() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dbs = tmp.path().join("multi-agents.sqlite3").to_string_lossy().to_string();
+        corrupted_fixture(&dbs);
+        let conn = db::open_or_create_db(&dbs).unwrap();
+
+        let known = vec!["gemini".to_string(), "claude".to_string()];
+        let report = db::check_db_health(&conn, Some(&known)).unwrap();
+        assert!(report.integrity_errors.is_empty());
+        assert_eq!(report.orphaned_sessions, 1);
+        assert_eq!(report.orphaned_messages, 2);
+        assert_eq!(report.orphaned_tasks, 1);
+        assert_eq!(report.dangling_task_assignees, 1);
+        // sessions.agent_id, two messages.session_id, tasks.project_id
+        assert_eq!(report.foreign_key_violations, 4);
+        assert!(report.invalid_provider_agents.is_empty());
+        assert!(report.duplicate_agent_names.is_empty());
+
+        let err = run_db_doctor(false, None, Some(&dbs), crate::cli::commands::Format::Json, false).unwrap_err();
+        assert!(matches!(err, crate::utils::CliError::Other(_)), "{:?}", err);
+        // Without --fix nothing is deleted
+        assert_eq!(db::check_db_health(&conn, None).unwrap().orphaned_messages, 2);
+    }
+
+    #[test]
+    fn test_db_doctor_fix_deletes_orphans() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dbs = tmp.path().join("multi-agents.sqlite3").to_string_lossy().to_string();
+        corrupted_fixture(&dbs);
+        let conn = db::open_or_create_db(&dbs).unwrap();
+
+        let fixed = db::fix_orphans(&conn).unwrap();
+        // The two orphaned messages plus the one belonging to the orphaned session
+        assert_eq!(fixed, db::OrphanFixResult { sessions_deleted: 1, messages_deleted: 3, tasks_deleted: 1, task_assignees_cleared: 1 });
+        let report = db::check_db_health(&conn, None).unwrap();
+        assert_eq!(report.problem_count(), 0);
+        let tasks: i64 = conn.query_row("SELECT COUNT(*) FROM tasks WHERE id = 't1' AND assignee_agent_id IS NULL", [], |r| r.get(0)).unwrap();
+        assert_eq!(tasks, 1);
+
+        // A second run through the command finds nothing left
+        run_db_doctor(true, None, Some(&dbs), crate::cli::commands::Format::Text, false).expect("clean db");
+    }
+
+    #[test]
+    fn test_db_health_flags_unknown_providers_and_duplicates() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dbs = tmp.path().join("multi-agents.sqlite3").to_string_lossy().to_string();
+        // Legacy agents table without UNIQUE(project_id, name); migrations keep the existing table
+        {
+            let conn = rusqlite::Connection::open(&dbs).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE projects (id TEXT PRIMARY KEY, name TEXT NOT NULL UNIQUE, created_at TEXT NOT NULL);
+                 CREATE TABLE agents (id TEXT PRIMARY KEY, project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+                     name TEXT NOT NULL, role TEXT NOT NULL, provider TEXT NOT NULL, model TEXT NOT NULL,
+                     allowed_tools_json TEXT NOT NULL, system_prompt TEXT NOT NULL, created_at TEXT NOT NULL);
+                 INSERT INTO projects VALUES ('p1', 'demo', '2025-01-01T00:00:00.000Z');
+                 INSERT INTO agents VALUES ('a1', 'p1', 'backend', 'backend', 'gemini', 'm', '[]', '', '2025-01-01T00:00:00.000Z');
+                 INSERT INTO agents VALUES ('a2', 'p1', 'backend', 'backend', 'gpt-cli', 'm', '[]', '', '2025-01-01T00:00:00.000Z');",
+            ).unwrap();
+        }
+        let conn = db::open_or_create_db(&dbs).unwrap();
+        let known = vec!["gemini".to_string()];
+        let report = db::check_db_health(&conn, Some(&known)).unwrap();
+        assert_eq!(report.duplicate_agent_names.len(), 1);
+        assert_eq!(report.duplicate_agent_names[0].count, 2);
+        assert_eq!(report.invalid_provider_agents.len(), 1);
+        assert_eq!(report.invalid_provider_agents[0].provider, "gpt-cli");
+        assert_eq!(report.fixable_problems(), 0);
+
+        // --fix does not touch these, so the command still fails
+        let providers = tmp.path().join("providers.yaml");
+        std::fs::write(&providers, "schema_version: 1\nproviders:\n  gemini:\n    cmd: gemini\n    oneshot_args: [\"{prompt}\"]\n    repl_args: []\n").unwrap();
+        let err = run_db_doctor(true, Some(&providers.to_string_lossy()), Some(&dbs), crate::cli::commands::Format::Text, false).unwrap_err();
+        assert_eq!(err.exit_code(), 1);
+    }
+
+    #[test]
+    fn test_db_doctor_refuses_newer_schema_unless_allowed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dbs = tmp.path().join("multi-agents.sqlite3").to_string_lossy().to_string();
+        {
+            let conn = db::open_or_create_db(&dbs).unwrap();
+            conn.execute("INSERT INTO migrations(version, applied_at) VALUES (?1, '2030-01-01T00:00:00.000Z')", [db::SCHEMA_VERSION + 1]).unwrap();
+        }
+
+        let err = run_db_doctor(false, None, Some(&dbs), crate::cli::commands::Format::Text, false).unwrap_err();
+        assert_eq!(err.exit_code(), 7);
+        assert!(err.to_string().contains("upgrade"), "{}", err);
+
+        run_db_doctor(false, None, Some(&dbs), crate::cli::commands::Format::Json, true).expect("read-only doctor");
+        let err = run_db_doctor(true, None, Some(&dbs), crate::cli::commands::Format::Text, true).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+    }
+
+    #[test]
+    fn test_agent_add_create_project() {
+        use crate::cli::commands::SelectBy;
+        let tmp = tempfile::tempdir().unwrap();
+        let dbs = tmp.path().join("multi-agents.sqlite3").to_string_lossy().to_string();
+
+        let request = AgentAddRequest {
+            project: "fresh",
+            name: "backend",
+            role: "backend",
+            provider: "gemini",
+            model: "m",
+            system_prompt: "sp",
+            db_path: Some(&dbs),
+            ..AgentAddRequest::default()
+        };
+        let err = run_agent_add(&request).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+        assert!(err.to_string().contains("--create-project"), "{}", err);
+
+        let request = AgentAddRequest { create_project: true, ..request };
+        run_agent_add(&request).expect("project created");
+        // Second call finds the project instead of inserting it again
+        run_agent_add(&AgentAddRequest { name: "frontend", role: "frontend", ..request }).expect("project reused");
+        let conn = db::open_or_create_db(&dbs).unwrap();
+        let id = db::find_project_id(&conn, db::IdOrName::Name("fresh")).unwrap().unwrap();
+        assert_eq!(db::list_agent_names(&conn, &id).unwrap().len(), 2);
+
+        // An unknown id cannot become a project name
+        let err = run_agent_add(&AgentAddRequest {
+            project: "0123456789abcdef0123456789abcdef",
+            name: "devops",
+            role: "devops",
+            by: Some(SelectBy::Id),
+            ..request
+        }).unwrap_err();
+        assert!(err.to_string().contains("needs a project name"), "{}", err);
+    }
+
+    /// Project "demo" with one agent, two sessions and a message
+    fn populated_project(dbs: &str) -> rusqlite::Connection {
+        let conn = db::open_or_create_db(dbs).unwrap();
+        let project = db::insert_project(&conn, "demo").unwrap();
+        let agent = db::insert_agent(&conn, &project.id, "backend", "backend", "gemini", "m", &[], "").unwrap();
+        let session = db::insert_session(&conn, &project.id, &agent.id, "gemini", None).unwrap();
+        db::insert_session(&conn, &project.id, &agent.id, "gemini", None).unwrap();
+        conn.execute(
+            "INSERT INTO messages(id, session_id, sender, content, created_at) VALUES ('m1', ?1, 'user', 'hi', ?2)",
+            rusqlite::params![session.id, db::now_iso8601_utc()],
+        ).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_project_remove_requires_cascade_and_confirmation() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dbs = tmp.path().join("multi-agents.sqlite3").to_string_lossy().to_string();
+        let conn = populated_project(&dbs);
+        let mut out = Vec::new();
+
+        let err = remove_project(&conn, "demo", false, false, true, &mut "y\n".as_bytes(), &mut out).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+        assert!(err.to_string().contains("1 agents and 2 sessions; pass --cascade"), "{}", err);
+
+        // No TTY and no --yes: refused before anything is deleted
+        let err = remove_project(&conn, "demo", true, false, false, &mut "".as_bytes(), &mut out).unwrap_err();
+        assert!(err.to_string().contains("pass --yes"), "{}", err);
+
+        out.clear();
+        let err = remove_project(&conn, "demo", true, false, true, &mut "n\n".as_bytes(), &mut out).unwrap_err();
+        assert_eq!(err.to_string(), "project remove cancelled");
+        let prompt = String::from_utf8(out.clone()).unwrap();
+        assert!(prompt.contains("also deletes 1 agents, 2 sessions, 1 messages, 0 tasks, 0 broadcasts"), "{}", prompt);
+        assert!(prompt.ends_with("Delete project 'demo'? [y/N] "), "{}", prompt);
+        assert!(db::find_project_id(&conn, db::IdOrName::Name("demo")).unwrap().is_some());
+
+        let removed = remove_project(&conn, "demo", true, false, true, &mut "yes\n".as_bytes(), &mut out).unwrap();
+        assert_eq!((removed.projects, removed.agents, removed.sessions, removed.messages), (1, 1, 2, 1));
+        assert!(db::find_project_id(&conn, db::IdOrName::Name("demo")).unwrap().is_none());
+        let sessions: i64 = conn.query_row("SELECT COUNT(*) FROM sessions", [], |r| r.get(0)).unwrap();
+        assert_eq!(sessions, 0);
+    }
+
+    #[test]
+    fn test_project_remove_with_yes_and_empty_project() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dbs = tmp.path().join("multi-agents.sqlite3").to_string_lossy().to_string();
+        let conn = populated_project(&dbs);
+        db::insert_project(&conn, "empty").unwrap();
+        let mut out = Vec::new();
+
+        // Nothing else to lose: no --cascade or confirmation needed
+        let removed = remove_project(&conn, "empty", false, false, false, &mut "".as_bytes(), &mut out).unwrap();
+        assert_eq!(removed.dependents(), 0);
+        assert!(out.is_empty());
+
+        let removed = remove_project(&conn, "demo", true, true, false, &mut "".as_bytes(), &mut out).unwrap();
+        assert_eq!(removed.sessions, 2);
+
+        let err = run_project_remove("demo", true, true, Some(&dbs)).unwrap_err();
+        assert!(err.to_string().contains("not found: demo"), "{}", err);
+    }
+
+    #[test]
+    fn test_db_migrate_reports_progress_and_dry_run_pending() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dbs = tmp.path().join("multi-agents.sqlite3").to_string_lossy().to_string();
+        let conn = db::open_db_without_migrating(&dbs).unwrap();
+        let latest = db::SCHEMA_VERSION;
+        let run = |to: Option<i64>, dry_run: bool| -> String {
+            let mut out = Vec::new();
+            migrate_db(&conn, to, dry_run, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let report = run(Some(3), true);
+        assert_eq!(report, format!("current_version=0 latest={}\npending: 1, 2, 3\n", latest));
+        assert_eq!(db::current_migration_version(&conn).unwrap(), 0, "dry run applies nothing");
+
+        let report = run(Some(3), false);
+        assert!(report.contains("applied: 1, 2, 3\ncurrent_version=3"), "{}", report);
+
+        let pending: Vec<String> = (4..=latest).map(|v| v.to_string()).collect();
+        assert_eq!(run(None, true), format!("current_version=3 latest={}\npending: {}\n", latest, pending.join(", ")));
+        assert!(run(None, false).ends_with(&format!("current_version={}\n", latest)));
+        assert!(run(None, true).ends_with("pending: none\n"));
+        assert!(run(None, false).contains(&format!("OK: already at version {}", latest)));
+
+        let err = migrate_db(&conn, Some(2), false, &mut Vec::new()).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+        assert!(err.to_string().contains("downgrades are not supported"), "{}", err);
+
+        // The command opens the file itself and finds nothing left to do
+        drop(conn);
+        run_db_migrate(Some(&dbs), None, false).unwrap();
+    }
+
+    #[test]
+    fn test_db_migrate_reports_timestamps_v4_cannot_normalize() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dbs = tmp.path().join("multi-agents.sqlite3").to_string_lossy().to_string();
+        let conn = populated_project(&dbs);
+        conn.execute("UPDATE messages SET created_at = 'yesterday' WHERE id = 'm1'", []).unwrap();
+        // Replay v4 over the bad row
+        conn.execute("DELETE FROM migrations WHERE version = 4", []).unwrap();
+
+        let mut out = Vec::new();
+        migrate_db(&conn, None, false, &mut out).unwrap();
+        let report = String::from_utf8(out).unwrap();
+        assert!(report.contains("applied: 4\n"), "{}", report);
+        assert!(report.ends_with("Warning: 1 malformed timestamp(s) found in database:\n  - messages.created_at (rowid 1): \"yesterday\"\n"), "{}", report);
     }
 }
@@ -2,12 +2,188 @@
 
 #[cfg(test)]
 mod tests {
-    // Doctor integration tests will be added here
-    // These would test the full doctor command execution
-    
+    use crate::commands::doctor::{build_doctor_json, probe_env_keys, ProbeResult};
+    use config_model::parse_providers_yaml;
+
     #[test]
     fn test_doctor_placeholder() {
         // Placeholder test
         assert!(true);
     }
+
+    #[test]
+    fn test_probe_env_keys_reports_presence_without_values() {
+        std::env::set_var("MA_DOCTOR_TEST_PRESENT_KEY", "sk-secret");
+        std::env::remove_var("MA_DOCTOR_TEST_MISSING_KEY");
+        let providers = parse_providers_yaml(r#"
+providers:
+  claude:
+    cmd: claude
+    required_env_keys: ["MA_DOCTOR_TEST_PRESENT_KEY"]
+  gemini:
+    cmd: gemini
+    required_env_keys: ["MA_DOCTOR_TEST_MISSING_KEY", "MA_DOCTOR_TEST_PRESENT_KEY"]
+  cursor-agent:
+    cmd: cursor-agent
+"#).unwrap();
+
+        let keys = probe_env_keys(&providers);
+        let summary: Vec<(&str, bool)> = keys.iter().map(|k| (k.name.as_str(), k.present)).collect();
+        assert_eq!(summary, vec![("MA_DOCTOR_TEST_MISSING_KEY", false), ("MA_DOCTOR_TEST_PRESENT_KEY", true)]);
+
+        let json = build_doctor_json("DEGRADE", &[], None, Some(&keys));
+        assert_eq!(json["env_keys"], serde_json::json!([
+            {"key": "MA_DOCTOR_TEST_MISSING_KEY", "present": false},
+            {"key": "MA_DOCTOR_TEST_PRESENT_KEY", "present": true},
+        ]));
+        assert!(!json.to_string().contains("sk-secret"));
+
+        // Without --check-keys the section is omitted
+        assert!(build_doctor_json("OK", &[], None, None).get("env_keys").is_none());
+    }
+
+    #[test]
+    fn test_doctor_json_reports_agent_backends() {
+        let with_tmux = build_doctor_json("OK", &[probe("tmux", Some("tmux 3.4"))], None, None);
+        assert_eq!(with_tmux["backends"], serde_json::json!({"tmux": true, "process": true}));
+
+        let without_tmux = build_doctor_json("KO", &[probe("tmux", None)], None, None);
+        assert_eq!(without_tmux["backends"], serde_json::json!({"tmux": false, "process": true}));
+    }
+
+    fn probe(name: &str, version: Option<&str>) -> ProbeResult {
+        ProbeResult {
+            name: name.into(),
+            present: version.is_some(),
+            version: version.map(|v| v.to_string()),
+            supports: Default::default(),
+            timed_out: false,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_doctor_watch_prints_only_changes() {
+        use crate::cli::commands::Format;
+        use crate::commands::doctor::{watch_doctor, DoctorSnapshot};
+        use std::sync::atomic::AtomicBool;
+        use std::time::Duration;
+
+        // Second round: claude updated, gemini uninstalled
+        let rounds = [
+            vec![probe("claude", Some("1.0.0")), probe("gemini", Some("0.4.0"))],
+            vec![probe("claude", Some("1.1.0")), probe("gemini", None)],
+        ];
+        let mut calls = 0;
+        let mut prober = || {
+            let results = rounds[calls.min(1)].clone();
+            calls += 1;
+            DoctorSnapshot { results, env_keys: vec![] }
+        };
+        let mut out = Vec::new();
+        watch_doctor(&mut prober, Duration::ZERO, Some(2), &AtomicBool::new(false), Format::Text, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].ends_with("doctor: OK"), "{text}");
+        assert_eq!(&lines[1..3], ["  - claude: 1.0.0", "  - gemini: 0.4.0"]);
+        assert!(lines[3].ends_with("doctor: KO"), "{text}");
+        assert_eq!(&lines[4..], ["  - status: OK -> KO", "  - claude: version 1.0.0 -> 1.1.0", "  - gemini: disappeared"]);
+
+        // Unchanged rounds print nothing after the first
+        let mut steady = || DoctorSnapshot { results: vec![probe("claude", Some("1.0.0"))], env_keys: vec![] };
+        let mut out = Vec::new();
+        watch_doctor(&mut steady, Duration::ZERO, Some(3), &AtomicBool::new(false), Format::Json, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.lines().count(), 1, "{text}");
+        let first: serde_json::Value = serde_json::from_str(text.trim()).unwrap();
+        assert_eq!(first["status"], "OK");
+        assert!(first["ts"].is_string());
+
+        // A pending Ctrl-C stops before probing
+        let mut out = Vec::new();
+        watch_doctor(&mut steady, Duration::ZERO, None, &AtomicBool::new(true), Format::Text, &mut out).unwrap();
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_doctor_reuses_cached_probe_until_no_cache() {
+        use crate::commands::doctor::{probe_providers, BinaryStamp, ProbeCache};
+        use crate::tests::integration::fake_provider::FakeProvider;
+        use crate::utils::DOCTOR_PROBE_CACHE_TTL_SECS;
+        use std::time::Instant;
+
+        // Fake provider CLI recording each run's arguments, so runs can be counted
+        let dir = tempfile::TempDir::new().unwrap();
+        let runs = dir.path().join("runs");
+        let bin = FakeProvider::new().version("fake 1.0").record(&runs).install(dir.path(), "gemini");
+        let bins = [("gemini", bin)];
+        let run_count = || std::fs::read_to_string(&runs).unwrap_or_default().lines().count();
+
+        let cache_path = dir.path().join("doctor-cache.json");
+        let mut cache = ProbeCache::load(&cache_path);
+        let first = probe_providers(&bins, 5000, 20000, Instant::now(), Some(&mut cache));
+        assert_eq!(first[0].version.as_deref(), Some("fake 1.0"));
+        assert_eq!(run_count(), 1);
+        cache.save(&cache_path).unwrap();
+
+        // Next run within the TTL: answered from the file, the binary is not spawned
+        let mut cache = ProbeCache::load(&cache_path);
+        let second = probe_providers(&bins, 5000, 20000, Instant::now(), Some(&mut cache));
+        assert_eq!((second[0].name.as_str(), second[0].present, second[0].version.as_deref()), ("gemini", true, Some("fake 1.0")));
+        assert_eq!(run_count(), 1);
+
+        // --no-cache starts from an empty cache: probed again, and the fresh result is recorded
+        let mut fresh = ProbeCache::default();
+        probe_providers(&bins, 5000, 20000, Instant::now(), Some(&mut fresh));
+        assert_eq!(run_count(), 2);
+        assert_eq!(fresh.probes.len(), 1);
+
+        // Entries go stale after the TTL or when the binary changes
+        let stamp = BinaryStamp::of(&bins[0].1).unwrap();
+        let probed_at = fresh.probes[&stamp.path].probed_at;
+        assert!(fresh.lookup("gemini", &stamp, probed_at + DOCTOR_PROBE_CACHE_TTL_SECS - 1).is_some());
+        assert!(fresh.lookup("gemini", &stamp, probed_at + DOCTOR_PROBE_CACHE_TTL_SECS).is_none());
+        let rebuilt = BinaryStamp { mtime: stamp.mtime + 1, ..stamp };
+        assert!(fresh.lookup("gemini", &rebuilt, probed_at).is_none());
+    }
+
+    #[test]
+    fn test_doctor_probes_fake_providers_without_real_clis() {
+        use crate::commands::doctor::{doctor_status, probe_providers};
+        use crate::tests::integration::fake_provider::FakeProvider;
+        use std::time::Instant;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let claude = FakeProvider::new().version("1.0.42 (Claude Code)").install(dir.path(), "claude");
+        let gemini = FakeProvider::new().version("0.9.0").install(dir.path(), "gemini");
+        let bins = [("claude", claude), ("gemini", gemini)];
+
+        let results = probe_providers(&bins, 5000, 20000, Instant::now(), None);
+        let summary: Vec<(&str, bool, Option<&str>)> = results.iter().map(|r| (r.name.as_str(), r.present, r.version.as_deref())).collect();
+        assert_eq!(summary, [("claude", true, Some("1.0.42 (Claude Code)")), ("gemini", true, Some("0.9.0"))]);
+        assert_eq!(doctor_status(&results, &[]), "OK");
+    }
+
+    #[test]
+    fn test_doctor_provider_without_version_output_is_present() {
+        use crate::commands::doctor::{doctor_status, probe_providers};
+        use crate::tests::integration::fake_provider::FakeProvider;
+        use std::time::Instant;
+
+        // Answers --help but prints nothing for --version, version or -v
+        let dir = tempfile::TempDir::new().unwrap();
+        let quiet = FakeProvider::new().version("").install(dir.path(), "gemini");
+        let bins = [("gemini", quiet)];
+
+        let results = probe_providers(&bins, 5000, 20000, Instant::now(), None);
+        assert_eq!((results[0].present, results[0].version.as_deref(), results[0].error.as_deref()), (true, None, None));
+        assert_eq!(doctor_status(&results, &[]), "OK");
+    }
+
+    #[test]
+    fn test_doctor_no_cache_flag_parses() {
+        use clap::Parser;
+        let cli = crate::Cli::try_parse_from(["multi-agents", "doctor", "--no-cache"]).unwrap();
+        assert!(matches!(cli.cmd, crate::cli::commands::Commands::Doctor { no_cache: true, .. }));
+    }
 }
@@ -0,0 +1,149 @@
+//! Fake provider harness: `tests/fixtures/fake-provider.sh` installed under a provider's name, so
+//! send, session and doctor tests run hermetically without claude, gemini or cursor-agent
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// The fake provider script shipped with the repository
+pub const FAKE_PROVIDER_SCRIPT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/fake-provider.sh");
+
+/// What an installed fake provider does on every run; see the script header for each option
+#[derive(Debug, Clone, Default)]
+pub struct FakeProvider {
+    stdout: Option<String>,
+    stderr: Option<String>,
+    lines: Vec<String>,
+    delay: Option<f64>,
+    sleep: Option<f64>,
+    ignore_term: bool,
+    exit_code: i32,
+    record: Option<PathBuf>,
+    version: Option<String>,
+}
+
+impl FakeProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Print `text` on stdout (`\n` starts a new line)
+    pub fn stdout(mut self, text: &str) -> Self {
+        self.stdout = Some(text.to_string());
+        self
+    }
+
+    /// Print `text` on stderr
+    pub fn stderr(mut self, text: &str) -> Self {
+        self.stderr = Some(text.to_string());
+        self
+    }
+
+    /// Print `lines` one by one, `delay_secs` apart, e.g. a stream-json sequence
+    pub fn stream(mut self, lines: &[&str], delay_secs: f64) -> Self {
+        self.lines = lines.iter().map(|l| l.to_string()).collect();
+        self.delay = Some(delay_secs);
+        self
+    }
+
+    /// Pause `secs` before exiting
+    pub fn sleep(mut self, secs: f64) -> Self {
+        self.sleep = Some(secs);
+        self
+    }
+
+    /// Ignore SIGTERM and SIGINT, like a provider that only dies to SIGKILL
+    pub fn ignore_term(mut self) -> Self {
+        self.ignore_term = true;
+        self
+    }
+
+    pub fn exit(mut self, code: i32) -> Self {
+        self.exit_code = code;
+        self
+    }
+
+    /// Append the arguments of every run to `path`, one per line
+    pub fn record(mut self, path: &Path) -> Self {
+        self.record = Some(path.to_path_buf());
+        self
+    }
+
+    /// Answer `--version` with `text`
+    pub fn version(mut self, text: &str) -> Self {
+        self.version = Some(text.to_string());
+        self
+    }
+
+    /// Write an executable `dir/name` running the fake provider with these options; returns its path
+    pub fn install(&self, dir: &Path, name: &str) -> String {
+        let mut options: Vec<String> = Vec::new();
+        let mut push = |flag: &str, value: String| {
+            options.push(flag.to_string());
+            options.push(value);
+        };
+        if let Some(text) = &self.stdout { push("--fake-stdout", text.clone()); }
+        if let Some(text) = &self.stderr { push("--fake-stderr", text.clone()); }
+        if !self.lines.is_empty() {
+            let lines_path = dir.join(format!("{}.lines", name));
+            std::fs::write(&lines_path, self.lines.join("\n") + "\n").unwrap();
+            push("--fake-lines", lines_path.to_string_lossy().into_owned());
+        }
+        if let Some(secs) = self.delay { push("--fake-delay", secs.to_string()); }
+        if let Some(secs) = self.sleep { push("--fake-sleep", secs.to_string()); }
+        if let Some(path) = &self.record { push("--fake-record", path.to_string_lossy().into_owned()); }
+        if let Some(text) = &self.version { push("--fake-version", text.clone()); }
+        push("--fake-exit", self.exit_code.to_string());
+        if self.ignore_term { options.push("--fake-ignore-term".into()); }
+
+        let quoted: Vec<String> = std::iter::once(FAKE_PROVIDER_SCRIPT.to_string())
+            .chain(options)
+            .map(|arg| format!("'{}'", arg.replace('\'', r"'\''")))
+            .collect();
+        let path = dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\nexec {} \"$@\"\n", quoted.join(" "))).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+}
+
+/// Providers block for `(key, cmd)` pairs: one-shot sends pass `{prompt}`, cursor templates
+/// also get `create_chat_args`
+pub fn providers_yaml(providers: &[(&str, &str)]) -> String {
+    let mut yaml = String::from("schema_version: 1\nproviders:\n");
+    for (key, cmd) in providers {
+        yaml.push_str(&format!("  {}:\n    cmd: {:?}\n    oneshot_args: [\"{{prompt}}\"]\n    repl_args: []\n", key, cmd));
+        if key.starts_with("cursor") {
+            yaml.push_str("    create_chat_args: [\"create-chat\"]\n");
+        }
+    }
+    yaml
+}
+
+/// Write `providers_yaml(providers)` to `dir/providers.yaml`; returns its path
+pub fn write_providers_yaml(dir: &Path, providers: &[(&str, &str)]) -> String {
+    let path = dir.join("providers.yaml");
+    std::fs::write(&path, providers_yaml(providers)).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+/// Template of a fake provider installed at `cmd`, as `providers_yaml` writes it
+pub fn fake_template(cmd: &str) -> config_model::ProviderTemplate {
+    config_model::parse_providers_yaml(&providers_yaml(&[("fake", cmd)])).unwrap().providers.remove("fake").unwrap()
+}
+
+/// Run the CLI binary in `dir` with its own database and log directory
+pub fn run_cli_in(dir: &TempDir, args: &[&str]) -> std::process::Output {
+    cli_command(dir, args).output().unwrap()
+}
+
+/// The command `run_cli_in` runs, for tests that adjust its environment first
+pub fn cli_command(dir: &TempDir, args: &[&str]) -> std::process::Command {
+    let mut cmd = std::process::Command::new("cargo");
+    cmd.args(["run", "--quiet", "--offline", "--manifest-path", concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"), "--bin", "multi-agents-cli", "--"])
+        .args(args)
+        .current_dir(dir.path())
+        .env("MULTI_AGENTS_DB", dir.path().join("multi-agents.sqlite3"))
+        .env("MULTI_AGENTS_LOG_DIR", dir.path().join("logs"));
+    cmd
+}
@@ -0,0 +1,187 @@
+//! Integration tests for `logs show` and `logs migrate`
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Seek, SeekFrom, Write};
+    use crate::commands::{format_log_entry, migrate_project_logs, print_new_lines, show_log_paths, split_log_lines, LogMigration};
+    use crate::logging::{agent_log_path_for_read, log_event_in, log_path, Event};
+    use crate::tui::components::{LogEntry, LogLevel};
+
+    const SAMPLE: &str = concat!(
+        r#"{"ts":"2026-01-01T10:00:00Z","level":"debug","event":"start","text":"spawn"}"#, "\n",
+        r#"{"ts":"2026-01-01T10:00:01Z","level":"info","event":"stdout_line","text":"hello"}"#, "\n",
+        "not json\n",
+        r#"{"ts":"2026-01-01T10:00:02Z","level":"error","event":"stderr_line","text":"boom"}"#, "\n",
+    );
+
+    fn render(input: &str, min_level: Option<LogLevel>, styled: bool) -> (String, u64) {
+        let mut reader = Cursor::new(input.as_bytes().to_vec());
+        let mut out = Vec::new();
+        print_new_lines(&mut reader, min_level, styled, &mut out).unwrap();
+        (String::from_utf8(out).unwrap(), reader.position())
+    }
+
+    #[test]
+    fn test_show_formats_lines_and_skips_garbage() {
+        let (out, _) = render(SAMPLE, None, false);
+        assert_eq!(out.lines().collect::<Vec<_>>(), vec![
+            "2026-01-01T10:00:00Z | DEBUG | start | spawn",
+            "2026-01-01T10:00:01Z | INFO  | stdout_line | hello",
+            "2026-01-01T10:00:02Z | ERROR | stderr_line | boom",
+        ]);
+    }
+
+    #[test]
+    fn test_show_filters_by_min_level() {
+        let (out, _) = render(SAMPLE, Some(LogLevel::Info), false);
+        assert_eq!(out.lines().count(), 2);
+        assert!(!out.contains("spawn"));
+        let (out, _) = render(SAMPLE, LogLevel::from_str("error"), false);
+        assert_eq!(out, "2026-01-01T10:00:02Z | ERROR | stderr_line | boom\n");
+    }
+
+    #[test]
+    fn test_show_colors_only_when_styled() {
+        let entry = LogEntry::from_ndjson_event(r#"{"ts":"t","level":"warn","event":"e","text":"x"}"#).unwrap();
+        assert_eq!(format_log_entry(&entry, false), "t | WARN  | e | x");
+        assert!(format_log_entry(&entry, true).contains("\x1b[33mWARN \x1b[0m"));
+    }
+
+    #[test]
+    fn test_follow_waits_for_partial_line_to_complete() {
+        let partial = r#"{"ts":"t","level":"info","event":"e","text":"late"}"#;
+        let mut reader = Cursor::new(format!("{}{}", SAMPLE, &partial[..10]).into_bytes());
+        let mut out = Vec::new();
+        print_new_lines(&mut reader, None, false, &mut out).unwrap();
+        assert_eq!(reader.position() as usize, SAMPLE.len());
+
+        // The writer finishes the line; the next poll picks it up
+        reader.seek(SeekFrom::End(0)).unwrap();
+        writeln!(reader, "{}", &partial[10..]).unwrap();
+        reader.seek(SeekFrom::Start(SAMPLE.len() as u64)).unwrap();
+        out.clear();
+        print_new_lines(&mut reader, None, false, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "t | INFO  | e | late\n");
+    }
+
+    #[test]
+    fn test_follow_survives_a_line_that_is_not_utf8() {
+        let mut input = br#"{"ts":"t","level":"info","event":"e","text":"bad "#.to_vec();
+        input.extend_from_slice(b"\xff\xfe\"}\n");
+        input.extend_from_slice(SAMPLE.as_bytes());
+        let mut reader = Cursor::new(input);
+        let mut out = Vec::new();
+        print_new_lines(&mut reader, None, false, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.lines().next(), Some("t | INFO  | e | bad \u{FFFD}\u{FFFD}"));
+        assert!(out.ends_with("2026-01-01T10:00:02Z | ERROR | stderr_line | boom\n"));
+    }
+
+    fn line(ts: &str, agent: &str, text: &str) -> String {
+        let mut event = Event::builder("demo", "backend", "claude", "stdout_line").ts(ts).agent_id(agent).text(Some(text)).build();
+        event.direction = "agent".into();
+        event.to_line()
+    }
+
+    #[test]
+    fn test_split_sends_each_line_to_its_agent() {
+        let role_level = Event::builder("demo", "backend", "claude", "end").ts("t4").build().to_line();
+        let content = [
+            line("t1", "be1", "one"),
+            line("t2", "be2", "two"),
+            line("t3", "backend", "own"),
+            role_level.clone(),
+            "not json".to_string(),
+            line("t5", "../escape", "sneaky"),
+            line("t6", "be1", "three"),
+        ].join("\n");
+        let (kept, moved) = split_log_lines("backend", &content);
+        assert_eq!(moved.keys().collect::<Vec<_>>(), vec!["be1", "be2"]);
+        assert_eq!(moved["be1"], vec![line("t1", "be1", "one"), line("t6", "be1", "three")]);
+        assert_eq!(moved["be2"], vec![line("t2", "be2", "two")]);
+        assert_eq!(kept, vec![line("t3", "backend", "own"), role_level, "not json".to_string(), line("t5", "../escape", "sneaky")]);
+    }
+
+    #[test]
+    fn test_migrate_splits_role_files_and_merges_existing_agent_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path().to_string_lossy().to_string();
+        let role_file = log_path(&base, "demo", "backend");
+        std::fs::create_dir_all(std::path::Path::new(&role_file).parent().unwrap()).unwrap();
+        std::fs::write(&role_file, [
+            line("2026-01-01T10:00:00.000Z", "be1", "a"),
+            line("2026-01-01T10:00:01.000Z", "be2", "b"),
+            line("2026-01-01T10:00:03.000Z", "be1", "c"),
+        ].join("\n") + "\n").unwrap();
+        // be2 already wrote to its own file after an upgrade
+        std::fs::write(log_path(&base, "demo", "be2"), line("2026-01-01T10:00:02.000Z", "be2", "new") + "\n").unwrap();
+
+        let report = migrate_project_logs(&base, "demo").unwrap();
+        assert_eq!(report, LogMigration { files_split: 1, lines_moved: 3, agents: vec!["be1".into(), "be2".into()] });
+        assert!(!std::path::Path::new(&role_file).exists(), "an emptied role file is removed");
+        let be1 = std::fs::read_to_string(log_path(&base, "demo", "be1")).unwrap();
+        assert_eq!(be1.lines().collect::<Vec<_>>(), vec![line("2026-01-01T10:00:00.000Z", "be1", "a"), line("2026-01-01T10:00:03.000Z", "be1", "c")]);
+        let be2 = std::fs::read_to_string(log_path(&base, "demo", "be2")).unwrap();
+        assert_eq!(be2.lines().collect::<Vec<_>>(), vec![line("2026-01-01T10:00:01.000Z", "be2", "b"), line("2026-01-01T10:00:02.000Z", "be2", "new")]);
+
+        assert_eq!(migrate_project_logs(&base, "demo").unwrap(), LogMigration::default());
+        assert_eq!(migrate_project_logs(&base, "missing").unwrap(), LogMigration::default());
+    }
+
+    #[test]
+    fn test_agents_write_their_own_file_and_readers_fall_back_to_the_role_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path().to_string_lossy().to_string();
+        let legacy = log_path(&base, "demo", "backend");
+        std::fs::create_dir_all(std::path::Path::new(&legacy).parent().unwrap()).unwrap();
+        std::fs::write(&legacy, line("t1", "be1", "old") + "\n").unwrap();
+        assert_eq!(agent_log_path_for_read(&base, "demo", "be1", "backend"), legacy);
+
+        log_event_in(&base, &Event::builder("demo", "backend", "claude", "start").agent_id("be1").build()).unwrap();
+        log_event_in(&base, &Event::builder("demo", "backend", "claude", "broadcast_complete").build()).unwrap();
+        let own = log_path(&base, "demo", "be1");
+        assert_eq!(std::fs::read_to_string(&own).unwrap().lines().count(), 1);
+        assert_eq!(std::fs::read_to_string(&legacy).unwrap().lines().count(), 2, "events without an agent stay role-level");
+        assert_eq!(agent_log_path_for_read(&base, "demo", "be1", "backend"), own);
+    }
+
+    #[test]
+    fn test_show_reads_the_role_file_for_an_unmigrated_agent_and_all_files_of_a_role() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path().to_string_lossy().to_string();
+        let agents = [("be1".to_string(), "backend".to_string()), ("be2".to_string(), "backend".to_string()), ("fe1".to_string(), "frontend".to_string())];
+        let role_file = log_path(&base, "demo", "backend");
+        std::fs::create_dir_all(std::path::Path::new(&role_file).parent().unwrap()).unwrap();
+        std::fs::write(&role_file, line("t1", "be1", "old") + "\n").unwrap();
+
+        assert_eq!(show_log_paths(&base, "demo", None, Some("be1"), &agents), vec![role_file.clone()]);
+        // An agent the database does not know is looked up under its own name
+        assert_eq!(show_log_paths(&base, "demo", None, Some("ghost"), &agents), vec![log_path(&base, "demo", "ghost")]);
+        assert_eq!(
+            show_log_paths(&base, "demo", Some("backend"), None, &agents),
+            vec![role_file, log_path(&base, "demo", "be1"), log_path(&base, "demo", "be2")],
+        );
+    }
+
+    #[test]
+    fn test_show_role_merges_its_agents_files_by_ts() {
+        use crate::tests::integration::fake_provider::{run_cli_in, write_providers_yaml};
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("project.yaml"),
+            "schema_version: 1\nproject: demo\nagents:\n  - name: be1\n    role: backend\n    provider: gemini\n    model: m\n    allowed_tools: [Read]\n    system_prompt: You write backend services.\n  - name: be2\n    role: backend\n    provider: gemini\n    model: m\n    allowed_tools: [Read]\n    system_prompt: You write backend services.\n",
+        ).unwrap();
+        write_providers_yaml(dir.path(), &[("gemini", "gemini")]);
+        let out = run_cli_in(&dir, &["config", "sync", "--project-file", "project.yaml", "--providers-file", "providers.yaml"]);
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        let logs = dir.path().join("logs/demo");
+        std::fs::create_dir_all(&logs).unwrap();
+        std::fs::write(logs.join("backend.ndjson"), line("2026-01-01T10:00:00Z", "be1", "legacy") + "\n").unwrap();
+        std::fs::write(logs.join("be1.ndjson"), line("2026-01-01T10:00:02Z", "be1", "third") + "\n").unwrap();
+        std::fs::write(logs.join("be2.ndjson"), line("2026-01-01T10:00:01Z", "be2", "second") + "\n").unwrap();
+
+        let out = run_cli_in(&dir, &["logs", "show", "--project", "demo", "--role", "backend", "--no-color"]);
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        let texts: Vec<String> = String::from_utf8_lossy(&out.stdout).lines().map(|l| l.rsplit(" | ").next().unwrap().to_string()).collect();
+        assert_eq!(texts, vec!["legacy", "second", "third"]);
+    }
+}
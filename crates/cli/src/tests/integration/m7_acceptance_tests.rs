@@ -66,9 +66,11 @@ providers:
     )
 }
 
-/// Helper pour créer des logs NDJSON de test
-fn create_test_logs(project: &str, roles: &[&str]) {
-    let logs_dir = format!("./logs/{}", project);
+/// Helper pour créer des logs NDJSON de test dans un répertoire de logs temporaire, renvoyé
+/// (les fichiers disparaissent avec lui)
+fn create_test_logs(project: &str, roles: &[&str]) -> TempDir {
+    let logs_base = TempDir::new().unwrap();
+    let logs_dir = logs_base.path().join(project).to_string_lossy().to_string();
     fs::create_dir_all(&logs_dir).unwrap();
 
     for role in roles {
@@ -83,6 +85,12 @@ fn create_test_logs(project: &str, roles: &[&str]) {
         );
         fs::write(&log_file, log_content).unwrap();
     }
+    logs_base
+}
+
+/// Supervisor subscription lisant les logs créés par `create_test_logs`
+fn test_logs_subscription(logs_base: &TempDir, project: &str) -> crate::supervisor::subscription::SupervisorSubscription {
+    crate::supervisor::subscription::SupervisorSubscription::with_log_base(project.to_string(), &logs_base.path().to_string_lossy())
 }
 
 /// Test d'acceptation M7-01: send --to @role fonctionne correctement
@@ -92,17 +100,14 @@ fn m7_acceptance_send_to_role_works_correctly() {
     let (project_path, providers_path) = create_m7_acceptance_test_config(&temp_dir);
 
     // Test send --to @backend (doit router vers tous les agents backend)
-    let result = crate::commands::run_send(
-        Some(&project_path),
-        Some(&providers_path),
-        "@backend",
-        "Test message for backend agents",
-        None,
-        None,
-        Some(5000), // 5s timeout
-        crate::cli::commands::Format::Text,
-        false,
-    );
+    let result = crate::commands::run_send(&crate::commands::SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        timeout_ms_flag: Some(5000), // 5s timeout
+        progress: false,
+        yes: true,
+        ..crate::commands::SendRequest::new("@backend", "Test message for backend agents")
+    });
 
     // Doit réussir ou échouer de manière contrôlée (pas de panic)
     assert!(result.is_ok() || result.is_err());
@@ -130,17 +135,14 @@ fn m7_acceptance_send_to_all_works_correctly() {
     let (project_path, providers_path) = create_m7_acceptance_test_config(&temp_dir);
 
     // Test send --to @all (doit router vers tous les agents)
-    let result = crate::commands::run_send(
-        Some(&project_path),
-        Some(&providers_path),
-        "@all",
-        "Test message for all agents",
-        None,
-        None,
-        Some(5000), // 5s timeout
-        crate::cli::commands::Format::Text,
-        false,
-    );
+    let result = crate::commands::run_send(&crate::commands::SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        timeout_ms_flag: Some(5000), // 5s timeout
+        progress: false,
+        yes: true,
+        ..crate::commands::SendRequest::new("@all", "Test message for all agents")
+    });
 
     // Doit réussir ou échouer de manière contrôlée (pas de panic)
     assert!(result.is_ok() || result.is_err());
@@ -169,10 +171,10 @@ fn m7_acceptance_supervisor_receives_system_logs() {
     let roles = ["backend", "frontend", "devops"];
     
     // Créer des logs de test
-    create_test_logs(project, &roles);
+    let logs_base = create_test_logs(project, &roles);
     
     // Créer un supervisor subscription
-    let mut subscription = crate::supervisor::subscription::SupervisorSubscription::new(project.to_string());
+    let mut subscription = test_logs_subscription(&logs_base, project);
     
     // Tester la détection d'événements routed
     let routed_events = subscription.tail_and_filter("backend".to_string(), Some("routed".to_string()), 100)
@@ -204,13 +206,13 @@ fn m7_acceptance_supervisor_metrics_validation() {
     let roles = ["backend", "frontend", "devops"];
     
     // Créer des logs de test avec événements routed
-    create_test_logs(project, &roles);
+    let logs_base = create_test_logs(project, &roles);
     
     // Créer un supervisor manager
     let manager = crate::supervisor::manager::SupervisorManager::new();
     
     // Tester le calcul de métriques routed
-    let logs_dir = format!("./logs/{}", project);
+    let logs_dir = logs_base.path().join(project).to_string_lossy().to_string();
     let mut all_lines = Vec::new();
     
     for role in &roles {
@@ -243,17 +245,14 @@ fn m7_acceptance_exit_codes_validation() {
     let (project_path, providers_path) = create_m7_acceptance_test_config(&temp_dir);
     
     // Test avec configuration valide - doit retourner exit code approprié
-    let result = crate::commands::run_send(
-        Some(&project_path),
-        Some(&providers_path),
-        "@backend",
-        "Test message",
-        None,
-        None,
-        Some(1000), // 1s timeout
-        crate::cli::commands::Format::Text,
-        false,
-    );
+    let result = crate::commands::run_send(&crate::commands::SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        timeout_ms_flag: Some(1000), // 1s timeout
+        progress: false,
+        yes: true,
+        ..crate::commands::SendRequest::new("@backend", "Test message")
+    });
     
     // Doit retourner un Result (pas de panic)
     match result {
@@ -300,38 +299,32 @@ fn m7_acceptance_specifications_validation() {
     let (project_path, providers_path) = create_m7_acceptance_test_config(&temp_dir);
     
     // Test routing vers role spécifique
-    let role_result = crate::commands::run_send(
-        Some(&project_path),
-        Some(&providers_path),
-        "@backend",
-        "Role routing test",
-        None,
-        None,
-        Some(1000),
-        crate::cli::commands::Format::Text,
-        false,
-    );
+    let role_result = crate::commands::run_send(&crate::commands::SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        timeout_ms_flag: Some(1000),
+        progress: false,
+        yes: true,
+        ..crate::commands::SendRequest::new("@backend", "Role routing test")
+    });
     assert!(role_result.is_ok() || role_result.is_err(), "Role routing should work");
     
     // Test routing vers tous les agents
-    let all_result = crate::commands::run_send(
-        Some(&project_path),
-        Some(&providers_path),
-        "@all",
-        "All routing test",
-        None,
-        None,
-        Some(1000),
-        crate::cli::commands::Format::Text,
-        false,
-    );
+    let all_result = crate::commands::run_send(&crate::commands::SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        timeout_ms_flag: Some(1000),
+        progress: false,
+        yes: true,
+        ..crate::commands::SendRequest::new("@all", "All routing test")
+    });
     assert!(all_result.is_ok() || all_result.is_err(), "All routing should work");
     
     // 3. Must: supervisor receives system log entries
     let project = "m7-acceptance-test";
-    create_test_logs(project, &["backend", "frontend"]);
+    let logs_base = create_test_logs(project, &["backend", "frontend"]);
     
-    let mut subscription = crate::supervisor::subscription::SupervisorSubscription::new(project.to_string());
+    let mut subscription = test_logs_subscription(&logs_base, project);
     let events = subscription.tail_and_filter("backend".to_string(), Some("routed".to_string()), 100);
     assert!(events.is_ok(), "Supervisor should be able to receive system log entries");
 }
@@ -346,17 +339,14 @@ fn m7_acceptance_system_robustness_validation() {
     let invalid_targets = ["@nonexistent", "@", "invalid", ""];
     
     for target in &invalid_targets {
-        let result = crate::commands::run_send(
-            Some(&project_path),
-            Some(&providers_path),
-            target,
-            "Test message",
-            None,
-            None,
-            Some(1000),
-            crate::cli::commands::Format::Text,
-            false,
-        );
+        let result = crate::commands::run_send(&crate::commands::SendRequest {
+            project_path_opt: Some(&project_path),
+            providers_path_opt: Some(&providers_path),
+            timeout_ms_flag: Some(1000),
+            progress: false,
+            yes: true,
+            ..crate::commands::SendRequest::new(target, "Test message")
+        });
         
         // Doit retourner une erreur contrôlée (pas de panic)
         assert!(result.is_err(), "Should handle invalid target '{}' gracefully", target);
@@ -369,17 +359,14 @@ fn m7_acceptance_system_robustness_validation() {
     }
     
     // Test avec timeout - doit gérer les timeouts
-    let timeout_result = crate::commands::run_send(
-        Some(&project_path),
-        Some(&providers_path),
-        "@backend",
-        "Test message",
-        None,
-        None,
-        Some(1), // 1ms timeout (très court)
-        crate::cli::commands::Format::Text,
-        false,
-    );
+    let timeout_result = crate::commands::run_send(&crate::commands::SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        timeout_ms_flag: Some(1), // 1ms timeout (très court)
+        progress: false,
+        yes: true,
+        ..crate::commands::SendRequest::new("@backend", "Test message")
+    });
     
     // Doit gérer le timeout gracieusement
     assert!(timeout_result.is_ok() || timeout_result.is_err(), "Should handle timeout gracefully");
@@ -393,25 +380,24 @@ fn m7_acceptance_complete_integration_validation() {
     let project = "m7-acceptance-test";
     
     // 1. Envoyer un message à tous les agents
-    let send_result = crate::commands::run_send(
-        Some(&project_path),
-        Some(&providers_path),
-        "@all",
-        "Integration test message",
-        None,
-        None,
-        Some(5000),
-        crate::cli::commands::Format::Text,
-        false,
-    );
+    let send_result = crate::commands::run_send(&crate::commands::SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        timeout_ms_flag: Some(5000),
+        progress: false,
+        yes: true,
+        ..crate::commands::SendRequest::new("@all", "Integration test message")
+    });
     
     // 2. Créer des logs simulés si l'envoi a réussi
-    if send_result.is_ok() {
-        create_test_logs(project, &["backend", "frontend", "devops"]);
-    }
+    let logs_base = if send_result.is_ok() {
+        create_test_logs(project, &["backend", "frontend", "devops"])
+    } else {
+        TempDir::new().unwrap()
+    };
     
     // 3. Vérifier que le supervisor peut recevoir et traiter les logs
-    let mut subscription = crate::supervisor::subscription::SupervisorSubscription::new(project.to_string());
+    let mut subscription = test_logs_subscription(&logs_base, project);
     
     // Tester la détection d'événements
     let backend_events = subscription.tail_and_filter("backend".to_string(), Some("routed".to_string()), 100);
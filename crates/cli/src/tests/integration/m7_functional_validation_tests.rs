@@ -81,40 +81,35 @@ fn m7_functional_send_to_role_generates_routed_events() {
     assert!(result.is_ok() || result.as_ref().err().map(|e| e.to_string()).unwrap_or_default().contains("UNIQUE constraint"));
     
     // Ajouter les agents
-    let _ = crate::commands::run_agent_add(
-        "m7-functional-test",
-        "backend1",
-        "backend",
-        "gemini",
-        "2.0",
-        &[],
-        "You are a backend developer",
-        None,
-    );
-    
-    let _ = crate::commands::run_agent_add(
-        "m7-functional-test",
-        "frontend1",
-        "frontend",
-        "gemini",
-        "2.0",
-        &[],
-        "You are a frontend developer",
-        None,
-    );
+    let _ = crate::commands::run_agent_add(&crate::commands::AgentAddRequest {
+        project: "m7-functional-test",
+        name: "backend1",
+        role: "backend",
+        provider: "gemini",
+        model: "2.0",
+        system_prompt: "You are a backend developer",
+        ..crate::commands::AgentAddRequest::default()
+    });
+    
+    let _ = crate::commands::run_agent_add(&crate::commands::AgentAddRequest {
+        project: "m7-functional-test",
+        name: "frontend1",
+        role: "frontend",
+        provider: "gemini",
+        model: "2.0",
+        system_prompt: "You are a frontend developer",
+        ..crate::commands::AgentAddRequest::default()
+    });
     
     // Tester send --to @backend
-    let result = crate::commands::run_send(
-        Some(&project_path),
-        Some(&providers_path),
-        "@backend",
-        "Test message for backend agents",
-        None,
-        None,
-        Some(5000), // 5s timeout
-        crate::cli::commands::Format::Text,
-        false,
-    );
+    let result = crate::commands::run_send(&crate::commands::SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        timeout_ms_flag: Some(5000), // 5s timeout
+        progress: false,
+        yes: true,
+        ..crate::commands::SendRequest::new("@backend", "Test message for backend agents")
+    });
     
     // Vérifier que la commande s'exécute (peut échouer si les providers ne sont pas disponibles, mais la logique doit fonctionner)
     match result {
@@ -153,17 +148,14 @@ fn m7_functional_send_to_all_broadcasts_to_all_agents() {
     );
     
     // Tester send --to @all
-    let result = crate::commands::run_send(
-        Some(&project_path),
-        Some(&providers_path),
-        "@all",
-        "Broadcast message to all agents",
-        None,
-        None,
-        Some(5000),
-        crate::cli::commands::Format::Text,
-        false,
-    );
+    let result = crate::commands::run_send(&crate::commands::SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        timeout_ms_flag: Some(5000),
+        progress: false,
+        yes: true,
+        ..crate::commands::SendRequest::new("@all", "Broadcast message to all agents")
+    });
     
     // Vérifier que la commande s'exécute
     match result {
@@ -268,6 +260,11 @@ fn m7_functional_metrics_are_calculated_correctly() {
             broadcast_id: Some("broadcast-123".to_string()),
             session_id: Some("session-1".to_string()),
             message_id: Some("msg-1".to_string()),
+            direction: "system".to_string(),
+            exit_code: None,
+            lines: None,
+            bytes: None,
+            cached: None,
         },
         crate::logging::events::NdjsonEvent {
             ts: "2025-01-15T10:00:01.000Z".to_string(),
@@ -282,6 +279,11 @@ fn m7_functional_metrics_are_calculated_correctly() {
             broadcast_id: Some("broadcast-123".to_string()),
             session_id: Some("session-2".to_string()),
             message_id: Some("msg-2".to_string()),
+            direction: "system".to_string(),
+            exit_code: None,
+            lines: None,
+            bytes: None,
+            cached: None,
         },
         crate::logging::events::NdjsonEvent {
             ts: "2025-01-15T10:00:02.000Z".to_string(),
@@ -296,6 +298,11 @@ fn m7_functional_metrics_are_calculated_correctly() {
             broadcast_id: Some("broadcast-456".to_string()),
             session_id: Some("session-3".to_string()),
             message_id: Some("msg-3".to_string()),
+            direction: "system".to_string(),
+            exit_code: None,
+            lines: None,
+            bytes: None,
+            cached: None,
         },
     ];
     
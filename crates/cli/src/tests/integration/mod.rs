@@ -1,18 +1,26 @@
 //! Integration tests
 
+pub mod fake_provider;
 pub mod config_tests;
 pub mod doctor_tests;
 pub mod db_tests;
 pub mod send_tests;
 pub mod session_tests;
 pub mod agent_tests;
+pub mod project_tests;
+pub mod stats_tests;
+pub mod completions_tests;
+pub mod logs_tests;
+pub mod upgrade_tests;
 pub mod tmux_tests;
+pub mod backend_tests;
 pub mod broadcast_tests;
 pub mod performance_tests;
 pub mod tui_integration_tests;
 pub mod tui_performance_tests;
 pub mod tui_compatibility_tests;
 pub mod tui_regression_tests;
+pub mod tui_logs_tests;
 pub mod supervisor_subscription_tests;
 pub mod supervisor_aggregation_tests;
 pub mod broadcast_supervisor_integration_tests;
@@ -87,16 +87,16 @@ fn setup_performance_test_database(temp_dir: &TempDir, agent_count: usize) -> St
     
     // Add agents
     for i in 1..=agent_count {
-        let _ = run_agent_add(
-            "performance-test",
-            &format!("agent{}", i),
-            "developer",
-            "claude",
-            "claude-3-5-sonnet-20241022",
-            &[],
-            &format!("/tmp/agent{}", i),
-            Some(&db_path.to_string_lossy())
-        );
+        let _ = run_agent_add(&AgentAddRequest {
+            project: "performance-test",
+            name: &format!("agent{}", i),
+            role: "developer",
+            provider: "claude",
+            model: "claude-3-5-sonnet-20241022",
+            system_prompt: &format!("/tmp/agent{}", i),
+            db_path: Some(&db_path.to_string_lossy()),
+            ..AgentAddRequest::default()
+        });
     }
     
     db_path.to_string_lossy().to_string()
@@ -0,0 +1,91 @@
+//! Integration tests for project commands
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::run_project_rename;
+    use crate::utils::CliError;
+    use db::{open_or_create_db, insert_project, find_project_id, IdOrName};
+    use std::fs;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn setup(temp_dir: &TempDir, from: &str, project_yaml: &str) -> (String, String, String) {
+        let db_path = temp_dir.path().join("test.db").to_string_lossy().to_string();
+        let conn = open_or_create_db(&db_path).unwrap();
+        insert_project(&conn, from).unwrap();
+        let config_dir = temp_dir.path().join("config");
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::write(config_dir.join("project.yaml"), project_yaml).unwrap();
+        let logs_dir = temp_dir.path().join("logs").to_string_lossy().to_string();
+        fs::create_dir_all(format!("{}/{}", logs_dir, from)).unwrap();
+        fs::write(format!("{}/{}/backend.ndjson", logs_dir, from), "{}\n").unwrap();
+        (db_path, config_dir.to_string_lossy().to_string(), logs_dir)
+    }
+
+    #[test]
+    fn test_project_rename_updates_db_logs_and_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let from = "rename-src".to_string();
+        let to = "rename-dst".to_string();
+        let yaml = format!("schema_version: 1\nproject: {}\nagents: []\n", from);
+        let (db_path, config_dir, logs_dir) = setup(&temp_dir, &from, &yaml);
+
+        run_project_rename(&from, &to, Some(&db_path), Some(&config_dir), Some(&logs_dir), true).unwrap();
+
+        let conn = open_or_create_db(&db_path).unwrap();
+        assert!(find_project_id(&conn, IdOrName::Name(&from)).unwrap().is_none());
+        assert!(find_project_id(&conn, IdOrName::Name(&to)).unwrap().is_some());
+        assert!(!Path::new(&format!("{}/{}", logs_dir, from)).exists());
+        assert!(Path::new(&format!("{}/{}/backend.ndjson", logs_dir, to)).exists());
+        let config = fs::read_to_string(format!("{}/project.yaml", config_dir)).unwrap();
+        let project = config_model::parse_project_yaml(&config).unwrap();
+        assert_eq!(project.project, to);
+    }
+
+    #[test]
+    fn test_project_rename_reports_failed_config_step_and_keeps_other_steps() {
+        let temp_dir = TempDir::new().unwrap();
+        let from = "rename-src".to_string();
+        let to = "rename-dst".to_string();
+        let (db_path, config_dir, logs_dir) = setup(&temp_dir, &from, "project: [unterminated\n");
+
+        let err = run_project_rename(&from, &to, Some(&db_path), Some(&config_dir), Some(&logs_dir), true).unwrap_err();
+        assert!(matches!(&err, CliError::Other(msg) if msg.contains("config")), "{:?}", err);
+
+        // The failed config step does not undo the database and logs steps
+        let conn = open_or_create_db(&db_path).unwrap();
+        assert!(find_project_id(&conn, IdOrName::Name(&from)).unwrap().is_none());
+        assert!(find_project_id(&conn, IdOrName::Name(&to)).unwrap().is_some());
+        assert!(Path::new(&format!("{}/{}/backend.ndjson", logs_dir, to)).exists());
+        assert_eq!(fs::read_to_string(format!("{}/project.yaml", config_dir)).unwrap(), "project: [unterminated\n");
+    }
+
+    #[test]
+    fn test_project_rename_leaves_config_without_update_flag() {
+        let temp_dir = TempDir::new().unwrap();
+        let from = "rename-src".to_string();
+        let to = "rename-dst".to_string();
+        let yaml = format!("schema_version: 1\nproject: {}\nagents: []\n", from);
+        let (db_path, config_dir, logs_dir) = setup(&temp_dir, &from, &yaml);
+
+        run_project_rename(&from, &to, Some(&db_path), Some(&config_dir), Some(&logs_dir), false).unwrap();
+
+        let conn = open_or_create_db(&db_path).unwrap();
+        assert!(find_project_id(&conn, IdOrName::Name(&to)).unwrap().is_some());
+        assert_eq!(fs::read_to_string(format!("{}/project.yaml", config_dir)).unwrap(), yaml);
+    }
+
+    #[test]
+    fn test_project_rename_rejects_taken_name() {
+        let temp_dir = TempDir::new().unwrap();
+        let from = "rename-src".to_string();
+        let yaml = format!("schema_version: 1\nproject: {}\nagents: []\n", from);
+        let (db_path, config_dir, logs_dir) = setup(&temp_dir, &from, &yaml);
+        let conn = open_or_create_db(&db_path).unwrap();
+        insert_project(&conn, "already-taken").unwrap();
+
+        let err = run_project_rename(&from, "already-taken", Some(&db_path), Some(&config_dir), Some(&logs_dir), true).unwrap_err();
+        assert!(matches!(err, CliError::Validation { .. }), "{:?}", err);
+        assert!(Path::new(&format!("{}/{}", logs_dir, from)).exists());
+    }
+}
@@ -1,7 +1,8 @@
 //! Integration tests for send command (Routing M7)
 
 use tempfile::TempDir;
-use crate::commands::run_send;
+use crate::commands::{run_send, SendRequest};
+use super::fake_provider::{fake_template, run_cli_in, write_providers_yaml, FakeProvider};
 
 /// Helper to create a minimal test project with multiple agents/roles
 fn create_test_project_config(temp_dir: &TempDir) -> (String, String) {
@@ -28,28 +29,13 @@ agents:
     allowed_tools: []
 "#;
 
-    let providers_config = r#"
-providers:
-  gemini:
-    cmd: echo
-    oneshot_args: ["--version"]
-    repl_args: ["--version"]
-  claude:
-    cmd: echo
-    oneshot_args: ["--version"]
-    repl_args: ["--version"]
-"#;
-
     let project_path = temp_dir.path().join("project.yaml");
-    let providers_path = temp_dir.path().join("providers.yaml");
-
     std::fs::write(&project_path, project_config).unwrap();
-    std::fs::write(&providers_path, providers_config).unwrap();
+    let gemini = FakeProvider::new().stdout("gemini answer").install(temp_dir.path(), "gemini");
+    let claude = FakeProvider::new().stdout("claude answer").install(temp_dir.path(), "claude");
+    let providers_path = write_providers_yaml(temp_dir.path(), &[("gemini", &gemini), ("claude", &claude)]);
 
-    (
-        project_path.to_string_lossy().to_string(),
-        providers_path.to_string_lossy().to_string(),
-    )
+    (project_path.to_string_lossy().to_string(), providers_path)
 }
 
 #[test]
@@ -58,17 +44,14 @@ fn send_routes_to_all_with_at_all() {
     let (project_path, providers_path) = create_test_project_config(&temp_dir);
 
     // Should not panic; return Ok or Err but must parse and route targets
-    let result = run_send(
-        Some(&project_path),
-        Some(&providers_path),
-        "@all",
-        "Hello",
-        None,
-        None,
-        Some(1000),
-        crate::cli::commands::Format::Text,
-        false,
-    );
+    let result = run_send(&SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        timeout_ms_flag: Some(1000),
+        progress: false,
+        yes: true,
+        ..SendRequest::new("@all", "Hello")
+    });
 
     assert!(result.is_ok() || result.is_err());
 }
@@ -78,17 +61,14 @@ fn send_routes_to_role_with_at_role() {
     let temp_dir = TempDir::new().unwrap();
     let (project_path, providers_path) = create_test_project_config(&temp_dir);
 
-    let result = run_send(
-        Some(&project_path),
-        Some(&providers_path),
-        "@backend",
-        "Hello",
-        None,
-        None,
-        Some(1000),
-        crate::cli::commands::Format::Text,
-        false,
-    );
+    let result = run_send(&SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        timeout_ms_flag: Some(1000),
+        progress: false,
+        yes: true,
+        ..SendRequest::new("@backend", "Hello")
+    });
 
     assert!(result.is_ok() || result.is_err());
 }
@@ -98,18 +78,1053 @@ fn send_errors_on_invalid_role() {
     let temp_dir = TempDir::new().unwrap();
     let (project_path, providers_path) = create_test_project_config(&temp_dir);
 
-    let result = run_send(
-        Some(&project_path),
-        Some(&providers_path),
-        "@unknownrole",
-        "Hello",
-        None,
-        None,
-        Some(1000),
-        crate::cli::commands::Format::Text,
-        false,
-    );
+    let result = run_send(&SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        timeout_ms_flag: Some(1000),
+        progress: false,
+        yes: true,
+        ..SendRequest::new("@unknownrole", "Hello")
+    });
 
     // Expect graceful error (exit code 2 path inside run_send). From tests we just ensure no panic.
     assert!(result.is_err() || result.is_ok());
-}
\ No newline at end of file
+}
+#[test]
+fn send_prompt_file_placeholder_passes_temp_file_and_cleans_up() {
+    let temp_dir = TempDir::new().unwrap();
+    let copy_path = temp_dir.path().join("prompt-copy.txt");
+    let seen_path = temp_dir.path().join("prompt-path.txt");
+    let tpl = config_model::ProviderTemplate {
+        extends: None,
+        alias_of: None,
+        cmd: "sh".into(),
+        oneshot_args: vec![
+            "-c".into(),
+            r#"cat "$1" > "$2"; printf '%s' "$1" > "$3""#.into(),
+            "sh".into(),
+            "{prompt_file}".into(),
+            copy_path.to_string_lossy().to_string(),
+            seen_path.to_string_lossy().to_string(),
+        ],
+        repl_args: vec![],
+        create_chat_args: None,
+        allowlist_flag: None,
+        forbid_flags: None,
+        required_env_keys: vec![],
+        known_tools: None,
+        timeouts: None,
+        session_ttl_ms: None,
+        session_expiry_slack_ms: None,
+    };
+    let prompt = "line one\nline two ".repeat(1000);
+
+    let code = crate::commands::send::run_oneshot_provider(&oneshot_target("test-prompt-file", "filecat", &tpl), &prompt, false, None, None, None, &crate::utils::RandomIds);
+
+    assert_eq!(code, 0);
+    assert_eq!(std::fs::read_to_string(&copy_path).unwrap(), prompt);
+    let passed = std::fs::read_to_string(&seen_path).unwrap();
+    assert!(passed.contains("multi-agents-prompt-"));
+    assert!(!std::path::Path::new(&passed).exists());
+}
+
+/// One-shot run of agent `worker` (role `backend`) with no session flags and a 5s timeout
+fn oneshot_target<'a>(project: &'a str, provider_key: &'a str, tpl: &'a config_model::ProviderTemplate) -> crate::commands::send::OneshotTarget<'a> {
+    crate::commands::send::OneshotTarget {
+        project,
+        agent_role: "backend",
+        agent_name: "worker",
+        provider_key,
+        family: provider_key,
+        tpl,
+        system_prompt: "sp",
+        allowed_tools: &[],
+        session_id_opt: None,
+        chat_id_opt: None,
+        conversation_id: None,
+        timeout_ms: 5000,
+        inactivity_ms: 0,
+        echo: true,
+    }
+}
+
+/// The generator `run_send` uses, for requests built directly
+fn random_ids() -> std::sync::Arc<dyn crate::utils::IdGenerator> {
+    std::sync::Arc::new(crate::utils::RandomIds)
+}
+
+#[test]
+fn send_without_session_id_passes_generated_ids_to_provider() {
+    use crate::commands::send::{run_oneshot_provider, OneshotTarget};
+    use crate::utils::SequenceIds;
+
+    let temp_dir = TempDir::new().unwrap();
+    let seen_path = temp_dir.path().join("args.txt");
+    let mut tpl = fake_template(&FakeProvider::new().record(&seen_path).install(temp_dir.path(), "provider"));
+    tpl.oneshot_args = vec!["--session-id".into(), "{session_id}".into()];
+    let ids = SequenceIds::new("fixed");
+    for provider in ["claude", "gemini", "ollama"] {
+        let code = run_oneshot_provider(&oneshot_target("test-ids", provider, &tpl), "hi", false, None, None, None, &ids);
+        assert_eq!(code, 0);
+    }
+    // An explicit session id is passed through and consumes no generated id
+    let code = run_oneshot_provider(&OneshotTarget {
+        session_id_opt: Some("given"),
+        ..oneshot_target("test-ids", "claude", &tpl)
+    }, "hi", false, None, None, None, &ids);
+    assert_eq!(code, 0);
+    // Aliases and extends children get their root provider's prefix
+    for (provider, family) in [("claude-careful", "claude"), ("gemini-fast", "gemini")] {
+        let code = run_oneshot_provider(&OneshotTarget { family, ..oneshot_target("test-ids", provider, &tpl) }, "hi", false, None, None, None, &ids);
+        assert_eq!(code, 0);
+    }
+    let seen = std::fs::read_to_string(&seen_path).unwrap();
+    let passed: Vec<&str> = seen.lines().filter(|l| *l != "--session-id").collect();
+    assert_eq!(passed, ["valid_session_fixed-1", "valid_context_fixed-2", "fixed-3", "given", "valid_session_fixed-4", "valid_context_fixed-5"]);
+}
+
+/// One-shot template running `script` through sh, with `inactivity_ms` as its provider default
+fn scripted_provider(script: &str, inactivity_ms: u64) -> config_model::ProviderTemplate {
+    let yaml = format!(
+        "schema_version: 1\nproviders:\n  scripted:\n    cmd: sh\n    oneshot_args: [\"-c\", {:?}, \"sh\", \"{{prompt}}\"]\n    timeouts:\n      inactivity_ms: {}\n",
+        script, inactivity_ms
+    );
+    config_model::parse_providers_yaml(&yaml).unwrap().providers.remove("scripted").unwrap()
+}
+
+#[test]
+fn send_inactivity_timeout_spares_steady_stream_and_kills_stall() {
+    use crate::commands::send::{run_oneshot_provider, send_timeouts, OneshotTarget, SEND_INACTIVITY_TIMEOUT};
+
+    let dir = TempDir::new().unwrap();
+    let mut steady = fake_template(&FakeProvider::new().stream(&["part 1", "part 2", "part 3", "part 4", "part 5"], 0.2).install(dir.path(), "steady"));
+    steady.timeouts = Some(config_model::ProviderTimeouts { total_ms: None, inactivity_ms: Some(600) });
+    let (total, inactivity) = send_timeouts(Some(&steady), None, None);
+    assert_eq!(inactivity, 600);
+    let code = run_oneshot_provider(&OneshotTarget {
+        timeout_ms: total,
+        inactivity_ms: inactivity,
+        ..oneshot_target("inactivity-proj", "scripted", &steady)
+    }, "hi", false, None, None, None, &crate::utils::RandomIds);
+    assert_eq!(code, 0, "a provider that keeps printing outlives the inactivity window");
+
+    let mut stalled = fake_template(&FakeProvider::new().stdout("thinking").sleep(30.0).install(dir.path(), "stalled"));
+    stalled.timeouts = Some(config_model::ProviderTimeouts { total_ms: None, inactivity_ms: Some(300) });
+    let started = std::time::Instant::now();
+    let (total, inactivity) = send_timeouts(Some(&stalled), None, None);
+    let code = run_oneshot_provider(&OneshotTarget {
+        timeout_ms: total,
+        inactivity_ms: inactivity,
+        ..oneshot_target("inactivity-proj", "scripted", &stalled)
+    }, "hi", false, None, None, None, &crate::utils::RandomIds);
+    assert_eq!(code, SEND_INACTIVITY_TIMEOUT);
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+}
+
+#[test]
+fn send_to_cursor_without_chat_id_creates_one_then_resumes_it() {
+    use crate::commands::send::run_oneshot_provider;
+
+    let dir = TempDir::new().unwrap();
+    let seen = dir.path().join("args.txt");
+    let cursor = FakeProvider::new().stdout("Creating chat...\nchat-77").record(&seen).install(dir.path(), "cursor-agent");
+    let providers_path = write_providers_yaml(dir.path(), &[("cursor-agent", &cursor)]);
+    let mut tpl = config_model::parse_providers_yaml(&std::fs::read_to_string(providers_path).unwrap()).unwrap().providers.remove("cursor-agent").unwrap();
+    tpl.oneshot_args = vec!["--resume".into(), "{chat_id}".into(), "{prompt}".into()];
+
+    let code = run_oneshot_provider(&oneshot_target("cursor-chat-proj", "cursor-agent", &tpl), "hi", false, None, None, None, &crate::utils::RandomIds);
+    assert_eq!(code, 0);
+    let args = std::fs::read_to_string(&seen).unwrap();
+    assert!(args.starts_with("create-chat\n--resume\nchat-77\nhi\n"), "{}", args);
+}
+
+#[test]
+fn send_reads_stream_json_and_maps_provider_failures_to_exit_codes() {
+    use crate::commands::send::run_oneshot_provider;
+
+    let dir = TempDir::new().unwrap();
+    let envelope = r#"{"type":"result","content":[{"type":"text","text":"streamed"}],"session_id":"sess-1"}"#;
+    let claude = FakeProvider::new().stream(&["not json", envelope], 0.0).install(dir.path(), "claude");
+    let mut tpl = fake_template(&claude);
+    tpl.oneshot_args = vec!["--output-format".into(), "json".into(), "{prompt}".into()];
+    let mut output = String::new();
+    let code = run_oneshot_provider(&oneshot_target("stream-proj", "claude", &tpl), "hi", false, None, None, Some(&mut output), &crate::utils::RandomIds);
+    assert_eq!(code, 0);
+    assert!(output.contains("streamed"), "{}", output);
+
+    let failing = fake_template(&FakeProvider::new().stderr("quota exceeded").exit(3).install(dir.path(), "failing"));
+    let code = run_oneshot_provider(&oneshot_target("stream-proj", "gemini", &failing), "hi", false, None, None, None, &crate::utils::RandomIds);
+    assert_eq!(code, 4, "a provider exiting non-zero is a failed send");
+
+    let missing = fake_template(&dir.path().join("not-installed").to_string_lossy());
+    let code = run_oneshot_provider(&oneshot_target("stream-proj", "gemini", &missing), "hi", false, None, None, None, &crate::utils::RandomIds);
+    assert_eq!(code, 3, "a missing provider binary is not configured");
+}
+
+#[test]
+fn send_logs_user_prompt_event_before_provider_output() {
+    use clap::Parser;
+    use crate::logging::{agent_log_path, Event};
+
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("project.yaml"),
+        "schema_version: 1\nproject: prompt-log\nagents:\n  - name: worker\n    role: backend\n    provider: gemini\n    model: m\n    allowed_tools: [Read]\n    system_prompt: \"\"\n",
+    ).unwrap();
+    // The provider's output is its own log file as it stands when the provider runs; `run_cli_in`
+    // sets the log base to `dir/logs`
+    let log = agent_log_path(&dir.path().join("logs").to_string_lossy(), "prompt-log", "worker", "backend");
+    std::fs::write(dir.path().join("providers.yaml"), format!(
+        "schema_version: 1\nproviders:\n  gemini:\n    cmd: sh\n    oneshot_args: [\"-c\", \"cat '{}'\"]\n    repl_args: []\n", log,
+    )).unwrap();
+    let out = run_cli_in(&dir, &["send", "--to", "worker", "--message", "hello there", "--quiet", "--no-cache",
+        "--project-file", "project.yaml", "--providers-file", "providers.yaml"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    let output = String::from_utf8_lossy(&out.stdout);
+    let events: Vec<Event> = output.lines().map(|l| Event::from_line(l).unwrap()).collect();
+    assert_eq!(events.iter().map(|e| e.event.as_str()).collect::<Vec<_>>(), ["start", "prompt"], "{}", output);
+    assert_eq!(events[1].direction, "user");
+    assert_eq!(events[1].text.as_deref(), Some("hello there"));
+
+    let cli = crate::Cli::try_parse_from(["multi-agents", "send", "--to", "@all", "--message", "hi", "--echo-prompt"]).unwrap();
+    assert!(matches!(cli.cmd, crate::cli::commands::Commands::Send { echo_prompt: true, .. }));
+}
+
+#[test]
+fn send_end_event_counts_stdout_lines_and_bytes() {
+    use crate::commands::send::send_end_event;
+    use crate::utils::{run_with_timeout_streaming, StreamContext};
+
+    // CRLF and invalid UTF-8 count as read, not as decoded
+    let script = "printf 'one\\r\\ntwo\\n\\377three\\n'; echo oops >&2";
+    let outcome = run_with_timeout_streaming(
+        "sh", &["-c", script], std::time::Duration::from_secs(5), std::time::Duration::ZERO,
+        &StreamContext {
+            project: "end-counts-proj",
+            agent_role: "backend",
+            agent_name: "worker",
+            provider_key: "scripted",
+            family: "scripted",
+            session_id: "s1",
+            format: crate::providers::OutputFormat::Text,
+            progress: None,
+            echo: true,
+        },
+        None, None,
+    ).unwrap();
+    // stderr is not provider output
+    assert_eq!((outcome.stdout_lines, outcome.stdout_bytes), (3, 16));
+
+    let end = send_end_event("end-counts-proj", "backend", "scripted", "s1", &outcome, "worker");
+    assert_eq!(end.event, "end");
+    assert_eq!(end.exit_code, Some(0));
+    assert_eq!((end.lines, end.bytes), (Some(3), Some(16)));
+    assert!(end.to_line().ends_with(r#""exit_code":0,"dur_ms":null,"lines":3,"bytes":16,"cached":null}"#), "{}", end.to_line());
+}
+
+#[test]
+fn send_timeouts_prefer_flags_then_provider_block() {
+    use crate::commands::send::send_timeouts;
+    use crate::utils::DEFAULT_SEND_TIMEOUT_MS;
+
+    let tpl = scripted_provider("true", 30_000);
+    assert_eq!(send_timeouts(Some(&tpl), None, None), (DEFAULT_SEND_TIMEOUT_MS, 30_000));
+    assert_eq!(send_timeouts(Some(&tpl), Some(600_000), Some(0)), (600_000, 0));
+    assert_eq!(send_timeouts(None, None, None), (DEFAULT_SEND_TIMEOUT_MS, 0));
+}
+
+#[test]
+fn send_quiet_leaves_only_the_payload_on_stdout() {
+    let dir = TempDir::new().unwrap();
+    // A gemini that only ever answers "hello"
+    std::fs::write(dir.path().join("project.yaml"),
+        "schema_version: 1\nproject: quiet-proj\nagents:\n  - name: worker\n    role: backend\n    provider: gemini\n    model: m\n    allowed_tools: []\n    system_prompt: \"\"\n  - name: helper\n    role: frontend\n    provider: gemini\n    model: m\n    allowed_tools: []\n    system_prompt: \"\"\n",
+    ).unwrap();
+    let gemini = FakeProvider::new().stdout("hello").install(dir.path(), "gemini");
+    write_providers_yaml(dir.path(), &[("gemini", &gemini)]);
+    let files = ["--project-file", "project.yaml", "--providers-file", "providers.yaml"];
+    let send = |extra: &[&str]| {
+        let mut args = vec!["send", "--message", "hi"];
+        args.extend_from_slice(&files);
+        args.extend_from_slice(extra);
+        run_cli_in(&dir, &args)
+    };
+
+    // Without --quiet the project sync and the per-target headers are announced
+    let out = send(&["--to", "@all"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("Project 'quiet-proj' synchronized successfully\n"), "{}", stdout);
+    assert!(stdout.contains("=== role:backend provider:gemini ===\n"), "{}", stdout);
+
+    let out = send(&["--to", "worker", "--quiet"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "hello\n");
+
+    let out = send(&["--to", "@all", "--quiet"]);
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "hello\nhello\n");
+
+    // With --format json stdout is a single document carrying the replies
+    let out = send(&["--to", "worker", "--quiet", "--format", "json"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let doc: serde_json::Value = serde_json::from_slice(&out.stdout).expect("stdout is one JSON value");
+    assert_eq!(doc["status"], "ok");
+    assert_eq!(doc["replies"], serde_json::json!([{"agent": "worker", "role": "backend", "provider": "gemini", "output": "hello\n"}]));
+
+    // `session start --quiet` prints the bare conversation id
+    let mut args = vec!["session", "start", "--agent", "worker", "--quiet"];
+    args.extend_from_slice(&files);
+    let out = run_cli_in(&dir, &args);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let id = String::from_utf8(out.stdout).unwrap();
+    let id = id.strip_suffix('\n').unwrap();
+    assert!(!id.is_empty() && !id.contains('='), "{:?}", id);
+    let conn = db::open_or_create_db(dir.path().join("multi-agents.sqlite3").to_string_lossy().as_ref()).unwrap();
+    assert!(db::find_session(&conn, id).unwrap().is_some());
+}
+
+#[test]
+fn send_through_alias_provider_uses_base_session_manager() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("project.yaml"),
+        "schema_version: 1\nproject: alias-proj\nagents:\n  - name: careful\n    role: backend\n    provider: claude-careful\n    model: m\n    allowed_tools: [Read]\n    system_prompt: \"\"\n",
+    ).unwrap();
+    let claude = FakeProvider::new().stdout("claude answer").install(dir.path(), "claude");
+    let yaml = super::fake_provider::providers_yaml(&[("claude", &claude)]) + "  claude-careful:\n    alias_of: claude\n";
+    std::fs::write(dir.path().join("providers.yaml"), yaml).unwrap();
+
+    let out = run_cli_in(&dir, &["send", "--to", "careful", "--message", "hi", "--quiet", "--project-file", "project.yaml", "--providers-file", "providers.yaml"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "claude answer\n");
+
+    // The session belongs to the base provider, so session resume handles it like any claude session
+    let out = run_cli_in(&dir, &["session", "start", "--agent", "careful", "--quiet", "--project-file", "project.yaml", "--providers-file", "providers.yaml"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let id = String::from_utf8(out.stdout).unwrap();
+    let conn = db::open_or_create_db(dir.path().join("multi-agents.sqlite3").to_string_lossy().as_ref()).unwrap();
+    let session = db::find_session(&conn, id.trim()).unwrap().unwrap();
+    assert_eq!(session.provider, "claude");
+    assert!(session.provider_session_id.as_deref().unwrap().starts_with("valid_session_"), "{:?}", session.provider_session_id);
+}
+
+#[test]
+fn send_through_cursor_alias_creates_chat_and_streams_json() {
+    use crate::logging::{agent_log_path, Event};
+
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("project.yaml"),
+        "schema_version: 1\nproject: cursor-alias-proj\nagents:\n  - name: quick\n    role: backend\n    provider: quick-cursor\n    model: m\n    allowed_tools: []\n    system_prompt: \"\"\n",
+    ).unwrap();
+    let seen = dir.path().join("args.txt");
+    let cursor = FakeProvider::new().stdout("chat-77").record(&seen).install(dir.path(), "cursor-agent");
+    let yaml = super::fake_provider::providers_yaml(&[("cursor-agent", &cursor)])
+        .replace("oneshot_args: [\"{prompt}\"]", "oneshot_args: [\"--resume\", \"{chat_id}\", \"{prompt}\"]")
+        + "  quick-cursor:\n    alias_of: cursor-agent\n";
+    std::fs::write(dir.path().join("providers.yaml"), yaml).unwrap();
+
+    let out = run_cli_in(&dir, &["send", "--to", "quick", "--message", "hi", "--quiet", "--no-cache", "--project-file", "project.yaml", "--providers-file", "providers.yaml"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    // The chat is created on the fly and the reply is read as stream-json, as for cursor-agent itself
+    let args = std::fs::read_to_string(&seen).unwrap();
+    assert!(args.ends_with("create-chat\n--resume\nchat-77\nhi\n--output-format\nstream-json\n"), "{}", args);
+
+    let log = agent_log_path(&dir.path().join("logs").to_string_lossy(), "cursor-alias-proj", "quick", "backend");
+    let start = std::fs::read_to_string(log).unwrap().lines()
+        .filter_map(|l| Event::from_line(l).ok())
+        .find(|e| e.event == "start")
+        .unwrap();
+    assert_eq!(start.session_id.as_deref(), Some("chat-77"));
+}
+
+#[test]
+fn send_through_extends_child_uses_root_session_manager() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("project.yaml"),
+        "schema_version: 1\nproject: extends-proj\nagents:\n  - name: fast\n    role: backend\n    provider: gemini-fast\n    model: m\n    allowed_tools: [Read]\n    system_prompt: \"\"\n",
+    ).unwrap();
+    let gemini = FakeProvider::new().stdout("gemini answer").install(dir.path(), "gemini");
+    let fast = FakeProvider::new().stdout("fast answer").install(dir.path(), "gemini-fast");
+    let yaml = super::fake_provider::providers_yaml(&[("gemini", &gemini)]) + &format!("  gemini-fast:\n    extends: gemini\n    cmd: {:?}\n", fast);
+    std::fs::write(dir.path().join("providers.yaml"), yaml).unwrap();
+    let files = ["--project-file", "project.yaml", "--providers-file", "providers.yaml"];
+
+    let out = run_cli_in(&dir, &[&["send", "--to", "fast", "--message", "hi", "--quiet"][..], &files].concat());
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "fast answer\n");
+
+    let out = run_cli_in(&dir, &[&["session", "start", "--agent", "fast", "--quiet"][..], &files].concat());
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let id = String::from_utf8(out.stdout).unwrap();
+    let conn = db::open_or_create_db(dir.path().join("multi-agents.sqlite3").to_string_lossy().as_ref()).unwrap();
+    let session = db::find_session(&conn, id.trim()).unwrap().unwrap();
+    assert_eq!(session.provider, "gemini");
+    assert!(session.provider_session_id.as_deref().unwrap().starts_with("valid_context_"), "{:?}", session.provider_session_id);
+}
+
+#[test]
+fn send_cache_stores_the_answer_redacted() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("project.yaml"),
+        "schema_version: 1\nproject: cache-redact\ndefaults:\n  redaction: [\"sk-[a-z0-9]+\"]\nagents:\n  - name: worker\n    role: backend\n    provider: gemini\n    model: m\n    allowed_tools: [Read]\n    system_prompt: \"\"\n",
+    ).unwrap();
+    let gemini = FakeProvider::new().stdout("your key is sk-abc123").install(dir.path(), "gemini");
+    write_providers_yaml(dir.path(), &[("gemini", &gemini)]);
+
+    let out = run_cli_in(&dir, &["send", "--to", "worker", "--message", "hi", "--cache", "--quiet", "--project-file", "project.yaml", "--providers-file", "providers.yaml"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let conn = db::open_or_create_db(dir.path().join("multi-agents.sqlite3").to_string_lossy().as_ref()).unwrap();
+    let stored: String = conn.query_row("SELECT stdout FROM response_cache", [], |r| r.get(0)).unwrap();
+    assert_eq!(stored.trim_end(), "your key is [REDACTED]");
+}
+
+#[test]
+fn send_manifest_preview_is_redacted_unless_disabled() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("project.yaml"),
+        "schema_version: 1\nproject: manifest-redact\ndefaults:\n  redaction: [\"sk-[a-z0-9]+\"]\nagents:\n  - name: worker\n    role: backend\n    provider: gemini\n    model: m\n    allowed_tools: [Read]\n    system_prompt: \"\"\n",
+    ).unwrap();
+    let gemini = FakeProvider::new().stdout("ok").install(dir.path(), "gemini");
+    write_providers_yaml(dir.path(), &[("gemini", &gemini)]);
+    let preview = |extra: &[&str]| {
+        let mut args = vec!["send", "--to", "worker", "--message", "use sk-abc123", "--quiet", "--manifest", "manifest.json",
+            "--project-file", "project.yaml", "--providers-file", "providers.yaml"];
+        args.extend_from_slice(extra);
+        let out = run_cli_in(&dir, &args);
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        let manifest: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.path().join("manifest.json")).unwrap()).unwrap();
+        manifest["prompt"]["preview"].as_str().unwrap().to_string()
+    };
+
+    assert_eq!(preview(&[]), "use [REDACTED]");
+    assert_eq!(preview(&["--no-redaction"]), "use sk-abc123");
+}
+
+#[test]
+fn send_pool_never_exceeds_concurrency() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use crate::commands::send::{run_bounded, SendJob};
+
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let jobs: Vec<(String, SendJob)> = (0..10).map(|i| {
+        let running = running.clone();
+        let peak = peak.clone();
+        let job: SendJob = Box::new(move |_| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(30));
+            running.fetch_sub(1, Ordering::SeqCst);
+            i
+        });
+        (format!("agent-{}", i), job)
+    }).collect();
+
+    let mut results = run_bounded(3, jobs, false).unwrap();
+    assert_eq!(peak.load(Ordering::SeqCst), 3);
+    results.sort();
+    assert_eq!(results, (0..10).map(|i| (i, format!("agent-{}", i))).collect::<Vec<_>>());
+}
+
+#[test]
+fn send_pool_does_not_wait_on_slowest_target() {
+    use std::time::{Duration, Instant};
+    use crate::commands::send::{run_bounded, SendJob};
+
+    // One slow target must not hold back the next batch: the 6 fast ones share the 2 free slots
+    let mut jobs: Vec<(String, SendJob)> = vec![("slow".into(), Box::new(|_| { std::thread::sleep(Duration::from_millis(600)); 0 }))];
+    for i in 0..6 {
+        jobs.push((format!("fast-{}", i), Box::new(|_| { std::thread::sleep(Duration::from_millis(50)); 0 })));
+    }
+    let start = Instant::now();
+    let results = run_bounded(3, jobs, false).unwrap();
+    assert_eq!(results.len(), 7);
+    // Completion order: every fast target reports before the slow one
+    assert_eq!(results.last().unwrap().1, "slow");
+    assert!(start.elapsed() < Duration::from_millis(900), "elapsed {:?}", start.elapsed());
+}
+
+#[test]
+fn send_pool_reports_panicking_job_as_failure() {
+    use crate::commands::send::{run_bounded, SendJob};
+
+    let jobs: Vec<(String, SendJob)> = vec![
+        ("ok".into(), Box::new(|_| 0)),
+        ("boom".into(), Box::new(|_| panic!("provider thread crashed"))),
+    ];
+    let mut results = run_bounded(2, jobs, false).unwrap();
+    results.sort();
+    assert_eq!(results, vec![(0, "ok".to_string()), (1, "boom".to_string())]);
+}
+
+#[test]
+fn send_broadcast_status_from_target_codes() {
+    use crate::commands::send::{broadcast_status, SEND_CANCELLED};
+
+    let codes = |c: &[i32]| -> Vec<(i32, String)> { c.iter().map(|c| (*c, "k".to_string())).collect() };
+    assert_eq!(broadcast_status(&codes(&[0, 0])), db::BroadcastStatus::Completed);
+    assert_eq!(broadcast_status(&codes(&[0, 4, SEND_CANCELLED])), db::BroadcastStatus::PartialFailure);
+    assert_eq!(broadcast_status(&codes(&[5, SEND_CANCELLED])), db::BroadcastStatus::Failed);
+}
+
+#[test]
+fn send_fail_fast_cancels_slow_targets() {
+    use std::sync::atomic::Ordering;
+    use std::time::{Duration, Instant};
+    use crate::commands::send::{run_bounded, SendJob, SEND_CANCELLED};
+
+    // Slow targets poll the cancel flag like a streaming provider run would
+    let slow = || -> SendJob {
+        Box::new(|cancel| {
+            let start = Instant::now();
+            while start.elapsed() < Duration::from_secs(5) {
+                if cancel.load(Ordering::SeqCst) { return SEND_CANCELLED; }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            0
+        })
+    };
+    let build = || -> Vec<(String, SendJob)> {
+        let mut jobs: Vec<(String, SendJob)> = vec![("fails".into(), Box::new(|_| { std::thread::sleep(Duration::from_millis(50)); 4 }))];
+        for i in 0..5 {
+            jobs.push((format!("slow-{}", i), slow()));
+        }
+        jobs
+    };
+
+    let start = Instant::now();
+    let results = run_bounded(3, build(), true).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(2), "elapsed {:?}", start.elapsed());
+    assert_eq!(results.len(), 6);
+    assert_eq!(results[0], (4, "fails".to_string()));
+    assert!(results[1..].iter().all(|(code, _)| *code == SEND_CANCELLED), "{:?}", results);
+
+    // Default wait-for-all: a fatal code does not stop the others
+    let cancel_free: Vec<(String, SendJob)> = vec![
+        ("fails".into(), Box::new(|_| 4)),
+        ("slow".into(), Box::new(|cancel| { std::thread::sleep(Duration::from_millis(100)); if cancel.load(Ordering::SeqCst) { SEND_CANCELLED } else { 0 } })),
+    ];
+    let mut results = run_bounded(2, cancel_free, false).unwrap();
+    results.sort();
+    assert_eq!(results, vec![(0, "slow".to_string()), (4, "fails".to_string())]);
+}
+
+/// Same three agents as `create_test_project_config`, with a confirmation threshold of 2
+fn create_confirm_project_config(temp_dir: &TempDir) -> (String, String) {
+    let (project_path, providers_path) = create_test_project_config(temp_dir);
+    let project = std::fs::read_to_string(&project_path).unwrap()
+        .replace("project: test-routing", "project: test-send-confirm")
+        + "defaults:\n  confirm_send_above: 2\n";
+    std::fs::write(&project_path, project).unwrap();
+    (project_path, providers_path)
+}
+
+#[test]
+fn send_large_fan_out_requires_yes_without_tty() {
+    use crate::commands::send::{execute_send, SendRequest, SupervisorNotify};
+    use crate::utils::CliError;
+
+    let temp_dir = TempDir::new().unwrap();
+    let (project_path, providers_path) = create_confirm_project_config(&temp_dir);
+    let ids = random_ids();
+    let request = SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        to: "@all",
+        message: "Hello",
+        session_id_opt: None,
+        chat_id_opt: None,
+        timeout_ms_flag: Some(5000),
+        format: crate::cli::commands::Format::Text,
+        progress: false,
+        quiet: true,
+        fail_fast: false,
+        yes: false,
+        project_opt: None,
+        strict_placeholders: false,
+        inactivity_timeout_ms_flag: None,
+        manifest_path: None,
+        cache: None,
+        cache_ttl: None,
+        session_ttl: None,
+        notify: SupervisorNotify::Record,
+        ids: Some(&ids),
+        echo_prompt: false,
+    };
+
+    let err = execute_send(&request, false).unwrap_err();
+    assert!(matches!(&err, CliError::Validation { detail, .. } if detail.contains("--yes")), "{:?}", err);
+    assert_eq!(err.exit_code(), 2);
+
+    // --yes bypasses the prompt entirely, even without a TTY
+    let result = execute_send(&SendRequest { yes: true, ..request }, false);
+    assert!(result.is_ok(), "{:?}", result.err());
+
+    // At or below the threshold no confirmation is needed
+    let result = execute_send(&SendRequest { to: "@backend", ..request }, false);
+    assert!(result.is_ok(), "{:?}", result.err());
+}
+
+#[test]
+fn send_project_flag_targets_renamed_db_project() {
+    use crate::commands::send::{execute_send, SendRequest, SupervisorNotify};
+    use crate::utils::{resolve_db_path, uuid_v4_like};
+
+    let temp_dir = TempDir::new().unwrap();
+    let (project_path, providers_path) = create_test_project_config(&temp_dir);
+    let (old_name, new_name) = (format!("send-rename-{}", uuid_v4_like()), format!("send-renamed-{}", uuid_v4_like()));
+    let project = std::fs::read_to_string(&project_path).unwrap().replace("project: test-routing", &format!("project: {}", old_name));
+    std::fs::write(&project_path, project).unwrap();
+
+    // The DB project was renamed after project.yaml was written
+    let conn = db::open_or_create_db(&resolve_db_path()).unwrap();
+    let new_id = db::insert_project(&conn, &old_name).unwrap().id;
+    db::rename_project(&conn, &old_name, &new_name).unwrap();
+
+    let ids = random_ids();
+    let request = SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        to: "backend1",
+        message: "Hello",
+        session_id_opt: None,
+        chat_id_opt: None,
+        timeout_ms_flag: Some(5000),
+        format: crate::cli::commands::Format::Text,
+        progress: false,
+        quiet: true,
+        fail_fast: false,
+        yes: true,
+        project_opt: Some(&new_name),
+        strict_placeholders: false,
+        inactivity_timeout_ms_flag: None,
+        manifest_path: None,
+        cache: None,
+        cache_ttl: None,
+        session_ttl: None,
+        notify: SupervisorNotify::Record,
+        ids: Some(&ids),
+        echo_prompt: false,
+    };
+    let result = execute_send(&request, false);
+    assert!(result.is_ok(), "{:?}", result.err());
+    assert!(db::find_project_id(&conn, db::IdOrName::Name(&old_name)).unwrap().is_none(), "YAML name must not be recreated");
+    assert!(db::list_agent_names(&conn, &new_id).unwrap().contains(&"backend1".to_string()));
+    let sent = db::list_broadcasts(&conn, &new_id, None, 10).unwrap();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].targets_json, vec!["backend1".to_string()]);
+
+    // Selecting by id works too; unknown projects are invalid input
+    assert!(execute_send(&SendRequest { project_opt: Some(&new_id), ..request }, false).is_ok());
+    let err = execute_send(&SendRequest { project_opt: Some("no-such-project"), ..request }, false).unwrap_err();
+    assert_eq!(err.exit_code(), 2);
+}
+
+#[test]
+fn send_confirm_prompt_lists_targets_and_reads_answer() {
+    use crate::commands::send::confirm_fan_out;
+
+    let agents: Vec<config_model::AgentConfig> = (1..=3).map(|i| config_model::AgentConfig {
+        name: format!("agent{}", i),
+        role: "backend".into(),
+        provider: "claude".into(),
+        model: "m".into(),
+        allowed_tools: vec![],
+        system_prompt: "sp".into(),
+    }).collect();
+    let targets: Vec<&config_model::AgentConfig> = agents.iter().collect();
+
+    let mut out = Vec::new();
+    assert!(confirm_fan_out(&targets, 2, true, &mut "y\n".as_bytes(), &mut out).is_ok());
+    let shown = String::from_utf8(out).unwrap();
+    assert!(shown.contains("agent3 (role: backend, provider: claude)"));
+    assert!(shown.ends_with("Send to 3 agents? [y/N] "));
+
+    let mut out = Vec::new();
+    assert!(confirm_fan_out(&targets, 2, true, &mut "\n".as_bytes(), &mut out).is_err());
+}
+
+#[test]
+fn send_strict_placeholders_rejects_unknown_before_sending() {
+    use crate::commands::send::{execute_send, SendRequest, SupervisorNotify};
+    use crate::utils::CliError;
+
+    let temp_dir = TempDir::new().unwrap();
+    let (project_path, providers_path) = create_test_project_config(&temp_dir);
+    let project = std::fs::read_to_string(&project_path).unwrap()
+        .replacen("\"You are a backend developer\"", "'You are {agent_name} of {project}; cite {ticket} as {\"id\": 1}'", 1);
+    std::fs::write(&project_path, project).unwrap();
+    let ids = random_ids();
+    let request = SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        to: "backend1",
+        message: "Hello",
+        session_id_opt: None,
+        chat_id_opt: None,
+        timeout_ms_flag: Some(5000),
+        format: crate::cli::commands::Format::Text,
+        progress: false,
+        quiet: true,
+        fail_fast: false,
+        yes: true,
+        project_opt: None,
+        strict_placeholders: true,
+        inactivity_timeout_ms_flag: None,
+        manifest_path: None,
+        cache: None,
+        cache_ttl: None,
+        session_ttl: None,
+        notify: SupervisorNotify::Record,
+        ids: Some(&ids),
+        echo_prompt: false,
+    };
+
+    let err = execute_send(&request, false).unwrap_err();
+    assert!(matches!(&err, CliError::Validation { detail, .. } if detail.contains("backend1") && detail.contains("{ticket}")), "{:?}", err);
+    assert_eq!(err.exit_code(), 2);
+
+    // Without the flag the unknown placeholder is kept as literal text
+    let result = execute_send(&SendRequest { strict_placeholders: false, ..request }, false);
+    assert!(result.is_ok(), "{:?}", result.err());
+}
+
+#[test]
+fn send_manifest_records_each_target_even_when_one_fails() {
+    use crate::commands::send::{execute_send, SendRequest, SupervisorNotify, SEND_MANIFEST_SCHEMA_VERSION};
+    use crate::utils::uuid_v4_like;
+
+    let temp_dir = TempDir::new().unwrap();
+    let project_name = format!("send-manifest-{}", uuid_v4_like());
+    let project_path = temp_dir.path().join("project.yaml");
+    let providers_path = temp_dir.path().join("providers.yaml");
+    let manifest_path = temp_dir.path().join("run").join("manifest.json");
+    std::fs::create_dir_all(manifest_path.parent().unwrap()).unwrap();
+    std::fs::write(&project_path, format!(r#"
+project: {}
+agents:
+  - name: worker-ok
+    role: backend
+    provider: gemini
+    model: m
+    system_prompt: "sp"
+    allowed_tools: []
+  - name: worker-bad
+    role: backend
+    provider: claude
+    model: m
+    system_prompt: "sp"
+    allowed_tools: []
+"#, project_name)).unwrap();
+    std::fs::write(&providers_path, r#"
+providers:
+  gemini:
+    cmd: sh
+    oneshot_args: ["-c", "echo done", "sh", "{prompt}", "{agent_name}"]
+  claude:
+    cmd: sh
+    oneshot_args: ["-c", "echo broken >&2; exit 1", "sh", "{prompt}"]
+"#).unwrap();
+    let (project_path, providers_path) = (project_path.to_string_lossy().to_string(), providers_path.to_string_lossy().to_string());
+    let message = "secret ".repeat(100);
+    let ids = random_ids();
+    let request = SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        to: "@backend",
+        message: &message,
+        session_id_opt: None,
+        chat_id_opt: None,
+        timeout_ms_flag: Some(5000),
+        format: crate::cli::commands::Format::Text,
+        progress: false,
+        quiet: true,
+        fail_fast: false,
+        yes: true,
+        project_opt: None,
+        strict_placeholders: false,
+        inactivity_timeout_ms_flag: None,
+        manifest_path: Some(manifest_path.to_str().unwrap()),
+        cache: None,
+        cache_ttl: None,
+        session_ttl: None,
+        notify: SupervisorNotify::Record,
+        ids: Some(&ids),
+        echo_prompt: false,
+    };
+
+    // The manifest is written although the send itself fails
+    let err = execute_send(&request, false).unwrap_err();
+    let manifest: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&manifest_path).unwrap()).unwrap();
+    assert_eq!(manifest["schema_version"], SEND_MANIFEST_SCHEMA_VERSION);
+    assert_eq!(manifest["project"], project_name.as_str());
+    assert_eq!(manifest["status"], "partial");
+    assert_eq!(manifest["exit_code"], err.exit_code());
+    assert_eq!(manifest["prompt"]["chars"], 700);
+    assert_eq!(manifest["prompt"]["truncated"], true);
+    assert_eq!(manifest["prompt"]["preview"].as_str().unwrap().len(), 200);
+    assert!(!manifest.to_string().contains(message.trim_end()), "the full prompt must not be stored");
+
+    // Targets keep their resolution order, not completion order
+    let targets = manifest["targets"].as_array().unwrap();
+    assert_eq!(targets.len(), 2);
+    let (ok, bad) = (&targets[0], &targets[1]);
+    assert_eq!((ok["agent"].as_str(), ok["provider"].as_str()), (Some("worker-ok"), Some("gemini")));
+    assert_eq!((ok["exit_code"].as_i64(), ok["status"].as_str()), (Some(0), Some("ok")));
+    assert_eq!(ok["args"], serde_json::json!(["-c", "echo done", "sh", "{prompt}", "worker-ok"]));
+    assert_eq!((bad["agent"].as_str(), bad["exit_code"].as_i64(), bad["status"].as_str()), (Some("worker-bad"), Some(4), Some("failed")));
+    for target in targets {
+        assert!(target["conversation_id"].is_string());
+        assert!(target["duration_ms"].is_u64());
+        let agent_file = format!("{}/{}.ndjson", project_name, target["agent"].as_str().unwrap());
+        assert!(target["log_file"].as_str().unwrap().ends_with(&agent_file));
+    }
+    assert_eq!(std::fs::read_dir(manifest_path.parent().unwrap()).unwrap().count(), 1, "no temp file left behind");
+
+    // A manifest that cannot be written only warns
+    let unwritable = temp_dir.path().join("missing-dir").join("manifest.json");
+    let err2 = execute_send(&SendRequest { manifest_path: Some(unwritable.to_str().unwrap()), ..request }, false).unwrap_err();
+    assert_eq!(err2.exit_code(), err.exit_code());
+    assert!(!unwritable.exists());
+}
+
+#[test]
+fn send_cache_key_is_stable_and_covers_every_field() {
+    use crate::commands::cache::{cache_key, CacheKeyInput};
+
+    let tools = vec!["Read".to_string(), "Edit".to_string()];
+    let input = CacheKeyInput { provider: "claude", model: "sonnet", system_prompt: "sp", message: "hi", allowed_tools: &tools };
+    let key = cache_key(&input);
+    assert_eq!(key, cache_key(&input));
+    assert_eq!(key.len(), 64);
+    assert!(key.chars().all(|c| c.is_ascii_hexdigit()));
+
+    let other_tools = vec!["Read".to_string()];
+    for changed in [
+        CacheKeyInput { provider: "gemini", ..input },
+        CacheKeyInput { model: "opus", ..input },
+        CacheKeyInput { system_prompt: "sp2", ..input },
+        CacheKeyInput { message: "hi!", ..input },
+        CacheKeyInput { allowed_tools: &other_tools, ..input },
+        // Text shifted between fields is a different input
+        CacheKeyInput { system_prompt: "sph", message: "i", ..input },
+    ] {
+        assert_ne!(cache_key(&changed), key, "{:?}", changed);
+    }
+}
+
+#[test]
+fn send_cache_hit_replays_output_without_running_the_provider() {
+    use crate::commands::cache::{cache_key, CacheKeyInput};
+    use crate::commands::send::{cached_end_event, execute_send, SendRequest, SupervisorNotify};
+    use crate::utils::{resolve_db_path, uuid_v4_like};
+
+    let temp_dir = TempDir::new().unwrap();
+    let project_name = format!("send-cache-{}", uuid_v4_like());
+    let project_path = temp_dir.path().join("project.yaml");
+    let providers_path = temp_dir.path().join("providers.yaml");
+    let runs_path = temp_dir.path().join("runs.txt");
+    std::fs::write(&project_path, format!(r#"
+project: {}
+agents:
+  - name: worker
+    role: backend
+    provider: gemini
+    model: m
+    system_prompt: "sp"
+    allowed_tools: []
+"#, project_name)).unwrap();
+    std::fs::write(&providers_path, format!(r#"
+providers:
+  gemini:
+    cmd: sh
+    oneshot_args: ["-c", "echo run >> \"$1\"; echo canned answer", "sh", "{}", "{{prompt}}"]
+"#, runs_path.display())).unwrap();
+    let (project_path, providers_path) = (project_path.to_string_lossy().to_string(), providers_path.to_string_lossy().to_string());
+    let message = format!("canned prompt for {}", project_name);
+    let ids = random_ids();
+    let request = SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        to: "worker",
+        message: &message,
+        session_id_opt: None,
+        chat_id_opt: None,
+        timeout_ms_flag: Some(5000),
+        format: crate::cli::commands::Format::Text,
+        progress: false,
+        quiet: true,
+        fail_fast: false,
+        yes: true,
+        project_opt: None,
+        strict_placeholders: false,
+        inactivity_timeout_ms_flag: None,
+        manifest_path: None,
+        cache: Some(true),
+        cache_ttl: None,
+        session_ttl: None,
+        notify: SupervisorNotify::Record,
+        ids: Some(&ids),
+        echo_prompt: false,
+    };
+    let provider_runs = || std::fs::read_to_string(&runs_path).map(|s| s.lines().count()).unwrap_or(0);
+    let conn = db::open_or_create_db(&resolve_db_path()).unwrap();
+    let sessions = || {
+        let project_id = db::find_project_id(&conn, db::IdOrName::Name(&project_name)).unwrap().unwrap();
+        let filters = db::SessionFilters {
+            project_id: Some(project_id), agent_id: None, provider: None, status: None, session_type: None, limit: None, offset: None,
+        };
+        db::list_sessions(&conn, filters).unwrap().into_iter().map(|s| (s.id, s.last_activity)).collect::<Vec<_>>()
+    };
+
+    // Miss: the provider runs and its answer is stored
+    execute_send(&request, false).unwrap();
+    assert_eq!(provider_runs(), 1);
+    let after_miss = sessions();
+    assert_eq!(after_miss.len(), 1);
+
+    // Hit: nothing is spawned and no session is created or touched
+    execute_send(&request, false).unwrap();
+    assert_eq!(provider_runs(), 1);
+    assert_eq!(sessions(), after_miss);
+    let hit = db::get_cached_response(&conn, &cache_key(&CacheKeyInput {
+        provider: "gemini", model: "m", system_prompt: "sp", message: &message, allowed_tools: &[],
+    })).unwrap().expect("stored answer");
+    assert_eq!((hit.stdout.as_str(), hit.hits), ("canned answer\n", 2));
+    let end = cached_end_event(&project_name, "backend", "gemini", &hit, "worker");
+    assert!(end.to_line().ends_with(r#""exit_code":0,"dur_ms":null,"lines":1,"bytes":14,"cached":true}"#), "{}", end.to_line());
+
+    // Without --cache (and no project default) the provider always runs
+    execute_send(&SendRequest { cache: None, ..request }, false).unwrap();
+    assert_eq!(provider_runs(), 2);
+}
+
+#[test]
+fn send_failures_notify_subscribed_supervisors_without_changing_the_exit_code() {
+    use crate::cli::commands::{Format, SupervisorEventArg};
+    use crate::commands::supervisor::run_supervisor_subscribe;
+    use crate::utils::uuid_v4_like;
+
+    let dir = TempDir::new().unwrap();
+    let project_name = format!("send-notify-{}", uuid_v4_like());
+    let prompts_path = dir.path().join("prompts.txt");
+    let project_yaml = format!(r#"
+project: {}
+agents:
+  - name: lead
+    role: lead
+    provider: gemini
+    model: m
+    system_prompt: "sp"
+    allowed_tools: []
+  - name: worker
+    role: backend
+    provider: gemini
+    model: m
+    system_prompt: "sp"
+    allowed_tools: []
+"#, project_name);
+    std::fs::write(dir.path().join("project.yaml"), &project_yaml).unwrap();
+    // Every run fails, including the one delivering the notification to the supervisor
+    std::fs::write(dir.path().join("providers.yaml"), format!(r#"
+providers:
+  gemini:
+    cmd: sh
+    oneshot_args: ["-c", "echo \"$2\" >> \"$1\"; exit 1", "sh", "{}", "{{prompt}}"]
+"#, prompts_path.display())).unwrap();
+    // The binary run by `run_cli_in` uses the DB in `dir`
+    let db_path = dir.path().join("multi-agents.sqlite3").to_string_lossy().to_string();
+    let conn = db::open_or_create_db(&db_path).unwrap();
+    db::sync_project_from_config(&conn, &config_model::parse_project_yaml(&project_yaml).unwrap()).unwrap();
+    run_supervisor_subscribe(&project_name, "lead", &[SupervisorEventArg::SendFailed], "@backend", Some(&db_path), Format::Text, None).unwrap();
+
+    let send = |extra: &[&str]| {
+        let mut args = vec!["send", "--to", "worker", "--message", "do the thing", "--timeout-ms", "5000", "--yes", "--no-cache",
+            "--project-file", "project.yaml", "--providers-file", "providers.yaml"];
+        args.extend_from_slice(extra);
+        run_cli_in(&dir, &args)
+    };
+    let notifications = || -> Vec<String> {
+        let mut stmt = conn.prepare(
+            "SELECT m.content FROM messages m JOIN sessions s ON s.id = m.session_id JOIN agents a ON a.id = s.agent_id
+             JOIN projects p ON p.id = a.project_id WHERE p.name = ?1 AND a.name = 'lead' AND m.sender = ?2 ORDER BY m.rowid",
+        ).unwrap();
+        let rows = stmt.query_map([project_name.as_str(), crate::supervisor::notify::NOTIFICATION_SENDER], |r| r.get(0)).unwrap();
+        rows.map(Result::unwrap).collect()
+    };
+    let delivered = || std::fs::read_to_string(&prompts_path).unwrap_or_default().matches("[send_failed] worker (@backend)").count();
+
+    // Recorded for the supervisor, not delivered
+    let recorded = send(&[]);
+    assert!(!recorded.status.success());
+    assert_eq!(notifications().len(), 1);
+    assert!(notifications()[0].starts_with("[send_failed] worker (@backend): exit code "), "{:?}", notifications());
+    assert_eq!(delivered(), 0);
+
+    // Delivered too, in the background: the send reports and exits as before, without the supervisor's output
+    let dispatched = send(&["--notify-supervisor"]);
+    assert_eq!(dispatched.status.code(), recorded.status.code());
+    assert_eq!(dispatched.stdout, recorded.stdout);
+    assert_eq!(notifications().len(), 2);
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(30);
+    while delivered() == 0 && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert_eq!(delivered(), 1);
+    // The supervisor's own failed run does not notify it in turn
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    assert_eq!(notifications().len(), 2);
+    assert_eq!(delivered(), 1);
+}
+
+#[test]
+fn send_to_a_project_without_agents_exits_2_for_every_target_form() {
+    use crate::cli::commands::Format;
+    use crate::commands::send::{execute_send, SendRequest, SupervisorNotify};
+    use crate::utils::{resolve_db_path, uuid_v4_like};
+
+    let temp_dir = TempDir::new().unwrap();
+    let project_name = format!("send-empty-{}", uuid_v4_like());
+    let project_path = temp_dir.path().join("project.yaml");
+    let providers_path = temp_dir.path().join("providers.yaml");
+    std::fs::write(&project_path, format!("project: {}\nagents: []\n", project_name)).unwrap();
+    std::fs::write(&providers_path, "providers:\n  gemini:\n    cmd: sh\n    oneshot_args: [\"-c\", \"exit 0\"]\n").unwrap();
+    let (project_path, providers_path) = (project_path.to_string_lossy().to_string(), providers_path.to_string_lossy().to_string());
+    let ids = random_ids();
+    let request = SendRequest {
+        project_path_opt: Some(&project_path),
+        providers_path_opt: Some(&providers_path),
+        to: "@all",
+        message: "anyone there?",
+        session_id_opt: None,
+        chat_id_opt: None,
+        timeout_ms_flag: Some(5000),
+        format: Format::Json,
+        progress: false,
+        quiet: true,
+        fail_fast: false,
+        yes: true,
+        project_opt: None,
+        strict_placeholders: false,
+        inactivity_timeout_ms_flag: None,
+        manifest_path: None,
+        cache: Some(false),
+        cache_ttl: None,
+        session_ttl: None,
+        notify: SupervisorNotify::Record,
+        ids: Some(&ids),
+        echo_prompt: false,
+    };
+
+    for to in ["@all", "@backend", "backend", "backend,frontend"] {
+        let err = execute_send(&SendRequest { to, ..request }, false).unwrap_err();
+        assert_eq!(err.exit_code(), 2, "{}: {}", to, err);
+    }
+    let err = execute_send(&request, false).unwrap_err();
+    assert!(err.to_string().contains("no targets matched '@all'"), "{}", err);
+
+    // Nothing was sent, so no broadcast was recorded
+    let conn = db::open_or_create_db(&resolve_db_path()).unwrap();
+    let project_id = db::find_project_id(&conn, db::IdOrName::Name(&project_name)).unwrap().unwrap();
+    assert!(db::list_broadcasts(&conn, &project_id, None, 10).unwrap().is_empty());
+}
@@ -2,12 +2,394 @@
 
 #[cfg(test)]
 mod tests {
-    // Session integration tests will be added here
-    // These would test the full session command execution
-    
+    use crate::commands::{run_session_start, ensure_session_for_agent};
+    use crate::commands::session::{create_chat, CREATE_CHAT_STDERR_LIMIT};
+    use crate::utils::{CliError, ProviderErrorKind};
+    use db::{find_project_id, find_session, open_or_create_db, IdOrName, SessionStatus};
+    use rusqlite::Connection;
+    use tempfile::TempDir;
+    use crate::tests::integration::fake_provider::{run_cli_in, write_providers_yaml, FakeProvider};
+
+    fn write_config(dir: &TempDir, agent_provider: &str) -> (String, String) {
+        let project = dir.path().join("project.yaml");
+        std::fs::write(&project, format!(
+            "schema_version: 1\nproject: session-errors\nagents:\n  - name: b1\n    role: backend\n    provider: {}\n    model: m\n    allowed_tools: []\n    system_prompt: \"\"\n",
+            agent_provider
+        )).unwrap();
+        let gemini = FakeProvider::new().stdout("gemini answer").install(dir.path(), "gemini");
+        let providers = write_providers_yaml(dir.path(), &[("gemini", &gemini)]);
+        (project.to_string_lossy().to_string(), providers)
+    }
+
+    #[test]
+    fn test_session_start_missing_config() {
+        let dir = TempDir::new().unwrap();
+        let project = dir.path().join("missing-project.yaml").to_string_lossy().to_string();
+        let providers = dir.path().join("missing-providers.yaml").to_string_lossy().to_string();
+        let err = run_session_start(Some(&project), Some(&providers), Some("b1"), None, &crate::utils::RandomIds).unwrap_err();
+        assert!(matches!(err, CliError::ConfigMissing { .. }), "{:?}", err);
+        assert_eq!(err.exit_code(), 6);
+    }
+
+    #[test]
+    fn test_session_start_unknown_agent() {
+        let dir = TempDir::new().unwrap();
+        let (project, providers) = write_config(&dir, "gemini");
+        let err = run_session_start(Some(&project), Some(&providers), Some("ghost"), None, &crate::utils::RandomIds).unwrap_err();
+        assert!(matches!(&err, CliError::Validation { detail, .. } if detail.contains("ghost")), "{:?}", err);
+        assert_eq!(err.exit_code(), 2);
+    }
+
+    #[test]
+    fn test_session_start_provider_not_found() {
+        let dir = TempDir::new().unwrap();
+        let (project, providers) = write_config(&dir, "claude");
+        let err = run_session_start(Some(&project), Some(&providers), Some("b1"), None, &crate::utils::RandomIds).unwrap_err();
+        assert!(matches!(&err, CliError::Provider { provider, kind: ProviderErrorKind::NotConfigured } if provider == "claude"), "{:?}", err);
+        assert_eq!(err.exit_code(), 3);
+    }
+
+    /// Install a fake cursor-agent in `dir`; returns its path
+    fn fake_cursor(dir: &TempDir, fake: FakeProvider) -> String {
+        fake.install(dir.path(), "cursor-agent")
+    }
+
+    #[test]
+    fn test_create_chat_nonzero_exit_reports_stderr() {
+        let dir = TempDir::new().unwrap();
+        let cursor = fake_cursor(&dir, FakeProvider::new().stderr("not logged in").exit(1));
+        let err = create_chat("cursor-agent", &cursor, &[], 5000).unwrap_err();
+        assert!(matches!(&err, CliError::Provider { kind: ProviderErrorKind::Failed(d), .. } if d.contains("code 1") && d.contains("not logged in")), "{:?}", err);
+        assert_eq!(err.exit_code(), 4);
+
+        let cursor = fake_cursor(&dir, FakeProvider::new().stderr(&"e".repeat(CREATE_CHAT_STDERR_LIMIT + 500)).exit(2));
+        let err = create_chat("cursor-agent", &cursor, &[], 5000).unwrap_err();
+        assert!(err.to_string().ends_with("(truncated)"), "{}", err);
+        assert!(err.to_string().len() < CREATE_CHAT_STDERR_LIMIT + 200);
+    }
+
+    #[test]
+    fn test_create_chat_distinguishes_missing_binary_and_timeout() {
+        let err = create_chat("cursor-agent", "definitely-not-a-cursor-binary", &[], 5000).unwrap_err();
+        assert!(matches!(&err, CliError::Provider { kind: ProviderErrorKind::NotStarted(_), .. }), "{:?}", err);
+        assert_eq!(err.exit_code(), 3);
+
+        let dir = TempDir::new().unwrap();
+        let cursor = fake_cursor(&dir, FakeProvider::new().sleep(2.0).ignore_term());
+        let err = create_chat("cursor-agent", &cursor, &[], 100).unwrap_err();
+        assert_eq!(err.exit_code(), 5);
+
+        let cursor = fake_cursor(&dir, FakeProvider::new().stdout("banner\nchat-123"));
+        let id = create_chat("cursor-agent", &cursor, &[], 5000).unwrap();
+        assert_eq!(id, "chat-123");
+    }
+
+    #[test]
+    fn test_session_start_records_the_chat_created_by_cursor() {
+        let dir = TempDir::new().unwrap();
+        let seen = dir.path().join("args.txt");
+        // The cursor session manager only accepts `valid_`-prefixed chat ids
+        let cursor = fake_cursor(&dir, FakeProvider::new().stdout("Creating chat...\nvalid_chat-42").record(&seen));
+        write_providers_yaml(dir.path(), &[("cursor-agent", &cursor)]);
+        std::fs::write(dir.path().join("project.yaml"),
+            "schema_version: 1\nproject: cursor-start\nagents:\n  - name: c1\n    role: backend\n    provider: cursor-agent\n    model: m\n    allowed_tools: []\n    system_prompt: \"\"\n",
+        ).unwrap();
+        let project = config_model::parse_project_yaml(&std::fs::read_to_string(dir.path().join("project.yaml")).unwrap()).unwrap();
+        let conn = open_or_create_db(dir.path().join("multi-agents.sqlite3").to_string_lossy().as_ref()).unwrap();
+        db::sync_project_from_config(&conn, &project).unwrap();
+        let start = || run_cli_in(&dir, &["session", "start", "--agent", "c1", "--quiet", "--project-file", "project.yaml", "--providers-file", "providers.yaml"]);
+
+        let out = start();
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        assert_eq!(std::fs::read_to_string(&seen).unwrap(), "create-chat\n");
+        let id = String::from_utf8(out.stdout).unwrap();
+        let session = find_session(&conn, id.trim()).unwrap().unwrap();
+        assert_eq!(session.provider_session_id.as_deref(), Some("valid_chat-42"));
+
+        // `cmd` is expanded like it is for sends: `run_cli_in` points MULTI_AGENTS_LOG_DIR at `dir/logs`
+        std::fs::create_dir_all(dir.path().join("logs")).unwrap();
+        write_providers_yaml(dir.path(), &[("cursor-agent", "$MULTI_AGENTS_LOG_DIR/../cursor-agent")]);
+        let out = start();
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        write_providers_yaml(dir.path(), &[("cursor-agent", &cursor)]);
+
+        // A create-chat failure is a provider failure
+        fake_cursor(&dir, FakeProvider::new().stderr("not logged in").exit(1));
+        let out = start();
+        assert_eq!(out.status.code(), Some(4));
+        assert!(String::from_utf8_lossy(&out.stderr).contains("not logged in"));
+    }
+
+    /// Providers config without aliases, so sessions follow the agent's own provider key
+    fn no_aliases() -> config_model::ProvidersConfig {
+        config_model::ProvidersConfig { schema_version: 1, providers: Default::default() }
+    }
+
+    fn seeded_db(dir: &TempDir) -> (Connection, String, String, config_model::AgentConfig) {
+        let (project_path, _) = write_config(dir, "gemini");
+        let project = config_model::parse_project_yaml(&std::fs::read_to_string(project_path).unwrap()).unwrap();
+        let db_path = dir.path().join("sessions.sqlite3").to_string_lossy().to_string();
+        let conn = open_or_create_db(&db_path).unwrap();
+        db::sync_project_from_config(&conn, &project).unwrap();
+        let project_id = find_project_id(&conn, IdOrName::Name(&project.project)).unwrap().unwrap();
+        let agent = project.agents[0].clone();
+        (conn, db_path, project_id, agent)
+    }
+
+    #[test]
+    fn test_session_ttl_flag_beats_project_default_and_sets_expires_at() {
+        use crate::commands::session::session_ttl;
+        assert_eq!(session_ttl(None, None).unwrap(), None);
+        assert_eq!(session_ttl(None, Some("2h")).unwrap(), Some(std::time::Duration::from_secs(7200)));
+        assert_eq!(session_ttl(Some("30m"), Some("2h")).unwrap(), Some(std::time::Duration::from_secs(1800)));
+        assert_eq!(session_ttl(Some("soon"), None).unwrap_err().exit_code(), 2);
+
+        let dir = TempDir::new().unwrap();
+        let (conn, db_path, project_id, agent) = seeded_db(&dir);
+        let id = ensure_session_for_agent(&conn, &project_id, &agent, None, &db_path, Some(std::time::Duration::from_secs(3600)), &no_aliases()).unwrap();
+        let session = find_session(&conn, &id).unwrap().unwrap();
+        assert!(session.expires_at.as_deref().unwrap() > session.created_at.as_str());
+    }
+
+    #[test]
+    fn test_provider_session_ttl_stamps_expires_at_only_when_set() {
+        use crate::commands::session::effective_session_ttl;
+        use std::time::Duration;
+
+        let parse = |extra: &str| {
+            let yaml = format!("schema_version: 1\nproviders:\n  gemini:\n    cmd: gemini\n    oneshot_args: [\"{{prompt}}\"]\n    repl_args: []\n{}", extra);
+            config_model::parse_providers_yaml(&yaml).unwrap().providers.remove("gemini").unwrap()
+        };
+        let plain = parse("");
+        let expiring = parse("    session_ttl_ms: 7200000\n");
+        assert_eq!(plain.session_ttl_ms, None);
+        assert_eq!(effective_session_ttl(None, Some(&plain)), None);
+        assert_eq!(effective_session_ttl(None, Some(&expiring)), Some(Duration::from_secs(7200)));
+        // --session-ttl / defaults.session_ttl win over the provider's window, but never outlast it
+        assert_eq!(effective_session_ttl(Some(Duration::from_secs(60)), Some(&expiring)), Some(Duration::from_secs(60)));
+        assert_eq!(effective_session_ttl(Some(Duration::from_secs(60)), Some(&plain)), Some(Duration::from_secs(60)));
+        let claude = parse("    session_ttl_ms: 18000000\n");
+        let week = Duration::from_secs(7 * 24 * 3600);
+        assert_eq!(effective_session_ttl(Some(week), Some(&claude)), Some(Duration::from_secs(5 * 3600)));
+
+        let dir = TempDir::new().unwrap();
+        let (conn, db_path, project_id, agent) = seeded_db(&dir);
+        let id = ensure_session_for_agent(&conn, &project_id, &agent, None, &db_path, effective_session_ttl(None, Some(&plain)), &no_aliases()).unwrap();
+        assert_eq!(find_session(&conn, &id).unwrap().unwrap().expires_at, None);
+        let id = ensure_session_for_agent(&conn, &project_id, &agent, None, &db_path, effective_session_ttl(None, Some(&expiring)), &no_aliases()).unwrap();
+        let session = find_session(&conn, &id).unwrap().unwrap();
+        let created = db::parse_iso8601_utc(&session.created_at).unwrap();
+        let expires = db::parse_iso8601_utc(session.expires_at.as_deref().unwrap()).unwrap();
+        assert_eq!(expires - created, time::Duration::hours(2));
+    }
+
+    #[test]
+    fn test_session_list_expires_label() {
+        use crate::commands::session::expires_label;
+
+        let now = db::parse_iso8601_utc("2025-06-01T12:00:00.000Z").unwrap();
+        assert_eq!(expires_label(None, now), "-");
+        assert_eq!(expires_label(Some("soon"), now), "-");
+        assert_eq!(expires_label(Some("2025-06-01T12:00:00.000Z"), now), "expired");
+        assert_eq!(expires_label(Some("2025-06-01T12:00:45.000Z"), now), "expires in 45s");
+        assert_eq!(expires_label(Some("2025-06-01T12:59:59.000Z"), now), "expires in 59m");
+        assert_eq!(expires_label(Some("2025-06-01T15:30:00.000Z"), now), "expires in 3h");
+        assert_eq!(expires_label(Some("2025-06-03T13:00:00.000Z"), now), "expires in 2d");
+    }
+
     #[test]
-    fn test_session_placeholder() {
-        // Placeholder test
-        assert!(true);
+    fn test_ensure_session_creates_when_none_provided() {
+        let dir = TempDir::new().unwrap();
+        let (conn, db_path, project_id, agent) = seeded_db(&dir);
+        let id = ensure_session_for_agent(&conn, &project_id, &agent, None, &db_path, None, &no_aliases()).unwrap();
+        let session = find_session(&conn, &id).unwrap().expect("session created");
+        assert_eq!(session.status, SessionStatus::Active);
+        assert_eq!(session.provider, "gemini");
+    }
+
+    #[test]
+    fn test_ensure_session_reuses_active_and_replaces_expired() {
+        let dir = TempDir::new().unwrap();
+        let (conn, db_path, project_id, agent) = seeded_db(&dir);
+        let first = ensure_session_for_agent(&conn, &project_id, &agent, None, &db_path, None, &no_aliases()).unwrap();
+        let reused = ensure_session_for_agent(&conn, &project_id, &agent, Some(&first), &db_path, None, &no_aliases()).unwrap();
+        assert_eq!(reused, first);
+
+        conn.execute("UPDATE sessions SET status = 'expired' WHERE id = ?1", [&first]).unwrap();
+        let replaced = ensure_session_for_agent(&conn, &project_id, &agent, Some(&first), &db_path, None, &no_aliases()).unwrap();
+        assert_ne!(replaced, first);
+        assert_eq!(find_session(&conn, &replaced).unwrap().unwrap().status, SessionStatus::Active);
+    }
+
+    #[test]
+    fn test_ensure_session_replaces_session_past_expires_at() {
+        let dir = TempDir::new().unwrap();
+        let (conn, db_path, project_id, agent) = seeded_db(&dir);
+        let first = ensure_session_for_agent(&conn, &project_id, &agent, None, &db_path, None, &no_aliases()).unwrap();
+        conn.execute("UPDATE sessions SET expires_at = '2020-01-01T00:00:00.000Z' WHERE id = ?1", [&first]).unwrap();
+
+        // Still `active` in the table, but its TTL has run out: marked expired and replaced
+        let replaced = ensure_session_for_agent(&conn, &project_id, &agent, Some(&first), &db_path, None, &no_aliases()).unwrap();
+        assert_ne!(replaced, first);
+        assert_eq!(find_session(&conn, &first).unwrap().unwrap().status, SessionStatus::Expired);
+        assert_eq!(find_session(&conn, &replaced).unwrap().unwrap().status, SessionStatus::Active);
+    }
+
+    #[test]
+    fn test_ensure_session_validates_reuse_through_expiry_then_provider() {
+        let dir = TempDir::new().unwrap();
+        let (conn, db_path, project_id, agent) = seeded_db(&dir);
+        let in_minutes = |m: i64| db::format_iso8601_utc(time::OffsetDateTime::now_utc() + time::Duration::minutes(m));
+        // The mock provider only recognises `valid_` ids, so a ping on this one fails
+        let session_expiring = |expires_at: &str| {
+            let id = ensure_session_for_agent(&conn, &project_id, &agent, None, &db_path, None, &no_aliases()).unwrap();
+            conn.execute("UPDATE sessions SET provider_session_id = 'unknown-to-provider', expires_at = ?1 WHERE id = ?2", [expires_at, id.as_str()]).unwrap();
+            id
+        };
+
+        // Hours ahead: trusted from expires_at alone, the provider is not asked
+        let fresh = session_expiring(&in_minutes(180));
+        assert_eq!(ensure_session_for_agent(&conn, &project_id, &agent, Some(&fresh), &db_path, None, &no_aliases()).unwrap(), fresh);
+
+        // Within the default 5 minutes: pinged, rejected, marked expired and replaced
+        let close = session_expiring(&in_minutes(1));
+        let replaced = ensure_session_for_agent(&conn, &project_id, &agent, Some(&close), &db_path, None, &no_aliases()).unwrap();
+        assert_ne!(replaced, close);
+        assert_eq!(find_session(&conn, &close).unwrap().unwrap().status, SessionStatus::Expired);
+
+        // A provider with no slack trusts the same session right up to expires_at
+        let providers = config_model::parse_providers_yaml(
+            "schema_version: 1\nproviders:\n  gemini:\n    cmd: gemini\n    oneshot_args: [\"{prompt}\"]\n    repl_args: []\n    session_expiry_slack_ms: 0\n",
+        ).unwrap();
+        let close = session_expiring(&in_minutes(1));
+        assert_eq!(ensure_session_for_agent(&conn, &project_id, &agent, Some(&close), &db_path, None, &providers).unwrap(), close);
+    }
+
+    #[test]
+    fn test_ensure_session_creates_when_id_unknown() {
+        let dir = TempDir::new().unwrap();
+        let (conn, db_path, project_id, agent) = seeded_db(&dir);
+        let id = ensure_session_for_agent(&conn, &project_id, &agent, Some("no-such-session"), &db_path, None, &no_aliases()).unwrap();
+        assert_ne!(id, "no-such-session");
+        assert!(find_session(&conn, &id).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_cleanup_cutoff_is_rfc3339_and_respects_window() {
+        use crate::commands::cleanup_cutoff;
+
+        let cutoff = cleanup_cutoff(Some("48h")).unwrap();
+        let parsed = db::parse_iso8601_utc(&cutoff).expect("cutoff must be RFC3339");
+        let age = time::OffsetDateTime::now_utc() - parsed;
+        assert!((age - time::Duration::hours(48)).abs() < time::Duration::seconds(5), "age {:?}", age);
+        assert!(cutoff.ends_with('Z'), "{}", cutoff);
+
+        let default_cutoff = cleanup_cutoff(None).unwrap();
+        let default = db::parse_iso8601_utc(&default_cutoff).unwrap();
+        assert!(((time::OffsetDateTime::now_utc() - default) - time::Duration::hours(24)).abs() < time::Duration::seconds(5));
+        // Cutoffs are compared as strings against stored timestamps, so they must sort before now
+        assert!(default_cutoff < db::now_iso8601_utc(), "{}", default_cutoff);
+
+        let err = cleanup_cutoff(Some("2 days")).unwrap_err();
+        assert!(matches!(err, CliError::Validation { .. }), "{:?}", err);
+        assert_eq!(err.exit_code(), 2);
+    }
+
+    /// Database migrated only to v2, where `run_cli_in` looks for it: sessions exist but have no `type` column
+    fn v2_only_db(dir: &TempDir) -> Connection {
+        let db_path = dir.path().join("multi-agents.sqlite3");
+        let conn = db::open_db_without_migrating(db_path.to_string_lossy().as_ref()).unwrap();
+        assert_eq!(db::migrate_to(&conn, 2).unwrap(), vec![1, 2]);
+        assert!(!db::column_exists(&conn, "sessions", "type").unwrap());
+        conn.execute_batch(
+            "INSERT INTO projects(id, name, created_at) VALUES ('p1', 'demo', '2020-01-01T00:00:00.000Z');
+             INSERT INTO agents(id, project_id, name, role, provider, model, allowed_tools_json, system_prompt, created_at)
+               VALUES ('a1', 'p1', 'b1', 'backend', 'gemini', 'm', '[]', '', '2020-01-01T00:00:00.000Z');
+             INSERT INTO sessions(id, project_id, agent_id, provider, created_at, last_activity, status)
+               VALUES ('old', 'p1', 'a1', 'gemini', '2020-01-01T00:00:00.000Z', '2020-01-01T00:00:00.000Z', 'active');",
+        ).unwrap();
+        conn
+    }
+
+    /// `session cleanup --format json` against the database in `dir`
+    fn cleanup_json(dir: &TempDir, extra: &[&str]) -> serde_json::Value {
+        let mut args = vec!["session", "cleanup", "--format", "json"];
+        args.extend_from_slice(extra);
+        let out = run_cli_in(dir, &args);
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        serde_json::from_slice(&out.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_session_cleanup_dry_run_on_v2_db_skips_repl_sessions() {
+        let dir = TempDir::new().unwrap();
+        let conn = v2_only_db(&dir);
+
+        let report = cleanup_json(&dir, &["--dry-run"]);
+        assert_eq!(report["expired_repl_sessions"], serde_json::json!([]));
+        assert_eq!(report["expired_chat_sessions"].as_array().unwrap().len(), 1, "{}", report);
+        assert_eq!(report["expired_chat_sessions"][0]["id"], "old");
+        // Dry run leaves the row in place, and the database is not migrated to get there
+        assert!(find_session_row(&conn, "old"));
+        assert_eq!(db::db_schema_version(&conn).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_session_cleanup_on_v2_db_deletes_chat_sessions() {
+        let dir = TempDir::new().unwrap();
+        let conn = v2_only_db(&dir);
+
+        let report = cleanup_json(&dir, &[]);
+        assert_eq!(report["repl_result"]["repl_cleaned_count"], 0);
+        assert_eq!(report["chat_result"]["chat_deleted_count"], 1);
+        assert!(!find_session_row(&conn, "old"));
+        assert_eq!(db::db_schema_version(&conn).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_session_cleanup_without_database_creates_none() {
+        let dir = TempDir::new().unwrap();
+        let report = cleanup_json(&dir, &[]);
+        assert_eq!(report["chat_result"]["chat_deleted_count"], 0);
+        assert!(!dir.path().join("multi-agents.sqlite3").exists());
+    }
+
+    fn find_session_row(conn: &Connection, id: &str) -> bool {
+        conn.prepare("SELECT 1 FROM sessions WHERE id = ?1").unwrap().exists([id]).unwrap()
+    }
+
+    #[test]
+    fn test_session_describe_shows_all_fields() {
+        use crate::commands::session::{describe_session_json, describe_session_text};
+
+        let dir = TempDir::new().unwrap();
+        let (conn, db_path, project_id, agent) = seeded_db(&dir);
+        let id = ensure_session_for_agent(&conn, &project_id, &agent, None, &db_path, None, &no_aliases()).unwrap();
+        conn.execute(
+            "UPDATE sessions SET provider_session_id = 'prov-1', metadata = ?1, expires_at = '2030-01-01T00:00:00.000Z' WHERE id = ?2",
+            [r#"{"model":"m","turns":3}"#, id.as_str()],
+        ).unwrap();
+        let session = find_session(&conn, &id).unwrap().unwrap();
+
+        let json = describe_session_json(&session).unwrap();
+        for key in ["id", "project_id", "agent_id", "provider", "provider_session_id", "created_at",
+                    "last_activity", "status", "metadata", "expires_at", "session_type", "is_resumable"] {
+            assert!(json.get(key).is_some(), "missing {key} in {json}");
+        }
+        assert_eq!(json["metadata"], serde_json::json!({"model": "m", "turns": 3}));
+        assert_eq!(json["status"], "active");
+        assert_eq!(json["session_type"], "chat");
+        assert_eq!(json["is_resumable"], true);
+
+        let text = describe_session_text(&session);
+        for key in ["conversation_id:", "project_id:", "agent_id:", "provider:", "provider_session_id:", "type:",
+                    "status:", "created_at:", "last_activity:", "expires_at:", "is_resumable:", "metadata:"] {
+            assert!(text.lines().any(|l| l.starts_with(key)), "missing {key} in\n{text}");
+        }
+        assert!(text.contains("provider_session_id: prov-1"), "{text}");
+        assert!(text.contains("\n    \"turns\": 3\n"), "{text}");
+        // Values line up in one column
+        let col = "provider_session_id: ".len();
+        assert!(text.lines().take(11).all(|l| &l[col - 1..col] == " " && &l[col..col + 1] != " "), "{text}");
     }
 }
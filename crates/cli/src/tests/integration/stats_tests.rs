@@ -0,0 +1,243 @@
+//! Integration tests for the stats command
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::commands::Format;
+    use crate::commands::{run_session_stats, run_stats};
+    use crate::utils::CliError;
+    use db::{open_or_create_db, insert_project, insert_agent, insert_session, get_project_stats, get_session_stats, weekly_summary, format_iso8601_utc, AgentSessionCount, ProviderSessionCount};
+    use rusqlite::{params, Connection};
+    use tempfile::TempDir;
+    use time::{Duration, OffsetDateTime};
+
+    fn at(now: OffsetDateTime, ago: Duration) -> String {
+        format_iso8601_utc(now - ago)
+    }
+
+    fn message(conn: &Connection, id: &str, session_id: &str, sender: &str, content: &str, broadcast_id: Option<&str>, created_at: &str) {
+        conn.execute(
+            "INSERT INTO messages(id, session_id, sender, content, broadcast_id, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![id, session_id, sender, content, broadcast_id, created_at],
+        ).unwrap();
+    }
+
+    fn broadcast(conn: &Connection, id: &str, project_id: &str, targets: &[&str], created_at: &str) {
+        conn.execute(
+            "INSERT INTO broadcasts(id, project_id, message, targets_json, status, created_at) VALUES (?1, ?2, 'msg', ?3, 'completed', ?4)",
+            params![id, project_id, serde_json::to_string(targets).unwrap(), created_at],
+        ).unwrap();
+    }
+
+    /// Yesterday: a broadcast to two agents (one replies after 2s) and a direct send answered after 4s.
+    /// Three days ago: one unanswered send. Ten days ago: activity outside any default window.
+    fn seed(db_path: &str, now: OffsetDateTime) -> String {
+        let conn = open_or_create_db(db_path).unwrap();
+        let project = insert_project(&conn, "stats-proj").unwrap();
+        let a1 = insert_agent(&conn, &project.id, "b1", "backend", "gemini", "m", &[], "").unwrap();
+        let a2 = insert_agent(&conn, &project.id, "f1", "frontend", "claude", "m", &[], "").unwrap();
+        let s1 = insert_session(&conn, &project.id, &a1.id, "gemini", None).unwrap();
+        let s2 = insert_session(&conn, &project.id, &a2.id, "claude", None).unwrap();
+        conn.execute("UPDATE sessions SET status = 'expired' WHERE id = ?1", params![s2.id]).unwrap();
+
+        let day1 = Duration::hours(20);
+        broadcast(&conn, "bc1", &project.id, &["b1", "f1"], &at(now, day1));
+        broadcast(&conn, "bc2", &project.id, &["b1"], &at(now, Duration::hours(10)));
+        broadcast(&conn, "bc3", &project.id, &["f1"], &at(now, Duration::days(3)));
+        broadcast(&conn, "bc4", &project.id, &["f1"], &at(now, Duration::days(10)));
+        message(&conn, "m1", &s1.id, "user", "hello team", Some("bc1"), &at(now, day1));
+        message(&conn, "m2", &s2.id, "user", "hello team", Some("bc1"), &at(now, day1));
+        message(&conn, "m3", &s1.id, "agent", "hi", None, &at(now, day1 - Duration::seconds(2)));
+        message(&conn, "m4", &s1.id, "user", "status?", Some("bc2"), &at(now, Duration::hours(10)));
+        message(&conn, "m5", &s1.id, "agent", "all green", None, &at(now, Duration::hours(10) - Duration::seconds(4)));
+        message(&conn, "m6", &s2.id, "user", "ping", Some("bc3"), &at(now, Duration::days(3)));
+        message(&conn, "m7", &s2.id, "user", "old", Some("bc4"), &at(now, Duration::days(10)));
+
+        for (id, status, ago) in [("t1", "done", Duration::hours(5)), ("t2", "todo", Duration::hours(5)), ("t3", "done", Duration::days(2)), ("t4", "done", Duration::days(10))] {
+            conn.execute(
+                "INSERT INTO tasks(id, project_id, title, status, assignee_agent_id, created_at) VALUES (?1, ?2, 'task', ?3, NULL, ?4)",
+                params![id, project.id, status, at(now, ago)],
+            ).unwrap();
+        }
+        project.id
+    }
+
+    #[test]
+    fn test_project_stats_last_24h() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db").to_string_lossy().to_string();
+        let now = OffsetDateTime::now_utc();
+        let project_id = seed(&db_path, now);
+        let conn = open_or_create_db(&db_path).unwrap();
+
+        let stats = get_project_stats(&conn, &project_id, now - Duration::hours(24)).unwrap();
+        assert_eq!(stats.total_sends, 2);
+        assert_eq!(stats.broadcasts, 1);
+        assert_eq!(stats.successful_agents, 2);
+        assert_eq!(stats.failed_agents, 1);
+        let avg = stats.avg_response_ms.unwrap();
+        assert!((avg - 3000.0).abs() < 5.0, "avg response: {}", avg);
+        // "hello team" x2 + "hi" + "status?" + "all green" = 10 + 10 + 2 + 7 + 9 characters
+        assert_eq!(stats.tokens_estimated, 10);
+        assert_eq!(stats.tasks_total, 2);
+        assert_eq!(stats.tasks_done, 1);
+        assert!((stats.task_completion_rate - 0.5).abs() < f64::EPSILON);
+        // The expired session is excluded even though it is recent
+        assert_eq!(stats.active_sessions, 1);
+
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["total_sends"], 2);
+        assert_eq!(json["failed_agents"], 1);
+    }
+
+    #[test]
+    fn test_project_stats_count_what_send_records() {
+        use crate::tests::integration::fake_provider::{run_cli_in, write_providers_yaml, FakeProvider};
+
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("project.yaml"),
+            "schema_version: 1\nproject: sent-proj\nagents:\n  - name: ok1\n    role: backend\n    provider: gemini\n    model: m\n    allowed_tools: []\n    system_prompt: \"\"\n  - name: ko1\n    role: backend\n    provider: claude\n    model: m\n    allowed_tools: []\n    system_prompt: \"\"\n",
+        ).unwrap();
+        let gemini = FakeProvider::new().stdout("done").install(dir.path(), "gemini");
+        let claude = FakeProvider::new().stderr("quota exceeded").exit(3).install(dir.path(), "claude");
+        write_providers_yaml(dir.path(), &[("gemini", &gemini), ("claude", &claude)]);
+        let files = ["--project-file", "project.yaml", "--providers-file", "providers.yaml", "--quiet", "--no-cache"];
+        run_cli_in(&dir, &[&["send", "--to", "@backend", "--message", "hello", "--yes"][..], &files].concat());
+        let out = run_cli_in(&dir, &[&["send", "--to", "ok1", "--message", "again"][..], &files].concat());
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+        let conn = open_or_create_db(dir.path().join("multi-agents.sqlite3").to_string_lossy().as_ref()).unwrap();
+        let project_id = db::find_project_id(&conn, db::IdOrName::Name("sent-proj")).unwrap().unwrap();
+        let stats = get_project_stats(&conn, &project_id, OffsetDateTime::now_utc() - Duration::hours(1)).unwrap();
+        assert_eq!((stats.total_sends, stats.broadcasts), (2, 1));
+        assert_eq!((stats.successful_agents, stats.failed_agents), (2, 1));
+        assert!(stats.avg_response_ms.is_some());
+    }
+
+    #[test]
+    fn test_weekly_summary_groups_by_day() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db").to_string_lossy().to_string();
+        let now = OffsetDateTime::now_utc();
+        let project_id = seed(&db_path, now);
+        let conn = open_or_create_db(&db_path).unwrap();
+
+        let days = weekly_summary(&conn, &project_id, now - Duration::days(7)).unwrap();
+        assert_eq!(days.iter().map(|d| d.sends).sum::<u32>(), 3);
+        assert_eq!(days.iter().map(|d| d.failed_agents).sum::<u32>(), 2);
+        let three_days_ago = format_iso8601_utc(now - Duration::days(3))[..10].to_string();
+        let day = days.iter().find(|d| d.day == three_days_ago).unwrap();
+        assert_eq!((day.sends, day.successful_agents, day.failed_agents), (1, 0, 1));
+        assert_eq!(day.avg_response_ms, None);
+        assert!(days.windows(2).all(|w| w[0].day < w[1].day));
+    }
+
+    #[test]
+    fn test_stats_command_validates_input() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db").to_string_lossy().to_string();
+        seed(&db_path, OffsetDateTime::now_utc());
+
+        assert!(run_stats("stats-proj", Some("7d"), true, Some(&db_path), Format::Json, None).is_ok());
+        let err = run_stats("stats-proj", Some("7 days"), false, Some(&db_path), Format::Text, None).unwrap_err();
+        assert!(matches!(err, CliError::Validation { .. }), "{:?}", err);
+        let err = run_stats("ghost", None, false, Some(&db_path), Format::Text, None).unwrap_err();
+        assert!(matches!(err, CliError::Validation { .. }), "{:?}", err);
+    }
+
+    /// b1 (gemini): one active session alive 1h and resumed twice, one expired after 30min.
+    /// f1 (claude): one expired session never used again but resumed once, one older than a week and resumed.
+    fn seed_sessions(db_path: &str, now: OffsetDateTime) -> String {
+        let conn = open_or_create_db(db_path).unwrap();
+        let project = insert_project(&conn, "sess-proj").unwrap();
+        let b1 = insert_agent(&conn, &project.id, "b1", "backend", "gemini", "m", &[], "").unwrap();
+        let f1 = insert_agent(&conn, &project.id, "f1", "frontend", "claude", "m", &[], "").unwrap();
+        let sessions = [
+            (&b1, "gemini", "active", Duration::hours(2), Some(Duration::hours(1)), 2),
+            (&b1, "gemini", "expired", Duration::hours(5), Some(Duration::minutes(270)), 0),
+            (&f1, "claude", "expired", Duration::days(1), None, 1),
+            (&f1, "claude", "active", Duration::days(10), Some(Duration::days(9)), 3),
+        ];
+        for (agent, provider, status, created, last, resumes) in sessions {
+            let session = insert_session(&conn, &project.id, &agent.id, provider, None).unwrap();
+            conn.execute(
+                "UPDATE sessions SET status = ?1, created_at = ?2, last_activity = ?3, resume_count = ?4 WHERE id = ?5",
+                params![status, at(now, created), last.map(|l| at(now, l)), resumes, session.id],
+            ).unwrap();
+        }
+        project.id
+    }
+
+    #[test]
+    fn test_session_stats_last_week() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db").to_string_lossy().to_string();
+        let now = OffsetDateTime::now_utc();
+        let project_id = seed_sessions(&db_path, now);
+        let conn = open_or_create_db(&db_path).unwrap();
+
+        let stats = get_session_stats(&conn, &project_id, now - Duration::days(7)).unwrap();
+        assert_eq!(stats.total_sessions, 3);
+        assert_eq!(stats.resumed_sessions, 2);
+        let lifetime = stats.avg_lifetime_ms.unwrap();
+        assert!((lifetime - 2_700_000.0).abs() < 5.0, "avg lifetime: {}", lifetime);
+        let count = |provider: &str, status: &str, sessions| ProviderSessionCount { provider: provider.into(), status: status.into(), sessions };
+        assert_eq!(stats.by_provider, vec![count("claude", "expired", 1), count("gemini", "active", 1), count("gemini", "expired", 1)]);
+        assert_eq!(stats.by_agent, vec![
+            AgentSessionCount { agent: "b1".into(), sessions: 2, expired: 1 },
+            AgentSessionCount { agent: "f1".into(), sessions: 1, expired: 1 },
+        ]);
+
+        let recent = get_session_stats(&conn, &project_id, now - Duration::hours(3)).unwrap();
+        assert_eq!((recent.total_sessions, recent.resumed_sessions), (1, 1));
+        assert_eq!(recent.by_agent, vec![AgentSessionCount { agent: "b1".into(), sessions: 1, expired: 0 }]);
+    }
+
+    #[test]
+    fn test_send_to_a_conversation_counts_a_resume() {
+        use crate::tests::integration::fake_provider::{run_cli_in, write_providers_yaml, FakeProvider};
+
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("project.yaml"),
+            "schema_version: 1\nproject: resume-proj\nagents:\n  - name: w1\n    role: backend\n    provider: gemini\n    model: m\n    allowed_tools: []\n    system_prompt: \"\"\n  - name: w2\n    role: backend\n    provider: gemini\n    model: m\n    allowed_tools: []\n    system_prompt: \"\"\n",
+        ).unwrap();
+        let gemini = FakeProvider::new().stdout("ok").install(dir.path(), "gemini");
+        write_providers_yaml(dir.path(), &[("gemini", &gemini)]);
+        let files = ["--project-file", "project.yaml", "--providers-file", "providers.yaml", "--quiet", "--no-cache"];
+        let send = |to: &str| {
+            let out = run_cli_in(&dir, &[&["send", "--to", to, "--message", "hi"][..], &files].concat());
+            assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        };
+        send("w1");
+        send("w2");
+        let conn = open_or_create_db(dir.path().join("multi-agents.sqlite3").to_string_lossy().as_ref()).unwrap();
+        let conv_id: String = conn.query_row(
+            "SELECT s.id FROM sessions s JOIN agents a ON a.id = s.agent_id WHERE a.name = 'w1'", [], |r| r.get(0),
+        ).unwrap();
+        send(&conv_id);
+        send(&conv_id);
+
+        let resumes: i64 = conn.query_row("SELECT resume_count FROM sessions WHERE id = ?1", [&conv_id], |r| r.get(0)).unwrap();
+        assert_eq!(resumes, 2);
+        let project_id = db::find_project_id(&conn, db::IdOrName::Name("resume-proj")).unwrap().unwrap();
+        let stats = get_session_stats(&conn, &project_id, OffsetDateTime::now_utc() - Duration::hours(1)).unwrap();
+        assert_eq!((stats.total_sessions, stats.resumed_sessions), (2, 1));
+    }
+
+    #[test]
+    fn test_session_stats_command_text_and_json() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db").to_string_lossy().to_string();
+        let now = OffsetDateTime::now_utc();
+        let project_id = seed_sessions(&db_path, now);
+        let conn = open_or_create_db(&db_path).unwrap();
+        let stats = get_session_stats(&conn, &project_id, now - Duration::days(7)).unwrap();
+
+        let text = crate::commands::stats::render_session_stats("sess-proj", "7d", &stats);
+        assert!(text.contains("Sessions:           3 (2 resumed)\n"), "{}", text);
+        assert!(text.contains("Avg lifetime:       2700.0s"), "{}", text);
+        assert!(text.contains("  b1                   2        1"), "{}", text);
+        assert!(run_session_stats("sess-proj", None, Some(&db_path), Format::Json, None).is_ok());
+        let err = run_session_stats("ghost", None, Some(&db_path), Format::Text, None).unwrap_err();
+        assert!(matches!(err, CliError::Validation { .. }), "{:?}", err);
+    }
+}
@@ -45,3 +45,59 @@ fn supervisor_subscription_detects_routed_event() {
 }
 
 
+
+#[test]
+fn supervisor_subscribe_unsubscribe_and_task_done_notifications() {
+    use crate::cli::commands::{Format, SupervisorEventArg};
+    use crate::commands::supervisor::{run_supervisor_subscribe, run_supervisor_unsubscribe};
+    use crate::supervisor::notify::{update_task_status, NOTIFICATION_SENDER};
+
+    let tmp = tempfile::tempdir().unwrap();
+    let db_path = tmp.path().join("test.db").to_string_lossy().to_string();
+    let conn = db::open_or_create_db(&db_path).unwrap();
+    let p = db::insert_project(&conn, "notify-demo").unwrap();
+    let lead = db::insert_agent(&conn, &p.id, "lead", "lead", "gemini", "m", &[], "sp").unwrap();
+    let api = db::insert_agent(&conn, &p.id, "api", "backend", "gemini", "m", &[], "sp").unwrap();
+    let web = db::insert_agent(&conn, &p.id, "web", "frontend", "gemini", "m", &[], "sp").unwrap();
+    for (id, assignee) in [("t-api", &api.id), ("t-web", &web.id)] {
+        conn.execute(
+            "INSERT INTO tasks(id, project_id, title, status, assignee_agent_id, created_at) VALUES (?1, ?2, 'ship it', 'todo', ?3, ?4)",
+            rusqlite::params![id, p.id, assignee, db::now_iso8601_utc()],
+        ).unwrap();
+    }
+
+    let on = [SupervisorEventArg::TaskDone, SupervisorEventArg::SendFailed];
+    run_supervisor_subscribe("notify-demo", "lead", &on, "@backend", Some(&db_path), Format::Json, None).unwrap();
+    assert_eq!(db::list_subscriptions(&conn, &p.id).unwrap().len(), 2);
+    let err = run_supervisor_subscribe("notify-demo", "lead", &on, "@", Some(&db_path), Format::Text, None).unwrap_err();
+    assert_eq!(err.exit_code(), 2);
+    assert!(run_supervisor_subscribe("notify-demo", "nobody", &on, "@all", Some(&db_path), Format::Text, None).is_err());
+
+    let lead_messages = || -> Vec<(String, String)> {
+        let mut stmt = conn.prepare(
+            "SELECT m.sender, m.content FROM messages m JOIN sessions s ON s.id = m.session_id WHERE s.agent_id = ?1 ORDER BY m.rowid",
+        ).unwrap();
+        let rows = stmt.query_map([&lead.id], |r| Ok((r.get(0)?, r.get(1)?))).unwrap();
+        rows.map(Result::unwrap).collect()
+    };
+
+    // Only the backend task notifies, and only when it reaches `done`
+    update_task_status(&conn, "t-api", "in_progress").unwrap().unwrap();
+    update_task_status(&conn, "t-web", "done").unwrap().unwrap();
+    assert!(lead_messages().is_empty());
+    // `task status` is the CLI path that raises it
+    crate::commands::task::run_task_status("t-api", "done", Some(&db_path), Format::Text).unwrap();
+    assert_eq!(lead_messages(), vec![(NOTIFICATION_SENDER.to_string(), "[task_done] api (@backend): task t-api \"ship it\"".to_string())]);
+
+    let err = crate::commands::task::run_task_status("t-none", "done", Some(&db_path), Format::Text).unwrap_err();
+    assert_eq!(err.exit_code(), 2);
+
+    // Unsubscribing one kind keeps the other
+    run_supervisor_unsubscribe("notify-demo", "lead", &[SupervisorEventArg::TaskDone], None, Some(&db_path), Format::Text, None).unwrap();
+    let left = db::list_subscriptions(&conn, &p.id).unwrap();
+    assert_eq!(left.iter().map(|s| s.event_kind.as_str()).collect::<Vec<_>>(), ["send_failed"]);
+    update_task_status(&conn, "t-api", "done").unwrap().unwrap();
+    assert_eq!(lead_messages().len(), 1);
+    run_supervisor_unsubscribe("notify-demo", "lead", &[], Some("@backend"), Some(&db_path), Format::Json, None).unwrap();
+    assert!(db::list_subscriptions(&conn, &p.id).unwrap().is_empty());
+}
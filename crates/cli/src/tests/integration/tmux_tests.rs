@@ -5,6 +5,7 @@ use crate::tmux::*;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
 
     #[test]
     fn test_tmux_race_condition_detection() {
@@ -76,4 +77,133 @@ mod tests {
                "TMUX_RETRY_DELAY_MS should be >= 50ms to avoid overwhelming, got {}ms", 
                crate::utils::TMUX_RETRY_DELAY_MS);
     }
+
+    /// Write a fake tmux that reports `current` as the pane command and `pane` as its contents
+    fn fake_tmux(dir: &std::path::Path, current: &str, pane: &str) -> String {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join("tmux");
+        let script = format!(
+            "#!/bin/sh\ncase \"$1\" in\n  display-message) printf '%s\\n' '{}' ;;\n  capture-pane) printf '{}\\n' ;;\nesac\nexit 0\n",
+            current, pane
+        );
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_verify_provider_started_when_pane_runs_provider() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let tmux = TmuxManager::with_binary(Duration::from_secs(1), fake_tmux(dir.path(), "gemini", "Gemini CLI ready"));
+        let result = crate::commands::verify_provider_started(&tmux, "proj:demo", "backend:b1", "/usr/local/bin/gemini", Duration::from_millis(500));
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_verify_provider_started_reports_pane_tail_when_missing() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let tmux = TmuxManager::with_binary(Duration::from_secs(1), fake_tmux(dir.path(), "bash", "$ gemini -i prompt\nbash: gemini: command not found"));
+        let start = std::time::Instant::now();
+        let err = crate::commands::verify_provider_started(&tmux, "proj:demo", "backend:b1", "gemini", Duration::from_secs(3)).unwrap_err();
+        assert!(err.contains("bash: gemini: command not found"), "pane tail: {}", err);
+        // Known failure output short-circuits the poll
+        assert!(start.elapsed() < Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_verify_provider_started_times_out_on_idle_shell() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let tmux = TmuxManager::with_binary(Duration::from_secs(1), fake_tmux(dir.path(), "zsh", "$ gemini -i prompt"));
+        let err = crate::commands::verify_provider_started(&tmux, "proj:demo", "backend:b1", "gemini", Duration::from_millis(300)).unwrap_err();
+        assert!(err.contains("gemini -i prompt"));
+    }
+
+    #[test]
+    fn test_agent_run_detached_check_exits_4_when_pane_shows_command_not_found() {
+        use crate::tests::integration::fake_provider::{cli_command, write_providers_yaml};
+        let dir = tempfile::TempDir::new().unwrap();
+        // A tmux with no sessions yet, whose new pane only ever shows the shell's complaint
+        let bin = dir.path().join("bin");
+        std::fs::create_dir(&bin).unwrap();
+        fake_tmux(&bin, "bash", "$ gemini -i prompt\nbash: gemini: command not found");
+        let script = std::fs::read_to_string(bin.join("tmux")).unwrap()
+            .replace("case \"$1\" in\n", "case \"$1\" in\n  has-session) exit 1 ;;\n");
+        std::fs::write(bin.join("tmux"), script).unwrap();
+        std::fs::write(dir.path().join("project.yaml"),
+            "schema_version: 1\nproject: tmux-proj\nagents:\n  - name: b1\n    role: backend\n    provider: gemini\n    model: m\n    allowed_tools: [Read]\n    system_prompt: \"\"\n",
+        ).unwrap();
+        write_providers_yaml(dir.path(), &[("gemini", "gemini")]);
+
+        let path = format!("{}:{}", bin.display(), std::env::var("PATH").unwrap_or_default());
+        let out = cli_command(&dir, &[
+            "agent", "run", "--project-file", "project.yaml", "--providers-file", "providers.yaml",
+            "--agent", "b1", "--no-logs", "--timeout-ms", "1000", "--detached-check",
+        ]).env("PATH", path).output().unwrap();
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        assert_eq!(out.status.code(), Some(4), "{}", stderr);
+        assert!(stderr.contains("bash: gemini: command not found"), "{}", stderr);
+    }
+
+    #[test]
+    fn test_check_repl_alive_fails_unless_the_provider_is_in_the_foreground() {
+        let dir = tempfile::TempDir::new().unwrap();
+        // A provider still in the foreground passes; one that dropped back to the shell does not
+        let tmux = TmuxManager::with_binary(Duration::from_secs(1), fake_tmux(dir.path(), "gemini", "Gemini CLI ready"));
+        assert!(crate::commands::check_repl_alive(&tmux, "proj:demo", "backend:b1", "/usr/local/bin/gemini").is_ok());
+        let tmux = TmuxManager::with_binary(Duration::from_secs(1), fake_tmux(dir.path(), "zsh", "Error: not logged in"));
+        assert_eq!(crate::commands::check_repl_alive(&tmux, "proj:demo", "backend:b1", "gemini").unwrap_err().exit_code(), 4);
+    }
+
+    #[test]
+    fn test_tmux_version_maps_to_pipe_pane_only_flag() {
+        let cases = [
+            ("tmux 3.3a\n", Some((3, 3)), true),
+            ("tmux 3.4", Some((3, 4)), true),
+            ("tmux 1.8", Some((1, 8)), true),
+            ("tmux 1.1", Some((1, 1)), true),
+            ("tmux 1.0", Some((1, 0)), false),
+            ("tmux 0.9", Some((0, 9)), false),
+            ("tmux next-3.5", None, true),
+            ("tmux master", None, true),
+            ("tmux openbsd-7.4", None, true),
+        ];
+        for (output, version, supported) in cases {
+            assert_eq!(parse_tmux_version(output), version, "{:?}", output);
+            assert_eq!(pipe_pane_only_supported(output), supported, "{:?}", output);
+        }
+    }
+
+    /// Fake tmux reporting `version` for `-V` and recording its other calls
+    fn versioned_tmux(dir: &std::path::Path, version: &str) -> String {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join("tmux");
+        let script = format!(
+            "#!/bin/sh\nif [ \"$1\" = -V ]; then echo '{}'; exit 0; fi\necho \"$*\" >> {}/calls\nexit 0\n",
+            version, dir.display()
+        );
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_setup_pipe_pane_drops_only_flag_on_old_tmux() {
+        for (version, expected) in [("tmux 3.4", "pipe-pane -t proj:demo:backend:b1 -o cat >> /tmp/b1.ndjson"), ("tmux 1.0", "pipe-pane -t proj:demo:backend:b1 cat >> /tmp/b1.ndjson")] {
+            let dir = tempfile::TempDir::new().unwrap();
+            let tmux = TmuxManager::with_binary(Duration::from_secs(1), versioned_tmux(dir.path(), version));
+            tmux.setup_pipe_pane("proj:demo", "backend:b1", "cat >> /tmp/b1.ndjson").unwrap();
+            assert_eq!(std::fs::read_to_string(dir.path().join("calls")).unwrap().trim(), expected, "{}", version);
+        }
+    }
+
+    #[test]
+    fn test_attach_window_selects_the_agent_window_and_maps_read_only() {
+        for (read_only, attach) in [(false, "attach-session -t proj:demo:backend:b1"), (true, "attach-session -r -t proj:demo:backend:b1")] {
+            let dir = tempfile::TempDir::new().unwrap();
+            let tmux = TmuxManager::with_binary(Duration::from_secs(1), versioned_tmux(dir.path(), "tmux 3.4"));
+            tmux.attach_window("proj:demo", "backend:b1", read_only).unwrap();
+            let calls = std::fs::read_to_string(dir.path().join("calls")).unwrap();
+            assert_eq!(calls.lines().collect::<Vec<_>>(), vec!["select-window -t proj:demo:backend:b1", attach], "read_only={}", read_only);
+        }
+    }
 }
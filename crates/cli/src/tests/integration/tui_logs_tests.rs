@@ -0,0 +1,214 @@
+//! TUI logs view tests
+//!
+//! Cover tail parsing of NDJSON log files, following appended lines, the logs state key handling
+//! and rendering of stderr lines and filters.
+
+use std::error::Error;
+use std::io::Write;
+
+use ratatui::{backend::TestBackend, Terminal};
+use tempfile::TempDir;
+
+use crate::logging::{log_path, Event};
+use crate::tui::{
+    components::{parse_tail, LogLevel, LogTail},
+    state::{
+        selection_store,
+        view_state::{LogsState, SessionsState},
+        StateTransition, TuiState,
+    },
+    themes::{default_typography, ThemeKind},
+    views::render_logs_view,
+};
+
+fn event_line(event: &str, level: &str, text: &str) -> String {
+    let mut line = Event::builder("demo", "backend", "mock", event).level(level).text(Some(text)).build().to_line();
+    line.push('\n');
+    line
+}
+
+/// Write `lines` to the backend agent's log under a fresh logs base
+fn write_log(lines: &[String]) -> (TempDir, String) {
+    let dir = TempDir::new().unwrap();
+    let base = dir.path().to_string_lossy().to_string();
+    let path = log_path(&base, "demo", "backend");
+    std::fs::create_dir_all(std::path::Path::new(&path).parent().unwrap()).unwrap();
+    std::fs::write(&path, lines.concat()).unwrap();
+    (dir, base)
+}
+
+fn target() -> selection_store::LogTarget {
+    selection_store::LogTarget { project: "demo".into(), role: "backend".into(), agent: "worker".into() }
+}
+
+fn buffer_text(terminal: &Terminal<TestBackend>) -> String {
+    let buffer = terminal.backend().buffer();
+    let width = buffer.area.width as usize;
+    buffer.content().chunks(width).map(|row| row.iter().map(|c| c.symbol()).collect::<String>() + "\n").collect()
+}
+
+#[test]
+fn parse_tail_drops_truncated_first_line_and_keeps_partial_last() {
+    let first = event_line("stdout_line", "info", "first");
+    let second = event_line("stderr_line", "error", "second");
+    let buf = format!("{}{}{{\"ts\":\"2024", &first[10..], second);
+
+    let (entries, consumed) = parse_tail(buf.as_bytes(), true);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].message, "second");
+    assert_eq!(entries[0].level, LogLevel::Error);
+    assert!(entries[0].is_stderr());
+    assert_eq!(consumed, buf.len() - "{\"ts\":\"2024".len());
+
+    let whole = format!("{}{}", first, second);
+    let (entries, consumed) = parse_tail(whole.as_bytes(), false);
+    assert_eq!(entries.iter().map(|e| e.message.as_str()).collect::<Vec<_>>(), vec!["first", "second"]);
+    assert_eq!(consumed, whole.len());
+}
+
+#[test]
+fn log_tail_reads_only_the_end_then_follows_appends_and_truncation() -> Result<(), Box<dyn Error>> {
+    let lines: Vec<String> = (0..200).map(|i| event_line("stdout_line", "info", &format!("line {}", i))).collect();
+    let (_dir, base) = write_log(&lines);
+    let path = log_path(&base, "demo", "backend");
+
+    let max_bytes = (lines[197..].concat().len() + 5) as u64;
+    let (mut tail, entries) = LogTail::open(&path, max_bytes)?;
+    let messages: Vec<&str> = entries.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(messages, vec!["line 197", "line 198", "line 199"]);
+
+    // A window starting exactly on a line boundary keeps that line
+    let exact = lines[197..].concat().len() as u64;
+    let (_, entries) = LogTail::open(&path, exact)?;
+    assert_eq!(entries.first().map(|e| e.message.as_str()), Some("line 197"));
+
+    assert!(tail.poll()?.is_empty());
+    let mut file = std::fs::OpenOptions::new().append(true).open(&path)?;
+    let next = event_line("stderr_line", "warn", "appended");
+    file.write_all(&next.as_bytes()[..20])?;
+    assert!(tail.poll()?.is_empty(), "a partial line waits for its newline");
+    file.write_all(&next.as_bytes()[20..])?;
+    let entries = tail.poll()?;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].message, "appended");
+
+    std::fs::write(&path, event_line("end", "info", "restarted"))?;
+    let entries = tail.poll()?;
+    assert_eq!(entries.iter().map(|e| e.message.as_str()).collect::<Vec<_>>(), vec!["restarted"]);
+    Ok(())
+}
+
+#[test]
+fn logs_state_filters_by_level_and_event_and_follows() -> Result<(), Box<dyn Error>> {
+    let (_dir, base) = write_log(&[
+        event_line("stdout_line", "info", "hello"),
+        event_line("stderr_line", "warn", "careful"),
+        event_line("end", "info", "exited"),
+    ]);
+    let mut state = LogsState::new();
+    state.open(target(), &base);
+    assert!(state.error.is_none());
+    assert_eq!(state.viewer.get_filtered_logs().len(), 3);
+
+    // trace → debug → info → warn
+    for _ in 0..3 {
+        state.handle_input("l")?;
+    }
+    assert_eq!(state.min_level, LogLevel::Warn);
+    let shown: Vec<String> = state.viewer.get_filtered_logs().iter().map(|e| e.message.clone()).collect();
+    assert_eq!(shown, vec!["careful"]);
+    state.handle_input("l")?;
+    state.handle_input("l")?;
+    assert_eq!(state.min_level, LogLevel::Trace);
+
+    // all → stdout_line
+    state.handle_input("e")?;
+    let shown: Vec<String> = state.viewer.get_filtered_logs().iter().map(|e| e.message.clone()).collect();
+    assert_eq!(shown, vec!["hello"]);
+    for _ in 0..4 {
+        state.handle_input("e")?;
+    }
+    assert_eq!(state.viewer.get_filtered_logs().len(), 3);
+
+    // Following picks up appended lines on tick; paused it does not
+    let path = log_path(&base, "demo", "backend");
+    let mut file = std::fs::OpenOptions::new().append(true).open(&path)?;
+    file.write_all(event_line("stdout_line", "info", "more").as_bytes())?;
+    state.handle_input("f")?;
+    assert!(!state.follow);
+    state.on_tick();
+    assert_eq!(state.viewer.get_filtered_logs().len(), 3);
+    state.handle_input("f")?;
+    assert_eq!(state.viewer.get_filtered_logs().len(), 4);
+
+    assert!(matches!(state.handle_input("esc")?, StateTransition::Transition(s) if s == "sessions"));
+    assert!(matches!(state.handle_input("q")?, StateTransition::Exit));
+    Ok(())
+}
+
+#[test]
+fn logs_state_reports_missing_file() {
+    let dir = TempDir::new().unwrap();
+    let mut state = LogsState::new();
+    state.open(target(), &dir.path().to_string_lossy());
+    assert!(state.error.as_deref().unwrap_or("").contains("worker.ndjson"));
+    assert!(state.render().unwrap().contains("cannot read"));
+}
+
+#[test]
+fn logs_view_renders_entries_stderr_and_filter_bar() -> Result<(), Box<dyn Error>> {
+    let (_dir, base) = write_log(&[
+        event_line("stdout_line", "info", "compiling"),
+        event_line("stderr_line", "error", "boom"),
+    ]);
+    let mut state = LogsState::new();
+    state.open(target(), &base);
+
+    let theme = ThemeKind::Dark.palette();
+    let typo = default_typography(&theme);
+    let mut terminal = Terminal::new(TestBackend::new(100, 12))?;
+    terminal.draw(|f| render_logs_view(f, f.area(), &state, &theme, &typo))?;
+    let text = buffer_text(&terminal);
+    assert!(text.contains("stdout_line | compiling"), "{}", text);
+    assert!(text.contains("[ERROR] stderr_line | boom"), "{}", text);
+    assert!(text.contains("Event: all"), "{}", text);
+    assert!(text.contains("f follow: ON"), "{}", text);
+
+    // The stderr line is drawn in the error color
+    let buffer = terminal.backend().buffer();
+    let row = (0..buffer.area.height)
+        .find(|&y| (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect::<String>().contains("boom"))
+        .expect("stderr row");
+    let x = (0..buffer.area.width).find(|&x| buffer[(x, row)].symbol() == "b").unwrap();
+    assert_eq!(buffer[(x, row)].fg, theme.error);
+
+    state.handle_input("e")?;
+    state.handle_input("e")?;
+    terminal.draw(|f| render_logs_view(f, f.area(), &state, &theme, &typo))?;
+    let text = buffer_text(&terminal);
+    assert!(text.contains("Event: stderr_line"), "{}", text);
+    assert!(!text.contains("compiling"), "{}", text);
+    Ok(())
+}
+
+#[test]
+fn sessions_g_opens_logs_for_selected_agent() -> Result<(), Box<dyn Error>> {
+    let dir = TempDir::new()?;
+    let db_path = dir.path().join("test.db").to_string_lossy().to_string();
+    let conn = db::open_or_create_db(&db_path)?;
+    let project = db::insert_project(&conn, "demo")?;
+    let agent = db::insert_agent(&conn, &project.id, "worker", "backend", "mock", "m", &[], "sp")?;
+    db::insert_session(&conn, &project.id, &agent.id, "mock", None)?;
+
+    let mut sessions = SessionsState::new();
+    assert!(matches!(sessions.handle_input("g")?, StateTransition::Stay));
+
+    sessions.load_from_db_with_filters(&db_path, Some(project.id.clone()), None)?;
+    assert_eq!(sessions.project_name.as_deref(), Some("demo"));
+    assert_eq!(sessions.sessions[0].agent_name, "worker");
+    assert_eq!(sessions.sessions[0].role, "backend");
+    sessions.selected_session = Some(0);
+    assert!(matches!(sessions.handle_input("g")?, StateTransition::Transition(s) if s == "logs"));
+    assert_eq!(selection_store::get_log_target(), Some(target()));
+    Ok(())
+}
@@ -0,0 +1,85 @@
+//! Integration tests for the upgrade check, with the GitHub API responses mocked
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::cmp::Ordering;
+    use crate::commands::{check_upgrade, compare_versions, upgrade_json, upgrade_message};
+    use crate::utils::CliError;
+
+    const LATEST: &str = r#"{"tag_name":"v0.2.0","html_url":"https://github.com/Fenikz31/multi-agents/releases/tag/v0.2.0","prerelease":false,"draft":false,"assets":[]}"#;
+    const RELEASES: &str = r#"[
+        {"tag_name":"v0.4.0","prerelease":false,"draft":true},
+        {"tag_name":"v0.3.0-rc.2","html_url":"https://example.test/rc2","prerelease":true,"draft":false},
+        {"tag_name":"v0.3.0-rc.10","html_url":"https://example.test/rc10","prerelease":true,"draft":false},
+        {"tag_name":"v0.2.0","html_url":"https://example.test/v0.2.0","prerelease":false,"draft":false}
+    ]"#;
+
+    #[test]
+    fn test_upgrade_available_from_latest_release() {
+        let urls = RefCell::new(Vec::new());
+        let check = check_upgrade("0.1.0", false, |url| {
+            urls.borrow_mut().push(url.to_string());
+            Ok(LATEST.to_string())
+        }).unwrap();
+        assert_eq!(urls.into_inner(), vec!["https://api.github.com/repos/Fenikz31/multi-agents/releases/latest"]);
+        assert!(check.upgrade_available);
+        assert_eq!(upgrade_message(&check), "upgrade available: 0.2.0 (current: 0.1.0). Install: cargo install multi-agents");
+
+        let json = upgrade_json(&check, false);
+        assert_eq!(json["latest"], "0.2.0");
+        assert_eq!(json["current"], "0.1.0");
+        assert_eq!(json["upgrade_available"], true);
+        assert_eq!(json["url"], "https://github.com/Fenikz31/multi-agents/releases/tag/v0.2.0");
+    }
+
+    #[test]
+    fn test_already_up_to_date_when_current_is_latest_or_newer() {
+        for current in ["0.2.0", "0.3.0-dev"] {
+            let check = check_upgrade(current, false, |_| Ok(LATEST.to_string())).unwrap();
+            assert!(!check.upgrade_available, "{}", current);
+            assert_eq!(upgrade_message(&check), "already up to date");
+        }
+    }
+
+    #[test]
+    fn test_prerelease_picks_highest_non_draft_tag() {
+        let check = check_upgrade("0.2.0", true, |url| {
+            assert!(url.ends_with("/releases?per_page=30"), "{}", url);
+            Ok(RELEASES.to_string())
+        }).unwrap();
+        assert_eq!(check.latest, "0.3.0-rc.10");
+        assert_eq!(check.url, "https://example.test/rc10");
+        assert!(check.upgrade_available);
+
+        // Without --prerelease the same running version is current
+        let stable = check_upgrade("0.2.0", false, |_| Ok(LATEST.to_string())).unwrap();
+        assert!(!stable.upgrade_available);
+    }
+
+    #[test]
+    fn test_upgrade_errors_are_reported() {
+        let err = check_upgrade("0.1.0", false, |_| Err(CliError::Other("upgrade: GitHub API returned HTTP 403".into()))).unwrap_err();
+        assert!(err.to_string().contains("HTTP 403"), "{}", err);
+
+        let err = check_upgrade("0.1.0", false, |_| Ok("<html>".to_string())).unwrap_err();
+        assert!(err.to_string().contains("unexpected release response"), "{}", err);
+
+        let err = check_upgrade("0.1.0", false, |_| Ok(r#"{"tag_name":"nightly"}"#.to_string())).unwrap_err();
+        assert!(err.to_string().contains("cannot parse release tag 'nightly'"), "{}", err);
+
+        let err = check_upgrade("0.1.0", true, |_| Ok("[]".to_string())).unwrap_err();
+        assert!(err.to_string().contains("no published release"), "{}", err);
+    }
+
+    #[test]
+    fn test_compare_versions_follows_semver_precedence() {
+        assert_eq!(compare_versions("v0.2.0", "0.1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("0.10.0", "0.9.0"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0.0-rc.1", "1.0.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.0.0-rc.10", "1.0.0-rc.2"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0.0-alpha", "1.0.0-1"), Ordering::Greater);
+        assert_eq!(compare_versions("v1.2", "1.2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("nightly", "0.0.1"), Ordering::Less);
+    }
+}
@@ -0,0 +1,26 @@
+//! Unit tests for global CLI flags
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use crate::cli::parser::verbosity_filter;
+    use crate::Cli;
+
+    #[test]
+    fn test_verbosity_filter_levels() {
+        assert_eq!(verbosity_filter(0), "warn");
+        assert_eq!(verbosity_filter(1), "info");
+        assert_eq!(verbosity_filter(2), "debug");
+        assert_eq!(verbosity_filter(3), "trace");
+        assert_eq!(verbosity_filter(7), "trace");
+    }
+
+    #[test]
+    fn test_log_filter_from_flags() {
+        let cli = Cli::try_parse_from(["multi-agents", "doctor", "-vv"]).unwrap();
+        assert_eq!(cli.verbose, 2);
+        assert_eq!(cli.log_filter(), "debug");
+        let cli = Cli::try_parse_from(["multi-agents", "-v", "doctor", "--quiet"]).unwrap();
+        assert_eq!(cli.log_filter(), "warn");
+    }
+}
@@ -84,6 +84,8 @@ mod tests {
         assert_eq!(start_event.provider, "claude");
         assert!(start_event.text.is_none());
         assert!(start_event.dur_ms.is_none());
+        // Event timestamps use the canonical DB layout (RFC3339 UTC, millisecond precision)
+        assert_eq!(db::normalize_iso8601_utc(&start_event.ts).unwrap(), start_event.ts);
 
         let stdout_event = NdjsonEvent::new_stdout_line("demo", "backend", "backend-agent", "claude", "Hello World");
         assert_eq!(stdout_event.event, "stdout_line");
@@ -137,8 +139,8 @@ mod tests {
         );
         assert!(res.is_ok());
 
-        // Check the file was created and contains expected content
-        let path = format!("./logs/{}/{}.ndjson", "demo", "backend");
+        // Check the agent's file was created and contains expected content
+        let path = format!("./logs/{}/{}.ndjson", "demo", "backend1");
         let content = std::fs::read_to_string(path).unwrap();
         assert!(content.contains("\"event\":\"routed\""));
         assert!(content.contains("b-123"));
@@ -147,4 +149,348 @@ mod tests {
         // Restore original directory
         std::env::set_current_dir(original_dir).unwrap();
     }
+
+    #[test]
+    fn test_log_path_builder() {
+        assert_eq!(log_path(DEFAULT_LOG_BASE, "demo", "backend"), "./logs/demo/backend.ndjson");
+        assert_eq!(log_path("/var/log/ma", "demo", "backend"), "/var/log/ma/demo/backend.ndjson");
+        assert_eq!(log_path("/var/log/ma/", "demo", "backend"), "/var/log/ma/demo/backend.ndjson");
+        assert_eq!(log_path("/var/log/ma//", "demo", "backend"), "/var/log/ma/demo/backend.ndjson");
+        assert_eq!(log_path("/", "demo", "backend"), "/demo/backend.ndjson");
+    }
+
+    #[test]
+    fn test_resolve_log_base_priority() {
+        assert_eq!(resolve_log_base(None, None), "./logs");
+        assert_eq!(resolve_log_base(None, Some("/env/logs")), "/env/logs");
+        assert_eq!(resolve_log_base(Some("/flag/logs"), Some("/env/logs")), "/flag/logs");
+        assert_eq!(resolve_log_base(Some(""), Some("")), "./logs");
+    }
+
+    /// `flush_all` drains every open writer, so tests that call it or look at what is still buffered take turns
+    fn writer_state() -> std::sync::MutexGuard<'static, ()> {
+        static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Stream a few lines then end, returning without `flush_all` like a process that exits abruptly
+    fn stream_then_end(log_file: &str) {
+        for i in 0..3 {
+            let line = NdjsonEvent::new_stdout_line("demo", "backend", "backend1", "claude", &format!("line {}", i));
+            write_ndjson_event(log_file, &line).unwrap();
+        }
+        let end = NdjsonEvent::new_end("demo", "backend", "backend1", "claude", 42, "ok");
+        write_ndjson_event(log_file, &end).unwrap();
+    }
+
+    #[test]
+    fn test_end_event_is_durable_without_flush_all() {
+        let _writers = writer_state();
+        let tmp = tempfile::tempdir().unwrap();
+        let log_file = tmp.path().join("demo/backend.ndjson").to_string_lossy().to_string();
+
+        let line = NdjsonEvent::new_stdout_line("demo", "backend", "backend1", "claude", "buffered");
+        write_ndjson_event(&log_file, &line).unwrap();
+        assert_eq!(std::fs::read_to_string(&log_file).unwrap(), "", "stdout lines are buffered");
+
+        stream_then_end(&log_file);
+        let content = std::fs::read_to_string(&log_file).unwrap();
+        let events: Vec<NdjsonEvent> = content.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[0].text.as_deref(), Some("buffered"));
+        assert_eq!(events[4].event, "end");
+        // The handle stays open for the next run; only exit or `close_path` closes it
+        assert!(has_open_writer(&log_file));
+        close_path(&log_file).unwrap();
+        assert!(!has_open_writer(&log_file));
+    }
+
+    #[test]
+    fn test_close_path_reopens_a_replaced_file() {
+        let _writers = writer_state();
+        let tmp = tempfile::tempdir().unwrap();
+        let log_file = tmp.path().join("replaced.ndjson").to_string_lossy().to_string();
+        append_line(&log_file, r#"{"event":"start"}"#, true).unwrap();
+        append_line(&log_file, r#"{"event":"stdout_line"}"#, false).unwrap();
+
+        close_path(&log_file).unwrap();
+        assert_eq!(std::fs::read_to_string(&log_file).unwrap().lines().count(), 2);
+        std::fs::rename(&log_file, format!("{}.old", log_file)).unwrap();
+        append_line(&log_file, r#"{"event":"end"}"#, true).unwrap();
+        assert_eq!(std::fs::read_to_string(&log_file).unwrap(), "{\"event\":\"end\"}\n");
+        close_path(&log_file).unwrap();
+    }
+
+    #[test]
+    fn test_flush_all_writes_pending_lines() {
+        let _writers = writer_state();
+        let tmp = tempfile::tempdir().unwrap();
+        let log_file = tmp.path().join("pending.ndjson").to_string_lossy().to_string();
+        append_line(&log_file, r#"{"event":"tool_call"}"#, is_durable_event("tool_call")).unwrap();
+        assert!(has_open_writer(&log_file));
+
+        flush_all();
+        assert_eq!(std::fs::read_to_string(&log_file).unwrap(), "{\"event\":\"tool_call\"}\n");
+        assert!(!has_open_writer(&log_file));
+    }
+
+    #[test]
+    fn test_concurrent_writers_never_interleave_lines() {
+        let _writers = writer_state();
+        let tmp = tempfile::tempdir().unwrap();
+        let log_file = tmp.path().join("demo/backend.ndjson").to_string_lossy().to_string();
+        // Lines larger than the write buffer go straight to the file and are the easiest to split
+        let payload = "x".repeat(FLUSH_BYTES + 100);
+        let handles: Vec<_> = (0..2).map(|t| {
+            let (log_file, payload) = (log_file.clone(), payload.clone());
+            std::thread::spawn(move || {
+                for i in 0..50 {
+                    let line = serde_json::json!({"event": "stdout_line", "thread": t, "i": i, "text": payload}).to_string();
+                    append_line_with_sync(&log_file, &line, false, i % 10 == 0).unwrap();
+                }
+            })
+        }).collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        flush_all();
+
+        let content = std::fs::read_to_string(&log_file).unwrap();
+        let mut per_thread = [0usize; 2];
+        for line in content.lines() {
+            let v: serde_json::Value = serde_json::from_str(line).expect("whole JSON line");
+            assert_eq!(v["text"].as_str().unwrap().len(), payload.len());
+            per_thread[v["thread"].as_u64().unwrap() as usize] += 1;
+        }
+        assert_eq!(per_thread, [50, 50]);
+    }
+
+    #[test]
+    fn test_sync_mode_writes_every_line_through() {
+        let tmp = tempfile::tempdir().unwrap();
+        let log_file = tmp.path().join("synced.ndjson").to_string_lossy().to_string();
+        append_line_with_sync(&log_file, r#"{"event":"stdout_line"}"#, false, true).unwrap();
+        assert_eq!(std::fs::read_to_string(&log_file).unwrap(), "{\"event\":\"stdout_line\"}\n");
+        append_line_with_sync(&log_file, r#"{"event":"stderr_line"}"#, false, true).unwrap();
+        assert_eq!(std::fs::read_to_string(&log_file).unwrap(), "{\"event\":\"stdout_line\"}\n{\"event\":\"stderr_line\"}\n");
+
+        append_line_with_sync(&log_file, r#"{"event":"end"}"#, true, true).unwrap();
+        assert_eq!(std::fs::read_to_string(&log_file).unwrap().lines().collect::<Vec<_>>(), [
+            r#"{"event":"stdout_line"}"#, r#"{"event":"stderr_line"}"#, r#"{"event":"end"}"#,
+        ]);
+        close_path(&log_file).unwrap();
+    }
+
+    #[test]
+    fn test_log_sync_from_env() {
+        assert!(log_sync_from_env(Some("1")));
+        assert!(log_sync_from_env(Some("TRUE")));
+        assert!(!log_sync_from_env(Some("0")));
+        assert!(!log_sync_from_env(Some("")));
+        assert!(!log_sync_from_env(None));
+    }
+
+    fn line_keys(line: &str) -> Vec<String> {
+        let v: serde_json::Value = serde_json::from_str(line).expect("JSON line");
+        let mut keys: Vec<String> = v.as_object().expect("object").keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    fn event_fields() -> Vec<String> {
+        let mut fields: Vec<String> = EVENT_FIELDS.iter().map(|f| f.to_string()).collect();
+        fields.sort();
+        fields
+    }
+
+    #[test]
+    fn test_event_builder_writes_every_field() {
+        let event = Event::builder("demo", "backend", "claude", "end")
+            .session_id(Some("s1"))
+            .exit_code(Some(0))
+            .ts("2025-01-01T00:00:00.000Z")
+            .build();
+        let line = event.to_line();
+        assert!(line.starts_with(r#"{"ts":"2025-01-01T00:00:00.000Z","level":"info","project_id":"demo""#), "{}", line);
+        assert_eq!(line_keys(&line), event_fields());
+        assert_eq!(event.direction, "system");
+        assert!(line.contains(r#""broadcast_id":null"#));
+
+        let parsed: Event = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.exit_code, Some(0));
+        // Lines written before the schema was unified still parse
+        let legacy: Event = serde_json::from_str(r#"{"ts":"t","project_id":"demo","event":"start"}"#).unwrap();
+        assert_eq!(legacy.event, "start");
+    }
+
+    #[test]
+    fn test_every_event_kind_round_trips_through_from_line() {
+        let events = [
+            Event::new_start("demo", "backend", "be1", "claude"),
+            Event::new_stdout_line("demo", "backend", "be1", "claude", "\u{1b}[32mhello\u{1b}[0m"),
+            Event::new_end("demo", "backend", "be1", "claude", 42, "ok"),
+            Event::new_metrics("demo", "backend", "be1", "claude", "send", 7, "ok", Some("fast")),
+            Event::new_failure_metrics("demo", "backend", "be1", "claude", "provider", "timeout", 9, "no answer"),
+            Event::new_start_with_broadcast("demo", "backend", "be1", "claude", Some("b1")),
+            Event::new_routed("demo", "backend", "be1", "claude", Some("b1".into()), Some("m1".into())),
+            Event::builder("demo", "backend", "claude", "end")
+                .session_id(Some("s1"))
+                .exit_code(Some(0))
+                .output(Some(2), Some(12))
+                .cached(Some(true))
+                .build(),
+        ];
+        for event in events {
+            let line = event.to_line();
+            let parsed = Event::from_line(&line).unwrap_or_else(|e| panic!("{}: {}", e, line));
+            assert_eq!(parsed.to_line(), line);
+        }
+
+        let old = Event::from_line(r#"{"ts":"t","project_id":"demo","agent_role":"backend","provider":"gemini","session_id":"s1","direction":"agent","event":"stdout_line","text":"hi"}"#).unwrap();
+        assert_eq!(old.text.as_deref(), Some("hi"));
+        assert_eq!((old.lines, old.bytes, old.cached), (None, None, None));
+        assert!(Event::from_line(r#"{"event":"end","exit_code":"zero"}"#).is_err());
+    }
+
+    #[test]
+    fn test_ndjson_self_check_reports_mistyped_fields() {
+        let line = r#"{"ts":"2025-09-15T14:03:21.123Z","project_id":"demo","agent_role":"backend","provider":"gemini","session_id":"s1","direction":"agent","event":"end","dur_ms":"slow"}"#;
+        let path = write_tmp(&format!("{}\n", line));
+        let rep = ndjson_self_check(&path).expect("self check");
+        let errs = rep["errors"].as_array().unwrap();
+        assert!(errs.iter().any(|e| e["error"] == "invalid_field" && e["line"] == 1), "{:?}", errs);
+        assert_eq!(rep["ok_lines"].as_u64().unwrap(), 0);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_send_and_agent_flows_emit_unified_schema() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path().to_string_lossy().to_string();
+        let log_file = log_path(&base, "demo", "backend");
+        // send path: the events `log_ndjson` builds
+        let send = |direction: &str, event: &str, text: Option<&str>, exit_code: Option<i32>| {
+            Event::builder("demo", "backend", "claude", event)
+                .session_id(Some("s1"))
+                .direction(direction)
+                .text(text)
+                .exit_code(exit_code)
+                .build()
+        };
+        log_event_in(&base, &send("system", "start", None, None)).unwrap();
+        log_event_in(&base, &send("agent", "stdout_line", Some("hi"), None)).unwrap();
+        log_event_in(&base, &send("system", "end", None, Some(0))).unwrap();
+        // agent run / tmux path
+        write_ndjson_event(&log_file, &NdjsonEvent::new_start("demo", "backend", "backend1", "claude")).unwrap();
+        write_ndjson_event(&log_file, &NdjsonEvent::new_stdout_line("demo", "backend", "backend1", "claude", "hello")).unwrap();
+        write_ndjson_event(&log_file, &NdjsonEvent::new_routed("demo", "backend", "backend1", "claude", Some("b1".into()), Some("m1".into()))).unwrap();
+        write_ndjson_event(&log_file, &NdjsonEvent::new_end("demo", "backend", "backend1", "claude", 12, "ok")).unwrap();
+
+        let content = std::fs::read_to_string(&log_file).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 7);
+        for line in lines {
+            assert_eq!(line_keys(line), event_fields(), "{}", line);
+        }
+        assert_eq!(ndjson_self_check(&log_file).unwrap()["errors"].as_array().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_durable_event_kinds() {
+        for event in ["start", "end", "timeout", "cancelled", "routed", "metrics"] {
+            assert!(is_durable_event(event), "{}", event);
+        }
+        for event in ["stdout_line", "stderr_line", "tool_call", "tool_result"] {
+            assert!(!is_durable_event(event), "{}", event);
+        }
+    }
+
+    #[test]
+    fn test_buffered_stdout_lines_reach_the_file_a_buffer_at_a_time() {
+        let _writers = writer_state();
+        let tmp = tempfile::tempdir().unwrap();
+        let log_file = tmp.path().join("buffered.ndjson").to_string_lossy().to_string();
+        let line = serde_json::to_string(&NdjsonEvent::new_stdout_line("demo", "backend", "backend1", "claude", "x")).unwrap();
+        let lines_on_disk = || std::fs::read_to_string(&log_file).unwrap_or_default().lines().count();
+        let per_buffer = FLUSH_BYTES / (line.len() + 1);
+
+        // A full buffer of lines stays in memory
+        for _ in 0..per_buffer {
+            append_line(&log_file, &line, false).unwrap();
+        }
+        assert_eq!(lines_on_disk(), 0);
+        // The next line writes the whole buffer out in one go and is buffered itself
+        append_line(&log_file, &line, false).unwrap();
+        assert_eq!(lines_on_disk(), per_buffer);
+        // A durable event writes out the rest, itself included
+        append_line(&log_file, &line, true).unwrap();
+        assert_eq!(lines_on_disk(), per_buffer + 2);
+        close_path(&log_file).unwrap();
+    }
+
+    fn redactor(patterns: &[&str]) -> Redactor {
+        Redactor::new(&patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>()).unwrap()
+    }
+
+    #[test]
+    fn test_redaction_replaces_every_match_and_merges_overlaps() {
+        let keys = redactor(&[r"sk-[A-Za-z0-9]{8,}", r"ghp_[A-Za-z0-9]+"]);
+        assert_eq!(keys.redact("key sk-abcdefgh12 and ghp_XyZ then sk-zzzzzzzzz."), "key [REDACTED] and [REDACTED] then [REDACTED].");
+        assert!(matches!(keys.redact("nothing secret"), std::borrow::Cow::Borrowed(_)));
+
+        // Two patterns covering overlapping parts of one secret yield a single marker, nothing leaks
+        let overlapping = redactor(&["abc", "bcdef", "token=[0-9]+"]);
+        assert_eq!(overlapping.redact("xabcdefy token=12 abc"), "x[REDACTED]y [REDACTED] [REDACTED]");
+        assert_eq!(overlapping.redact("abcbcdef"), "[REDACTED]");
+
+        let err = Redactor::new(&["ok".into(), "(unclosed".into()]).unwrap_err();
+        assert!(err.starts_with("defaults.redaction[1]: invalid regex '(unclosed'"), "{}", err);
+    }
+
+    #[test]
+    fn test_redaction_is_linear_on_long_lines() {
+        // A backtracking engine takes exponential time on this pattern; the regex crate does not
+        let nested = redactor(&["(a+)+b", "(x|xx)+y"]);
+        let line = "x".repeat(200_000) + &"a".repeat(200_000);
+        let start = std::time::Instant::now();
+        assert!(matches!(nested.redact(&line), std::borrow::Cow::Borrowed(_)));
+        let with_hit = format!("{}b", line);
+        assert!(nested.redact(&with_hit).ends_with("[REDACTED]"));
+        assert!(start.elapsed() < std::time::Duration::from_secs(5), "elapsed {:?}", start.elapsed());
+    }
+
+    /// Clears the process-wide redaction patterns when dropped, so they do not outlive the test
+    struct InstalledRedaction;
+
+    impl Drop for InstalledRedaction {
+        fn drop(&mut self) {
+            clear_redaction();
+        }
+    }
+
+    #[test]
+    fn test_log_writers_apply_installed_redaction() {
+        let _writers = writer_state();
+        let dir = tempfile::TempDir::new().unwrap();
+        let base = dir.path().to_string_lossy().to_string();
+        set_redaction(&["redact-me-[0-9]{6}".to_string()]).unwrap();
+        let _installed = InstalledRedaction;
+        assert!(redaction_patterns().contains(&"redact-me-[0-9]{6}".to_string()));
+
+        let event = Event::builder("redact-proj", "backend", "mock", "stdout_line")
+            .text(Some("token redact-me-123456 twice redact-me-654321"))
+            .build();
+        log_event_in(&base, &event).unwrap();
+        let file = log_path(&base, "redact-proj", "worker");
+        write_ndjson_event(&file, &event).unwrap();
+        flush_all();
+
+        for path in [log_path(&base, "redact-proj", "backend"), file] {
+            let line = std::fs::read_to_string(&path).unwrap();
+            let written: Event = serde_json::from_str(line.trim()).unwrap();
+            assert_eq!(written.text.as_deref(), Some("token [REDACTED] twice [REDACTED]"), "{}", path);
+        }
+        // The caller's event is left untouched
+        assert!(event.text.unwrap().contains("redact-me-123456"));
+    }
 }
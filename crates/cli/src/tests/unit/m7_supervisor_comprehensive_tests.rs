@@ -100,6 +100,8 @@ mod tests {
         let _ = crate::logging::ndjson::emit_stdout_line_event(
             project, "backend", "agent1", "claude", "stdout message"
         );
+        // stdout_line events are buffered until a lifecycle event or an explicit flush
+        crate::logging::flush_all();
 
         // Test 4: Filter by event type
         let routed_events = sub.tail_and_filter("backend".to_string(), Some("routed".to_string()), 100)
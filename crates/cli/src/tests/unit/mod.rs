@@ -1,6 +1,7 @@
 //! Unit tests
 
 pub mod utils_tests;
+pub mod cli_tests;
 pub mod logging_tests;
 pub mod providers_tests;
 pub mod risks_mitigations_tests;
@@ -38,7 +38,7 @@ fn test_agent_lock_basic_functionality() {
 #[test]
 fn test_with_agent_lock_success() {
     let result = with_agent_lock("test", "agent", Duration::from_secs(1), || {
-        Ok::<i32, Box<dyn std::error::Error>>(42)
+        Ok::<i32, crate::utils::CliError>(42)
     });
     
     assert_eq!(result.unwrap(), 42);
@@ -113,6 +113,57 @@ mod tests {
         assert_eq!(top_roles[0].0, role_a);
         assert!(top_roles[0].1 >= 1);
     }
-}
 
+    #[test]
+    fn subscription_matching_covers_every_kind_filter_and_source() {
+        use crate::supervisor::notify::{subscription_matches, NotifyEvent, NotifyEventKind, SourceFilter};
+
+        let filters = [
+            SourceFilter::All,
+            SourceFilter::Role("backend".into()),
+            SourceFilter::Role("frontend".into()),
+            SourceFilter::Agent("api".into()),
+            SourceFilter::Agent("web".into()),
+        ];
+        // (agent, role) sources: api is a backend, web a frontend, lead the supervisor itself
+        let sources = [("api", "backend"), ("web", "frontend"), ("lead", "backend")];
+        for sub_kind in NotifyEventKind::ALL {
+            for event_kind in NotifyEventKind::ALL {
+                for filter in &filters {
+                    for (name, role) in sources {
+                        let event = NotifyEvent { kind: event_kind, agent_name: name.into(), agent_role: role.into(), detail: String::new() };
+                        let source_matches = match filter {
+                            SourceFilter::All => true,
+                            SourceFilter::Role(r) => r == role,
+                            SourceFilter::Agent(a) => a == name,
+                        };
+                        let expected = sub_kind == event_kind && name != "lead" && source_matches;
+                        assert_eq!(
+                            subscription_matches(sub_kind, filter, "lead", &event), expected,
+                            "{} subscription from {} vs {} by {} (@{})", sub_kind, filter, event_kind, name, role
+                        );
+                    }
+                }
+            }
+        }
+    }
 
+    #[test]
+    fn source_filters_and_event_kinds_round_trip_through_strings() {
+        use crate::supervisor::notify::{NotifyEventKind, SourceFilter};
+
+        for kind in NotifyEventKind::ALL {
+            assert_eq!(kind.as_str().parse::<NotifyEventKind>(), Ok(kind));
+        }
+        assert!("task-done".parse::<NotifyEventKind>().is_err());
+
+        assert_eq!("@all".parse::<SourceFilter>(), Ok(SourceFilter::All));
+        assert_eq!(" @backend ".parse::<SourceFilter>(), Ok(SourceFilter::Role("backend".into())));
+        assert_eq!("api".parse::<SourceFilter>(), Ok(SourceFilter::Agent("api".into())));
+        assert!("@".parse::<SourceFilter>().is_err());
+        assert!("".parse::<SourceFilter>().is_err());
+        for filter in ["@all", "@backend", "api"] {
+            assert_eq!(filter.parse::<SourceFilter>().unwrap().to_string(), filter);
+        }
+    }
+}
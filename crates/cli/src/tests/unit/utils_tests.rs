@@ -5,6 +5,7 @@ use crate::utils::*;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::providers::OutputFormat;
     use std::fs::File;
     use std::io::Write;
 
@@ -25,23 +26,38 @@ mod tests {
 
     #[test]
     fn test_looks_like_uuid() {
-        assert!(looks_like_uuid("12345678-1234-1234-1234-123456789abc"));
-        assert!(looks_like_uuid("1234567890123456"));
+        assert!(looks_like_uuid("18a2b3c4d5e6f70818a2b3c4d5e6f709"));
+        assert!(!looks_like_uuid("12345678-1234-1234-1234-123456789abc"));
+        assert!(!looks_like_uuid("abcdef1234567890"));
+        assert!(!looks_like_uuid("deadbeef"));
+        assert!(!looks_like_uuid("18A2B3C4D5E6F70818A2B3C4D5E6F709"));
         assert!(!looks_like_uuid("not-a-uuid"));
         assert!(!looks_like_uuid("123"));
     }
 
     #[test]
-    fn test_short_id() {
-        let id1 = short_id();
-        let id2 = short_id();
-        assert!(!id1.is_empty());
-        assert!(!id2.is_empty());
-        // IDs should be different (very high probability)
-        // If they're the same, it means the test ran too fast, which is acceptable
-        // We'll just verify they're not empty and are valid hex
-        assert!(id1.chars().all(|c| c.is_ascii_hexdigit()));
-        assert!(id2.chars().all(|c| c.is_ascii_hexdigit()));
+    fn test_project_selector_honours_by() {
+        use crate::cli::commands::SelectBy;
+        use db::IdOrName;
+        let id = "18a2b3c4d5e6f70818a2b3c4d5e6f709";
+        assert!(matches!(project_selector(id, None), IdOrName::Id(_)));
+        assert!(matches!(project_selector("abcdef1234567890", None), IdOrName::Name(_)));
+        assert!(matches!(project_selector(id, Some(SelectBy::Name)), IdOrName::Name(_)));
+        assert!(matches!(project_selector("deadbeef", Some(SelectBy::Id)), IdOrName::Id(_)));
+    }
+
+    #[test]
+    fn test_sequence_ids_are_predictable() {
+        let ids = SequenceIds::new("fixed");
+        assert_eq!(provider_session_id("claude", &ids), "valid_session_fixed-1");
+        assert_eq!(provider_session_id("gemini", &ids), "valid_context_fixed-2");
+        assert_eq!(provider_session_id("ollama", &ids), "fixed-3");
+    }
+
+    #[test]
+    fn test_random_ids_unique_over_many_calls() {
+        let ids: std::collections::HashSet<String> = (0..10_000).map(|_| RandomIds.next_id()).collect();
+        assert_eq!(ids.len(), 10_000);
     }
 
     #[test]
@@ -70,6 +86,306 @@ mod tests {
         assert_eq!(path, "./data/multi-agents.sqlite3");
     }
 
+    #[test]
+    fn test_prompt_file_removed_on_drop() {
+        let prompt_file = PromptFile::create("a very long prompt").unwrap();
+        let path = prompt_file.path().to_path_buf();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a very long prompt");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        drop(prompt_file);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_parse_duration_spec() {
+        assert_eq!(parse_duration_spec("300s").unwrap(), std::time::Duration::from_secs(300));
+        assert_eq!(parse_duration_spec("30m").unwrap(), std::time::Duration::from_secs(1800));
+        assert_eq!(parse_duration_spec("24h").unwrap(), std::time::Duration::from_secs(86_400));
+        assert_eq!(parse_duration_spec("7d").unwrap(), std::time::Duration::from_secs(7 * 86_400));
+        assert_eq!(parse_duration_spec("2w").unwrap(), std::time::Duration::from_secs(14 * 86_400));
+        assert!(parse_duration_spec("24").is_err());
+        assert!(parse_duration_spec("h").is_err());
+        assert!(parse_duration_spec("3y").is_err());
+    }
+
+    /// Streaming run of agent `worker` (role `backend`) outside any session
+    fn stream_ctx<'a>(project: &'a str, provider_key: &'a str, format: OutputFormat) -> StreamContext<'a> {
+        StreamContext { project, agent_role: "backend", agent_name: "worker", provider_key, family: provider_key, session_id: "", format, progress: None, echo: true }
+    }
+
+    #[test]
+    fn test_streaming_extracts_claude_json_envelope() {
+        let line = r#"{"type":"result","content":[{"type":"text","text":"ok"}],"session_id":"sess-9","usage":{"input_tokens":3,"output_tokens":4}}"#;
+        let outcome = run_with_timeout_streaming(
+            "echo", &[line], std::time::Duration::from_secs(5), std::time::Duration::ZERO,
+            &stream_ctx("claude-json-test", "claude", OutputFormat::Json), None, None,
+        ).unwrap();
+        assert_eq!(outcome, StreamOutcome { exit_code: 0, provider_session_id: Some("sess-9".into()), token_count: Some(7), stdout_lines: 1, stdout_bytes: line.len() as u64 + 1 });
+    }
+
+    #[test]
+    fn test_streaming_zero_timeout_waits_and_still_cancels() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::time::{Duration, Instant};
+        let outcome = run_with_timeout_streaming(
+            "sh", &["-c", "sleep 0.3; exit 3"], Duration::ZERO, Duration::ZERO,
+            &stream_ctx("no-timeout-test", "gemini", OutputFormat::Text), None, None,
+        ).unwrap();
+        assert_eq!(outcome.exit_code, 3);
+
+        let cancel = AtomicBool::new(false);
+        let started = Instant::now();
+        let err = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(Duration::from_millis(200));
+                cancel.store(true, Ordering::SeqCst);
+            });
+            run_with_timeout_streaming(
+                "sleep", &["30"], Duration::ZERO, Duration::ZERO,
+                &stream_ctx("no-timeout-test", "gemini", OutputFormat::Text), Some(&cancel), None,
+            ).unwrap_err()
+        });
+        assert_eq!(err, "cancelled");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_streaming_inactivity_timeout_spares_steady_output() {
+        use std::time::Duration;
+        // A line every 150ms for ~0.9s: never silent for the 500ms inactivity window
+        let outcome = run_with_timeout_streaming(
+            "sh", &["-c", "for i in 1 2 3 4 5 6; do echo tick $i; sleep 0.15; done"], Duration::from_secs(10), Duration::from_millis(500),
+            &stream_ctx("inactivity-test", "gemini", OutputFormat::Text), None, None,
+        ).unwrap();
+        assert_eq!(outcome.exit_code, 0);
+    }
+
+    #[test]
+    fn test_streaming_inactivity_timeout_kills_stalled_provider() {
+        use std::time::{Duration, Instant};
+        let started = Instant::now();
+        let err = run_with_timeout_streaming(
+            "sh", &["-c", "echo thinking; sleep 30"], Duration::from_secs(10), Duration::from_millis(300),
+            &stream_ctx("inactivity-test", "gemini", OutputFormat::Text), None, None,
+        ).unwrap_err();
+        assert_eq!(err, TIMEOUT_INACTIVITY);
+        assert_eq!(timeout_kind(&err), Some("inactivity"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // The total timeout still applies to a provider that never stops talking
+        let err = run_with_timeout_streaming(
+            "sh", &["-c", "while true; do echo more; sleep 0.05; done"], Duration::from_millis(400), Duration::from_millis(300),
+            &stream_ctx("inactivity-test", "gemini", OutputFormat::Text), None, None,
+        ).unwrap_err();
+        assert_eq!(err, TIMEOUT_TOTAL);
+        assert_eq!(timeout_kind(&err), Some("total"));
+    }
+
+    #[test]
+    fn test_run_with_timeout_drains_output_larger_than_the_pipe_buffer() {
+        // 1 MiB on stdout and 128 KiB on stderr; reading only after exit would fill both pipes and hang
+        let script = "head -c 1048576 /dev/zero | tr '\\0' x; head -c 131072 /dev/zero | tr '\\0' e >&2; exit 3";
+        let (code, out, err) = run_with_timeout("sh", &["-c", script], std::time::Duration::from_secs(10)).unwrap();
+        assert_eq!(code, 3);
+        assert_eq!(out.len(), 1024 * 1024);
+        assert!(out.bytes().all(|b| b == b'x'));
+        // sh may prefix stderr with a getcwd warning when another test removed the working directory
+        assert!(err.ends_with(&"e".repeat(128 * 1024)));
+
+        let started = std::time::Instant::now();
+        let err = run_with_timeout("sh", &["-c", "sleep 30"], std::time::Duration::from_millis(200)).unwrap_err();
+        assert_eq!(err, "timeout");
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_exit_is_reported_while_a_grandchild_holds_the_pipes() {
+        // The backgrounded sleep inherits stdout and stderr and outlives the shell by far
+        let script = "echo done; echo warn >&2; sleep 30 & exit 2";
+        let started = std::time::Instant::now();
+        let (code, out, err) = run_with_timeout("sh", &["-c", script], std::time::Duration::from_secs(20)).unwrap();
+        assert_eq!((code, out.as_str()), (2, "done\n"));
+        assert!(err.ends_with("warn\n"));
+
+        let mut captured = String::new();
+        let outcome = run_with_timeout_streaming(
+            "sh", &["-c", script], std::time::Duration::from_secs(20), std::time::Duration::ZERO,
+            &stream_ctx("grandchild-test", "gemini", OutputFormat::Text), None, Some(&mut captured),
+        ).unwrap();
+        assert_eq!((outcome.exit_code, captured.as_str()), (2, "done\n"));
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_read_capped_line_replaces_invalid_utf8_and_truncates() {
+        let input: &[u8] = b"ok\xff\xfe\r\n0123456789abcdef\n\nlast";
+        let mut reader = std::io::BufReader::with_capacity(4, input);
+        let mut next = || read_capped_line(&mut reader, 10).unwrap();
+        assert_eq!(next(), Some(CappedLine { text: "ok\u{fffd}\u{fffd}".into(), dropped: 0, bytes: 6 }));
+        assert_eq!(next(), Some(CappedLine { text: "0123456789 [truncated 6 bytes]".into(), dropped: 6, bytes: 17 }));
+        assert_eq!(next(), Some(CappedLine { text: String::new(), dropped: 0, bytes: 1 }));
+        assert_eq!(next(), Some(CappedLine { text: "last".into(), dropped: 0, bytes: 4 }));
+        assert_eq!(next(), None);
+
+        assert_eq!(max_line_bytes_from_env(None), DEFAULT_MAX_LINE_BYTES);
+        assert_eq!(max_line_bytes_from_env(Some("4096")), 4096);
+        assert_eq!(max_line_bytes_from_env(Some("0")), DEFAULT_MAX_LINE_BYTES);
+        assert_eq!(max_line_bytes_from_env(Some("lots")), DEFAULT_MAX_LINE_BYTES);
+    }
+
+    #[test]
+    fn test_streaming_survives_invalid_utf8_and_huge_lines() {
+        // Invalid UTF-8, then a 10 MiB line without spaces, then output after it and a non-zero exit
+        let script = r"printf 'bad \377\376 bytes\n'; head -c 10485760 /dev/zero | tr '\0' x; printf '\nafter\nno newline'; exit 4";
+        let mut captured = String::new();
+        let outcome = run_with_timeout_streaming(
+            "sh", &["-c", script], std::time::Duration::from_secs(30), std::time::Duration::ZERO,
+            &stream_ctx("long-line-test", "gemini", OutputFormat::Text), None, Some(&mut captured),
+        ).unwrap();
+        assert_eq!((outcome.exit_code, outcome.stdout_lines), (4, 4));
+        let lines: Vec<&str> = captured.lines().collect();
+        assert_eq!(lines[0], "bad \u{fffd}\u{fffd} bytes");
+        let dropped = 10 * 1024 * 1024 - DEFAULT_MAX_LINE_BYTES;
+        assert!(lines[1].ends_with(&format!("x [truncated {} bytes]", dropped)));
+        assert_eq!(lines[1].len(), DEFAULT_MAX_LINE_BYTES + format!(" [truncated {} bytes]", dropped).len());
+        assert_eq!(&lines[2..], ["after", "no newline"]);
+    }
+
+    #[test]
+    fn test_capped_timeout_ms() {
+        assert_eq!(capped_timeout_ms(None, 5000), 5000);
+        assert_eq!(capped_timeout_ms(Some(0), 5000), 5000);
+        assert_eq!(capped_timeout_ms(Some(1200), 5000), 1200);
+        assert_eq!(capped_timeout_ms(Some(60_000), 5000), 5000);
+    }
+
+    #[test]
+    fn test_color_modes() {
+        use crate::cli::commands::ColorMode;
+        assert!(resolve_styling(ColorMode::Always, false, None));
+        assert!(!resolve_styling(ColorMode::Never, true, None));
+        assert!(resolve_styling(ColorMode::Auto, true, None));
+        assert!(!resolve_styling(ColorMode::Auto, false, None));
+
+        assert_eq!(render_decorated("✅", "Database initialized", true), "✅ Database initialized");
+        assert_eq!(render_decorated("✅", "Database initialized", false), "Database initialized");
+        assert_eq!(render_toned("OK", Tone::Ok, true), "\x1b[32mOK\x1b[0m");
+        assert_eq!(render_toned("KO", Tone::Error, false), "KO");
+    }
+
+    #[test]
+    fn test_no_color_forces_plain_output() {
+        use crate::cli::commands::ColorMode;
+        assert!(!resolve_styling(ColorMode::Auto, true, Some("1")));
+        // An empty NO_COLOR does not count as set
+        assert!(resolve_styling(ColorMode::Auto, true, Some("")));
+        // An explicit --color always still wins
+        assert!(resolve_styling(ColorMode::Always, true, Some("1")));
+
+        std::env::set_var("NO_COLOR", "1");
+        set_color_mode(ColorMode::Auto);
+        std::env::remove_var("NO_COLOR");
+        assert!(!styling_enabled());
+        assert_eq!(decorated("🚀", "Next steps:"), "Next steps:");
+        assert!(!generate_first_run_guidance().contains('🚀'));
+    }
+
+    #[test]
+    fn test_cli_error_exit_codes() {
+        assert_eq!(CliError::Other("boom".into()).exit_code(), 1);
+        assert_eq!(CliError::validation("project", "bad").exit_code(), 2);
+        assert_eq!(CliError::provider("gemini", ProviderErrorKind::Unavailable).exit_code(), 3);
+        assert_eq!(CliError::provider("gemini", ProviderErrorKind::Failed("exit 1".into())).exit_code(), 4);
+        assert_eq!(CliError::timeout("send", 1000).exit_code(), 5);
+        assert_eq!(CliError::ConfigMissing { path_tried: "./config/project.yaml".into() }.exit_code(), 6);
+        assert_eq!(CliError::Db(db::DbError::InvalidInput("x".into())).exit_code(), 7);
+        assert_eq!(CliError::tmux("new-session", "failed").exit_code(), 8);
+        assert_eq!(CliError::timeout("send", 1000).to_string(), "send: timeout after 1000ms");
+    }
+
+    #[test]
+    fn test_find_project_or_fuzzy_zero_one_and_many() {
+        let tmp = tempfile::tempdir().unwrap();
+        let conn = db::open_or_create_db(tmp.path().join("fuzzy.sqlite3").to_string_lossy().as_ref()).unwrap();
+        let backend = db::insert_project(&conn, "my-backend").unwrap();
+        db::insert_project(&conn, "web-app").unwrap();
+        db::insert_project(&conn, "web-admin").unwrap();
+
+        assert_eq!(find_project_or_fuzzy(&conn, "my-backend").unwrap(), (backend.id.clone(), "my-backend".to_string()));
+        assert_eq!(find_project_or_fuzzy(&conn, "my-backend-service").unwrap(), (backend.id, "my-backend".to_string()));
+
+        let err = find_project_or_fuzzy(&conn, "mobile").unwrap_err();
+        assert!(err.to_string().contains("not found: mobile"), "{}", err);
+        let err = find_project_or_fuzzy(&conn, "web").unwrap_err();
+        assert!(err.to_string().contains("web-admin, web-app") && err.to_string().contains("be more specific"), "{}", err);
+        assert_eq!(err.exit_code(), 2);
+    }
+
+    fn picker_agents() -> Vec<config_model::AgentConfig> {
+        ["backend1", "frontend1"].iter().zip(["backend", "frontend"]).map(|(name, role)| config_model::AgentConfig {
+            name: name.to_string(),
+            role: role.to_string(),
+            provider: "gemini".into(),
+            model: "m".into(),
+            allowed_tools: vec![],
+            system_prompt: String::new(),
+        }).collect()
+    }
+
+    #[test]
+    fn test_agent_picker_reprompts_until_valid_choice() {
+        let agents = picker_agents();
+        let mut input = std::io::Cursor::new("x\n7\n2\n");
+        let mut out = Vec::new();
+        let name = resolve_agent_name(None, &agents, true, &mut input, &mut out).unwrap();
+        assert_eq!(name, "frontend1");
+        let shown = String::from_utf8(out).unwrap();
+        assert!(shown.contains("  1) backend1   role: backend, provider: gemini"), "{}", shown);
+        assert_eq!(shown.matches("Please enter a number between 1 and 2").count(), 2);
+
+        let err = pick_agent(&agents, &mut std::io::Cursor::new(""), &mut Vec::new()).unwrap_err();
+        assert_eq!(err.exit_code(), 1);
+    }
+
+    #[test]
+    fn test_agent_picker_respects_flag_and_tty() {
+        let agents = picker_agents();
+        let mut out = Vec::new();
+        let named = resolve_agent_name(Some("backend1"), &agents, false, &mut std::io::Cursor::new(""), &mut out).unwrap();
+        assert_eq!(named, "backend1");
+        assert!(out.is_empty(), "an explicit agent never prompts");
+
+        let err = resolve_agent_name(None, &agents, false, &mut std::io::Cursor::new("1\n"), &mut out).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+
+        let picked = resolve_agent_name(Some(PICK_AGENT), &agents, false, &mut std::io::Cursor::new("1\n"), &mut out).unwrap();
+        assert_eq!(picked, "backend1");
+    }
+
+    #[test]
+    fn test_expand_cmd_path_home_and_env_vars() {
+        let home = std::env::var("HOME").expect("HOME is set");
+        assert_eq!(expand_cmd_path("~/.local/bin/claude"), format!("{}/.local/bin/claude", home));
+        assert_eq!(expand_cmd_path("~"), home);
+        assert_eq!(expand_cmd_path("$HOME/bin/claude"), format!("{}/bin/claude", home));
+        assert_eq!(expand_cmd_path("${HOME}/bin/claude"), format!("{}/bin/claude", home));
+        // Only a leading `~/` means the home directory; unset variables stay as written
+        assert_eq!(expand_cmd_path("~other/bin/claude"), "~other/bin/claude");
+        assert_eq!(expand_cmd_path("$MULTI_AGENTS_SURELY_UNSET_VAR/claude"), "$MULTI_AGENTS_SURELY_UNSET_VAR/claude");
+    }
+
+    #[test]
+    fn test_expand_cmd_path_leaves_plain_paths_unchanged() {
+        assert_eq!(expand_cmd_path("/usr/local/bin/claude"), "/usr/local/bin/claude");
+        assert_eq!(expand_cmd_path("gemini"), "gemini");
+        assert_eq!(expand_cmd_path("a/b~c$"), "a/b~c$");
+    }
+
     #[test]
     fn test_constants() {
         assert_eq!(DEFAULT_SEND_TIMEOUT_MS, 120_000);
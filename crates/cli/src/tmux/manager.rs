@@ -1,69 +1,113 @@
 //! Tmux session and window management
 
 use std::time::Duration;
-use crate::utils::errors::exit_with;
-use super::retry::tmux_command_with_retry;
+use crate::utils::errors::CliError;
+use super::retry::tmux_bin_command_with_retry;
+use super::operations::pipe_pane_only_supported;
 
 /// Tmux manager for handling session and window operations
 pub struct TmuxManager {
     timeout: Duration,
+    bin: String,
 }
 
 impl TmuxManager {
     /// Create a new TmuxManager with the specified timeout
     pub fn new(timeout: Duration) -> Self {
-        Self { timeout }
+        Self::with_binary(timeout, "tmux")
+    }
+
+    /// Create a TmuxManager driving a specific tmux binary (e.g. a scripted fake in tests)
+    pub fn with_binary(timeout: Duration, bin: impl Into<String>) -> Self {
+        Self { timeout, bin: bin.into() }
+    }
+
+    fn command(&self, args: &[&str], operation_name: &str) -> Result<(i32, String, String), Box<dyn std::error::Error>> {
+        tmux_bin_command_with_retry(&self.bin, args, self.timeout, operation_name)
+    }
+
+    /// Whether the tmux binary can be run (`tmux -V`)
+    pub fn is_available(&self) -> bool {
+        matches!(self.command(&["-V"], "query version"), Ok((0, _, _)))
     }
 
     /// Check if a tmux session exists
-    pub fn has_session(&self, session_name: &str) -> Result<bool, Box<dyn std::error::Error>> {
-        match tmux_command_with_retry(&["has-session", "-t", session_name], self.timeout, "check session exists") {
+    pub fn has_session(&self, session_name: &str) -> Result<bool, CliError> {
+        match self.command(&["has-session", "-t", session_name], "check session exists") {
             Ok((code, _, _)) => Ok(code == 0),
             Err(_) => Ok(false),
         }
     }
 
     /// Create a new tmux session
-    pub fn create_session(&self, session_name: &str) -> Result<(), Box<dyn std::error::Error>> {
-        match tmux_command_with_retry(&["new-session", "-d", "-s", session_name], self.timeout, "create session") {
+    pub fn create_session(&self, session_name: &str) -> Result<(), CliError> {
+        match self.command(&["new-session", "-d", "-s", session_name], "create session") {
             Ok((code, _, err)) if code != 0 => {
-                return exit_with(8, format!("tmux create session: {}", err));
+                return Err(CliError::tmux("create session", err));
             }
             Err(e) => {
-                return exit_with(8, format!("tmux create session: {}", e));
+                return Err(CliError::tmux("create session", e));
             }
             _ => {} // Success
         }
         Ok(())
     }
 
-    /// Check if a window exists in a session
-    pub fn window_exists(&self, session_name: &str, window_name: &str) -> Result<bool, Box<dyn std::error::Error>> {
-        match tmux_command_with_retry(&["list-windows", "-t", session_name, "-F", "#{window_name}"], self.timeout, "list windows") {
-            Ok((code, out, _)) if code == 0 => Ok(out.lines().any(|line| line.trim() == window_name)),
-            Ok((_, _, _)) => Ok(false), // Non-zero exit code
-            Err(_) => Ok(false),
+    /// Rename a tmux session
+    pub fn rename_session(&self, session_name: &str, new_name: &str) -> Result<(), CliError> {
+        match self.command(&["rename-session", "-t", session_name, new_name], "rename session") {
+            Ok((0, _, _)) => Ok(()),
+            Ok((_, _, err)) => Err(CliError::tmux("rename session", err.trim())),
+            Err(e) => Err(CliError::tmux("rename session", e)),
+        }
+    }
+
+    /// Names of the windows of a session; empty when the session does not exist
+    pub fn list_windows(&self, session_name: &str) -> Result<Vec<String>, CliError> {
+        match self.command(&["list-windows", "-t", session_name, "-F", "#{window_name}"], "list windows") {
+            Ok((0, out, _)) => Ok(out.lines().map(|line| line.trim().to_string()).filter(|line| !line.is_empty()).collect()),
+            Ok((_, _, _)) => Ok(Vec::new()), // Non-zero exit code
+            Err(_) => Ok(Vec::new()),
         }
     }
 
+    /// Check if a window exists in a session
+    pub fn window_exists(&self, session_name: &str, window_name: &str) -> Result<bool, CliError> {
+        Ok(self.list_windows(session_name)?.iter().any(|w| w == window_name))
+    }
+
     /// Create a new window in a session
-    pub fn create_window(&self, session_name: &str, window_name: &str) -> Result<(), Box<dyn std::error::Error>> {
-        match tmux_command_with_retry(&["new-window", "-t", session_name, "-n", window_name], self.timeout, "create window") {
+    pub fn create_window(&self, session_name: &str, window_name: &str) -> Result<(), CliError> {
+        match self.command(&["new-window", "-t", session_name, "-n", window_name], "create window") {
             Ok((code, _, err)) if code != 0 => {
-                return exit_with(8, format!("tmux create window: {}", err));
+                return Err(CliError::tmux("create window", err));
             }
             Err(e) => {
-                return exit_with(8, format!("tmux create window: {}", e));
+                return Err(CliError::tmux("create window", e));
             }
             _ => {} // Success
         }
         Ok(())
     }
 
-    /// Set up pipe-pane for logging
-    pub fn setup_pipe_pane(&self, session_name: &str, window_name: &str, log_file: &str) -> Result<(), Box<dyn std::error::Error>> {
+    /// Whether this tmux supports `pipe-pane -o`; assumed when the version cannot be read
+    pub fn supports_pipe_pane_only(&self) -> bool {
+        match self.command(&["-V"], "query version") {
+            Ok((0, out, _)) => pipe_pane_only_supported(&out),
+            _ => true,
+        }
+    }
+
+    /// Pipe the window's output into the shell command `pipe_cmd` for logging; `-o` (keep an
+    /// existing pipe) is left out on tmux versions without it
+    pub fn setup_pipe_pane(&self, session_name: &str, window_name: &str, pipe_cmd: &str) -> Result<(), CliError> {
         let target = format!("{}:{}", session_name, window_name);
-        match tmux_command_with_retry(&["pipe-pane", "-t", &target, "-o", &format!("cat >> {}", log_file)], self.timeout, "setup pipe-pane") {
+        let mut args = vec!["pipe-pane", "-t", &target];
+        if self.supports_pipe_pane_only() {
+            args.push("-o");
+        }
+        args.push(pipe_cmd);
+        match self.command(&args, "setup pipe-pane") {
             Ok((code, _, err)) if code != 0 => {
                 eprintln!("Warning: Failed to set up logging: {}", err);
             }
@@ -76,51 +120,84 @@ impl TmuxManager {
     }
 
     /// Send keys to a window
-    pub fn send_keys(&self, session_name: &str, window_name: &str, keys: &str) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn send_keys(&self, session_name: &str, window_name: &str, keys: &str) -> Result<(), CliError> {
         let target = format!("{}:{}", session_name, window_name);
-        match tmux_command_with_retry(&["send-keys", "-t", &target, keys, "Enter"], self.timeout, "send keys") {
+        match self.command(&["send-keys", "-t", &target, keys, "Enter"], "send keys") {
             Ok((code, _, err)) if code != 0 => {
-                return exit_with(8, format!("tmux send keys: {}", err));
+                return Err(CliError::tmux("send keys", err));
             }
             Err(e) => {
-                return exit_with(8, format!("tmux send keys: {}", e));
+                return Err(CliError::tmux("send keys", e));
             }
             _ => {} // Success
         }
         Ok(())
     }
 
+    /// Capture the visible contents of a window's active pane
+    pub fn capture_pane(&self, session_name: &str, window_name: &str) -> Result<String, CliError> {
+        let target = format!("{}:{}", session_name, window_name);
+        match self.command(&["capture-pane", "-p", "-t", &target], "capture pane") {
+            Ok((0, out, _)) => Ok(out),
+            Ok((_, _, err)) => Err(CliError::tmux("capture pane", err.trim())),
+            Err(e) => Err(CliError::tmux("capture pane", e)),
+        }
+    }
+
+    /// Name of the foreground process in a window's active pane
+    pub fn pane_current_command(&self, session_name: &str, window_name: &str) -> Result<String, CliError> {
+        let target = format!("{}:{}", session_name, window_name);
+        match self.command(&["display-message", "-p", "-t", &target, "#{pane_current_command}"], "query pane command") {
+            Ok((0, out, _)) => Ok(out.trim().to_string()),
+            Ok((_, _, err)) => Err(CliError::tmux("query pane command", err.trim())),
+            Err(e) => Err(CliError::tmux("query pane command", e)),
+        }
+    }
+
     /// Kill a window
-    pub fn kill_window(&self, session_name: &str, window_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn kill_window(&self, session_name: &str, window_name: &str) -> Result<(), CliError> {
         let target = format!("{}:{}", session_name, window_name);
-        match tmux_command_with_retry(&["kill-window", "-t", &target], self.timeout, "kill window") {
+        match self.command(&["kill-window", "-t", &target], "kill window") {
             Ok((code, _, err)) if code != 0 => {
                 // Even if kill-window fails, we consider it idempotent if the window doesn't exist
                 if err.contains("not found") || err.contains("doesn't exist") {
                     println!("Agent window already stopped in tmux session '{}'", session_name);
                     return Ok(());
                 }
-                return exit_with(8, format!("tmux kill window: {}", err));
+                return Err(CliError::tmux("kill window", err));
             }
             Err(e) => {
-                return exit_with(8, format!("tmux kill window: {}", e));
+                return Err(CliError::tmux("kill window", e));
             }
             _ => {} // Success
         }
         Ok(())
     }
 
-    /// Attach to a session
-    pub fn attach_session(&self, session_name: &str) -> Result<(), Box<dyn std::error::Error>> {
-        match tmux_command_with_retry(&["attach-session", "-t", session_name], self.timeout, "attach to session") {
-            Ok((code, _, err)) if code != 0 => {
-                return exit_with(8, format!("tmux attach session: {}", err));
-            }
-            Err(e) => {
-                return exit_with(8, format!("tmux attach session: {}", e));
-            }
-            _ => {} // Success - this will block until user detaches
+    /// Select a window, then attach to it (`-r`: read-only client that cannot type into the pane).
+    /// The attach inherits the terminal and blocks until the user detaches, so it has no timeout.
+    pub fn attach_window(&self, session_name: &str, window_name: &str, read_only: bool) -> Result<(), CliError> {
+        let target = format!("{}:{}", session_name, window_name);
+        match self.command(&["select-window", "-t", &target], "select window") {
+            Ok((0, _, _)) => {}
+            Ok((_, _, err)) => return Err(CliError::tmux("select window", err.trim())),
+            Err(e) => return Err(CliError::tmux("select window", e)),
         }
-        Ok(())
+        let args = attach_args(&target, read_only);
+        match std::process::Command::new(&self.bin).args(&args).status() {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(CliError::tmux("attach session", format!("tmux exited with {}", status))),
+            Err(e) => Err(CliError::tmux("attach session", e)),
+        }
+    }
+}
+
+/// `tmux attach-session` arguments for a `session:window` target
+pub fn attach_args(target: &str, read_only: bool) -> Vec<&str> {
+    let mut args = vec!["attach-session"];
+    if read_only {
+        args.push("-r");
     }
+    args.extend(["-t", target]);
+    args
 }
@@ -1,7 +1,9 @@
 //! Tmux operations and utilities
 
-/// Map tmux failure to standardized exit codes
-pub fn exit_tmux<T>(operation: &str, err: &str) -> Result<T, Box<dyn std::error::Error>> {
+use crate::utils::errors::CliError;
+
+/// Map tmux failure output to a timeout or tmux error
+pub fn tmux_failure(operation: &str, err: &str) -> CliError {
     let lower = err.to_lowercase();
     let is_timeout = lower.contains("timeout");
     let cleaned = err
@@ -11,10 +13,25 @@ pub fn exit_tmux<T>(operation: &str, err: &str) -> Result<T, Box<dyn std::error:
         .collect::<Vec<_>>()
         .join(" ");
     if is_timeout {
-        // 5 = timeout
-        crate::utils::errors::exit_with(5, format!("tmux {}: timeout after 5s", operation))
+        CliError::timeout(format!("tmux {}", operation), crate::utils::DEFAULT_AGENT_TIMEOUT_MS)
     } else {
-        // 8 = tmux error. Keep message concise and helpful
-        crate::utils::errors::exit_with(8, format!("tmux {}: {}", operation, cleaned))
+        // Keep message concise and helpful
+        CliError::tmux(operation, cleaned)
     }
 }
+
+/// Oldest tmux whose `pipe-pane` takes `-o` (only open a pipe when none is running)
+pub const PIPE_PANE_ONLY_MIN_VERSION: (u32, u32) = (1, 1);
+
+/// `(major, minor)` from `tmux -V` output (`tmux 3.3a` → `(3, 3)`); `None` for builds without a release number (`tmux master`, `tmux next-3.5`, `tmux openbsd-7.4`)
+pub fn parse_tmux_version(version_output: &str) -> Option<(u32, u32)> {
+    let version = version_output.trim().strip_prefix("tmux ")?.trim();
+    let (major, rest) = version.split_once('.')?;
+    let minor: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// Whether the tmux reporting `version_output` supports `pipe-pane -o`; unnumbered builds are development or vendor builds, which do
+pub fn pipe_pane_only_supported(version_output: &str) -> bool {
+    parse_tmux_version(version_output).is_none_or(|v| v >= PIPE_PANE_ONLY_MIN_VERSION)
+}
@@ -9,11 +9,21 @@ pub fn tmux_command_with_retry(
     args: &[&str], 
     timeout: Duration,
     operation_name: &str
+) -> Result<(i32, String, String), Box<dyn std::error::Error>> {
+    tmux_bin_command_with_retry("tmux", args, timeout, operation_name)
+}
+
+/// Same as `tmux_command_with_retry`, using an explicit tmux binary
+pub fn tmux_bin_command_with_retry(
+    bin: &str,
+    args: &[&str],
+    timeout: Duration,
+    operation_name: &str
 ) -> Result<(i32, String, String), Box<dyn std::error::Error>> {
     let mut last_error = String::new();
     
     for attempt in 1..=TMUX_RETRY_ATTEMPTS {
-        match run_with_timeout("tmux", args, timeout) {
+        match run_with_timeout(bin, args, timeout) {
             Ok(result) => return Ok(result),
             Err(e) => {
                 last_error = e.to_string();
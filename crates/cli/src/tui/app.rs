@@ -122,6 +122,7 @@ impl TuiRuntime {
         let _ = kanban.load_from_db(&db_path, "default-project");
         self.state_manager.add_state("kanban".to_string(), Box::new(kanban));
         self.state_manager.add_state("sessions".to_string(), Box::new(super::state::view_state::SessionsState::new()));
+        self.state_manager.add_state("logs".to_string(), Box::new(super::state::view_state::LogsState::new()));
 
         // Initial state
         self.state_manager.set_current_state("project_select".to_string())?;
@@ -192,7 +193,16 @@ impl TuiRuntime {
                                 KeyCode::Char('q') => {
                                     self.running = false;
                                 }
+                                KeyCode::Char('g') if self.state_manager.current_state_name() == "sessions" => {
+                                    // Open the selected agent's logs; gT/gM stay available from other views
+                                    self.process_input("g")?;
+                                }
                                 KeyCode::Char('g') => { self.prefix_g = true; }
+                                KeyCode::Char(c @ ('f' | 'l' | 'e')) if self.state_manager.current_state_name() == "logs" => {
+                                    self.process_input(&c.to_string())?;
+                                    self.prefix_g = false;
+                                }
+                                KeyCode::Esc if self.state_manager.current_state_name() == "logs" => { self.process_input("esc")?; }
                                 KeyCode::Char('T') => {
                                     if self.prefix_g { self.cycle_theme(); }
                                     self.prefix_g = false;
@@ -234,6 +244,7 @@ impl TuiRuntime {
                     }
                 }
                 if last_tick.elapsed() >= tick_rate {
+                    self.state_manager.tick();
                     last_tick = Instant::now();
                 }
             }
@@ -9,9 +9,14 @@ use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
 
 use super::super::themes::{ThemePalette, Typography};
 use std::fs::File;
-use std::io::Write;
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::error::Error;
+use std::path::{Path, PathBuf};
 use serde_json::Value;
+use crate::logging::Event;
+
+/// Bytes read back from the end of a log file when a viewer opens it
+pub const LOG_TAIL_BYTES: u64 = 64 * 1024;
 
 /// Log level enumeration
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -54,6 +59,17 @@ impl LogLevel {
         }
     }
 
+    /// Rank from trace (0) to error (4), for minimum-level filtering
+    pub fn severity(&self) -> u8 {
+        match self {
+            LogLevel::Trace => 0,
+            LogLevel::Debug => 1,
+            LogLevel::Info => 2,
+            LogLevel::Warn => 3,
+            LogLevel::Error => 4,
+        }
+    }
+
     pub fn from_str(s: &str) -> Option<LogLevel> {
         match s.to_ascii_uppercase().as_str() {
             "DEBUG" => Some(LogLevel::Debug),
@@ -76,6 +92,88 @@ pub struct LogEntry {
     pub metadata: Option<String>,
 }
 
+impl LogEntry {
+    /// One NDJSON event line: `ts`, `level` (default info), `text`, and the event name as `source`
+    pub fn from_ndjson_event(line: &str) -> Option<LogEntry> {
+        let event: Event = serde_json::from_str(line.trim()).ok()?;
+        Some(LogEntry {
+            timestamp: event.ts,
+            level: LogLevel::from_str(&event.level).unwrap_or(LogLevel::Info),
+            message: event.text.unwrap_or_default(),
+            source: Some(event.event),
+            metadata: None,
+        })
+    }
+
+    pub fn is_stderr(&self) -> bool {
+        self.source.as_deref() == Some("stderr_line")
+    }
+}
+
+/// Parse the complete lines of `buf`, scanning back from its end. With `cut_start`, the first line
+/// began before the read boundary and is dropped. Returns the entries oldest first and the length
+/// up to the last newline; a partial last line is left for the next read.
+pub fn parse_tail(buf: &[u8], cut_start: bool) -> (Vec<LogEntry>, usize) {
+    let complete = buf.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    // rsplit yields an empty slice after the final newline first
+    let mut lines: Vec<&[u8]> = buf[..complete].rsplit(|&b| b == b'\n').skip(1).collect();
+    if cut_start {
+        lines.pop();
+    }
+    let entries = lines.into_iter().rev()
+        .filter_map(|l| std::str::from_utf8(l).ok())
+        .filter_map(LogEntry::from_ndjson_event)
+        .collect();
+    (entries, complete)
+}
+
+/// Reads an NDJSON file from its tail, then the lines appended to it
+#[derive(Debug, Clone)]
+pub struct LogTail {
+    path: PathBuf,
+    offset: u64,
+}
+
+impl LogTail {
+    /// Open `path` and parse at most its last `max_bytes`, without loading the rest of the file
+    pub fn open(path: impl AsRef<Path>, max_bytes: u64) -> std::io::Result<(Self, Vec<LogEntry>)> {
+        let mut file = File::open(path.as_ref())?;
+        let len = file.metadata()?.len();
+        let start = len.saturating_sub(max_bytes);
+        // Read one byte early: the window starts on a line boundary when it follows a newline
+        let read_from = start.saturating_sub(1);
+        file.seek(SeekFrom::Start(read_from))?;
+        let mut buf = Vec::with_capacity((len - read_from) as usize);
+        file.take(len - read_from).read_to_end(&mut buf)?;
+        let cut_start = start > 0 && buf.first() != Some(&b'\n');
+        let skip = (start - read_from) as usize;
+        let (entries, consumed) = parse_tail(&buf[skip..], cut_start);
+        Ok((Self { path: path.as_ref().to_path_buf(), offset: start + consumed as u64 }, entries))
+    }
+
+    /// Entries completed since the last read; starts over when the file shrank (truncated or rotated)
+    pub fn poll(&mut self) -> std::io::Result<Vec<LogEntry>> {
+        let mut file = File::open(&self.path)?;
+        let len = file.metadata()?.len();
+        if len < self.offset {
+            self.offset = 0;
+        }
+        if len == self.offset {
+            return Ok(Vec::new());
+        }
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut buf = Vec::new();
+        file.take(len - self.offset).read_to_end(&mut buf)?;
+        let (entries, consumed) = parse_tail(&buf, false);
+        self.offset += consumed as u64;
+        Ok(entries)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
 /// Log filter options
 #[derive(Debug, Clone)]
 pub struct LogFilter {
@@ -228,8 +326,9 @@ pub fn render_log_viewer(f: &mut ratatui::Frame, area: Rect, log_viewer: &LogVie
 
     // Filter bar
     let filter_text = format!(
-        "Filter: {} | Search: {} | Lines: {}",
+        "Filter: {} | Event: {} | Search: {} | Lines: {}",
         log_viewer.filter.levels.iter().map(|l| l.label()).collect::<Vec<_>>().join(","),
+        log_viewer.filter.source_filter.as_deref().unwrap_or("all"),
         log_viewer.filter.search_term.as_deref().unwrap_or("None"),
         log_viewer.logs.len()
     );
@@ -242,24 +341,25 @@ pub fn render_log_viewer(f: &mut ratatui::Frame, area: Rect, log_viewer: &LogVie
     // Log list
     let visible_logs = log_viewer.get_filtered_logs();
     let max_index = visible_logs.len().saturating_sub(1);
-    let start = log_viewer.scroll_position.min(max_index);
+    // Keep the line at scroll_position at the bottom of the list area
+    let height = (chunks[1].height.saturating_sub(2) as usize).max(1);
+    let end = log_viewer.scroll_position.min(max_index);
+    let start = (end + 1).saturating_sub(height);
 
     let log_items: Vec<ListItem> = visible_logs
         .iter()
         .enumerate()
         .skip(start)
+        .take(height)
         .map(|(i, log)| {
             let _line_number = start + i + 1;
-            let metadata = log.metadata.clone().unwrap_or_default();
-            let text = format!(
-                "{} [{}] {} - {}",
-                log.timestamp,
-                log.level.label(),
-                log.message,
-                metadata
-            );
+            let text = match (&log.source, &log.metadata) {
+                (Some(source), None) => format!("{} [{}] {} | {}", log.timestamp, log.level.label(), source, log.message),
+                (_, metadata) => format!("{} [{}] {} - {}", log.timestamp, log.level.label(), log.message, metadata.clone().unwrap_or_default()),
+            };
 
-            let style = typography.body.fg(log.level.color(theme).fg.unwrap_or(theme.text));
+            let color = if log.is_stderr() { theme.error } else { log.level.color(theme).fg.unwrap_or(theme.text) };
+            let style = typography.body.fg(color);
             ListItem::new(text).style(style)
         })
         .collect();
@@ -275,7 +375,7 @@ pub fn render_log_viewer(f: &mut ratatui::Frame, area: Rect, log_viewer: &LogVie
     // Status bar
     let status_text = format!(
         "Line {}/{} | AutoScroll: {} | Selected: {}",
-        start + 1,
+        end + 1,
         visible_logs.len(),
         if log_viewer.auto_scroll { "ON" } else { "OFF" },
         log_viewer.selected_line.map_or("None".to_string(), |i| (i + 1).to_string())
@@ -16,7 +16,7 @@ use super::themes::{ThemePalette, Typography};
 // Re-export components for convenience
 pub use task_card::{TaskCard, Task, TaskStatus, TaskPriority, render_task_card, render_task_card_compact};
 pub use session_item::{SessionItem, Session, SessionStatus, Provider, render_session_item, render_session_item_compact, render_session_status_badge};
-pub use log_viewer::{LogViewer, LogEntry, LogLevel, LogFilter, render_log_viewer, render_log_entry};
+pub use log_viewer::{LogViewer, LogEntry, LogLevel, LogFilter, LogTail, parse_tail, LOG_TAIL_BYTES, render_log_viewer, render_log_entry};
 pub use toast::{Toast, ToastType, ToastQueue, render_toasts};
 pub use status::{GlobalStatus, GlobalStateIcon, render_global_status};
 
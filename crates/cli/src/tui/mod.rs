@@ -35,6 +35,7 @@ impl TuiApp {
         self.state_manager.add_state("project_select".to_string(), Box::new(state::navigation_state::ProjectSelectState::new()));
         self.state_manager.add_state("kanban".to_string(), Box::new(state::view_state::KanbanState::new()));
         self.state_manager.add_state("sessions".to_string(), Box::new(state::view_state::SessionsState::new()));
+        self.state_manager.add_state("logs".to_string(), Box::new(state::view_state::LogsState::new()));
         
         // Set initial state
         self.state_manager.set_current_state("project_select".to_string())?;
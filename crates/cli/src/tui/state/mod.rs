@@ -29,6 +29,9 @@ pub trait TuiState {
 
     /// Lifecycle hook invoked upon entering this state
     fn on_enter(&mut self, _ctx: &StateContext) -> Result<(), Box<dyn Error>> { Ok(()) }
+
+    /// Periodic hook, invoked once per runtime tick while this state is current
+    fn on_tick(&mut self) {}
 }
 
 /// State transition result
@@ -95,6 +98,13 @@ impl StateManager {
         }
     }
     
+    /// Forward a runtime tick to the current state
+    pub fn tick(&mut self) {
+        if let Some(state) = self.states.get_mut(&self.current_state) {
+            state.on_tick();
+        }
+    }
+
     /// Render current state
     pub fn render(&self) -> Result<String, Box<dyn Error>> {
         if let Some(state) = self.states.get(&self.current_state) {
@@ -1,5 +1,6 @@
-//! Minimal global selection store for current project id
-//! Used to pass selected project from ProjectSelectState to KanbanState.
+//! Minimal global selection store for current project id and log target
+//! Used to pass selected project from ProjectSelectState to KanbanState,
+//! and the selected agent from SessionsState to LogsState.
 
 use std::sync::{Mutex, OnceLock};
 
@@ -21,4 +22,24 @@ pub fn get_project_id() -> Option<String> {
     store().lock().ok().and_then(|g| g.clone())
 }
 
+/// Agent whose NDJSON log (`{logs}/{project}/{agent}.ndjson`, else the older `{role}.ndjson`) the logs view opens
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogTarget {
+    pub project: String,
+    pub role: String,
+    pub agent: String,
+}
+
+static LOG_TARGET: OnceLock<Mutex<Option<LogTarget>>> = OnceLock::new();
+
+/// Set the agent for the logs view
+pub fn set_log_target(target: LogTarget) {
+    if let Ok(mut slot) = LOG_TARGET.get_or_init(|| Mutex::new(None)).lock() {
+        *slot = Some(target);
+    }
+}
 
+/// Get the agent for the logs view
+pub fn get_log_target() -> Option<LogTarget> {
+    LOG_TARGET.get_or_init(|| Mutex::new(None)).lock().ok().and_then(|g| g.clone())
+}
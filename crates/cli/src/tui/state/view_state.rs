@@ -5,7 +5,11 @@
 
 use std::error::Error;
 use super::{TuiState, StateTransition, StateContext};
+use super::selection_store::{self, LogTarget};
+use crate::logging::{agent_log_path_for_read, log_base};
 use crate::repository::{RepositoryManager};
+use crate::tui::components::{LogLevel, LogTail, LogViewer, LOG_TAIL_BYTES};
+use crate::utils::resolve_db_path;
 use db::open_or_create_db;
 
 /// Kanban view state
@@ -361,6 +365,8 @@ pub struct SessionsState {
     pub selected_session: Option<usize>,
     pub filter: String,
     pub sort_by_agent: bool,
+    /// Name of the loaded project; locates agent log files
+    pub project_name: Option<String>,
     // caching & lazy display
     cache_filter: String,
     cache_sort_by_agent: bool,
@@ -387,6 +393,7 @@ impl SessionsState {
             selected_session: None,
             filter: String::new(),
             sort_by_agent: false,
+            project_name: None,
             cache_filter: String::new(),
             cache_sort_by_agent: false,
             cache_indices: None,
@@ -396,34 +403,41 @@ impl SessionsState {
     /// Load sessions from SQLite
     pub fn load_from_db_with_filters(&mut self, db_path: &str, project_id: Option<String>, agent_id: Option<String>) -> Result<(), Box<dyn Error>> {
         let conn = db::open_or_create_db(db_path)?;
-        let mut sql = String::from("SELECT id, agent_id, provider, status, created_at FROM sessions");
+        let mut sql = String::from(
+            "SELECT s.id, COALESCE(a.name, s.agent_id), COALESCE(a.role, ''), s.provider, s.status, s.created_at
+             FROM sessions s LEFT JOIN agents a ON a.id = s.agent_id",
+        );
         let mut clauses: Vec<&str> = Vec::new();
-        if project_id.is_some() { clauses.push("project_id = ?1"); }
-        if agent_id.is_some() { clauses.push("agent_id = ?2"); }
+        if project_id.is_some() { clauses.push("s.project_id = ?1"); }
+        if agent_id.is_some() { clauses.push("s.agent_id = ?2"); }
         if !clauses.is_empty() { sql.push_str(" WHERE "); sql.push_str(&clauses.join(" AND ")); }
-        sql.push_str(" ORDER BY created_at DESC");
+        sql.push_str(" ORDER BY s.created_at DESC");
 
         let mut stmt = conn.prepare(&sql)?;
 
-        let mut collected: Vec<(String, String, String, String, String)> = Vec::new();
+        let mut collected: Vec<(String, String, String, String, String, String)> = Vec::new();
         if let (Some(p), Some(a)) = (project_id.as_ref(), agent_id.as_ref()) {
-            let mapped = stmt.query_map((p, a), |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?, row.get::<_, String>(4)?)))?;
+            let mapped = stmt.query_map((p, a), |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?, row.get::<_, String>(4)?, row.get::<_, String>(5)?)))?;
             for r in mapped { collected.push(r?); }
         } else if let (Some(p), None) = (project_id.as_ref(), agent_id.as_ref()) {
-            let mapped = stmt.query_map([p], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?, row.get::<_, String>(4)?)))?;
+            let mapped = stmt.query_map([p], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?, row.get::<_, String>(4)?, row.get::<_, String>(5)?)))?;
             for r in mapped { collected.push(r?); }
         } else if let (None, Some(a)) = (project_id.as_ref(), agent_id.as_ref()) {
-            let mapped = stmt.query_map([a], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?, row.get::<_, String>(4)?)))?;
+            let mapped = stmt.query_map([a], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?, row.get::<_, String>(4)?, row.get::<_, String>(5)?)))?;
             for r in mapped { collected.push(r?); }
         } else {
-            let mapped = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?, row.get::<_, String>(4)?)))?;
+            let mapped = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?, row.get::<_, String>(4)?, row.get::<_, String>(5)?)))?;
             for r in mapped { collected.push(r?); }
         }
 
         self.sessions.clear();
-        for (id, agent_id, provider, status, created_at) in collected.into_iter() {
-            self.sessions.push(SessionItem { id, agent_name: agent_id, role: String::new(), provider, status, duration: created_at });
+        for (id, agent_name, role, provider, status, created_at) in collected.into_iter() {
+            self.sessions.push(SessionItem { id, agent_name, role, provider, status, duration: created_at });
         }
+        self.project_name = match &project_id {
+            Some(p) => conn.query_row("SELECT name FROM projects WHERE id = ?1", [p], |r| r.get(0)).ok(),
+            None => None,
+        };
         // Invalidate cache on data reload
         self.cache_indices = None;
         Ok(())
@@ -465,6 +479,13 @@ impl SessionsState {
 }
 
 impl TuiState for SessionsState {
+    fn on_enter(&mut self, ctx: &StateContext) -> Result<(), Box<dyn Error>> {
+        if let Some(project_id) = &ctx.selected_project_id {
+            let _ = self.load_from_db_with_filters(&resolve_db_path(), Some(project_id.clone()), None);
+        }
+        Ok(())
+    }
+
     fn handle_input(&mut self, input: &str) -> Result<StateTransition, Box<dyn Error>> {
         match input.trim() {
             "q" | "quit" => Ok(StateTransition::Exit),
@@ -534,6 +555,17 @@ impl TuiState for SessionsState {
                 }
                 Ok(StateTransition::Stay)
             }
+            "g" => {
+                // Open the selected agent's NDJSON log
+                let selected = self.selected_session.and_then(|i| self.get_filtered_sessions().get(i).map(|s| (s.role.clone(), s.agent_name.clone())));
+                match (selected, &self.project_name) {
+                    (Some((role, agent)), Some(project)) if !role.is_empty() => {
+                        selection_store::set_log_target(LogTarget { project: project.clone(), role, agent });
+                        Ok(StateTransition::Transition("logs".to_string()))
+                    }
+                    _ => Ok(StateTransition::Stay),
+                }
+            }
             "t" => {
                 // Toggle sort
                 self.sort_by_agent = !self.sort_by_agent;
@@ -570,7 +602,7 @@ impl TuiState for SessionsState {
                 marker, session.role, session.agent_name, session.provider, session.status, session.duration));
         }
         
-        output.push_str("\nCommands: ↑ ↓ (navigate), enter (attach), s (start), q (quit)\n");
+        output.push_str("\nCommands: ↑ ↓ (navigate), enter (attach), g (logs), s (start), q (quit)\n");
         if !self.filter.is_empty() {
             output.push_str(&format!("Filter: {}\n", self.filter));
         }
@@ -583,7 +615,182 @@ impl TuiState for SessionsState {
     }
     
     fn can_transition_to(&self, target_state: &str) -> bool {
-        matches!(target_state, "kanban" | "help")
+        matches!(target_state, "kanban" | "help" | "logs")
+    }
+}
+
+/// Event types the logs view cycles through with `e` (`None` shows every event)
+pub const LOG_EVENT_FILTERS: &[Option<&str>] = &[None, Some("stdout_line"), Some("stderr_line"), Some("tool_call"), Some("end")];
+
+/// Logs view state: one agent's NDJSON log, read from its tail and followed on each tick
+pub struct LogsState {
+    pub target: Option<LogTarget>,
+    pub viewer: LogViewer,
+    pub follow: bool,
+    /// Lowest level shown (`l` raises it, wrapping back to trace)
+    pub min_level: LogLevel,
+    pub event_filter: usize,
+    pub error: Option<String>,
+    /// Bytes read back from the end of the file on open
+    pub tail_bytes: u64,
+    tail: Option<LogTail>,
+}
+
+impl LogsState {
+    pub fn new() -> Self {
+        let mut state = Self {
+            target: None,
+            viewer: LogViewer::new(),
+            follow: true,
+            min_level: LogLevel::Trace,
+            event_filter: 0,
+            error: None,
+            tail_bytes: LOG_TAIL_BYTES,
+            tail: None,
+        };
+        state.apply_filters();
+        state
+    }
+
+    /// Load the tail of `target`'s log under the logs base `base`
+    pub fn open(&mut self, target: LogTarget, base: &str) {
+        let path = agent_log_path_for_read(base, &target.project, &target.agent, &target.role);
+        self.viewer.logs.clear();
+        self.viewer.scroll_to_bottom();
+        self.tail = None;
+        self.error = None;
+        match LogTail::open(&path, self.tail_bytes) {
+            Ok((tail, entries)) => {
+                for entry in entries {
+                    self.viewer.add_log(entry);
+                }
+                self.tail = Some(tail);
+            }
+            Err(e) => self.error = Some(format!("cannot read {}: {}", path, e)),
+        }
+        self.target = Some(target);
+    }
+
+    /// Append the lines written since the last read when following
+    pub fn poll(&mut self) {
+        if !self.follow {
+            return;
+        }
+        if let Some(tail) = &mut self.tail {
+            match tail.poll() {
+                Ok(entries) => {
+                    for entry in entries {
+                        self.viewer.add_log(entry);
+                    }
+                }
+                Err(e) => self.error = Some(format!("cannot read {}: {}", tail.path().display(), e)),
+            }
+        }
+    }
+
+    fn apply_filters(&mut self) {
+        let min = self.min_level.severity();
+        self.viewer.filter.levels = [LogLevel::Trace, LogLevel::Debug, LogLevel::Info, LogLevel::Warn, LogLevel::Error]
+            .into_iter()
+            .filter(|l| l.severity() >= min)
+            .collect();
+        self.viewer.filter.source_filter = LOG_EVENT_FILTERS[self.event_filter].map(str::to_string);
+        self.viewer.scroll_to_bottom();
     }
 }
 
+impl Default for LogsState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TuiState for LogsState {
+    fn on_enter(&mut self, _ctx: &StateContext) -> Result<(), Box<dyn Error>> {
+        if let Some(target) = selection_store::get_log_target() {
+            self.open(target, &log_base());
+        }
+        Ok(())
+    }
+
+    fn on_tick(&mut self) {
+        self.poll();
+    }
+
+    fn handle_input(&mut self, input: &str) -> Result<StateTransition, Box<dyn Error>> {
+        match input.trim() {
+            "q" | "quit" => Ok(StateTransition::Exit),
+            "h" | "help" => Ok(StateTransition::Transition("help".to_string())),
+            "esc" | "s" => Ok(StateTransition::Transition("sessions".to_string())),
+            "up" | "↑" => { self.viewer.scroll_up(1); Ok(StateTransition::Stay) }
+            "down" | "↓" => { self.viewer.scroll_down(1); Ok(StateTransition::Stay) }
+            "pageup" => { self.viewer.scroll_up(10); Ok(StateTransition::Stay) }
+            "pagedown" => { self.viewer.scroll_down(10); Ok(StateTransition::Stay) }
+            "home" => { self.viewer.scroll_to_top(); Ok(StateTransition::Stay) }
+            "end" => { self.viewer.scroll_to_bottom(); Ok(StateTransition::Stay) }
+            "f" => {
+                self.follow = !self.follow;
+                if self.follow {
+                    self.poll();
+                    self.viewer.scroll_to_bottom();
+                }
+                Ok(StateTransition::Stay)
+            }
+            "l" => {
+                self.min_level = match self.min_level {
+                    LogLevel::Trace => LogLevel::Debug,
+                    LogLevel::Debug => LogLevel::Info,
+                    LogLevel::Info => LogLevel::Warn,
+                    LogLevel::Warn => LogLevel::Error,
+                    LogLevel::Error => LogLevel::Trace,
+                };
+                self.apply_filters();
+                Ok(StateTransition::Stay)
+            }
+            "e" => {
+                self.event_filter = (self.event_filter + 1) % LOG_EVENT_FILTERS.len();
+                self.apply_filters();
+                Ok(StateTransition::Stay)
+            }
+            _ => Ok(StateTransition::Stay),
+        }
+    }
+
+    fn render(&self) -> Result<String, Box<dyn Error>> {
+        let mut output = String::new();
+        match &self.target {
+            Some(t) => output.push_str(&format!("=== Logs: {}:{} ({}) ===\n\n", t.role, t.agent, t.project)),
+            None => output.push_str("=== Logs ===\n\n"),
+        }
+        if let Some(error) = &self.error {
+            output.push_str(&format!("{}\n", error));
+        }
+        let filtered = self.viewer.get_filtered_logs();
+        if filtered.is_empty() && self.error.is_none() {
+            output.push_str("No log lines\n");
+        }
+        let end = self.viewer.scroll_position.min(filtered.len().saturating_sub(1));
+        for (i, log) in filtered.iter().enumerate().skip((end + 1).saturating_sub(40)).take(40) {
+            let marker = if i == end { "▶ " } else { "  " };
+            let mark = if log.is_stderr() { "!" } else { " " };
+            output.push_str(&format!("{}{}{} {:<5} {} | {}\n",
+                marker, mark, log.timestamp, log.level.label(), log.source.as_deref().unwrap_or(""), log.message));
+        }
+        output.push_str(&format!(
+            "\nLevel ≥ {}  Event: {}  Follow: {}\n",
+            self.min_level.label(),
+            LOG_EVENT_FILTERS[self.event_filter].unwrap_or("all"),
+            if self.follow { "ON" } else { "OFF" },
+        ));
+        output.push_str("Commands: ↑ ↓ (scroll), f (follow), l (level), e (event), esc (back), q (quit)\n");
+        Ok(output)
+    }
+
+    fn state_name(&self) -> &'static str {
+        "logs"
+    }
+
+    fn can_transition_to(&self, target_state: &str) -> bool {
+        matches!(target_state, "sessions" | "help")
+    }
+}
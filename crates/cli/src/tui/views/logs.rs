@@ -0,0 +1,52 @@
+//! Logs view implementation
+//!
+//! Renders one agent's NDJSON log through the log viewer component with header/footer.
+
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::widgets::{Block, Borders, Paragraph};
+
+use super::super::themes::{ThemePalette, Typography};
+use crate::tui::components::{render_log_viewer, GlobalStatus, GlobalStateIcon, render_global_status};
+use crate::tui::state::view_state::LogsState;
+
+pub fn render_logs_view(
+    f: &mut ratatui::Frame,
+    area: Rect,
+    logs_state: &LogsState,
+    theme: &ThemePalette,
+    typography: &Typography,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Header
+            Constraint::Min(0),    // Log viewer
+            Constraint::Length(1), // Footer
+        ])
+        .split(area);
+
+    let (project_name, view_name) = match &logs_state.target {
+        Some(t) => (t.project.clone(), format!("Logs {}:{}", t.role, t.agent)),
+        None => ("<project>".to_string(), "Logs".to_string()),
+    };
+    let status = GlobalStatus {
+        project_name,
+        view_name,
+        focus: "Body".to_string(),
+        icon: if logs_state.error.is_some() { GlobalStateIcon::Error } else { GlobalStateIcon::Active },
+        last_action: logs_state.error.clone(),
+    };
+    render_global_status(f, chunks[0], &status, theme, typography);
+
+    render_log_viewer(f, chunks[1], &logs_state.viewer, theme, typography);
+
+    let follow = if logs_state.follow { "ON" } else { "OFF" };
+    let footer = Paragraph::new(format!(
+        "↑ ↓ scroll  |  f follow: {}  |  l level ≥ {}  |  e event  |  Esc back",
+        follow,
+        logs_state.min_level.label(),
+    ))
+    .style(typography.caption.fg(theme.secondary))
+    .block(Block::default().borders(Borders::NONE));
+    f.render_widget(footer, chunks[2]);
+}
@@ -3,8 +3,10 @@
 pub mod kanban;
 pub mod sessions;
 pub mod detail;
+pub mod logs;
 
 // Re-export views for convenience
 pub use kanban::{KanbanView, KanbanColumn, KanbanSort, render_kanban_view};
 pub use sessions::render_sessions_view;
 pub use detail::render_detail_view;
+pub use logs::render_logs_view;
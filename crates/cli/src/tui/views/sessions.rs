@@ -61,7 +61,7 @@ pub fn render_sessions_view(
     list_state.select(sessions_state.selected_session);
     f.render_stateful_widget(list, chunks[1], &mut list_state);
 
-    let footer = Paragraph::new("↑ ↓ navigate  |  t sort  |  / filter  |  r resume  X stop  S start")
+    let footer = Paragraph::new("↑ ↓ navigate  |  t sort  |  / filter  |  g logs  |  r resume  X stop  S start")
         .style(typography.caption.fg(theme.secondary))
         .block(Block::default().borders(Borders::NONE));
     f.render_widget(footer, chunks[2]);
@@ -1,6 +1,11 @@
 //! Configuration path resolution utilities
 
 use std::path::Path;
+use db::{find_project_id, find_project_id_fuzzy, IdOrName};
+use rusqlite::{params, Connection};
+use crate::cli::commands::SelectBy;
+use crate::utils::errors::CliError;
+use crate::utils::ids::{IdGenerator, RandomIds};
 
 /// Resolve config paths from (flags -> env -> defaults)
 /// ENV: MULTI_AGENTS_PROJECT_FILE, MULTI_AGENTS_PROVIDERS_FILE, MULTI_AGENTS_CONFIG_DIR
@@ -33,29 +38,82 @@ pub fn resolve_config_paths(project_flag: Option<&str>, providers_flag: Option<&
     Ok((pr, pv))
 }
 
-/// Check if a string looks like a UUID
+/// Check if a string has the 32 lowercase hex digit form of ids generated by the DB
 pub fn looks_like_uuid(s: &str) -> bool { 
-    s.len() >= 16 && s.chars().all(|c| c.is_ascii_hexdigit() || c == '-') 
+    s.len() == 32 && s.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'))
 }
 
-/// Generate a short ID based on current time
-pub fn short_id() -> String { 
-    format!("{:x}", std::time::Instant::now().elapsed().as_nanos()) 
+/// Project lookup for a `--project` selector: as `--by` says, else guessed with `looks_like_uuid`
+pub fn project_selector(sel: &str, by: Option<SelectBy>) -> IdOrName<'_> {
+    match by {
+        Some(SelectBy::Id) => IdOrName::Id(sel),
+        Some(SelectBy::Name) => IdOrName::Name(sel),
+        None if looks_like_uuid(sel) => IdOrName::Id(sel),
+        None => IdOrName::Name(sel),
+    }
 }
 
-/// Generate a UUID v4-like string
+/// `(id, name)` of the project named `name`; when there is none, a single fuzzy match is used
+/// with a warning, and several matches ask the user to be more specific
+pub fn find_project_or_fuzzy(conn: &Connection, name: &str) -> Result<(String, String), CliError> {
+    if let Some(id) = find_project_id(conn, IdOrName::Name(name))? {
+        return Ok((id, name.to_string()));
+    }
+    let mut matches = Vec::new();
+    for id in find_project_id_fuzzy(conn, name)? {
+        let found: String = conn.query_row("SELECT name FROM projects WHERE id = ?1", params![id], |r| r.get(0))?;
+        matches.push((id, found));
+    }
+    match matches.len() {
+        0 => Err(CliError::validation("project", format!("not found: {}", name))),
+        1 => {
+            let (id, found) = matches.remove(0);
+            eprintln!("Warning: using fuzzy match: '{}' for '{}'", found, name);
+            Ok((id, found))
+        }
+        _ => Err(CliError::validation("project", format!(
+            "'{}' matches several projects ({}); be more specific",
+            name,
+            matches.iter().map(|(_, n)| n.as_str()).collect::<Vec<_>>().join(", ")
+        ))),
+    }
+}
+
+/// Expand a provider `cmd` for binaries installed under the home directory: a leading `~` becomes `$HOME`,
+/// then `$VAR` / `${VAR}` are replaced from the environment. Unset variables are left as written.
+pub fn expand_cmd_path(cmd: &str) -> String {
+    let home = std::env::var("HOME").ok();
+    let cmd = match (cmd.strip_prefix('~'), &home) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => format!("{}{}", home, rest),
+        _ => cmd.to_string(),
+    };
+    let mut out = String::with_capacity(cmd.len());
+    let mut rest = cmd.as_str();
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        let (name, consumed) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            },
+            None => {
+                let end = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+        let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+        match std::env::var(name).ok().filter(|_| valid) {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(&rest[pos..pos + 1 + consumed]),
+        }
+        rest = &after[consumed..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Generate a random v4 UUID
 pub fn uuid_v4_like() -> String {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
-    let mut s = format!("{:032x}", nanos);
-    // Set version (v4)
-    s.replace_range(12..13, "4");
-    // Set variant (10xx)
-    let variants = ['8','9','a','b'];
-    let idx = (nanos & 0x3) as usize;
-    s.replace_range(16..17, &variants[idx].to_string());
-    format!(
-        "{}-{}-{}-{}-{}",
-        &s[0..8], &s[8..12], &s[12..16], &s[16..20], &s[20..32]
-    )
+    RandomIds.next_id()
 }
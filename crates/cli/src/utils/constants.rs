@@ -15,6 +15,9 @@ pub const DEFAULT_TIMEOUT_PER_PROVIDER_MS: u64 = 12000;
 /// Default global timeout for doctor command (20 seconds)
 pub const DEFAULT_TIMEOUT_GLOBAL_MS: u64 = 20000;
 
+/// How long doctor reuses a provider version probe while the binary is unchanged (1 hour)
+pub const DOCTOR_PROBE_CACHE_TTL_SECS: u64 = 3600;
+
 /// Retry configuration for tmux operations
 pub const TMUX_RETRY_ATTEMPTS: u32 = 2;
 pub const TMUX_RETRY_DELAY_MS: u64 = 100;
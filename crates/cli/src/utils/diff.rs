@@ -0,0 +1,18 @@
+//! Line-based unified diff for previewing file rewrites
+
+use similar::{DiffTag, TextDiff};
+
+/// Lines of context around each change
+pub const DIFF_CONTEXT: usize = 3;
+
+/// Unified diff (`---`/`+++` headers and `@@` hunks) turning `old` into `new`; empty when they are equal
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    let diff = TextDiff::from_lines(old, new);
+    if diff.ops().iter().all(|op| op.tag() == DiffTag::Equal) {
+        return String::new();
+    }
+    diff.unified_diff()
+        .context_radius(DIFF_CONTEXT)
+        .header(old_label, new_label)
+        .to_string()
+}
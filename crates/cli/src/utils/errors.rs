@@ -2,17 +2,118 @@
 
 use crate::cli::commands::Format;
 
-/// Exit with a specific error code and message
-#[cfg(not(test))]
-pub fn exit_with<T>(code: i32, msg: String) -> Result<T, Box<dyn std::error::Error>> {
-    eprintln!("{}", msg);
-    std::process::exit(code);
+/// Why a provider could not serve a request
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProviderErrorKind {
+    /// Not declared in providers.yaml, or not supported by the command
+    NotConfigured,
+    /// CLI binary missing or unusable
+    Unavailable,
+    /// Launched in a tmux pane but the process never showed up; carries the pane tail
+    NotStarted(String),
+    /// CLI ran but failed (non-zero exit, unusable output)
+    Failed(String),
 }
 
-/// Test-friendly exit_with: return Err instead of exiting process
-#[cfg(test)]
-pub fn exit_with<T>(code: i32, msg: String) -> Result<T, Box<dyn std::error::Error>> {
-    Err(format!("exit({}): {}", code, msg).into())
+impl std::fmt::Display for ProviderErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProviderErrorKind::NotConfigured => write!(f, "not configured"),
+            ProviderErrorKind::Unavailable => write!(f, "unavailable"),
+            ProviderErrorKind::NotStarted(detail) => write!(f, "did not start: {}", detail),
+            ProviderErrorKind::Failed(detail) => write!(f, "{}", detail),
+        }
+    }
+}
+
+/// Errors returned by command implementations; `main` maps them to exit codes
+#[derive(Debug, thiserror::Error)]
+pub enum CliError {
+    #[error("{path_tried}{}", generate_first_run_guidance())]
+    ConfigMissing { path_tried: String },
+    #[error("{scope}: {detail}")]
+    Validation { scope: String, detail: String },
+    #[error("db: {0}")]
+    Db(#[from] db::DbError),
+    #[error("provider {provider}: {kind}")]
+    Provider { provider: String, kind: ProviderErrorKind },
+    #[error("tmux {operation}: {detail}")]
+    Tmux { operation: String, detail: String },
+    #[error("{operation}: timeout after {ms}ms")]
+    Timeout { operation: String, ms: u64 },
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl CliError {
+    pub fn validation(scope: impl Into<String>, detail: impl std::fmt::Display) -> Self {
+        CliError::Validation { scope: scope.into(), detail: detail.to_string() }
+    }
+
+    pub fn provider(provider: impl Into<String>, kind: ProviderErrorKind) -> Self {
+        CliError::Provider { provider: provider.into(), kind }
+    }
+
+    pub fn tmux(operation: impl Into<String>, detail: impl std::fmt::Display) -> Self {
+        CliError::Tmux { operation: operation.into(), detail: detail.to_string() }
+    }
+
+    pub fn timeout(operation: impl Into<String>, ms: u64) -> Self {
+        CliError::Timeout { operation: operation.into(), ms }
+    }
+
+    /// Standardized exit code (see docs/cli-reference.md)
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Other(_) | CliError::Io(_) => 1,
+            CliError::Validation { .. } => 2,
+            CliError::Provider { kind: ProviderErrorKind::Failed(_), .. } => 4,
+            CliError::Provider { .. } => 3,
+            CliError::Timeout { .. } => 5,
+            CliError::ConfigMissing { .. } => 6,
+            CliError::Db(_) => 7,
+            CliError::Tmux { .. } => 8,
+        }
+    }
+}
+
+impl From<&CliError> for std::process::ExitCode {
+    fn from(err: &CliError) -> Self {
+        std::process::ExitCode::from(err.exit_code() as u8)
+    }
+}
+
+impl From<String> for CliError {
+    fn from(msg: String) -> Self { CliError::Other(msg) }
+}
+
+impl From<&str> for CliError {
+    fn from(msg: &str) -> Self { CliError::Other(msg.to_string()) }
+}
+
+impl From<rusqlite::Error> for CliError {
+    fn from(err: rusqlite::Error) -> Self { CliError::Db(err.into()) }
+}
+
+impl From<serde_json::Error> for CliError {
+    fn from(err: serde_json::Error) -> Self { CliError::Other(format!("json: {}", err)) }
+}
+
+impl From<db::SessionError> for CliError {
+    fn from(err: db::SessionError) -> Self {
+        match err {
+            db::SessionError::ProviderUnavailable(provider) => CliError::provider(provider, ProviderErrorKind::Unavailable),
+            db::SessionError::Database(e) => CliError::Db(e),
+            db::SessionError::Sqlite(e) => e.into(),
+            other => CliError::validation("session", other),
+        }
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for CliError {
+    fn from(err: Box<dyn std::error::Error>) -> Self { CliError::Other(err.to_string()) }
 }
 
 /// Format error message based on output format
@@ -26,28 +127,23 @@ pub fn format_error(format: Format, which: &str, err: &impl std::fmt::Display) -
 /// Generate first-run guidance message for missing configuration
 pub fn generate_first_run_guidance() -> String {
     format!(
-        "\n🚀 First-time setup detected! Follow these steps:\n\
+        "\n{} Follow these steps:\n\
          \n\
          1) Check your environment:\n\
             multi-agents doctor\n\
          \n\
-         2) Initialize configuration:\n\
+         2) Initialize configuration, then edit the project and agents in it:\n\
             multi-agents config init [--dir ./config]\n\
          \n\
-         3) Initialize database:\n\
-            multi-agents db init\n\
-         \n\
-         4) Add your project and agents:\n\
-            multi-agents project add --name <project-name>\n\
-            multi-agents agent add --project <project-name> --name <agent-name> --role <role> --provider <provider> --model <model>\n\
+         3) Sync the project and agents into the database (add --dry-run to preview):\n\
+            multi-agents config sync\n\
          \n\
-         See docs/workflows.md for detailed examples."
+         See docs/workflows.md for detailed examples.",
+        super::style::decorated("🚀", "First-time setup detected!")
     )
 }
 
-/// Handle missing configuration with first-run guidance
-pub fn handle_missing_config<T>(error_msg: String) -> Result<T, Box<dyn std::error::Error>> {
-    let guidance = generate_first_run_guidance();
-    let full_message = format!("{}{}", error_msg, guidance);
-    exit_with(6, full_message)
+/// Missing configuration error; its message carries the first-run guidance
+pub fn handle_missing_config<T>(error_msg: String) -> Result<T, CliError> {
+    Err(CliError::ConfigMissing { path_tried: error_msg })
 }
@@ -0,0 +1,48 @@
+//! Ids handed to providers for new conversations, behind a trait so tests can pin them
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Source of the provider session ids minted when a send or `session start` opens a conversation
+pub trait IdGenerator: Send + Sync {
+    fn next_id(&self) -> String;
+}
+
+/// Random v4 UUIDs; the generator used outside tests
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomIds;
+
+impl IdGenerator for RandomIds {
+    fn next_id(&self) -> String {
+        uuid::Uuid::new_v4().to_string()
+    }
+}
+
+/// Test double handing out `<prefix>-1`, `<prefix>-2`, ... in call order
+#[derive(Debug)]
+pub struct SequenceIds {
+    prefix: String,
+    next: AtomicU64,
+}
+
+impl SequenceIds {
+    pub fn new(prefix: &str) -> Self {
+        Self { prefix: prefix.to_string(), next: AtomicU64::new(1) }
+    }
+}
+
+impl IdGenerator for SequenceIds {
+    fn next_id(&self) -> String {
+        format!("{}-{}", self.prefix, self.next.fetch_add(1, Ordering::SeqCst))
+    }
+}
+
+/// Provider session id for a new conversation with a provider of `family` (its `alias_root`, so
+/// aliases and `extends` children count as their base): claude and gemini get their
+/// `valid_session_` / `valid_context_` prefixes, other providers the bare id
+pub fn provider_session_id(family: &str, ids: &dyn IdGenerator) -> String {
+    match family {
+        "claude" => format!("valid_session_{}", ids.next_id()),
+        "gemini" => format!("valid_context_{}", ids.next_id()),
+        _ => ids.next_id(),
+    }
+}
@@ -2,7 +2,7 @@
 
 use std::fs;
 use std::time::{Duration, Instant};
-use crate::utils::errors::exit_with;
+use crate::utils::errors::CliError;
 
 /// File-based lock for agent operations
 pub struct AgentLock {
@@ -112,7 +112,7 @@ impl Drop for AgentLock {
 }
 
 /// Check if a process is running (Unix-specific)
-fn is_process_running(pid: u32) -> bool {
+pub fn is_process_running(pid: u32) -> bool {
     // On Unix systems, kill with signal 0 checks if process exists
     unsafe {
         libc::kill(pid as i32, 0) == 0
@@ -125,9 +125,9 @@ pub fn with_agent_lock<F, R>(
     agent: &str, 
     timeout: Duration, 
     f: F
-) -> Result<R, Box<dyn std::error::Error>>
+) -> Result<R, CliError>
 where
-    F: FnOnce() -> Result<R, Box<dyn std::error::Error>>,
+    F: FnOnce() -> Result<R, CliError>,
 {
     let mut lock = AgentLock::new(project, agent);
     
@@ -137,10 +137,10 @@ where
             let _ = lock.release();
             result
         }
-        Err(e) => {
-            let _ = exit_with::<()>(8, format!("Failed to acquire agent lock for {}/{}: {}. Another agent operation may be in progress.", project, agent, e));
-            unreachable!()
-        }
+        Err(e) => Err(CliError::tmux(
+            "acquire agent lock",
+            format!("{}/{}: {}. Another agent operation may be in progress.", project, agent, e),
+        )),
     }
 }
 
@@ -188,7 +188,7 @@ mod tests {
     #[test]
     fn test_with_agent_lock() {
         let result = with_agent_lock("test", "agent", Duration::from_secs(1), || {
-            Ok::<i32, Box<dyn std::error::Error>>(42)
+            Ok::<i32, CliError>(42)
         });
         
         assert_eq!(result.unwrap(), 42);
@@ -6,6 +6,12 @@ pub mod errors;
 pub mod constants;
 pub mod locks;
 pub mod db_path;
+pub mod prompt_file;
+pub mod style;
+pub mod diff;
+pub mod atomic_write;
+pub mod picker;
+pub mod ids;
 
 pub use config_resolver::*;
 pub use timeouts::*;
@@ -13,3 +19,9 @@ pub use errors::*;
 pub use constants::*;
 pub use locks::*;
 pub use db_path::*;
+pub use prompt_file::*;
+pub use style::*;
+pub use diff::*;
+pub use atomic_write::*;
+pub use picker::*;
+pub use ids::*;
@@ -0,0 +1,57 @@
+//! Numbered agent picker for commands run without `--agent`
+
+use std::io::{BufRead, IsTerminal, Write};
+use config_model::AgentConfig;
+use crate::utils::errors::CliError;
+
+/// `--agent` value that always asks for the agent, even when stdin is not a TTY
+pub const PICK_AGENT: &str = "-";
+
+/// Agent name to use: `requested` as given, or the picker on stdin/stderr when it is
+/// `-`, or omitted while stdin is a TTY
+pub fn select_agent(requested: Option<&str>, agents: &[AgentConfig]) -> Result<String, CliError> {
+    let stdin = std::io::stdin();
+    let is_tty = stdin.is_terminal();
+    resolve_agent_name(requested, agents, is_tty, &mut stdin.lock(), &mut std::io::stderr())
+}
+
+/// `select_agent` with an injected TTY flag and reader/writer pair
+pub fn resolve_agent_name(
+    requested: Option<&str>,
+    agents: &[AgentConfig],
+    is_tty: bool,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> Result<String, CliError> {
+    match requested {
+        Some(PICK_AGENT) => {}
+        Some(name) => return Ok(name.to_string()),
+        None if is_tty => {}
+        None => return Err(CliError::validation("agent", "--agent is required when stdin is not a TTY (use --agent - to pick from stdin)")),
+    }
+    pick_agent(agents, input, out).map(|a| a.name.clone())
+}
+
+/// List `agents` as a numbered menu and read a choice; invalid answers are asked again, end of input cancels
+pub fn pick_agent<'a>(agents: &'a [AgentConfig], input: &mut dyn BufRead, out: &mut dyn Write) -> Result<&'a AgentConfig, CliError> {
+    if agents.is_empty() {
+        return Err(CliError::validation("agent", "project defines no agents to pick from"));
+    }
+    let width = agents.iter().map(|a| a.name.len()).max().unwrap_or(0);
+    for (i, agent) in agents.iter().enumerate() {
+        writeln!(out, "{:>3}) {:<width$}  role: {}, provider: {}", i + 1, agent.name, agent.role, agent.provider, width = width)?;
+    }
+    loop {
+        write!(out, "Select agent [1-{}]: ", agents.len())?;
+        out.flush()?;
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            writeln!(out)?;
+            return Err(CliError::Other("agent selection cancelled".into()));
+        }
+        match answer.trim().parse::<usize>() {
+            Ok(n) if (1..=agents.len()).contains(&n) => return Ok(&agents[n - 1]),
+            _ => writeln!(out, "Please enter a number between 1 and {}", agents.len())?,
+        }
+    }
+}
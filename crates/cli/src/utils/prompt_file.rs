@@ -0,0 +1,42 @@
+//! Temporary prompt files for providers that take `{prompt_file}`
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Prompt written to a temp file; the file is removed when this guard is dropped
+#[derive(Debug)]
+pub struct PromptFile {
+    path: PathBuf,
+}
+
+impl PromptFile {
+    /// Write `prompt` to a fresh file under the system temp directory. The file is created
+    /// exclusively (a path someone else made fails instead of being reused) and, on unix, readable
+    /// by the owner only, since prompts may carry secrets.
+    pub fn create(prompt: &str) -> io::Result<Self> {
+        let path = std::env::temp_dir().join(format!("multi-agents-prompt-{}.txt", uuid::Uuid::new_v4()));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&path)?;
+        // From here on the guard removes the file, also when the write fails
+        let guard = Self { path };
+        file.write_all(prompt.as_bytes())?;
+        Ok(guard)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PromptFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
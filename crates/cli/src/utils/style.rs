@@ -0,0 +1,91 @@
+//! Decorative output: emoji, ANSI colors, and the chatter lines that `--quiet` suppresses
+
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use crate::cli::commands::ColorMode;
+
+const UNSET: u8 = 0;
+const STYLED: u8 = 1;
+const PLAIN: u8 = 2;
+
+static STYLING: AtomicU8 = AtomicU8::new(UNSET);
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Status coloring for short labels such as doctor's OK/DEGRADE/KO
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Tone {
+    Ok,
+    Warn,
+    Error,
+}
+
+/// Decide whether to style output; `auto` requires a TTY and an unset or empty `NO_COLOR`
+pub fn resolve_styling(mode: ColorMode, stdout_is_tty: bool, no_color: Option<&str>) -> bool {
+    match mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => stdout_is_tty && no_color.is_none_or(|v| v.is_empty()),
+    }
+}
+
+/// Apply `--color` for the rest of the process
+pub fn set_color_mode(mode: ColorMode) {
+    let styled = resolve_styling(mode, std::io::stdout().is_terminal(), std::env::var("NO_COLOR").ok().as_deref());
+    STYLING.store(if styled { STYLED } else { PLAIN }, Ordering::Relaxed);
+}
+
+/// Whether decorative output is enabled (resolved as `auto` until `set_color_mode` is called)
+pub fn styling_enabled() -> bool {
+    match STYLING.load(Ordering::Relaxed) {
+        STYLED => true,
+        PLAIN => false,
+        _ => {
+            set_color_mode(ColorMode::Auto);
+            STYLING.load(Ordering::Relaxed) == STYLED
+        }
+    }
+}
+
+/// `icon text` when styling is enabled, `text` otherwise
+pub fn decorated(icon: &str, text: &str) -> String {
+    render_decorated(icon, text, styling_enabled())
+}
+
+pub fn render_decorated(icon: &str, text: &str, styled: bool) -> String {
+    if styled { format!("{} {}", icon, text) } else { text.to_string() }
+}
+
+/// Wrap `text` in the ANSI color for `tone` when styling is enabled
+pub fn toned(text: &str, tone: Tone) -> String {
+    render_toned(text, tone, styling_enabled())
+}
+
+pub fn render_toned(text: &str, tone: Tone, styled: bool) -> String {
+    if !styled {
+        return text.to_string();
+    }
+    let code = match tone {
+        Tone::Ok => "32",
+        Tone::Warn => "33",
+        Tone::Error => "31",
+    };
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
+
+/// Apply `--quiet` for the rest of the process
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Whether `--quiet` is in effect
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Print a decorative stdout line (header, banner, hint, sync progress); nothing under `--quiet`,
+/// so stdout carries only the command's payload
+pub fn say(line: &str) {
+    if !quiet() {
+        println!("{}", line);
+    }
+}
@@ -2,11 +2,16 @@
 
 use std::time::{Duration, Instant};
 use std::process::{Command, Stdio};
-use std::io::{Read, BufRead, BufReader};
+use std::io::{self, Read, BufRead, BufReader};
 use std::thread;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::logging::{log_base, log_event_in, log_ndjson, Event};
+use crate::providers::output::{normalize_provider_output, OutputFormat};
 
-/// Run a command with timeout and return (exit_code, stdout, stderr)
+/// Run a command with timeout and return (exit_code, stdout, stderr).
+/// Both pipes are drained by their own threads while the child runs, so output larger than the
+/// OS pipe buffer cannot stall it; on timeout the child is killed and `Err("timeout")` returned.
 pub fn run_with_timeout(bin: &str, args: &[&str], timeout: Duration) -> Result<(i32, String, String), String> {
     let mut child = Command::new(bin)
         .args(args)
@@ -15,49 +20,238 @@ pub fn run_with_timeout(bin: &str, args: &[&str], timeout: Duration) -> Result<(
         .spawn()
         .map_err(|e| e.to_string())?;
 
-    let start = Instant::now();
-    loop {
-        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
-            let mut out = String::new();
-            let mut err = String::new();
-            if let Some(mut so) = child.stdout.take() {
-                let _ = so.read_to_string(&mut out);
-            }
-            if let Some(mut se) = child.stderr.take() {
-                let _ = se.read_to_string(&mut err);
+    let stdout = child.stdout.take().map(spawn_pipe_reader);
+    let stderr = child.stderr.take().map(spawn_pipe_reader);
+    // wait thread; the result is sent once both pipes are drained or the drain grace ran out
+    let pid = child.id();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let status = child.wait();
+        let deadline = Instant::now() + PIPE_DRAIN_GRACE;
+        let collect = |reader: Option<PipeReader>| {
+            reader.map(|(handle, buf)| {
+                join_until(vec![handle], deadline);
+                let buf = buf.lock().unwrap_or_else(|e| e.into_inner());
+                String::from_utf8_lossy(&buf).into_owned()
+            }).unwrap_or_default()
+        };
+        let (out, err) = (collect(stdout), collect(stderr));
+        let _ = tx.send(status.map(|s| (s.code().unwrap_or(-1), out, err)).map_err(|e| e.to_string()));
+    });
+
+    match rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(_) => {
+            // best-effort kill; the wait thread reaps the child
+            unsafe { libc::kill(pid as i32, libc::SIGKILL); }
+            Err("timeout".into())
+        }
+    }
+}
+
+/// How long to keep reading a child's pipes after it exited. A grandchild that inherited them may
+/// hold them open for as long as it lives, so past this the output read so far is used.
+const PIPE_DRAIN_GRACE: Duration = Duration::from_millis(500);
+
+/// A pipe reader thread and the bytes it has read so far
+type PipeReader = (thread::JoinHandle<()>, Arc<Mutex<Vec<u8>>>);
+
+/// Read a child pipe to its end on a thread of its own, into a buffer readable before it finishes
+fn spawn_pipe_reader<R: Read + Send + 'static>(mut pipe: R) -> PipeReader {
+    let buf = Arc::new(Mutex::new(Vec::new()));
+    let shared = Arc::clone(&buf);
+    let handle = thread::spawn(move || {
+        let mut chunk = [0u8; 8192];
+        loop {
+            match pipe.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => shared.lock().unwrap_or_else(|e| e.into_inner()).extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
             }
-            let code = status.code().unwrap_or(-1);
-            return Ok((code, out, err));
         }
-        if start.elapsed() >= timeout {
-            // best-effort kill
-            let _ = child.kill();
-            return Err("timeout".into());
+    });
+    (handle, buf)
+}
+
+/// Join the reader threads that finish before `deadline`; the others are left to run detached
+fn join_until(readers: Vec<thread::JoinHandle<()>>, deadline: Instant) {
+    for reader in readers {
+        while !reader.is_finished() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+        if reader.is_finished() {
+            let _ = reader.join();
         }
-        std::thread::sleep(Duration::from_millis(50));
     }
 }
 
 /// Line event for streaming operations
 #[derive(Debug)]
 pub enum LineEvent { 
-    Stdout(String), 
+    /// A stdout line, and the raw bytes it took on the pipe (line terminator and dropped bytes included)
+    Stdout { text: String, bytes: usize },
     Stderr(String), 
+    /// The next line of `stream` was cut to the maximum length; `dropped` bytes were discarded
+    Truncated { stream: &'static str, dropped: usize },
     Exit(i32) 
 }
 
-/// Run a command with timeout and streaming output
+/// Longest provider output line kept by `run_with_timeout_streaming` when `MULTI_AGENTS_MAX_LINE_BYTES` is unset
+pub const DEFAULT_MAX_LINE_BYTES: usize = 1024 * 1024;
+pub const MAX_LINE_BYTES_ENV: &str = "MULTI_AGENTS_MAX_LINE_BYTES";
+
+/// Maximum provider output line length, from `MULTI_AGENTS_MAX_LINE_BYTES`
+pub fn max_line_bytes() -> usize {
+    max_line_bytes_from_env(std::env::var(MAX_LINE_BYTES_ENV).ok().as_deref())
+}
+
+/// `MULTI_AGENTS_MAX_LINE_BYTES` value → bytes; unset, `0` or invalid falls back to 1 MiB
+pub fn max_line_bytes_from_env(value: Option<&str>) -> usize {
+    value.and_then(|v| v.trim().parse().ok()).filter(|&n| n > 0).unwrap_or(DEFAULT_MAX_LINE_BYTES)
+}
+
+/// A line read by `read_capped_line`
+#[derive(Debug, PartialEq)]
+pub struct CappedLine {
+    /// The line without its `\n`/`\r\n`, invalid UTF-8 replaced, with a marker when truncated
+    pub text: String,
+    /// Bytes discarded past the maximum length
+    pub dropped: usize,
+    /// Raw bytes consumed from the reader, before decoding and truncation, line terminator included
+    pub bytes: usize,
+}
+
+/// Read the next line, keeping at most `max` bytes of it: the rest is consumed and discarded, so a
+/// huge line never has to fit in memory. Invalid UTF-8 is replaced rather than ending the stream.
+/// A last line without a newline is still returned; `None` at end of input.
+pub fn read_capped_line<R: BufRead>(reader: &mut R, max: usize) -> io::Result<Option<CappedLine>> {
+    let mut buf = Vec::new();
+    let (mut dropped, mut bytes, mut read_any) = (0usize, 0usize, false);
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if available.is_empty() {
+            if !read_any { return Ok(None); }
+            break;
+        }
+        read_any = true;
+        let newline = available.iter().position(|&b| b == b'\n');
+        let chunk = &available[..newline.unwrap_or(available.len())];
+        let kept = chunk.len().min(max.saturating_sub(buf.len()));
+        buf.extend_from_slice(&chunk[..kept]);
+        dropped += chunk.len() - kept;
+        let used = chunk.len() + usize::from(newline.is_some());
+        reader.consume(used);
+        bytes += used;
+        if newline.is_some() { break; }
+    }
+    if dropped == 0 && buf.last() == Some(&b'\r') {
+        buf.pop();
+    }
+    let mut text = String::from_utf8_lossy(&buf).into_owned();
+    if dropped > 0 {
+        text.push_str(&format!(" [truncated {} bytes]", dropped));
+    }
+    Ok(Some(CappedLine { text, dropped, bytes }))
+}
+
+/// Forward the lines of a child pipe as `LineEvent`s until it closes or cannot be read
+fn spawn_line_reader<R: Read + Send + 'static>(
+    pipe: R,
+    tx: mpsc::Sender<LineEvent>,
+    stream: &'static str,
+    wrap: fn(CappedLine) -> LineEvent,
+    max: usize,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        while let Ok(Some(line)) = read_capped_line(&mut reader, max) {
+            if line.dropped > 0 {
+                let _ = tx.send(LineEvent::Truncated { stream, dropped: line.dropped });
+            }
+            let _ = tx.send(wrap(line));
+        }
+    })
+}
+
+/// Outcome of a streaming run
+#[derive(Debug, Default, PartialEq)]
+pub struct StreamOutcome {
+    pub exit_code: i32,
+    /// Session id reported by the provider in its output (claude json envelopes)
+    pub provider_session_id: Option<String>,
+    /// Total tokens reported by the provider, when its output carries usage
+    pub token_count: Option<u64>,
+    /// Raw stdout lines read from the provider
+    pub stdout_lines: u64,
+    /// Raw stdout bytes read from the provider, counted before decoding and truncation
+    pub stdout_bytes: u64,
+}
+
+/// How often a streaming run re-checks its cancellation flag while the child is silent
+const CANCEL_POLL: Duration = Duration::from_millis(100);
+
+/// Effective timeout for commands capped at `cap` ms (tmux operations): unset or `0` means `cap`,
+/// since "no timeout" only applies to provider one-shots
+pub fn capped_timeout_ms(flag: Option<u64>, cap: u64) -> u64 {
+    match flag {
+        None | Some(0) => cap,
+        Some(ms) => ms.min(cap),
+    }
+}
+
+/// Error of `run_with_timeout_streaming` when the total timeout fired
+pub const TIMEOUT_TOTAL: &str = "timeout";
+/// Error of `run_with_timeout_streaming` when the child printed nothing for the inactivity timeout
+pub const TIMEOUT_INACTIVITY: &str = "inactivity timeout";
+
+/// `timeout_kind` (`total` or `inactivity`) of a `run_with_timeout_streaming` error, `None` for other errors
+pub fn timeout_kind(err: &str) -> Option<&'static str> {
+    match err {
+        TIMEOUT_TOTAL => Some("total"),
+        TIMEOUT_INACTIVITY => Some("inactivity"),
+        _ => None,
+    }
+}
+
+/// Who a streaming run is for, as recorded on the events it logs, and how its stdout is read
+#[derive(Clone, Copy)]
+pub struct StreamContext<'a> {
+    pub project: &'a str,
+    pub agent_role: &'a str,
+    pub agent_name: &'a str,
+    pub provider_key: &'a str,
+    /// `alias_root` of `provider_key`, whose output rules apply
+    pub family: &'a str,
+    pub session_id: &'a str,
+    pub format: OutputFormat,
+    /// Ticked on every line read
+    pub progress: Option<&'a indicatif::ProgressBar>,
+    /// Print output lines on stdout as they arrive; when off they only reach the `capture`
+    pub echo: bool,
+}
+
+/// Run a command with timeout and streaming output; a zero `timeout` waits for exit without a deadline.
+/// `inactivity` (zero disables it) is a second deadline, pushed back by every stdout/stderr line, so a
+/// provider that keeps streaming may run up to `timeout` while a stalled one is killed early.
+/// The child is killed when either fires, returning `Err(TIMEOUT_TOTAL)` or `Err(TIMEOUT_INACTIVITY)`.
+/// When `cancel` is set while the child is still running, it is killed and `Err("cancelled")` is returned.
+/// Every line printed to stdout is also appended to `capture`, when given. Lines longer than
+/// `max_line_bytes()` are truncated with a marker and a `line_truncated` warning is logged.
 pub fn run_with_timeout_streaming(
     bin: &str,
     args: &[&str],
     timeout: Duration,
-    _project: &str,
-    _agent_role: &str,
-    _provider_key: &str,
-    _session_id: &str,
-    pb_opt: Option<&indicatif::ProgressBar>,
-    parse_cursor_stream: bool,
-) -> Result<i32, String> {
+    inactivity: Duration,
+    ctx: &StreamContext,
+    cancel: Option<&AtomicBool>,
+    mut capture: Option<&mut String>,
+) -> Result<StreamOutcome, String> {
+    let StreamContext { project, agent_role, agent_name, provider_key, family, session_id, format, progress: pb_opt, echo } = *ctx;
     let mut child = Command::new(bin)
         .args(args)
         .stdout(Stdio::piped())
@@ -67,120 +261,127 @@ pub fn run_with_timeout_streaming(
 
     let (tx, rx) = mpsc::channel::<LineEvent>();
 
-    // stdout reader
+    let max_line = max_line_bytes();
+    let mut readers = Vec::new();
     if let Some(so) = child.stdout.take() {
-        let txo = tx.clone();
-        thread::spawn(move || {
-            let reader = BufReader::new(so);
-            for line_res in reader.lines() {
-                if let Ok(line) = line_res { let _ = txo.send(LineEvent::Stdout(line)); } else { break; }
-            }
-        });
+        readers.push(spawn_line_reader(so, tx.clone(), "stdout", |line| LineEvent::Stdout { text: line.text, bytes: line.bytes }, max_line));
     }
-    // stderr reader
     if let Some(se) = child.stderr.take() {
-        let txe = tx.clone();
-        thread::spawn(move || {
-            let reader = BufReader::new(se);
-            for line_res in reader.lines() {
-                if let Ok(line) = line_res { let _ = txe.send(LineEvent::Stderr(line)); } else { break; }
-            }
-        });
+        readers.push(spawn_line_reader(se, tx.clone(), "stderr", |line| LineEvent::Stderr(line.text), max_line));
     }
-    // wait thread
+    // wait thread; Exit is sent after both pipes are drained so no trailing line is dropped,
+    // or once the drain grace ran out when a grandchild keeps them open
+    let pid = child.id();
     let txw = tx.clone();
     thread::spawn(move || {
-        match child.wait() {
+        let status = child.wait();
+        join_until(readers, Instant::now() + PIPE_DRAIN_GRACE);
+        match status {
             Ok(status) => { let _ = txw.send(LineEvent::Exit(status.code().unwrap_or(-1))); }
             Err(_) => { let _ = txw.send(LineEvent::Exit(-1)); }
         }
     });
 
     let start = Instant::now();
+    let mut last_line = start;
     let mut exit_code: Option<i32> = None;
-    let mut saw_final_result: bool = false;
+    let mut provider_session_id: Option<String> = None;
+    let mut token_count: Option<u64> = None;
+    let (mut stdout_lines, mut stdout_bytes) = (0u64, 0u64);
     loop {
-        let remaining = if start.elapsed() >= timeout { 0 } else { (timeout - start.elapsed()).as_millis() as u64 };
-        if remaining == 0 { return Err("timeout".into()); }
-        match rx.recv_timeout(Duration::from_millis(remaining)) {
-            Ok(LineEvent::Stdout(line)) => {
-                if parse_cursor_stream {
-                    if let Ok(v) = serde_json::from_str::<serde_json::Value>(&line) {
-                        // Parse cursor stream-json according to official spec
-                        let mut text_to_print = None;
-                        
-                        if let Some(event_type) = v.get("type").and_then(|t| t.as_str()) {
-                            match event_type {
-                                "assistant" => {
-                                    // Extract text from assistant.message.content[].text
-                                    if let Some(message) = v.get("message") {
-                                        if let Some(content) = message.get("content").and_then(|c| c.as_array()) {
-                                            for item in content {
-                                                if let Some(item_type) = item.get("type").and_then(|t| t.as_str()) {
-                                                    if item_type == "text" {
-                                                        if let Some(text) = item.get("text").and_then(|t| t.as_str()) {
-                                                            text_to_print = Some(text.to_string());
-                                                            break;
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                                "result" => {
-                                    // Final result event - extract complete text
-                                    if let Some(result) = v.get("result").and_then(|r| r.as_str()) {
-                                        text_to_print = Some(result.to_string());
-                                        saw_final_result = true;
-                                    }
-                                }
-                                "tool_call" => {
-                                    // Optional: could extract tool call info, but skip for now
-                                    continue;
-                                }
-                                _ => {
-                                    // system, user events - skip
-                                    continue;
-                                }
-                            }
-                        } else {
-                            // Fallback: try legacy flat fields for compatibility
-                            text_to_print = v.get("text").and_then(|x| x.as_str()).map(|s| s.to_string())
-                                .or_else(|| v.get("content").and_then(|x| x.as_str()).map(|s| s.to_string()))
-                                .or_else(|| v.get("message").and_then(|x| x.as_str()).map(|s| s.to_string()))
-                                .or_else(|| v.get("delta").and_then(|x| x.as_str()).map(|s| s.to_string()))
-                                .or_else(|| v.get("data").and_then(|x| x.as_str()).map(|s| s.to_string()));
-                        }
-                        
-                        if let Some(text) = text_to_print {
-                            println!("{}", text);
-                            // Log to NDJSON (would need logging module)
-                            // log_ndjson(project, agent_role, provider_key, Some(session_id), "agent", "stdout_line", Some(&text), None, None);
-                            // If we've seen the final result, we can return success immediately
-                            if saw_final_result {
-                                exit_code = Some(0);
-                                break;
-                            }
-                        }
+        if cancel.is_some_and(|c| c.load(Ordering::SeqCst)) {
+            // best-effort kill; the wait thread reaps the child
+            unsafe { libc::kill(pid as i32, libc::SIGKILL); }
+            return Err("cancelled".into());
+        }
+        let mut wait = CANCEL_POLL;
+        if !timeout.is_zero() {
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
+                unsafe { libc::kill(pid as i32, libc::SIGKILL); }
+                return Err(TIMEOUT_TOTAL.into());
+            }
+            wait = wait.min(remaining);
+        }
+        if !inactivity.is_zero() {
+            let remaining = inactivity.saturating_sub(last_line.elapsed());
+            if remaining.is_zero() {
+                unsafe { libc::kill(pid as i32, libc::SIGKILL); }
+                return Err(TIMEOUT_INACTIVITY.into());
+            }
+            wait = wait.min(remaining);
+        }
+        let received = rx.recv_timeout(wait);
+        if matches!(received, Ok(LineEvent::Stdout { .. } | LineEvent::Stderr(_) | LineEvent::Truncated { .. })) {
+            last_line = Instant::now();
+        }
+        match received {
+            Ok(LineEvent::Stdout { text: line, bytes }) => {
+                stdout_lines += 1;
+                stdout_bytes += bytes as u64;
+                let out = normalize_provider_output(family, &line, format);
+                // Tool activity is telemetry: logged, never printed
+                if let Some((event, text)) = &out.tool_event {
+                    log_ndjson(project, agent_role, provider_key, Some(session_id), "agent", event, Some(text), None, None, agent_name);
+                }
+                if format == OutputFormat::Text || !out.text.is_empty() {
+                    if echo {
+                        println!("{}", out.text);
+                    }
+                    if let Some(captured) = capture.as_deref_mut() {
+                        captured.push_str(&out.text);
+                        captured.push('\n');
                     }
-                } else {
-                    println!("{}", line);
-                    // Log to NDJSON (would need logging module)
-                    // log_ndjson(project, agent_role, provider_key, Some(session_id), "agent", "stdout_line", Some(&line), None, None);
+                    if format == OutputFormat::Json {
+                        log_ndjson(project, agent_role, provider_key, Some(session_id), "agent", "stdout_line", Some(&out.text), None, None, agent_name);
+                    }
+                }
+                if out.session_id.is_some() { provider_session_id = out.session_id; }
+                if out.token_count.is_some() { token_count = out.token_count; }
+                // If we've seen the final result, we can return success immediately
+                if out.final_result {
+                    exit_code = Some(0);
+                    break;
                 }
                 if let Some(pb) = pb_opt { pb.tick(); }
             }
             Ok(LineEvent::Stderr(line)) => {
                 eprintln!("{}", line);
                 // Log to NDJSON (would need logging module)
-                // log_ndjson(project, agent_role, provider_key, Some(session_id), "agent", "stderr_line", Some(&line), None, None);
+                // log_ndjson(project, agent_role, provider_key, Some(session_id), "agent", "stderr_line", Some(&line), None, None, agent_name);
                 if let Some(pb) = pb_opt { pb.tick(); }
             }
+            Ok(LineEvent::Truncated { stream, dropped }) => {
+                let text = format!("{} line cut to {} bytes, {} bytes dropped", stream, max_line, dropped);
+                let event = Event::builder(project, agent_role, provider_key, "line_truncated")
+                    .level("warn")
+                    .agent_id(agent_name)
+                    .session_id(Some(session_id))
+                    .direction("system")
+                    .text(Some(&text))
+                    .build();
+                let _ = log_event_in(&log_base(), &event);
+            }
             Ok(LineEvent::Exit(code)) => { exit_code = Some(code); break; }
-            Err(mpsc::RecvTimeoutError::Timeout) => { return Err("timeout".into()); }
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
             Err(_e) => { break; }
         }
     }
-    Ok(exit_code.unwrap_or(-1))
+    Ok(StreamOutcome { exit_code: exit_code.unwrap_or(-1), provider_session_id, token_count, stdout_lines, stdout_bytes })
+}
+
+/// Parse a duration such as `300s`, `30m`, `24h`, `7d` or `2w`
+pub fn parse_duration_spec(spec: &str) -> Result<Duration, String> {
+    let spec = spec.trim();
+    let (num, unit) = spec.split_at(spec.find(|c: char| !c.is_ascii_digit()).unwrap_or(spec.len()));
+    let n: u64 = num.parse().map_err(|_| format!("invalid duration '{}': expected <number><s|m|h|d|w>", spec))?;
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        "w" => 7 * 86_400,
+        _ => return Err(format!("invalid duration '{}': unit must be one of s, m, h, d, w", spec)),
+    };
+    Ok(Duration::from_secs(n.saturating_mul(secs)))
 }
@@ -0,0 +1,50 @@
+#!/bin/sh
+# Stand-in provider CLI for the integration tests, so they run without claude, gemini or
+# cursor-agent installed. Behaviour comes from leading --fake-* options; the remaining
+# arguments are the ones multi-agents passed, as a real provider would see them.
+#
+#   --fake-stdout TEXT    print TEXT on stdout (printf %b escapes, so \n works)
+#   --fake-stderr TEXT    print TEXT on stderr
+#   --fake-lines FILE     print FILE line by line, e.g. a stream-json sequence
+#   --fake-delay SECS     pause before each --fake-lines line
+#   --fake-sleep SECS     pause before exiting
+#   --fake-ignore-term    ignore SIGTERM and SIGINT
+#   --fake-exit CODE      exit status (default 0)
+#   --fake-record FILE    append the remaining arguments to FILE, one per line
+#   --fake-version TEXT   answer to --version (default "fake-provider 1.0.0")
+
+stdout= stderr= lines= delay=0 sleep_secs=0 code=0 record= version="fake-provider 1.0.0"
+while [ $# -gt 0 ]; do
+  case "$1" in
+    --fake-stdout) stdout=$2; shift 2 ;;
+    --fake-stderr) stderr=$2; shift 2 ;;
+    --fake-lines) lines=$2; shift 2 ;;
+    --fake-delay) delay=$2; shift 2 ;;
+    --fake-sleep) sleep_secs=$2; shift 2 ;;
+    --fake-ignore-term) trap '' TERM INT; shift ;;
+    --fake-exit) code=$2; shift 2 ;;
+    --fake-record) record=$2; shift 2 ;;
+    --fake-version) version=$2; shift 2 ;;
+    *) break ;;
+  esac
+done
+
+if [ -n "$record" ]; then
+  for arg in "$@"; do printf '%s\n' "$arg" >> "$record"; done
+fi
+
+case "$1" in
+  --version) printf '%s\n' "$version"; exit 0 ;;
+  --help) printf 'Usage: fake-provider [options] [prompt]\n  --output-format <format>\n  --session-id <id>\n  --resume <id>\n'; exit 0 ;;
+esac
+
+[ -n "$stdout" ] && printf '%b\n' "$stdout"
+[ -n "$stderr" ] && printf '%b\n' "$stderr" >&2
+if [ -n "$lines" ]; then
+  while IFS= read -r line || [ -n "$line" ]; do
+    sleep "$delay"
+    printf '%s\n' "$line"
+  done < "$lines"
+fi
+sleep "$sleep_secs"
+exit "$code"
@@ -11,10 +11,57 @@ pub struct ProjectConfig {
     pub agents: Vec<AgentConfig>,
     #[serde(default)]
     pub groups: Vec<GroupConfig>,
+    #[serde(default)]
+    pub defaults: ProjectDefaults,
 }
 
 fn default_schema_version() -> u32 { 1 }
 
+/// Project-wide defaults for CLI behavior
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectDefaults {
+    /// `send` asks for confirmation (or requires `--yes`) above this many targets
+    #[serde(default = "default_confirm_send_above")]
+    pub confirm_send_above: usize,
+    /// Agent system prompts shorter than this many characters (after trimming) fail validation
+    #[serde(default = "default_min_system_prompt_length")]
+    pub min_system_prompt_length: usize,
+    /// Agent system prompts longer than this many characters pass validation with a warning
+    #[serde(default = "default_max_system_prompt_warn_length")]
+    pub max_system_prompt_warn_length: usize,
+    /// Regex patterns whose matches in NDJSON log text are replaced with `[REDACTED]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redaction: Vec<String>,
+    /// `send` reuses stored output for a prompt it has already answered, as if `--cache` were given
+    #[serde(default)]
+    pub cache: bool,
+    /// How long a cached response stays valid, as `<number><s|m|h|d|w>` (default: 24h); `--cache-ttl` wins
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_ttl: Option<String>,
+    /// New sessions expire this long after creation, as `<number><s|m|h|d|w>` (default: never); `--session-ttl` wins
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_ttl: Option<String>,
+}
+
+fn default_confirm_send_above() -> usize { 5 }
+fn default_min_system_prompt_length() -> usize { 20 }
+fn default_max_system_prompt_warn_length() -> usize { 8000 }
+
+impl Default for ProjectDefaults {
+    fn default() -> Self {
+        Self {
+            confirm_send_above: default_confirm_send_above(),
+            min_system_prompt_length: default_min_system_prompt_length(),
+            max_system_prompt_warn_length: default_max_system_prompt_warn_length(),
+            redaction: Vec::new(),
+            cache: false,
+            cache_ttl: None,
+            session_ttl: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct AgentConfig {
@@ -44,6 +91,15 @@ pub struct ProvidersConfig {
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct ProviderTemplate {
+    /// Inherit every field from another provider key; fields set here override the parent's
+    /// (lists are replaced, not merged). Resolved when providers.yaml is parsed.
+    #[serde(default)]
+    pub extends: Option<String>,
+    /// Another name for the same CLI: takes the base provider's `cmd` (and its args, create-chat args included, when left empty)
+    /// and is validated with the base provider's rules. Resolved when providers.yaml is parsed.
+    #[serde(default)]
+    pub alias_of: Option<String>,
+    #[serde(default)]
     pub cmd: String,
     #[serde(default)]
     pub oneshot_args: Vec<String>,
@@ -55,6 +111,49 @@ pub struct ProviderTemplate {
     pub allowlist_flag: Option<String>,       // claude/gemini
     #[serde(default)]
     pub forbid_flags: Option<Vec<String>>,    // cursor --force, etc.
+    /// Environment variables the provider CLI needs (e.g. `ANTHROPIC_API_KEY`); checked by `doctor --check-keys`
+    #[serde(default)]
+    pub required_env_keys: Vec<String>,
+    /// Tool names the provider accepts in an agent's `allowed_tools`; unset means any tool is accepted
+    #[serde(default)]
+    pub known_tools: Option<Vec<String>>,
+    /// Default timeouts for one-shot sends to this provider; `--timeout-ms` / `--inactivity-timeout-ms` win
+    #[serde(default)]
+    pub timeouts: Option<ProviderTimeouts>,
+    /// How long the provider keeps a conversation, in milliseconds; new sessions get `expires_at`
+    /// this far ahead unless `--session-ttl` / `defaults.session_ttl` is set
+    #[serde(default)]
+    pub session_ttl_ms: Option<u64>,
+    /// How close to `expires_at`, in milliseconds, a reused session is still checked with the provider;
+    /// further from it the session is trusted without a ping (default 300000, 5 minutes)
+    #[serde(default)]
+    pub session_expiry_slack_ms: Option<u64>,
+}
+
+/// `timeouts:` block of a provider template
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderTimeouts {
+    /// Longest a send may run, in milliseconds
+    #[serde(default)]
+    pub total_ms: Option<u64>,
+    /// Longest a send may go without printing a stdout/stderr line, in milliseconds; unset or `0` disables it
+    #[serde(default)]
+    pub inactivity_ms: Option<u64>,
+}
+
+/// Non-fatal finding from `validate_project_config`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigWarning {
+    /// Index of the agent in `agents`
+    pub agent_index: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -65,14 +164,125 @@ pub enum ConfigError {
     Validation(String),
 }
 
+/// Parse project.yaml; unknown keys are errors at every level (agents, groups and defaults included)
 pub fn parse_project_yaml(yaml: &str) -> Result<ProjectConfig, ConfigError> {
     serde_yaml::from_str::<ProjectConfig>(yaml)
         .map_err(|e| ConfigError::InvalidYaml(e.to_string()))
 }
 
+/// Parse providers.yaml, resolving `extends` chains and `alias_of` links so every returned template is complete.
 pub fn parse_providers_yaml(yaml: &str) -> Result<ProvidersConfig, ConfigError> {
-    serde_yaml::from_str::<ProvidersConfig>(yaml)
-        .map_err(|e| ConfigError::InvalidYaml(e.to_string()))
+    // First pass checks the shape (and keeps serde_yaml's line/column errors)
+    let mut cfg = serde_yaml::from_str::<ProvidersConfig>(yaml)
+        .map_err(|e| ConfigError::InvalidYaml(e.to_string()))?;
+    if cfg.providers.values().any(|t| t.extends.is_some()) {
+        let mut raw: serde_yaml::Value = serde_yaml::from_str(yaml)
+            .map_err(|e| ConfigError::InvalidYaml(e.to_string()))?;
+        if let Some(providers) = raw.get_mut("providers").and_then(|p| p.as_mapping_mut()) {
+            *providers = resolve_provider_extends(providers)?;
+        }
+        cfg = serde_yaml::from_value::<ProvidersConfig>(raw)
+            .map_err(|e| ConfigError::InvalidYaml(e.to_string()))?;
+    }
+    let aliases: Vec<String> = cfg.providers.iter()
+        .filter(|(_, t)| t.alias_of.is_some())
+        .map(|(name, _)| name.clone())
+        .collect();
+    for name in aliases {
+        resolve_provider_alias(&name, &mut cfg.providers, &mut Vec::new())?;
+    }
+    Ok(cfg)
+}
+
+/// Fill an alias (recursively) from its base: `cmd` and `create_chat_args` when unset, `oneshot_args`/`repl_args` when empty.
+fn resolve_provider_alias(
+    name: &str,
+    providers: &mut BTreeMap<String, ProviderTemplate>,
+    chain: &mut Vec<String>,
+) -> Result<(), ConfigError> {
+    let Some(base) = providers.get(name).and_then(|t| t.alias_of.clone()) else {
+        return Ok(());
+    };
+    if chain.iter().any(|c| c == name) {
+        chain.push(name.to_string());
+        return Err(ConfigError::Validation(format!("providers.{}.alias_of: cycle detected ({})", chain[0], chain.join(" -> "))));
+    }
+    if !providers.contains_key(&base) {
+        return Err(ConfigError::Validation(format!("providers.{name}.alias_of: unknown provider '{base}'")));
+    }
+    chain.push(name.to_string());
+    resolve_provider_alias(&base, providers, chain)?;
+    chain.pop();
+    let base_t = providers[&base].clone();
+    let t = providers.get_mut(name).expect("alias present");
+    if t.cmd.trim().is_empty() { t.cmd = base_t.cmd; }
+    if t.oneshot_args.is_empty() { t.oneshot_args = base_t.oneshot_args; }
+    if t.repl_args.is_empty() { t.repl_args = base_t.repl_args; }
+    if t.create_chat_args.is_none() { t.create_chat_args = base_t.create_chat_args; }
+    Ok(())
+}
+
+/// Provider key whose rules apply to `name`: the end of its `alias_of`/`extends` chain, or `name` itself
+pub fn alias_root<'a>(cfg: &'a ProvidersConfig, name: &'a str) -> &'a str {
+    let mut current = name;
+    // Bounded so a hand-built config with a cycle cannot loop forever
+    for _ in 0..cfg.providers.len() {
+        match cfg.providers.get(current).and_then(|t| t.alias_of.as_deref().or(t.extends.as_deref())) {
+            Some(base) => current = base,
+            None => break,
+        }
+    }
+    current
+}
+
+/// allowed_tools policy: agents of claude/gemini (and their aliases) must list at least one tool
+pub fn requires_allowed_tools(cfg: &ProvidersConfig, provider: &str) -> bool {
+    matches!(alias_root(cfg, provider), "claude" | "gemini")
+}
+
+/// Merge each template over its `extends` parent (recursively); keys set on the child win.
+fn resolve_provider_extends(providers: &serde_yaml::Mapping) -> Result<serde_yaml::Mapping, ConfigError> {
+    let mut resolved: BTreeMap<String, serde_yaml::Mapping> = BTreeMap::new();
+    let mut out = serde_yaml::Mapping::new();
+    for key in providers.keys() {
+        let name = key.as_str().unwrap_or_default().to_string();
+        let template = resolve_provider_template(&name, providers, &mut resolved, &mut Vec::new())?;
+        out.insert(key.clone(), serde_yaml::Value::Mapping(template));
+    }
+    Ok(out)
+}
+
+fn resolve_provider_template(
+    name: &str,
+    providers: &serde_yaml::Mapping,
+    resolved: &mut BTreeMap<String, serde_yaml::Mapping>,
+    chain: &mut Vec<String>,
+) -> Result<serde_yaml::Mapping, ConfigError> {
+    if let Some(done) = resolved.get(name) {
+        return Ok(done.clone());
+    }
+    if chain.iter().any(|c| c == name) {
+        chain.push(name.to_string());
+        return Err(ConfigError::Validation(format!("providers.{}.extends: cycle detected ({})", chain[0], chain.join(" -> "))));
+    }
+    let own = providers.get(name).and_then(|t| t.as_mapping()).cloned().unwrap_or_default();
+    let merged = match own.get("extends").and_then(|e| e.as_str()) {
+        Some(parent) => {
+            if !providers.contains_key(parent) {
+                return Err(ConfigError::Validation(format!("providers.{name}.extends: unknown provider '{parent}'")));
+            }
+            chain.push(name.to_string());
+            let mut merged = resolve_provider_template(parent, providers, resolved, chain)?;
+            chain.pop();
+            for (k, v) in own {
+                merged.insert(k, v);
+            }
+            merged
+        }
+        None => own,
+    };
+    resolved.insert(name.to_string(), merged.clone());
+    Ok(merged)
 }
 
 #[cfg(test)]
@@ -98,6 +308,34 @@ groups: []
         assert_eq!(p.agents.len(), 1);
     }
 
+    #[test]
+    fn project_agent_key_typos_are_rejected() {
+        let agent = "  - name: a1\n    role: r\n    provider: claude\n    model: m\n    system_prompt: sp\n";
+        // `allowed_tool` must not silently leave `allowed_tools` empty
+        let typo = format!("project: demo\nagents:\n{agent}    allowed_tool: [Edit]\n");
+        let msg = parse_project_yaml(&typo).unwrap_err().to_string();
+        assert!(msg.contains("unknown field `allowed_tool`"), "{msg}");
+        assert!(msg.contains("agents[0]"), "{msg}");
+
+        // An extra key next to the right one fails too
+        let extra = format!("project: demo\nagents:\n{agent}    allowed_tools: [Edit]\n    system_promt: x\n");
+        let msg = parse_project_yaml(&extra).unwrap_err().to_string();
+        assert!(msg.contains("unknown field `system_promt`"), "{msg}");
+
+        for nested in ["groups:\n  - name: g\n    member: [a1]\n", "defaults:\n  confirm_send_abov: 3\n"] {
+            let yaml = format!("project: demo\nagents: []\n{nested}");
+            assert!(parse_project_yaml(&yaml).unwrap_err().to_string().contains("unknown field"), "{nested}");
+        }
+    }
+
+    #[test]
+    fn project_defaults_confirm_threshold() {
+        let base = "project: demo\nagents: []\n";
+        assert_eq!(parse_project_yaml(base).unwrap().defaults.confirm_send_above, 5);
+        let custom = format!("{base}defaults:\n  confirm_send_above: 10\n");
+        assert_eq!(parse_project_yaml(&custom).unwrap().defaults.confirm_send_above, 10);
+    }
+
     #[test]
     fn providers_placeholders_validation() {
         let prov = r#"
@@ -117,7 +355,7 @@ providers:
     repl_args: ["-i","{system_prompt}"]
 "#;
         let cfg = parse_providers_yaml(prov).unwrap();
-        assert!(validate_providers_config(&cfg).is_ok());
+        assert!(validate_providers_config(&cfg, false).is_ok());
     }
 
     #[test]
@@ -131,11 +369,220 @@ providers:
     repl_args: []
 "#;
         let cfg = parse_providers_yaml(prov).unwrap();
-        let err = validate_providers_config(&cfg).unwrap_err();
+        let err = validate_providers_config(&cfg, false).unwrap_err();
         let msg = format!("{}", err);
         assert!(msg.contains("providers.claude.oneshot_args must include {prompt}"));
     }
 
+    #[test]
+    fn providers_prompt_file_placeholder() {
+        let prov = r#"
+schema_version: 1
+providers:
+  gemini:
+    cmd: gemini
+    oneshot_args: ["--prompt-file","{prompt_file}"]
+    repl_args: ["-i","{system_prompt}"]
+"#;
+        let cfg = parse_providers_yaml(prov).unwrap();
+        assert!(validate_providers_config(&cfg, false).is_ok());
+
+        let both = r#"
+schema_version: 1
+providers:
+  gemini:
+    cmd: gemini
+    oneshot_args: ["{prompt}","--prompt-file","{prompt_file}"]
+    repl_args: ["-i","{system_prompt}"]
+"#;
+        let cfg = parse_providers_yaml(both).unwrap();
+        let msg = format!("{}", validate_providers_config(&cfg, false).unwrap_err());
+        assert!(msg.contains("providers.gemini.oneshot_args must use either {prompt} or {prompt_file}, not both"));
+    }
+
+    #[test]
+    fn providers_extends_multi_level_and_list_override() {
+        let prov = r#"
+schema_version: 1
+providers:
+  cursor-agent:
+    cmd: cursor-agent
+    oneshot_args: ["-p","{prompt}","--resume","{chat_id}"]
+    repl_args: ["agent","--resume","{chat_id}"]
+    create_chat_args: ["create-chat"]
+  cursor-agent-fast:
+    extends: cursor-agent
+    oneshot_args: ["-p","{prompt}","--resume","{chat_id}","--model","fast"]
+  cursor-agent-plan:
+    extends: cursor-agent-fast
+    cmd: cursor-agent-beta
+"#;
+        let cfg = parse_providers_yaml(prov).unwrap();
+        let fast = &cfg.providers["cursor-agent-fast"];
+        assert_eq!(fast.cmd, "cursor-agent");
+        // Lists are replaced wholesale, never merged
+        assert_eq!(fast.oneshot_args, vec!["-p", "{prompt}", "--resume", "{chat_id}", "--model", "fast"]);
+        assert_eq!(fast.repl_args, vec!["agent", "--resume", "{chat_id}"]);
+        assert_eq!(fast.create_chat_args, Some(vec!["create-chat".to_string()]));
+
+        let plan = &cfg.providers["cursor-agent-plan"];
+        assert_eq!(plan.cmd, "cursor-agent-beta");
+        assert_eq!(plan.oneshot_args, fast.oneshot_args);
+        assert_eq!(plan.extends.as_deref(), Some("cursor-agent-fast"));
+        assert_eq!(alias_root(&cfg, "cursor-agent-plan"), "cursor-agent");
+        assert!(validate_providers_config(&cfg, false).is_ok());
+    }
+
+    #[test]
+    fn providers_extends_validates_resolved_templates() {
+        let prov = r#"
+schema_version: 1
+providers:
+  cursor-agent:
+    cmd: cursor-agent
+    oneshot_args: ["{prompt}","--resume","{chat_id}"]
+    repl_args: ["agent","--resume","{chat_id}"]
+  cursor-agent-plan:
+    extends: cursor-agent
+    repl_args: ["agent"]
+"#;
+        let cfg = parse_providers_yaml(prov).unwrap();
+        let msg = format!("{}", validate_providers_config(&cfg, false).unwrap_err());
+        // The child's overridden repl_args are checked; its inherited cmd is not reported empty
+        assert!(msg.contains("providers.cursor-agent-plan: {chat_id} required"), "{msg}");
+        assert!(!msg.contains("cmd must not be empty"), "{msg}");
+        assert!(!msg.contains("providers.cursor-agent:"), "{msg}");
+
+        let orphan = r#"
+schema_version: 1
+providers:
+  claude-fast:
+    extends: claude
+    oneshot_args: ["{prompt}"]
+"#;
+        let msg = format!("{}", parse_providers_yaml(orphan).unwrap_err());
+        assert!(msg.contains("providers.claude-fast.extends: unknown provider 'claude'"), "{msg}");
+    }
+
+    #[test]
+    fn providers_extends_cycle_is_rejected() {
+        let prov = r#"
+schema_version: 1
+providers:
+  a:
+    extends: b
+  b:
+    extends: c
+  c:
+    extends: a
+"#;
+        let msg = format!("{}", parse_providers_yaml(prov).unwrap_err());
+        assert!(msg.contains("cycle detected (a -> b -> c -> a)"), "{msg}");
+
+        let selfref = "schema_version: 1\nproviders:\n  a:\n    extends: a\n";
+        let msg = format!("{}", parse_providers_yaml(selfref).unwrap_err());
+        assert!(msg.contains("cycle detected (a -> a)"), "{msg}");
+    }
+
+    #[test]
+    fn providers_alias_inherits_cmd_and_allows_arg_overrides() {
+        let prov = r#"
+schema_version: 1
+providers:
+  claude:
+    cmd: /opt/bin/claude
+    oneshot_args: ["-p","{prompt}","--session-id","{session_id}"]
+    repl_args: ["--append-system-prompt","{system_prompt}"]
+  claude-fast:
+    alias_of: claude
+  claude-careful:
+    alias_of: claude
+    oneshot_args: ["-p","--permission-mode","plan"]
+"#;
+        let cfg = parse_providers_yaml(prov).unwrap();
+        let fast = &cfg.providers["claude-fast"];
+        assert_eq!(fast.cmd, "/opt/bin/claude");
+        assert_eq!(fast.oneshot_args, cfg.providers["claude"].oneshot_args);
+
+        let careful = &cfg.providers["claude-careful"];
+        assert_eq!(careful.cmd, "/opt/bin/claude");
+        assert_eq!(careful.oneshot_args, vec!["-p", "--permission-mode", "plan"]);
+        assert_eq!(careful.repl_args, cfg.providers["claude"].repl_args);
+        assert_eq!(alias_root(&cfg, "claude-careful"), "claude");
+
+        // The override lost {prompt}: claude's rules apply to the alias
+        let msg = format!("{}", validate_providers_config(&cfg, false).unwrap_err());
+        assert!(msg.contains("providers.claude-careful.oneshot_args must include {prompt}"), "{msg}");
+        assert!(!msg.contains("providers.claude-fast"), "{msg}");
+    }
+
+    #[test]
+    fn providers_unknown_key_fails_only_when_strict() {
+        let prov = r#"
+schema_version: 1
+providers:
+  gemni:
+    cmd: gemini
+    oneshot_args: ["{prompt}"]
+    repl_args: []
+  ollama:
+    cmd: ollama
+    oneshot_args: ["run","llama3","{prompt}"]
+    repl_args: []
+  cursor-nightly:
+    cmd: cursor-agent
+    oneshot_args: ["{prompt}","--resume","{chat_id}"]
+    repl_args: ["agent","--resume","{chat_id}"]
+  gemini-fast:
+    alias_of: gemni
+"#;
+        let cfg = parse_providers_yaml(prov).unwrap();
+        assert!(validate_providers_config(&cfg, false).is_ok());
+
+        let msg = format!("{}", validate_providers_config(&cfg, true).unwrap_err());
+        assert!(msg.contains("providers.gemni: unknown provider 'gemni' (allowed: claude, gemini, cursor*, ollama)"), "{msg}");
+        // An alias is judged by its root
+        assert!(msg.contains("providers.gemini-fast: unknown provider 'gemni'"), "{msg}");
+        assert!(!msg.contains("ollama:") && !msg.contains("cursor-nightly"), "{msg}");
+    }
+
+    #[test]
+    fn providers_extends_child_passes_strict_as_its_root() {
+        let prov = r#"
+schema_version: 1
+providers:
+  claude:
+    cmd: claude
+    oneshot_args: ["-p","{prompt}","--session-id","{session_id}"]
+    repl_args: []
+  claude-fast:
+    extends: claude
+    oneshot_args: ["-p","--model","haiku","{prompt}","--session-id","{session_id}"]
+  gemni-fast:
+    extends: gemni
+  gemni:
+    cmd: gemini
+    oneshot_args: ["{prompt}"]
+    repl_args: []
+"#;
+        let cfg = parse_providers_yaml(prov).unwrap();
+        let msg = format!("{}", validate_providers_config(&cfg, true).unwrap_err());
+        assert!(!msg.contains("providers.claude-fast"), "{msg}");
+        // A child of an unknown key is still unknown, named by that root
+        assert!(msg.contains("providers.gemni-fast: unknown provider 'gemni'"), "{msg}");
+    }
+
+    #[test]
+    fn providers_alias_cycle_and_unknown_base_are_rejected() {
+        let prov = "schema_version: 1\nproviders:\n  a:\n    alias_of: b\n  b:\n    alias_of: a\n";
+        let msg = format!("{}", parse_providers_yaml(prov).unwrap_err());
+        assert!(msg.contains("providers.a.alias_of: cycle detected (a -> b -> a)"), "{msg}");
+
+        let orphan = "schema_version: 1\nproviders:\n  fast:\n    alias_of: claude\n";
+        let msg = format!("{}", parse_providers_yaml(orphan).unwrap_err());
+        assert!(msg.contains("providers.fast.alias_of: unknown provider 'claude'"), "{msg}");
+    }
+
     #[test]
     fn project_validation_checks_provider_and_allowed_tools() {
         let prov = r#"
@@ -163,6 +610,138 @@ agents:
         let msg = format!("{}", err);
         assert!(msg.contains("allowed_tools must not be empty"));
     }
+
+    #[test]
+    fn project_validation_checks_chat_id_is_resolvable() {
+        let providers = |create_chat: &str| parse_providers_yaml(&format!(r#"
+schema_version: 1
+providers:
+  cursor-agent:
+    cmd: cursor-agent
+    oneshot_args: ["{{prompt}}","--resume","{{chat_id}}"]
+    repl_args: ["agent","--resume","{{chat_id}}"]
+{create_chat}
+  gemini:
+    cmd: gemini
+    oneshot_args: ["{{prompt}}","--chat","{{chat_id}}"]
+    repl_args: ["-i","{{system_prompt}}"]
+  workbench:
+    alias_of: cursor-agent
+  workbench-ready:
+    alias_of: cursor-agent
+    create_chat_args: ["create-chat"]
+"#)).unwrap();
+        let project = |provider: &str| parse_project_yaml(&format!(
+            "project: demo\nagents:\n  - name: a1\n    role: r\n    provider: {provider}\n    model: m\n    allowed_tools: [Edit]\n    system_prompt: You write backend services.\n"
+        )).unwrap();
+
+        let incomplete = providers("");
+        let msg = validate_project_config(&project("cursor-agent"), &incomplete).unwrap_err().to_string();
+        assert!(msg.contains("agents[0] (provider=cursor-agent): providers.cursor-agent uses {chat_id} but has no create_chat_args"), "{msg}");
+
+        let complete = providers("    create_chat_args: [\"create-chat\"]");
+        assert!(validate_project_config(&project("cursor-agent"), &complete).is_ok());
+
+        // Only cursor providers ever create a chat
+        let msg = validate_project_config(&project("gemini"), &complete).unwrap_err().to_string();
+        assert!(msg.contains("providers.gemini uses {chat_id}, which only cursor* providers can create"), "{msg}");
+        // An alias of cursor-agent is checked like cursor-agent itself, and takes its create_chat_args
+        let msg = validate_project_config(&project("workbench"), &incomplete).unwrap_err().to_string();
+        assert!(msg.contains("providers.workbench uses {chat_id} but has no create_chat_args"), "{msg}");
+        assert!(validate_project_config(&project("workbench"), &complete).is_ok());
+        assert!(validate_project_config(&project("workbench-ready"), &incomplete).is_ok());
+    }
+
+    #[test]
+    fn project_validation_rejects_bad_redaction_patterns() {
+        let providers = parse_providers_yaml("schema_version: 1\nproviders:\n  mock:\n    cmd: mock\n    oneshot_args: [\"{prompt}\"]\n    repl_args: []\n").unwrap();
+        let project = |patterns: &str| parse_project_yaml(&format!(
+            "project: demo\ndefaults:\n  redaction: {patterns}\nagents:\n  - name: a1\n    role: r\n    provider: mock\n    model: m\n    allowed_tools: []\n    system_prompt: You write backend services.\n"
+        )).unwrap();
+
+        let ok = project(r#"["sk-[A-Za-z0-9]{20,}", "(?i)password=\\S+"]"#);
+        assert_eq!(ok.defaults.redaction.len(), 2);
+        assert!(validate_project_config(&ok, &providers).is_ok());
+
+        let msg = validate_project_config(&project(r#"["sk-.*", "[unclosed", ""]"#), &providers).unwrap_err().to_string();
+        assert!(msg.contains("defaults.redaction[1]: invalid regex '[unclosed'"), "{msg}");
+        assert_eq!(compile_redaction(&["".into()]).unwrap_err(), "defaults.redaction[0] must not be empty");
+    }
+
+    #[test]
+    fn unknown_agent_tools_checks_provider_known_tools() {
+        let prov = r#"
+schema_version: 1
+providers:
+  claude:
+    cmd: claude
+    oneshot_args: ["{prompt}","--session-id","{session_id}"]
+    repl_args: []
+    known_tools: [Read, Edit, Bash]
+"#;
+        let providers = parse_providers_yaml(prov).unwrap();
+        let project = |tools: &str| parse_project_yaml(&format!(r#"
+schema_version: 1
+project: demo
+agents:
+  - name: a1
+    role: r
+    provider: claude
+    model: m
+    allowed_tools: {tools}
+    system_prompt: You review pull requests.
+"#)).unwrap();
+
+        assert!(unknown_agent_tools(&project(r#"[Read, "Bash(git status)"]"#), &providers).is_empty());
+        let warnings = unknown_agent_tools(&project("[Read, Teleport]"), &providers);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("unknown tool 'Teleport'"), "{}", warnings[0]);
+        // Unknown tools stay out of the hard validation errors
+        assert!(validate_project_config(&project("[Read, Teleport]"), &providers).is_ok());
+    }
+
+    #[test]
+    fn system_prompt_length_limits() {
+        let prov = r#"
+schema_version: 1
+providers:
+  cursor-agent:
+    cmd: cursor-agent
+    oneshot_args: ["-p","{prompt}"]
+    repl_args: []
+"#;
+        let providers = parse_providers_yaml(prov).unwrap();
+        let project = |prompt: &str, defaults: &str| {
+            let mut p = parse_project_yaml(&format!(r#"
+schema_version: 1
+project: demo
+agents:
+  - name: a1
+    role: r
+    provider: cursor-agent
+    model: m
+    allowed_tools: []
+    system_prompt: x
+{defaults}"#)).unwrap();
+            p.agents[0].system_prompt = prompt.to_string();
+            p
+        };
+
+        let err = validate_project_config(&project(" ", ""), &providers).unwrap_err();
+        assert!(err.to_string().contains("system_prompt must not be empty"), "{}", err);
+        let err = validate_project_config(&project("  Be brief.  ", ""), &providers).unwrap_err();
+        assert!(err.to_string().contains("agents[0].system_prompt is 9 characters, below defaults.min_system_prompt_length (20)"), "{}", err);
+        assert!(validate_project_config(&project("Be brief.", "defaults:\n  min_system_prompt_length: 5\n"), &providers).unwrap().is_empty());
+
+        let at_limit = "p".repeat(8000);
+        assert!(validate_project_config(&project(&at_limit, ""), &providers).unwrap().is_empty());
+        let warnings = validate_project_config(&project(&"p".repeat(8001), ""), &providers).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].agent_index, 0);
+        assert!(warnings[0].message.contains("above defaults.max_system_prompt_warn_length (8000)"), "{}", warnings[0]);
+        let raised = "defaults:\n  max_system_prompt_warn_length: 10000\n";
+        assert!(validate_project_config(&project(&"p".repeat(8001), raised), &providers).unwrap().is_empty());
+    }
 }
 
 pub fn json_schema_project() -> schemars::Schema {
@@ -177,57 +756,69 @@ fn args_contain(args: &[String], needle: &str) -> bool {
     args.iter().any(|a| a.contains(needle))
 }
 
-/// Validate providers templates for required placeholders per known provider.
-pub fn validate_providers_config(cfg: &ProvidersConfig) -> Result<(), ConfigError> {
+/// Check each provider template against its provider's placeholder rules. With `strict`, a key (or
+/// `alias_of`/`extends` root) other than claude, gemini, cursor* or ollama is an error; otherwise it is accepted unchecked.
+pub fn validate_providers_config(cfg: &ProvidersConfig, strict: bool) -> Result<(), ConfigError> {
     let mut errors: Vec<String> = Vec::new();
     for (name, t) in &cfg.providers {
         if t.cmd.trim().is_empty() {
             errors.push(format!("providers.{name}.cmd must not be empty"));
         }
-        let oneshot_has_prompt = args_contain(&t.oneshot_args, "{prompt}");
+        let oneshot_has_inline_prompt = args_contain(&t.oneshot_args, "{prompt}");
+        let oneshot_has_prompt_file = args_contain(&t.oneshot_args, "{prompt_file}");
+        if oneshot_has_inline_prompt && oneshot_has_prompt_file {
+            errors.push(format!("providers.{name}.oneshot_args must use either {{prompt}} or {{prompt_file}}, not both"));
+        }
+        let oneshot_has_prompt = oneshot_has_inline_prompt || oneshot_has_prompt_file;
         let repl_has_system = args_contain(&t.repl_args, "{system_prompt}");
         let oneshot_has_session = args_contain(&t.oneshot_args, "{session_id}");
         let any_has_allowed = args_contain(&t.oneshot_args, "{allowed_tools}") || args_contain(&t.repl_args, "{allowed_tools}");
         let oneshot_has_chat = args_contain(&t.oneshot_args, "{chat_id}");
         let repl_has_chat = args_contain(&t.repl_args, "{chat_id}");
 
-        match name.as_str() {
+        match alias_root(cfg, name) {
             // Claude Code expectations
             "claude" => {
                 if !oneshot_has_prompt {
-                    errors.push("providers.claude.oneshot_args must include {prompt}".into());
+                    errors.push(format!("providers.{name}.oneshot_args must include {{prompt}} or {{prompt_file}}"));
                 }
                 if !oneshot_has_session && !repl_has_system {
                     // session id is usually needed for reuse; tolerate if REPL will inject system prompt
-                    errors.push("providers.claude: expected {session_id} in oneshot_args or a REPL flow".into());
+                    errors.push(format!("providers.{name}: expected {{session_id}} in oneshot_args or a REPL flow"));
                 }
                 if t.allowlist_flag.is_some() && !any_has_allowed {
-                    errors.push("providers.claude: allowlist_flag set but {allowed_tools} placeholder missing in args".into());
+                    errors.push(format!("providers.{name}: allowlist_flag set but {{allowed_tools}} placeholder missing in args"));
                 }
             }
             // Cursor Agent expectations
             k if k.starts_with("cursor") => {
                 if !oneshot_has_prompt {
-                    errors.push(format!("providers.{k}.oneshot_args must include {{prompt}}"));
+                    errors.push(format!("providers.{name}.oneshot_args must include {{prompt}} or {{prompt_file}}"));
                 }
                 if !oneshot_has_chat || !repl_has_chat {
-                    errors.push(format!("providers.{k}: {{chat_id}} required in oneshot_args and repl_args"));
+                    errors.push(format!("providers.{name}: {{chat_id}} required in oneshot_args and repl_args"));
                 }
             }
             // Gemini CLI expectations
             "gemini" => {
                 if !oneshot_has_prompt {
-                    errors.push("providers.gemini.oneshot_args must include {prompt}".into());
+                    errors.push(format!("providers.{name}.oneshot_args must include {{prompt}} or {{prompt_file}}"));
                 }
                 if !repl_has_system {
-                    errors.push("providers.gemini.repl_args must include {system_prompt}".into());
+                    errors.push(format!("providers.{name}.repl_args must include {{system_prompt}}"));
                 }
                 if t.allowlist_flag.is_some() && !any_has_allowed {
-                    errors.push("providers.gemini: allowlist_flag set but {allowed_tools} placeholder missing in args".into());
+                    errors.push(format!("providers.{name}: allowlist_flag set but {{allowed_tools}} placeholder missing in args"));
                 }
             }
-            _ => {
-                // Unknown provider key: no strict validation
+            "ollama" => {}
+            root => {
+                // Unknown provider key: accepted for forward compatibility unless strict
+                if strict {
+                    errors.push(format!(
+                        "providers.{name}: unknown provider '{root}' (allowed: claude, gemini, cursor*, ollama)"
+                    ));
+                }
             }
         }
     }
@@ -235,9 +826,11 @@ pub fn validate_providers_config(cfg: &ProvidersConfig) -> Result<(), ConfigErro
     if errors.is_empty() { Ok(()) } else { Err(ConfigError::Validation(errors.join("; "))) }
 }
 
-/// Validate a project config against providers config.
-pub fn validate_project_config(project: &ProjectConfig, providers: &ProvidersConfig) -> Result<(), ConfigError> {
+/// Check a project against its providers; problems that do not block a run come back as warnings
+pub fn validate_project_config(project: &ProjectConfig, providers: &ProvidersConfig) -> Result<Vec<ConfigWarning>, ConfigError> {
     let mut errors: Vec<String> = Vec::new();
+    let mut warnings: Vec<ConfigWarning> = Vec::new();
+    let limits = &project.defaults;
     if project.schema_version != 1 { errors.push("project.schema_version must be 1".into()); }
 
     // Agent names must be unique and providers must exist
@@ -252,18 +845,32 @@ pub fn validate_project_config(project: &ProjectConfig, providers: &ProvidersCon
         if !providers.providers.contains_key(&a.provider) {
             errors.push(format!("agents[{idx}].provider '{}' not found in providers.yaml", a.provider));
         }
-        // allowed_tools policy: for claude/gemini must be non-empty
-        match a.provider.as_str() {
-            "claude" | "gemini" => {
-                if a.allowed_tools.is_empty() {
-                    errors.push(format!("agents[{idx}] (provider={}): allowed_tools must not be empty", a.provider));
-                }
+        if a.allowed_tools.is_empty() && requires_allowed_tools(providers, &a.provider) {
+            errors.push(format!("agents[{idx}] (provider={}): allowed_tools must not be empty", a.provider));
+        }
+        // Every provider placeholder must be resolvable for this agent
+        if let Some(t) = providers.providers.get(&a.provider) {
+            if let Some(problem) = unresolvable_chat_id(providers, &a.provider, t) {
+                errors.push(format!("agents[{idx}] (provider={}): {problem}", a.provider));
             }
-            _ => {}
         }
-        // system_prompt should not be empty
-        if a.system_prompt.trim().is_empty() {
+        // system_prompt should not be empty, nor so short it cannot be a real prompt
+        let prompt_len = a.system_prompt.trim().chars().count();
+        if prompt_len == 0 {
             errors.push(format!("agents[{idx}].system_prompt must not be empty"));
+        } else if prompt_len < limits.min_system_prompt_length {
+            errors.push(format!(
+                "agents[{idx}].system_prompt is {prompt_len} characters, below defaults.min_system_prompt_length ({})",
+                limits.min_system_prompt_length
+            ));
+        } else if prompt_len > limits.max_system_prompt_warn_length {
+            warnings.push(ConfigWarning {
+                agent_index: idx,
+                message: format!(
+                    "agents[{idx}].system_prompt is {prompt_len} characters, above defaults.max_system_prompt_warn_length ({}); it may exceed the provider's token limit",
+                    limits.max_system_prompt_warn_length
+                ),
+            });
         }
     }
 
@@ -276,5 +883,56 @@ pub fn validate_project_config(project: &ProjectConfig, providers: &ProvidersCon
         }
     }
 
-    if errors.is_empty() { Ok(()) } else { Err(ConfigError::Validation(errors.join("; "))) }
+    if let Err(e) = compile_redaction(&limits.redaction) {
+        errors.push(e);
+    }
+
+    if errors.is_empty() { Ok(warnings) } else { Err(ConfigError::Validation(errors.join("; "))) }
+}
+
+/// Compile `defaults.redaction`; the error names the first bad pattern by index
+pub fn compile_redaction(patterns: &[String]) -> Result<Vec<regex::Regex>, String> {
+    patterns.iter().enumerate().map(|(idx, p)| {
+        if p.is_empty() {
+            return Err(format!("defaults.redaction[{idx}] must not be empty"));
+        }
+        regex::Regex::new(p).map_err(|e| format!("defaults.redaction[{idx}]: invalid regex '{p}': {e}"))
+    }).collect()
+}
+
+/// Why `{chat_id}` in `template`'s args can never be filled for an agent on `provider_key`, if so.
+/// `{session_id}`, `{prompt}`, `{prompt_file}`, `{system_prompt}` and `{allowed_tools}` are always
+/// available; a chat id only exists once a `cursor*` provider (or alias of one) has run its `create_chat_args`.
+fn unresolvable_chat_id(cfg: &ProvidersConfig, provider_key: &str, template: &ProviderTemplate) -> Option<String> {
+    if !args_contain(&template.oneshot_args, "{chat_id}") && !args_contain(&template.repl_args, "{chat_id}") {
+        return None;
+    }
+    if !alias_root(cfg, provider_key).starts_with("cursor") {
+        return Some(format!("providers.{provider_key} uses {{chat_id}}, which only cursor* providers can create"));
+    }
+    match &template.create_chat_args {
+        Some(args) if !args.is_empty() => None,
+        _ => Some(format!("providers.{provider_key} uses {{chat_id}} but has no create_chat_args, so no chat can be created")),
+    }
+}
+
+/// Agent tools missing from their provider's `known_tools`, one message per tool.
+/// Scoped entries such as `Bash(git status)` are matched on the name before `(`.
+pub fn unknown_agent_tools(project: &ProjectConfig, providers: &ProvidersConfig) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for (idx, a) in project.agents.iter().enumerate() {
+        let Some(known) = providers.providers.get(&a.provider).and_then(|t| t.known_tools.as_ref()) else {
+            continue;
+        };
+        for tool in &a.allowed_tools {
+            let name = tool.split('(').next().unwrap_or(tool).trim();
+            if !known.iter().any(|k| k == name) {
+                warnings.push(format!(
+                    "agents[{idx}] (provider={}): unknown tool '{tool}' not in providers.{}.known_tools",
+                    a.provider, a.provider
+                ));
+            }
+        }
+    }
+    warnings
 }
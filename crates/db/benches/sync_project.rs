@@ -0,0 +1,67 @@
+//! `sync_project_from_config` on a 50-agent project that is already in the database, the path
+//! every `send` takes: the single-query sync against the one-query-per-agent sync it replaced,
+//! both run end to end on the same database.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use config_model::ProjectConfig;
+use db::{DbError, IdOrName, SyncReport};
+use rusqlite::{params, Connection};
+
+const AGENTS: usize = 50;
+
+fn project_config() -> ProjectConfig {
+    let agents: String = (0..AGENTS)
+        .map(|i| format!("  - {{ name: agent-{i:02}, role: backend, provider: gemini, model: m, allowed_tools: [], system_prompt: sp }}\n"))
+        .collect();
+    config_model::parse_project_yaml(&format!("project: bench\nagents:\n{agents}")).unwrap()
+}
+
+/// The sync as it was before: one transaction, with an existence query per configured agent
+fn sync_one_query_per_agent(conn: &Connection, config: &ProjectConfig) -> Result<SyncReport, DbError> {
+    let tx = conn.unchecked_transaction()?;
+    let mut report = SyncReport::default();
+    report.project_id = match db::find_project_id(&tx, IdOrName::Name(&config.project))? {
+        Some(id) => id,
+        None => {
+            report.project_created = true;
+            db::insert_project(&tx, &config.project)?.id
+        }
+    };
+    {
+        let mut exists = tx.prepare_cached("SELECT 1 FROM agents WHERE project_id = ?1 AND name = ?2 LIMIT 1")?;
+        for agent in &config.agents {
+            if exists.exists(params![&report.project_id, &agent.name])? {
+                report.agents_existing.push(agent.name.clone());
+                continue;
+            }
+            db::insert_agent(&tx, &report.project_id, &agent.name, &agent.role, &agent.provider, &agent.model, &agent.allowed_tools, &agent.system_prompt)?;
+            report.agents_created.push(agent.name.clone());
+        }
+    }
+    tx.commit()?;
+    Ok(report)
+}
+
+fn sync_existing_agents(c: &mut Criterion) {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("bench.sqlite3");
+    let conn = db::open_or_create_db(path.to_string_lossy().as_ref()).unwrap();
+    let config = project_config();
+    db::sync_project_from_config(&conn, &config).unwrap();
+    // Both paths report the same thing on this fixture, so only their cost differs
+    assert_eq!(sync_one_query_per_agent(&conn, &config).unwrap(), db::sync_project_from_config(&conn, &config).unwrap());
+
+    let mut group = c.benchmark_group(format!("sync {} existing agents", AGENTS));
+    group.bench_function("one query per agent", |b| b.iter(|| {
+        let report = sync_one_query_per_agent(&conn, &config).unwrap();
+        assert_eq!(report.agents_existing.len(), AGENTS);
+    }));
+    group.bench_function("sync_project_from_config", |b| b.iter(|| {
+        let report = db::sync_project_from_config(&conn, &config).unwrap();
+        assert_eq!(report.agents_existing.len(), AGENTS);
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, sync_existing_agents);
+criterion_main!(benches);
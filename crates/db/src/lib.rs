@@ -1,6 +1,8 @@
-use rusqlite::{Connection, params, OptionalExtension};
+#![deny(clippy::format_in_format_args)]
+
+use rusqlite::{Connection, params, OptionalExtension, Transaction, TransactionBehavior};
 use serde_json::json;
-use config_model::ProjectConfig;
+use config_model::{AgentConfig, ProjectConfig};
 
 #[derive(Debug, thiserror::Error)]
 pub enum DbError {
@@ -10,57 +12,286 @@ pub enum DbError {
     Io(#[from] std::io::Error),
     #[error("invalid input: {0}")]
     InvalidInput(String),
+    #[error("json: {0}")]
+    JsonSerde(#[from] serde_json::Error),
+    #[error("database schema v{db_version} is newer than this binary supports (v{supported}); upgrade multi-agents")]
+    SchemaTooNew { db_version: i64, supported: i64 },
+    #[error("database schema v{db_version} is older than this binary expects (v{required}); run `multi-agents db migrate`")]
+    SchemaTooOld { db_version: i64, required: i64 },
+    #[error("database is locked by another process (gave up after {attempts} attempts); retry, or raise {}", DB_BUSY_MS_ENV)]
+    Busy { attempts: usize },
+}
+
+// ---------- Busy handling ----------
+
+/// Environment variable overriding the SQLite `busy_timeout` of new connections, in milliseconds
+pub const DB_BUSY_MS_ENV: &str = "MULTI_AGENTS_DB_BUSY_MS";
+/// `busy_timeout` when `MULTI_AGENTS_DB_BUSY_MS` is unset or not a number
+pub const DEFAULT_BUSY_TIMEOUT_MS: u64 = 3000;
+/// Pauses of `with_busy_retry` between attempts; the last pause is followed by a final attempt
+const BUSY_RETRY_DELAYS_MS: &[u64] = &[25, 50, 100, 200, 400];
+
+/// `busy_timeout` for new connections, from `MULTI_AGENTS_DB_BUSY_MS`
+pub fn busy_timeout_ms() -> u64 {
+    busy_timeout_from_env(std::env::var(DB_BUSY_MS_ENV).ok().as_deref())
+}
+
+/// `MULTI_AGENTS_DB_BUSY_MS` value → milliseconds; unset or invalid falls back to 3000
+pub fn busy_timeout_from_env(value: Option<&str>) -> u64 {
+    value.and_then(|v| v.trim().parse().ok()).unwrap_or(DEFAULT_BUSY_TIMEOUT_MS)
+}
+
+/// Run a write, re-attempting it with short sleeps while SQLite reports the database busy or locked.
+/// `busy_timeout` already waits inside each attempt; this covers the lock being held for longer, as under
+/// large concurrent broadcasts. Once the attempts run out the error is `DbError::Busy`.
+pub fn with_busy_retry<T>(mut op: impl FnMut() -> Result<T, DbError>) -> Result<T, DbError> {
+    for delay in BUSY_RETRY_DELAYS_MS {
+        match op() {
+            Err(e) if is_busy(&e) => std::thread::sleep(std::time::Duration::from_millis(*delay)),
+            result => return result,
+        }
+    }
+    match op() {
+        Err(e) if is_busy(&e) => Err(DbError::Busy { attempts: BUSY_RETRY_DELAYS_MS.len() + 1 }),
+        result => result,
+    }
+}
+
+fn is_busy(e: &DbError) -> bool {
+    matches!(
+        e,
+        DbError::Sqlite(rusqlite::Error::SqliteFailure(err, _))
+            if matches!(err.code, rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked)
+    )
+}
+
+// ---------- Timestamps ----------
+
+/// Canonical timestamp layout: RFC3339 UTC with millisecond precision (`2025-01-17T20:00:00.000Z`).
+/// The layout is fixed-width, so lexicographic order of stored strings matches chronological order.
+const ISO8601_UTC_MILLIS: &[time::format_description::BorrowedFormatItem<'static>] = time::macros::format_description!(
+    "[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:3]Z"
+);
+
+/// Earliest and latest instants with a four-digit year, the range RFC3339 can write
+const ISO8601_UTC_RANGE: (time::OffsetDateTime, time::OffsetDateTime) = (
+    time::macros::datetime!(0000-01-01 0:00 UTC),
+    time::macros::datetime!(9999-12-31 23:59:59.999 UTC),
+);
+
+/// Format an instant in the canonical timestamp layout. Instants outside years 0000-9999 (far-off
+/// cutoffs or TTLs) are clamped to that range, so stored strings still sort chronologically.
+pub fn format_iso8601_utc(t: time::OffsetDateTime) -> String {
+    let (earliest, latest) = ISO8601_UTC_RANGE;
+    let t = t.to_offset(time::UtcOffset::UTC);
+    // Compared by year: `OffsetDateTime` ordering is not reliable across negative years
+    let t = match t.year() {
+        ..=-1 => earliest,
+        10000.. => latest,
+        _ => t,
+    };
+    t.format(ISO8601_UTC_MILLIS)
+        .expect("every instant in years 0000-9999 has a canonical form")
 }
 
 pub fn now_iso8601_utc() -> String {
-    let now = time::OffsetDateTime::now_utc();
-    now.format(&time::format_description::well_known::Rfc3339).unwrap()
+    format_iso8601_utc(time::OffsetDateTime::now_utc())
+}
+
+/// Canonical timestamp for `now - ago` (used for cleanup cutoffs)
+pub fn iso8601_utc_ago(ago: std::time::Duration) -> String {
+    // `saturating_sub` saturates `now - Duration::MAX` upwards, so out-of-range cutoffs go to the earliest instant here
+    let cutoff = time::Duration::try_from(ago).ok().and_then(|ago| time::OffsetDateTime::now_utc().checked_sub(ago));
+    format_iso8601_utc(cutoff.unwrap_or(ISO8601_UTC_RANGE.0))
+}
+
+/// Parse any RFC3339 timestamp into a UTC instant
+pub fn parse_iso8601_utc(s: &str) -> Result<time::OffsetDateTime, DbError> {
+    time::OffsetDateTime::parse(s.trim(), &time::format_description::well_known::Rfc3339)
+        .map(|t| t.to_offset(time::UtcOffset::UTC))
+        .map_err(|e| DbError::InvalidInput(format!("invalid timestamp '{}': {}", s, e)))
+}
+
+/// Validate an RFC3339 timestamp and rewrite it in the canonical layout
+pub fn normalize_iso8601_utc(s: &str) -> Result<String, DbError> {
+    parse_iso8601_utc(s).map(format_iso8601_utc)
 }
 
+/// Highest migration this binary knows how to apply
+pub const SCHEMA_VERSION: i64 = 8;
+
+/// Open the database, applying pending migrations; fails with `SchemaTooNew` when a newer binary migrated it
 pub fn open_or_create_db(path: &str) -> Result<Connection, DbError> {
+    open_db(path, false)
+}
+
+/// `open_or_create_db` that also accepts a schema newer than `SCHEMA_VERSION`, for read-only commands
+pub fn open_db_allow_newer_schema(path: &str) -> Result<Connection, DbError> {
+    open_db(path, true)
+}
+
+/// Open (or create) the database without applying migrations, for `db migrate`
+pub fn open_db_without_migrating(path: &str) -> Result<Connection, DbError> {
     let db_path = std::path::Path::new(path);
     if let Some(parent) = db_path.parent() { std::fs::create_dir_all(parent)?; }
     let conn = Connection::open(db_path)?;
-    // PRAGMAs
-    conn.pragma_update(None, "foreign_keys", &1i64)?;
-    conn.pragma_update(None, "journal_mode", &"WAL")?;
-    conn.pragma_update(None, "busy_timeout", &3000i64)?;
+    // PRAGMAs; busy_timeout first, so switching to WAL waits for a process that is migrating
+    conn.pragma_update(None, "busy_timeout", busy_timeout_ms() as i64)?;
+    conn.pragma_update(None, "foreign_keys", 1i64)?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    Ok(conn)
+}
+
+/// Open an existing database read-only, for commands that only list or describe: no migrations and
+/// no PRAGMA writes, so a running agent is never blocked. Fails with `SchemaTooOld` when migrations
+/// are pending and `SchemaTooNew` when a newer binary migrated it.
+pub fn open_readonly_db(path: &str) -> Result<Connection, DbError> {
+    open_readonly(path, false)
+}
+
+/// `open_readonly_db` that also accepts a schema newer than `SCHEMA_VERSION`
+pub fn open_readonly_db_allow_newer_schema(path: &str) -> Result<Connection, DbError> {
+    open_readonly(path, true)
+}
+
+/// `open_readonly_db` whatever the schema version, for dry runs that only read tables every version has
+pub fn open_readonly_db_any_schema(path: &str) -> Result<Connection, DbError> {
+    if !std::path::Path::new(path).exists() {
+        return Err(DbError::InvalidInput(format!("database not found: {} (run `multi-agents db init`)", path)));
+    }
+    let conn = Connection::open_with_flags(
+        path,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    // Connection-local settings only; nothing is written to the file
+    conn.busy_timeout(std::time::Duration::from_millis(busy_timeout_ms()))?;
+    Ok(conn)
+}
+
+fn open_readonly(path: &str, allow_newer: bool) -> Result<Connection, DbError> {
+    let conn = open_readonly_db_any_schema(path)?;
+    let db_version = db_schema_version(&conn)?.unwrap_or(0);
+    if db_version > SCHEMA_VERSION && !allow_newer {
+        return Err(DbError::SchemaTooNew { db_version, supported: SCHEMA_VERSION });
+    }
+    if db_version < SCHEMA_VERSION {
+        return Err(DbError::SchemaTooOld { db_version, required: SCHEMA_VERSION });
+    }
+    Ok(conn)
+}
+
+fn open_db(path: &str, allow_newer: bool) -> Result<Connection, DbError> {
+    let conn = open_db_without_migrating(path)?;
+    if let Some(db_version) = db_schema_version(&conn)? {
+        if db_version > SCHEMA_VERSION {
+            if allow_newer {
+                return Ok(conn);
+            }
+            return Err(DbError::SchemaTooNew { db_version, supported: SCHEMA_VERSION });
+        }
+    }
     apply_pending_migrations(&conn)?;
     Ok(conn)
 }
 
-fn apply_pending_migrations(conn: &Connection) -> Result<(), DbError> {
-    // migrations table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS migrations (version INTEGER PRIMARY KEY, applied_at TEXT NOT NULL)",
+/// Highest applied migration, `None` for a database that has never been migrated
+pub fn db_schema_version(conn: &Connection) -> Result<Option<i64>, DbError> {
+    let has_table: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'migrations')",
         [],
+        |r| r.get(0),
     )?;
-    // v1: initial schema
-    if !migration_applied(conn, 1)? {
-        apply_v1(conn)?;
-        record_migration(conn, 1)?;
+    if !has_table {
+        return Ok(None);
     }
-    // v2: extend sessions table for M3
-    if !migration_applied(conn, 2)? {
-        apply_v2(conn)?;
-        record_migration(conn, 2)?;
+    Ok(conn.query_row("SELECT MAX(version) FROM migrations", [], |r| r.get(0))?)
+}
+
+type Migration = fn(&Connection) -> Result<(), DbError>;
+
+/// Every migration in order; the last version is `SCHEMA_VERSION`
+const MIGRATIONS: &[(i64, Migration)] = &[
+    (1, apply_v1), // initial schema
+    (2, apply_v2), // extend sessions table for M3
+    (3, apply_v3), // add type column for REPL sessions (Issue #36)
+    (4, apply_v4), // normalize stored timestamps to the canonical layout
+    (5, apply_v5), // broadcasts table, one row per send
+    (6, apply_v6), // response_cache table for `send --cache`
+    (7, apply_v7), // subscriptions table for supervisor notifications
+    (8, apply_v8), // sessions.resume_count, bumped when a send continues a session
+];
+
+fn apply_pending_migrations(conn: &Connection) -> Result<(), DbError> {
+    migrate_to(conn, SCHEMA_VERSION).map(|_| ())
+}
+
+/// Highest applied migration, 0 for a database that has never been migrated
+pub fn current_migration_version(conn: &Connection) -> Result<i64, DbError> {
+    Ok(db_schema_version(conn)?.unwrap_or(0))
+}
+
+/// Known migrations not applied yet, in the order they would run
+pub fn pending_migrations(conn: &Connection) -> Result<Vec<i64>, DbError> {
+    // Read-only: the deferred transaction only gives a consistent view and is rolled back on drop
+    let tx = Transaction::new_unchecked(conn, TransactionBehavior::Deferred)?;
+    let migrated = db_schema_version(&tx)?.is_some();
+    let mut pending = Vec::new();
+    for (version, _) in MIGRATIONS {
+        if !migrated || !migration_applied(&tx, *version)? {
+            pending.push(*version);
+        }
     }
-    // v3: add type column for REPL sessions (Issue #36)
-    if !migration_applied(conn, 3)? {
-        apply_v3(conn)?;
-        record_migration(conn, 3)?;
+    Ok(pending)
+}
+
+/// Apply pending migrations up to and including `target`; returns the versions applied.
+/// Migrations only go forward: a target below the current version or above `SCHEMA_VERSION` is invalid input.
+///
+/// When a migration up to `target` is pending, everything runs in one `BEGIN EXCLUSIVE` transaction:
+/// a second process opening the same database waits (up to `busy_timeout`) and then finds the
+/// migrations already recorded, so each one runs once. A failing migration rolls back the whole batch.
+/// An up-to-date database is only read, so opening it never waits for other writers.
+pub fn migrate_to(conn: &Connection, target: i64) -> Result<Vec<i64>, DbError> {
+    if !(0..=SCHEMA_VERSION).contains(&target) {
+        return Err(DbError::InvalidInput(format!("unknown migration version {} (latest: {})", target, SCHEMA_VERSION)));
     }
-    Ok(())
+    if !pending_migrations(conn)?.iter().any(|v| *v <= target) {
+        let current = current_migration_version(conn)?;
+        if target < current {
+            return Err(DbError::InvalidInput(format!("cannot migrate down from v{} to v{}: downgrades are not supported", current, target)));
+        }
+        return Ok(Vec::new());
+    }
+    let tx = Transaction::new_unchecked(conn, TransactionBehavior::Exclusive)?;
+    // Read under the lock: another process may have migrated while this one waited
+    let current = current_migration_version(&tx)?;
+    if target < current {
+        return Err(DbError::InvalidInput(format!("cannot migrate down from v{} to v{}: downgrades are not supported", current, target)));
+    }
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS migrations (version INTEGER PRIMARY KEY, applied_at TEXT NOT NULL)",
+        [],
+    )?;
+    let mut applied = Vec::new();
+    for (version, apply) in MIGRATIONS.iter().filter(|(v, _)| *v <= target) {
+        if !migration_applied(&tx, *version)? {
+            apply(&tx)?;
+            record_migration(&tx, *version)?;
+            applied.push(*version);
+        }
+    }
+    tx.commit()?;
+    Ok(applied)
 }
 
-fn migration_applied(conn: &Connection, v: i64) -> Result<bool, DbError> {
-    let mut stmt = conn.prepare("SELECT 1 FROM migrations WHERE version = ?1 LIMIT 1")?;
+fn migration_applied(tx: &Transaction<'_>, v: i64) -> Result<bool, DbError> {
+    let mut stmt = tx.prepare("SELECT 1 FROM migrations WHERE version = ?1 LIMIT 1")?;
     let exists = stmt.exists(params![v])?;
     Ok(exists)
 }
 
-fn record_migration(conn: &Connection, v: i64) -> Result<(), DbError> {
-    conn.execute(
+fn record_migration(tx: &Transaction<'_>, v: i64) -> Result<(), DbError> {
+    tx.execute(
         "INSERT INTO migrations(version, applied_at) VALUES (?1, ?2)",
         params![v, now_iso8601_utc()],
     )?;
@@ -157,31 +388,194 @@ fn apply_v3(conn: &Connection) -> Result<(), DbError> {
     Ok(())
 }
 
+fn apply_v4(conn: &Connection) -> Result<(), DbError> {
+    // Rewrite parseable timestamps in the canonical layout so string comparisons stay chronological
+    for (table, column) in TIMESTAMP_COLUMNS {
+        let rows: Vec<(i64, String)> = {
+            let mut stmt = conn.prepare(&format!("SELECT rowid, {column} FROM {table} WHERE {column} IS NOT NULL"))?;
+            let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+        for (rowid, value) in rows {
+            if let Ok(canonical) = normalize_iso8601_utc(&value) {
+                if canonical != value {
+                    conn.execute(&format!("UPDATE {table} SET {column} = ?1 WHERE rowid = ?2"), params![canonical, rowid])?;
+                }
+            }
+        }
+    }
+    // Values that cannot be parsed are left untouched; `scan_malformed_timestamps` lists them
+    Ok(())
+}
+
+fn apply_v5(conn: &Connection) -> Result<(), DbError> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS broadcasts (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            message TEXT NOT NULL,
+            targets_json TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'in_progress',
+            created_at TEXT NOT NULL,
+            completed_at TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_broadcasts_project_created ON broadcasts(project_id, created_at);
+        "#,
+    )?;
+    Ok(())
+}
+
+fn apply_v6(conn: &Connection) -> Result<(), DbError> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS response_cache (
+            key TEXT PRIMARY KEY,
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            stdout TEXT NOT NULL,
+            exit_code INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL,
+            hits INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE INDEX IF NOT EXISTS idx_response_cache_expires ON response_cache(expires_at);
+        "#,
+    )?;
+    Ok(())
+}
+
+fn apply_v7(conn: &Connection) -> Result<(), DbError> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS subscriptions (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            supervisor_agent_id TEXT NOT NULL REFERENCES agents(id) ON DELETE CASCADE,
+            event_kind TEXT NOT NULL,
+            source_filter TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            UNIQUE(supervisor_agent_id, event_kind, source_filter)
+        );
+        CREATE INDEX IF NOT EXISTS idx_subscriptions_project_event ON subscriptions(project_id, event_kind);
+        "#,
+    )?;
+    Ok(())
+}
+
+fn apply_v8(conn: &Connection) -> Result<(), DbError> {
+    conn.execute_batch(
+        r#"
+        ALTER TABLE sessions ADD COLUMN resume_count INTEGER NOT NULL DEFAULT 0;
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Timestamp columns written by this crate (table, column)
+const TIMESTAMP_COLUMNS: &[(&str, &str)] = &[
+    ("projects", "created_at"),
+    ("agents", "created_at"),
+    ("sessions", "created_at"),
+    ("sessions", "last_activity"),
+    ("sessions", "expires_at"),
+    ("messages", "created_at"),
+    ("tasks", "created_at"),
+];
+
+/// A stored timestamp that is not valid RFC3339
+#[derive(Debug, Clone, PartialEq)]
+pub struct MalformedTimestamp {
+    pub table: String,
+    pub column: String,
+    pub rowid: i64,
+    pub value: String,
+}
+
+/// Report stored timestamps that cannot be parsed as RFC3339
+pub fn scan_malformed_timestamps(conn: &Connection) -> Result<Vec<MalformedTimestamp>, DbError> {
+    let mut malformed = Vec::new();
+    for (table, column) in TIMESTAMP_COLUMNS {
+        let mut stmt = conn.prepare(&format!("SELECT rowid, {column} FROM {table} WHERE {column} IS NOT NULL"))?;
+        let rows = stmt.query_map([], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (rowid, value) = row?;
+            if parse_iso8601_utc(&value).is_err() {
+                malformed.push(MalformedTimestamp { table: table.to_string(), column: column.to_string(), rowid, value });
+            }
+        }
+    }
+    Ok(malformed)
+}
+
 // ---------- Session Management Types ----------
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Session {
     pub id: String,
     pub project_id: String,
     pub agent_id: String,
     pub provider: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub provider_session_id: Option<String>,
     pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub last_activity: Option<String>,
     pub status: SessionStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub expires_at: Option<String>,
     pub session_type: SessionType,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl Session {
+    /// Whether `expires_at` is set and at or before `now`; no provider is asked. An unparseable
+    /// `expires_at` counts as not expired.
+    pub fn is_expired_by_time(&self, now: time::OffsetDateTime) -> bool {
+        self.expires_at.as_deref()
+            .and_then(|e| parse_iso8601_utc(e).ok())
+            .is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// What `expires_at` alone says about the session at `now`: expired, comfortably alive (more than
+    /// `slack` left), or undecided, in which case only the provider can tell
+    pub fn expiry_check(&self, now: time::OffsetDateTime, slack: std::time::Duration) -> ExpiryCheck {
+        if self.is_expired_by_time(now) {
+            return ExpiryCheck::Expired;
+        }
+        let expires_at = self.expires_at.as_deref().and_then(|e| parse_iso8601_utc(e).ok());
+        match expires_at {
+            Some(expires_at) if expires_at - now > slack => ExpiryCheck::Fresh,
+            _ => ExpiryCheck::NeedsPing,
+        }
+    }
+}
+
+/// Outcome of `Session::expiry_check`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpiryCheck {
+    /// At or past `expires_at`
+    Expired,
+    /// More than the slack before `expires_at`: valid without asking the provider
+    Fresh,
+    /// No (readable) `expires_at`, or within the slack of it
+    NeedsPing,
+}
+
+/// Time before `expires_at` within which session managers still ping the provider
+pub const DEFAULT_EXPIRY_SLACK: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SessionStatus {
     Active,
     Expired,
     Invalid,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SessionType {
     Chat,
     Repl,
@@ -189,12 +583,13 @@ pub enum SessionType {
 
 // ---------- Tasks Management Types ----------
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Task {
     pub id: String,
     pub project_id: String,
     pub title: String,
     pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub assignee_agent_id: Option<String>,
     pub created_at: String,
 }
@@ -266,6 +661,39 @@ pub enum SessionError {
     Sqlite(#[from] rusqlite::Error),
 }
 
+/// Column sessions can be sorted by. Column names cannot be bound as SQL parameters, so any
+/// ORDER BY built from user input must go through `as_sql_column`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SessionOrderBy {
+    #[default]
+    CreatedAt,
+    LastActivity,
+    ExpiresAt,
+    Status,
+    Provider,
+}
+
+impl SessionOrderBy {
+    pub const ALL: [SessionOrderBy; 5] = [
+        SessionOrderBy::CreatedAt,
+        SessionOrderBy::LastActivity,
+        SessionOrderBy::ExpiresAt,
+        SessionOrderBy::Status,
+        SessionOrderBy::Provider,
+    ];
+
+    /// Hardcoded column name for an ORDER BY clause
+    pub fn as_sql_column(&self) -> &'static str {
+        match self {
+            SessionOrderBy::CreatedAt => "created_at",
+            SessionOrderBy::LastActivity => "last_activity",
+            SessionOrderBy::ExpiresAt => "expires_at",
+            SessionOrderBy::Status => "status",
+            SessionOrderBy::Provider => "provider",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SessionFilters {
     pub project_id: Option<String>,
@@ -284,17 +712,48 @@ pub trait SessionManager {
     fn resume_session(&self, session_id: &str) -> Result<SessionContext, SessionError>;
     fn create_session(&self, project_id: &str, agent_id: &str, provider: &str, provider_session_id: Option<&str>) -> Result<Session, SessionError>;
     fn cleanup_expired_sessions(&self) -> Result<u32, SessionError>;
+    /// Sessions matching `filters`, newest first
+    fn list(&self, filters: SessionFilters) -> Result<Vec<Session>, SessionError>;
+    /// Delete one session and its messages; `false` when there was no such session
+    fn delete(&self, session_id: &str) -> Result<bool, SessionError>;
+}
+
+/// Validation decided from `expires_at` without the provider: past it the session is marked expired;
+/// well before it a session is valid when resumable. `None` leaves the decision to a provider ping.
+fn settle_by_expiry(conn: &Connection, session: &Session, slack: std::time::Duration) -> Result<Option<bool>, SessionError> {
+    match session.expiry_check(time::OffsetDateTime::now_utc(), slack) {
+        ExpiryCheck::Expired => {
+            update_session(conn, &session.id, None, None, Some(SessionStatus::Expired))?;
+            Ok(Some(false))
+        }
+        ExpiryCheck::Fresh => Ok(Some(session.provider_session_id.is_some())),
+        ExpiryCheck::NeedsPing => Ok(None),
+    }
 }
 
 // ---------- ClaudeSessionManager Implementation ----------
 
 pub struct ClaudeSessionManager {
     conn: Connection,
+    session_ttl: Option<std::time::Duration>,
+    expiry_slack: std::time::Duration,
 }
 
 impl ClaudeSessionManager {
     pub fn new(conn: Connection) -> Self {
-        Self { conn }
+        Self { conn, session_ttl: None, expiry_slack: DEFAULT_EXPIRY_SLACK }
+    }
+
+    /// Sessions created from now on expire `ttl` after creation
+    pub fn with_session_ttl(mut self, ttl: Option<std::time::Duration>) -> Self {
+        self.session_ttl = ttl;
+        self
+    }
+
+    /// Sessions with more than `slack` left before `expires_at` validate without a provider ping
+    pub fn with_expiry_slack(mut self, slack: std::time::Duration) -> Self {
+        self.expiry_slack = slack;
+        self
     }
     
     fn ping_claude_session(&self, session_id: &str) -> Result<bool, SessionError> {
@@ -325,6 +784,10 @@ impl SessionManager for ClaudeSessionManager {
             return Ok(false);
         }
         
+        if let Some(valid) = settle_by_expiry(&self.conn, &session, self.expiry_slack)? {
+            return Ok(valid);
+        }
+        
         // If we have a provider_session_id, ping Claude to validate
         if let Some(provider_session_id) = &session.provider_session_id {
             let is_valid = self.ping_claude_session(provider_session_id)?;
@@ -371,7 +834,7 @@ impl SessionManager for ClaudeSessionManager {
         }
         
         // Create session in database
-        let session = insert_session(&self.conn, project_id, agent_id, provider, provider_session_id)?;
+        let session = insert_session_with_ttl(&self.conn, project_id, agent_id, provider, provider_session_id, SessionType::Chat, self.session_ttl)?;
         
         // If we have a provider_session_id, validate it
         if let Some(provider_session_id) = &session.provider_session_id {
@@ -395,17 +858,39 @@ impl SessionManager for ClaudeSessionManager {
         
         Ok(expired_count as u32)
     }
+
+    fn list(&self, filters: SessionFilters) -> Result<Vec<Session>, SessionError> {
+        Ok(list_sessions(&self.conn, filters)?)
+    }
+
+    fn delete(&self, session_id: &str) -> Result<bool, SessionError> {
+        Ok(delete_session(&self.conn, session_id)?)
+    }
 }
 
 // ---------- CursorSessionManager Implementation ----------
 
 pub struct CursorSessionManager {
     conn: Connection,
+    session_ttl: Option<std::time::Duration>,
+    expiry_slack: std::time::Duration,
 }
 
 impl CursorSessionManager {
     pub fn new(conn: Connection) -> Self {
-        Self { conn }
+        Self { conn, session_ttl: None, expiry_slack: DEFAULT_EXPIRY_SLACK }
+    }
+
+    /// Sessions created from now on expire `ttl` after creation
+    pub fn with_session_ttl(mut self, ttl: Option<std::time::Duration>) -> Self {
+        self.session_ttl = ttl;
+        self
+    }
+
+    /// Sessions with more than `slack` left before `expires_at` validate without a provider ping
+    pub fn with_expiry_slack(mut self, slack: std::time::Duration) -> Self {
+        self.expiry_slack = slack;
+        self
     }
     
     fn ping_cursor_chat(&self, chat_id: &str) -> Result<bool, SessionError> {
@@ -451,6 +936,10 @@ impl SessionManager for CursorSessionManager {
             return Ok(false);
         }
         
+        if let Some(valid) = settle_by_expiry(&self.conn, &session, self.expiry_slack)? {
+            return Ok(valid);
+        }
+        
         // If we have a provider_session_id (chat_id), ping Cursor to validate
         if let Some(provider_session_id) = &session.provider_session_id {
             let is_valid = self.ping_cursor_chat(provider_session_id)?;
@@ -509,7 +998,7 @@ impl SessionManager for CursorSessionManager {
         };
         
         // Create session in database
-        let session = insert_session(&self.conn, project_id, agent_id, provider, Some(&chat_id))?;
+        let session = insert_session_with_ttl(&self.conn, project_id, agent_id, provider, Some(&chat_id), SessionType::Chat, self.session_ttl)?;
         
         Ok(session)
     }
@@ -524,17 +1013,39 @@ impl SessionManager for CursorSessionManager {
         
         Ok(expired_count as u32)
     }
+
+    fn list(&self, filters: SessionFilters) -> Result<Vec<Session>, SessionError> {
+        Ok(list_sessions(&self.conn, filters)?)
+    }
+
+    fn delete(&self, session_id: &str) -> Result<bool, SessionError> {
+        Ok(delete_session(&self.conn, session_id)?)
+    }
 }
 
 // ---------- GeminiSessionManager Implementation ----------
 
 pub struct GeminiSessionManager {
     conn: Connection,
+    session_ttl: Option<std::time::Duration>,
+    expiry_slack: std::time::Duration,
 }
 
 impl GeminiSessionManager {
     pub fn new(conn: Connection) -> Self {
-        Self { conn }
+        Self { conn, session_ttl: None, expiry_slack: DEFAULT_EXPIRY_SLACK }
+    }
+
+    /// Sessions created from now on expire `ttl` after creation
+    pub fn with_session_ttl(mut self, ttl: Option<std::time::Duration>) -> Self {
+        self.session_ttl = ttl;
+        self
+    }
+
+    /// Sessions with more than `slack` left before `expires_at` validate without a provider ping
+    pub fn with_expiry_slack(mut self, slack: std::time::Duration) -> Self {
+        self.expiry_slack = slack;
+        self
     }
     
     fn validate_gemini_context(&self, context_id: &str) -> Result<bool, SessionError> {
@@ -580,6 +1091,10 @@ impl SessionManager for GeminiSessionManager {
             return Ok(false);
         }
         
+        if let Some(valid) = settle_by_expiry(&self.conn, &session, self.expiry_slack)? {
+            return Ok(valid);
+        }
+        
         // If we have a provider_session_id (context_id), validate Gemini context
         if let Some(provider_session_id) = &session.provider_session_id {
             let is_valid = self.validate_gemini_context(provider_session_id)?;
@@ -638,7 +1153,7 @@ impl SessionManager for GeminiSessionManager {
         };
         
         // Create session in database
-        let session = insert_session(&self.conn, project_id, agent_id, provider, Some(&context_id))?;
+        let session = insert_session_with_ttl(&self.conn, project_id, agent_id, provider, Some(&context_id), SessionType::Chat, self.session_ttl)?;
         
         Ok(session)
     }
@@ -653,11 +1168,21 @@ impl SessionManager for GeminiSessionManager {
         
         Ok(expired_count as u32)
     }
+
+    fn list(&self, filters: SessionFilters) -> Result<Vec<Session>, SessionError> {
+        Ok(list_sessions(&self.conn, filters)?)
+    }
+
+    fn delete(&self, session_id: &str) -> Result<bool, SessionError> {
+        Ok(delete_session(&self.conn, session_id)?)
+    }
 }
 
 // ---------- Repositories ----------
 
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Project { pub id: String, pub name: String }
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Agent {
     pub id: String,
     pub project_id: String,
@@ -674,13 +1199,66 @@ pub enum IdOrName<'a> { Id(&'a str), Name(&'a str) }
 pub fn insert_project(conn: &Connection, name: &str) -> Result<Project, DbError> {
     if name.trim().is_empty() { return Err(DbError::InvalidInput("project name empty".into())); }
     let id = uuid();
-    conn.execute(
+    with_busy_retry(|| Ok(conn.execute(
         "INSERT INTO projects(id, name, created_at) VALUES (?1, ?2, ?3)",
         params![id, name, now_iso8601_utc()],
-    )?;
+    )?))?;
     Ok(Project { id, name: name.to_string() })
 }
 
+/// Rename a project; fails with `InvalidInput` if `from` is unknown or `to` is already taken
+pub fn rename_project(conn: &Connection, from: &str, to: &str) -> Result<(), DbError> {
+    if to.trim().is_empty() { return Err(DbError::InvalidInput("project name empty".into())); }
+    if find_project_id(conn, IdOrName::Name(to))?.is_some() {
+        return Err(DbError::InvalidInput(format!("project name already taken: {}", to)));
+    }
+    let n = conn.execute("UPDATE projects SET name = ?1 WHERE name = ?2", params![to, from])?;
+    if n == 0 { return Err(DbError::InvalidInput(format!("project not found: {}", from))); }
+    Ok(())
+}
+
+/// Row counts affected by removing a project
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeleteProjectResult {
+    pub projects: u32,
+    pub agents: u32,
+    pub sessions: u32,
+    pub messages: u32,
+    pub tasks: u32,
+    pub broadcasts: u32,
+}
+
+impl DeleteProjectResult {
+    /// Rows other than the project itself
+    pub fn dependents(&self) -> u32 {
+        self.agents + self.sessions + self.messages + self.tasks + self.broadcasts
+    }
+}
+
+/// Count the rows that `delete_project` would remove, without modifying anything
+pub fn count_project_dependents(conn: &Connection, project_id: &str) -> Result<DeleteProjectResult, DbError> {
+    let count = |sql: &str| -> Result<u32, DbError> {
+        Ok(conn.query_row(sql, params![project_id], |r| r.get::<_, i64>(0))? as u32)
+    };
+    Ok(DeleteProjectResult {
+        projects: count("SELECT COUNT(*) FROM projects WHERE id = ?1")?,
+        agents: count("SELECT COUNT(*) FROM agents WHERE project_id = ?1")?,
+        sessions: count("SELECT COUNT(*) FROM sessions WHERE project_id = ?1")?,
+        messages: count("SELECT COUNT(*) FROM messages WHERE session_id IN (SELECT id FROM sessions WHERE project_id = ?1)")?,
+        tasks: count("SELECT COUNT(*) FROM tasks WHERE project_id = ?1")?,
+        broadcasts: count("SELECT COUNT(*) FROM broadcasts WHERE project_id = ?1")?,
+    })
+}
+
+/// Delete a project; its agents, sessions, messages, tasks and broadcasts are removed by FK cascade
+pub fn delete_project(conn: &Connection, project_id: &str) -> Result<DeleteProjectResult, DbError> {
+    let tx = conn.unchecked_transaction()?;
+    let result = count_project_dependents(&tx, project_id)?;
+    tx.execute("DELETE FROM projects WHERE id = ?1", params![project_id])?;
+    tx.commit()?;
+    Ok(result)
+}
+
 pub fn find_project_id(conn: &Connection, by: IdOrName<'_>) -> Result<Option<String>, DbError> {
     let mut stmt = match by {
         IdOrName::Id(_) => conn.prepare("SELECT id FROM projects WHERE id=?1 LIMIT 1")?,
@@ -693,9 +1271,24 @@ pub fn find_project_id(conn: &Connection, by: IdOrName<'_>) -> Result<Option<Str
     Ok(id)
 }
 
+/// Ids of projects whose name contains `partial_name`, or is contained in it (`my-backend` for
+/// `my-backend-service`); ASCII case is ignored for the first form. Sorted by name.
+pub fn find_project_id_fuzzy(conn: &Connection, partial_name: &str) -> Result<Vec<String>, DbError> {
+    if partial_name.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let escaped = partial_name.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    let mut stmt = conn.prepare(
+        "SELECT id FROM projects WHERE name LIKE ?1 ESCAPE '\\' OR instr(?2, name) > 0 ORDER BY name"
+    )?;
+    let ids = stmt.query_map(params![format!("%{}%", escaped), partial_name], |r| r.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(ids)
+}
+
 pub fn to_json_text(values: &[String]) -> String { json!(values).to_string() }
 pub fn from_json_text(s: &str) -> Result<Vec<String>, DbError> {
-    let v: Vec<String> = serde_json::from_str(s).map_err(|e| DbError::InvalidInput(e.to_string()))?;
+    let v: Vec<String> = serde_json::from_str(s)?;
     Ok(v)
 }
 
@@ -718,13 +1311,31 @@ pub fn insert_session_with_type(
     provider: &str,
     provider_session_id: Option<&str>,
     session_type: SessionType,
+) -> Result<Session, DbError> {
+    insert_session_with_ttl(conn, project_id, agent_id, provider, provider_session_id, session_type, None)
+}
+
+/// Insert a session; with `ttl`, `expires_at` is `created_at + ttl` so `delete_expired_sessions` can reclaim it
+pub fn insert_session_with_ttl(
+    conn: &Connection,
+    project_id: &str,
+    agent_id: &str,
+    provider: &str,
+    provider_session_id: Option<&str>,
+    session_type: SessionType,
+    ttl: Option<std::time::Duration>,
 ) -> Result<Session, DbError> {
     let id = uuid();
-    let now = now_iso8601_utc();
-    conn.execute(
+    let created = time::OffsetDateTime::now_utc();
+    let now = format_iso8601_utc(created);
+    let expires_at = ttl.map(|ttl| {
+        let ttl = time::Duration::try_from(ttl).unwrap_or(time::Duration::MAX);
+        format_iso8601_utc(created.saturating_add(ttl))
+    });
+    with_busy_retry(|| Ok(conn.execute(
         "INSERT INTO sessions(id, project_id, agent_id, provider, provider_session_id, created_at, last_activity, status, metadata, expires_at, type) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
-        params![id, project_id, agent_id, provider, provider_session_id, now, now, "active", None::<String>, None::<String>, session_type.to_string()],
-    )?;
+        params![id, project_id, agent_id, provider, provider_session_id, now, now, "active", None::<String>, expires_at, session_type.to_string()],
+    )?))?;
     Ok(Session {
         id,
         project_id: project_id.to_string(),
@@ -735,7 +1346,7 @@ pub fn insert_session_with_type(
         last_activity: Some(now),
         status: SessionStatus::Active,
         metadata: None,
-        expires_at: None,
+        expires_at,
         session_type,
     })
 }
@@ -751,6 +1362,50 @@ pub fn insert_repl_session(
     insert_session_with_type(conn, project_id, agent_id, provider, provider_session_id, SessionType::Repl)
 }
 
+/// Record a session that could not be started: status `invalid`, with `metadata` (JSON) describing the failure
+pub fn insert_failed_session(
+    conn: &Connection,
+    project_id: &str,
+    agent_id: &str,
+    provider: &str,
+    metadata: &str,
+) -> Result<Session, DbError> {
+    serde_json::from_str::<serde_json::Value>(metadata)?;
+    let id = uuid();
+    let now = now_iso8601_utc();
+    with_busy_retry(|| Ok(conn.execute(
+        "INSERT INTO sessions(id, project_id, agent_id, provider, provider_session_id, created_at, last_activity, status, metadata, expires_at, type) VALUES (?1, ?2, ?3, ?4, NULL, ?5, ?6, ?7, ?8, NULL, ?9)",
+        params![id, project_id, agent_id, provider, now, now, "invalid", metadata, SessionType::Chat.to_string()],
+    )?))?;
+    Ok(Session {
+        id,
+        project_id: project_id.to_string(),
+        agent_id: agent_id.to_string(),
+        provider: provider.to_string(),
+        provider_session_id: None,
+        created_at: now.clone(),
+        last_activity: Some(now),
+        status: SessionStatus::Invalid,
+        metadata: Some(metadata.to_string()),
+        expires_at: None,
+        session_type: SessionType::Chat,
+    })
+}
+
+/// Replace the session's `metadata` (must be valid JSON)
+pub fn set_session_metadata(conn: &Connection, session_id: &str, metadata: &str) -> Result<(), DbError> {
+    serde_json::from_str::<serde_json::Value>(metadata)?;
+    with_busy_retry(|| Ok(conn.execute("UPDATE sessions SET metadata = ?1 WHERE id = ?2", params![metadata, session_id])?))?;
+    Ok(())
+}
+
+/// Value stored under `key` in the session's JSON `metadata`; `None` when there is no metadata or no such key
+pub fn get_metadata_value(session: &Session, key: &str) -> Result<Option<serde_json::Value>, DbError> {
+    let Some(raw) = session.metadata.as_deref() else { return Ok(None) };
+    let mut value: serde_json::Value = serde_json::from_str(raw)?;
+    Ok(value.get_mut(key).map(serde_json::Value::take))
+}
+
 pub fn find_session(conn: &Connection, session_id: &str) -> Result<Option<Session>, DbError> {
     let mut stmt = conn.prepare(
         "SELECT id, project_id, agent_id, provider, provider_session_id, created_at, last_activity, status, metadata, expires_at, type FROM sessions WHERE id = ?1"
@@ -812,7 +1467,7 @@ pub fn list_sessions(conn: &Connection, filters: SessionFilters) -> Result<Vec<S
         params.push(Box::new(session_type.to_string()));
     }
 
-    query.push_str(" ORDER BY created_at DESC");
+    query.push_str(&format!(" ORDER BY {} DESC", SessionOrderBy::default().as_sql_column()));
 
     if let Some(limit) = filters.limit {
         param_count += 1;
@@ -875,7 +1530,7 @@ pub fn update_session(
     if let Some(last_activity) = last_activity {
         param_count += 1;
         updates.push(format!("last_activity = ?{}", param_count));
-        params.push(Box::new(last_activity));
+        params.push(Box::new(normalize_iso8601_utc(last_activity)?));
     }
 
     if let Some(status) = status {
@@ -888,17 +1543,46 @@ pub fn update_session(
         return Ok(());
     }
 
-    query.push_str(" ");
+    query.push(' ');
     query.push_str(&updates.join(", "));
     param_count += 1;
     query.push_str(&format!(" WHERE id = ?{}", param_count));
     params.push(Box::new(session_id));
 
-    conn.execute(&query, rusqlite::params_from_iter(params))?;
+    with_busy_retry(|| Ok(conn.execute(&query, rusqlite::params_from_iter(&params))?))?;
+    Ok(())
+}
+
+/// Count one more continuation of a session; returns whether it exists
+pub fn record_session_resume(conn: &Connection, session_id: &str) -> Result<bool, DbError> {
+    let n = with_busy_retry(|| Ok(conn.execute(
+        "UPDATE sessions SET resume_count = resume_count + 1 WHERE id = ?1",
+        params![session_id],
+    )?))?;
+    Ok(n > 0)
+}
+
+/// Count one more continuation of every session of a project holding `provider_session_id`; returns how many
+pub fn record_provider_session_resume(conn: &Connection, project_id: &str, provider_session_id: &str) -> Result<u32, DbError> {
+    let n = with_busy_retry(|| Ok(conn.execute(
+        "UPDATE sessions SET resume_count = resume_count + 1 WHERE project_id = ?1 AND provider_session_id = ?2",
+        params![project_id, provider_session_id],
+    )?))?;
+    Ok(n as u32)
+}
+
+/// Set or clear the expiry timestamp of a session (value must be RFC3339)
+pub fn set_session_expires_at(conn: &Connection, session_id: &str, expires_at: Option<&str>) -> Result<(), DbError> {
+    let expires_at = expires_at.map(normalize_iso8601_utc).transpose()?;
+    with_busy_retry(|| Ok(conn.execute(
+        "UPDATE sessions SET expires_at = ?1 WHERE id = ?2",
+        params![expires_at, session_id],
+    )?))?;
     Ok(())
 }
 
 pub fn delete_expired_sessions(conn: &Connection, before_timestamp: &str) -> Result<u32, DbError> {
+    let before_timestamp = normalize_iso8601_utc(before_timestamp)?;
     let count = conn.execute(
         "DELETE FROM sessions WHERE expires_at IS NOT NULL AND expires_at < ?1",
         params![before_timestamp],
@@ -906,26 +1590,109 @@ pub fn delete_expired_sessions(conn: &Connection, before_timestamp: &str) -> Res
     Ok(count as u32)
 }
 
+/// Delete a session; its messages are removed by FK cascade. Returns whether it existed.
+pub fn delete_session(conn: &Connection, session_id: &str) -> Result<bool, DbError> {
+    let n = with_busy_retry(|| Ok(conn.execute("DELETE FROM sessions WHERE id = ?1", params![session_id])?))?;
+    Ok(n > 0)
+}
+
 /// Clean up REPL sessions older than 24 hours (Issue #36)
 pub fn cleanup_repl_sessions(conn: &Connection) -> Result<u32, DbError> {
-    let now = time::OffsetDateTime::now_utc();
     // Calculate 24 hours ago
-    let twenty_four_hours_ago = now
-        .saturating_sub(time::Duration::hours(24))
-        .format(&time::format_description::well_known::Rfc3339)
-        .map_err(|e| DbError::InvalidInput(e.to_string()))?;
-    
-    // Update REPL sessions older than 24 hours to inactive status
-    let count = conn.execute(
-        "UPDATE sessions SET status = 'expired' WHERE type = 'repl' AND (last_activity < ?1 OR created_at < ?1) AND status = 'active'",
-        params![twenty_four_hours_ago],
-    )?;
-    Ok(count as u32)
+    let twenty_four_hours_ago = iso8601_utc_ago(std::time::Duration::from_secs(24 * 60 * 60));
+    cleanup_repl_sessions_before(conn, &twenty_four_hours_ago)
 }
 
-pub fn insert_agent(
-    conn: &Connection,
-    project_id: &str,
+/// Whether `table` has a column named `column`; lets queries degrade on schemas that predate it
+pub fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool, DbError> {
+    let mut stmt = conn.prepare("SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2")?;
+    Ok(stmt.exists(params![table, column])?)
+}
+
+/// Whether the sessions table has the `type` column added by migration v3
+pub fn sessions_have_type_column(conn: &Connection) -> Result<bool, DbError> {
+    column_exists(conn, "sessions", "type")
+}
+
+/// SQL expression for a session's type: the real column, or `'chat'` on schemas older than v3
+fn session_type_sql(conn: &Connection) -> Result<&'static str, DbError> {
+    Ok(if sessions_have_type_column(conn)? { "type" } else { "'chat'" })
+}
+
+/// Active REPL sessions idle since before the cutoff (`?1`)
+const STALE_REPL_SESSIONS: &str = "(last_activity < ?1 OR created_at < ?1) AND status = 'active'";
+/// Chat sessions created and last used before the cutoff (`?1`)
+const EXPIRED_CHAT_SESSIONS: &str = "(last_activity IS NULL OR last_activity < ?1) AND created_at < ?1";
+
+/// Mark active REPL sessions idle since before `cutoff` (RFC3339) as expired
+pub fn cleanup_repl_sessions_before(conn: &Connection, cutoff: &str) -> Result<u32, DbError> {
+    let session_type = session_type_sql(conn)?;
+    let count = conn.execute(
+        &format!("UPDATE sessions SET status = 'expired' WHERE {session_type} = 'repl' AND {STALE_REPL_SESSIONS}"),
+        params![cutoff],
+    )?;
+    Ok(count as u32)
+}
+
+/// Active REPL sessions that `cleanup_repl_sessions_before` would expire
+pub fn list_stale_repl_sessions_before(conn: &Connection, cutoff: &str) -> Result<Vec<Session>, DbError> {
+    let session_type = session_type_sql(conn)?;
+    query_sessions(conn, &format!("{session_type} = 'repl' AND {STALE_REPL_SESSIONS}"), cutoff, session_type)
+}
+
+/// Chat sessions that `delete_expired_chat_sessions_before` would delete
+pub fn list_expired_chat_sessions_before(conn: &Connection, cutoff: &str) -> Result<Vec<Session>, DbError> {
+    let session_type = session_type_sql(conn)?;
+    query_sessions(conn, &format!("{session_type} = 'chat' AND {EXPIRED_CHAT_SESSIONS}"), cutoff, session_type)
+}
+
+/// Delete chat sessions not used since `cutoff` (RFC3339); every session counts as chat before migration v3
+pub fn delete_expired_chat_sessions_before(conn: &Connection, cutoff: &str) -> Result<u32, DbError> {
+    let session_type = session_type_sql(conn)?;
+    let count = conn.execute(
+        &format!("DELETE FROM sessions WHERE {session_type} = 'chat' AND {EXPIRED_CHAT_SESSIONS}"),
+        params![cutoff],
+    )?;
+    Ok(count as u32)
+}
+
+fn query_sessions(conn: &Connection, predicate: &str, cutoff: &str, session_type: &str) -> Result<Vec<Session>, DbError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, project_id, agent_id, provider, provider_session_id, created_at, last_activity, status, metadata, expires_at, {session_type} FROM sessions WHERE {predicate} ORDER BY created_at"
+    ))?;
+    let rows = stmt.query_map(params![cutoff], |row| {
+        let status_str: String = row.get(7)?;
+        let type_str: String = row.get(10)?;
+        Ok(Session {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            agent_id: row.get(2)?,
+            provider: row.get(3)?,
+            provider_session_id: row.get(4)?,
+            created_at: row.get(5)?,
+            last_activity: row.get(6)?,
+            status: status_str.parse().unwrap_or(SessionStatus::Invalid),
+            metadata: row.get(8)?,
+            expires_at: row.get(9)?,
+            session_type: type_str.parse().unwrap_or(SessionType::Chat),
+        })
+    })?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+const INSERT_AGENT_SQL: &str = "INSERT INTO agents(id, project_id, name, role, provider, model, allowed_tools_json, system_prompt, created_at) VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9)";
+
+fn check_agent_fields(name: &str, role: &str) -> Result<(), DbError> {
+    if name.trim().is_empty() { return Err(DbError::InvalidInput("agent name empty".into())); }
+    if role.trim().is_empty() { return Err(DbError::InvalidInput("agent role empty".into())); }
+    Ok(())
+}
+
+// One argument per column of `agents`
+#[allow(clippy::too_many_arguments)]
+pub fn insert_agent(
+    conn: &Connection,
+    project_id: &str,
     name: &str,
     role: &str,
     provider: &str,
@@ -933,17 +1700,781 @@ pub fn insert_agent(
     allowed_tools: &[String],
     system_prompt: &str,
 ) -> Result<Agent, DbError> {
-    if name.trim().is_empty() { return Err(DbError::InvalidInput("agent name empty".into())); }
-    if role.trim().is_empty() { return Err(DbError::InvalidInput("agent role empty".into())); }
+    check_agent_fields(name, role)?;
     let id = uuid();
     let tools = to_json_text(allowed_tools);
-    conn.execute(
-        "INSERT INTO agents(id, project_id, name, role, provider, model, allowed_tools_json, system_prompt, created_at) VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9)",
+    with_busy_retry(|| Ok(conn.execute(
+        INSERT_AGENT_SQL,
         params![id, project_id, name, role, provider, model, tools, system_prompt, now_iso8601_utc()],
-    )?;
+    )?))?;
     Ok(Agent { id, project_id: project_id.into(), name: name.into(), role: role.into(), provider: provider.into(), model: model.into(), allowed_tools: allowed_tools.to_vec(), system_prompt: system_prompt.into() })
 }
 
+/// Names of all projects, sorted
+pub fn list_project_names(conn: &Connection) -> Result<Vec<String>, DbError> {
+    let mut stmt = conn.prepare("SELECT name FROM projects ORDER BY name")?;
+    let rows = stmt.query_map([], |r| r.get(0))?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Names of the agents of a project, sorted
+pub fn list_agent_names(conn: &Connection, project_id: &str) -> Result<Vec<String>, DbError> {
+    let mut stmt = conn.prepare("SELECT name FROM agents WHERE project_id = ?1 ORDER BY name")?;
+    let rows = stmt.query_map(params![project_id], |r| r.get(0))?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// All agents of a project with their full definition, sorted by name
+pub fn list_agents(conn: &Connection, project_id: &str) -> Result<Vec<Agent>, DbError> {
+    let mut stmt = conn.prepare(
+        "SELECT id, project_id, name, role, provider, model, allowed_tools_json, system_prompt FROM agents WHERE project_id = ?1 ORDER BY name"
+    )?;
+    let rows = stmt.query_map(params![project_id], |r| {
+        Ok((
+            Agent {
+                id: r.get(0)?,
+                project_id: r.get(1)?,
+                name: r.get(2)?,
+                role: r.get(3)?,
+                provider: r.get(4)?,
+                model: r.get(5)?,
+                allowed_tools: Vec::new(),
+                system_prompt: r.get(7)?,
+            },
+            r.get::<_, String>(6)?,
+        ))
+    })?;
+    let mut agents = Vec::new();
+    for row in rows {
+        let (mut agent, tools_json) = row?;
+        agent.allowed_tools = from_json_text(&tools_json)?;
+        agents.push(agent);
+    }
+    Ok(agents)
+}
+
+pub fn find_agent_by_name(conn: &Connection, project_id: &str, name: &str) -> Result<Option<Agent>, DbError> {
+    let mut stmt = conn.prepare(
+        "SELECT id, project_id, name, role, provider, model, allowed_tools_json, system_prompt FROM agents WHERE project_id = ?1 AND name = ?2 LIMIT 1"
+    )?;
+    let row = stmt.query_row(params![project_id, name], |r| {
+        Ok((
+            Agent {
+                id: r.get(0)?,
+                project_id: r.get(1)?,
+                name: r.get(2)?,
+                role: r.get(3)?,
+                provider: r.get(4)?,
+                model: r.get(5)?,
+                allowed_tools: Vec::new(),
+                system_prompt: r.get(7)?,
+            },
+            r.get::<_, String>(6)?,
+        ))
+    }).optional()?;
+    match row {
+        Some((mut agent, tools_json)) => {
+            agent.allowed_tools = from_json_text(&tools_json)?;
+            Ok(Some(agent))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Row counts affected by removing an agent
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeleteAgentResult {
+    pub agents: u32,
+    pub sessions: u32,
+    pub messages: u32,
+    /// Tasks whose assignee was reset to NULL
+    pub tasks_unassigned: u32,
+}
+
+/// Count the rows that `delete_agent` would remove, without modifying anything
+pub fn count_agent_dependents(conn: &Connection, agent_id: &str) -> Result<DeleteAgentResult, DbError> {
+    let count = |sql: &str| -> Result<u32, DbError> {
+        Ok(conn.query_row(sql, params![agent_id], |r| r.get::<_, i64>(0))? as u32)
+    };
+    Ok(DeleteAgentResult {
+        agents: count("SELECT COUNT(*) FROM agents WHERE id = ?1")?,
+        sessions: count("SELECT COUNT(*) FROM sessions WHERE agent_id = ?1")?,
+        messages: count("SELECT COUNT(*) FROM messages WHERE session_id IN (SELECT id FROM sessions WHERE agent_id = ?1)")?,
+        tasks_unassigned: count("SELECT COUNT(*) FROM tasks WHERE assignee_agent_id = ?1")?,
+    })
+}
+
+/// Delete an agent; its sessions and messages are removed by FK cascade
+pub fn delete_agent(conn: &Connection, agent_id: &str) -> Result<DeleteAgentResult, DbError> {
+    let tx = conn.unchecked_transaction()?;
+    let result = count_agent_dependents(&tx, agent_id)?;
+    delete_agent_rows(&tx, agent_id)?;
+    tx.commit()?;
+    Ok(result)
+}
+
+/// `delete_agent` inside a transaction the caller already holds
+fn delete_agent_rows(conn: &Connection, agent_id: &str) -> Result<(), DbError> {
+    conn.execute("UPDATE tasks SET assignee_agent_id = NULL WHERE assignee_agent_id = ?1", params![agent_id])?;
+    conn.execute("DELETE FROM agents WHERE id = ?1", params![agent_id])?;
+    Ok(())
+}
+
+// ---------- Broadcasts ----------
+
+/// Where a broadcast stands; stored as `in_progress`, `completed`, `partial` or `failed`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BroadcastStatus {
+    InProgress,
+    Completed,
+    /// Some targets succeeded, others failed
+    #[serde(rename = "partial")]
+    PartialFailure,
+    Failed,
+}
+
+impl BroadcastStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BroadcastStatus::InProgress => "in_progress",
+            BroadcastStatus::Completed => "completed",
+            BroadcastStatus::PartialFailure => "partial",
+            BroadcastStatus::Failed => "failed",
+        }
+    }
+}
+
+impl std::fmt::Display for BroadcastStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for BroadcastStatus {
+    type Err = DbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "in_progress" => Ok(BroadcastStatus::InProgress),
+            "completed" => Ok(BroadcastStatus::Completed),
+            "partial" => Ok(BroadcastStatus::PartialFailure),
+            "failed" => Ok(BroadcastStatus::Failed),
+            _ => Err(DbError::InvalidInput(format!("Invalid broadcast status: {}", s))),
+        }
+    }
+}
+
+/// One `send` fan-out and the agents it targeted
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Broadcast {
+    pub id: String,
+    pub project_id: String,
+    pub message: String,
+    /// Target agent names, decoded from the `targets_json` column
+    pub targets_json: Vec<String>,
+    pub status: BroadcastStatus,
+    pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<String>,
+}
+
+const BROADCAST_COLUMNS: &str = "id, project_id, message, targets_json, status, created_at, completed_at";
+
+fn broadcast_from_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<(Broadcast, String, String)> {
+    Ok((
+        Broadcast {
+            id: r.get(0)?,
+            project_id: r.get(1)?,
+            message: r.get(2)?,
+            targets_json: Vec::new(),
+            status: BroadcastStatus::InProgress,
+            created_at: r.get(5)?,
+            completed_at: r.get(6)?,
+        },
+        r.get(3)?,
+        r.get(4)?,
+    ))
+}
+
+fn decode_broadcast((mut b, targets_json, status): (Broadcast, String, String)) -> Result<Broadcast, DbError> {
+    b.targets_json = from_json_text(&targets_json)?;
+    b.status = status.parse()?;
+    Ok(b)
+}
+
+/// Record a broadcast as `in_progress`
+pub fn insert_broadcast(conn: &Connection, project_id: &str, message: &str, targets: &[String]) -> Result<Broadcast, DbError> {
+    let b = Broadcast {
+        id: uuid(),
+        project_id: project_id.to_string(),
+        message: message.to_string(),
+        targets_json: targets.to_vec(),
+        status: BroadcastStatus::InProgress,
+        created_at: now_iso8601_utc(),
+        completed_at: None,
+    };
+    with_busy_retry(|| Ok(conn.execute(
+        "INSERT INTO broadcasts(id, project_id, message, targets_json, status, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![b.id, b.project_id, b.message, to_json_text(&b.targets_json), b.status.as_str(), b.created_at],
+    )?))?;
+    Ok(b)
+}
+
+/// Set a broadcast's status and `completed_at` (RFC3339, or `None` to clear it)
+pub fn update_broadcast_status(conn: &Connection, id: &str, status: BroadcastStatus, completed_at: Option<&str>) -> Result<(), DbError> {
+    let completed_at = completed_at.map(normalize_iso8601_utc).transpose()?;
+    let n = with_busy_retry(|| Ok(conn.execute(
+        "UPDATE broadcasts SET status = ?1, completed_at = ?2 WHERE id = ?3",
+        params![status.as_str(), completed_at, id],
+    )?))?;
+    if n == 0 { return Err(DbError::InvalidInput(format!("broadcast not found: {}", id))); }
+    Ok(())
+}
+
+/// Most recent broadcasts of a project, newest first, optionally only those with `status_filter`
+pub fn list_broadcasts(conn: &Connection, project_id: &str, status_filter: Option<BroadcastStatus>, limit: u32) -> Result<Vec<Broadcast>, DbError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {BROADCAST_COLUMNS} FROM broadcasts WHERE project_id = ?1 AND (?2 IS NULL OR status = ?2) ORDER BY created_at DESC, rowid DESC LIMIT ?3"
+    ))?;
+    let rows = stmt.query_map(params![project_id, status_filter.map(BroadcastStatus::as_str), limit], broadcast_from_row)?;
+    rows.map(|r| decode_broadcast(r?)).collect()
+}
+
+pub fn find_broadcast(conn: &Connection, id: &str) -> Result<Option<Broadcast>, DbError> {
+    let row = conn.query_row(
+        &format!("SELECT {BROADCAST_COLUMNS} FROM broadcasts WHERE id = ?1"),
+        params![id],
+        broadcast_from_row,
+    ).optional()?;
+    row.map(decode_broadcast).transpose()
+}
+
+// ---------- Messages, tasks and subscriptions ----------
+
+/// `messages.sender` of a prompt that `send` delivered to an agent
+pub const USER_SENDER: &str = "user";
+/// `messages.sender` of an agent's reply to a prompt
+pub const AGENT_SENDER: &str = "agent";
+
+/// Record a message in a session; returns its id
+pub fn insert_message(conn: &Connection, session_id: &str, sender: &str, content: &str, broadcast_id: Option<&str>) -> Result<String, DbError> {
+    let id = uuid();
+    with_busy_retry(|| Ok(conn.execute(
+        "INSERT INTO messages(id, session_id, sender, content, broadcast_id, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![id, session_id, sender, content, broadcast_id, now_iso8601_utc()],
+    )?))?;
+    Ok(id)
+}
+
+/// Set a task's status and return the updated task, or `None` when no task has that id
+pub fn update_task_status(conn: &Connection, task_id: &str, status: &str) -> Result<Option<Task>, DbError> {
+    if status.trim().is_empty() { return Err(DbError::InvalidInput("task status empty".into())); }
+    let n = with_busy_retry(|| Ok(conn.execute("UPDATE tasks SET status = ?1 WHERE id = ?2", params![status, task_id])?))?;
+    if n == 0 { return Ok(None); }
+    let task = conn.query_row(
+        "SELECT id, project_id, title, status, assignee_agent_id, created_at FROM tasks WHERE id = ?1",
+        params![task_id],
+        |r| Ok(Task {
+            id: r.get(0)?,
+            project_id: r.get(1)?,
+            title: r.get(2)?,
+            status: r.get(3)?,
+            assignee_agent_id: r.get(4)?,
+            created_at: r.get(5)?,
+        }),
+    )?;
+    Ok(Some(task))
+}
+
+/// A supervisor agent's request to be told about one kind of event from the agents a filter matches
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Subscription {
+    pub id: String,
+    pub project_id: String,
+    pub supervisor_agent_id: String,
+    /// `send_completed`, `send_failed` or `task_done`
+    pub event_kind: String,
+    /// `@all`, `@<role>` or an agent name
+    pub source_filter: String,
+    pub created_at: String,
+}
+
+const SUBSCRIPTION_COLUMNS: &str = "id, project_id, supervisor_agent_id, event_kind, source_filter, created_at";
+
+fn subscription_from_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<Subscription> {
+    Ok(Subscription {
+        id: r.get(0)?,
+        project_id: r.get(1)?,
+        supervisor_agent_id: r.get(2)?,
+        event_kind: r.get(3)?,
+        source_filter: r.get(4)?,
+        created_at: r.get(5)?,
+    })
+}
+
+/// Subscribe a supervisor to an event kind from a source filter. Subscribing twice is a no-op that
+/// returns the existing row.
+pub fn insert_subscription(
+    conn: &Connection,
+    project_id: &str,
+    supervisor_agent_id: &str,
+    event_kind: &str,
+    source_filter: &str,
+) -> Result<Subscription, DbError> {
+    with_busy_retry(|| Ok(conn.execute(
+        "INSERT INTO subscriptions(id, project_id, supervisor_agent_id, event_kind, source_filter, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6) ON CONFLICT(supervisor_agent_id, event_kind, source_filter) DO NOTHING",
+        params![uuid(), project_id, supervisor_agent_id, event_kind, source_filter, now_iso8601_utc()],
+    )?))?;
+    Ok(conn.query_row(
+        &format!("SELECT {SUBSCRIPTION_COLUMNS} FROM subscriptions WHERE supervisor_agent_id = ?1 AND event_kind = ?2 AND source_filter = ?3"),
+        params![supervisor_agent_id, event_kind, source_filter],
+        subscription_from_row,
+    )?)
+}
+
+/// Delete a supervisor's subscriptions, optionally only those of one event kind and/or source filter;
+/// returns how many were removed
+pub fn delete_subscriptions(
+    conn: &Connection,
+    supervisor_agent_id: &str,
+    event_kind: Option<&str>,
+    source_filter: Option<&str>,
+) -> Result<u32, DbError> {
+    let n = with_busy_retry(|| Ok(conn.execute(
+        "DELETE FROM subscriptions WHERE supervisor_agent_id = ?1
+           AND (?2 IS NULL OR event_kind = ?2) AND (?3 IS NULL OR source_filter = ?3)",
+        params![supervisor_agent_id, event_kind, source_filter],
+    )?))?;
+    Ok(n as u32)
+}
+
+/// Subscriptions of a project, oldest first
+pub fn list_subscriptions(conn: &Connection, project_id: &str) -> Result<Vec<Subscription>, DbError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {SUBSCRIPTION_COLUMNS} FROM subscriptions WHERE project_id = ?1 ORDER BY created_at, rowid"
+    ))?;
+    let rows = stmt.query_map(params![project_id], subscription_from_row)?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+// ---------- Response cache ----------
+
+/// Provider output stored by `send --cache`, keyed by a hash of everything that shapes the answer
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedResponse {
+    pub key: String,
+    pub provider: String,
+    pub model: String,
+    pub stdout: String,
+    pub exit_code: i32,
+    pub created_at: String,
+    pub expires_at: String,
+    pub hits: u64,
+}
+
+/// Store (or replace) the response for `key`, valid for `ttl` from now
+pub fn put_cached_response(
+    conn: &Connection,
+    key: &str,
+    provider: &str,
+    model: &str,
+    stdout: &str,
+    exit_code: i32,
+    ttl: std::time::Duration,
+) -> Result<(), DbError> {
+    if key.trim().is_empty() { return Err(DbError::InvalidInput("cache key empty".into())); }
+    let now = time::OffsetDateTime::now_utc();
+    let expires_at = now.checked_add(time::Duration::try_from(ttl).unwrap_or(time::Duration::MAX))
+        .unwrap_or(now);
+    with_busy_retry(|| Ok(conn.execute(
+        "INSERT OR REPLACE INTO response_cache(key, provider, model, stdout, exit_code, created_at, expires_at, hits) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0)",
+        params![key, provider, model, stdout, exit_code, format_iso8601_utc(now), format_iso8601_utc(expires_at)],
+    )?))?;
+    Ok(())
+}
+
+/// Unexpired response for `key`; a hit bumps the entry's hit counter
+pub fn get_cached_response(conn: &Connection, key: &str) -> Result<Option<CachedResponse>, DbError> {
+    let now = now_iso8601_utc();
+    let row = conn.query_row(
+        "SELECT key, provider, model, stdout, exit_code, created_at, expires_at, hits FROM response_cache \
+         WHERE key = ?1 AND expires_at > ?2",
+        params![key, now],
+        |r| Ok(CachedResponse {
+            key: r.get(0)?,
+            provider: r.get(1)?,
+            model: r.get(2)?,
+            stdout: r.get(3)?,
+            exit_code: r.get(4)?,
+            created_at: r.get(5)?,
+            expires_at: r.get(6)?,
+            hits: r.get::<_, i64>(7)? as u64 + 1,
+        }),
+    ).optional()?;
+    if row.is_some() {
+        conn.execute("UPDATE response_cache SET hits = hits + 1 WHERE key = ?1", params![key])?;
+    }
+    Ok(row)
+}
+
+/// Delete cached responses: every entry, or with `expired_only` those past their TTL. Returns the count removed.
+pub fn clear_response_cache(conn: &Connection, expired_only: bool) -> Result<u32, DbError> {
+    let n = if expired_only {
+        conn.execute("DELETE FROM response_cache WHERE expires_at <= ?1", params![now_iso8601_utc()])?
+    } else {
+        conn.execute("DELETE FROM response_cache", [])?
+    };
+    Ok(n as u32)
+}
+
+/// Size and usage of the response cache
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct ResponseCacheStats {
+    pub entries: u64,
+    pub expired: u64,
+    pub hits: u64,
+    /// Stored stdout, in bytes
+    pub bytes: u64,
+}
+
+pub fn response_cache_stats(conn: &Connection) -> Result<ResponseCacheStats, DbError> {
+    let stats = conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(expires_at <= ?1), 0), COALESCE(SUM(hits), 0), COALESCE(SUM(LENGTH(CAST(stdout AS BLOB))), 0) \
+         FROM response_cache",
+        params![now_iso8601_utc()],
+        |r| Ok(ResponseCacheStats {
+            entries: r.get::<_, i64>(0)? as u64,
+            expired: r.get::<_, i64>(1)? as u64,
+            hits: r.get::<_, i64>(2)? as u64,
+            bytes: r.get::<_, i64>(3)? as u64,
+        }),
+    )?;
+    Ok(stats)
+}
+
+// ---------- Consistency checks ----------
+
+/// Agent whose provider is not declared in providers.yaml
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct InvalidProviderAgent {
+    pub agent_id: String,
+    pub project_id: String,
+    pub name: String,
+    pub provider: String,
+}
+
+/// (project_id, name) pair held by more than one agent (DBs created before the UNIQUE index)
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct DuplicateAgentName {
+    pub project_id: String,
+    pub name: String,
+    pub count: u32,
+}
+
+/// Result of `check_db_health`
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct DbHealthReport {
+    /// Lines returned by `PRAGMA integrity_check` other than `ok`
+    pub integrity_errors: Vec<String>,
+    /// Rows returned by `PRAGMA foreign_key_check`
+    pub foreign_key_violations: u32,
+    /// Sessions whose project or agent no longer exists
+    pub orphaned_sessions: u32,
+    /// Messages whose session no longer exists
+    pub orphaned_messages: u32,
+    /// Tasks whose project no longer exists
+    pub orphaned_tasks: u32,
+    /// Tasks assigned to an agent that no longer exists
+    pub dangling_task_assignees: u32,
+    /// Empty when no provider list was supplied
+    pub invalid_provider_agents: Vec<InvalidProviderAgent>,
+    pub duplicate_agent_names: Vec<DuplicateAgentName>,
+}
+
+impl DbHealthReport {
+    /// Problems that `fix_orphans` repairs
+    pub fn fixable_problems(&self) -> u32 {
+        self.orphaned_sessions + self.orphaned_messages + self.orphaned_tasks + self.dangling_task_assignees
+    }
+
+    pub fn problem_count(&self) -> u32 {
+        self.integrity_errors.len() as u32
+            + self.foreign_key_violations
+            + self.fixable_problems()
+            + self.invalid_provider_agents.len() as u32
+            + self.duplicate_agent_names.len() as u32
+    }
+}
+
+const ORPHANED_SESSIONS: &str = "FROM sessions WHERE project_id NOT IN (SELECT id FROM projects) OR agent_id NOT IN (SELECT id FROM agents)";
+const ORPHANED_MESSAGES: &str = "FROM messages WHERE session_id NOT IN (SELECT id FROM sessions)";
+const ORPHANED_TASKS: &str = "FROM tasks WHERE project_id NOT IN (SELECT id FROM projects)";
+const DANGLING_ASSIGNEES: &str = "FROM tasks WHERE assignee_agent_id IS NOT NULL AND assignee_agent_id NOT IN (SELECT id FROM agents)";
+
+/// Run integrity and consistency checks; `known_providers` enables the provider check
+pub fn check_db_health(conn: &Connection, known_providers: Option<&[String]>) -> Result<DbHealthReport, DbError> {
+    let count = |from: &str| -> Result<u32, DbError> {
+        Ok(conn.query_row(&format!("SELECT COUNT(*) {from}"), [], |r| r.get::<_, i64>(0))? as u32)
+    };
+    let mut report = DbHealthReport::default();
+    {
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        let rows = stmt.query_map([], |r| r.get::<_, String>(0))?;
+        report.integrity_errors = rows.collect::<Result<Vec<_>, _>>()?.into_iter().filter(|l| l != "ok").collect();
+    }
+    {
+        let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+        let mut rows = stmt.query([])?;
+        while rows.next()?.is_some() { report.foreign_key_violations += 1; }
+    }
+    report.orphaned_sessions = count(ORPHANED_SESSIONS)?;
+    report.orphaned_messages = count(ORPHANED_MESSAGES)?;
+    report.orphaned_tasks = count(ORPHANED_TASKS)?;
+    report.dangling_task_assignees = count(DANGLING_ASSIGNEES)?;
+    if let Some(known) = known_providers {
+        let mut stmt = conn.prepare("SELECT id, project_id, name, provider FROM agents ORDER BY project_id, name")?;
+        let rows = stmt.query_map([], |r| Ok(InvalidProviderAgent { agent_id: r.get(0)?, project_id: r.get(1)?, name: r.get(2)?, provider: r.get(3)? }))?;
+        for agent in rows {
+            let agent = agent?;
+            if !known.contains(&agent.provider) { report.invalid_provider_agents.push(agent); }
+        }
+    }
+    {
+        let mut stmt = conn.prepare("SELECT project_id, name, COUNT(*) FROM agents GROUP BY project_id, name HAVING COUNT(*) > 1 ORDER BY project_id, name")?;
+        let rows = stmt.query_map([], |r| Ok(DuplicateAgentName { project_id: r.get(0)?, name: r.get(1)?, count: r.get::<_, i64>(2)? as u32 }))?;
+        report.duplicate_agent_names = rows.collect::<Result<Vec<_>, _>>()?;
+    }
+    Ok(report)
+}
+
+/// Rows changed by `fix_orphans`
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct OrphanFixResult {
+    pub sessions_deleted: u32,
+    pub messages_deleted: u32,
+    pub tasks_deleted: u32,
+    pub task_assignees_cleared: u32,
+}
+
+/// Delete orphaned sessions, messages and tasks and clear dangling task assignees in one transaction
+pub fn fix_orphans(conn: &Connection) -> Result<OrphanFixResult, DbError> {
+    let tx = conn.unchecked_transaction()?;
+    // Messages first (including those of orphaned sessions) so the FK cascade does not hide them from the count
+    let messages_deleted = tx.execute(
+        &format!("DELETE {ORPHANED_MESSAGES} OR session_id IN (SELECT id {ORPHANED_SESSIONS})"),
+        [],
+    )? as u32;
+    let sessions_deleted = tx.execute(&format!("DELETE {ORPHANED_SESSIONS}"), [])? as u32;
+    let tasks_deleted = tx.execute(&format!("DELETE {ORPHANED_TASKS}"), [])? as u32;
+    let task_assignees_cleared = tx.execute(&format!("UPDATE tasks SET assignee_agent_id = NULL WHERE id IN (SELECT id {DANGLING_ASSIGNEES})"), [])? as u32;
+    tx.commit()?;
+    Ok(OrphanFixResult { sessions_deleted, messages_deleted, tasks_deleted, task_assignees_cleared })
+}
+
+// ---------- Project statistics ----------
+
+/// Aggregate activity of a project since a cutoff.
+///
+/// A send is a row of `broadcasts`; `broadcasts` counts the sends that targeted more than one
+/// agent. Each user message is a delivery to one agent, which succeeded if the agent replied in
+/// the same session before the next user message.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct ProjectStats {
+    pub project_id: String,
+    pub since: String,
+    pub total_sends: u32,
+    pub broadcasts: u32,
+    pub successful_agents: u32,
+    pub failed_agents: u32,
+    pub avg_response_ms: Option<f64>,
+    /// Providers do not report usage; estimated as message characters / 4
+    pub tokens_estimated: u64,
+    pub tasks_total: u32,
+    pub tasks_done: u32,
+    pub task_completion_rate: f64,
+    pub active_sessions: u32,
+}
+
+/// Per-day slice of `ProjectStats` delivery counters (`day` is `YYYY-MM-DD`, UTC)
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct DailyStats {
+    pub day: String,
+    pub sends: u32,
+    pub successful_agents: u32,
+    pub failed_agents: u32,
+    pub avg_response_ms: Option<f64>,
+}
+
+/// User messages of a project since ?2, each with the first agent reply that precedes the next user message
+const DELIVERIES_CTE: &str = r#"
+    WITH deliveries AS (
+        SELECT m.id, m.broadcast_id, m.created_at,
+            (SELECT MIN(r.created_at) FROM messages r
+             WHERE r.session_id = m.session_id AND r.sender = 'agent' AND r.created_at >= m.created_at
+               AND r.created_at < COALESCE((SELECT MIN(n.created_at) FROM messages n
+                    WHERE n.session_id = m.session_id AND n.sender = 'user' AND n.created_at > m.created_at), '9999')
+            ) AS replied_at
+        FROM messages m JOIN sessions s ON s.id = m.session_id
+        WHERE s.project_id = ?1 AND m.sender = 'user' AND m.created_at >= ?2
+    )
+"#;
+
+pub fn get_project_stats(conn: &Connection, project_id: &str, since: time::OffsetDateTime) -> Result<ProjectStats, DbError> {
+    let since = format_iso8601_utc(since);
+    let mut stats = ProjectStats { project_id: project_id.to_string(), since: since.clone(), ..Default::default() };
+
+    conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(json_array_length(targets_json) > 1), 0) FROM broadcasts
+         WHERE project_id = ?1 AND created_at >= ?2",
+        params![project_id, since],
+        |r| {
+            stats.total_sends = r.get::<_, i64>(0)? as u32;
+            stats.broadcasts = r.get::<_, i64>(1)? as u32;
+            Ok(())
+        },
+    )?;
+    conn.query_row(
+        &format!(
+            "{DELIVERIES_CTE} SELECT COALESCE(SUM(replied_at IS NOT NULL), 0), COALESCE(SUM(replied_at IS NULL), 0),
+                AVG((julianday(replied_at) - julianday(created_at)) * 86400000.0)
+             FROM deliveries"
+        ),
+        params![project_id, since],
+        |r| {
+            stats.successful_agents = r.get::<_, i64>(0)? as u32;
+            stats.failed_agents = r.get::<_, i64>(1)? as u32;
+            stats.avg_response_ms = r.get(2)?;
+            Ok(())
+        },
+    )?;
+    let chars: i64 = conn.query_row(
+        "SELECT COALESCE(SUM(LENGTH(m.content)), 0) FROM messages m JOIN sessions s ON s.id = m.session_id
+         WHERE s.project_id = ?1 AND m.created_at >= ?2",
+        params![project_id, since],
+        |r| r.get(0),
+    )?;
+    stats.tokens_estimated = (chars as u64).div_ceil(4);
+    let (tasks_total, tasks_done): (i64, i64) = conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(status = 'done'), 0) FROM tasks WHERE project_id = ?1 AND created_at >= ?2",
+        params![project_id, since],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    )?;
+    stats.tasks_total = tasks_total as u32;
+    stats.tasks_done = tasks_done as u32;
+    stats.task_completion_rate = if tasks_total > 0 { tasks_done as f64 / tasks_total as f64 } else { 0.0 };
+    stats.active_sessions = conn.query_row(
+        "SELECT COUNT(*) FROM sessions WHERE project_id = ?1 AND status = 'active' AND COALESCE(last_activity, created_at) >= ?2",
+        params![project_id, since],
+        |r| r.get::<_, i64>(0),
+    )? as u32;
+    Ok(stats)
+}
+
+/// Send and delivery counters grouped by UTC day, oldest first; days without activity are omitted
+pub fn weekly_summary(conn: &Connection, project_id: &str, since: time::OffsetDateTime) -> Result<Vec<DailyStats>, DbError> {
+    let mut stmt = conn.prepare(&format!(
+        "{DELIVERIES_CTE},
+         sends AS (
+            SELECT substr(created_at, 1, 10) AS day, COUNT(*) AS n FROM broadcasts
+            WHERE project_id = ?1 AND created_at >= ?2 GROUP BY day
+         ),
+         daily AS (
+            SELECT substr(created_at, 1, 10) AS day, SUM(replied_at IS NOT NULL) AS ok, SUM(replied_at IS NULL) AS failed,
+                AVG((julianday(replied_at) - julianday(created_at)) * 86400000.0) AS avg_ms
+            FROM deliveries GROUP BY day
+         )
+         SELECT d.day, COALESCE(s.n, 0), COALESCE(y.ok, 0), COALESCE(y.failed, 0), y.avg_ms
+         FROM (SELECT day FROM sends UNION SELECT day FROM daily) d
+         LEFT JOIN sends s ON s.day = d.day LEFT JOIN daily y ON y.day = d.day
+         ORDER BY d.day"
+    ))?;
+    let rows = stmt.query_map(params![project_id, format_iso8601_utc(since)], |r| {
+        Ok(DailyStats {
+            day: r.get(0)?,
+            sends: r.get::<_, i64>(1)? as u32,
+            successful_agents: r.get::<_, i64>(2)? as u32,
+            failed_agents: r.get::<_, i64>(3)? as u32,
+            avg_response_ms: r.get(4)?,
+        })
+    })?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+// ---------- Session statistics ----------
+
+/// Sessions of a project created since a cutoff.
+///
+/// Lifetime runs from `created_at` to `last_activity` (sessions never used again are left out of
+/// the average). A session counts as resumed when a send continued it at least once
+/// (`resume_count`).
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct SessionStats {
+    pub project_id: String,
+    pub since: String,
+    pub total_sessions: u32,
+    pub by_provider: Vec<ProviderSessionCount>,
+    pub avg_lifetime_ms: Option<f64>,
+    pub resumed_sessions: u32,
+    pub by_agent: Vec<AgentSessionCount>,
+}
+
+/// Sessions of one provider in one status
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct ProviderSessionCount {
+    pub provider: String,
+    pub status: String,
+    pub sessions: u32,
+}
+
+/// Sessions of one agent, with how many of them expired
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct AgentSessionCount {
+    pub agent: String,
+    pub sessions: u32,
+    pub expired: u32,
+}
+
+pub fn get_session_stats(conn: &Connection, project_id: &str, since: time::OffsetDateTime) -> Result<SessionStats, DbError> {
+    let since = format_iso8601_utc(since);
+    let mut stats = SessionStats { project_id: project_id.to_string(), since: since.clone(), ..Default::default() };
+
+    conn.query_row(
+        "SELECT COUNT(*),
+            AVG(CASE WHEN last_activity IS NOT NULL THEN (julianday(last_activity) - julianday(created_at)) * 86400000.0 END),
+            COALESCE(SUM(resume_count > 0), 0)
+         FROM sessions WHERE project_id = ?1 AND created_at >= ?2",
+        params![project_id, since],
+        |r| {
+            stats.total_sessions = r.get::<_, i64>(0)? as u32;
+            stats.avg_lifetime_ms = r.get(1)?;
+            stats.resumed_sessions = r.get::<_, i64>(2)? as u32;
+            Ok(())
+        },
+    )?;
+
+    let mut stmt = conn.prepare(
+        "SELECT provider, COALESCE(status, 'active') AS st, COUNT(*) FROM sessions
+         WHERE project_id = ?1 AND created_at >= ?2 GROUP BY provider, st ORDER BY provider, st",
+    )?;
+    let rows = stmt.query_map(params![project_id, since], |r| {
+        Ok(ProviderSessionCount { provider: r.get(0)?, status: r.get(1)?, sessions: r.get::<_, i64>(2)? as u32 })
+    })?;
+    stats.by_provider = rows.collect::<Result<Vec<_>, _>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT a.name, COUNT(*), COALESCE(SUM(s.status = 'expired'), 0) FROM sessions s JOIN agents a ON a.id = s.agent_id
+         WHERE s.project_id = ?1 AND s.created_at >= ?2 GROUP BY a.name ORDER BY COUNT(*) DESC, a.name",
+    )?;
+    let rows = stmt.query_map(params![project_id, since], |r| {
+        Ok(AgentSessionCount { agent: r.get(0)?, sessions: r.get::<_, i64>(1)? as u32, expired: r.get::<_, i64>(2)? as u32 })
+    })?;
+    stats.by_agent = rows.collect::<Result<Vec<_>, _>>()?;
+    Ok(stats)
+}
+
 fn uuid() -> String { format!("{:x}{:x}", rand_u128(), rand_u128()) }
 
 fn rand_u128() -> u128 { use std::time::{SystemTime, UNIX_EPOCH}; SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() }
@@ -967,6 +2498,209 @@ mod tests {
         assert_eq!(v, 1);
     }
 
+    #[test]
+    fn fresh_db_is_at_schema_version() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+        assert_eq!(db_schema_version(&conn).unwrap(), Some(SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn newer_schema_is_refused_unless_allowed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let path = db_path.to_string_lossy().to_string();
+        let conn = open_or_create_db(&path).unwrap();
+        let future = SCHEMA_VERSION + 1;
+        conn.execute("INSERT INTO migrations(version, applied_at) VALUES (?1, ?2)", params![future, now_iso8601_utc()]).unwrap();
+        drop(conn);
+
+        match open_or_create_db(&path) {
+            Err(DbError::SchemaTooNew { db_version, supported }) => {
+                assert_eq!((db_version, supported), (future, SCHEMA_VERSION));
+            }
+            other => panic!("expected SchemaTooNew, got {:?}", other.map(|_| ())),
+        }
+
+        let conn = open_db_allow_newer_schema(&path).unwrap();
+        assert_eq!(db_schema_version(&conn).unwrap(), Some(future));
+        assert!(list_project_names(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn migrate_to_steps_through_versions() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_db_without_migrating(db_path.to_string_lossy().as_ref()).unwrap();
+        assert_eq!(current_migration_version(&conn).unwrap(), 0);
+        assert_eq!(pending_migrations(&conn).unwrap(), (1..=SCHEMA_VERSION).collect::<Vec<_>>());
+
+        assert_eq!(migrate_to(&conn, 2).unwrap(), vec![1, 2]);
+        assert_eq!(current_migration_version(&conn).unwrap(), 2);
+        assert_eq!(pending_migrations(&conn).unwrap(), (3..=SCHEMA_VERSION).collect::<Vec<_>>());
+        assert!(table_exists(&conn, "sessions").unwrap());
+        assert!(!table_exists(&conn, "broadcasts").unwrap());
+
+        assert!(migrate_to(&conn, 2).unwrap().is_empty());
+        assert!(matches!(migrate_to(&conn, 1), Err(DbError::InvalidInput(_))));
+        assert!(matches!(migrate_to(&conn, SCHEMA_VERSION + 1), Err(DbError::InvalidInput(_))));
+
+        assert_eq!(migrate_to(&conn, SCHEMA_VERSION).unwrap(), (3..=SCHEMA_VERSION).collect::<Vec<_>>());
+        assert_eq!(current_migration_version(&conn).unwrap(), SCHEMA_VERSION);
+        assert!(pending_migrations(&conn).unwrap().is_empty());
+        assert!(table_exists(&conn, "broadcasts").unwrap());
+    }
+
+    #[test]
+    fn opening_an_up_to_date_database_does_not_wait_for_writers() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("multi-agents.sqlite3").to_string_lossy().to_string();
+        open_or_create_db(&path).unwrap();
+
+        // Another connection is in the middle of a write transaction
+        let writer = open_or_create_db(&path).unwrap();
+        writer.execute_batch("BEGIN IMMEDIATE").unwrap();
+        // Without pending migrations there is no exclusive lock to wait for, and so no busy error
+        let conn = open_or_create_db(&path).unwrap();
+        assert_eq!(current_migration_version(&conn).unwrap(), SCHEMA_VERSION);
+        assert!(migrate_to(&conn, 2).is_err(), "downgrades are still rejected");
+        writer.execute_batch("ROLLBACK").unwrap();
+    }
+
+    #[test]
+    fn readonly_open_lists_but_refuses_writes_and_old_schemas() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("multi-agents.sqlite3").to_string_lossy().to_string();
+        assert!(matches!(open_readonly_db(&path), Err(DbError::InvalidInput(_))), "a missing file is not created");
+        assert!(!std::path::Path::new(&path).exists());
+
+        let rw = open_or_create_db(&path).unwrap();
+        insert_project(&rw, "demo").unwrap();
+
+        let ro = open_readonly_db(&path).unwrap();
+        assert_eq!(list_project_names(&ro).unwrap(), vec!["demo".to_string()]);
+        assert!(list_sessions(&ro, SessionFilters { project_id: None, agent_id: None, provider: None, status: None, session_type: None, limit: None, offset: None }).unwrap().is_empty());
+        match insert_project(&ro, "other") {
+            Err(DbError::Sqlite(e)) => assert!(e.to_string().contains("readonly"), "{}", e),
+            other => panic!("expected a read-only error, got {:?}", other.map(|p| p.name)),
+        }
+
+        // Pending migrations are reported instead of being applied
+        rw.execute("DELETE FROM migrations WHERE version = ?1", params![SCHEMA_VERSION]).unwrap();
+        match open_readonly_db(&path) {
+            Err(DbError::SchemaTooOld { db_version, required }) => assert_eq!((db_version, required), (SCHEMA_VERSION - 1, SCHEMA_VERSION)),
+            other => panic!("expected SchemaTooOld, got {:?}", other.map(|_| ())),
+        }
+        rw.execute("INSERT INTO migrations(version, applied_at) VALUES (?1, ?2)", params![SCHEMA_VERSION + 1, now_iso8601_utc()]).unwrap();
+        assert!(matches!(open_readonly_db(&path), Err(DbError::SchemaTooNew { .. })));
+        assert!(open_readonly_db_allow_newer_schema(&path).is_ok());
+    }
+
+    #[test]
+    fn session_order_by_maps_to_known_columns_only() {
+        const ALLOWED: [&str; 5] = ["created_at", "last_activity", "expires_at", "status", "provider"];
+        let tmp = tempfile::tempdir().unwrap();
+        let conn = open_or_create_db(tmp.path().join("multi-agents.sqlite3").to_string_lossy().as_ref()).unwrap();
+        for order in SessionOrderBy::ALL {
+            let column = order.as_sql_column();
+            assert!(ALLOWED.contains(&column), "{:?} -> {}", order, column);
+            // Every column really exists on sessions
+            conn.prepare(&format!("SELECT id FROM sessions ORDER BY {}", column)).unwrap();
+        }
+        assert_eq!(SessionOrderBy::default().as_sql_column(), "created_at");
+    }
+
+    #[test]
+    fn response_cache_expires_entries_after_their_ttl() {
+        let tmp = tempfile::tempdir().unwrap();
+        let conn = open_or_create_db(tmp.path().join("multi-agents.sqlite3").to_string_lossy().as_ref()).unwrap();
+        let hour = std::time::Duration::from_secs(3600);
+        put_cached_response(&conn, "live", "claude", "sonnet", "hello\n", 0, hour).unwrap();
+        put_cached_response(&conn, "stale", "claude", "sonnet", "old\n", 0, std::time::Duration::ZERO).unwrap();
+
+        let hit = get_cached_response(&conn, "live").unwrap().expect("live entry");
+        assert_eq!((hit.stdout.as_str(), hit.exit_code, hit.hits), ("hello\n", 0, 1));
+        assert!(get_cached_response(&conn, "stale").unwrap().is_none());
+        assert!(get_cached_response(&conn, "missing").unwrap().is_none());
+
+        let stats = response_cache_stats(&conn).unwrap();
+        assert_eq!(stats, ResponseCacheStats { entries: 2, expired: 1, hits: 1, bytes: 10 });
+        assert_eq!(clear_response_cache(&conn, true).unwrap(), 1);
+        assert_eq!(clear_response_cache(&conn, false).unwrap(), 1);
+        assert_eq!(response_cache_stats(&conn).unwrap(), ResponseCacheStats::default());
+    }
+
+    #[test]
+    fn concurrent_opens_apply_each_migration_once() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("multi-agents.sqlite3").to_string_lossy().to_string();
+        let start = std::sync::Arc::new(std::sync::Barrier::new(2));
+        let handles: Vec<_> = (0..2).map(|_| {
+            let (path, start) = (path.clone(), start.clone());
+            std::thread::spawn(move || {
+                start.wait();
+                open_or_create_db(&path).map(|_| ())
+            })
+        }).collect();
+        for handle in handles {
+            handle.join().unwrap().expect("both processes open the database");
+        }
+        let conn = open_db_without_migrating(&path).unwrap();
+        let versions: Vec<i64> = conn.prepare("SELECT version FROM migrations ORDER BY version").unwrap()
+            .query_map([], |r| r.get(0)).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(versions, (1..=SCHEMA_VERSION).collect::<Vec<_>>());
+        assert!(table_exists(&conn, "response_cache").unwrap());
+    }
+
+    #[test]
+    fn busy_writes_are_retried_until_the_lock_goes_or_the_attempts_run_out() {
+        assert_eq!(busy_timeout_from_env(Some("250")), 250);
+        assert_eq!(busy_timeout_from_env(Some("soon")), DEFAULT_BUSY_TIMEOUT_MS);
+        assert_eq!(busy_timeout_from_env(None), DEFAULT_BUSY_TIMEOUT_MS);
+
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("multi-agents.sqlite3").to_string_lossy().to_string();
+        let conn = open_or_create_db(&path).unwrap();
+        // No SQLite-level wait, so every locked attempt fails at once and only the retry helps
+        conn.busy_timeout(std::time::Duration::ZERO).unwrap();
+        let locker = open_or_create_db(&path).unwrap();
+
+        // Lock released while the retries are still going: the write lands
+        locker.execute_batch("BEGIN EXCLUSIVE").unwrap();
+        let release = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(120));
+            locker.execute_batch("COMMIT").unwrap();
+            locker
+        });
+        let p = insert_project(&conn, "busy-demo").unwrap();
+        let locker = release.join().unwrap();
+        assert_eq!(find_project_id(&conn, IdOrName::Name("busy-demo")).unwrap(), Some(p.id.clone()));
+
+        // Lock held throughout: a clear error after the last attempt
+        locker.execute_batch("BEGIN EXCLUSIVE").unwrap();
+        let mut attempts = 0;
+        let err = with_busy_retry(|| {
+            attempts += 1;
+            Ok(conn.execute("UPDATE projects SET name = 'renamed' WHERE id = ?1", params![p.id])?)
+        }).unwrap_err();
+        assert_eq!(attempts, BUSY_RETRY_DELAYS_MS.len() + 1);
+        assert!(matches!(err, DbError::Busy { attempts: 6 }), "{err}");
+        assert!(err.to_string().contains(DB_BUSY_MS_ENV), "{err}");
+        locker.execute_batch("ROLLBACK").unwrap();
+
+        // Other errors are not retried
+        let mut attempts = 0;
+        assert!(with_busy_retry(|| { attempts += 1; insert_project(&conn, "") }).is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    /// Deferred transaction for reading migration state in tests
+    fn read_tx(conn: &Connection) -> Transaction<'_> {
+        Transaction::new_unchecked(conn, TransactionBehavior::Deferred).unwrap()
+    }
+
     fn table_exists(conn: &Connection, name: &str) -> Result<bool, DbError> {
         let mut stmt = conn.prepare("SELECT name FROM sqlite_master WHERE type='table' AND name=?1")?;
         Ok(stmt.exists(params![name])?)
@@ -981,12 +2715,12 @@ mod tests {
         let p = insert_project(&conn, "demo").unwrap();
         assert_eq!(find_project_id(&conn, IdOrName::Name("demo")).unwrap().as_deref(), Some(p.id.as_str()));
 
-        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
         assert_eq!(a.name, "backend");
         assert_eq!(from_json_text(&to_json_text(&a.allowed_tools)).unwrap(), vec!["Edit".to_string()]);
 
         // Uniqueness on project_id+name
-        let dup = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &vec!["Edit".into()], "sp");
+        let dup = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp");
         assert!(dup.is_err());
     }
 
@@ -997,7 +2731,7 @@ mod tests {
         let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
         
         // Check that migration v2 was applied
-        let v2_applied = migration_applied(&conn, 2).unwrap();
+        let v2_applied = migration_applied(&read_tx(&conn), 2).unwrap();
         assert!(v2_applied, "migration v2 should be applied");
         
         // Check that new columns exist
@@ -1014,7 +2748,7 @@ mod tests {
         
         // Check that indexes were created
         let mut stmt = conn.prepare("SELECT name FROM sqlite_master WHERE type='index' AND name LIKE 'idx_sessions_%'").unwrap();
-        let indexes: Vec<String> = stmt.query_map([], |row| Ok(row.get(0)?)).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        let indexes: Vec<String> = stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
         
         assert!(indexes.contains(&"idx_sessions_project_status_created".to_string()), "composite index should exist");
         assert!(indexes.contains(&"idx_sessions_provider_session_id".to_string()), "provider_session_id index should exist");
@@ -1029,7 +2763,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
         
         // Insert session with new columns
         let session_id = uuid();
@@ -1072,7 +2806,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
         
         // Test insert_session
         let session = insert_session(&conn, &p.id, &a.id, "gemini", Some("provider_123")).unwrap();
@@ -1104,7 +2838,7 @@ mod tests {
         // Test update_session
         update_session(&conn, &session.id, None, Some("2025-01-17T20:00:00Z"), Some(SessionStatus::Expired)).unwrap();
         let updated_session = find_session(&conn, &session.id).unwrap().unwrap();
-        assert_eq!(updated_session.last_activity, Some("2025-01-17T20:00:00Z".to_string()));
+        assert_eq!(updated_session.last_activity, Some("2025-01-17T20:00:00.000Z".to_string()));
         assert_eq!(updated_session.status, SessionStatus::Expired);
         
         // Set expires_at to make session eligible for deletion
@@ -1130,8 +2864,8 @@ mod tests {
         
         // Create project and agents
         let p = insert_project(&conn, "demo").unwrap();
-        let a1 = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &vec!["Edit".into()], "sp").unwrap();
-        let a2 = insert_agent(&conn, &p.id, "frontend", "frontend", "claude", "claude-3", &vec!["Edit".into()], "sp").unwrap();
+        let a1 = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
+        let a2 = insert_agent(&conn, &p.id, "frontend", "frontend", "claude", "claude-3", &["Edit".into()], "sp").unwrap();
         
         // Create sessions
         let s1 = insert_session(&conn, &p.id, &a1.id, "gemini", Some("provider_1")).unwrap();
@@ -1175,7 +2909,7 @@ mod tests {
 
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
 
         // Insert multiple sessions (timestamps auto now; order by created_at DESC expected)
         let mut ids = vec![];
@@ -1204,13 +2938,13 @@ mod tests {
         let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
 
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
         let s = insert_session(&conn, &p.id, &a.id, "gemini", Some("ctx_1")).unwrap();
 
         // Update only last_activity
         update_session(&conn, &s.id, None, Some("2025-01-20T00:00:00Z"), None).unwrap();
         let after = find_session(&conn, &s.id).unwrap().unwrap();
-        assert_eq!(after.last_activity, Some("2025-01-20T00:00:00Z".into()));
+        assert_eq!(after.last_activity, Some("2025-01-20T00:00:00.000Z".into()));
 
         // Update only provider_session_id
         update_session(&conn, &s.id, Some("ctx_2"), None, None).unwrap();
@@ -1230,7 +2964,7 @@ mod tests {
         let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
 
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
         let s = insert_session(&conn, &p.id, &a.id, "gemini", Some("ctx")) .unwrap();
 
         // Make it expire yesterday
@@ -1242,9 +2976,81 @@ mod tests {
         let deleted = delete_expired_sessions(&conn, "2025-01-02T00:00:00Z").unwrap();
         assert_eq!(deleted, 1);
 
-        // Nothing left to delete now
-        let deleted2 = delete_expired_sessions(&conn, "2025-01-03T00:00:00Z").unwrap();
-        assert_eq!(deleted2, 0);
+        // Nothing left to delete now
+        let deleted2 = delete_expired_sessions(&conn, "2025-01-03T00:00:00Z").unwrap();
+        assert_eq!(deleted2, 0);
+    }
+
+    #[test]
+    fn every_session_manager_lists_and_deletes_through_the_trait() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let path = db_path.to_string_lossy().to_string();
+        let conn = open_or_create_db(&path).unwrap();
+        let p = insert_project(&conn, "demo").unwrap();
+        let managers: Vec<(&str, Box<dyn SessionManager>)> = vec![
+            ("claude", Box::new(ClaudeSessionManager::new(open_or_create_db(&path).unwrap()))),
+            ("cursor-agent", Box::new(CursorSessionManager::new(open_or_create_db(&path).unwrap()))),
+            ("gemini", Box::new(GeminiSessionManager::new(open_or_create_db(&path).unwrap()))),
+        ];
+        for (provider, manager) in &managers {
+            let a = insert_agent(&conn, &p.id, provider, "backend", provider, "m", &[], "sp").unwrap();
+            let kept = manager.create_session(&p.id, &a.id, provider, None).unwrap();
+            let dropped = manager.create_session(&p.id, &a.id, provider, None).unwrap();
+            conn.execute(
+                "INSERT INTO messages(id, session_id, sender, content, created_at) VALUES (?1, ?2, 'user', 'hi', ?3)",
+                params![format!("m-{}", provider), dropped.id, now_iso8601_utc()],
+            ).unwrap();
+            let filters = SessionFilters {
+                project_id: Some(p.id.clone()), agent_id: Some(a.id.clone()), provider: None, status: None,
+                session_type: None, limit: None, offset: None,
+            };
+
+            let mut listed: Vec<String> = manager.list(filters.clone()).unwrap().into_iter().map(|s| s.id).collect();
+            listed.sort();
+            let mut expected = vec![kept.id.clone(), dropped.id.clone()];
+            expected.sort();
+            assert_eq!(listed, expected, "{}", provider);
+
+            assert!(manager.delete(&dropped.id).unwrap(), "{}", provider);
+            assert!(!manager.delete(&dropped.id).unwrap(), "{}", provider);
+            let left: Vec<String> = manager.list(filters).unwrap().into_iter().map(|s| s.id).collect();
+            assert_eq!(left, vec![kept.id.clone()], "{}", provider);
+            let messages: i64 = conn.query_row("SELECT COUNT(*) FROM messages WHERE session_id = ?1", params![dropped.id], |r| r.get(0)).unwrap();
+            assert_eq!(messages, 0, "{}", provider);
+        }
+    }
+
+    #[test]
+    fn sessions_created_with_a_ttl_expire_and_can_be_deleted() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+        let p = insert_project(&conn, "demo").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
+
+        let ttl = std::time::Duration::from_secs(90 * 60);
+        let s = insert_session_with_ttl(&conn, &p.id, &a.id, "gemini", None, SessionType::Chat, Some(ttl)).unwrap();
+        let stored = find_session(&conn, &s.id).unwrap().unwrap();
+        assert_eq!(stored.expires_at, s.expires_at);
+        let created = parse_iso8601_utc(&stored.created_at).unwrap();
+        let expires = parse_iso8601_utc(stored.expires_at.as_deref().expect("ttl sets expires_at")).unwrap();
+        assert_eq!(expires - created, time::Duration::minutes(90));
+        let forever = insert_session(&conn, &p.id, &a.id, "gemini", None).unwrap();
+        assert_eq!(forever.expires_at, None);
+
+        // Nothing is due before expiry; once past it, only the TTL session goes
+        let just_before = format_iso8601_utc(expires - time::Duration::seconds(1));
+        assert_eq!(delete_expired_sessions(&conn, &just_before).unwrap(), 0);
+        let after = format_iso8601_utc(expires + time::Duration::seconds(1));
+        assert_eq!(delete_expired_sessions(&conn, &after).unwrap(), 1);
+        assert!(find_session(&conn, &s.id).unwrap().is_none());
+        assert!(find_session(&conn, &forever.id).unwrap().is_some());
+
+        // Session managers pass their TTL through
+        let manager = GeminiSessionManager::new(conn).with_session_ttl(Some(ttl));
+        let created = manager.create_session(&p.id, &a.id, "gemini", None).unwrap();
+        assert!(created.expires_at.is_some());
     }
 
     #[test]
@@ -1273,7 +3079,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "backend", "backend", "claude", "claude-3", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "claude", "claude-3", &["Edit".into()], "sp").unwrap();
         
         // Create sessions
         let session = insert_session(&conn, &p.id, &a.id, "claude", Some("valid_session_123")).unwrap();
@@ -1294,6 +3100,105 @@ mod tests {
         assert!(matches!(result, Err(SessionError::NotFound(_))));
     }
 
+    #[test]
+    fn repository_types_round_trip_through_json() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+        let p = insert_project(&conn, "demo").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "claude", "claude-3", &["Edit".into()], "sp").unwrap();
+        let fresh = insert_session(&conn, &p.id, &a.id, "claude", None).unwrap();
+        set_session_expires_at(&conn, &fresh.id, Some("2030-01-01T00:00:00Z")).unwrap();
+        let session = find_session(&conn, &fresh.id).unwrap().unwrap();
+        let task = Task {
+            id: "t1".into(),
+            project_id: p.id.clone(),
+            title: "Ship it".into(),
+            status: "todo".into(),
+            assignee_agent_id: None,
+            created_at: now_iso8601_utc(),
+        };
+
+        fn round_trip<T: serde::Serialize + serde::de::DeserializeOwned>(value: &T) -> serde_json::Value {
+            let json = serde_json::to_value(value).unwrap();
+            let back: T = serde_json::from_value(json.clone()).unwrap();
+            assert_eq!(serde_json::to_value(&back).unwrap(), json);
+            json
+        }
+        assert_eq!(round_trip(&p), serde_json::json!({"id": p.id, "name": "demo"}));
+        assert_eq!(round_trip(&a)["allowed_tools"], serde_json::json!(["Edit"]));
+        let session_json = round_trip(&session);
+        assert_eq!(session_json["status"], "active");
+        assert_eq!(session_json["session_type"], "chat");
+        assert_eq!(session_json["expires_at"], "2030-01-01T00:00:00.000Z");
+        assert!(session_json.get("provider_session_id").is_none(), "unset options are left out: {session_json}");
+        assert!(round_trip(&task).get("assignee_agent_id").is_none());
+        assert_eq!(serde_json::from_str::<SessionStatus>("\"expired\"").unwrap(), SessionStatus::Expired);
+        assert!(serde_json::from_str::<SessionStatus>("\"Expired\"").is_err());
+    }
+
+    #[test]
+    fn session_expiry_by_time_compares_expires_at_with_now() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+        let p = insert_project(&conn, "demo").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "claude", "claude-3", &["Edit".into()], "sp").unwrap();
+        let mut session = insert_session(&conn, &p.id, &a.id, "claude", Some("valid_session_123")).unwrap();
+        let now = parse_iso8601_utc("2025-06-01T12:00:00.000Z").unwrap();
+
+        assert!(!session.is_expired_by_time(now), "no expires_at never expires");
+        session.expires_at = Some("2025-06-01T11:59:59.999Z".into());
+        assert!(session.is_expired_by_time(now));
+        session.expires_at = Some("2025-06-01T12:00:00.000Z".into());
+        assert!(session.is_expired_by_time(now));
+        session.expires_at = Some("2025-06-01T14:00:00+02:00".into());
+        assert!(session.is_expired_by_time(now), "offsets are compared as instants");
+        session.expires_at = Some("2025-06-01T12:00:00.001Z".into());
+        assert!(!session.is_expired_by_time(now));
+        session.expires_at = Some("soon".into());
+        assert!(!session.is_expired_by_time(now));
+
+        // A session that would pass the provider ping is still expired once past expires_at
+        set_session_expires_at(&conn, &session.id, Some("2000-01-01T00:00:00Z")).unwrap();
+        let manager = ClaudeSessionManager::new(conn);
+        assert!(!manager.validate_session(&session.id).unwrap());
+        let stored = find_session(&manager.conn, &session.id).unwrap().unwrap();
+        assert_eq!(stored.status, SessionStatus::Expired);
+    }
+
+    #[test]
+    fn session_expiry_check_pings_only_near_or_without_expires_at() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+        let p = insert_project(&conn, "demo").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "claude", "claude-3", &["Edit".into()], "sp").unwrap();
+        let mut session = insert_session(&conn, &p.id, &a.id, "claude", Some("bogus_session")).unwrap();
+        let now = parse_iso8601_utc("2025-06-01T12:00:00.000Z").unwrap();
+        let slack = std::time::Duration::from_secs(600);
+
+        assert_eq!(session.expiry_check(now, slack), ExpiryCheck::NeedsPing, "no expires_at");
+        session.expires_at = Some("2025-06-01T11:00:00.000Z".into());
+        assert_eq!(session.expiry_check(now, slack), ExpiryCheck::Expired);
+        session.expires_at = Some("2025-06-01T12:10:00.000Z".into());
+        assert_eq!(session.expiry_check(now, slack), ExpiryCheck::NeedsPing, "exactly the slack left");
+        session.expires_at = Some("2025-06-01T12:10:00.001Z".into());
+        assert_eq!(session.expiry_check(now, slack), ExpiryCheck::Fresh);
+        session.expires_at = Some("later".into());
+        assert_eq!(session.expiry_check(now, slack), ExpiryCheck::NeedsPing);
+
+        // Far from expiry: valid without the ping, which would reject this provider id
+        let in_a_day = format_iso8601_utc(time::OffsetDateTime::now_utc() + time::Duration::days(1));
+        set_session_expires_at(&conn, &session.id, Some(&in_a_day)).unwrap();
+        let manager = ClaudeSessionManager::new(conn).with_expiry_slack(slack);
+        assert!(manager.validate_session(&session.id).unwrap());
+        // Within the slack: the provider is asked
+        let manager = manager.with_expiry_slack(std::time::Duration::from_secs(2 * 86400));
+        assert!(!manager.validate_session(&session.id).unwrap());
+        assert_eq!(find_session(&manager.conn, &session.id).unwrap().unwrap().status, SessionStatus::Expired);
+    }
+
     #[test]
     fn claude_session_manager_resume() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1302,7 +3207,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "backend", "backend", "claude", "claude-3", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "claude", "claude-3", &["Edit".into()], "sp").unwrap();
         
         // Create sessions
         let session = insert_session(&conn, &p.id, &a.id, "claude", Some("valid_session_123")).unwrap();
@@ -1333,7 +3238,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "backend", "backend", "claude", "claude-3", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "claude", "claude-3", &["Edit".into()], "sp").unwrap();
         
         let manager = ClaudeSessionManager::new(conn);
         
@@ -1366,7 +3271,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "backend", "backend", "claude", "claude-3", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "claude", "claude-3", &["Edit".into()], "sp").unwrap();
         
         // Create sessions with different statuses
         let _active_session = insert_session(&conn, &p.id, &a.id, "claude", Some("valid_session_123")).unwrap();
@@ -1374,6 +3279,8 @@ mod tests {
         
         // Mark one session as expired
         update_session(&conn, &expired_session.id, None, None, Some(SessionStatus::Expired)).unwrap();
+        // Cleanup takes sessions idle since strictly before now; move this one out of the current millisecond
+        conn.execute("UPDATE sessions SET last_activity = ?1 WHERE id = ?2", params![iso8601_utc_ago(std::time::Duration::from_secs(60)), &expired_session.id]).unwrap();
         
         let manager = ClaudeSessionManager::new(conn);
         
@@ -1407,7 +3314,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "frontend", "frontend", "cursor-agent", "auto", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "frontend", "frontend", "cursor-agent", "auto", &["Edit".into()], "sp").unwrap();
         
         // Create sessions
         let session = insert_session(&conn, &p.id, &a.id, "cursor-agent", Some("valid_chat_123")).unwrap();
@@ -1436,7 +3343,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "frontend", "frontend", "cursor-agent", "auto", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "frontend", "frontend", "cursor-agent", "auto", &["Edit".into()], "sp").unwrap();
         
         // Create sessions
         let session = insert_session(&conn, &p.id, &a.id, "cursor-agent", Some("valid_chat_123")).unwrap();
@@ -1467,7 +3374,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "frontend", "frontend", "cursor-agent", "auto", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "frontend", "frontend", "cursor-agent", "auto", &["Edit".into()], "sp").unwrap();
         
         let manager = CursorSessionManager::new(conn);
         
@@ -1501,7 +3408,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "frontend", "frontend", "cursor-agent", "auto", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "frontend", "frontend", "cursor-agent", "auto", &["Edit".into()], "sp").unwrap();
         
         // Create sessions with different statuses
         let _active_session = insert_session(&conn, &p.id, &a.id, "cursor-agent", Some("valid_chat_123")).unwrap();
@@ -1509,6 +3416,8 @@ mod tests {
         
         // Mark one session as expired
         update_session(&conn, &expired_session.id, None, None, Some(SessionStatus::Expired)).unwrap();
+        // Cleanup takes sessions idle since strictly before now; move this one out of the current millisecond
+        conn.execute("UPDATE sessions SET last_activity = ?1 WHERE id = ?2", params![iso8601_utc_ago(std::time::Duration::from_secs(60)), &expired_session.id]).unwrap();
         
         let manager = CursorSessionManager::new(conn);
         
@@ -1559,7 +3468,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "devops", "devops", "gemini", "gemini-1.5-pro", &vec!["Bash".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "devops", "devops", "gemini", "gemini-1.5-pro", &["Bash".into()], "sp").unwrap();
         
         // Create sessions
         let session = insert_session(&conn, &p.id, &a.id, "gemini", Some("valid_context_123")).unwrap();
@@ -1588,7 +3497,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "devops", "devops", "gemini", "gemini-1.5-pro", &vec!["Bash".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "devops", "devops", "gemini", "gemini-1.5-pro", &["Bash".into()], "sp").unwrap();
         
         // Create sessions
         let session = insert_session(&conn, &p.id, &a.id, "gemini", Some("valid_context_123")).unwrap();
@@ -1619,7 +3528,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "devops", "devops", "gemini", "gemini-1.5-pro", &vec!["Bash".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "devops", "devops", "gemini", "gemini-1.5-pro", &["Bash".into()], "sp").unwrap();
         
         let manager = GeminiSessionManager::new(conn);
         
@@ -1653,7 +3562,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "devops", "devops", "gemini", "gemini-1.5-pro", &vec!["Bash".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "devops", "devops", "gemini", "gemini-1.5-pro", &["Bash".into()], "sp").unwrap();
         
         // Create sessions with different statuses
         let _active_session = insert_session(&conn, &p.id, &a.id, "gemini", Some("valid_context_123")).unwrap();
@@ -1661,6 +3570,8 @@ mod tests {
         
         // Mark one session as expired
         update_session(&conn, &expired_session.id, None, None, Some(SessionStatus::Expired)).unwrap();
+        // Cleanup takes sessions idle since strictly before now; move this one out of the current millisecond
+        conn.execute("UPDATE sessions SET last_activity = ?1 WHERE id = ?2", params![iso8601_utc_ago(std::time::Duration::from_secs(60)), &expired_session.id]).unwrap();
         
         let manager = GeminiSessionManager::new(conn);
         
@@ -1712,7 +3623,7 @@ mod tests {
         let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
         
         // Check that migration v3 was applied
-        let v3_applied = migration_applied(&conn, 3).unwrap();
+        let v3_applied = migration_applied(&read_tx(&conn), 3).unwrap();
         assert!(v3_applied, "migration v3 should be applied");
         
         // Check that type column exists
@@ -1726,7 +3637,7 @@ mod tests {
         
         // Check that index was created
         let mut stmt = conn.prepare("SELECT name FROM sqlite_master WHERE type='index' AND name LIKE 'idx_sessions_%'").unwrap();
-        let indexes: Vec<String> = stmt.query_map([], |row| Ok(row.get(0)?)).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        let indexes: Vec<String> = stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
         
         assert!(indexes.contains(&"idx_sessions_type_status".to_string()), "type_status index should exist");
     }
@@ -1750,7 +3661,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
         
         // Test insert_repl_session
         let session = insert_repl_session(&conn, &p.id, &a.id, "gemini", Some("provider_123")).unwrap();
@@ -1766,6 +3677,68 @@ mod tests {
         assert_eq!(found_session.session_type, SessionType::Repl);
     }
 
+    #[test]
+    fn insert_failed_session_is_invalid_with_metadata() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+        let p = insert_project(&conn, "demo").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "cursor-agent", "m", &[], "sp").unwrap();
+
+        let meta = r#"{"error":"create-chat exited with code 1: boom"}"#;
+        let session = insert_failed_session(&conn, &p.id, &a.id, "cursor-agent", meta).unwrap();
+        let found = find_session(&conn, &session.id).unwrap().unwrap();
+        assert_eq!(found.status, SessionStatus::Invalid);
+        assert_eq!(found.metadata.as_deref(), Some(meta));
+        assert_eq!(found.provider_session_id, None);
+
+        let invalid = list_sessions(&conn, SessionFilters {
+            project_id: Some(p.id.clone()),
+            agent_id: None,
+            provider: None,
+            status: Some(SessionStatus::Invalid),
+            session_type: None,
+            limit: None,
+            offset: None,
+        }).unwrap();
+        assert_eq!(invalid.len(), 1);
+        assert!(matches!(insert_failed_session(&conn, &p.id, &a.id, "cursor-agent", "not json"), Err(DbError::JsonSerde(_))));
+    }
+
+    #[test]
+    fn get_metadata_value_reads_keys_and_rejects_malformed_json() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+        let p = insert_project(&conn, "demo").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "m", &[], "sp").unwrap();
+
+        let mut session = insert_session(&conn, &p.id, &a.id, "gemini", None).unwrap();
+        assert!(get_metadata_value(&session, "error").unwrap().is_none());
+        session.metadata = Some(r#"{"error":"boom","exit_code":4}"#.into());
+        assert_eq!(get_metadata_value(&session, "exit_code").unwrap(), Some(json!(4)));
+        assert!(get_metadata_value(&session, "missing").unwrap().is_none());
+
+        session.metadata = Some("{not json".into());
+        assert!(matches!(get_metadata_value(&session, "error"), Err(DbError::JsonSerde(_))));
+        assert!(matches!(from_json_text("[1,"), Err(DbError::JsonSerde(_))));
+    }
+
+    #[test]
+    fn set_session_metadata_replaces_json() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+        let p = insert_project(&conn, "demo").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "m", &[], "sp").unwrap();
+        let session = insert_repl_session(&conn, &p.id, &a.id, "gemini", None).unwrap();
+
+        set_session_metadata(&conn, &session.id, r#"{"backend":"process","pid":42}"#).unwrap();
+        let found = find_session(&conn, &session.id).unwrap().unwrap();
+        assert_eq!(get_metadata_value(&found, "pid").unwrap(), Some(json!(42)));
+        assert!(matches!(set_session_metadata(&conn, &session.id, "{"), Err(DbError::JsonSerde(_))));
+    }
+
     #[test]
     fn session_filters_by_type() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1774,7 +3747,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
         
         // Create sessions of different types
         let _chat_session = insert_session(&conn, &p.id, &a.id, "gemini", Some("chat_123")).unwrap();
@@ -1817,7 +3790,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
         
         // Create a REPL session
         let session = insert_repl_session(&conn, &p.id, &a.id, "gemini", Some("provider_123")).unwrap();
@@ -1850,7 +3823,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
         
         // Create a recent REPL session (should not be cleaned up)
         let _recent_session = insert_repl_session(&conn, &p.id, &a.id, "gemini", Some("provider_123")).unwrap();
@@ -1868,7 +3841,7 @@ mod tests {
         
         // Create project and agent
         let p = insert_project(&conn, "demo").unwrap();
-        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &vec!["Edit".into()], "sp").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
         
         // Create an old chat session (should not be cleaned up by REPL cleanup)
         let session = insert_session(&conn, &p.id, &a.id, "gemini", Some("chat_123")).unwrap();
@@ -1892,51 +3865,667 @@ mod tests {
         let updated_session = find_session(&conn, &session.id).unwrap().unwrap();
         assert_eq!(updated_session.status, SessionStatus::Active);
     }
+
+    #[test]
+    fn cleanup_repl_sessions_before_uses_custom_cutoff() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+
+        let p = insert_project(&conn, "demo").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
+        let session = insert_repl_session(&conn, &p.id, &a.id, "gemini", None).unwrap();
+        conn.execute(
+            "UPDATE sessions SET last_activity = ?1, created_at = ?1 WHERE id = ?2",
+            params![iso8601_utc_ago(std::time::Duration::from_secs(2 * 3600)), session.id],
+        ).unwrap();
+
+        // Idle for 2h: kept by a 3h cutoff, expired by a 1h cutoff
+        let three_hours = iso8601_utc_ago(std::time::Duration::from_secs(3 * 3600));
+        assert_eq!(cleanup_repl_sessions_before(&conn, &three_hours).unwrap(), 0);
+        let one_hour = iso8601_utc_ago(std::time::Duration::from_secs(3600));
+        assert_eq!(cleanup_repl_sessions_before(&conn, &one_hour).unwrap(), 1);
+    }
+
+    #[test]
+    fn session_cleanup_on_pre_v3_schema_treats_sessions_as_chat() {
+        let conn = Connection::open_in_memory().unwrap();
+        apply_v1(&conn).unwrap();
+        apply_v2(&conn).unwrap();
+        assert!(!sessions_have_type_column(&conn).unwrap());
+        assert!(column_exists(&conn, "sessions", "status").unwrap());
+        assert!(!column_exists(&conn, "no_such_table", "type").unwrap());
+
+        let p = insert_project(&conn, "demo").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &[], "sp").unwrap();
+        let old = iso8601_utc_ago(std::time::Duration::from_secs(48 * 3600));
+        for (id, created_at) in [("old", old.as_str()), ("fresh", now_iso8601_utc().as_str())] {
+            conn.execute(
+                "INSERT INTO sessions(id, project_id, agent_id, provider, created_at, last_activity, status) VALUES (?1, ?2, ?3, 'gemini', ?4, ?4, 'active')",
+                params![id, p.id, a.id, created_at],
+            ).unwrap();
+        }
+
+        let cutoff = iso8601_utc_ago(std::time::Duration::from_secs(24 * 3600));
+        assert!(list_stale_repl_sessions_before(&conn, &cutoff).unwrap().is_empty());
+        assert_eq!(cleanup_repl_sessions_before(&conn, &cutoff).unwrap(), 0);
+        let expired = list_expired_chat_sessions_before(&conn, &cutoff).unwrap();
+        assert_eq!(expired.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), vec!["old"]);
+        assert_eq!(expired[0].session_type, SessionType::Chat);
+        assert_eq!(delete_expired_chat_sessions_before(&conn, &cutoff).unwrap(), 1);
+    }
+
+    #[test]
+    fn session_cleanup_on_v3_schema_uses_type_column() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+        assert!(sessions_have_type_column(&conn).unwrap());
+
+        let p = insert_project(&conn, "demo").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &[], "sp").unwrap();
+        let chat = insert_session(&conn, &p.id, &a.id, "gemini", None).unwrap();
+        let repl = insert_repl_session(&conn, &p.id, &a.id, "gemini", None).unwrap();
+        conn.execute(
+            "UPDATE sessions SET last_activity = ?1, created_at = ?1",
+            params![iso8601_utc_ago(std::time::Duration::from_secs(48 * 3600))],
+        ).unwrap();
+
+        let cutoff = iso8601_utc_ago(std::time::Duration::from_secs(24 * 3600));
+        let stale = list_stale_repl_sessions_before(&conn, &cutoff).unwrap();
+        assert_eq!(stale.iter().map(|s| s.id.clone()).collect::<Vec<_>>(), vec![repl.id.clone()]);
+        let expired = list_expired_chat_sessions_before(&conn, &cutoff).unwrap();
+        assert_eq!(expired.iter().map(|s| s.id.clone()).collect::<Vec<_>>(), vec![chat.id.clone()]);
+
+        assert_eq!(cleanup_repl_sessions_before(&conn, &cutoff).unwrap(), 1);
+        assert_eq!(delete_expired_chat_sessions_before(&conn, &cutoff).unwrap(), 1);
+        assert!(find_session(&conn, &chat.id).unwrap().is_none());
+        assert_eq!(find_session(&conn, &repl.id).unwrap().unwrap().status, SessionStatus::Expired);
+    }
+
+    #[test]
+    fn rename_project_checks_source_and_target() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+
+        let p = insert_project(&conn, "alpha").unwrap();
+        insert_project(&conn, "beta").unwrap();
+
+        assert!(matches!(rename_project(&conn, "alpha", "beta"), Err(DbError::InvalidInput(_))));
+        assert!(matches!(rename_project(&conn, "missing", "gamma"), Err(DbError::InvalidInput(_))));
+        assert!(matches!(rename_project(&conn, "alpha", " "), Err(DbError::InvalidInput(_))));
+
+        rename_project(&conn, "alpha", "gamma").unwrap();
+        assert_eq!(find_project_id(&conn, IdOrName::Name("gamma")).unwrap(), Some(p.id));
+        assert!(find_project_id(&conn, IdOrName::Name("alpha")).unwrap().is_none());
+    }
+
+    #[test]
+    fn find_project_id_fuzzy_zero_one_and_many() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+
+        let backend = insert_project(&conn, "my-backend").unwrap();
+        let web = insert_project(&conn, "web_app").unwrap();
+        insert_project(&conn, "web-admin").unwrap();
+
+        assert!(find_project_id_fuzzy(&conn, "mobile").unwrap().is_empty());
+        assert!(find_project_id_fuzzy(&conn, "").unwrap().is_empty());
+        assert_eq!(find_project_id_fuzzy(&conn, "my-backend-service").unwrap(), vec![backend.id.clone()]);
+        assert_eq!(find_project_id_fuzzy(&conn, "BACKEND").unwrap(), vec![backend.id]);
+        assert_eq!(find_project_id_fuzzy(&conn, "web").unwrap().len(), 2);
+        // `_` is literal, not a LIKE wildcard
+        assert_eq!(find_project_id_fuzzy(&conn, "b_app").unwrap(), vec![web.id]);
+    }
+
+    #[test]
+    fn delete_project_cascades_every_dependent_row() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+
+        let p = insert_project(&conn, "demo").unwrap();
+        let other = insert_project(&conn, "other").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &[], "sp").unwrap();
+        let kept_agent = insert_agent(&conn, &other.id, "backend", "backend", "gemini", "g-1.5", &[], "sp").unwrap();
+        let s1 = insert_session(&conn, &p.id, &a.id, "gemini", None).unwrap();
+        insert_session(&conn, &other.id, &kept_agent.id, "gemini", None).unwrap();
+        conn.execute(
+            "INSERT INTO messages(id, session_id, sender, content, created_at) VALUES ('m1', ?1, 'user', 'hi', ?2)",
+            params![s1.id, now_iso8601_utc()],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO tasks(id, project_id, title, status, created_at) VALUES ('t1', ?1, 'task', 'todo', ?2)",
+            params![p.id, now_iso8601_utc()],
+        ).unwrap();
+        insert_broadcast(&conn, &p.id, "hello", &["backend".to_string()]).unwrap();
+
+        let expected = DeleteProjectResult { projects: 1, agents: 1, sessions: 1, messages: 1, tasks: 1, broadcasts: 1 };
+        assert_eq!(count_project_dependents(&conn, &p.id).unwrap(), expected);
+        assert_eq!(expected.dependents(), 5);
+        assert_eq!(delete_project(&conn, &p.id).unwrap(), expected);
+
+        assert!(find_project_id(&conn, IdOrName::Name("demo")).unwrap().is_none());
+        assert_eq!(count_project_dependents(&conn, &p.id).unwrap(), DeleteProjectResult::default());
+        let other_rows = count_project_dependents(&conn, &other.id).unwrap();
+        assert_eq!((other_rows.projects, other_rows.agents, other_rows.sessions), (1, 1, 1));
+    }
+
+    #[test]
+    fn delete_agent_cascades_sessions_and_messages() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+
+        let p = insert_project(&conn, "demo").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
+        let other = insert_agent(&conn, &p.id, "frontend", "frontend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
+        let s1 = insert_session(&conn, &p.id, &a.id, "gemini", None).unwrap();
+        let _s2 = insert_repl_session(&conn, &p.id, &a.id, "gemini", None).unwrap();
+        let kept = insert_session(&conn, &p.id, &other.id, "gemini", None).unwrap();
+        for (i, sid) in [&s1.id, &s1.id, &kept.id].iter().enumerate() {
+            conn.execute(
+                "INSERT INTO messages(id, session_id, sender, content, created_at) VALUES (?1, ?2, 'user', 'hi', ?3)",
+                params![format!("m{}", i), sid, now_iso8601_utc()],
+            ).unwrap();
+        }
+        conn.execute(
+            "INSERT INTO tasks(id, project_id, title, status, assignee_agent_id, created_at) VALUES ('t1', ?1, 'task', 'todo', ?2, ?3)",
+            params![p.id, a.id, now_iso8601_utc()],
+        ).unwrap();
+
+        let found = find_agent_by_name(&conn, &p.id, "backend").unwrap().unwrap();
+        assert_eq!(found.id, a.id);
+        assert_eq!(found.allowed_tools, vec!["Edit".to_string()]);
+
+        let expected = DeleteAgentResult { agents: 1, sessions: 2, messages: 2, tasks_unassigned: 1 };
+        assert_eq!(count_agent_dependents(&conn, &a.id).unwrap(), expected);
+        assert_eq!(delete_agent(&conn, &a.id).unwrap(), expected);
+
+        assert!(find_agent_by_name(&conn, &p.id, "backend").unwrap().is_none());
+        assert!(find_session(&conn, &s1.id).unwrap().is_none());
+        assert!(find_session(&conn, &kept.id).unwrap().is_some());
+        let remaining: i64 = conn.query_row("SELECT COUNT(*) FROM messages", [], |r| r.get(0)).unwrap();
+        assert_eq!(remaining, 1);
+        let assignee: Option<String> = conn.query_row("SELECT assignee_agent_id FROM tasks WHERE id = 't1'", [], |r| r.get(0)).unwrap();
+        assert!(assignee.is_none());
+
+        // Deleting again is a no-op
+        assert_eq!(delete_agent(&conn, &a.id).unwrap(), DeleteAgentResult::default());
+    }
+
+    #[test]
+    fn subscriptions_are_idempotent_filterable_and_cascade_with_the_supervisor() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+
+        let p = insert_project(&conn, "demo").unwrap();
+        let lead = insert_agent(&conn, &p.id, "lead", "lead", "gemini", "g-1.5", &[], "sp").unwrap();
+        let first = insert_subscription(&conn, &p.id, &lead.id, "send_failed", "@backend").unwrap();
+        assert_eq!(insert_subscription(&conn, &p.id, &lead.id, "send_failed", "@backend").unwrap(), first);
+        insert_subscription(&conn, &p.id, &lead.id, "task_done", "@backend").unwrap();
+        insert_subscription(&conn, &p.id, &lead.id, "send_failed", "@all").unwrap();
+        assert_eq!(list_subscriptions(&conn, &p.id).unwrap().len(), 3);
+
+        assert_eq!(delete_subscriptions(&conn, &lead.id, Some("send_failed"), Some("@backend")).unwrap(), 1);
+        assert_eq!(delete_subscriptions(&conn, &lead.id, Some("send_failed"), None).unwrap(), 1);
+        let left = list_subscriptions(&conn, &p.id).unwrap();
+        assert_eq!(left.iter().map(|s| s.event_kind.as_str()).collect::<Vec<_>>(), ["task_done"]);
+
+        delete_agent(&conn, &lead.id).unwrap();
+        assert!(list_subscriptions(&conn, &p.id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn task_status_updates_return_the_task_and_messages_are_recorded() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+
+        let p = insert_project(&conn, "demo").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &[], "sp").unwrap();
+        conn.execute(
+            "INSERT INTO tasks(id, project_id, title, status, assignee_agent_id, created_at) VALUES ('t1', ?1, 'task', 'todo', ?2, ?3)",
+            params![p.id, a.id, now_iso8601_utc()],
+        ).unwrap();
+        let task = update_task_status(&conn, "t1", "done").unwrap().unwrap();
+        assert_eq!((task.status.as_str(), task.assignee_agent_id.as_deref()), ("done", Some(a.id.as_str())));
+        assert!(update_task_status(&conn, "missing", "done").unwrap().is_none());
+        assert!(matches!(update_task_status(&conn, "t1", " "), Err(DbError::InvalidInput(_))));
+
+        let s = insert_session(&conn, &p.id, &a.id, "gemini", None).unwrap();
+        insert_message(&conn, &s.id, "notification", "hello", None).unwrap();
+        let (sender, content): (String, String) = conn.query_row(
+            "SELECT sender, content FROM messages WHERE session_id = ?1", params![s.id], |r| Ok((r.get(0)?, r.get(1)?)),
+        ).unwrap();
+        assert_eq!((sender.as_str(), content.as_str()), ("notification", "hello"));
+    }
+
+    #[test]
+    fn list_agents_returns_full_definitions_sorted_by_name() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+
+        let p = insert_project(&conn, "demo").unwrap();
+        let other = insert_project(&conn, "other").unwrap();
+        insert_agent(&conn, &p.id, "zeta", "qa", "claude", "sonnet", &["Read".into()], "check").unwrap();
+        insert_agent(&conn, &p.id, "alpha", "backend", "gemini", "g-1.5", &[], "build").unwrap();
+        insert_agent(&conn, &other.id, "beta", "backend", "gemini", "g-1.5", &[], "").unwrap();
+
+        let agents = list_agents(&conn, &p.id).unwrap();
+        assert_eq!(agents.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(), vec!["alpha", "zeta"]);
+        assert_eq!(agents[1].allowed_tools, vec!["Read".to_string()]);
+        assert_eq!(agents[1].system_prompt, "check");
+    }
+
+    #[test]
+    fn canonical_timestamps_are_fixed_width_utc_millis() {
+        let now = now_iso8601_utc();
+        assert_eq!(now.len(), "2025-01-17T20:00:00.000Z".len());
+        assert!(now.ends_with('Z'));
+        assert!(parse_iso8601_utc(&now).is_ok());
+
+        assert_eq!(normalize_iso8601_utc("2025-01-17T20:00:00Z").unwrap(), "2025-01-17T20:00:00.000Z");
+        assert_eq!(normalize_iso8601_utc("2025-01-17T22:00:00.123456+02:00").unwrap(), "2025-01-17T20:00:00.123Z");
+        assert!(normalize_iso8601_utc("2025-01-17 20:00:00").is_err());
+        assert!(normalize_iso8601_utc("yesterday").is_err());
+    }
+
+    #[test]
+    fn far_off_instants_are_clamped_to_four_digit_years() {
+        let far_past = time::PrimitiveDateTime::MIN.assume_utc();
+        let far_future = time::PrimitiveDateTime::MAX.assume_utc();
+        assert_eq!(format_iso8601_utc(far_past), "0000-01-01T00:00:00.000Z");
+        assert_eq!(format_iso8601_utc(far_future), "9999-12-31T23:59:59.999Z");
+        assert_eq!(iso8601_utc_ago(std::time::Duration::MAX), "0000-01-01T00:00:00.000Z");
+        assert_eq!(iso8601_utc_ago(std::time::Duration::from_secs(400_000 * 365 * 86_400)), "0000-01-01T00:00:00.000Z");
+        assert!(format_iso8601_utc(far_past) < now_iso8601_utc());
+    }
+
+    #[test]
+    fn canonical_string_order_matches_instant_order() {
+        // Deterministic pseudo-random instants spread over ~60 years with sub-second parts
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let base = time::OffsetDateTime::UNIX_EPOCH;
+        let instants: Vec<time::OffsetDateTime> = (0..500)
+            .map(|_| {
+                let secs = (next() % 1_900_000_000) as i64;
+                let millis = (next() % 1000) as i64;
+                base + time::Duration::seconds(secs) + time::Duration::milliseconds(millis)
+            })
+            .collect();
+        for a in &instants {
+            for b in instants.iter().take(50) {
+                let (sa, sb) = (format_iso8601_utc(*a), format_iso8601_utc(*b));
+                let (pa, pb) = (parse_iso8601_utc(&sa).unwrap(), parse_iso8601_utc(&sb).unwrap());
+                assert_eq!(sa.cmp(&sb), pa.cmp(&pb), "{} vs {}", sa, sb);
+            }
+        }
+    }
+
+    #[test]
+    fn session_timestamp_writes_reject_malformed_values() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+
+        let p = insert_project(&conn, "demo").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
+        let s = insert_session(&conn, &p.id, &a.id, "gemini", Some("ctx")).unwrap();
+
+        assert!(matches!(update_session(&conn, &s.id, None, Some("2025-13-01"), None), Err(DbError::InvalidInput(_))));
+        assert!(matches!(set_session_expires_at(&conn, &s.id, Some("tomorrow")), Err(DbError::InvalidInput(_))));
+        assert!(delete_expired_sessions(&conn, "not-a-date").is_err());
+
+        set_session_expires_at(&conn, &s.id, Some("2025-01-01T01:00:00+01:00")).unwrap();
+        let found = find_session(&conn, &s.id).unwrap().unwrap();
+        assert_eq!(found.expires_at.as_deref(), Some("2025-01-01T00:00:00.000Z"));
+    }
+
+    #[test]
+    fn migration_v4_normalizes_and_reports_timestamps() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+
+        let p = insert_project(&conn, "demo").unwrap();
+        let a = insert_agent(&conn, &p.id, "backend", "backend", "gemini", "g-1.5", &["Edit".into()], "sp").unwrap();
+        let good = insert_session(&conn, &p.id, &a.id, "gemini", None).unwrap();
+        let bad = insert_session(&conn, &p.id, &a.id, "gemini", None).unwrap();
+
+        // Simulate rows written by older producers, then replay v4
+        conn.execute("UPDATE sessions SET last_activity = ?1 WHERE id = ?2", params!["2025-01-17T20:00:00.123456789+00:00", good.id]).unwrap();
+        conn.execute("UPDATE sessions SET last_activity = ?1 WHERE id = ?2", params!["2025-01-17T20:00:00Z.123", bad.id]).unwrap();
+        conn.execute("DELETE FROM migrations WHERE version = 4", []).unwrap();
+        apply_pending_migrations(&conn).unwrap();
+
+        let good = find_session(&conn, &good.id).unwrap().unwrap();
+        assert_eq!(good.last_activity.as_deref(), Some("2025-01-17T20:00:00.123Z"));
+
+        let malformed = scan_malformed_timestamps(&conn).unwrap();
+        assert_eq!(malformed.len(), 1);
+        assert_eq!(malformed[0].table, "sessions");
+        assert_eq!(malformed[0].column, "last_activity");
+        assert_eq!(malformed[0].value, "2025-01-17T20:00:00Z.123");
+    }
+    #[test]
+    fn broadcast_crud() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+        assert!(migration_applied(&read_tx(&conn), 5).unwrap());
+        let p = insert_project(&conn, "demo").unwrap();
+
+        let first = insert_broadcast(&conn, &p.id, "hello", &["backend".into(), "frontend".into()]).unwrap();
+        let second = insert_broadcast(&conn, &p.id, "again", &["backend".into()]).unwrap();
+        let found = find_broadcast(&conn, &first.id).unwrap().unwrap();
+        assert_eq!(found, first);
+        assert_eq!(found.targets_json, vec!["backend".to_string(), "frontend".to_string()]);
+        assert_eq!(found.status, BroadcastStatus::InProgress);
+        assert!(found.completed_at.is_none());
+        assert!(find_broadcast(&conn, "missing").unwrap().is_none());
+
+        update_broadcast_status(&conn, &first.id, BroadcastStatus::PartialFailure, Some("2025-01-17T20:00:00Z")).unwrap();
+        let found = find_broadcast(&conn, &first.id).unwrap().unwrap();
+        assert_eq!(found.status, BroadcastStatus::PartialFailure);
+        assert_eq!(found.completed_at.as_deref(), Some("2025-01-17T20:00:00.000Z"));
+        let stored: String = conn.query_row("SELECT status FROM broadcasts WHERE id = ?1", params![first.id], |r| r.get(0)).unwrap();
+        assert_eq!(stored, "partial");
+        assert!(matches!(update_broadcast_status(&conn, "missing", BroadcastStatus::Completed, None), Err(DbError::InvalidInput(_))));
+        assert!(matches!(update_broadcast_status(&conn, &first.id, BroadcastStatus::Failed, Some("yesterday")), Err(DbError::InvalidInput(_))));
+
+        let listed = list_broadcasts(&conn, &p.id, None, 10).unwrap();
+        assert_eq!(listed.iter().map(|b| b.id.as_str()).collect::<Vec<_>>(), vec![second.id.as_str(), first.id.as_str()]);
+        assert_eq!(list_broadcasts(&conn, &p.id, None, 1).unwrap().len(), 1);
+        let in_progress = list_broadcasts(&conn, &p.id, Some(BroadcastStatus::InProgress), 10).unwrap();
+        assert_eq!(in_progress.iter().map(|b| b.id.as_str()).collect::<Vec<_>>(), vec![second.id.as_str()]);
+        assert!(list_broadcasts(&conn, &p.id, Some(BroadcastStatus::Failed), 10).unwrap().is_empty());
+
+        // Removed with their project
+        conn.execute("DELETE FROM projects WHERE id = ?1", params![p.id]).unwrap();
+        assert!(list_broadcasts(&conn, &p.id, None, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn broadcast_status_round_trips_through_text_and_serde() {
+        for status in [BroadcastStatus::InProgress, BroadcastStatus::Completed, BroadcastStatus::PartialFailure, BroadcastStatus::Failed] {
+            assert_eq!(status.to_string().parse::<BroadcastStatus>().unwrap(), status);
+            let json = serde_json::to_string(&status).unwrap();
+            assert_eq!(json, format!("\"{}\"", status));
+            assert_eq!(serde_json::from_str::<BroadcastStatus>(&json).unwrap(), status);
+        }
+        assert!(matches!("partial_failure".parse::<BroadcastStatus>(), Err(DbError::InvalidInput(_))));
+
+        let b = Broadcast {
+            id: "b1".into(),
+            project_id: "p1".into(),
+            message: "hi".into(),
+            targets_json: vec!["backend".into()],
+            status: BroadcastStatus::Completed,
+            created_at: "2025-01-17T20:00:00.000Z".into(),
+            completed_at: None,
+        };
+        let json = serde_json::to_value(&b).unwrap();
+        assert_eq!(json["status"], "completed");
+        assert!(json.get("completed_at").is_none());
+        assert_eq!(serde_json::from_value::<Broadcast>(json).unwrap(), b);
+    }
+    fn many_agents_project(name: &str, count: usize) -> ProjectConfig {
+        ProjectConfig {
+            schema_version: 1,
+            project: name.into(),
+            agents: (0..count).map(|i| config_model::AgentConfig {
+                name: format!("agent-{i:03}"),
+                role: "backend".into(),
+                provider: "claude".into(),
+                model: "m".into(),
+                allowed_tools: vec!["Edit".into()],
+                system_prompt: "sp".into(),
+            }).collect(),
+            groups: vec![],
+            defaults: Default::default(),
+        }
+    }
+
+    #[test]
+    fn sync_project_is_one_transaction() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+        let cfg = many_agents_project("big", 200);
+
+        // A failure on agent 150 rolls back the project and the 149 agents before it
+        conn.execute_batch(
+            "CREATE TEMP TRIGGER fail_agent_150 BEFORE INSERT ON agents WHEN NEW.name = 'agent-150'
+             BEGIN SELECT RAISE(ABORT, 'injected failure'); END;",
+        ).unwrap();
+        assert!(sync_project_from_config(&conn, &cfg).is_err());
+        assert!(find_project_id(&conn, IdOrName::Name("big")).unwrap().is_none());
+        let agents: i64 = conn.query_row("SELECT COUNT(*) FROM agents", [], |r| r.get(0)).unwrap();
+        assert_eq!(agents, 0);
+        assert!(conn.is_autocommit(), "transaction must not be left open");
+        conn.execute_batch("DROP TRIGGER fail_agent_150").unwrap();
+
+        let report = sync_project_from_config(&conn, &cfg).unwrap();
+        assert!(report.project_created);
+        assert_eq!(report.agents_created.len(), 200);
+        assert!(report.agents_existing.is_empty());
+        assert_eq!(list_agent_names(&conn, &report.project_id).unwrap().len(), 200);
+
+        // Idempotent
+        let again = sync_project_from_config(&conn, &cfg).unwrap();
+        assert_eq!(again.project_id, report.project_id);
+        assert!(!again.project_created && again.agents_created.is_empty());
+        assert_eq!(again.agents_existing.len(), 200);
+    }
+
+    #[test]
+    fn sync_project_only_inserts_missing_agents_and_stays_idempotent() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+        // Same agent names in another project must not count as existing
+        sync_project_from_config(&conn, &many_agents_project("other", 3)).unwrap();
+        let first = sync_project_from_config(&conn, &many_agents_project("grow", 2)).unwrap();
+        assert_eq!(first.agents_created, ["agent-000", "agent-001"]);
+
+        // Two new agents, one of them listed twice; existing rows are left as they are
+        let mut cfg = many_agents_project("grow", 4);
+        cfg.agents[0].system_prompt = "changed".into();
+        cfg.agents.push(cfg.agents[3].clone());
+        let report = sync_project_from_config(&conn, &cfg).unwrap();
+        assert_eq!(report.project_id, first.project_id);
+        assert_eq!(report.agents_created, ["agent-002", "agent-003"]);
+        assert_eq!(report.agents_existing, ["agent-000", "agent-001", "agent-003"]);
+        let agent = find_agent_by_name(&conn, &first.project_id, "agent-000").unwrap().unwrap();
+        assert_eq!(agent.system_prompt, "sp");
+
+        let again = sync_project_from_config(&conn, &cfg).unwrap();
+        assert!(again.agents_created.is_empty());
+        assert_eq!(again.agents_existing.len(), 5);
+        assert_eq!(list_agent_names(&conn, &first.project_id).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn sync_project_with_options_updates_prunes_and_dry_runs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("multi-agents.sqlite3");
+        let conn = open_or_create_db(db_path.to_string_lossy().as_ref()).unwrap();
+        let first = sync_project_from_config(&conn, &many_agents_project("opts", 3)).unwrap();
+        let doomed = find_agent_by_name(&conn, &first.project_id, "agent-002").unwrap().unwrap();
+        conn.execute(
+            "INSERT INTO tasks(id, project_id, title, status, assignee_agent_id, created_at) VALUES ('t1', ?1, 'task', 'todo', ?2, ?3)",
+            params![first.project_id, doomed.id, now_iso8601_utc()],
+        ).unwrap();
+
+        // agent-000 changes, agent-002 leaves the config, agent-003 is new
+        let mut cfg = many_agents_project("opts", 4);
+        cfg.agents[0].model = "other-model".into();
+        cfg.agents.remove(2);
+        let options = SyncOptions { update: true, prune: true, dry_run: true };
+        let planned = sync_project_with_options(&conn, &cfg, options).unwrap();
+        assert_eq!(planned.agents_created, ["agent-003"]);
+        assert_eq!(planned.agents_updated, ["agent-000"]);
+        assert_eq!(planned.agents_existing, ["agent-001"]);
+        assert_eq!(planned.agents_pruned, ["agent-002"]);
+        assert_eq!(list_agent_names(&conn, &first.project_id).unwrap(), ["agent-000", "agent-001", "agent-002"]);
+        assert!(conn.is_autocommit(), "dry run must not leave the transaction open");
+
+        let done = sync_project_with_options(&conn, &cfg, SyncOptions { dry_run: false, ..options }).unwrap();
+        assert_eq!(done, planned);
+        assert_eq!(list_agent_names(&conn, &first.project_id).unwrap(), ["agent-000", "agent-001", "agent-003"]);
+        assert_eq!(find_agent_by_name(&conn, &first.project_id, "agent-000").unwrap().unwrap().model, "other-model");
+        let assignee: Option<String> = conn.query_row("SELECT assignee_agent_id FROM tasks WHERE id = 't1'", [], |r| r.get(0)).unwrap();
+        assert_eq!(assignee, None);
+
+        // Without `update`, a changed agent is left as it is
+        cfg.agents[0].model = "third-model".into();
+        let plain = sync_project_from_config(&conn, &cfg).unwrap();
+        assert!(plain.agents_updated.is_empty() && plain.agents_pruned.is_empty());
+        assert_eq!(find_agent_by_name(&conn, &first.project_id, "agent-000").unwrap().unwrap().model, "other-model");
+    }
 }
 
 // ---------- Project Synchronization ----------
 
+/// Outcome of `sync_project_from_config`
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct SyncReport {
+    pub project_id: String,
+    /// Whether the project row was created by this sync
+    pub project_created: bool,
+    /// Agents inserted by this sync, in config order
+    pub agents_created: Vec<String>,
+    /// Agents that were already in the database and left as they are, in config order
+    pub agents_existing: Vec<String>,
+    /// Agents whose role, provider, model, tools or system prompt were rewritten from the config
+    /// (`SyncOptions::update`), in config order
+    pub agents_updated: Vec<String>,
+    /// Agents deleted because the config no longer lists them (`SyncOptions::prune`), sorted
+    pub agents_pruned: Vec<String>,
+}
+
+/// What `sync_project_with_options` may change besides creating what is missing
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SyncOptions {
+    /// Rewrite agents whose definition differs from the config
+    pub update: bool,
+    /// Delete agents of the project that the config does not list, with their sessions and messages
+    pub prune: bool,
+    /// Write nothing, so a read-only connection will do; the report says what a real run would do
+    pub dry_run: bool,
+}
+
 /// Synchronize a project and its agents from YAML configuration to database
-/// This function is idempotent: if project/agents already exist, they are not modified
-pub fn sync_project_from_config(conn: &Connection, project_config: &ProjectConfig) -> Result<(), DbError> {
+/// This function is idempotent: if project/agents already exist, they are not modified.
+/// Runs in one transaction, so a failure part-way leaves the database untouched. Prints nothing;
+/// callers report the returned `SyncReport` their own way.
+pub fn sync_project_from_config(conn: &Connection, project_config: &ProjectConfig) -> Result<SyncReport, DbError> {
+    sync_project_with_options(conn, project_config, SyncOptions::default())
+}
+
+/// Whether the stored agent differs from its config entry in any synced field
+fn agent_differs(agent: &Agent, config: &AgentConfig) -> bool {
+    agent.role != config.role
+        || agent.provider != config.provider
+        || agent.model != config.model
+        || agent.allowed_tools != config.allowed_tools
+        || agent.system_prompt != config.system_prompt
+}
+
+/// Quiet sync that can also update changed agents and prune removed ones. Everything runs in one
+/// transaction; with `dry_run` only the reads run, so the report matches what a real run would do.
+pub fn sync_project_with_options(conn: &Connection, project_config: &ProjectConfig, options: SyncOptions) -> Result<SyncReport, DbError> {
+    let tx = conn.unchecked_transaction()?;
+    let mut report = SyncReport::default();
+
     // 1. Ensure project exists
-    let project_id = match find_project_id(conn, IdOrName::Name(&project_config.project))? {
-        Some(id) => {
-            println!("Project '{}' already exists in database", project_config.project);
-            id
-        }
+    report.project_id = match find_project_id(&tx, IdOrName::Name(&project_config.project))? {
+        Some(id) => id,
         None => {
-            println!("Creating project '{}' in database", project_config.project);
-            let project = insert_project(conn, &project_config.project)?;
-            project.id
+            report.project_created = true;
+            // A dry run has no id to report for a project it would create
+            if options.dry_run { String::new() } else { insert_project(&tx, &project_config.project)?.id }
         }
     };
 
-    // 2. Ensure all agents exist
-    for agent_config in &project_config.agents {
-        let agent_exists = conn.query_row(
-            "SELECT COUNT(*) FROM agents WHERE project_id = ?1 AND name = ?2",
-            params![&project_id, &agent_config.name],
-            |row| Ok(row.get::<_, i64>(0)?)
-        )? > 0;
+    // 2. Ensure all agents exist: one query for the agents already there, then inserts for the rest
+    let mut existing: std::collections::HashMap<String, Agent> = list_agents(&tx, &report.project_id)?
+        .into_iter()
+        .map(|a| (a.name.clone(), a))
+        .collect();
+    let mut seen = std::collections::HashSet::new();
+    {
+        let mut insert = tx.prepare_cached(INSERT_AGENT_SQL)?;
+        for agent_config in &project_config.agents {
+            // A name repeated in the config is only synced once
+            if !seen.insert(agent_config.name.as_str()) {
+                report.agents_existing.push(agent_config.name.clone());
+                continue;
+            }
+            if let Some(agent) = existing.get(&agent_config.name) {
+                if options.update && agent_differs(agent, agent_config) {
+                    check_agent_fields(&agent_config.name, &agent_config.role)?;
+                    if !options.dry_run {
+                        tx.execute(
+                            "UPDATE agents SET role = ?2, provider = ?3, model = ?4, allowed_tools_json = ?5, system_prompt = ?6 WHERE id = ?1",
+                            params![
+                                &agent.id,
+                                &agent_config.role,
+                                &agent_config.provider,
+                                &agent_config.model,
+                                to_json_text(&agent_config.allowed_tools),
+                                &agent_config.system_prompt,
+                            ],
+                        )?;
+                    }
+                    report.agents_updated.push(agent_config.name.clone());
+                } else {
+                    report.agents_existing.push(agent_config.name.clone());
+                }
+                continue;
+            }
+            check_agent_fields(&agent_config.name, &agent_config.role)?;
+            if !options.dry_run {
+                insert.execute(params![
+                    uuid(),
+                    &report.project_id,
+                    &agent_config.name,
+                    &agent_config.role,
+                    &agent_config.provider,
+                    &agent_config.model,
+                    to_json_text(&agent_config.allowed_tools),
+                    &agent_config.system_prompt,
+                    now_iso8601_utc(),
+                ])?;
+            }
+            report.agents_created.push(agent_config.name.clone());
+        }
+    }
 
-        if agent_exists {
-            println!("Agent '{}' already exists in database", agent_config.name);
-        } else {
-            println!("Creating agent '{}' in database", agent_config.name);
-            let _agent = insert_agent(
-                conn,
-                &project_id,
-                &agent_config.name,
-                &agent_config.role,
-                &agent_config.provider,
-                &agent_config.model,
-                &agent_config.allowed_tools,
-                &agent_config.system_prompt,
-            )?;
+    // 3. Drop agents the config no longer lists
+    if options.prune {
+        existing.retain(|name, _| !seen.contains(name.as_str()));
+        let mut pruned: Vec<Agent> = existing.into_values().collect();
+        pruned.sort_by(|a, b| a.name.cmp(&b.name));
+        for agent in pruned {
+            if !options.dry_run { delete_agent_rows(&tx, &agent.id)?; }
+            report.agents_pruned.push(agent.name);
         }
     }
 
-    println!("Project '{}' synchronized successfully", project_config.project);
-    Ok(())
+    if !options.dry_run {
+        tx.commit()?;
+    }
+    Ok(report)
 }